@@ -0,0 +1,20 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Deserialize, PartialEq, Serialize, Debug, Default)]
+pub struct AccessAuditEntry {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    #[serde(default)]
+    pub config_id: Option<i64>,
+    pub context: String,
+    pub namespace: String,
+    pub target: String,
+    pub reason: String,
+    #[serde(default)]
+    pub user: Option<String>,
+    pub started_at: i64,
+}
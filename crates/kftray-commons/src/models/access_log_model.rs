@@ -0,0 +1,23 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::models::protocol_model::StreamProtocol;
+
+#[derive(Clone, Deserialize, PartialEq, Serialize, Debug)]
+pub struct AccessLogEntry {
+    pub config_id: i64,
+    pub timestamp_ms: u64,
+    pub client_addr: String,
+    pub local_port: u16,
+    pub duration_ms: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<StreamProtocol>,
+}
@@ -0,0 +1,31 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value;
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+#[serde(default)]
+pub struct ConfigFilter {
+    pub ids: Option<Vec<i64>>,
+    pub context: Option<String>,
+    pub kubeconfig: Option<String>,
+    pub namespace: Option<String>,
+    pub profile: Option<String>,
+    pub tag: Option<String>,
+    pub query: Option<String>,
+}
+
+#[derive(Clone, Deserialize, PartialEq, Serialize, Debug)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+#[derive(Clone, Deserialize, PartialEq, Serialize, Debug)]
+pub struct ConfigChange {
+    pub config_id: i64,
+    pub alias: Option<String>,
+    pub changes: Vec<FieldChange>,
+}
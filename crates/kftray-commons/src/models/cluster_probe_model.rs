@@ -0,0 +1,18 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+pub struct ContextProbe {
+    pub context: String,
+    pub reachable: bool,
+    pub authenticated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_version: Option<String>,
+    pub checked_at_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
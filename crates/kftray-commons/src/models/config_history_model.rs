@@ -0,0 +1,17 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::models::bulk_update_model::FieldChange;
+
+#[derive(Clone, Deserialize, PartialEq, Serialize, Debug)]
+pub struct ConfigHistoryEntry {
+    pub id: i64,
+    pub config_id: i64,
+    pub action: String,
+    #[serde(default)]
+    pub user: Option<String>,
+    pub changed_at: i64,
+    pub changes: Vec<FieldChange>,
+}
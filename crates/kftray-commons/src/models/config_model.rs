@@ -3,6 +3,8 @@ use serde::{
     Serialize,
 };
 
+use crate::models::mock_rule_model::MockRule;
+
 #[derive(Clone, Deserialize, PartialEq, Serialize, Debug)]
 pub struct Config {
     #[serde(default)]
@@ -36,6 +38,157 @@ pub struct Config {
     pub kubeconfig: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub target: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_start: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_stop: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_failure: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hook_timeout_secs: Option<u64>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub readiness_check: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub readiness_path: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub readiness_timeout_secs: Option<u64>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_log: Option<bool>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_store: Option<bool>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_log: Option<bool>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_log_redact: Option<bool>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_protocol: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fan_out: Option<bool>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fan_out_base_port: Option<u16>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grpc_health_services: Option<Vec<String>>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rbac_preflight: Option<bool>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mdns_advertise: Option<bool>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kube_request_timeout_secs: Option<u64>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kube_qps: Option<u32>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transport: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssm_instance_id: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssm_profile: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssm_region: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<u32>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection_overflow: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_file: Option<String>,
+    // configs that have to be up before this one is auto-started
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<Vec<i64>>,
+    // presentation only, shown by the tray, kftui and the web ui
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    // a color name like "red" or a "#rrggbb" value
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    // mirrors the local port onto a remote machine with `ssh -R`, the user
+    // and key fall back to the ssh config of the host
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh_mirror_host: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh_mirror_user: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh_mirror_key: Option<String>,
+    // the port on the remote machine, the local port when unset
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh_mirror_port: Option<u16>,
+    // a service without ready endpoints is started once they appear, instead
+    // of failing the start
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wait_for_endpoints: Option<bool>,
+    // a target that doesn't exist yet queues the start until it shows up
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wait_for_target: Option<bool>,
+    // a second service, or label selector for pod configs, that gets
+    // split_percent of the new connections, e.g. a canary next to stable
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub split_target: Option<String>,
+    // the remote port of the split target, the remote port when unset
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub split_remote_port: Option<u16>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub split_percent: Option<u8>,
+    // evaluated on each HTTP/1 request going through the forward
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mock_rules: Option<Vec<MockRule>>,
+    // path to a Rhai script with `on_request` and/or `on_response` functions
+    // that see and can change the HTTP/1 traffic of the forward
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub traffic_script: Option<String>,
+    // file or URL of an OpenAPI spec the HTTP log checks the traffic against
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub openapi_spec: Option<String>,
 }
 
 impl Default for Config {
@@ -55,6 +208,51 @@ impl Default for Config {
             alias: Some("default-alias".to_string()),
             kubeconfig: Some("default".to_string()),
             target: Some("default-target".to_string()),
+            profile: None,
+            tags: None,
+            on_start: None,
+            on_stop: None,
+            on_failure: None,
+            hook_timeout_secs: None,
+            readiness_check: None,
+            readiness_path: None,
+            readiness_timeout_secs: None,
+            access_log: None,
+            http_store: None,
+            query_log: None,
+            query_log_redact: None,
+            app_protocol: None,
+            fan_out: None,
+            fan_out_base_port: None,
+            grpc_health_services: None,
+            rbac_preflight: None,
+            mdns_advertise: None,
+            kube_request_timeout_secs: None,
+            kube_qps: None,
+            transport: None,
+            ssm_instance_id: None,
+            ssm_profile: None,
+            ssm_region: None,
+            max_connections: None,
+            connection_overflow: None,
+            log_level: None,
+            log_file: None,
+            depends_on: None,
+            display_name: None,
+            icon: None,
+            color: None,
+            ssh_mirror_host: None,
+            ssh_mirror_user: None,
+            ssh_mirror_key: None,
+            ssh_mirror_port: None,
+            wait_for_endpoints: None,
+            wait_for_target: None,
+            split_target: None,
+            split_remote_port: None,
+            split_percent: None,
+            mock_rules: None,
+            traffic_script: None,
+            openapi_spec: None,
         }
     }
 }
@@ -0,0 +1,12 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Deserialize, PartialEq, Serialize, Debug, Default)]
+pub struct ConfigSnapshot {
+    pub id: i64,
+    pub reason: String,
+    pub config_count: i64,
+    pub created_at: i64,
+}
@@ -8,4 +8,17 @@ pub struct ConfigState {
     pub id: Option<i64>,
     pub config_id: i64,
     pub is_running: bool,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_port: Option<u16>,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Eq, Serialize, Debug)]
+pub enum ForwardPhase {
+    Starting,
+    Running,
+    Stopping,
+    #[default]
+    Stopped,
+    Failed,
 }
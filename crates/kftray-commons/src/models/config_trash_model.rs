@@ -0,0 +1,12 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::models::config_model::Config;
+
+#[derive(Clone, Deserialize, PartialEq, Serialize, Debug)]
+pub struct TrashedConfig {
+    pub config: Config,
+    pub deleted_at: i64,
+}
@@ -0,0 +1,12 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Deserialize, PartialEq, Serialize, Debug, Default)]
+pub struct ConfigUsage {
+    pub config_id: i64,
+    pub last_started_at: Option<i64>,
+    pub start_count: i64,
+    pub pinned: bool,
+}
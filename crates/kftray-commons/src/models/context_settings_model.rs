@@ -0,0 +1,26 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+pub struct ContextSettings {
+    pub context: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub default_namespace: Option<String>,
+    #[serde(default)]
+    pub local_address_range: Option<String>,
+    #[serde(default)]
+    pub default_protocol: Option<String>,
+    // forwards into production contexts need a reason, kept in the access audit
+    #[serde(default)]
+    pub production: bool,
+    // read-only contexts only take plain port forwards, no proxy pods are
+    // ever deployed there
+    #[serde(default)]
+    pub read_only: bool,
+}
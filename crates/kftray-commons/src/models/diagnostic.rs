@@ -0,0 +1,49 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Serialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+#[derive(Clone, Deserialize, PartialEq, Eq, Serialize, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub field: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(field: &str, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(field: &str, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn info(field: &str, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Info,
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
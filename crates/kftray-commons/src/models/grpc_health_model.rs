@@ -0,0 +1,35 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Eq, Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ServingStatus {
+    #[default]
+    Unknown,
+    Serving,
+    NotServing,
+    ServiceUnknown,
+}
+
+impl ServingStatus {
+    pub fn from_code(code: u64) -> Self {
+        match code {
+            1 => ServingStatus::Serving,
+            2 => ServingStatus::NotServing,
+            3 => ServingStatus::ServiceUnknown,
+            _ => ServingStatus::Unknown,
+        }
+    }
+}
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+pub struct GrpcHealthStatus {
+    pub config_id: i64,
+    pub service: String,
+    pub status: ServingStatus,
+    pub checked_at_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
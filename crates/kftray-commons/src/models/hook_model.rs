@@ -0,0 +1,34 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    OnStart,
+    OnStop,
+    OnFailure,
+}
+
+impl HookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::OnStart => "on_start",
+            HookEvent::OnStop => "on_stop",
+            HookEvent::OnFailure => "on_failure",
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, PartialEq, Serialize, Debug)]
+pub struct HookResult {
+    pub config_id: i64,
+    pub event: HookEvent,
+    pub command: String,
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+}
@@ -0,0 +1,36 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Serialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpLogKind {
+    Request,
+    Response,
+}
+
+#[derive(Clone, Deserialize, PartialEq, Serialize, Debug)]
+pub struct HttpLogRecord {
+    pub config_id: i64,
+    pub trace_id: String,
+    pub kind: HttpLogKind,
+    pub timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    pub headers: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_size: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub took_ms: Option<i64>,
+    // where the message doesn't match the OpenAPI spec of the config
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub spec_violations: Vec<String>,
+}
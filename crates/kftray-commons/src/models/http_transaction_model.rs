@@ -0,0 +1,40 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Deserialize, PartialEq, Serialize, Debug)]
+pub struct HttpTransaction {
+    pub id: i64,
+    pub config_id: i64,
+    pub trace_id: String,
+    pub requested_at: i64,
+    pub method: Option<String>,
+    pub path: Option<String>,
+    pub status: Option<u16>,
+    pub took_ms: Option<i64>,
+    pub request_headers: Vec<(String, String)>,
+    pub request_body: Option<String>,
+    pub response_headers: Vec<(String, String)>,
+    pub response_body: Option<String>,
+}
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+pub struct HttpTransactionQuery {
+    #[serde(default)]
+    pub config_id: Option<i64>,
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub path_prefix: Option<String>,
+    #[serde(default)]
+    pub status_min: Option<u16>,
+    #[serde(default)]
+    pub status_max: Option<u16>,
+    #[serde(default)]
+    pub since_ms: Option<i64>,
+    #[serde(default)]
+    pub until_ms: Option<i64>,
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
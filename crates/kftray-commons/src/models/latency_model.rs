@@ -0,0 +1,21 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+pub struct Percentiles {
+    pub p50: i64,
+    pub p90: i64,
+    pub p95: i64,
+    pub p99: i64,
+    pub max: i64,
+}
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+pub struct LatencyStats {
+    pub config_id: i64,
+    pub count: usize,
+    pub ttfb_ms: Percentiles,
+    pub total_ms: Percentiles,
+}
@@ -0,0 +1,31 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceMesh {
+    Istio,
+    Linkerd,
+}
+
+impl ServiceMesh {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ServiceMesh::Istio => "Istio",
+            ServiceMesh::Linkerd => "Linkerd",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MeshDiagnosis {
+    pub mesh: ServiceMesh,
+    // the namespace only takes mutual tls, plain connections get reset
+    pub strict: bool,
+    // the target pod runs the mesh proxy next to the app
+    pub sidecar: bool,
+    pub explanation: String,
+    pub remediation: Vec<String>,
+}
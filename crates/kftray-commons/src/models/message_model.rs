@@ -0,0 +1,41 @@
+use std::collections::BTreeMap;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+// what happened, for a ui to render in its own words. the english text lives
+// in utils::messages
+#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageCode {
+    ForwardAlreadyRunning,
+    ForwardStarted,
+    FanOutForwardStarted,
+    ForwardFailed,
+    ForwardStopped,
+    InvalidProcessKey,
+    HostsFileFailed,
+}
+
+#[derive(Clone, Deserialize, PartialEq, Eq, Serialize, Debug)]
+pub struct UserMessage {
+    pub code: MessageCode,
+    #[serde(default)]
+    pub params: BTreeMap<String, String>,
+}
+
+impl UserMessage {
+    pub fn new(code: MessageCode) -> Self {
+        UserMessage {
+            code,
+            params: BTreeMap::new(),
+        }
+    }
+
+    pub fn with(mut self, name: &str, value: impl ToString) -> Self {
+        self.params.insert(name.to_string(), value.to_string());
+        self
+    }
+}
@@ -0,0 +1,49 @@
+use std::collections::BTreeMap;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MockAction {
+    // answers the request without sending it to the target
+    Respond {
+        status: u16,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+        #[serde(default)]
+        body: String,
+    },
+    // sends the request on with its path or headers changed
+    Modify {
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+        #[serde(default)]
+        set_headers: BTreeMap<String, String>,
+        #[serde(default)]
+        remove_headers: Vec<String>,
+    },
+    // sends the request on untouched, e.g. to exempt a path from a later rule
+    Pass,
+}
+
+// a rule matches when every condition that is set matches, the first
+// matching rule of a config wins
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MockRule {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    // the exact path, or a prefix ending in `*`. the query string is ignored
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    // `name` to require the header, `name: value` to require its value
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+    pub action: MockAction,
+}
@@ -1,4 +1,33 @@
+pub mod access_audit_model;
+pub mod access_log_model;
+pub mod bulk_update_model;
+pub mod cluster_probe_model;
+pub mod config_history_model;
 pub mod config_model;
+pub mod config_snapshot_model;
 pub mod config_state_model;
+pub mod config_trash_model;
+pub mod config_usage_model;
+pub mod context_settings_model;
+pub mod diagnostic;
+pub mod grpc_health_model;
+pub mod hook_model;
+pub mod http_log_model;
+pub mod http_transaction_model;
+pub mod latency_model;
+pub mod mesh_model;
+pub mod message_model;
+pub mod mock_rule_model;
+pub mod pod_status_model;
+pub mod profile_model;
+pub mod protocol_model;
+pub mod proxy_diagnosis_model;
+pub mod query_log_model;
+pub mod reconcile_model;
+pub mod reload_model;
 pub mod response;
+pub mod session_model;
+pub mod settings_model;
+pub mod target_description_model;
+pub mod tool_import_model;
 pub mod window;
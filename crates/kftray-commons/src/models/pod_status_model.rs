@@ -0,0 +1,35 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+pub struct PodEvent {
+    pub kind: String,
+    pub reason: String,
+    pub message: String,
+    pub count: i32,
+    pub last_seen_ms: i64,
+}
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+pub struct TargetPodStatus {
+    pub config_id: i64,
+    pub pod_name: String,
+    pub phase: String,
+    pub ready: bool,
+    pub restart_count: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub waiting_reason: Option<String>,
+    pub events: Vec<PodEvent>,
+    pub updated_at_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+pub struct PodLogLine {
+    pub config_id: i64,
+    pub pod_name: String,
+    pub line: String,
+}
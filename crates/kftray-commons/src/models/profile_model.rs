@@ -0,0 +1,10 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Deserialize, PartialEq, Serialize, Debug)]
+pub struct Profile {
+    pub name: String,
+    pub is_active: bool,
+}
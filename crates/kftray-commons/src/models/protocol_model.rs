@@ -0,0 +1,58 @@
+use std::collections::BTreeMap;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Copy, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamProtocol {
+    Http1,
+    Http2,
+    Grpc,
+    Tls,
+    Postgres,
+    Mysql,
+    Redis,
+    Kafka,
+    Unknown,
+}
+
+impl StreamProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StreamProtocol::Http1 => "http1",
+            StreamProtocol::Http2 => "http2",
+            StreamProtocol::Grpc => "grpc",
+            StreamProtocol::Tls => "tls",
+            StreamProtocol::Postgres => "postgres",
+            StreamProtocol::Mysql => "mysql",
+            StreamProtocol::Redis => "redis",
+            StreamProtocol::Kafka => "kafka",
+            StreamProtocol::Unknown => "unknown",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "http1" => Some(StreamProtocol::Http1),
+            "http2" => Some(StreamProtocol::Http2),
+            "grpc" => Some(StreamProtocol::Grpc),
+            "tls" => Some(StreamProtocol::Tls),
+            "postgres" => Some(StreamProtocol::Postgres),
+            "mysql" => Some(StreamProtocol::Mysql),
+            "redis" => Some(StreamProtocol::Redis),
+            "kafka" => Some(StreamProtocol::Kafka),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+pub struct ProtocolStats {
+    pub config_id: i64,
+    pub connections: BTreeMap<StreamProtocol, u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_detected: Option<StreamProtocol>,
+}
@@ -0,0 +1,45 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+    // the check doesn't apply to the target, or couldn't run
+    Skipped,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DiagnosticCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyFailureCause {
+    DnsResolution,
+    MissingService,
+    NoEndpoints,
+    NetworkPolicy,
+    ConnectionFailed,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProxyDiagnosis {
+    pub config_id: i64,
+    pub pod_name: String,
+    pub remote_address: String,
+    pub remote_port: u16,
+    pub checks: Vec<DiagnosticCheck>,
+    // none when every check passed, the failure is then past the connection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cause: Option<ProxyFailureCause>,
+    // `namespace/name` of the policies that drop the traffic
+    pub blocking_policies: Vec<String>,
+    pub summary: String,
+}
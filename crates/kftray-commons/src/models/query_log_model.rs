@@ -0,0 +1,24 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::models::protocol_model::StreamProtocol;
+
+#[derive(Clone, Deserialize, PartialEq, Serialize, Debug)]
+pub struct QueryLogEntry {
+    pub config_id: i64,
+    pub timestamp_ms: u64,
+    pub protocol: StreamProtocol,
+    pub statement: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<String>,
+    pub duration_ms: u64,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows: Option<u64>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
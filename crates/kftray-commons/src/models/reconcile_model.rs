@@ -0,0 +1,21 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+pub struct ReconcileReport {
+    pub stopped_configs: Vec<i64>,
+    pub cleaned_hosts: Vec<i64>,
+    pub deleted_proxy_pods: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl ReconcileReport {
+    pub fn is_clean(&self) -> bool {
+        self.stopped_configs.is_empty()
+            && self.cleaned_hosts.is_empty()
+            && self.deleted_proxy_pods.is_empty()
+            && self.errors.is_empty()
+    }
+}
@@ -0,0 +1,10 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+pub struct PendingReload {
+    pub config_id: i64,
+    pub changed_fields: Vec<String>,
+}
@@ -1,3 +1,5 @@
+use crate::models::message_model::UserMessage;
+
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 
 pub struct CustomResponse {
@@ -11,4 +13,101 @@ pub struct CustomResponse {
     pub stderr: String,
     pub status: i32,
     pub protocol: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub firewall_warning: Option<FirewallWarning>,
+    // stdout and stderr are these messages in english, for the uis that still
+    // print them as they are
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<UserMessage>,
+}
+
+// a listener reachable from the lan that the os firewall blocks or will ask
+// about, with what the user can do about it
+#[derive(Clone, serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct FirewallWarning {
+    pub firewall: String,
+    pub address: String,
+    pub port: u16,
+    pub blocked: bool,
+    pub message: String,
+    pub action: String,
+}
+
+// what a start or stop did, for the uis to match on instead of reading
+// stdout, stderr and status
+#[derive(Clone, serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum ForwardOutcome {
+    Started {
+        config_id: Option<i64>,
+        service: String,
+        namespace: String,
+        context: String,
+        protocol: String,
+        actual_port: u16,
+        remote_port: u16,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        firewall_warning: Option<FirewallWarning>,
+    },
+    Stopped {
+        config_id: Option<i64>,
+        service: String,
+    },
+    Failed {
+        config_id: Option<i64>,
+        error: String,
+    },
+    // a batch where some configs came up and others didn't
+    PartialFailure {
+        succeeded: Vec<ForwardOutcome>,
+        failed: Vec<ForwardOutcome>,
+    },
+}
+
+impl ForwardOutcome {
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            ForwardOutcome::Failed { .. } | ForwardOutcome::PartialFailure { .. }
+        )
+    }
+}
+
+// existing responses carry no outcome of their own. a start is the only one
+// that reports the local port it bound
+impl From<&CustomResponse> for ForwardOutcome {
+    fn from(response: &CustomResponse) -> Self {
+        if response.status != 0 {
+            let error = if response.stderr.is_empty() {
+                response.stdout.clone()
+            } else {
+                response.stderr.clone()
+            };
+            return ForwardOutcome::Failed {
+                config_id: response.id,
+                error,
+            };
+        }
+
+        if response.local_port == 0 {
+            return ForwardOutcome::Stopped {
+                config_id: response.id,
+                service: response.service.clone(),
+            };
+        }
+
+        ForwardOutcome::Started {
+            config_id: response.id,
+            service: response.service.clone(),
+            namespace: response.namespace.clone(),
+            context: response.context.clone(),
+            protocol: response.protocol.clone(),
+            actual_port: response.local_port,
+            remote_port: response.remote_port,
+            firewall_warning: response.firewall_warning.clone(),
+        }
+    }
 }
@@ -0,0 +1,11 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Deserialize, PartialEq, Eq, Serialize, Debug)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub config_ids: Vec<i64>,
+    pub is_current: bool,
+}
@@ -0,0 +1,102 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Eq, Serialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum HostsBackend {
+    #[default]
+    File,
+    Disabled,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Eq, Serialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum HotkeyAction {
+    // stops the target when any of it is running, starts it otherwise
+    #[default]
+    Toggle,
+    Start,
+    Stop,
+}
+
+#[derive(Clone, Deserialize, PartialEq, Eq, Serialize, Debug)]
+#[serde(rename_all = "lowercase", tag = "kind", content = "value")]
+pub enum HotkeyTarget {
+    Config(i64),
+    Tag(String),
+    Context(String),
+}
+
+#[derive(Clone, Deserialize, PartialEq, Eq, Serialize, Debug)]
+pub struct HotkeyBinding {
+    // a tauri accelerator, like "CmdOrCtrl+Alt+1"
+    pub accelerator: String,
+    pub target: HotkeyTarget,
+    #[serde(default)]
+    pub action: HotkeyAction,
+}
+
+#[derive(Clone, Deserialize, PartialEq, Serialize, Debug)]
+#[serde(default)]
+pub struct Settings {
+    pub default_local_address: String,
+    pub default_protocol: String,
+    pub log_level: String,
+    pub start_retry_attempts: u32,
+    pub start_retry_delay_ms: u64,
+    pub idle_timeout_secs: u64,
+    pub udp_idle_timeout_secs: u64,
+    pub circuit_breaker_threshold: u32,
+    pub circuit_breaker_cooldown_secs: u64,
+    pub readiness_timeout_secs: u64,
+    pub hosts_backend: HostsBackend,
+    pub auto_reload_configs: bool,
+    pub kube_connect_timeout_secs: u64,
+    pub kube_request_timeout_secs: u64,
+    pub kube_qps: Option<u32>,
+    // pause between the first connections to different clusters on launch, so
+    // auth plugins aren't all run at once
+    pub startup_stagger_ms: u64,
+    pub startup_retry_attempts: u32,
+    pub startup_retry_delay_secs: u64,
+    // serves a pac file and proxies alias domains to their forwards, 0 picks a
+    // free port
+    pub pac_proxy_port: Option<u16>,
+    // deleted configs can be restored for this long
+    pub trash_retention_days: u64,
+    pub hotkeys: Vec<HotkeyBinding>,
+    // how long a warm relay pod is kept running for the next proxy start in
+    // its context and namespace, 0 turns the pool off
+    pub proxy_warm_pool_ttl_secs: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            default_local_address: "127.0.0.1".to_string(),
+            default_protocol: "tcp".to_string(),
+            log_level: "off".to_string(),
+            start_retry_attempts: 0,
+            start_retry_delay_ms: 1000,
+            idle_timeout_secs: 600,
+            udp_idle_timeout_secs: 120,
+            circuit_breaker_threshold: 5,
+            circuit_breaker_cooldown_secs: 30,
+            readiness_timeout_secs: 10,
+            hosts_backend: HostsBackend::File,
+            auto_reload_configs: false,
+            kube_connect_timeout_secs: 10,
+            kube_request_timeout_secs: 30,
+            kube_qps: None,
+            startup_stagger_ms: 500,
+            startup_retry_attempts: 6,
+            startup_retry_delay_secs: 10,
+            pac_proxy_port: None,
+            trash_retention_days: 30,
+            hotkeys: Vec::new(),
+            proxy_warm_pool_ttl_secs: 0,
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::BTreeMap;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::models::mesh_model::MeshDiagnosis;
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+pub struct ServiceDescription {
+    pub name: String,
+    pub service_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cluster_ip: Option<String>,
+    pub selector: BTreeMap<String, String>,
+    pub ports: Vec<String>,
+}
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+pub struct EndpointsDescription {
+    pub ready_addresses: Vec<String>,
+    pub not_ready_addresses: Vec<String>,
+}
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+pub struct PodConditionDescription {
+    pub condition_type: String,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+pub struct PodDescription {
+    pub name: String,
+    pub phase: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pod_ip: Option<String>,
+    pub labels: BTreeMap<String, String>,
+    pub images: Vec<String>,
+    pub conditions: Vec<PodConditionDescription>,
+}
+
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize, Debug)]
+pub struct TargetDescription {
+    pub config_id: i64,
+    pub namespace: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<ServiceDescription>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoints: Option<EndpointsDescription>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pod: Option<PodDescription>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mesh: Option<MeshDiagnosis>,
+}
@@ -0,0 +1,14 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::models::config_model::Config;
+
+#[derive(Clone, Deserialize, PartialEq, Serialize, Debug, Default)]
+pub struct ToolImport {
+    pub tool: String,
+    pub configs: Vec<Config>,
+    // entries that have no kftray equivalent, with the reason
+    pub skipped: Vec<String>,
+}
@@ -0,0 +1,299 @@
+use std::collections::HashSet;
+use std::time::{
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+use sqlx::{
+    Row,
+    SqlitePool,
+};
+
+use crate::db::get_db_pool;
+use crate::models::access_audit_model::AccessAuditEntry;
+use crate::models::config_model::Config;
+use crate::utils::context_settings::list_context_settings_with_pool;
+
+pub(crate) fn access_user() -> Option<String> {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+        .filter(|user| !user.is_empty())
+}
+
+fn audit_target(config: &Config) -> String {
+    let target = match config.workload_type.as_deref() {
+        Some("pod") => config.target.clone(),
+        _ => config.service.clone(),
+    };
+    format!(
+        "{}:{}",
+        target.unwrap_or_default(),
+        config.remote_port.unwrap_or_default()
+    )
+}
+
+async fn production_contexts_with_pool(pool: &SqlitePool) -> Result<HashSet<String>, String> {
+    Ok(list_context_settings_with_pool(pool)
+        .await?
+        .into_iter()
+        .filter(|settings| settings.production)
+        .map(|settings| settings.context)
+        .collect())
+}
+
+pub async fn needs_access_reason(configs: &[Config]) -> Result<bool, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    needs_access_reason_with_pool(configs, &pool).await
+}
+
+pub async fn needs_access_reason_with_pool(
+    configs: &[Config], pool: &SqlitePool,
+) -> Result<bool, String> {
+    let production = production_contexts_with_pool(pool).await?;
+    Ok(configs
+        .iter()
+        .any(|config| production.contains(&config.context)))
+}
+
+pub async fn require_access_reason(
+    configs: &[Config], reason: Option<&str>,
+) -> Result<Vec<AccessAuditEntry>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    require_access_reason_with_pool(configs, reason, &pool).await
+}
+
+// the audit entries for the configs that go into production contexts, fails
+// when there are any and no reason was given
+pub async fn require_access_reason_with_pool(
+    configs: &[Config], reason: Option<&str>, pool: &SqlitePool,
+) -> Result<Vec<AccessAuditEntry>, String> {
+    let production = production_contexts_with_pool(pool).await?;
+    let production_configs: Vec<&Config> = configs
+        .iter()
+        .filter(|config| production.contains(&config.context))
+        .collect();
+    let Some(first) = production_configs.first() else {
+        return Ok(Vec::new());
+    };
+
+    let reason = reason.map(str::trim).unwrap_or_default();
+    if reason.is_empty() {
+        return Err(format!(
+            "Context {} is marked as production, a reason is required to start forwards there",
+            first.context
+        ));
+    }
+
+    let started_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?
+        .as_secs() as i64;
+    let user = access_user();
+    Ok(production_configs
+        .into_iter()
+        .map(|config| AccessAuditEntry {
+            id: None,
+            config_id: config.id,
+            context: config.context.clone(),
+            namespace: config.namespace.clone(),
+            target: audit_target(config),
+            reason: reason.to_string(),
+            user: user.clone(),
+            started_at,
+        })
+        .collect())
+}
+
+pub async fn record_access_audit(entries: &[AccessAuditEntry]) -> Result<(), String> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    record_access_audit_with_pool(entries, &pool).await
+}
+
+pub async fn record_access_audit_with_pool(
+    entries: &[AccessAuditEntry], pool: &SqlitePool,
+) -> Result<(), String> {
+    for entry in entries {
+        sqlx::query(
+            "INSERT INTO access_audit
+                (config_id, context, namespace, target, reason, user, started_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )
+        .bind(entry.config_id)
+        .bind(&entry.context)
+        .bind(&entry.namespace)
+        .bind(&entry.target)
+        .bind(&entry.reason)
+        .bind(&entry.user)
+        .bind(entry.started_at)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+pub async fn list_access_audit(context: Option<&str>) -> Result<Vec<AccessAuditEntry>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    list_access_audit_with_pool(context, &pool).await
+}
+
+pub async fn list_access_audit_with_pool(
+    context: Option<&str>, pool: &SqlitePool,
+) -> Result<Vec<AccessAuditEntry>, String> {
+    let rows = sqlx::query(
+        "SELECT * FROM access_audit
+         WHERE ?1 IS NULL OR context = ?1
+         ORDER BY started_at DESC, id DESC",
+    )
+    .bind(context)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(AccessAuditEntry {
+                id: row.try_get("id").map_err(|e| e.to_string())?,
+                config_id: row.try_get("config_id").map_err(|e| e.to_string())?,
+                context: row.try_get("context").map_err(|e| e.to_string())?,
+                namespace: row.try_get("namespace").map_err(|e| e.to_string())?,
+                target: row.try_get("target").map_err(|e| e.to_string())?,
+                reason: row.try_get("reason").map_err(|e| e.to_string())?,
+                user: row.try_get("user").map_err(|e| e.to_string())?,
+                started_at: row.try_get("started_at").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn access_audit_to_csv(entries: &[AccessAuditEntry]) -> String {
+    let mut csv = String::from("started_at,user,context,namespace,target,config_id,reason\n");
+    for entry in entries {
+        let fields = [
+            entry.started_at.to_string(),
+            entry.user.clone().unwrap_or_default(),
+            entry.context.clone(),
+            entry.namespace.clone(),
+            entry.target.clone(),
+            entry.config_id.map(|id| id.to_string()).unwrap_or_default(),
+            entry.reason.clone(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+pub async fn export_access_audit(format: &str) -> Result<String, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    export_access_audit_with_pool(format, &pool).await
+}
+
+pub async fn export_access_audit_with_pool(
+    format: &str, pool: &SqlitePool,
+) -> Result<String, String> {
+    let entries = list_access_audit_with_pool(None, pool).await?;
+    match format {
+        "json" => serde_json::to_string_pretty(&entries).map_err(|e| e.to_string()),
+        "csv" => Ok(access_audit_to_csv(&entries)),
+        other => Err(format!("Unsupported audit export format: {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_memory_db_pool;
+    use crate::models::context_settings_model::ContextSettings;
+    use crate::utils::context_settings::save_context_settings_with_pool;
+
+    #[tokio::test]
+    async fn test_production_access_needs_reason() {
+        let pool = create_memory_db_pool().await.unwrap();
+        save_context_settings_with_pool(
+            ContextSettings {
+                context: "prod".to_string(),
+                production: true,
+                ..Default::default()
+            },
+            &pool,
+        )
+        .await
+        .unwrap();
+
+        let staging = Config {
+            id: Some(1),
+            context: "staging".to_string(),
+            ..Default::default()
+        };
+        let prod = Config {
+            id: Some(2),
+            context: "prod".to_string(),
+            service: Some("payments".to_string()),
+            workload_type: Some("service".to_string()),
+            remote_port: Some(443),
+            ..Default::default()
+        };
+
+        assert!(
+            !needs_access_reason_with_pool(std::slice::from_ref(&staging), &pool)
+                .await
+                .unwrap()
+        );
+        assert!(
+            needs_access_reason_with_pool(&[staging.clone(), prod.clone()], &pool)
+                .await
+                .unwrap()
+        );
+        assert!(
+            require_access_reason_with_pool(std::slice::from_ref(&staging), None, &pool)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(require_access_reason_with_pool(
+            &[staging.clone(), prod.clone()],
+            Some(" "),
+            &pool
+        )
+        .await
+        .is_err());
+
+        let entries = require_access_reason_with_pool(
+            &[staging, prod],
+            Some("INC-42, refund \"stuck\""),
+            &pool,
+        )
+        .await
+        .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].target, "payments:443");
+        record_access_audit_with_pool(&entries, &pool)
+            .await
+            .unwrap();
+
+        let audit = list_access_audit_with_pool(Some("prod"), &pool)
+            .await
+            .unwrap();
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].config_id, Some(2));
+
+        let csv = export_access_audit_with_pool("csv", &pool).await.unwrap();
+        assert!(csv.ends_with(",2,\"INC-42, refund \"\"stuck\"\"\"\n"));
+        assert!(export_access_audit_with_pool("xml", &pool).await.is_err());
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::HashSet;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::sync::Mutex as StdMutex;
+use std::time::{
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+use lazy_static::lazy_static;
+use log::warn;
+use tokio::fs::{
+    self,
+    OpenOptions,
+};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::models::access_log_model::AccessLogEntry;
+use crate::utils::config_dir::get_access_log_folder_path;
+
+const MAX_ACCESS_LOG_BYTES: u64 = 5 * 1024 * 1024;
+const MAX_ROTATED_FILES: usize = 3;
+
+lazy_static! {
+    static ref ACCESS_LOG_ENABLED: StdMutex<HashSet<i64>> = StdMutex::new(HashSet::new());
+    static ref ACCESS_LOG_WRITE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+pub fn set_access_log_enabled(config_id: i64, enabled: bool) {
+    let mut configs = ACCESS_LOG_ENABLED.lock().unwrap();
+    if enabled {
+        configs.insert(config_id);
+    } else {
+        configs.remove(&config_id);
+    }
+}
+
+pub fn is_access_log_enabled(config_id: i64) -> bool {
+    ACCESS_LOG_ENABLED.lock().unwrap().contains(&config_id)
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn access_log_file(dir: &Path, config_id: i64, index: usize) -> PathBuf {
+    if index == 0 {
+        dir.join(format!("{}.log", config_id))
+    } else {
+        dir.join(format!("{}.log.{}", config_id, index))
+    }
+}
+
+async fn rotate_if_needed(dir: &Path, config_id: i64) -> Result<(), String> {
+    let current = access_log_file(dir, config_id, 0);
+
+    let size = match fs::metadata(&current).await {
+        Ok(metadata) => metadata.len(),
+        Err(_) => return Ok(()),
+    };
+
+    if size < MAX_ACCESS_LOG_BYTES {
+        return Ok(());
+    }
+
+    for index in (1..MAX_ROTATED_FILES).rev() {
+        let from = access_log_file(dir, config_id, index);
+        if fs::try_exists(&from).await.unwrap_or_default() {
+            fs::rename(&from, access_log_file(dir, config_id, index + 1))
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    fs::rename(&current, access_log_file(dir, config_id, 1))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn append_entry(dir: &Path, entry: &AccessLogEntry) -> Result<(), String> {
+    let _guard = ACCESS_LOG_WRITE_LOCK.lock().await;
+
+    fs::create_dir_all(dir).await.map_err(|e| e.to_string())?;
+    rotate_if_needed(dir, entry.config_id).await?;
+
+    let mut line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+    line.push('\n');
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(access_log_file(dir, entry.config_id, 0))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    file.write_all(line.as_bytes())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn read_entries(
+    dir: &Path, config_id: i64, since: Option<u64>,
+) -> Result<Vec<AccessLogEntry>, String> {
+    let mut entries = Vec::new();
+
+    for index in (0..=MAX_ROTATED_FILES).rev() {
+        let path = access_log_file(dir, config_id, index);
+        let content = match fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.to_string()),
+        };
+
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str::<AccessLogEntry>(line) {
+                Ok(entry) if since.is_none_or(|since| entry.timestamp_ms >= since) => {
+                    entries.push(entry)
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Skipping malformed access log line in {:?}: {}", path, e),
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+pub async fn append_access_log(entry: &AccessLogEntry) -> Result<(), String> {
+    let dir = get_access_log_folder_path()?;
+    append_entry(&dir, entry).await
+}
+
+pub async fn get_access_log(
+    config_id: i64, since: Option<u64>,
+) -> Result<Vec<AccessLogEntry>, String> {
+    let dir = get_access_log_folder_path()?;
+    read_entries(&dir, config_id, since).await
+}
+
+pub async fn clear_access_log(config_id: i64) -> Result<(), String> {
+    let dir = get_access_log_folder_path()?;
+
+    for index in 0..=MAX_ROTATED_FILES {
+        match fs::remove_file(access_log_file(&dir, config_id, index)).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(config_id: i64, timestamp_ms: u64) -> AccessLogEntry {
+        AccessLogEntry {
+            config_id,
+            timestamp_ms,
+            client_addr: "127.0.0.1:50000".to_string(),
+            local_port: 8080,
+            duration_ms: 12,
+            bytes_in: 100,
+            bytes_out: 200,
+            error: None,
+            protocol: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_append_and_read_entries() {
+        let dir = tempfile::tempdir().unwrap();
+
+        for timestamp in [1_000, 2_000, 3_000] {
+            append_entry(dir.path(), &entry(1, timestamp))
+                .await
+                .unwrap();
+        }
+        append_entry(dir.path(), &entry(2, 5_000)).await.unwrap();
+
+        assert_eq!(read_entries(dir.path(), 1, None).await.unwrap().len(), 3);
+
+        let recent = read_entries(dir.path(), 1, Some(2_000)).await.unwrap();
+        let timestamps: Vec<u64> = recent.iter().map(|e| e.timestamp_ms).collect();
+        assert_eq!(timestamps, vec![2_000, 3_000]);
+
+        assert!(read_entries(dir.path(), 3, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rotation_keeps_entries_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let current = access_log_file(dir.path(), 1, 0);
+
+        append_entry(dir.path(), &entry(1, 1_000)).await.unwrap();
+        let file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&current)
+            .unwrap();
+        file.set_len(MAX_ACCESS_LOG_BYTES).unwrap();
+
+        append_entry(dir.path(), &entry(1, 2_000)).await.unwrap();
+
+        assert!(access_log_file(dir.path(), 1, 1).exists());
+        let entries = read_entries(dir.path(), 1, Some(1_500)).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].timestamp_ms, 2_000);
+    }
+}
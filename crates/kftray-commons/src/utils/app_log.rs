@@ -0,0 +1,328 @@
+use std::collections::VecDeque;
+use std::fs::{
+    File,
+    OpenOptions,
+};
+use std::io::{
+    self,
+    Write,
+};
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::time::UNIX_EPOCH;
+
+use futures::stream::{
+    self,
+    Stream,
+};
+use lazy_static::lazy_static;
+use serde::Serialize;
+use tokio::sync::broadcast::{
+    self,
+    error::RecvError,
+};
+
+use crate::utils::config_dir::get_app_log_path;
+
+pub const APP_LOG_MAX_BYTES: u64 = 10 * 1024 * 1024;
+// rotated files kept next to the current app.log
+pub const APP_LOG_MAX_FILES: usize = 5;
+const APP_LOG_CHANNEL_CAPACITY: usize = 1024;
+
+lazy_static! {
+    static ref APP_LOG_LINES: broadcast::Sender<String> =
+        broadcast::channel(APP_LOG_CHANNEL_CAPACITY).0;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct AppLogFile {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    pub modified_ms: Option<i64>,
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}", index));
+    PathBuf::from(rotated)
+}
+
+// the current file first, then the rotated ones from newest to oldest
+fn app_log_files(path: &Path) -> Vec<PathBuf> {
+    std::iter::once(path.to_path_buf())
+        .chain((1..=APP_LOG_MAX_FILES).map(|index| rotated_path(path, index)))
+        .filter(|path| path.exists())
+        .collect()
+}
+
+// app.log rolls over to app.log.1, app.log.1 to app.log.2 and so on, the
+// oldest one past max_files is dropped. every complete line written is also
+// published to the followers of tail_app_logs
+pub struct RotatingLogWriter {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: Option<File>,
+    size: u64,
+    echo_stderr: bool,
+    pending: Vec<u8>,
+}
+
+impl RotatingLogWriter {
+    pub fn new(path: PathBuf, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingLogWriter {
+            path,
+            max_bytes,
+            max_files,
+            file: Some(file),
+            size,
+            echo_stderr: false,
+            pending: Vec::new(),
+        })
+    }
+
+    pub fn app() -> io::Result<Self> {
+        let path = get_app_log_path().map_err(io::Error::other)?;
+        Self::new(path, APP_LOG_MAX_BYTES, APP_LOG_MAX_FILES)
+    }
+
+    pub fn with_stderr_echo(mut self, echo_stderr: bool) -> Self {
+        self.echo_stderr = echo_stderr;
+        self
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        if self.max_files == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            for index in (1..self.max_files).rev() {
+                let from = rotated_path(&self.path, index);
+                if from.exists() {
+                    std::fs::rename(&from, rotated_path(&self.path, index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+        self.reopen()
+    }
+
+    fn reopen(&mut self) -> io::Result<()> {
+        self.file = Some(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?,
+        );
+        self.size = 0;
+        Ok(())
+    }
+
+    fn publish_lines(&mut self, buf: &[u8]) {
+        self.pending.extend_from_slice(buf);
+        while let Some(end) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            // nobody following is fine, the lines are in the file regardless
+            let _ = APP_LOG_LINES.send(String::from_utf8_lossy(&line[..end]).into_owned());
+        }
+    }
+}
+
+impl Write for RotatingLogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // the files may have been purged or removed underneath us
+        match std::fs::metadata(&self.path) {
+            Ok(metadata) => self.size = metadata.len(),
+            Err(_) => self.reopen()?,
+        }
+        // only roll over between lines so none is split across two files
+        if self.pending.is_empty() && self.size > 0 && self.size + buf.len() as u64 > self.max_bytes
+        {
+            self.rotate()?;
+        }
+
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            None => return Err(io::Error::other("Log file is not open")),
+        };
+        file.write_all(buf)?;
+        self.size += buf.len() as u64;
+
+        if self.echo_stderr {
+            let _ = io::stderr().write_all(buf);
+        }
+        self.publish_lines(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+fn app_log_file(path: &Path) -> Result<AppLogFile, String> {
+    let metadata = std::fs::metadata(path).map_err(|e| e.to_string())?;
+    Ok(AppLogFile {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        path: path.display().to_string(),
+        size: metadata.len(),
+        modified_ms: metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_millis() as i64),
+    })
+}
+
+pub fn list_app_logs_in(path: &Path) -> Result<Vec<AppLogFile>, String> {
+    app_log_files(path)
+        .iter()
+        .map(|path| app_log_file(path))
+        .collect()
+}
+
+pub fn list_app_logs() -> Result<Vec<AppLogFile>, String> {
+    list_app_logs_in(&get_app_log_path()?)
+}
+
+// walks back into the rotated files when the current one is shorter
+pub fn read_app_log_tail_in(path: &Path, lines: usize) -> Result<Vec<String>, String> {
+    let mut tail: VecDeque<String> = VecDeque::with_capacity(lines);
+    for file in app_log_files(path) {
+        if tail.len() >= lines {
+            break;
+        }
+        let content = std::fs::read(&file).map_err(|e| e.to_string())?;
+        let content = String::from_utf8_lossy(&content);
+        for line in content.lines().rev() {
+            if tail.len() >= lines {
+                break;
+            }
+            tail.push_front(line.to_string());
+        }
+    }
+    Ok(tail.into())
+}
+
+pub fn read_app_log_tail(lines: usize) -> Result<Vec<String>, String> {
+    read_app_log_tail_in(&get_app_log_path()?, lines)
+}
+
+// removes the rotated files and empties the current one, returns the bytes
+// freed
+pub fn purge_app_logs_in(path: &Path) -> Result<u64, String> {
+    let mut freed = 0;
+    for file in app_log_files(path) {
+        freed += std::fs::metadata(&file).map(|m| m.len()).unwrap_or(0);
+        if file == path {
+            File::create(&file).map_err(|e| e.to_string())?;
+        } else {
+            std::fs::remove_file(&file).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(freed)
+}
+
+pub fn purge_app_logs() -> Result<u64, String> {
+    purge_app_logs_in(&get_app_log_path()?)
+}
+
+pub struct AppLogTail {
+    backlog: VecDeque<String>,
+    receiver: Option<broadcast::Receiver<String>>,
+    dropped: u64,
+}
+
+impl AppLogTail {
+    pub async fn recv(&mut self) -> Option<String> {
+        if let Some(line) = self.backlog.pop_front() {
+            return Some(line);
+        }
+
+        let receiver = self.receiver.as_mut()?;
+        loop {
+            match receiver.recv().await {
+                Ok(line) => return Some(line),
+                Err(RecvError::Lagged(skipped)) => self.dropped += skipped,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn into_stream(self) -> impl Stream<Item = String> {
+        stream::unfold(self, |mut tail| async move {
+            tail.recv().await.map(|line| (line, tail))
+        })
+    }
+}
+
+fn tail_app_logs_in(path: &Path, lines: usize, follow: bool) -> Result<AppLogTail, String> {
+    // subscribe before reading so nothing written in between is missed
+    let receiver = follow.then(|| APP_LOG_LINES.subscribe());
+    Ok(AppLogTail {
+        backlog: read_app_log_tail_in(path, lines)?.into(),
+        receiver,
+        dropped: 0,
+    })
+}
+
+// the last lines of the app log, then every new line as it is written when
+// following
+pub fn tail_app_logs(lines: usize, follow: bool) -> Result<AppLogTail, String> {
+    tail_app_logs_in(&get_app_log_path()?, lines, follow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rotating_log_writer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        let mut writer = RotatingLogWriter::new(path.clone(), 64, 2).unwrap();
+        let mut tail = tail_app_logs_in(&path, 10, true).unwrap();
+
+        for i in 0..10 {
+            writeln!(writer, "line {:02} of the app log", i).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let files = list_app_logs_in(&path).unwrap();
+        let names: Vec<&str> = files.iter().map(|file| file.name.as_str()).collect();
+        assert_eq!(names, vec!["app.log", "app.log.1", "app.log.2"]);
+        let rotated = std::fs::read_to_string(rotated_path(&path, 1)).unwrap();
+        assert!(rotated.lines().all(|line| line.ends_with("of the app log")));
+
+        let lines = read_app_log_tail_in(&path, 4).unwrap();
+        assert_eq!(lines.first().unwrap(), "line 06 of the app log");
+        assert_eq!(lines.last().unwrap(), "line 09 of the app log");
+
+        assert_eq!(tail.recv().await.unwrap(), "line 00 of the app log");
+
+        assert!(purge_app_logs_in(&path).unwrap() > 0);
+        assert_eq!(list_app_logs_in(&path).unwrap().len(), 1);
+        assert!(read_app_log_tail_in(&path, 4).unwrap().is_empty());
+
+        writeln!(writer, "after purge").unwrap();
+        assert_eq!(read_app_log_tail_in(&path, 1).unwrap(), vec!["after purge"]);
+    }
+}
@@ -0,0 +1,380 @@
+use log::info;
+use serde_json::{
+    json,
+    Value,
+};
+use sqlx::SqlitePool;
+
+use crate::config::{
+    prepare_config,
+    read_configs_with_pool,
+};
+use crate::config_history::{
+    record_config_history_with_pool,
+    HISTORY_BULK_UPDATED,
+};
+use crate::config_snapshot::snapshot_configs_with_pool;
+use crate::db::get_db_pool;
+use crate::encryption::encode_config_data;
+use crate::models::bulk_update_model::{
+    ConfigChange,
+    ConfigFilter,
+    FieldChange,
+};
+use crate::models::config_model::Config;
+use crate::profile::config_profile;
+use crate::search::{
+    has_tag,
+    score_config,
+};
+
+fn is_empty_filter(filter: &ConfigFilter) -> bool {
+    filter.ids.is_none()
+        && filter.context.is_none()
+        && filter.kubeconfig.is_none()
+        && filter.namespace.is_none()
+        && filter.profile.is_none()
+        && filter.tag.is_none()
+        && filter
+            .query
+            .as_deref()
+            .unwrap_or_default()
+            .trim()
+            .is_empty()
+}
+
+// an unset criterion matches every config
+fn criterion<T: ?Sized>(value: Option<&T>, check: impl FnOnce(&T) -> bool) -> bool {
+    value.map(check).unwrap_or(true)
+}
+
+pub fn matches_filter(config: &Config, filter: &ConfigFilter) -> bool {
+    criterion(filter.ids.as_deref(), |ids| {
+        config.id.is_some_and(|id| ids.contains(&id))
+    }) && criterion(filter.context.as_deref(), |context| {
+        config.context == context
+    }) && criterion(filter.kubeconfig.as_deref(), |kubeconfig| {
+        config.kubeconfig.as_deref() == Some(kubeconfig)
+    }) && criterion(filter.namespace.as_deref(), |namespace| {
+        config.namespace == namespace
+    }) && criterion(filter.profile.as_deref(), |profile| {
+        config_profile(config) == profile
+    }) && criterion(filter.tag.as_deref(), |tag| has_tag(config, tag))
+        && criterion(filter.query.as_deref(), |query| {
+            score_config(config, query).is_some()
+        })
+}
+
+// merges the patch into the config's json, a null clears an optional field
+pub fn apply_patch(config: &Config, patch: &Value) -> Result<Config, String> {
+    let patch = patch
+        .as_object()
+        .ok_or_else(|| "The patch must be a JSON object".to_string())?;
+    if patch.contains_key("id") {
+        return Err("The patch can't change config ids".to_string());
+    }
+
+    let mut value = json!(config);
+    let fields = value
+        .as_object_mut()
+        .ok_or_else(|| "Failed to encode config".to_string())?;
+    for (field, patched) in patch {
+        fields.insert(field.clone(), patched.clone());
+    }
+
+    let mut patched: Config = serde_json::from_value(value)
+        .map_err(|e| format!("The patch doesn't fit a config: {}", e))?;
+    patched.id = config.id;
+    Ok(prepare_config(patched))
+}
+
+pub fn diff_configs(before: &Config, after: &Config) -> Vec<FieldChange> {
+    let before = json!(before);
+    let after = json!(after);
+    let (Some(before), Some(after)) = (before.as_object(), after.as_object()) else {
+        return Vec::new();
+    };
+
+    let mut fields: Vec<&String> = before.keys().chain(after.keys()).collect();
+    fields.sort();
+    fields.dedup();
+
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let old = before.get(field).cloned().unwrap_or(Value::Null);
+            let new = after.get(field).cloned().unwrap_or(Value::Null);
+            (old != new).then(|| FieldChange {
+                field: field.clone(),
+                before: old,
+                after: new,
+            })
+        })
+        .collect()
+}
+
+pub async fn update_configs_bulk(
+    filter: ConfigFilter, patch: Value, dry_run: bool,
+) -> Result<Vec<ConfigChange>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    update_configs_bulk_with_pool(filter, patch, dry_run, &pool).await
+}
+
+// with dry_run the affected configs and their changes are only returned,
+// otherwise every matching config is rewritten in one transaction
+pub async fn update_configs_bulk_with_pool(
+    filter: ConfigFilter, patch: Value, dry_run: bool, pool: &SqlitePool,
+) -> Result<Vec<ConfigChange>, String> {
+    if is_empty_filter(&filter) {
+        return Err("The filter must match on at least one field".to_string());
+    }
+
+    let mut updates = Vec::new();
+    let mut changes = Vec::new();
+    for config in read_configs_with_pool(pool)
+        .await?
+        .into_iter()
+        .filter(|config| matches_filter(config, &filter))
+    {
+        let config_id = config.id.unwrap_or_default();
+        let patched = apply_patch(&config, &patch)
+            .map_err(|e| format!("Failed to patch config {}: {}", config_id, e))?;
+        let diff = diff_configs(&config, &patched);
+        if diff.is_empty() {
+            continue;
+        }
+
+        changes.push(ConfigChange {
+            config_id,
+            alias: config.alias.clone(),
+            changes: diff,
+        });
+        updates.push(patched);
+    }
+    changes.sort_by_key(|change| change.config_id);
+
+    if dry_run || updates.is_empty() {
+        return Ok(changes);
+    }
+
+    write_bulk_updates(&updates, &changes, "bulk editing", pool).await?;
+    Ok(changes)
+}
+
+// snapshots first, then rewrites the configs in one transaction
+async fn write_bulk_updates(
+    updates: &[Config], changes: &[ConfigChange], action: &str, pool: &SqlitePool,
+) -> Result<(), String> {
+    snapshot_configs_with_pool(
+        &format!("Before {} {} config(s)", action, updates.len()),
+        pool,
+    )
+    .await?;
+
+    let mut transaction = pool.begin().await.map_err(|e| e.to_string())?;
+    for config in updates {
+        let data = encode_config_data(&json!(config).to_string())?;
+        sqlx::query("UPDATE configs SET data = ?1 WHERE id = ?2")
+            .bind(data)
+            .bind(config.id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| format!("Failed to update config {:?}: {}", config.id, e))?;
+    }
+    transaction.commit().await.map_err(|e| e.to_string())?;
+
+    for change in changes {
+        let history = change.changes.clone();
+        record_config_history_with_pool(change.config_id, HISTORY_BULK_UPDATED, history, pool)
+            .await?;
+    }
+
+    info!("Bulk updated {} config(s)", updates.len());
+    Ok(())
+}
+
+// adds or removes one tag, tags are matched without regard to case. returns
+// whether the config changed
+pub fn apply_tag(config: &mut Config, tag: &str, add: bool) -> bool {
+    let tag = tag.trim();
+    let mut tags = config.tags.clone().unwrap_or_default();
+    if tag.is_empty() || has_tag(config, tag) == add {
+        return false;
+    }
+
+    if add {
+        tags.push(tag.to_string());
+    } else {
+        tags.retain(|t| !t.eq_ignore_ascii_case(tag));
+    }
+    config.tags = (!tags.is_empty()).then_some(tags);
+    true
+}
+
+async fn retag_configs_with_pool(
+    ids: Vec<i64>, tag: &str, add: bool, pool: &SqlitePool,
+) -> Result<Vec<ConfigChange>, String> {
+    let mut updates = Vec::new();
+    let mut changes = Vec::new();
+    for config in read_configs_with_pool(pool)
+        .await?
+        .into_iter()
+        .filter(|config| config.id.is_some_and(|id| ids.contains(&id)))
+    {
+        let mut tagged = config.clone();
+        if !apply_tag(&mut tagged, tag, add) {
+            continue;
+        }
+
+        changes.push(ConfigChange {
+            config_id: config.id.unwrap_or_default(),
+            alias: config.alias.clone(),
+            changes: diff_configs(&config, &tagged),
+        });
+        updates.push(tagged);
+    }
+    changes.sort_by_key(|change| change.config_id);
+
+    if !updates.is_empty() {
+        let action = if add { "tagging" } else { "untagging" };
+        write_bulk_updates(&updates, &changes, action, pool).await?;
+    }
+    Ok(changes)
+}
+
+// configs that already have the tag, or don't for untagging, are left out of
+// the returned changes
+pub async fn tag_configs(ids: Vec<i64>, tag: String) -> Result<Vec<ConfigChange>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    tag_configs_with_pool(ids, tag, &pool).await
+}
+
+pub async fn tag_configs_with_pool(
+    ids: Vec<i64>, tag: String, pool: &SqlitePool,
+) -> Result<Vec<ConfigChange>, String> {
+    retag_configs_with_pool(ids, &tag, true, pool).await
+}
+
+pub async fn untag_configs(ids: Vec<i64>, tag: String) -> Result<Vec<ConfigChange>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    untag_configs_with_pool(ids, tag, &pool).await
+}
+
+pub async fn untag_configs_with_pool(
+    ids: Vec<i64>, tag: String, pool: &SqlitePool,
+) -> Result<Vec<ConfigChange>, String> {
+    retag_configs_with_pool(ids, &tag, false, pool).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        get_config_with_pool,
+        insert_config_with_pool,
+    };
+    use crate::db::create_memory_db_pool;
+
+    #[tokio::test]
+    async fn test_update_configs_bulk() {
+        let pool = create_memory_db_pool().await.unwrap();
+        for context in ["old-cluster", "old-cluster", "other"] {
+            let config = Config {
+                context: context.to_string(),
+                local_port: Some(8080),
+                ..Default::default()
+            };
+            insert_config_with_pool(config, &pool).await.unwrap();
+        }
+
+        let filter = ConfigFilter {
+            context: Some("old-cluster".to_string()),
+            ..Default::default()
+        };
+        let patch = json!({"context": "new-cluster", "domain_enabled": true});
+
+        let preview = update_configs_bulk_with_pool(filter.clone(), patch.clone(), true, &pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            preview.iter().map(|c| c.config_id).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(preview[0].changes[0].field, "context");
+        assert_eq!(preview[0].changes[1].field, "domain_enabled");
+        assert_eq!(
+            get_config_with_pool(1, &pool).await.unwrap().context,
+            "old-cluster"
+        );
+
+        update_configs_bulk_with_pool(filter.clone(), patch, false, &pool)
+            .await
+            .unwrap();
+        let updated = get_config_with_pool(2, &pool).await.unwrap();
+        assert_eq!(updated.context, "new-cluster");
+        assert_eq!(updated.domain_enabled, Some(true));
+        assert_eq!(
+            get_config_with_pool(3, &pool).await.unwrap().context,
+            "other"
+        );
+
+        let renamed = ConfigFilter {
+            context: Some("new-cluster".to_string()),
+            ..Default::default()
+        };
+        assert!(
+            update_configs_bulk_with_pool(renamed, json!({"local_port": "x"}), false, &pool)
+                .await
+                .is_err()
+        );
+        assert!(
+            update_configs_bulk_with_pool(filter, json!({"namespace": "x"}), true, &pool)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(update_configs_bulk_with_pool(
+            ConfigFilter::default(),
+            json!({"namespace": "x"}),
+            true,
+            &pool
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tag_configs() {
+        let pool = create_memory_db_pool().await.unwrap();
+        for tags in [None, Some(vec!["DB".to_string()]), None] {
+            let config = Config {
+                local_port: Some(8080),
+                tags,
+                ..Default::default()
+            };
+            insert_config_with_pool(config, &pool).await.unwrap();
+        }
+
+        let changes = tag_configs_with_pool(vec![1, 2], "db".to_string(), &pool)
+            .await
+            .unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].config_id, 1);
+        assert_eq!(changes[0].changes[0].field, "tags");
+        assert_eq!(
+            get_config_with_pool(1, &pool).await.unwrap().tags,
+            Some(vec!["db".to_string()])
+        );
+        assert_eq!(get_config_with_pool(3, &pool).await.unwrap().tags, None);
+
+        let changes = untag_configs_with_pool(vec![1, 2, 3], "Db".to_string(), &pool)
+            .await
+            .unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(get_config_with_pool(2, &pool).await.unwrap().tags, None);
+        assert!(tag_configs_with_pool(vec![1], " ".to_string(), &pool)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
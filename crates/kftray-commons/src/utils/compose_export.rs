@@ -0,0 +1,195 @@
+use std::net::IpAddr;
+
+use crate::models::config_model::Config;
+
+// containers reach the host through this name, docker desktop has it built in
+// and extra_hosts adds it on linux
+pub const DOCKER_HOST: &str = "host.docker.internal";
+
+// `api.db` becomes `API_DB`, env var names can't hold much else
+pub fn env_prefix(alias: &str) -> String {
+    let prefix: String = alias
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let prefix = prefix.trim_matches('_').to_string();
+    match prefix.chars().next() {
+        Some(first) if first.is_ascii_digit() => format!("_{}", prefix),
+        _ => prefix,
+    }
+}
+
+fn is_hostname(alias: &str) -> bool {
+    !alias.is_empty()
+        && alias.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+fn yaml_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// host.docker.internal maps to the docker bridge, so a forward bound to a
+// loopback address (the default) never sees the container's connection
+fn reachable_from_docker(config: &Config) -> bool {
+    match config.local_address.as_deref().unwrap_or_default() {
+        "" | "localhost" => false,
+        address => !matches!(address.parse::<IpAddr>(), Ok(ip) if ip.is_loopback()),
+    }
+}
+
+// a compose override that lets containers on this machine reach the forwards.
+// the alias of every forward resolves to the docker host and its address ends
+// up in `<ALIAS>_HOST` / `<ALIAS>_PORT`. the mapping is kept under an
+// `x-kftray` anchor that the given services merge in, without services it's
+// left for the user to merge where it's needed
+pub fn compose_override(forwards: &[Config], services: &[String]) -> String {
+    let mut notes = Vec::new();
+    let mut extra_hosts = vec![format!("{}:host-gateway", DOCKER_HOST)];
+    let mut environment = Vec::new();
+
+    for config in forwards {
+        let alias = config
+            .alias
+            .clone()
+            .or_else(|| config.service.clone())
+            .unwrap_or_default();
+        let Some(local_port) = config.local_port.filter(|port| *port != 0) else {
+            notes.push(format!("{}: no local port", alias));
+            continue;
+        };
+        if !reachable_from_docker(config) {
+            notes.push(format!(
+                "{}: listens on loopback {}, bind it to 0.0.0.0 or the docker bridge address \
+                 so containers reach it through {}",
+                alias,
+                config
+                    .local_address
+                    .as_deref()
+                    .filter(|address| !address.is_empty())
+                    .unwrap_or("127.0.0.1"),
+                DOCKER_HOST
+            ));
+        }
+
+        if is_hostname(&alias) {
+            let entry = format!("{}:host-gateway", alias.to_lowercase());
+            if !extra_hosts.contains(&entry) {
+                extra_hosts.push(entry);
+            }
+        }
+        let prefix = env_prefix(&alias);
+        if prefix.is_empty() {
+            notes.push(format!("{}: no usable env var name", alias));
+            continue;
+        }
+        environment.push(format!("{}_HOST: {}", prefix, yaml_string(DOCKER_HOST)));
+        environment.push(format!(
+            "{}_PORT: {}",
+            prefix,
+            yaml_string(&local_port.to_string())
+        ));
+    }
+
+    let mut yaml = vec!["# generated by kftray from the running port forwards".to_string()];
+    yaml.extend(notes.iter().map(|note| format!("# {}", note)));
+    yaml.push("x-kftray: &kftray".to_string());
+    yaml.push("  extra_hosts:".to_string());
+    yaml.extend(
+        extra_hosts
+            .iter()
+            .map(|entry| format!("    - {}", yaml_string(entry))),
+    );
+    if !environment.is_empty() {
+        yaml.push("  environment:".to_string());
+        yaml.extend(environment.iter().map(|entry| format!("    {}", entry)));
+    }
+
+    if services.is_empty() {
+        yaml.push("# merge it into the services that need it:".to_string());
+        yaml.push("# services:".to_string());
+        yaml.push("#   app:".to_string());
+        yaml.push("#     <<: *kftray".to_string());
+    } else {
+        yaml.push(String::new());
+        yaml.push("services:".to_string());
+        for service in services {
+            yaml.push(format!("  {}:", yaml_string(service)));
+            yaml.push("    <<: *kftray".to_string());
+        }
+    }
+    yaml.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compose_override() {
+        let forwards = vec![
+            Config {
+                alias: Some("api.local".to_string()),
+                local_port: Some(8080),
+                ..Default::default()
+            },
+            Config {
+                alias: Some("pg primary".to_string()),
+                local_port: Some(5432),
+                local_address: Some("127.0.0.5".to_string()),
+                ..Default::default()
+            },
+        ];
+        let yaml = compose_override(&forwards, &["web".to_string()]);
+        assert!(yaml.contains("    - \"host.docker.internal:host-gateway\"\n"));
+        assert!(yaml.contains("    - \"api.local:host-gateway\"\n"));
+        assert!(!yaml.contains("pg primary:host-gateway"));
+        assert!(yaml.contains("    API_LOCAL_PORT: \"8080\"\n"));
+        assert!(yaml.contains("    PG_PRIMARY_HOST: \"host.docker.internal\"\n"));
+        assert!(yaml.contains("# pg primary: listens on loopback 127.0.0.5"));
+        assert!(yaml.ends_with("services:\n  \"web\":\n    <<: *kftray\n"));
+
+        assert!(compose_override(&[], &[]).contains("#     <<: *kftray"));
+        assert_eq!(env_prefix("9-lives"), "_9_LIVES");
+    }
+
+    #[test]
+    fn test_loopback_forwards_are_flagged() {
+        let forward = |alias: &str, local_address: Option<&str>| Config {
+            alias: Some(alias.to_string()),
+            local_port: Some(8080),
+            local_address: local_address.map(str::to_string),
+            ..Default::default()
+        };
+        let yaml = compose_override(
+            &[
+                forward("default", None),
+                forward("loopback", Some("127.0.0.1")),
+                forward("v6", Some("::1")),
+                forward("any", Some("0.0.0.0")),
+                forward("bridge", Some("172.17.0.1")),
+            ],
+            &[],
+        );
+
+        assert!(yaml.contains(
+            "# default: listens on loopback 127.0.0.1, bind it to 0.0.0.0 or the docker bridge \
+             address"
+        ));
+        assert!(yaml.contains("# loopback: listens on loopback 127.0.0.1"));
+        assert!(yaml.contains("# v6: listens on loopback ::1"));
+        assert!(!yaml.contains("# any:"));
+        assert!(!yaml.contains("# bridge:"));
+        assert!(yaml.contains("    ANY_PORT: \"8080\"\n"));
+    }
+}
@@ -12,30 +12,71 @@ use serde_json::{
     json,
     Value as JsonValue,
 };
-use sqlx::Row;
+use sqlx::{
+    Row,
+    SqlitePool,
+};
 
+use crate::config_history::{
+    config_changes,
+    record_config_history_with_pool,
+    HISTORY_CREATED,
+    HISTORY_DELETED,
+    HISTORY_UPDATED,
+};
+use crate::config_snapshot::snapshot_configs_with_pool;
+use crate::config_trash::{
+    move_to_trash,
+    purge_expired_trash_with_pool,
+};
+use crate::context_settings::{
+    apply_context_defaults,
+    get_context_settings_with_pool,
+};
 use crate::db::get_db_pool;
-use crate::migration::migrate_configs;
+use crate::encryption::{
+    decode_config_data,
+    encode_config_data,
+};
+use crate::migration::migrate_configs_with_pool;
 use crate::models::config_model::Config;
+use crate::profile::get_active_profile_with_pool;
 
 pub async fn delete_config(id: i64) -> Result<(), String> {
     let pool = get_db_pool().await.map_err(|e| e.to_string())?;
-    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    delete_config_with_pool(id, &pool).await
+}
+
+// deleted configs go to the trash, from where they can be restored
+pub async fn delete_config_with_pool(id: i64, pool: &SqlitePool) -> Result<(), String> {
+    let mut transaction = pool.begin().await.map_err(|e| e.to_string())?;
 
+    move_to_trash(Some(&[id]), &mut transaction).await?;
     sqlx::query("DELETE FROM configs WHERE id = ?1")
         .bind(id)
-        .execute(&mut *conn)
+        .execute(&mut *transaction)
         .await
         .map_err(|e| format!("Failed to delete config: {}", e))?;
 
+    transaction.commit().await.map_err(|e| e.to_string())?;
+    record_config_history_with_pool(id, HISTORY_DELETED, Vec::new(), pool).await?;
+    purge_expired_trash_with_pool(pool).await?;
+
     Ok(())
 }
 
 pub async fn delete_configs(ids: Vec<i64>) -> Result<(), String> {
     let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    delete_configs_with_pool(ids, &pool).await
+}
+
+pub async fn delete_configs_with_pool(ids: Vec<i64>, pool: &SqlitePool) -> Result<(), String> {
+    snapshot_configs_with_pool(&format!("Before deleting {} config(s)", ids.len()), pool).await?;
+
     let mut transaction = pool.begin().await.map_err(|e| e.to_string())?;
 
-    for id in ids {
+    move_to_trash(Some(&ids), &mut transaction).await?;
+    for id in &ids {
         sqlx::query("DELETE FROM configs WHERE id = ?1")
             .bind(id)
             .execute(&mut *transaction)
@@ -44,51 +85,97 @@ pub async fn delete_configs(ids: Vec<i64>) -> Result<(), String> {
     }
 
     transaction.commit().await.map_err(|e| e.to_string())?;
+    for id in ids {
+        record_config_history_with_pool(id, HISTORY_DELETED, Vec::new(), pool).await?;
+    }
+    purge_expired_trash_with_pool(pool).await?;
 
     Ok(())
 }
 
 pub async fn delete_all_configs() -> Result<(), String> {
     let pool = get_db_pool().await.map_err(|e| e.to_string())?;
-    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    delete_all_configs_with_pool(&pool).await
+}
+
+pub async fn delete_all_configs_with_pool(pool: &SqlitePool) -> Result<(), String> {
+    snapshot_configs_with_pool("Before deleting all configs", pool).await?;
+
+    let mut transaction = pool.begin().await.map_err(|e| e.to_string())?;
 
+    let ids: Vec<i64> = sqlx::query("SELECT id FROM configs")
+        .fetch_all(&mut *transaction)
+        .await
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|row| row.try_get("id").map_err(|e| e.to_string()))
+        .collect::<Result<_, String>>()?;
+    move_to_trash(None, &mut transaction).await?;
     sqlx::query("DELETE FROM configs")
-        .execute(&mut *conn)
+        .execute(&mut *transaction)
         .await
         .map_err(|e| format!("Failed to delete all configs: {}", e))?;
 
+    transaction.commit().await.map_err(|e| e.to_string())?;
+    for id in ids {
+        record_config_history_with_pool(id, HISTORY_DELETED, Vec::new(), pool).await?;
+    }
+
     Ok(())
 }
 
 pub async fn insert_config(config: Config) -> Result<(), String> {
-    let config = prepare_config(config);
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    insert_config_with_pool(config, &pool).await
+}
+
+pub async fn insert_config_with_pool(config: Config, pool: &SqlitePool) -> Result<(), String> {
+    create_config_with_pool(config, pool).await.map(|_| ())
+}
 
+// inserts the config and returns the id it was given
+pub async fn create_config(config: Config) -> Result<i64, String> {
     let pool = get_db_pool().await.map_err(|e| e.to_string())?;
-    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    create_config_with_pool(config, &pool).await
+}
 
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS configs (
-            id INTEGER PRIMARY KEY,
-            data TEXT NOT NULL
-        )",
-    )
-    .execute(&mut *conn)
-    .await
-    .map_err(|e| e.to_string())?;
+pub async fn create_config_with_pool(mut config: Config, pool: &SqlitePool) -> Result<i64, String> {
+    if let Some(defaults) = get_context_settings_with_pool(&config.context, pool).await? {
+        let existing = read_configs_with_pool(pool).await?;
+        apply_context_defaults(&mut config, &defaults, &existing);
+    }
 
-    let data = json!(config).to_string();
+    let mut config = prepare_config(config);
+
+    if config.profile.is_none() {
+        config.profile = Some(get_active_profile_with_pool(pool).await?);
+    }
 
-    sqlx::query("INSERT INTO configs (data) VALUES (?1)")
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+
+    let data = encode_config_data(&json!(config).to_string())?;
+
+    let id = sqlx::query("INSERT INTO configs (data) VALUES (?1)")
         .bind(data)
         .execute(&mut *conn)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())?
+        .last_insert_rowid();
+    drop(conn);
 
-    Ok(())
+    config.id = Some(id);
+    let changes = config_changes(None, Some(&config));
+    record_config_history_with_pool(id, HISTORY_CREATED, changes, pool).await?;
+
+    Ok(id)
 }
 
 pub async fn read_configs() -> Result<Vec<Config>, String> {
     let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    read_configs_with_pool(&pool).await
+}
+
+pub async fn read_configs_with_pool(pool: &SqlitePool) -> Result<Vec<Config>, String> {
     let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
 
     let rows = sqlx::query("SELECT id, data FROM configs")
@@ -103,7 +190,7 @@ pub async fn read_configs() -> Result<Vec<Config>, String> {
 
             async move {
                 let id = id?;
-                let data = data?;
+                let data = decode_config_data(&data?)?;
                 let mut config = serde_json::from_str::<Config>(&data)
                     .map_err(|_| "Failed to decode config".to_string())?;
                 config.id = Some(id);
@@ -156,6 +243,10 @@ pub async fn get_configs() -> Result<Vec<Config>, String> {
 
 pub async fn get_config(id: i64) -> Result<Config, String> {
     let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    get_config_with_pool(id, &pool).await
+}
+
+pub async fn get_config_with_pool(id: i64, pool: &SqlitePool) -> Result<Config, String> {
     let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
 
     let row = sqlx::query("SELECT id, data FROM configs WHERE id = ?1")
@@ -168,6 +259,7 @@ pub async fn get_config(id: i64) -> Result<Config, String> {
         Some(row) => {
             let id: i64 = row.try_get("id").map_err(|e| e.to_string())?;
             let data: String = row.try_get("data").map_err(|e| e.to_string())?;
+            let data = decode_config_data(&data)?;
             let mut config: Config = serde_json::from_str(&data)
                 .map_err(|e| format!("Failed to parse config: {}", e))?;
             config.id = Some(id);
@@ -178,25 +270,44 @@ pub async fn get_config(id: i64) -> Result<Config, String> {
 }
 
 pub async fn update_config(config: Config) -> Result<(), String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    update_config_with_pool(config, &pool).await
+}
+
+pub async fn update_config_with_pool(config: Config, pool: &SqlitePool) -> Result<(), String> {
     let config = prepare_config(config);
+    let id = config.id.unwrap();
+    let before = get_config_with_pool(id, pool).await.ok();
 
-    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
     let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
 
-    let data = json!(config).to_string();
+    let data = encode_config_data(&json!(config).to_string())?;
 
     sqlx::query("UPDATE configs SET data = ?1 WHERE id = ?2")
         .bind(data)
-        .bind(config.id.unwrap())
+        .bind(id)
         .execute(&mut *conn)
         .await
         .map_err(|e| e.to_string())?;
+    drop(conn);
+
+    let changes = config_changes(before.as_ref(), Some(&config));
+    if !changes.is_empty() {
+        record_config_history_with_pool(id, HISTORY_UPDATED, changes, pool).await?;
+    }
 
     Ok(())
 }
 
 pub async fn export_configs() -> Result<String, String> {
-    let mut configs = read_configs().await.map_err(|e| e.to_string())?;
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    export_configs_with_pool(&pool).await
+}
+
+pub async fn export_configs_with_pool(pool: &SqlitePool) -> Result<String, String> {
+    let mut configs = read_configs_with_pool(pool)
+        .await
+        .map_err(|e| e.to_string())?;
 
     for config in &mut configs {
         config.id = None;
@@ -215,6 +326,11 @@ pub async fn export_configs() -> Result<String, String> {
 }
 
 pub async fn import_configs(json: String) -> Result<(), String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    import_configs_with_pool(json, &pool).await
+}
+
+pub async fn import_configs_with_pool(json: String, pool: &SqlitePool) -> Result<(), String> {
     let configs: Vec<Config> = match serde_json::from_str(&json) {
         Ok(configs) => configs,
         Err(e) => {
@@ -225,13 +341,19 @@ pub async fn import_configs(json: String) -> Result<(), String> {
         }
     };
 
+    snapshot_configs_with_pool(
+        &format!("Before importing {} config(s)", configs.len()),
+        pool,
+    )
+    .await?;
+
     for config in configs {
-        insert_config(config)
+        insert_config_with_pool(config, pool)
             .await
             .map_err(|e| format!("Failed to insert config: {}", e))?;
     }
 
-    if let Err(e) = migrate_configs().await {
+    if let Err(e) = migrate_configs_with_pool(pool).await {
         return Err(format!("Error migrating configs: {}", e));
     }
 
@@ -280,13 +402,26 @@ fn remove_blank_or_default_fields(value: &mut JsonValue, default_config: &JsonVa
     }
 }
 
-fn prepare_config(mut config: Config) -> Config {
+pub(crate) fn prepare_config(mut config: Config) -> Config {
     if let Some(ref mut alias) = config.alias {
         *alias = alias.trim().to_string();
     }
     if let Some(ref mut kubeconfig) = config.kubeconfig {
         *kubeconfig = kubeconfig.trim().to_string();
     }
+    if let Some(tags) = config.tags.take() {
+        let mut normalized: Vec<String> = Vec::new();
+        for tag in tags
+            .iter()
+            .map(|tag| tag.trim())
+            .filter(|tag| !tag.is_empty())
+        {
+            if !normalized.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                normalized.push(tag.to_string());
+            }
+        }
+        config.tags = (!normalized.is_empty()).then_some(normalized);
+    }
 
     if config.local_port == Some(0) || config.local_port.is_none() {
         match pick_unused_port() {
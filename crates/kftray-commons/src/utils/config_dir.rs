@@ -5,6 +5,11 @@ use std::{
 
 use anyhow::Result;
 
+use crate::utils::db_mode::{
+    read_database_settings,
+    resolve_db_path,
+};
+
 pub fn get_config_dir() -> Result<PathBuf, String> {
     if let Ok(config_dir) = env::var("KFTRAY_CONFIG") {
         return Ok(PathBuf::from(config_dir));
@@ -31,12 +36,45 @@ pub fn get_log_folder_path() -> Result<PathBuf, String> {
     Ok(config_path)
 }
 
+pub fn get_access_log_folder_path() -> Result<PathBuf, String> {
+    let mut config_path = get_config_dir()?;
+    config_path.push("access_logs");
+    Ok(config_path)
+}
+
+pub fn get_capture_folder_path() -> Result<PathBuf, String> {
+    let mut config_path = get_config_dir()?;
+    config_path.push("captures");
+    Ok(config_path)
+}
+
 pub fn get_db_file_path() -> Result<PathBuf, String> {
+    if let Some(db_path) =
+        resolve_db_path(env::var("KFTRAY_DB_PATH").ok(), &read_database_settings())
+    {
+        return Ok(db_path);
+    }
+
     let mut config_path = get_config_dir()?;
     config_path.push("configs.db");
     Ok(config_path)
 }
 
+// where the database is, read before the database can be opened
+pub fn get_db_settings_path() -> Result<PathBuf, String> {
+    let mut config_path = get_config_dir()?;
+    config_path.push("database.json");
+    Ok(config_path)
+}
+
+// exists while the configs in the database are encrypted, so saving one
+// knows whether to ask the keychain without asking it first
+pub fn get_encryption_marker_path() -> Result<PathBuf, String> {
+    let mut marker = get_db_file_path()?.into_os_string();
+    marker.push(".encrypted");
+    Ok(PathBuf::from(marker))
+}
+
 pub fn get_pod_manifest_path() -> Result<PathBuf, String> {
     let mut config_path = get_config_dir()?;
     config_path.push("proxy_manifest.json");
@@ -55,6 +93,41 @@ pub fn get_window_state_path() -> Result<PathBuf, String> {
     Ok(config_path)
 }
 
+// contexts kftray generates for cloud clusters, kept apart from ~/.kube/config
+pub fn get_managed_kubeconfig_path() -> Result<PathBuf, String> {
+    let mut config_path = get_config_dir()?;
+    config_path.push("kubeconfig");
+    Ok(config_path)
+}
+
+pub fn get_cloud_clusters_path() -> Result<PathBuf, String> {
+    let mut config_path = get_config_dir()?;
+    config_path.push("cloud_clusters.json");
+    Ok(config_path)
+}
+
+// the wireguard config and scripts of the cluster network, they hold a
+// private key so nothing else is kept there
+pub fn get_cluster_network_dir() -> Result<PathBuf, String> {
+    let mut config_path = get_config_dir()?;
+    config_path.push("cluster_network");
+    Ok(config_path)
+}
+
+// where the running forwards are published for other tools to pick up
+pub fn get_forward_mappings_path() -> Result<PathBuf, String> {
+    let mut config_path = get_config_dir()?;
+    config_path.push("forwards.json");
+    Ok(config_path)
+}
+
+// the smaller manifest editors show in their ports views
+pub fn get_port_manifest_path() -> Result<PathBuf, String> {
+    let mut config_path = get_config_dir()?;
+    config_path.push("ports.json");
+    Ok(config_path)
+}
+
 pub fn get_kubeconfig_paths() -> Result<Vec<PathBuf>> {
     let mut paths = Vec::new();
 
@@ -162,8 +235,9 @@ mod tests {
 
     #[test]
     fn test_get_db_file_path() {
-        let preserved_vars = preserve_env_vars(&["KFTRAY_CONFIG"]);
+        let preserved_vars = preserve_env_vars(&["KFTRAY_CONFIG", "KFTRAY_DB_PATH"]);
 
+        env::remove_var("KFTRAY_DB_PATH");
         env::set_var("KFTRAY_CONFIG", "/custom/config/dir");
         let db_file_path = get_db_file_path().unwrap();
         assert_eq!(db_file_path, PathBuf::from("/custom/config/dir/configs.db"));
@@ -171,6 +245,18 @@ mod tests {
         restore_env_vars(preserved_vars);
     }
 
+    #[test]
+    fn test_get_db_file_path_override() {
+        let preserved_vars = preserve_env_vars(&["KFTRAY_CONFIG", "KFTRAY_DB_PATH"]);
+
+        env::set_var("KFTRAY_CONFIG", "/custom/config/dir");
+        env::set_var("KFTRAY_DB_PATH", "/elsewhere/kftray.db");
+        let db_file_path = get_db_file_path().unwrap();
+        assert_eq!(db_file_path, PathBuf::from("/elsewhere/kftray.db"));
+
+        restore_env_vars(preserved_vars);
+    }
+
     #[test]
     fn test_get_pod_manifest_path() {
         let preserved_vars = preserve_env_vars(&["KFTRAY_CONFIG"]);
@@ -0,0 +1,103 @@
+use crate::models::config_model::Config;
+
+// a color is one of these names or a `#rrggbb` hex value, so every ui shows
+// the same shade for the same name
+pub const CONFIG_COLORS: [(&str, (u8, u8, u8)); 8] = [
+    ("red", (243, 139, 168)),
+    ("orange", (250, 179, 135)),
+    ("yellow", (249, 226, 175)),
+    ("green", (166, 227, 161)),
+    ("teal", (148, 226, 213)),
+    ("blue", (137, 180, 250)),
+    ("purple", (203, 166, 247)),
+    ("gray", (147, 153, 178)),
+];
+
+// longer than any emoji sequence, shorter than a word
+const MAX_ICON_CHARS: usize = 8;
+
+pub fn parse_config_color(color: &str) -> Option<(u8, u8, u8)> {
+    let color = color.trim().to_lowercase();
+    if let Some((_, rgb)) = CONFIG_COLORS.iter().find(|(name, _)| *name == color) {
+        return Some(*rgb);
+    }
+
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |at: usize| u8::from_str_radix(&hex[at..at + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+// the color of the config as `#rrggbb`, none when unset or invalid
+pub fn config_color_hex(config: &Config) -> Option<String> {
+    let (r, g, b) = parse_config_color(config.color.as_deref()?)?;
+    Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+}
+
+pub fn validate_config_icon(icon: &str) -> Result<(), String> {
+    if icon.chars().count() > MAX_ICON_CHARS {
+        return Err(format!(
+            "Icon '{}' should be an emoji or a short symbol",
+            icon
+        ));
+    }
+    Ok(())
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+// what a ui shows for the config, the alias unless a display name is set
+pub fn config_display_name(config: &Config) -> String {
+    non_empty(&config.display_name)
+        .or_else(|| non_empty(&config.alias))
+        .or_else(|| non_empty(&config.service))
+        .map(str::to_string)
+        .unwrap_or_else(|| config.id.unwrap_or_default().to_string())
+}
+
+// the display name with the icon in front of it
+pub fn config_label(config: &Config) -> String {
+    let name = config_display_name(config);
+    match non_empty(&config.icon) {
+        Some(icon) => format!("{} {}", icon, name),
+        None => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_presentation() {
+        assert_eq!(parse_config_color("Red"), Some((243, 139, 168)));
+        assert_eq!(parse_config_color("#0A0b0c"), Some((10, 11, 12)));
+        assert_eq!(parse_config_color("#0a0b0"), None);
+        assert_eq!(parse_config_color("crimson"), None);
+
+        let config = Config {
+            alias: Some("pg".to_string()),
+            color: Some("green".to_string()),
+            icon: Some("🐘".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(config_color_hex(&config).as_deref(), Some("#a6e3a1"));
+        assert_eq!(config_label(&config), "🐘 pg");
+
+        let named = Config {
+            display_name: Some("Postgres (prod)".to_string()),
+            icon: Some(" ".to_string()),
+            ..config
+        };
+        assert_eq!(config_label(&named), "Postgres (prod)");
+        assert!(validate_config_icon("🐘").is_ok());
+        assert!(validate_config_icon("elephant!").is_err());
+    }
+}
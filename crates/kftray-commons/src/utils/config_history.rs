@@ -0,0 +1,170 @@
+use std::time::{
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+use sqlx::{
+    Row,
+    SqlitePool,
+};
+
+use crate::access_audit::access_user;
+use crate::bulk_update::diff_configs;
+use crate::db::get_db_pool;
+use crate::encryption::{
+    decode_config_data,
+    encode_config_data,
+};
+use crate::models::bulk_update_model::FieldChange;
+use crate::models::config_history_model::ConfigHistoryEntry;
+use crate::models::config_model::Config;
+
+// older entries of a config are pruned past this
+const MAX_HISTORY_PER_CONFIG: i64 = 200;
+
+pub const HISTORY_CREATED: &str = "created";
+pub const HISTORY_UPDATED: &str = "updated";
+pub const HISTORY_BULK_UPDATED: &str = "bulk_updated";
+pub const HISTORY_DELETED: &str = "deleted";
+pub const HISTORY_RESTORED: &str = "restored";
+pub const HISTORY_ROLLED_BACK: &str = "rolled_back";
+
+// the fields that differ, a missing side counts as a default config. the id
+// isn't part of what changed
+pub fn config_changes(before: Option<&Config>, after: Option<&Config>) -> Vec<FieldChange> {
+    let default = Config {
+        id: before.or(after).and_then(|config| config.id),
+        ..Default::default()
+    };
+    diff_configs(before.unwrap_or(&default), after.unwrap_or(&default))
+        .into_iter()
+        .filter(|change| change.field != "id")
+        .collect()
+}
+
+pub(crate) async fn record_config_history_with_pool(
+    config_id: i64, action: &str, changes: Vec<FieldChange>, pool: &SqlitePool,
+) -> Result<(), String> {
+    let changed_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?
+        .as_secs() as i64;
+    // changed values can hold anything a config does, so they're stored the
+    // same way configs are
+    let changes = serde_json::to_string(&changes).map_err(|e| e.to_string())?;
+
+    sqlx::query(
+        "INSERT INTO config_history (config_id, action, user, changes, changed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )
+    .bind(config_id)
+    .bind(action)
+    .bind(access_user())
+    .bind(encode_config_data(&changes)?)
+    .bind(changed_at)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to record history of config {}: {}", config_id, e))?;
+
+    sqlx::query(
+        "DELETE FROM config_history WHERE config_id = ?1 AND id NOT IN
+            (SELECT id FROM config_history WHERE config_id = ?1 ORDER BY id DESC LIMIT ?2)",
+    )
+    .bind(config_id)
+    .bind(MAX_HISTORY_PER_CONFIG)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+pub async fn get_config_history(config_id: i64) -> Result<Vec<ConfigHistoryEntry>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    get_config_history_with_pool(config_id, &pool).await
+}
+
+// newest first
+pub async fn get_config_history_with_pool(
+    config_id: i64, pool: &SqlitePool,
+) -> Result<Vec<ConfigHistoryEntry>, String> {
+    let rows = sqlx::query(
+        "SELECT id, config_id, action, user, changes, changed_at FROM config_history
+         WHERE config_id = ?1 ORDER BY id DESC",
+    )
+    .bind(config_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            let changes: String = row.try_get("changes").map_err(|e| e.to_string())?;
+            let changes: Vec<FieldChange> = serde_json::from_str(&decode_config_data(&changes)?)
+                .map_err(|e| format!("Failed to parse config history: {}", e))?;
+            Ok(ConfigHistoryEntry {
+                id: row.try_get("id").map_err(|e| e.to_string())?,
+                config_id: row.try_get("config_id").map_err(|e| e.to_string())?,
+                action: row.try_get("action").map_err(|e| e.to_string())?,
+                user: row.try_get("user").map_err(|e| e.to_string())?,
+                changed_at: row.try_get("changed_at").map_err(|e| e.to_string())?,
+                changes,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        delete_config_with_pool,
+        get_config_with_pool,
+        insert_config_with_pool,
+        read_configs_with_pool,
+        update_config_with_pool,
+    };
+    use crate::db::create_memory_db_pool;
+
+    #[tokio::test]
+    async fn test_config_history() {
+        let pool = create_memory_db_pool().await.unwrap();
+        let config = Config {
+            alias: Some("api".to_string()),
+            local_port: Some(8080),
+            ..Default::default()
+        };
+        insert_config_with_pool(config, &pool).await.unwrap();
+        let id = read_configs_with_pool(&pool).await.unwrap()[0].id.unwrap();
+
+        let mut config = get_config_with_pool(id, &pool).await.unwrap();
+        update_config_with_pool(config.clone(), &pool)
+            .await
+            .unwrap();
+        config.local_port = Some(9090);
+        update_config_with_pool(config, &pool).await.unwrap();
+        delete_config_with_pool(id, &pool).await.unwrap();
+
+        let history = get_config_history_with_pool(id, &pool).await.unwrap();
+        let actions: Vec<&str> = history.iter().map(|entry| entry.action.as_str()).collect();
+        // saving an unchanged config leaves no entry
+        assert_eq!(
+            actions,
+            vec![HISTORY_DELETED, HISTORY_UPDATED, HISTORY_CREATED]
+        );
+
+        assert_eq!(
+            history[1].changes,
+            vec![FieldChange {
+                field: "local_port".to_string(),
+                before: 8080.into(),
+                after: 9090.into(),
+            }]
+        );
+        assert!(history[2]
+            .changes
+            .iter()
+            .any(|change| change.field == "alias" && change.after == "api"));
+        assert!(history[2].changes.iter().all(|change| change.field != "id"));
+    }
+}
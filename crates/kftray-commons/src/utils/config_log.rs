@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::fs::{
+    File,
+    OpenOptions,
+};
+use std::future::Future;
+use std::io::Write;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::str::FromStr;
+use std::sync::{
+    Mutex,
+    RwLock,
+};
+
+use k8s_openapi::chrono::{
+    SecondsFormat,
+    Utc,
+};
+use lazy_static::lazy_static;
+use log::{
+    LevelFilter,
+    Log,
+    Metadata,
+    Record,
+};
+use tokio::task::JoinHandle;
+
+use crate::models::config_model::Config;
+use crate::utils::config_dir::get_log_folder_path;
+
+tokio::task_local! {
+    static LOG_CONFIG_ID: i64;
+}
+
+struct ConfigLogRoute {
+    level: LevelFilter,
+    file: Option<Mutex<File>>,
+}
+
+lazy_static! {
+    static ref CONFIG_LOG_ROUTES: RwLock<HashMap<i64, ConfigLogRoute>> =
+        RwLock::new(HashMap::new());
+    static ref GLOBAL_LOG_LEVEL: RwLock<LevelFilter> = RwLock::new(LevelFilter::Off);
+}
+
+// the config the running task forwards for, if any
+pub fn current_log_config_id() -> Option<i64> {
+    LOG_CONFIG_ID.try_with(|config_id| *config_id).ok()
+}
+
+pub async fn with_config_logs<F: Future>(config_id: i64, future: F) -> F::Output {
+    LOG_CONFIG_ID.scope(config_id, future).await
+}
+
+// spawned tasks don't inherit the config of the task spawning them, forward
+// tasks go through here so their log lines stay attributed
+pub fn spawn_for_config<F>(config_id: i64, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::spawn(LOG_CONFIG_ID.scope(config_id, future))
+}
+
+pub fn parse_log_level(level: &str) -> Result<LevelFilter, String> {
+    LevelFilter::from_str(level).map_err(|_| format!("Invalid log level: {}", level))
+}
+
+// relative names land in the kftray log folder next to the http logs
+pub fn config_log_path(config: &Config) -> Result<Option<PathBuf>, String> {
+    match config.log_file.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(path) if Path::new(path).is_absolute() => Ok(Some(PathBuf::from(path))),
+        Some(name) => Ok(Some(get_log_folder_path()?.join(name))),
+    }
+}
+
+fn open_log_file(path: &Path) -> Result<File, String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Could not open log file {}: {}", path.display(), e))
+}
+
+fn update_max_level() {
+    let global = *GLOBAL_LOG_LEVEL.read().unwrap();
+    let routed = CONFIG_LOG_ROUTES
+        .read()
+        .unwrap()
+        .values()
+        .map(|route| route.level)
+        .max()
+        .unwrap_or(LevelFilter::Off);
+    log::set_max_level(global.max(routed));
+}
+
+pub fn set_config_log_route(config: &Config) -> Result<(), String> {
+    let Some(config_id) = config.id else {
+        return Ok(());
+    };
+
+    let level = config
+        .log_level
+        .as_deref()
+        .filter(|level| !level.trim().is_empty())
+        .map(parse_log_level)
+        .transpose()?;
+    let path = config_log_path(config)?;
+    if level.is_none() && path.is_none() {
+        remove_config_log_route(Some(config_id));
+        return Ok(());
+    }
+
+    let route = ConfigLogRoute {
+        level: level.unwrap_or(*GLOBAL_LOG_LEVEL.read().unwrap()),
+        file: path
+            .as_deref()
+            .map(open_log_file)
+            .transpose()?
+            .map(Mutex::new),
+    };
+    CONFIG_LOG_ROUTES.write().unwrap().insert(config_id, route);
+    update_max_level();
+    Ok(())
+}
+
+pub fn remove_config_log_route(config_id: Option<i64>) {
+    {
+        let mut routes = CONFIG_LOG_ROUTES.write().unwrap();
+        match config_id {
+            Some(config_id) => {
+                routes.remove(&config_id);
+            }
+            None => routes.clear(),
+        }
+    }
+    update_max_level();
+}
+
+fn route_level(config_id: Option<i64>) -> LevelFilter {
+    config_id
+        .and_then(|config_id| {
+            CONFIG_LOG_ROUTES
+                .read()
+                .unwrap()
+                .get(&config_id)
+                .map(|route| route.level)
+        })
+        .unwrap_or_else(|| *GLOBAL_LOG_LEVEL.read().unwrap())
+}
+
+// sits in front of the app logger: lines logged while forwarding for a config
+// follow that config's level and go to its own file when it has one
+pub struct ConfigLogRouter {
+    inner: Box<dyn Log>,
+}
+
+impl ConfigLogRouter {
+    fn write_to_file(&self, config_id: i64, record: &Record) -> bool {
+        let routes = CONFIG_LOG_ROUTES.read().unwrap();
+        let Some(file) = routes.get(&config_id).and_then(|route| route.file.as_ref()) else {
+            return false;
+        };
+
+        let mut file = file.lock().unwrap();
+        let _ = writeln!(
+            file,
+            "{} {:<5} config_id={} {}: {}",
+            Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            record.level(),
+            config_id,
+            record.target(),
+            record.args()
+        );
+        true
+    }
+}
+
+impl Log for ConfigLogRouter {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= route_level(current_log_config_id())
+    }
+
+    fn log(&self, record: &Record) {
+        let config_id = current_log_config_id();
+        if record.level() > route_level(config_id) {
+            return;
+        }
+
+        let Some(config_id) = config_id else {
+            self.inner.log(record);
+            return;
+        };
+
+        if !self.write_to_file(config_id, record) {
+            self.inner.log(
+                &Record::builder()
+                    .args(format_args!("config_id={} {}", config_id, record.args()))
+                    .level(record.level())
+                    .target(record.target())
+                    .module_path(record.module_path())
+                    .file(record.file())
+                    .line(record.line())
+                    .build(),
+            );
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+        for route in CONFIG_LOG_ROUTES.read().unwrap().values() {
+            if let Some(file) = &route.file {
+                let _ = file.lock().unwrap().flush();
+            }
+        }
+    }
+}
+
+// the inner logger should let everything through, levels are decided here
+pub fn install_config_log_router(inner: Box<dyn Log>, level: LevelFilter) -> Result<(), String> {
+    *GLOBAL_LOG_LEVEL.write().unwrap() = level;
+    let router: &'static ConfigLogRouter = Box::leak(Box::new(ConfigLogRouter { inner }));
+    log::set_logger(router).map_err(|e| e.to_string())?;
+    update_max_level();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_config_log_route() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            id: Some(-165),
+            log_level: Some("debug".to_string()),
+            log_file: Some(dir.path().join("noisy.log").display().to_string()),
+            ..Config::default()
+        };
+        set_config_log_route(&config).unwrap();
+
+        assert_eq!(current_log_config_id(), None);
+        let level = with_config_logs(-165, async {
+            assert_eq!(current_log_config_id(), Some(-165));
+            route_level(current_log_config_id())
+        })
+        .await;
+        assert_eq!(level, LevelFilter::Debug);
+        assert_eq!(
+            spawn_for_config(-165, async { current_log_config_id() })
+                .await
+                .unwrap(),
+            Some(-165)
+        );
+
+        let router = ConfigLogRouter {
+            inner: Box::new(log::logger()),
+        };
+        let record = Record::builder()
+            .args(format_args!("retrying upstream"))
+            .level(log::Level::Debug)
+            .target("kftray_portforward")
+            .build();
+        assert!(router.write_to_file(-165, &record));
+        router.flush();
+        let written = std::fs::read_to_string(dir.path().join("noisy.log")).unwrap();
+        assert!(written.contains("DEBUG config_id=-165 kftray_portforward: retrying upstream"));
+
+        remove_config_log_route(Some(-165));
+        assert!(!router.write_to_file(-165, &record));
+        assert!(parse_log_level("chatty").is_err());
+    }
+}
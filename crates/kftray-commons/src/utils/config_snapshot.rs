@@ -0,0 +1,271 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+use std::time::{
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+use log::info;
+use serde_json::json;
+use sqlx::{
+    Row,
+    SqlitePool,
+};
+
+use crate::config::read_configs_with_pool;
+use crate::config_history::{
+    config_changes,
+    record_config_history_with_pool,
+    HISTORY_ROLLED_BACK,
+};
+use crate::db::get_db_pool;
+use crate::encryption::{
+    decode_config_data,
+    encode_config_data,
+};
+use crate::models::config_model::Config;
+use crate::models::config_snapshot_model::ConfigSnapshot;
+
+// older snapshots are pruned once there are more than this
+const MAX_CONFIG_SNAPSHOTS: i64 = 20;
+
+async fn snapshot_data_with_pool(snapshot_id: i64, pool: &SqlitePool) -> Result<String, String> {
+    let row = sqlx::query("SELECT data FROM config_snapshots WHERE id = ?1")
+        .bind(snapshot_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No snapshot found with id: {}", snapshot_id))?;
+    let data: String = row.try_get("data").map_err(|e| e.to_string())?;
+    decode_config_data(&data)
+}
+
+pub async fn snapshot_configs(reason: &str) -> Result<Option<i64>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    snapshot_configs_with_pool(reason, &pool).await
+}
+
+// saves the whole config set before a bulk change. nothing is saved for an
+// empty set, and a set that didn't change since the last snapshot reuses it
+pub async fn snapshot_configs_with_pool(
+    reason: &str, pool: &SqlitePool,
+) -> Result<Option<i64>, String> {
+    let mut configs = read_configs_with_pool(pool).await?;
+    if configs.is_empty() {
+        return Ok(None);
+    }
+    configs.sort_by_key(|config| config.id);
+    let data = json!(configs).to_string();
+
+    let latest = sqlx::query("SELECT id FROM config_snapshots ORDER BY id DESC LIMIT 1")
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Some(row) = latest {
+        let latest_id: i64 = row.try_get("id").map_err(|e| e.to_string())?;
+        if snapshot_data_with_pool(latest_id, pool).await? == data {
+            return Ok(Some(latest_id));
+        }
+    }
+
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?
+        .as_secs() as i64;
+    let snapshot_id = sqlx::query(
+        "INSERT INTO config_snapshots (reason, config_count, data, created_at)
+         VALUES (?1, ?2, ?3, ?4)",
+    )
+    .bind(reason)
+    .bind(configs.len() as i64)
+    .bind(encode_config_data(&data)?)
+    .bind(created_at)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .last_insert_rowid();
+
+    sqlx::query(
+        "DELETE FROM config_snapshots WHERE id NOT IN
+            (SELECT id FROM config_snapshots ORDER BY id DESC LIMIT ?1)",
+    )
+    .bind(MAX_CONFIG_SNAPSHOTS)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    info!(
+        "Snapshotted {} config(s) as snapshot {}: {}",
+        configs.len(),
+        snapshot_id,
+        reason
+    );
+    Ok(Some(snapshot_id))
+}
+
+pub async fn list_snapshots() -> Result<Vec<ConfigSnapshot>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    list_snapshots_with_pool(&pool).await
+}
+
+pub async fn list_snapshots_with_pool(pool: &SqlitePool) -> Result<Vec<ConfigSnapshot>, String> {
+    let rows = sqlx::query(
+        "SELECT id, reason, config_count, created_at FROM config_snapshots ORDER BY id DESC",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(ConfigSnapshot {
+                id: row.try_get("id").map_err(|e| e.to_string())?,
+                reason: row.try_get("reason").map_err(|e| e.to_string())?,
+                config_count: row.try_get("config_count").map_err(|e| e.to_string())?,
+                created_at: row.try_get("created_at").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+pub async fn rollback_to(snapshot_id: i64) -> Result<usize, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    rollback_to_with_pool(snapshot_id, &pool).await
+}
+
+// replaces every config with the ones in the snapshot, under their old ids.
+// the current set is snapshotted first so the rollback itself can be undone
+pub async fn rollback_to_with_pool(snapshot_id: i64, pool: &SqlitePool) -> Result<usize, String> {
+    let data = snapshot_data_with_pool(snapshot_id, pool).await?;
+    let configs: Vec<Config> = serde_json::from_str(&data)
+        .map_err(|e| format!("Failed to parse snapshot {}: {}", snapshot_id, e))?;
+
+    snapshot_configs_with_pool(
+        &format!("Before rollback to snapshot {}", snapshot_id),
+        pool,
+    )
+    .await?;
+    let current: HashMap<Option<i64>, Config> = read_configs_with_pool(pool)
+        .await?
+        .into_iter()
+        .map(|config| (config.id, config))
+        .collect();
+
+    let mut transaction = pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query("DELETE FROM configs")
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Failed to clear configs: {}", e))?;
+    for config in &configs {
+        let data = encode_config_data(&json!(config).to_string())?;
+        sqlx::query("INSERT INTO configs (id, data) VALUES (?1, ?2)")
+            .bind(config.id)
+            .bind(data)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| format!("Failed to restore config {:?}: {}", config.id, e))?;
+    }
+    transaction.commit().await.map_err(|e| e.to_string())?;
+
+    let restored: HashSet<Option<i64>> = configs.iter().map(|config| config.id).collect();
+    for config in &configs {
+        let changes = config_changes(current.get(&config.id), Some(config));
+        if !changes.is_empty() {
+            let id = config.id.unwrap_or_default();
+            record_config_history_with_pool(id, HISTORY_ROLLED_BACK, changes, pool).await?;
+        }
+    }
+    for config in current
+        .values()
+        .filter(|config| !restored.contains(&config.id))
+    {
+        let id = config.id.unwrap_or_default();
+        record_config_history_with_pool(
+            id,
+            HISTORY_ROLLED_BACK,
+            config_changes(Some(config), None),
+            pool,
+        )
+        .await?;
+    }
+
+    info!(
+        "Rolled back to snapshot {}, {} config(s) restored",
+        snapshot_id,
+        configs.len()
+    );
+    Ok(configs.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::insert_config_with_pool;
+    use crate::db::create_memory_db_pool;
+
+    #[tokio::test]
+    async fn test_snapshot_and_rollback() {
+        let pool = create_memory_db_pool().await.unwrap();
+        assert_eq!(
+            snapshot_configs_with_pool("empty", &pool).await.unwrap(),
+            None
+        );
+
+        for alias in ["api", "db"] {
+            let config = Config {
+                alias: Some(alias.to_string()),
+                ..Default::default()
+            };
+            insert_config_with_pool(config, &pool).await.unwrap();
+        }
+        let mut before: Vec<Option<i64>> = read_configs_with_pool(&pool)
+            .await
+            .unwrap()
+            .iter()
+            .map(|config| config.id)
+            .collect();
+        before.sort();
+
+        let snapshot_id = snapshot_configs_with_pool("first", &pool).await.unwrap();
+        assert!(snapshot_id.is_some());
+        // nothing changed, so the same snapshot is handed back
+        assert_eq!(
+            snapshot_configs_with_pool("second", &pool).await.unwrap(),
+            snapshot_id
+        );
+
+        for alias in ["cache", "queue"] {
+            let config = Config {
+                alias: Some(alias.to_string()),
+                ..Default::default()
+            };
+            insert_config_with_pool(config, &pool).await.unwrap();
+        }
+        assert_eq!(read_configs_with_pool(&pool).await.unwrap().len(), 4);
+
+        let snapshots = list_snapshots_with_pool(&pool).await.unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].config_count, 2);
+
+        assert_eq!(
+            rollback_to_with_pool(snapshots[0].id, &pool).await.unwrap(),
+            2
+        );
+        let mut restored: Vec<Option<i64>> = read_configs_with_pool(&pool)
+            .await
+            .unwrap()
+            .iter()
+            .map(|config| config.id)
+            .collect();
+        restored.sort();
+        assert_eq!(restored, before);
+
+        // the rollback saved the four configs it replaced
+        let snapshots = list_snapshots_with_pool(&pool).await.unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[0].config_count, 4);
+        assert!(rollback_to_with_pool(999, &pool).await.is_err());
+    }
+}
@@ -1,19 +1,40 @@
 use log::error;
-use sqlx::Row;
+use sqlx::{
+    Row,
+    SqlitePool,
+};
 
 use crate::db::get_db_pool;
+use crate::models::config_model::Config;
 use crate::models::config_state_model::ConfigState;
+use crate::utils::session::current_session_id;
 
 pub async fn update_config_state(config_state: &ConfigState) -> Result<(), String> {
     let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    update_config_state_with_pool(config_state, &pool).await
+}
+
+pub async fn update_config_state_with_pool(
+    config_state: &ConfigState, pool: &SqlitePool,
+) -> Result<(), String> {
     let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
 
-    sqlx::query("UPDATE config_state SET is_running = ?1 WHERE config_id = ?2")
-        .bind(config_state.is_running)
-        .bind(config_state.config_id)
-        .execute(&mut *conn)
-        .await
-        .map_err(|e| e.to_string())?;
+    // running rows are claimed by this session so other instances can tell
+    // whose forwards they are, and keep the port that was actually bound
+    sqlx::query(
+        "UPDATE config_state
+         SET is_running = ?1,
+             session_id = CASE WHEN ?1 THEN ?3 ELSE NULL END,
+             local_port = CASE WHEN ?1 THEN ?4 ELSE NULL END
+         WHERE config_id = ?2",
+    )
+    .bind(config_state.is_running)
+    .bind(config_state.config_id)
+    .bind(current_session_id())
+    .bind(config_state.local_port)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?;
 
     Ok(())
 }
@@ -23,13 +44,18 @@ pub async fn read_config_states() -> Result<Vec<ConfigState>, sqlx::Error> {
         error!("Failed to get database pool: {}", e);
         sqlx::Error::Configuration(e.into())
     })?;
+    read_config_states_with_pool(&pool).await
+}
 
+pub async fn read_config_states_with_pool(
+    pool: &SqlitePool,
+) -> Result<Vec<ConfigState>, sqlx::Error> {
     let mut conn = pool.acquire().await.map_err(|e| {
         error!("Failed to acquire database connection: {}", e);
         e
     })?;
 
-    let rows = sqlx::query("SELECT id, config_id, is_running FROM config_state")
+    let rows = sqlx::query("SELECT id, config_id, is_running, local_port FROM config_state")
         .fetch_all(&mut *conn)
         .await
         .map_err(|e| {
@@ -49,10 +75,15 @@ pub async fn read_config_states() -> Result<Vec<ConfigState>, sqlx::Error> {
                 error!("Failed to get is_running: {}", e);
                 e
             })?;
+            let local_port: Option<u16> = row.try_get("local_port").map_err(|e| {
+                error!("Failed to get local_port: {}", e);
+                e
+            })?;
             Ok(ConfigState {
                 id,
                 config_id,
                 is_running,
+                local_port,
             })
         })
         .collect::<Result<Vec<_>, sqlx::Error>>()?;
@@ -66,3 +97,37 @@ pub async fn get_configs_state() -> Result<Vec<ConfigState>, String> {
         e.to_string()
     })
 }
+
+// the port a running forward is really bound to, which differs from the
+// configured one when that is 0
+pub fn bound_local_port(config: &Config, states: &[ConfigState]) -> Option<u16> {
+    states
+        .iter()
+        .find(|state| state.is_running && Some(state.config_id) == config.id)
+        .and_then(|state| state.local_port)
+        .or(config.local_port)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bound_local_port() {
+        let config = Config {
+            id: Some(1),
+            local_port: Some(0),
+            ..Config::default()
+        };
+        let mut states = vec![ConfigState {
+            id: Some(1),
+            config_id: 1,
+            is_running: true,
+            local_port: Some(49152),
+        }];
+        assert_eq!(bound_local_port(&config, &states), Some(49152));
+
+        states[0].is_running = false;
+        assert_eq!(bound_local_port(&config, &states), Some(0));
+    }
+}
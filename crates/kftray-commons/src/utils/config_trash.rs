@@ -0,0 +1,249 @@
+use std::time::{
+    Duration,
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+use log::info;
+use sqlx::{
+    Row,
+    SqliteConnection,
+    SqlitePool,
+};
+
+use crate::config_history::{
+    record_config_history_with_pool,
+    HISTORY_RESTORED,
+};
+use crate::db::get_db_pool;
+use crate::encryption::decode_config_data;
+use crate::models::config_model::Config;
+use crate::models::config_trash_model::TrashedConfig;
+use crate::settings::current_settings;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+fn now_secs() -> Result<i64, String> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?
+        .as_secs() as i64)
+}
+
+// copies the configs into the trash ahead of deleting them, all of them when
+// no id is given. a trashed config whose id was reused is replaced
+pub(crate) async fn move_to_trash(
+    ids: Option<&[i64]>, conn: &mut SqliteConnection,
+) -> Result<(), String> {
+    let deleted_at = now_secs()?;
+    match ids {
+        Some(ids) => {
+            for id in ids {
+                sqlx::query(
+                    "INSERT OR REPLACE INTO config_trash (id, data, deleted_at)
+                     SELECT id, data, ?2 FROM configs WHERE id = ?1",
+                )
+                .bind(id)
+                .bind(deleted_at)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| format!("Failed to move config {} to the trash: {}", id, e))?;
+            }
+        }
+        None => {
+            sqlx::query(
+                "INSERT OR REPLACE INTO config_trash (id, data, deleted_at)
+                 SELECT id, data, ?1 FROM configs",
+            )
+            .bind(deleted_at)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to move configs to the trash: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn list_trash() -> Result<Vec<TrashedConfig>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    list_trash_with_pool(&pool).await
+}
+
+pub async fn list_trash_with_pool(pool: &SqlitePool) -> Result<Vec<TrashedConfig>, String> {
+    purge_expired_trash_with_pool(pool).await?;
+
+    let rows =
+        sqlx::query("SELECT id, data, deleted_at FROM config_trash ORDER BY deleted_at DESC")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            let id: i64 = row.try_get("id").map_err(|e| e.to_string())?;
+            let data: String = row.try_get("data").map_err(|e| e.to_string())?;
+            let mut config: Config = serde_json::from_str(&decode_config_data(&data)?)
+                .map_err(|e| format!("Failed to parse trashed config {}: {}", id, e))?;
+            config.id = Some(id);
+            Ok(TrashedConfig {
+                config,
+                deleted_at: row.try_get("deleted_at").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+pub async fn restore_config(id: i64) -> Result<i64, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    restore_config_with_pool(id, &pool).await
+}
+
+// puts the config back under its old id, or under a new one when that id was
+// taken since. returns the id it got
+pub async fn restore_config_with_pool(id: i64, pool: &SqlitePool) -> Result<i64, String> {
+    let mut transaction = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let row = sqlx::query("SELECT data FROM config_trash WHERE id = ?1")
+        .bind(id)
+        .fetch_optional(&mut *transaction)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No deleted config found with id: {}", id))?;
+    let data: String = row.try_get("data").map_err(|e| e.to_string())?;
+
+    let taken = sqlx::query("SELECT 1 FROM configs WHERE id = ?1")
+        .bind(id)
+        .fetch_optional(&mut *transaction)
+        .await
+        .map_err(|e| e.to_string())?
+        .is_some();
+    let restored_id = if taken {
+        sqlx::query("INSERT INTO configs (data) VALUES (?1)")
+            .bind(&data)
+            .execute(&mut *transaction)
+            .await
+    } else {
+        sqlx::query("INSERT INTO configs (id, data) VALUES (?1, ?2)")
+            .bind(id)
+            .bind(&data)
+            .execute(&mut *transaction)
+            .await
+    }
+    .map_err(|e| format!("Failed to restore config {}: {}", id, e))?
+    .last_insert_rowid();
+
+    sqlx::query("DELETE FROM config_trash WHERE id = ?1")
+        .bind(id)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| e.to_string())?;
+    transaction.commit().await.map_err(|e| e.to_string())?;
+    record_config_history_with_pool(restored_id, HISTORY_RESTORED, Vec::new(), pool).await?;
+
+    info!("Restored config {} as {}", id, restored_id);
+    Ok(restored_id)
+}
+
+pub async fn purge_trash() -> Result<u64, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    purge_trash_with_pool(&pool).await
+}
+
+pub async fn purge_trash_with_pool(pool: &SqlitePool) -> Result<u64, String> {
+    let purged = sqlx::query("DELETE FROM config_trash")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to empty the trash: {}", e))?
+        .rows_affected();
+    info!("Emptied the trash, {} config(s) purged", purged);
+    Ok(purged)
+}
+
+pub async fn purge_expired_trash() -> Result<u64, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    purge_expired_trash_with_pool(&pool).await
+}
+
+// drops what was deleted longer ago than the retention setting
+pub async fn purge_expired_trash_with_pool(pool: &SqlitePool) -> Result<u64, String> {
+    let retention = Duration::from_secs(current_settings().trash_retention_days * SECONDS_PER_DAY);
+    purge_trash_older_than(retention, pool).await
+}
+
+async fn purge_trash_older_than(retention: Duration, pool: &SqlitePool) -> Result<u64, String> {
+    let cutoff = now_secs()? - retention.as_secs() as i64;
+    let purged = sqlx::query("DELETE FROM config_trash WHERE deleted_at < ?1")
+        .bind(cutoff)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .rows_affected();
+    if purged > 0 {
+        info!("Purged {} config(s) past their trash retention", purged);
+    }
+    Ok(purged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        delete_config_with_pool,
+        delete_configs_with_pool,
+        get_config_with_pool,
+        insert_config_with_pool,
+        read_configs_with_pool,
+    };
+    use crate::db::create_memory_db_pool;
+
+    #[tokio::test]
+    async fn test_trash_restore_and_purge() {
+        let pool = create_memory_db_pool().await.unwrap();
+        for alias in ["api", "db", "cache"] {
+            let config = Config {
+                alias: Some(alias.to_string()),
+                ..Default::default()
+            };
+            insert_config_with_pool(config, &pool).await.unwrap();
+        }
+        let mut ids: Vec<i64> = read_configs_with_pool(&pool)
+            .await
+            .unwrap()
+            .iter()
+            .filter_map(|config| config.id)
+            .collect();
+        ids.sort();
+
+        delete_config_with_pool(ids[0], &pool).await.unwrap();
+        delete_configs_with_pool(vec![ids[1], ids[2]], &pool)
+            .await
+            .unwrap();
+        assert!(read_configs_with_pool(&pool).await.unwrap().is_empty());
+        assert_eq!(list_trash_with_pool(&pool).await.unwrap().len(), 3);
+
+        assert_eq!(
+            restore_config_with_pool(ids[0], &pool).await.unwrap(),
+            ids[0]
+        );
+        let restored = get_config_with_pool(ids[0], &pool).await.unwrap();
+        assert_eq!(restored.alias.as_deref(), Some("api"));
+        assert!(restore_config_with_pool(ids[0], &pool).await.is_err());
+
+        // the next insert reuses the id after the highest one in use
+        let config = Config {
+            alias: Some("new".to_string()),
+            ..Default::default()
+        };
+        insert_config_with_pool(config, &pool).await.unwrap();
+        assert!(get_config_with_pool(ids[1], &pool).await.is_ok());
+        assert_ne!(
+            restore_config_with_pool(ids[1], &pool).await.unwrap(),
+            ids[1]
+        );
+
+        let day = Duration::from_secs(SECONDS_PER_DAY);
+        assert_eq!(purge_trash_older_than(day, &pool).await.unwrap(), 0);
+        assert_eq!(purge_trash_with_pool(&pool).await.unwrap(), 1);
+        assert!(list_trash_with_pool(&pool).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,1162 @@
+use std::net::{
+    IpAddr,
+    SocketAddr,
+    TcpListener,
+    UdpSocket,
+};
+use std::path::Path;
+
+use crate::models::config_model::Config;
+use crate::models::config_state_model::ConfigState;
+use crate::models::diagnostic::Diagnostic;
+use crate::models::mock_rule_model::MockAction;
+use crate::utils::config_display::{
+    parse_config_color,
+    validate_config_icon,
+};
+use crate::utils::config_log::parse_log_level;
+use crate::utils::settings::current_settings;
+
+const WORKLOAD_TYPES: [&str; 3] = ["service", "pod", "proxy"];
+const PROTOCOLS: [&str; 2] = ["tcp", "udp"];
+const READINESS_CHECKS: [&str; 3] = ["tcp", "http", "grpc"];
+const APP_PROTOCOLS: [&str; 5] = ["postgres", "mysql", "redis", "kafka", "grpc"];
+const TRANSPORTS: [&str; 3] = ["auto", "websocket", "spdy"];
+const CONNECTION_OVERFLOWS: [&str; 2] = ["reject", "queue"];
+
+fn is_blank(value: &Option<String>) -> bool {
+    value.as_deref().is_none_or(|v| v.trim().is_empty())
+}
+
+fn check_required_fields(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    if config.context.trim().is_empty() {
+        diagnostics.push(Diagnostic::error("context", "Context is required"));
+    }
+
+    if config.namespace.trim().is_empty() {
+        diagnostics.push(Diagnostic::error("namespace", "Namespace is required"));
+    }
+
+    match config.workload_type.as_deref() {
+        Some("service") if is_blank(&config.service) => {
+            diagnostics.push(Diagnostic::error(
+                "service",
+                "Service is required for service workloads",
+            ));
+        }
+        Some("pod") if is_blank(&config.target) => {
+            diagnostics.push(Diagnostic::error(
+                "target",
+                "Pod label selector is required for pod workloads",
+            ));
+        }
+        Some("proxy") if is_blank(&config.remote_address) => {
+            diagnostics.push(Diagnostic::error(
+                "remote_address",
+                "Remote address is required for proxy workloads",
+            ));
+        }
+        Some(workload_type) if !WORKLOAD_TYPES.contains(&workload_type) => {
+            diagnostics.push(Diagnostic::error(
+                "workload_type",
+                format!(
+                    "Unknown workload type '{}', expected one of: {}",
+                    workload_type,
+                    WORKLOAD_TYPES.join(", ")
+                ),
+            ));
+        }
+        None => {
+            diagnostics.push(Diagnostic::error(
+                "workload_type",
+                "Workload type is required",
+            ));
+        }
+        _ => {}
+    }
+
+    if !PROTOCOLS.contains(&config.protocol.as_str()) {
+        diagnostics.push(Diagnostic::error(
+            "protocol",
+            format!(
+                "Unknown protocol '{}', expected one of: {}",
+                config.protocol,
+                PROTOCOLS.join(", ")
+            ),
+        ));
+    }
+
+    if config.remote_port.unwrap_or_default() == 0 {
+        diagnostics.push(Diagnostic::error("remote_port", "Remote port is required"));
+    }
+}
+
+fn check_readiness(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    let Some(check) = config.readiness_check.as_deref() else {
+        return;
+    };
+
+    if !READINESS_CHECKS.contains(&check) {
+        diagnostics.push(Diagnostic::error(
+            "readiness_check",
+            format!(
+                "Unknown readiness check '{}', expected one of: {}",
+                check,
+                READINESS_CHECKS.join(", ")
+            ),
+        ));
+        return;
+    }
+
+    if config.protocol == "udp" {
+        diagnostics.push(Diagnostic::warning(
+            "readiness_check",
+            "Readiness checks are not supported for UDP forwards and will be skipped",
+        ));
+    }
+
+    if check == "http"
+        && config
+            .readiness_path
+            .as_deref()
+            .is_some_and(|path| !path.starts_with('/'))
+    {
+        diagnostics.push(Diagnostic::error(
+            "readiness_path",
+            "Readiness path must start with '/'",
+        ));
+    }
+}
+
+fn check_app_protocol(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    let Some(app_protocol) = config.app_protocol.as_deref() else {
+        return;
+    };
+
+    if !APP_PROTOCOLS.contains(&app_protocol) {
+        diagnostics.push(Diagnostic::error(
+            "app_protocol",
+            format!(
+                "Unknown application protocol '{}', expected one of: {}",
+                app_protocol,
+                APP_PROTOCOLS.join(", ")
+            ),
+        ));
+    } else if config.protocol == "udp" {
+        diagnostics.push(Diagnostic::warning(
+            "app_protocol",
+            "Application protocols only apply to TCP forwards and will be ignored",
+        ));
+    }
+
+    if config.grpc_health_services.is_some() && app_protocol != "grpc" {
+        diagnostics.push(Diagnostic::warning(
+            "grpc_health_services",
+            "gRPC health services are only probed when the application protocol is grpc",
+        ));
+    }
+}
+
+fn check_transport(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    let Some(transport) = config.transport.as_deref() else {
+        return;
+    };
+
+    if !TRANSPORTS.contains(&transport) {
+        diagnostics.push(Diagnostic::error(
+            "transport",
+            format!(
+                "Unknown transport '{}', expected one of: {}",
+                transport,
+                TRANSPORTS.join(", ")
+            ),
+        ));
+    }
+}
+
+fn check_connection_limit(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    if config.max_connections == Some(0) {
+        diagnostics.push(Diagnostic::error(
+            "max_connections",
+            "Max connections must be at least 1",
+        ));
+    }
+
+    if let Some(overflow) = config.connection_overflow.as_deref() {
+        if !CONNECTION_OVERFLOWS.contains(&overflow) {
+            diagnostics.push(Diagnostic::error(
+                "connection_overflow",
+                format!(
+                    "Unknown connection overflow '{}', expected one of: {}",
+                    overflow,
+                    CONNECTION_OVERFLOWS.join(", ")
+                ),
+            ));
+        } else if config.max_connections.is_none() {
+            diagnostics.push(Diagnostic::warning(
+                "connection_overflow",
+                "Connection overflow is ignored without max_connections",
+            ));
+        }
+    }
+
+    if config.max_connections.is_some() && config.protocol == "udp" {
+        diagnostics.push(Diagnostic::warning(
+            "max_connections",
+            "Connection limits only apply to TCP forwards and will be ignored",
+        ));
+    }
+}
+
+fn check_logging(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    if let Some(level) = config.log_level.as_deref().filter(|l| !l.trim().is_empty()) {
+        if let Err(e) = parse_log_level(level) {
+            diagnostics.push(Diagnostic::error("log_level", e));
+        }
+    }
+}
+
+fn check_presentation(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    if let Some(color) = config.color.as_deref().filter(|c| !c.trim().is_empty()) {
+        if parse_config_color(color).is_none() {
+            diagnostics.push(Diagnostic::error(
+                "color",
+                format!(
+                    "Color '{}' must be a color name like red or green, or a #rrggbb value",
+                    color
+                ),
+            ));
+        }
+    }
+    if let Some(icon) = config.icon.as_deref() {
+        if let Err(e) = validate_config_icon(icon.trim()) {
+            diagnostics.push(Diagnostic::error("icon", e));
+        }
+    }
+}
+
+fn check_ssm(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    let has_instance = config
+        .ssm_instance_id
+        .as_deref()
+        .is_some_and(|id| !id.is_empty());
+    if has_instance {
+        return;
+    }
+
+    for (field, value) in [
+        ("ssm_profile", &config.ssm_profile),
+        ("ssm_region", &config.ssm_region),
+    ] {
+        if value.as_deref().is_some_and(|v| !v.is_empty()) {
+            diagnostics.push(Diagnostic::warning(
+                field,
+                format!("{} is ignored without ssm_instance_id", field),
+            ));
+        }
+    }
+}
+
+fn check_ssh_mirror(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    if is_blank(&config.ssh_mirror_host) {
+        for (field, value) in [
+            ("ssh_mirror_user", &config.ssh_mirror_user),
+            ("ssh_mirror_key", &config.ssh_mirror_key),
+        ] {
+            if !is_blank(value) {
+                diagnostics.push(Diagnostic::warning(
+                    field,
+                    format!("{} is ignored without ssh_mirror_host", field),
+                ));
+            }
+        }
+        return;
+    }
+
+    if config.protocol == "udp" {
+        diagnostics.push(Diagnostic::error(
+            "ssh_mirror_host",
+            "SSH remote forwarding only carries TCP",
+        ));
+    }
+    if let Some(key) = config
+        .ssh_mirror_key
+        .as_deref()
+        .filter(|k| !k.trim().is_empty())
+    {
+        if !Path::new(key).is_file() {
+            diagnostics.push(Diagnostic::error(
+                "ssh_mirror_key",
+                format!("SSH key not found: {}", key),
+            ));
+        }
+    }
+}
+
+fn check_fan_out(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    if !config.fan_out.unwrap_or_default() {
+        return;
+    }
+
+    if !matches!(
+        config.workload_type.as_deref(),
+        Some("service") | Some("pod")
+    ) {
+        diagnostics.push(Diagnostic::error(
+            "fan_out",
+            "Fan-out is only supported for service and pod workloads",
+        ));
+    } else if config.protocol == "udp" {
+        diagnostics.push(Diagnostic::warning(
+            "fan_out",
+            "Fan-out only applies to TCP forwards and will be ignored",
+        ));
+    }
+
+    if config.fan_out_base_port.is_some() && config.fan_out_base_port == config.local_port {
+        diagnostics.push(Diagnostic::error(
+            "fan_out_base_port",
+            "Fan-out base port must differ from the local port",
+        ));
+    }
+}
+
+fn check_traffic_split(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    if is_blank(&config.split_target) {
+        for (field, set) in [
+            ("split_percent", config.split_percent.is_some()),
+            ("split_remote_port", config.split_remote_port.is_some()),
+        ] {
+            if set {
+                diagnostics.push(Diagnostic::warning(
+                    field,
+                    format!("{} is ignored without split_target", field),
+                ));
+            }
+        }
+        return;
+    }
+
+    if !matches!(
+        config.workload_type.as_deref(),
+        Some("service") | Some("pod")
+    ) || config.protocol != "tcp"
+    {
+        diagnostics.push(Diagnostic::error(
+            "split_target",
+            "Traffic can only be split for TCP service and pod workloads",
+        ));
+    }
+    match config.split_percent {
+        Some(percent) if percent > 100 => diagnostics.push(Diagnostic::error(
+            "split_percent",
+            "Split percent must be between 0 and 100",
+        )),
+        None | Some(0) => diagnostics.push(Diagnostic::warning(
+            "split_percent",
+            "No connections go to the split target at 0 percent",
+        )),
+        _ => {}
+    }
+}
+
+fn check_mock_rules(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    let rules = config.mock_rules.as_deref().unwrap_or_default();
+    if rules.is_empty() {
+        return;
+    }
+
+    if !matches!(
+        config.workload_type.as_deref(),
+        Some("service") | Some("pod")
+    ) || config.protocol != "tcp"
+    {
+        diagnostics.push(Diagnostic::error(
+            "mock_rules",
+            "Mock rules only apply to TCP service and pod workloads",
+        ));
+    }
+
+    for (index, rule) in rules.iter().enumerate() {
+        let number = index + 1;
+        if rule.header.as_deref().is_some_and(|header| {
+            header
+                .split(':')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .is_empty()
+        }) {
+            diagnostics.push(Diagnostic::error(
+                "mock_rules",
+                format!("Rule {} matches a header without a name", number),
+            ));
+        }
+        if let MockAction::Respond { status, .. } = &rule.action {
+            if !(100..=599).contains(status) {
+                diagnostics.push(Diagnostic::error(
+                    "mock_rules",
+                    format!("Rule {} responds with invalid status {}", number, status),
+                ));
+            }
+        }
+        let matches_all = rule.method.is_none() && rule.path.is_none() && rule.header.is_none();
+        if matches_all && number < rules.len() {
+            diagnostics.push(Diagnostic::warning(
+                "mock_rules",
+                format!(
+                    "Rule {} matches every request, the rules after it never apply",
+                    number
+                ),
+            ));
+        }
+    }
+}
+
+fn check_traffic_script(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    if is_blank(&config.traffic_script) {
+        return;
+    }
+
+    if !matches!(
+        config.workload_type.as_deref(),
+        Some("service") | Some("pod")
+    ) || config.protocol != "tcp"
+    {
+        diagnostics.push(Diagnostic::error(
+            "traffic_script",
+            "Traffic scripts only apply to TCP service and pod workloads",
+        ));
+    }
+
+    let path = config.traffic_script.as_deref().unwrap_or_default().trim();
+    if !Path::new(path).is_file() {
+        diagnostics.push(Diagnostic::error(
+            "traffic_script",
+            format!("Traffic script not found: {}", path),
+        ));
+    }
+}
+
+fn check_openapi_spec(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    if is_blank(&config.openapi_spec) {
+        return;
+    }
+
+    if !matches!(
+        config.workload_type.as_deref(),
+        Some("service") | Some("pod")
+    ) || config.protocol != "tcp"
+    {
+        diagnostics.push(Diagnostic::error(
+            "openapi_spec",
+            "OpenAPI specs only apply to TCP service and pod workloads",
+        ));
+    }
+
+    let spec = config.openapi_spec.as_deref().unwrap_or_default().trim();
+    let is_url = spec.starts_with("http://") || spec.starts_with("https://");
+    if !is_url && !Path::new(spec).is_file() {
+        diagnostics.push(Diagnostic::error(
+            "openapi_spec",
+            format!("OpenAPI spec not found: {}", spec),
+        ));
+    }
+}
+
+fn check_kubeconfig(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    let Some(kubeconfig) = config.kubeconfig.as_deref() else {
+        return;
+    };
+
+    if kubeconfig == "default" || kubeconfig.is_empty() {
+        return;
+    }
+
+    for path in kubeconfig.split(':').filter(|p| !p.is_empty()) {
+        if !Path::new(path).is_file() {
+            diagnostics.push(Diagnostic::error(
+                "kubeconfig",
+                format!("Kubeconfig file not found: {}", path),
+            ));
+        }
+    }
+}
+
+fn local_ip(
+    config: &Config, default_address: &str, diagnostics: &mut Vec<Diagnostic>,
+) -> Option<IpAddr> {
+    let local_address = config.local_address.as_deref().unwrap_or(default_address);
+
+    match local_address.parse::<IpAddr>() {
+        Ok(ip) => Some(ip),
+        Err(_) => {
+            diagnostics.push(Diagnostic::error(
+                "local_address",
+                format!("Invalid local address: {}", local_address),
+            ));
+            None
+        }
+    }
+}
+
+// the port a running config is bound to is taken by the config itself
+fn runs_on_port(config: &Config, port: u16, states: &[ConfigState]) -> bool {
+    states.iter().any(|state| {
+        state.is_running
+            && Some(state.config_id) == config.id
+            && state.local_port.or(config.local_port) == Some(port)
+    })
+}
+
+fn check_local_port(
+    config: &Config, ip: IpAddr, states: &[ConfigState], diagnostics: &mut Vec<Diagnostic>,
+) {
+    let port = match config.local_port {
+        Some(port) if port != 0 => port,
+        _ => {
+            diagnostics.push(Diagnostic::info(
+                "local_port",
+                "No local port set, a random port will be picked at start",
+            ));
+            return;
+        }
+    };
+    if runs_on_port(config, port, states) {
+        return;
+    }
+
+    let addr = SocketAddr::new(ip, port);
+    let available = if config.protocol == "udp" {
+        UdpSocket::bind(addr).is_ok()
+    } else {
+        TcpListener::bind(addr).is_ok()
+    };
+
+    if !available {
+        diagnostics.push(Diagnostic::warning(
+            "local_port",
+            format!("Local port {} is already in use on {}", port, ip),
+        ));
+    }
+}
+
+// configs without a local address bind the default one from the settings
+fn check_collisions(
+    config: &Config, existing: &[Config], default_address: &str, diagnostics: &mut Vec<Diagnostic>,
+) {
+    let others = existing
+        .iter()
+        .filter(|other| other.id.is_none() || other.id != config.id);
+
+    for other in others {
+        let other_id = other.id.unwrap_or_default();
+
+        if !is_blank(&config.alias)
+            && config.alias == other.alias
+            && config.domain_enabled.unwrap_or_default()
+            && other.domain_enabled.unwrap_or_default()
+        {
+            diagnostics.push(Diagnostic::error(
+                "alias",
+                format!(
+                    "Alias '{}' is already used as a domain by config {}",
+                    config.alias.clone().unwrap_or_default(),
+                    other_id
+                ),
+            ));
+        }
+
+        let same_local_port =
+            config.local_port.unwrap_or_default() != 0 && config.local_port == other.local_port;
+        let same_local_address = config.local_address.as_deref().unwrap_or(default_address)
+            == other.local_address.as_deref().unwrap_or(default_address);
+
+        if same_local_port && same_local_address && config.protocol == other.protocol {
+            diagnostics.push(Diagnostic::warning(
+                "local_port",
+                format!(
+                    "Local port {} is also used by config {}, they cannot run at the same time",
+                    config.local_port.unwrap_or_default(),
+                    other_id
+                ),
+            ));
+        }
+    }
+}
+
+pub fn validate_config_fields(
+    config: &Config, existing: &[Config], states: &[ConfigState],
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    check_required_fields(config, &mut diagnostics);
+    check_kubeconfig(config, &mut diagnostics);
+    check_readiness(config, &mut diagnostics);
+    check_app_protocol(config, &mut diagnostics);
+    check_transport(config, &mut diagnostics);
+    check_connection_limit(config, &mut diagnostics);
+    check_logging(config, &mut diagnostics);
+    check_presentation(config, &mut diagnostics);
+    check_ssm(config, &mut diagnostics);
+    check_ssh_mirror(config, &mut diagnostics);
+    check_fan_out(config, &mut diagnostics);
+    check_traffic_split(config, &mut diagnostics);
+    check_mock_rules(config, &mut diagnostics);
+    check_traffic_script(config, &mut diagnostics);
+    check_openapi_spec(config, &mut diagnostics);
+
+    let default_address = current_settings().default_local_address;
+    if let Some(ip) = local_ip(config, &default_address, &mut diagnostics) {
+        check_local_port(config, ip, states, &mut diagnostics);
+    }
+
+    check_collisions(config, existing, &default_address, &mut diagnostics);
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::models::diagnostic::Severity;
+    use crate::models::mock_rule_model::MockRule;
+
+    fn base() -> Config {
+        Config {
+            id: Some(1),
+            context: "kind".to_string(),
+            namespace: "shop".to_string(),
+            workload_type: Some("service".to_string()),
+            service: Some("api".to_string()),
+            protocol: "tcp".to_string(),
+            remote_port: Some(8080),
+            local_port: Some(0),
+            ..Default::default()
+        }
+    }
+
+    fn flagged(config: &Config) -> Vec<(String, Severity)> {
+        validate_config_fields(config, &[], &[])
+            .into_iter()
+            .map(|diagnostic| (diagnostic.field, diagnostic.severity))
+            .collect()
+    }
+
+    fn has(config: Config, field: &str, severity: Severity) -> bool {
+        flagged(&config).contains(&(field.to_string(), severity))
+    }
+
+    fn respond(status: u16) -> MockAction {
+        MockAction::Respond {
+            status,
+            headers: BTreeMap::new(),
+            body: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_required_fields() {
+        assert_eq!(
+            flagged(&base()),
+            vec![("local_port".to_string(), Severity::Info)]
+        );
+
+        let pod = || Config {
+            workload_type: Some("pod".to_string()),
+            ..base()
+        };
+        let proxy = || Config {
+            workload_type: Some("proxy".to_string()),
+            ..base()
+        };
+        let error = Severity::Error;
+        assert!(has(
+            Config {
+                context: " ".to_string(),
+                ..base()
+            },
+            "context",
+            error
+        ));
+        assert!(has(
+            Config {
+                namespace: String::new(),
+                ..base()
+            },
+            "namespace",
+            error
+        ));
+        assert!(has(
+            Config {
+                service: None,
+                ..base()
+            },
+            "service",
+            error
+        ));
+        assert!(has(
+            Config {
+                target: None,
+                ..pod()
+            },
+            "target",
+            error
+        ));
+        assert!(has(
+            Config {
+                remote_address: Some(String::new()),
+                ..proxy()
+            },
+            "remote_address",
+            error
+        ));
+        assert!(has(
+            Config {
+                workload_type: Some("deployment".to_string()),
+                ..base()
+            },
+            "workload_type",
+            error
+        ));
+        assert!(has(
+            Config {
+                workload_type: None,
+                ..base()
+            },
+            "workload_type",
+            error
+        ));
+        assert!(has(
+            Config {
+                protocol: "sctp".to_string(),
+                ..base()
+            },
+            "protocol",
+            error
+        ));
+        assert!(has(
+            Config {
+                remote_port: None,
+                ..base()
+            },
+            "remote_port",
+            error
+        ));
+        assert!(has(
+            Config {
+                kubeconfig: Some("/no/such/kubeconfig".to_string()),
+                ..base()
+            },
+            "kubeconfig",
+            error
+        ));
+        assert!(has(
+            Config {
+                local_address: Some("localhost".to_string()),
+                ..base()
+            },
+            "local_address",
+            error
+        ));
+    }
+
+    #[test]
+    fn test_protocol_options() {
+        let udp = || Config {
+            protocol: "udp".to_string(),
+            ..base()
+        };
+        let (error, warning) = (Severity::Error, Severity::Warning);
+        assert!(has(
+            Config {
+                readiness_check: Some("icmp".to_string()),
+                ..base()
+            },
+            "readiness_check",
+            error
+        ));
+        assert!(has(
+            Config {
+                readiness_check: Some("tcp".to_string()),
+                ..udp()
+            },
+            "readiness_check",
+            warning
+        ));
+        assert!(has(
+            Config {
+                readiness_check: Some("http".to_string()),
+                readiness_path: Some("healthz".to_string()),
+                ..base()
+            },
+            "readiness_path",
+            error
+        ));
+        assert!(has(
+            Config {
+                app_protocol: Some("mongodb".to_string()),
+                ..base()
+            },
+            "app_protocol",
+            error
+        ));
+        assert!(has(
+            Config {
+                app_protocol: Some("redis".to_string()),
+                ..udp()
+            },
+            "app_protocol",
+            warning
+        ));
+        assert!(has(
+            Config {
+                app_protocol: Some("redis".to_string()),
+                grpc_health_services: Some(vec!["api".to_string()]),
+                ..base()
+            },
+            "grpc_health_services",
+            warning
+        ));
+        assert!(has(
+            Config {
+                transport: Some("quic".to_string()),
+                ..base()
+            },
+            "transport",
+            error
+        ));
+        assert!(has(
+            Config {
+                max_connections: Some(0),
+                ..base()
+            },
+            "max_connections",
+            error
+        ));
+        assert!(has(
+            Config {
+                max_connections: Some(4),
+                connection_overflow: Some("drop".to_string()),
+                ..base()
+            },
+            "connection_overflow",
+            error
+        ));
+        assert!(has(
+            Config {
+                connection_overflow: Some("queue".to_string()),
+                ..base()
+            },
+            "connection_overflow",
+            warning
+        ));
+        assert!(has(
+            Config {
+                max_connections: Some(4),
+                ..udp()
+            },
+            "max_connections",
+            warning
+        ));
+    }
+
+    #[test]
+    fn test_presentation_and_remote_access() {
+        let (error, warning) = (Severity::Error, Severity::Warning);
+        assert!(has(
+            Config {
+                log_level: Some("loud".to_string()),
+                ..base()
+            },
+            "log_level",
+            error
+        ));
+        assert!(has(
+            Config {
+                color: Some("sparkly".to_string()),
+                ..base()
+            },
+            "color",
+            error
+        ));
+        assert!(has(
+            Config {
+                icon: Some("a very long icon".repeat(4)),
+                ..base()
+            },
+            "icon",
+            error
+        ));
+        assert!(has(
+            Config {
+                ssm_region: Some("eu-west-1".to_string()),
+                ..base()
+            },
+            "ssm_region",
+            warning
+        ));
+        assert!(has(
+            Config {
+                ssh_mirror_user: Some("deploy".to_string()),
+                ..base()
+            },
+            "ssh_mirror_user",
+            warning
+        ));
+        assert!(has(
+            Config {
+                ssh_mirror_host: Some("bastion".to_string()),
+                protocol: "udp".to_string(),
+                ..base()
+            },
+            "ssh_mirror_host",
+            error
+        ));
+        assert!(has(
+            Config {
+                ssh_mirror_host: Some("bastion".to_string()),
+                ssh_mirror_key: Some("/no/such/key".to_string()),
+                ..base()
+            },
+            "ssh_mirror_key",
+            error
+        ));
+    }
+
+    #[test]
+    fn test_traffic_features() {
+        let proxy = || Config {
+            workload_type: Some("proxy".to_string()),
+            ..base()
+        };
+        let (error, warning) = (Severity::Error, Severity::Warning);
+        assert!(has(
+            Config {
+                fan_out: Some(true),
+                ..proxy()
+            },
+            "fan_out",
+            error
+        ));
+        assert!(has(
+            Config {
+                fan_out: Some(true),
+                protocol: "udp".to_string(),
+                ..base()
+            },
+            "fan_out",
+            warning
+        ));
+        assert!(has(
+            Config {
+                fan_out: Some(true),
+                fan_out_base_port: Some(0),
+                ..base()
+            },
+            "fan_out_base_port",
+            error
+        ));
+
+        assert!(has(
+            Config {
+                split_percent: Some(10),
+                ..base()
+            },
+            "split_percent",
+            warning
+        ));
+        let split = || Config {
+            split_target: Some("api-canary".to_string()),
+            split_percent: Some(10),
+            ..base()
+        };
+        assert_eq!(
+            flagged(&split()),
+            vec![("local_port".to_string(), Severity::Info)]
+        );
+        assert!(has(
+            Config {
+                protocol: "udp".to_string(),
+                ..split()
+            },
+            "split_target",
+            error
+        ));
+        assert!(has(
+            Config {
+                split_percent: Some(101),
+                ..split()
+            },
+            "split_percent",
+            error
+        ));
+        assert!(has(
+            Config {
+                split_percent: Some(0),
+                ..split()
+            },
+            "split_percent",
+            warning
+        ));
+
+        let rule = |header: Option<&str>, action: MockAction| MockRule {
+            method: None,
+            path: Some("/orders".to_string()),
+            header: header.map(str::to_string),
+            action,
+        };
+        assert!(has(
+            Config {
+                mock_rules: Some(vec![rule(None, MockAction::Pass)]),
+                ..proxy()
+            },
+            "mock_rules",
+            error
+        ));
+        assert!(has(
+            Config {
+                mock_rules: Some(vec![rule(Some(": gzip"), MockAction::Pass)]),
+                ..base()
+            },
+            "mock_rules",
+            error
+        ));
+        assert!(has(
+            Config {
+                mock_rules: Some(vec![rule(None, respond(700))]),
+                ..base()
+            },
+            "mock_rules",
+            error
+        ));
+        let catch_all = MockRule {
+            path: None,
+            ..rule(None, respond(503))
+        };
+        assert!(has(
+            Config {
+                mock_rules: Some(vec![catch_all, rule(None, MockAction::Pass)]),
+                ..base()
+            },
+            "mock_rules",
+            warning
+        ));
+
+        assert!(has(
+            Config {
+                traffic_script: Some("/no/such/script.rhai".to_string()),
+                ..base()
+            },
+            "traffic_script",
+            error
+        ));
+        assert!(has(
+            Config {
+                openapi_spec: Some("/no/such/openapi.yaml".to_string()),
+                ..base()
+            },
+            "openapi_spec",
+            error
+        ));
+        assert!(has(
+            Config {
+                openapi_spec: Some("https://api.example.com/openapi.json".to_string()),
+                ..proxy()
+            },
+            "openapi_spec",
+            error
+        ));
+        assert!(!has(
+            Config {
+                openapi_spec: Some("https://api.example.com/openapi.json".to_string()),
+                ..base()
+            },
+            "openapi_spec",
+            error
+        ));
+    }
+
+    #[test]
+    fn test_local_port_in_use() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let config = Config {
+            local_port: Some(port),
+            ..base()
+        };
+        let in_use = ("local_port".to_string(), Severity::Warning);
+        assert!(flagged(&config).contains(&in_use));
+
+        let mut states = vec![ConfigState {
+            id: None,
+            config_id: 1,
+            is_running: true,
+            local_port: Some(port),
+        }];
+        let diagnostics = validate_config_fields(&config, &[], &states);
+        assert!(diagnostics.is_empty());
+
+        states[0].is_running = false;
+        let diagnostics = validate_config_fields(&config, &[], &states);
+        assert_eq!(diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn test_collisions() {
+        let config = Config {
+            local_port: Some(5432),
+            alias: Some("db".to_string()),
+            domain_enabled: Some(true),
+            ..base()
+        };
+        let other = Config {
+            id: Some(2),
+            ..config.clone()
+        };
+        let fields: Vec<(String, Severity)> = validate_config_fields(&config, &[other], &[])
+            .into_iter()
+            .map(|diagnostic| (diagnostic.field, diagnostic.severity))
+            .collect();
+        assert!(fields.contains(&("alias".to_string(), Severity::Error)));
+        assert!(fields.contains(&("local_port".to_string(), Severity::Warning)));
+
+        // the config itself is in the list of existing ones when edited
+        let diagnostics = validate_config_fields(&config, std::slice::from_ref(&config), &[]);
+        assert!(!diagnostics.iter().any(|d| d.field == "alias"));
+    }
+
+    #[test]
+    fn test_collisions_use_default_local_address() {
+        let config = Config {
+            local_port: Some(5432),
+            local_address: None,
+            ..base()
+        };
+        let other = |local_address: &str| Config {
+            id: Some(2),
+            local_address: Some(local_address.to_string()),
+            ..config.clone()
+        };
+
+        let mut diagnostics = Vec::new();
+        check_collisions(
+            &config,
+            &[other("127.0.0.2")],
+            "127.0.0.2",
+            &mut diagnostics,
+        );
+        assert_eq!(diagnostics.len(), 1);
+
+        let mut diagnostics = Vec::new();
+        check_collisions(
+            &config,
+            &[other("127.0.0.1")],
+            "127.0.0.2",
+            &mut diagnostics,
+        );
+        assert!(diagnostics.is_empty());
+    }
+}
@@ -0,0 +1,338 @@
+use std::net::Ipv4Addr;
+
+use sqlx::{
+    Row,
+    SqlitePool,
+};
+
+use crate::db::get_db_pool;
+use crate::models::config_model::Config;
+use crate::models::context_settings_model::ContextSettings;
+
+const PROTOCOLS: [&str; 2] = ["tcp", "udp"];
+
+fn blank(value: &Option<String>) -> bool {
+    value.as_deref().unwrap_or_default().trim().is_empty()
+}
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+// accepts `127.0.1.1-127.0.1.50` or a cidr like `127.0.1.0/24`, whose network
+// and broadcast addresses are skipped
+pub fn parse_address_range(range: &str) -> Result<(Ipv4Addr, Ipv4Addr), String> {
+    let invalid = |e: &dyn std::fmt::Display| format!("Invalid address range '{}': {}", range, e);
+
+    let (first, last) = if let Some((start, end)) = range.split_once('-') {
+        let start: Ipv4Addr = start.trim().parse().map_err(|e| invalid(&e))?;
+        let end: Ipv4Addr = end.trim().parse().map_err(|e| invalid(&e))?;
+        (u32::from(start), u32::from(end))
+    } else if let Some((network, prefix)) = range.split_once('/') {
+        let network: Ipv4Addr = network.trim().parse().map_err(|e| invalid(&e))?;
+        let prefix: u32 = prefix.trim().parse().map_err(|e| invalid(&e))?;
+        if prefix > 32 {
+            return Err(invalid(&"prefix is longer than 32 bits"));
+        }
+        let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+        let first = u32::from(network) & mask;
+        let last = first | !mask;
+        if prefix < 31 {
+            (first + 1, last - 1)
+        } else {
+            (first, last)
+        }
+    } else {
+        let address: Ipv4Addr = range.trim().parse().map_err(|e| invalid(&e))?;
+        (u32::from(address), u32::from(address))
+    };
+
+    if first > last {
+        return Err(invalid(&"the range is empty"));
+    }
+    Ok((Ipv4Addr::from(first), Ipv4Addr::from(last)))
+}
+
+fn validate_context_settings(settings: &ContextSettings) -> Result<(), String> {
+    if settings.context.trim().is_empty() {
+        return Err("Context name cannot be empty".to_string());
+    }
+    if let Some(range) = &settings.local_address_range {
+        parse_address_range(range)?;
+    }
+    if let Some(protocol) = &settings.default_protocol {
+        if !PROTOCOLS.contains(&protocol.as_str()) {
+            return Err(format!(
+                "Unknown protocol '{}', expected one of: {}",
+                protocol,
+                PROTOCOLS.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+// first address of the range that doesn't already serve the config's local port
+fn free_local_address(range: &str, config: &Config, existing: &[Config]) -> Option<String> {
+    let (first, last) = parse_address_range(range).ok()?;
+    (u32::from(first)..=u32::from(last))
+        .map(|address| Ipv4Addr::from(address).to_string())
+        .find(|address| {
+            !existing.iter().any(|other| {
+                (config.id.is_none() || other.id != config.id)
+                    && other.local_port == config.local_port
+                    && other.local_address.as_deref() == Some(address.as_str())
+            })
+        })
+}
+
+// only fills what the config leaves blank, so explicit values always win
+pub fn apply_context_defaults(
+    config: &mut Config, defaults: &ContextSettings, existing: &[Config],
+) {
+    if config.namespace.trim().is_empty() {
+        if let Some(namespace) = &defaults.default_namespace {
+            config.namespace = namespace.clone();
+        }
+    }
+    if config.protocol.trim().is_empty() {
+        if let Some(protocol) = &defaults.default_protocol {
+            config.protocol = protocol.clone();
+        }
+    }
+    if blank(&config.local_address) {
+        if let Some(range) = &defaults.local_address_range {
+            config.local_address = free_local_address(range, config, existing);
+        }
+    }
+}
+
+fn context_settings_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<ContextSettings, String> {
+    Ok(ContextSettings {
+        context: row.try_get("context").map_err(|e| e.to_string())?,
+        display_name: row.try_get("display_name").map_err(|e| e.to_string())?,
+        color: row.try_get("color").map_err(|e| e.to_string())?,
+        default_namespace: row
+            .try_get("default_namespace")
+            .map_err(|e| e.to_string())?,
+        local_address_range: row
+            .try_get("local_address_range")
+            .map_err(|e| e.to_string())?,
+        default_protocol: row.try_get("default_protocol").map_err(|e| e.to_string())?,
+        production: row.try_get("production").map_err(|e| e.to_string())?,
+        read_only: row.try_get("read_only").map_err(|e| e.to_string())?,
+    })
+}
+
+pub async fn list_context_settings() -> Result<Vec<ContextSettings>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    list_context_settings_with_pool(&pool).await
+}
+
+pub async fn list_context_settings_with_pool(
+    pool: &SqlitePool,
+) -> Result<Vec<ContextSettings>, String> {
+    let rows = sqlx::query("SELECT * FROM context_settings ORDER BY context")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    rows.iter().map(context_settings_from_row).collect()
+}
+
+pub async fn get_context_settings(context: &str) -> Result<Option<ContextSettings>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    get_context_settings_with_pool(context, &pool).await
+}
+
+pub async fn get_context_settings_with_pool(
+    context: &str, pool: &SqlitePool,
+) -> Result<Option<ContextSettings>, String> {
+    let row = sqlx::query("SELECT * FROM context_settings WHERE context = ?1")
+        .bind(context)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    row.as_ref().map(context_settings_from_row).transpose()
+}
+
+pub async fn save_context_settings(settings: ContextSettings) -> Result<(), String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    save_context_settings_with_pool(settings, &pool).await
+}
+
+pub async fn save_context_settings_with_pool(
+    settings: ContextSettings, pool: &SqlitePool,
+) -> Result<(), String> {
+    let settings = ContextSettings {
+        context: settings.context.trim().to_string(),
+        display_name: non_blank(settings.display_name),
+        color: non_blank(settings.color),
+        default_namespace: non_blank(settings.default_namespace),
+        local_address_range: non_blank(settings.local_address_range),
+        default_protocol: non_blank(settings.default_protocol).map(|p| p.to_lowercase()),
+        production: settings.production,
+        read_only: settings.read_only,
+    };
+    validate_context_settings(&settings)?;
+
+    sqlx::query(
+        "INSERT INTO context_settings
+            (context, display_name, color, default_namespace, local_address_range, default_protocol,
+             production, read_only)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT(context) DO UPDATE SET
+            display_name = excluded.display_name,
+            color = excluded.color,
+            default_namespace = excluded.default_namespace,
+            local_address_range = excluded.local_address_range,
+            default_protocol = excluded.default_protocol,
+            production = excluded.production,
+            read_only = excluded.read_only",
+    )
+    .bind(&settings.context)
+    .bind(&settings.display_name)
+    .bind(&settings.color)
+    .bind(&settings.default_namespace)
+    .bind(&settings.local_address_range)
+    .bind(&settings.default_protocol)
+    .bind(settings.production)
+    .bind(settings.read_only)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+pub async fn ensure_workloads_allowed(configs: &[Config]) -> Result<(), String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    ensure_workloads_allowed_with_pool(configs, &pool).await
+}
+
+// fails before anything gets created in a context marked as read-only
+pub async fn ensure_workloads_allowed_with_pool(
+    configs: &[Config], pool: &SqlitePool,
+) -> Result<(), String> {
+    for config in configs {
+        let settings = get_context_settings_with_pool(&config.context, pool).await?;
+        if settings.is_some_and(|settings| settings.read_only) {
+            return Err(format!(
+                "Context {} is read-only, kftray won't deploy a proxy pod there",
+                config.context
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub async fn delete_context_settings(context: &str) -> Result<(), String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    delete_context_settings_with_pool(context, &pool).await
+}
+
+pub async fn delete_context_settings_with_pool(
+    context: &str, pool: &SqlitePool,
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM context_settings WHERE context = ?1")
+        .bind(context)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_memory_db_pool;
+    use crate::utils::config::{
+        insert_config_with_pool,
+        read_configs_with_pool,
+    };
+
+    #[test]
+    fn test_parse_address_range() {
+        assert_eq!(
+            parse_address_range("127.0.1.0/24").unwrap(),
+            ("127.0.1.1".parse().unwrap(), "127.0.1.254".parse().unwrap())
+        );
+        assert_eq!(
+            parse_address_range("127.0.0.2 - 127.0.0.9").unwrap(),
+            ("127.0.0.2".parse().unwrap(), "127.0.0.9".parse().unwrap())
+        );
+        assert!(parse_address_range("127.0.0.9-127.0.0.2").is_err());
+        assert!(parse_address_range("127.0.0.0/33").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_context_defaults_fill_new_configs() {
+        let pool = create_memory_db_pool().await.unwrap();
+        save_context_settings_with_pool(
+            ContextSettings {
+                context: "prod".to_string(),
+                display_name: Some("Production".to_string()),
+                default_namespace: Some("payments".to_string()),
+                local_address_range: Some("127.0.1.1-127.0.1.10".to_string()),
+                default_protocol: Some("UDP".to_string()),
+                ..Default::default()
+            },
+            &pool,
+        )
+        .await
+        .unwrap();
+
+        for _ in 0..2 {
+            let config = Config {
+                context: "prod".to_string(),
+                namespace: String::new(),
+                protocol: String::new(),
+                local_address: None,
+                local_port: Some(8080),
+                ..Default::default()
+            };
+            insert_config_with_pool(config, &pool).await.unwrap();
+        }
+
+        let mut configs = read_configs_with_pool(&pool).await.unwrap();
+        configs.sort_by_key(|config| config.id);
+        assert_eq!(configs[0].namespace, "payments");
+        assert_eq!(configs[0].protocol, "udp");
+        assert_eq!(configs[0].local_address.as_deref(), Some("127.0.1.1"));
+        assert_eq!(configs[1].local_address.as_deref(), Some("127.0.1.2"));
+
+        let saved = get_context_settings_with_pool("prod", &pool)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved.display_name.as_deref(), Some("Production"));
+        assert!(ensure_workloads_allowed_with_pool(&configs, &pool)
+            .await
+            .is_ok());
+
+        save_context_settings_with_pool(
+            ContextSettings {
+                read_only: true,
+                ..saved
+            },
+            &pool,
+        )
+        .await
+        .unwrap();
+        assert!(ensure_workloads_allowed_with_pool(&configs, &pool)
+            .await
+            .is_err());
+
+        delete_context_settings_with_pool("prod", &pool)
+            .await
+            .unwrap();
+        assert!(list_context_settings_with_pool(&pool)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -13,6 +13,7 @@ use log::{
     info,
 };
 use serde_json::json;
+use sqlx::sqlite::SqlitePoolOptions;
 use sqlx::SqlitePool;
 use tokio::sync::OnceCell;
 
@@ -20,9 +21,13 @@ use crate::utils::config_dir::{
     get_db_file_path,
     get_pod_manifest_path,
 };
+use crate::utils::db_mode::DatabaseMode;
+use crate::utils::schema_migration::run_schema_migrations;
 
 pub async fn init() -> Result<(), Box<dyn std::error::Error>> {
-    if !db_file_exists() {
+    let mode = DatabaseMode::from_env();
+
+    if !mode.is_memory() && !db_file_exists() {
         create_db_file()?;
     }
 
@@ -30,108 +35,79 @@ pub async fn init() -> Result<(), Box<dyn std::error::Error>> {
         create_server_config_manifest()?;
     }
 
-    create_db_table().await?;
+    let pool = get_db_pool().await?;
+    create_db_table(&pool).await?;
 
     Ok(())
 }
 
+pub async fn init_with_pool(pool: &SqlitePool) -> Result<(), String> {
+    create_db_table(pool).await
+}
+
 static DB_POOL: OnceCell<Arc<SqlitePool>> = OnceCell::const_new();
 
 pub async fn get_db_pool() -> Result<Arc<SqlitePool>, String> {
     DB_POOL
         .get_or_try_init(|| async {
+            let pool = create_db_pool(DatabaseMode::from_env()).await?;
+            Ok(Arc::new(pool))
+        })
+        .await
+        .map(Arc::clone)
+}
+
+pub async fn create_db_pool(mode: DatabaseMode) -> Result<SqlitePool, String> {
+    match mode {
+        DatabaseMode::Memory => {
+            info!("Using in-memory database");
+            // every connection to `sqlite::memory:` opens a fresh database, so
+            // the pool is pinned to a single connection that is
+            // never recycled
+            SqlitePoolOptions::new()
+                .max_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .connect("sqlite::memory:")
+                .await
+                .map_err(|e| {
+                    error!("Failed to create in-memory DB: {}", e);
+                    e.to_string()
+                })
+        }
+        DatabaseMode::File => {
             let db_dir = get_db_file_path().map_err(|e| {
                 error!("Failed to get DB file path: {}", e);
                 e.to_string()
             })?;
             let db_dir_str = db_dir.to_str().ok_or("Invalid DB path")?;
             info!("Database file path: {}", db_dir_str);
-            let pool = SqlitePool::connect(db_dir_str).await.map_err(|e| {
+            SqlitePool::connect(db_dir_str).await.map_err(|e| {
                 error!("Failed to connect to DB: {}", e);
                 e.to_string()
-            })?;
-            Ok(Arc::new(pool))
-        })
-        .await
-        .map(Arc::clone)
+            })
+        }
+    }
 }
 
-async fn create_db_table() -> Result<(), sqlx::Error> {
+pub async fn create_memory_db_pool() -> Result<SqlitePool, String> {
+    let pool = create_db_pool(DatabaseMode::Memory).await?;
+    create_db_table(&pool).await?;
+    Ok(pool)
+}
+
+async fn create_db_table(pool: &SqlitePool) -> Result<(), String> {
     info!("Creating database tables and triggers.");
-    let pool = get_db_pool().await.map_err(|e| {
-        error!("Failed to get DB pool: {}", e);
-        sqlx::Error::Configuration(e.into())
-    })?;
-    let mut conn = pool.acquire().await.map_err(|e| {
-        error!("Failed to acquire connection: {}", e);
-        e
-    })?;
 
     sqlx::query("PRAGMA foreign_keys = ON;")
-        .execute(&mut *conn)
+        .execute(pool)
         .await
         .map_err(|e| {
             error!("Failed to set PRAGMA foreign_keys: {}", e);
-            e
+            e.to_string()
         })?;
 
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS configs (
-            id INTEGER PRIMARY KEY,
-            data TEXT NOT NULL
-        )",
-    )
-    .execute(&mut *conn)
-    .await
-    .map_err(|e| {
-        error!("Failed to create configs table: {}", e);
-        e
-    })?;
-
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS config_state (
-            id INTEGER PRIMARY KEY,
-            config_id INTEGER NOT NULL,
-            is_running BOOLEAN NOT NULL DEFAULT false,
-            FOREIGN KEY(config_id) REFERENCES configs(id) ON DELETE CASCADE
-        )",
-    )
-    .execute(&mut *conn)
-    .await
-    .map_err(|e| {
-        error!("Failed to create config_state table: {}", e);
-        e
-    })?;
-
-    sqlx::query(
-        "CREATE TRIGGER IF NOT EXISTS after_insert_config
-         AFTER INSERT ON configs
-         FOR EACH ROW
-         BEGIN
-             INSERT INTO config_state (config_id, is_running) VALUES (NEW.id, false);
-         END;",
-    )
-    .execute(&mut *conn)
-    .await
-    .map_err(|e| {
-        error!("Failed to create after_insert_config trigger: {}", e);
-        e
-    })?;
-
-    sqlx::query(
-        "CREATE TRIGGER IF NOT EXISTS after_delete_config
-         AFTER DELETE ON configs
-         FOR EACH ROW
-         BEGIN
-             DELETE FROM config_state WHERE config_id = OLD.id;
-         END;",
-    )
-    .execute(&mut *conn)
-    .await
-    .map_err(|e| {
-        error!("Failed to create after_delete_config trigger: {}", e);
-        e
-    })?;
+    run_schema_migrations(pool, false).await?;
 
     info!("Database tables and triggers created successfully.");
     Ok(())
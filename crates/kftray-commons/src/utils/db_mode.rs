@@ -0,0 +1,132 @@
+use std::env;
+use std::path::PathBuf;
+
+use log::warn;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::utils::config_dir::get_db_settings_path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseMode {
+    #[default]
+    File,
+    Memory,
+}
+
+// where the database lives can't be kept in the database, so it has its own
+// file in the config dir. KFTRAY_DB_PATH and KFTRAY_DB_MODE win over it
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseSettings {
+    pub path: Option<PathBuf>,
+    pub mode: DatabaseMode,
+}
+
+impl DatabaseMode {
+    pub fn from_env() -> Self {
+        resolve_db_mode(env::var("KFTRAY_DB_MODE").ok(), &read_database_settings())
+    }
+
+    pub fn is_memory(&self) -> bool {
+        matches!(self, DatabaseMode::Memory)
+    }
+}
+
+pub fn resolve_db_mode(env_mode: Option<String>, settings: &DatabaseSettings) -> DatabaseMode {
+    match env_mode {
+        Some(mode) if mode.eq_ignore_ascii_case("memory") => DatabaseMode::Memory,
+        Some(_) => DatabaseMode::File,
+        None => settings.mode,
+    }
+}
+
+pub fn resolve_db_path(env_path: Option<String>, settings: &DatabaseSettings) -> Option<PathBuf> {
+    env_path
+        .map(PathBuf::from)
+        .or_else(|| settings.path.clone())
+        .filter(|path| !path.as_os_str().is_empty())
+}
+
+// a missing or unreadable file means the defaults, the database has to open
+// either way
+pub fn read_database_settings() -> DatabaseSettings {
+    let Ok(path) = get_db_settings_path() else {
+        return DatabaseSettings::default();
+    };
+    if !path.exists() {
+        return DatabaseSettings::default();
+    }
+    match std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+    {
+        Ok(settings) => settings,
+        Err(e) => {
+            warn!("Ignoring database settings in {}: {}", path.display(), e);
+            DatabaseSettings::default()
+        }
+    }
+}
+
+// picked up on the next start, the open pool keeps its database
+pub fn save_database_settings(settings: &DatabaseSettings) -> Result<(), String> {
+    let path = get_db_settings_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_wins_over_database_settings() {
+        let settings = DatabaseSettings {
+            path: Some(PathBuf::from("/data/kftray.db")),
+            mode: DatabaseMode::Memory,
+        };
+
+        assert_eq!(resolve_db_mode(None, &settings), DatabaseMode::Memory);
+        assert_eq!(
+            resolve_db_mode(Some("file".to_string()), &settings),
+            DatabaseMode::File
+        );
+        assert_eq!(
+            resolve_db_mode(Some("MEMORY".to_string()), &DatabaseSettings::default()),
+            DatabaseMode::Memory
+        );
+
+        assert_eq!(
+            resolve_db_path(None, &settings),
+            Some(PathBuf::from("/data/kftray.db"))
+        );
+        assert_eq!(
+            resolve_db_path(Some("/elsewhere/kftray.db".to_string()), &settings),
+            Some(PathBuf::from("/elsewhere/kftray.db"))
+        );
+        assert_eq!(resolve_db_path(None, &DatabaseSettings::default()), None);
+    }
+
+    #[test]
+    fn test_database_settings_json() {
+        let settings: DatabaseSettings = serde_json::from_str(r#"{"mode":"memory"}"#).unwrap();
+        assert_eq!(
+            settings,
+            DatabaseSettings {
+                path: None,
+                mode: DatabaseMode::Memory,
+            }
+        );
+        assert_eq!(
+            serde_json::from_str::<DatabaseSettings>("{}").unwrap(),
+            DatabaseSettings::default()
+        );
+    }
+}
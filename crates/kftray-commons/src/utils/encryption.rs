@@ -0,0 +1,432 @@
+use std::sync::Mutex;
+
+use base64::{
+    engine::general_purpose,
+    Engine as _,
+};
+use keyring::credential::CredentialPersistence;
+use keyring::{
+    Entry,
+    Error as KeyringError,
+};
+use lazy_static::lazy_static;
+use log::{
+    error,
+    info,
+};
+use openssl::rand::rand_bytes;
+use openssl::symm::{
+    decrypt_aead,
+    encrypt_aead,
+    Cipher,
+};
+use sqlx::{
+    Row,
+    SqlitePool,
+};
+
+use crate::db::get_db_pool;
+use crate::utils::config_dir::get_encryption_marker_path;
+use crate::utils::db_mode::DatabaseMode;
+
+const KEYRING_SERVICE: &str = "kftray";
+const KEYRING_KEY_NAME: &str = "config-encryption-key";
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+type EncryptionKey = [u8; KEY_LEN];
+
+lazy_static! {
+    static ref KEY_CACHE: Mutex<Option<EncryptionKey>> = Mutex::new(None);
+}
+
+fn keyring_entry() -> Result<Entry, String> {
+    Entry::new(KEYRING_SERVICE, KEYRING_KEY_NAME).map_err(|e| e.to_string())
+}
+
+// a key that vanishes on reboot (kernel keyutils) or with the process (mock
+// store) would leave every encrypted config unreadable, so only keychains
+// that keep the key until it's deleted are accepted
+fn ensure_persistent_key_store() -> Result<(), String> {
+    match keyring::default::default_credential_builder().persistence() {
+        CredentialPersistence::UntilDelete => Ok(()),
+        _ => Err("No persistent keychain is available to store the encryption key".to_string()),
+    }
+}
+
+fn load_key() -> Result<Option<EncryptionKey>, String> {
+    let encoded = match keyring_entry()?.get_password() {
+        Ok(encoded) => encoded,
+        Err(KeyringError::NoEntry) => return Ok(None),
+        Err(e) => {
+            error!("Failed to read encryption key from keychain: {}", e);
+            return Err(e.to_string());
+        }
+    };
+
+    let bytes = general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("Invalid encryption key in keychain: {}", e))?;
+
+    let key: EncryptionKey = bytes
+        .try_into()
+        .map_err(|_| "Invalid encryption key length in keychain".to_string())?;
+
+    Ok(Some(key))
+}
+
+fn current_key() -> Result<Option<EncryptionKey>, String> {
+    let mut cache = KEY_CACHE.lock().map_err(|e| e.to_string())?;
+
+    if let Some(key) = *cache {
+        return Ok(Some(key));
+    }
+
+    // a missing key isn't cached: the keychain may just be locked or not
+    // reachable yet, and the next read should try again
+    let key = load_key()?;
+    if key.is_some() {
+        *cache = key;
+    }
+
+    Ok(key)
+}
+
+fn set_cached_key(key: Option<EncryptionKey>) -> Result<(), String> {
+    let mut cache = KEY_CACHE.lock().map_err(|e| e.to_string())?;
+    *cache = key;
+    Ok(())
+}
+
+pub fn is_encrypted(data: &str) -> bool {
+    data.starts_with(ENCRYPTED_PREFIX)
+}
+
+// a keychain that isn't there at all (no secret service on a headless box)
+// is never asked unless encryption was turned on
+pub fn is_encryption_enabled() -> Result<bool, String> {
+    if DatabaseMode::from_env().is_memory() {
+        return Ok(false);
+    }
+
+    Ok(get_encryption_marker_path()?.exists())
+}
+
+fn set_encryption_marker(enabled: bool) -> Result<(), String> {
+    let marker = get_encryption_marker_path()?;
+    let result = if enabled {
+        std::fs::write(&marker, b"")
+    } else {
+        match std::fs::remove_file(&marker) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    };
+    result.map_err(|e| format!("Failed to update {}: {}", marker.display(), e))
+}
+
+fn encrypt_with_key(plaintext: &str, key: &EncryptionKey) -> Result<String, String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand_bytes(&mut nonce).map_err(|e| e.to_string())?;
+
+    let mut tag = [0u8; TAG_LEN];
+    let ciphertext = encrypt_aead(
+        Cipher::aes_256_gcm(),
+        key,
+        Some(&nonce),
+        &[],
+        plaintext.as_bytes(),
+        &mut tag,
+    )
+    .map_err(|e| format!("Failed to encrypt config: {}", e))?;
+
+    let mut payload = Vec::with_capacity(NONCE_LEN + ciphertext.len() + TAG_LEN);
+    payload.extend_from_slice(&nonce);
+    payload.extend_from_slice(&ciphertext);
+    payload.extend_from_slice(&tag);
+
+    Ok(format!(
+        "{}{}",
+        ENCRYPTED_PREFIX,
+        general_purpose::STANDARD.encode(payload)
+    ))
+}
+
+fn decrypt_with_key(data: &str, key: &EncryptionKey) -> Result<String, String> {
+    let payload = general_purpose::STANDARD
+        .decode(&data[ENCRYPTED_PREFIX.len()..])
+        .map_err(|e| format!("Failed to decode encrypted config: {}", e))?;
+
+    if payload.len() < NONCE_LEN + TAG_LEN {
+        return Err("Encrypted config is truncated".to_string());
+    }
+
+    let (nonce, rest) = payload.split_at(NONCE_LEN);
+    let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LEN);
+
+    let plaintext = decrypt_aead(
+        Cipher::aes_256_gcm(),
+        key,
+        Some(nonce),
+        &[],
+        ciphertext,
+        tag,
+    )
+    .map_err(|e| format!("Failed to decrypt config: {}", e))?;
+
+    String::from_utf8(plaintext).map_err(|e| e.to_string())
+}
+
+pub fn encode_config_data(data: &str) -> Result<String, String> {
+    if !is_encryption_enabled()? {
+        return Ok(data.to_string());
+    }
+
+    // with encryption on, a locked keychain fails the save instead of
+    // writing the config in plaintext
+    let key = current_key()?
+        .ok_or_else(|| "Config encryption is on but no encryption key was found".to_string())?;
+    encrypt_with_key(data, &key)
+}
+
+pub fn decode_config_data(data: &str) -> Result<String, String> {
+    if !is_encrypted(data) {
+        return Ok(data.to_string());
+    }
+
+    let key = current_key()?
+        .ok_or_else(|| "Config is encrypted but no encryption key was found".to_string())?;
+
+    decrypt_with_key(data, &key)
+}
+
+// every column that stores `encode_config_data` output, as (table, column).
+// they are all rewritten together so no row is left behind under a key that
+// is about to be deleted
+const ENCODED_COLUMNS: &[(&str, &str)] = &[
+    ("configs", "data"),
+    ("config_snapshots", "data"),
+    ("config_trash", "data"),
+    ("config_history", "changes"),
+];
+
+async fn reencode_configs(
+    pool: &SqlitePool, current: Option<&EncryptionKey>, key: Option<&EncryptionKey>,
+) -> Result<(), String> {
+    let mut transaction = pool.begin().await.map_err(|e| e.to_string())?;
+
+    for (table, column) in ENCODED_COLUMNS {
+        let rows = sqlx::query(&format!("SELECT id, {} AS data FROM {}", column, table))
+            .fetch_all(&mut *transaction)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        for row in rows {
+            let id: i64 = row.try_get("id").map_err(|e| e.to_string())?;
+            let data: String = row.try_get("data").map_err(|e| e.to_string())?;
+
+            let plaintext = if is_encrypted(&data) {
+                let current = current.ok_or_else(|| {
+                    format!(
+                        "Row {} of {} is encrypted but no encryption key was found",
+                        id, table
+                    )
+                })?;
+                decrypt_with_key(&data, current)?
+            } else {
+                data
+            };
+            let updated = match key {
+                Some(key) => encrypt_with_key(&plaintext, key)?,
+                None => plaintext,
+            };
+
+            sqlx::query(&format!(
+                "UPDATE {} SET {} = ?1 WHERE id = ?2",
+                table, column
+            ))
+            .bind(updated)
+            .bind(id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+    }
+
+    transaction.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+pub async fn enable_encryption() -> Result<(), String> {
+    if DatabaseMode::from_env().is_memory() {
+        return Err("Encryption is not available for in-memory databases".to_string());
+    }
+
+    if is_encryption_enabled()? {
+        return Ok(());
+    }
+
+    ensure_persistent_key_store()?;
+
+    let mut key: EncryptionKey = [0u8; KEY_LEN];
+    rand_bytes(&mut key).map_err(|e| e.to_string())?;
+
+    keyring_entry()?
+        .set_password(&general_purpose::STANDARD.encode(key))
+        .map_err(|e| {
+            error!("Failed to store encryption key in keychain: {}", e);
+            e.to_string()
+        })?;
+
+    let pool = get_db_pool().await?;
+    let result = match reencode_configs(&pool, None, Some(&key)).await {
+        Ok(()) => set_encryption_marker(true),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        error!("Failed to encrypt configs: {}", e);
+        // the rows go back to plaintext before the key goes, if that fails
+        // too the key stays so they can still be read
+        if reencode_configs(&pool, Some(&key), None).await.is_ok() {
+            if let Err(e) = keyring_entry()?.delete_credential() {
+                error!("Failed to remove encryption key from keychain: {}", e);
+            }
+        }
+        return Err(e);
+    }
+    set_cached_key(Some(key))?;
+
+    info!("Config encryption enabled");
+    Ok(())
+}
+
+pub async fn disable_encryption() -> Result<(), String> {
+    if !is_encryption_enabled()? {
+        return Ok(());
+    }
+    let key = current_key()?;
+
+    let pool = get_db_pool().await?;
+    reencode_configs(&pool, key.as_ref(), None).await?;
+    set_encryption_marker(false)?;
+
+    match keyring_entry()?.delete_credential() {
+        Ok(()) | Err(KeyringError::NoEntry) => {}
+        Err(e) => {
+            error!("Failed to delete encryption key from keychain: {}", e);
+            return Err(e.to_string());
+        }
+    }
+    set_cached_key(None)?;
+
+    info!("Config encryption disabled");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_memory_db_pool;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let key: EncryptionKey = [7u8; KEY_LEN];
+        let plaintext = r#"{"service":"api","kubeconfig":"/home/user/.kube/config"}"#;
+
+        let encrypted = encrypt_with_key(plaintext, &key).unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.contains("kubeconfig"));
+
+        let decrypted = decrypt_with_key(&encrypted, &key).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_decrypt_with_wrong_key_fails() {
+        let encrypted = encrypt_with_key("secret", &[1u8; KEY_LEN]).unwrap();
+        assert!(decrypt_with_key(&encrypted, &[2u8; KEY_LEN]).is_err());
+    }
+
+    async fn read_encoded_columns(pool: &SqlitePool) -> Vec<String> {
+        let mut values = Vec::new();
+        for (table, column) in ENCODED_COLUMNS {
+            let rows = sqlx::query(&format!(
+                "SELECT {} AS data FROM {} ORDER BY id",
+                column, table
+            ))
+            .fetch_all(pool)
+            .await
+            .unwrap();
+            values.extend(rows.iter().map(|row| row.get::<String, _>("data")));
+        }
+        values
+    }
+
+    #[tokio::test]
+    async fn test_reencode_covers_snapshots_trash_and_history() {
+        let pool = create_memory_db_pool().await.unwrap();
+        let config = r#"{"service":"api","kubeconfig":"/home/user/.kube/config"}"#;
+        sqlx::query("INSERT INTO configs (data) VALUES (?1)")
+            .bind(config)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO config_snapshots (reason, config_count, data, created_at)
+             VALUES ('manual', 1, ?1, 0)",
+        )
+        .bind(format!("[{}]", config))
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO config_trash (id, data, deleted_at) VALUES (42, ?1, 0)")
+            .bind(config)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO config_history (config_id, action, changes, changed_at)
+             VALUES (1, 'updated', ?1, 0)",
+        )
+        .bind(r#"[{"field":"kubeconfig","old":null,"new":"/home/user/.kube/config"}]"#)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let original = read_encoded_columns(&pool).await;
+        assert_eq!(original.len(), ENCODED_COLUMNS.len());
+
+        let key: EncryptionKey = [9u8; KEY_LEN];
+        reencode_configs(&pool, None, Some(&key)).await.unwrap();
+        let encrypted = read_encoded_columns(&pool).await;
+        assert!(encrypted.iter().all(|data| is_encrypted(data)));
+        assert!(encrypted.iter().all(|data| !data.contains("kubeconfig")));
+
+        reencode_configs(&pool, Some(&key), None).await.unwrap();
+        assert_eq!(read_encoded_columns(&pool).await, original);
+    }
+
+    #[tokio::test]
+    async fn test_reencode_fails_without_the_current_key() {
+        let pool = create_memory_db_pool().await.unwrap();
+        let key: EncryptionKey = [3u8; KEY_LEN];
+        sqlx::query(
+            "INSERT INTO config_snapshots (reason, config_count, data, created_at)
+             VALUES ('manual', 0, ?1, 0)",
+        )
+        .bind(encrypt_with_key("[]", &key).unwrap())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert!(reencode_configs(&pool, None, None).await.is_err());
+    }
+
+    #[test]
+    fn test_plaintext_passes_through_decode() {
+        let data = r#"{"service":"api"}"#;
+        assert_eq!(decode_config_data(data).unwrap(), data);
+    }
+}
@@ -1,14 +1,39 @@
+use sqlx::SqlitePool;
+
+use crate::config_history::{
+    record_config_history_with_pool,
+    HISTORY_DELETED,
+};
+use crate::config_snapshot::snapshot_configs_with_pool;
 use crate::db::get_db_pool;
 
 pub async fn clear_existing_configs() -> Result<(), sqlx::Error> {
     let pool = get_db_pool()
         .await
         .map_err(|e| sqlx::Error::Configuration(format!("DB Pool error: {}", e).into()))?;
+    clear_existing_configs_with_pool(&pool).await
+}
+
+pub async fn clear_existing_configs_with_pool(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    snapshot_configs_with_pool("Before syncing configs from GitHub", pool)
+        .await
+        .map_err(|e| sqlx::Error::Configuration(e.into()))?;
+
     let mut conn = pool.acquire().await?;
 
+    let ids: Vec<i64> = sqlx::query_scalar("SELECT id FROM configs")
+        .fetch_all(&mut *conn)
+        .await?;
     sqlx::query("DELETE FROM configs")
         .execute(&mut *conn)
         .await?;
+    drop(conn);
+
+    for id in ids {
+        record_config_history_with_pool(id, HISTORY_DELETED, Vec::new(), pool)
+            .await
+            .map_err(|e| sqlx::Error::Configuration(e.into()))?;
+    }
 
     Ok(())
 }
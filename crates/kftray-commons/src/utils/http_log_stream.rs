@@ -0,0 +1,198 @@
+use std::collections::{
+    HashMap,
+    VecDeque,
+};
+use std::sync::Mutex;
+
+use futures::stream::{
+    self,
+    Stream,
+};
+use lazy_static::lazy_static;
+use tokio::sync::broadcast::{
+    self,
+    error::{
+        RecvError,
+        TryRecvError,
+    },
+};
+use tracing::warn;
+
+use crate::models::http_log_model::HttpLogRecord;
+
+const HTTP_LOG_RING_SIZE: usize = 200;
+const HTTP_LOG_CHANNEL_CAPACITY: usize = 256;
+
+struct HttpLogChannel {
+    sender: broadcast::Sender<HttpLogRecord>,
+    history: VecDeque<HttpLogRecord>,
+}
+
+impl HttpLogChannel {
+    fn new() -> Self {
+        let (sender, _) = broadcast::channel(HTTP_LOG_CHANNEL_CAPACITY);
+        Self {
+            sender,
+            history: VecDeque::with_capacity(HTTP_LOG_RING_SIZE),
+        }
+    }
+}
+
+lazy_static! {
+    static ref HTTP_LOG_CHANNELS: Mutex<HashMap<i64, HttpLogChannel>> = Mutex::new(HashMap::new());
+}
+
+pub struct HttpLogSubscription {
+    config_id: i64,
+    backlog: VecDeque<HttpLogRecord>,
+    receiver: broadcast::Receiver<HttpLogRecord>,
+    dropped: u64,
+}
+
+impl HttpLogSubscription {
+    pub async fn recv(&mut self) -> Option<HttpLogRecord> {
+        if let Some(record) = self.backlog.pop_front() {
+            return Some(record);
+        }
+
+        loop {
+            match self.receiver.recv().await {
+                Ok(record) => return Some(record),
+                Err(RecvError::Lagged(skipped)) => {
+                    self.dropped += skipped;
+                    warn!(
+                        "HTTP log subscriber for config {} lagged, dropped {} records",
+                        self.config_id, skipped
+                    );
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    // for callers that poll, like the terminal ui, `None` once nothing is
+    // waiting
+    pub fn try_recv(&mut self) -> Option<HttpLogRecord> {
+        if let Some(record) = self.backlog.pop_front() {
+            return Some(record);
+        }
+
+        loop {
+            match self.receiver.try_recv() {
+                Ok(record) => return Some(record),
+                Err(TryRecvError::Lagged(skipped)) => self.dropped += skipped,
+                Err(_) => return None,
+            }
+        }
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn into_stream(self) -> impl Stream<Item = HttpLogRecord> {
+        stream::unfold(self, |mut subscription| async move {
+            subscription
+                .recv()
+                .await
+                .map(|record| (record, subscription))
+        })
+    }
+}
+
+pub fn publish_http_log(record: HttpLogRecord) {
+    let mut channels = HTTP_LOG_CHANNELS.lock().unwrap();
+    let channel = channels
+        .entry(record.config_id)
+        .or_insert_with(HttpLogChannel::new);
+
+    if channel.history.len() == HTTP_LOG_RING_SIZE {
+        channel.history.pop_front();
+    }
+    channel.history.push_back(record.clone());
+
+    let _ = channel.sender.send(record);
+}
+
+pub fn subscribe_http_logs(config_id: i64) -> HttpLogSubscription {
+    let mut channels = HTTP_LOG_CHANNELS.lock().unwrap();
+    let channel = channels
+        .entry(config_id)
+        .or_insert_with(HttpLogChannel::new);
+
+    HttpLogSubscription {
+        config_id,
+        backlog: channel.history.clone(),
+        receiver: channel.sender.subscribe(),
+        dropped: 0,
+    }
+}
+
+pub fn clear_http_log_history(config_id: i64) {
+    if let Some(channel) = HTTP_LOG_CHANNELS.lock().unwrap().get_mut(&config_id) {
+        channel.history.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::models::http_log_model::HttpLogKind;
+
+    fn record(config_id: i64, trace_id: &str) -> HttpLogRecord {
+        HttpLogRecord {
+            config_id,
+            trace_id: trace_id.to_string(),
+            kind: HttpLogKind::Request,
+            timestamp: String::new(),
+            method: Some("GET".to_string()),
+            path: Some("/".to_string()),
+            status: None,
+            headers: Vec::new(),
+            body: None,
+            original_size: None,
+            took_ms: None,
+            spec_violations: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_late_subscriber_gets_history_then_live_records() {
+        let config_id = 9_001;
+        publish_http_log(record(config_id, "a"));
+        publish_http_log(record(config_id, "b"));
+
+        let stream = subscribe_http_logs(config_id).into_stream();
+        publish_http_log(record(config_id, "c"));
+        publish_http_log(record(config_id + 1, "other"));
+
+        let trace_ids: Vec<String> = stream.take(3).map(|r| r.trace_id).collect().await;
+        assert_eq!(trace_ids, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_try_recv_drains_without_waiting() {
+        let config_id = 9_050;
+        publish_http_log(record(config_id, "a"));
+
+        let mut subscription = subscribe_http_logs(config_id);
+        publish_http_log(record(config_id, "b"));
+        assert_eq!(subscription.try_recv().unwrap().trace_id, "a");
+        assert_eq!(subscription.try_recv().unwrap().trace_id, "b");
+        assert!(subscription.try_recv().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_history_is_bounded() {
+        let config_id = 9_100;
+        for i in 0..HTTP_LOG_RING_SIZE + 5 {
+            publish_http_log(record(config_id, &i.to_string()));
+        }
+
+        let mut subscription = subscribe_http_logs(config_id);
+        assert_eq!(subscription.backlog.len(), HTTP_LOG_RING_SIZE);
+        assert_eq!(subscription.recv().await.unwrap().trace_id, "5");
+    }
+}
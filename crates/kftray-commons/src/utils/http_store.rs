@@ -0,0 +1,299 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use k8s_openapi::chrono::{
+    DateTime,
+    Duration,
+};
+use lazy_static::lazy_static;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{
+    QueryBuilder,
+    Row,
+    Sqlite,
+    SqlitePool,
+};
+
+use crate::db::get_db_pool;
+use crate::models::http_log_model::{
+    HttpLogKind,
+    HttpLogRecord,
+};
+use crate::models::http_transaction_model::{
+    HttpTransaction,
+    HttpTransactionQuery,
+};
+
+const DEFAULT_QUERY_LIMIT: i64 = 500;
+
+lazy_static! {
+    static ref HTTP_STORE_ENABLED: Mutex<HashSet<i64>> = Mutex::new(HashSet::new());
+}
+
+pub fn set_http_store_enabled(config_id: i64, enabled: bool) {
+    let mut configs = HTTP_STORE_ENABLED.lock().unwrap();
+    if enabled {
+        configs.insert(config_id);
+    } else {
+        configs.remove(&config_id);
+    }
+}
+
+pub fn is_http_store_enabled(config_id: i64) -> bool {
+    HTTP_STORE_ENABLED.lock().unwrap().contains(&config_id)
+}
+
+fn record_millis(record: &HttpLogRecord) -> Result<i64, String> {
+    let timestamp = DateTime::parse_from_rfc3339(&record.timestamp).map_err(|e| e.to_string())?;
+    let requested_at = match record.kind {
+        HttpLogKind::Request => timestamp,
+        HttpLogKind::Response => {
+            timestamp - Duration::milliseconds(record.took_ms.unwrap_or_default())
+        }
+    };
+
+    Ok(requested_at.timestamp_millis())
+}
+
+pub async fn store_http_log_record(record: &HttpLogRecord) -> Result<(), String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    store_http_log_record_with_pool(record, &pool).await
+}
+
+pub async fn store_http_log_record_with_pool(
+    record: &HttpLogRecord, pool: &SqlitePool,
+) -> Result<(), String> {
+    let requested_at = record_millis(record)?;
+    let headers = serde_json::to_string(&record.headers).map_err(|e| e.to_string())?;
+
+    let query = match record.kind {
+        HttpLogKind::Request => {
+            "INSERT INTO http_transactions
+                 (config_id, trace_id, requested_at, method, path, request_headers, request_body)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(trace_id) DO UPDATE SET
+                 requested_at = excluded.requested_at,
+                 method = excluded.method,
+                 path = excluded.path,
+                 request_headers = excluded.request_headers,
+                 request_body = excluded.request_body"
+        }
+        HttpLogKind::Response => {
+            "INSERT INTO http_transactions
+                 (config_id, trace_id, requested_at, status, took_ms, response_headers, response_body)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(trace_id) DO UPDATE SET
+                 status = excluded.status,
+                 took_ms = excluded.took_ms,
+                 response_headers = excluded.response_headers,
+                 response_body = excluded.response_body"
+        }
+    };
+
+    let statement = sqlx::query(query)
+        .bind(record.config_id)
+        .bind(&record.trace_id)
+        .bind(requested_at);
+
+    let statement = match record.kind {
+        HttpLogKind::Request => statement.bind(&record.method).bind(&record.path),
+        HttpLogKind::Response => statement.bind(record.status).bind(record.took_ms),
+    };
+
+    statement
+        .bind(headers)
+        .bind(&record.body)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+fn parse_headers(row: &SqliteRow, column: &str) -> Result<Vec<(String, String)>, String> {
+    let raw: String = row.try_get(column).map_err(|e| e.to_string())?;
+    serde_json::from_str(&raw).map_err(|e| e.to_string())
+}
+
+fn row_to_transaction(row: &SqliteRow) -> Result<HttpTransaction, String> {
+    Ok(HttpTransaction {
+        id: row.try_get("id").map_err(|e| e.to_string())?,
+        config_id: row.try_get("config_id").map_err(|e| e.to_string())?,
+        trace_id: row.try_get("trace_id").map_err(|e| e.to_string())?,
+        requested_at: row.try_get("requested_at").map_err(|e| e.to_string())?,
+        method: row.try_get("method").map_err(|e| e.to_string())?,
+        path: row.try_get("path").map_err(|e| e.to_string())?,
+        status: row.try_get("status").map_err(|e| e.to_string())?,
+        took_ms: row.try_get("took_ms").map_err(|e| e.to_string())?,
+        request_headers: parse_headers(row, "request_headers")?,
+        request_body: row.try_get("request_body").map_err(|e| e.to_string())?,
+        response_headers: parse_headers(row, "response_headers")?,
+        response_body: row.try_get("response_body").map_err(|e| e.to_string())?,
+    })
+}
+
+pub async fn query_http_transactions(
+    query: &HttpTransactionQuery,
+) -> Result<Vec<HttpTransaction>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    query_http_transactions_with_pool(query, &pool).await
+}
+
+pub async fn query_http_transactions_with_pool(
+    query: &HttpTransactionQuery, pool: &SqlitePool,
+) -> Result<Vec<HttpTransaction>, String> {
+    let mut builder: QueryBuilder<Sqlite> =
+        QueryBuilder::new("SELECT * FROM http_transactions WHERE 1 = 1");
+
+    if let Some(config_id) = query.config_id {
+        builder.push(" AND config_id = ").push_bind(config_id);
+    }
+    if let Some(method) = &query.method {
+        builder
+            .push(" AND method = ")
+            .push_bind(method.to_uppercase());
+    }
+    if let Some(prefix) = &query.path_prefix {
+        builder
+            .push(" AND instr(path, ")
+            .push_bind(prefix.clone())
+            .push(") = 1");
+    }
+    if let Some(status_min) = query.status_min {
+        builder.push(" AND status >= ").push_bind(status_min);
+    }
+    if let Some(status_max) = query.status_max {
+        builder.push(" AND status <= ").push_bind(status_max);
+    }
+    if let Some(since_ms) = query.since_ms {
+        builder.push(" AND requested_at >= ").push_bind(since_ms);
+    }
+    if let Some(until_ms) = query.until_ms {
+        builder.push(" AND requested_at <= ").push_bind(until_ms);
+    }
+
+    builder
+        .push(" ORDER BY requested_at DESC LIMIT ")
+        .push_bind(query.limit.unwrap_or(DEFAULT_QUERY_LIMIT));
+
+    let rows = builder
+        .build()
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    rows.iter().map(row_to_transaction).collect()
+}
+
+pub async fn clear_http_transactions(config_id: Option<i64>) -> Result<u64, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    clear_http_transactions_with_pool(config_id, &pool).await
+}
+
+pub async fn clear_http_transactions_with_pool(
+    config_id: Option<i64>, pool: &SqlitePool,
+) -> Result<u64, String> {
+    let result = match config_id {
+        Some(config_id) => {
+            sqlx::query("DELETE FROM http_transactions WHERE config_id = ?1")
+                .bind(config_id)
+                .execute(pool)
+                .await
+        }
+        None => {
+            sqlx::query("DELETE FROM http_transactions")
+                .execute(pool)
+                .await
+        }
+    };
+
+    result
+        .map(|result| result.rows_affected())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_memory_db_pool;
+    use crate::models::config_model::Config;
+    use crate::utils::config::insert_config_with_pool;
+
+    fn record(
+        kind: HttpLogKind, trace_id: &str, timestamp: &str, path: &str, status: Option<u16>,
+    ) -> HttpLogRecord {
+        HttpLogRecord {
+            config_id: 1,
+            trace_id: trace_id.to_string(),
+            kind,
+            timestamp: timestamp.to_string(),
+            method: Some("GET".to_string()),
+            path: Some(path.to_string()),
+            status,
+            headers: vec![("host".to_string(), "localhost".to_string())],
+            body: None,
+            original_size: None,
+            took_ms: status.map(|_| 20),
+            spec_violations: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_and_query_transactions() {
+        let pool = create_memory_db_pool().await.unwrap();
+        insert_config_with_pool(Config::default(), &pool)
+            .await
+            .unwrap();
+
+        let transactions = [
+            ("a", "2024-01-01T10:00:00Z", "/api/orders/1", 200),
+            ("b", "2024-01-01T10:30:00Z", "/api/orders/2", 503),
+            ("c", "2024-01-01T10:45:00Z", "/api/users", 500),
+        ];
+        for (trace_id, timestamp, path, status) in transactions {
+            let response = record(
+                HttpLogKind::Response,
+                trace_id,
+                timestamp,
+                path,
+                Some(status),
+            );
+            store_http_log_record_with_pool(&response, &pool)
+                .await
+                .unwrap();
+            let request = record(HttpLogKind::Request, trace_id, timestamp, path, None);
+            store_http_log_record_with_pool(&request, &pool)
+                .await
+                .unwrap();
+        }
+
+        let query = HttpTransactionQuery {
+            config_id: Some(1),
+            path_prefix: Some("/api/orders".to_string()),
+            status_min: Some(500),
+            status_max: Some(599),
+            since_ms: Some(
+                DateTime::parse_from_rfc3339("2024-01-01T10:15:00Z")
+                    .unwrap()
+                    .timestamp_millis(),
+            ),
+            ..Default::default()
+        };
+        let results = query_http_transactions_with_pool(&query, &pool)
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].trace_id, "b");
+        assert_eq!(results[0].status, Some(503));
+        assert_eq!(results[0].method.as_deref(), Some("GET"));
+        assert_eq!(results[0].request_headers.len(), 1);
+
+        assert_eq!(
+            clear_http_transactions_with_pool(Some(1), &pool)
+                .await
+                .unwrap(),
+            3
+        );
+    }
+}
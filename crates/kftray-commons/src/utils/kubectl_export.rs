@@ -0,0 +1,193 @@
+use crate::models::config_model::Config;
+
+fn config_label(config: &Config) -> String {
+    config
+        .alias
+        .clone()
+        .or_else(|| config.service.clone())
+        .or_else(|| config.target.clone())
+        .or_else(|| config.id.map(|id| format!("config {}", id)))
+        .unwrap_or_else(|| "config".to_string())
+}
+
+// single quotes unless the value is plain enough to go without
+pub fn shell_quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=,@%+".contains(c));
+    if plain {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+// the cluster flags every kubectl call of the config shares. kftray takes a
+// colon separated list of kubeconfigs, kubectl only reads that from the env
+fn kubectl_base(config: &Config) -> String {
+    let mut base = match config.kubeconfig.as_deref() {
+        Some(kubeconfig) if !kubeconfig.is_empty() && kubeconfig != "default" => {
+            format!("KUBECONFIG={} kubectl", shell_quote(kubeconfig))
+        }
+        _ => "kubectl".to_string(),
+    };
+    if !config.context.is_empty() {
+        base.push_str(&format!(" --context {}", shell_quote(&config.context)));
+    }
+    if !config.namespace.is_empty() {
+        base.push_str(&format!(" -n {}", shell_quote(&config.namespace)));
+    }
+    base
+}
+
+// the `kubectl port-forward` equivalent of a config. kubectl can't pick pods by
+// label, so pod configs look the first match up when the command runs. udp and
+// proxied forwards need kftray's proxy pod and have no equivalent
+pub fn kubectl_command(config: &Config) -> Result<String, String> {
+    if config.protocol == "udp" {
+        return Err("kubectl only forwards TCP".to_string());
+    }
+    let remote_port = config
+        .remote_port
+        .ok_or_else(|| "no remote port".to_string())?;
+    let base = kubectl_base(config);
+
+    let resource = match config.workload_type.as_deref() {
+        Some("service") | None => {
+            let service = config
+                .service
+                .as_deref()
+                .filter(|service| !service.is_empty())
+                .ok_or_else(|| "no service".to_string())?;
+            shell_quote(&format!("svc/{}", service))
+        }
+        Some("pod") => {
+            let selector = config
+                .target
+                .as_deref()
+                .filter(|selector| !selector.is_empty())
+                .ok_or_else(|| "no pod selector".to_string())?;
+            format!(
+                "\"$({} get pod -l {} -o name | head -n 1)\"",
+                base,
+                shell_quote(selector)
+            )
+        }
+        Some(workload_type) => {
+            return Err(format!(
+                "{} forwards run through a proxy pod kubectl doesn't deploy",
+                workload_type
+            ))
+        }
+    };
+
+    // no local port lets kubectl pick one
+    let ports = match config.local_port.filter(|port| *port != 0) {
+        Some(local_port) => format!("{}:{}", local_port, remote_port),
+        None => format!(":{}", remote_port),
+    };
+    let mut command = format!("{} port-forward {} {}", base, resource, ports);
+    if let Some(address) = config
+        .local_address
+        .as_deref()
+        .filter(|address| !address.is_empty() && *address != "127.0.0.1")
+    {
+        command.push_str(&format!(" --address {}", shell_quote(address)));
+    }
+    Ok(command)
+}
+
+// one command per line, what can't be exported is left as a comment
+pub fn kubectl_commands(configs: &[Config]) -> String {
+    configs
+        .iter()
+        .map(|config| match kubectl_command(config) {
+            Ok(command) => command,
+            Err(e) => format!("# {}: {}", config_label(config), e),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// runs every forward in the background and stops them all together
+pub fn kubectl_script(configs: &[Config]) -> String {
+    let mut script = vec![
+        "#!/usr/bin/env bash".to_string(),
+        "# port forwards exported from kftray, Ctrl+C stops them all".to_string(),
+        "set -euo pipefail".to_string(),
+        String::new(),
+        "trap 'kill $(jobs -p) 2>/dev/null' EXIT".to_string(),
+        String::new(),
+    ];
+    for config in configs {
+        let label = config_label(config);
+        match kubectl_command(config) {
+            Ok(command) => {
+                script.push(format!("# {}", label));
+                script.push(format!("{} &", command));
+            }
+            Err(e) => script.push(format!("# skipped {}: {}", label, e)),
+        }
+    }
+    script.push(String::new());
+    script.push("wait".to_string());
+    script.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kubectl_command() {
+        let service = Config {
+            context: "prod".to_string(),
+            namespace: "default".to_string(),
+            service: Some("api".to_string()),
+            workload_type: Some("service".to_string()),
+            local_port: Some(8080),
+            remote_port: Some(80),
+            local_address: Some("127.0.0.2".to_string()),
+            kubeconfig: Some("/home/me/a.yaml:/home/me/my b.yaml".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            kubectl_command(&service).unwrap(),
+            "KUBECONFIG='/home/me/a.yaml:/home/me/my b.yaml' kubectl --context prod -n default \
+             port-forward svc/api 8080:80 --address 127.0.0.2"
+        );
+
+        let pod = Config {
+            context: "prod".to_string(),
+            namespace: "db".to_string(),
+            target: Some("app=pg".to_string()),
+            workload_type: Some("pod".to_string()),
+            local_port: Some(0),
+            remote_port: Some(5432),
+            ..Default::default()
+        };
+        assert_eq!(
+            kubectl_command(&pod).unwrap(),
+            "kubectl --context prod -n db port-forward \"$(kubectl --context prod -n db get pod -l \
+             app=pg -o name | head -n 1)\" :5432"
+        );
+
+        let proxy = Config {
+            alias: Some("tunnel".to_string()),
+            workload_type: Some("proxy".to_string()),
+            remote_port: Some(5432),
+            ..Default::default()
+        };
+        assert!(kubectl_command(&proxy).is_err());
+
+        let script = kubectl_script(&[service, proxy]);
+        assert!(script.starts_with("#!/usr/bin/env bash\n"));
+        assert!(script.contains("svc/api 8080:80 --address 127.0.0.2 &\n"));
+        assert!(script.contains("# skipped tunnel: proxy forwards"));
+        assert!(script.ends_with("wait\n"));
+
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+}
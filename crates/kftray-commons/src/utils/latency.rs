@@ -0,0 +1,109 @@
+use std::collections::{
+    HashMap,
+    VecDeque,
+};
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+
+use crate::models::latency_model::{
+    LatencyStats,
+    Percentiles,
+};
+
+const MAX_LATENCY_SAMPLES: usize = 1000;
+
+#[derive(Clone, Copy, Debug)]
+struct LatencySample {
+    ttfb_ms: i64,
+    total_ms: i64,
+}
+
+lazy_static! {
+    static ref LATENCY_SAMPLES: Mutex<HashMap<i64, VecDeque<LatencySample>>> =
+        Mutex::new(HashMap::new());
+}
+
+pub fn record_latency(config_id: i64, ttfb_ms: i64, total_ms: i64) {
+    let mut samples = LATENCY_SAMPLES.lock().unwrap();
+    let entries = samples.entry(config_id).or_default();
+
+    if entries.len() == MAX_LATENCY_SAMPLES {
+        entries.pop_front();
+    }
+    entries.push_back(LatencySample {
+        ttfb_ms: ttfb_ms.max(0),
+        total_ms: total_ms.max(ttfb_ms).max(0),
+    });
+}
+
+fn nearest_rank(sorted: &[i64], percentile: f64) -> i64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+
+    let rank = ((percentile / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+pub fn percentiles(mut values: Vec<i64>) -> Percentiles {
+    values.sort_unstable();
+
+    Percentiles {
+        p50: nearest_rank(&values, 50.0),
+        p90: nearest_rank(&values, 90.0),
+        p95: nearest_rank(&values, 95.0),
+        p99: nearest_rank(&values, 99.0),
+        max: values.last().copied().unwrap_or_default(),
+    }
+}
+
+pub fn get_latency_stats(config_id: i64) -> LatencyStats {
+    let samples = LATENCY_SAMPLES.lock().unwrap();
+    let Some(entries) = samples.get(&config_id) else {
+        return LatencyStats {
+            config_id,
+            ..Default::default()
+        };
+    };
+
+    LatencyStats {
+        config_id,
+        count: entries.len(),
+        ttfb_ms: percentiles(entries.iter().map(|s| s.ttfb_ms).collect()),
+        total_ms: percentiles(entries.iter().map(|s| s.total_ms).collect()),
+    }
+}
+
+pub fn clear_latency_stats(config_id: i64) {
+    LATENCY_SAMPLES.lock().unwrap().remove(&config_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let stats = percentiles((1..=100).rev().collect());
+        assert_eq!(stats.p50, 50);
+        assert_eq!(stats.p90, 90);
+        assert_eq!(stats.p99, 99);
+        assert_eq!(stats.max, 100);
+
+        assert_eq!(percentiles(Vec::new()), Percentiles::default());
+    }
+
+    #[test]
+    fn test_latency_stats_are_bounded_per_config() {
+        let config_id = 7_001;
+        for i in 0..(MAX_LATENCY_SAMPLES as i64 + 10) {
+            record_latency(config_id, i, i * 2);
+        }
+
+        let stats = get_latency_stats(config_id);
+        assert_eq!(stats.count, MAX_LATENCY_SAMPLES);
+        assert_eq!(stats.ttfb_ms.max, MAX_LATENCY_SAMPLES as i64 + 9);
+        assert_eq!(get_latency_stats(config_id + 1).count, 0);
+    }
+}
@@ -1,11 +1,18 @@
 use std::io::Read;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{
+    Arc,
+    Mutex as StdMutex,
+};
 
 use anyhow::Context;
 use bytes::Bytes;
 use dashmap::DashMap;
-use flate2::read::GzDecoder;
+use flate2::read::{
+    DeflateDecoder,
+    GzDecoder,
+    ZlibDecoder,
+};
 use httparse::Status;
 use k8s_openapi::chrono::{
     DateTime,
@@ -34,12 +41,25 @@ use tracing::{
 };
 use uuid::Uuid;
 
+use crate::models::http_log_model::{
+    HttpLogKind,
+    HttpLogRecord,
+};
 use crate::utils::config_dir::get_log_folder_path;
+use crate::utils::http_log_stream::publish_http_log;
+use crate::utils::http_store::{
+    is_http_store_enabled,
+    store_http_log_record,
+};
+use crate::utils::latency::record_latency;
+use crate::utils::openapi::openapi_spec;
 
 #[derive(Clone, Debug)]
 pub struct Logger {
     log_sender: Sender<LogMessage>,
     trace_map: TraceMap,
+    in_flight: Arc<StdMutex<Option<InFlight>>>,
+    config_id: i64,
 }
 
 type TraceMap = Arc<DashMap<String, TraceInfo>>;
@@ -48,10 +68,22 @@ type TraceMap = Arc<DashMap<String, TraceInfo>>;
 struct TraceInfo {
     trace_id: String,
     timestamp: DateTime<Utc>,
+    // what the response answers, to check it against the spec
+    method: Option<String>,
+    path: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+struct InFlight {
+    request_id: String,
+    trace_id: String,
+    requested_at: DateTime<Utc>,
+    first_byte_at: DateTime<Utc>,
+    last_byte_at: DateTime<Utc>,
 }
 
 impl Logger {
-    pub async fn new(log_file_path: PathBuf) -> anyhow::Result<Self> {
+    pub async fn new(log_file_path: PathBuf, config_id: i64) -> anyhow::Result<Self> {
         let (log_sender, mut log_receiver) = mpsc::channel(100);
         let log_file = Arc::new(RwLock::new(BufWriter::new(
             OpenOptions::new()
@@ -73,10 +105,14 @@ impl Logger {
         Ok(Self {
             log_sender,
             trace_map,
+            in_flight: Arc::new(StdMutex::new(None)),
+            config_id,
         })
     }
 
     pub async fn log_request(&self, buffer: Bytes) -> String {
+        self.finish();
+
         let request_id = Uuid::new_v4().to_string();
         let timestamp = Utc::now();
         debug!("Generated request ID: {}", request_id);
@@ -86,17 +122,21 @@ impl Logger {
             "Generated trace ID: {} for request ID: {}",
             trace_id, request_id
         );
+        let (method, path) = request_target(&buffer);
         self.trace_map.insert(
             request_id.clone(),
             TraceInfo {
                 trace_id: trace_id.clone(),
                 timestamp,
+                method,
+                path,
             },
         );
 
         let log_sender = self.log_sender.clone();
+        let config_id = self.config_id;
         tokio::spawn(async move {
-            log_request(buffer, log_sender, trace_id, timestamp)
+            log_request(buffer, log_sender, config_id, trace_id, timestamp)
                 .await
                 .unwrap_or_else(|e| error!("Failed to log request: {:?}", e));
         });
@@ -110,16 +150,52 @@ impl Logger {
 
         if let Some((_, trace_info)) = self.trace_map.remove(&request_id) {
             let took = calculate_time_diff(trace_info.timestamp, timestamp);
+            *self.in_flight.lock().unwrap() = Some(InFlight {
+                request_id: request_id.clone(),
+                trace_id: trace_info.trace_id.clone(),
+                requested_at: trace_info.timestamp,
+                first_byte_at: timestamp,
+                last_byte_at: timestamp,
+            });
+
             let log_sender = self.log_sender.clone();
+            let config_id = self.config_id;
             spawn(async move {
-                log_response(buffer, log_sender, trace_info.trace_id, timestamp, took)
+                log_response(buffer, log_sender, config_id, trace_info, timestamp, took)
                     .await
                     .unwrap_or_else(|e| error!("Failed to log response: {:?}", e));
             });
+        } else if let Some(in_flight) = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .as_mut()
+            .filter(|in_flight| in_flight.request_id == request_id)
+        {
+            in_flight.last_byte_at = timestamp;
         } else {
             error!("Trace ID not found for request ID: {}", request_id);
         }
     }
+
+    pub fn finish(&self) {
+        let Some(in_flight) = self.in_flight.lock().unwrap().take() else {
+            return;
+        };
+
+        let ttfb = calculate_time_diff(in_flight.requested_at, in_flight.first_byte_at);
+        let total = calculate_time_diff(in_flight.requested_at, in_flight.last_byte_at);
+        record_latency(self.config_id, ttfb, total);
+
+        let log_entry = format_timing_log(&in_flight.trace_id, ttfb, total);
+        if self
+            .log_sender
+            .try_send(LogMessage::Timing(log_entry))
+            .is_err()
+        {
+            error!("Log channel is full, dropping log message");
+        }
+    }
 }
 
 pub async fn create_log_file_path(
@@ -138,37 +214,350 @@ pub async fn create_log_file_path(
     Ok(path)
 }
 
+fn request_target(buffer: &[u8]) -> (Option<String>, Option<String>) {
+    let mut headers = [httparse::EMPTY_HEADER; 64];
+    let mut req = httparse::Request::new(&mut headers);
+    let _ = req.parse(buffer);
+    (req.method.map(str::to_string), req.path.map(str::to_string))
+}
+
+fn spec_violations(
+    record: &HttpLogRecord, method: Option<&str>, path: Option<&str>,
+) -> Vec<String> {
+    match (openapi_spec(record.config_id), method, path) {
+        (Some(spec), Some(method), Some(path)) => spec.check_record(record, method, path),
+        _ => Vec::new(),
+    }
+}
+
+fn append_spec_violations(log_entry: &mut String, violations: &[String]) {
+    if violations.is_empty() {
+        return;
+    }
+
+    log_entry.push_str("\nSpec mismatches:\n");
+    for violation in violations {
+        log_entry.push_str(&format!("- {}\n", violation));
+    }
+}
+
 async fn log_request(
-    buffer: Bytes, log_sender: Sender<LogMessage>, trace_id: String, timestamp: DateTime<Utc>,
+    buffer: Bytes, log_sender: Sender<LogMessage>, config_id: i64, trace_id: String,
+    timestamp: DateTime<Utc>,
 ) -> anyhow::Result<()> {
     debug!("Logging request with trace ID: {}", trace_id);
     let mut headers = [httparse::EMPTY_HEADER; 64];
     let mut req = httparse::Request::new(&mut headers);
     req.parse(&buffer)?;
 
-    let log_entry = format_request_log(&buffer, &trace_id, timestamp).await?;
+    let mut log_entry = format_request_log(&buffer, &trace_id, timestamp).await?;
+    let mut record = build_request_record(&buffer, config_id, &trace_id, timestamp).await;
+    record.spec_violations =
+        spec_violations(&record, record.method.as_deref(), record.path.as_deref());
+    append_spec_violations(&mut log_entry, &record.spec_violations);
     if log_sender.try_send(LogMessage::Request(log_entry)).is_err() {
         error!("Log channel is full, dropping log message");
     }
+
+    persist_record(&record).await;
+    publish_http_log(record);
     Ok(())
 }
 
 async fn log_response(
-    buffer: Bytes, log_sender: tokio::sync::mpsc::Sender<LogMessage>, trace_id: String,
-    timestamp: DateTime<Utc>, took: i64,
+    buffer: Bytes, log_sender: tokio::sync::mpsc::Sender<LogMessage>, config_id: i64,
+    trace_info: TraceInfo, timestamp: DateTime<Utc>, took: i64,
 ) -> anyhow::Result<()> {
+    let trace_id = trace_info.trace_id;
     debug!("Logging response with trace ID: {}", trace_id);
     let mut headers = [httparse::EMPTY_HEADER; 64];
     let mut res = httparse::Response::new(&mut headers);
     res.parse(&buffer)?;
 
-    let log_entry = format_response_log(&buffer, &trace_id, timestamp, took).await?;
+    let mut log_entry = format_response_log(&buffer, &trace_id, timestamp, took).await?;
+    let mut record = build_response_record(&buffer, config_id, &trace_id, timestamp, took).await;
+    record.spec_violations = spec_violations(
+        &record,
+        trace_info.method.as_deref(),
+        trace_info.path.as_deref(),
+    );
+    append_spec_violations(&mut log_entry, &record.spec_violations);
     if log_sender
         .try_send(LogMessage::Response(log_entry))
         .is_err()
     {
         error!("Log channel is full, dropping log message");
     }
+
+    persist_record(&record).await;
+    publish_http_log(record);
+    Ok(())
+}
+
+async fn persist_record(record: &HttpLogRecord) {
+    if !is_http_store_enabled(record.config_id) {
+        return;
+    }
+
+    if let Err(e) = store_http_log_record(record).await {
+        error!("Failed to store HTTP log record {}: {}", record.trace_id, e);
+    }
+}
+
+fn header_pairs(headers: &[httparse::Header<'_>]) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|h| {
+            (
+                h.name.to_string(),
+                std::str::from_utf8(h.value).unwrap_or("").to_string(),
+            )
+        })
+        .collect()
+}
+
+struct DecodedBody {
+    bytes: Vec<u8>,
+    wire_size: usize,
+    transforms: Vec<String>,
+}
+
+fn header_value<'a>(headers: &[httparse::Header<'a>], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|h| h.name.eq_ignore_ascii_case(name))
+        .and_then(|h| std::str::from_utf8(h.value).ok())
+}
+
+fn header_tokens(headers: &[httparse::Header<'_>], name: &str) -> Vec<String> {
+    headers
+        .iter()
+        .filter(|h| h.name.eq_ignore_ascii_case(name))
+        .filter_map(|h| std::str::from_utf8(h.value).ok())
+        .flat_map(|value| value.split(','))
+        .map(|token| token.trim().to_ascii_lowercase())
+        .filter(|token| !token.is_empty() && token != "identity")
+        .collect()
+}
+
+pub fn decode_chunked(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(data.len());
+    let mut rest = data;
+
+    while let Some(line_end) = rest.windows(2).position(|window| window == b"\r\n") {
+        let size_line = std::str::from_utf8(&rest[..line_end])?;
+        let size_hex = size_line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_hex, 16)
+            .with_context(|| format!("Invalid chunk size: {:?}", size_hex))?;
+        rest = &rest[line_end + 2..];
+
+        if size == 0 {
+            break;
+        }
+
+        let available = size.min(rest.len());
+        decoded.extend_from_slice(&rest[..available]);
+        if available < size {
+            break;
+        }
+
+        rest = rest.get(size + 2..).unwrap_or_default();
+    }
+
+    Ok(decoded)
+}
+
+fn raw_body(
+    buffer: &[u8], headers: &[httparse::Header<'_>],
+) -> Option<anyhow::Result<(Vec<u8>, bool)>> {
+    let headers_end = buffer.windows(4).position(|window| window == b"\r\n\r\n")?;
+    let body_start = headers_end + 4;
+
+    if header_tokens(headers, "transfer-encoding")
+        .iter()
+        .any(|token| token == "chunked")
+    {
+        return Some(decode_chunked(&buffer[body_start..]).map(|body| (body, true)));
+    }
+
+    let content_length = header_value(headers, "content-length")
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+
+    buffer
+        .get(body_start..body_start + content_length)
+        .map(|body| Ok((body.to_vec(), false)))
+}
+
+pub fn decode_content(data: Vec<u8>, encodings: &[String]) -> anyhow::Result<Vec<u8>> {
+    let mut data = data;
+
+    for encoding in encodings.iter().rev() {
+        let mut decoded = Vec::new();
+        match encoding.as_str() {
+            "gzip" | "x-gzip" => {
+                GzDecoder::new(&data[..]).read_to_end(&mut decoded)?;
+            }
+            "deflate" => {
+                if ZlibDecoder::new(&data[..])
+                    .read_to_end(&mut decoded)
+                    .is_err()
+                {
+                    decoded.clear();
+                    DeflateDecoder::new(&data[..]).read_to_end(&mut decoded)?;
+                }
+            }
+            "br" => {
+                brotli::Decompressor::new(&data[..], 4096).read_to_end(&mut decoded)?;
+            }
+            other => anyhow::bail!("Unsupported content encoding: {}", other),
+        }
+        data = decoded;
+    }
+
+    Ok(data)
+}
+
+async fn extract_body(
+    buffer: &[u8], headers: &[httparse::Header<'_>],
+) -> Option<anyhow::Result<DecodedBody>> {
+    let (body, chunked) = match raw_body(buffer, headers)? {
+        Ok(body) => body,
+        Err(e) => return Some(Err(e)),
+    };
+
+    let wire_size = body.len();
+    let encodings = header_tokens(headers, "content-encoding");
+
+    let mut transforms = Vec::new();
+    if chunked {
+        transforms.push("chunked".to_string());
+    }
+    transforms.extend(encodings.iter().cloned());
+
+    let decoded = if encodings.is_empty() {
+        Ok(body)
+    } else {
+        task::spawn_blocking(move || decode_content(body, &encodings))
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|result| result)
+    };
+
+    Some(decoded.map(|bytes| DecodedBody {
+        bytes,
+        wire_size,
+        transforms,
+    }))
+}
+
+fn render_body(body: &[u8], headers: &[httparse::Header<'_>]) -> anyhow::Result<String> {
+    if body.is_empty() {
+        return Ok("<empty>".to_string());
+    }
+
+    if let Ok(body_str) = std::str::from_utf8(body) {
+        let json_value = is_json(headers)
+            .then(|| serde_json::from_str::<Value>(body_str).ok())
+            .flatten();
+
+        if let Some(json_value) = json_value {
+            Ok(serde_json::to_string_pretty(&json_value)?)
+        } else {
+            Ok(body_str.trim_end().to_string())
+        }
+    } else if is_image(headers) {
+        Ok("<image>".to_string())
+    } else {
+        Ok("<binary>".to_string())
+    }
+}
+
+async fn record_body(
+    buffer: &[u8], headers: &[httparse::Header<'_>],
+) -> (Option<String>, Option<usize>) {
+    match extract_body(buffer, headers).await {
+        Some(Ok(body)) => {
+            let original_size = (!body.transforms.is_empty()).then_some(body.wire_size);
+            (render_body(&body.bytes, headers).ok(), original_size)
+        }
+        Some(Err(e)) => (Some(format!("Failed to decode body: {:?}", e)), None),
+        None => (None, None),
+    }
+}
+
+async fn build_request_record(
+    buffer: &[u8], config_id: i64, trace_id: &str, timestamp: DateTime<Utc>,
+) -> HttpLogRecord {
+    let mut headers = [httparse::EMPTY_HEADER; 64];
+    let mut req = httparse::Request::new(&mut headers);
+    let (body, original_size) = match req.parse(buffer) {
+        Ok(Status::Complete(_)) => record_body(buffer, req.headers).await,
+        _ => (None, None),
+    };
+
+    HttpLogRecord {
+        config_id,
+        trace_id: trace_id.to_string(),
+        kind: HttpLogKind::Request,
+        timestamp: timestamp.to_rfc3339(),
+        method: req.method.map(str::to_string),
+        path: req.path.map(str::to_string),
+        status: None,
+        headers: header_pairs(req.headers),
+        body,
+        original_size,
+        took_ms: None,
+        spec_violations: Vec::new(),
+    }
+}
+
+async fn build_response_record(
+    buffer: &[u8], config_id: i64, trace_id: &str, timestamp: DateTime<Utc>, took: i64,
+) -> HttpLogRecord {
+    let mut headers = [httparse::EMPTY_HEADER; 64];
+    let mut res = httparse::Response::new(&mut headers);
+    let (body, original_size) = match res.parse(buffer) {
+        Ok(Status::Complete(_)) => record_body(buffer, res.headers).await,
+        _ => (None, None),
+    };
+
+    HttpLogRecord {
+        config_id,
+        trace_id: trace_id.to_string(),
+        kind: HttpLogKind::Response,
+        timestamp: timestamp.to_rfc3339(),
+        method: None,
+        path: None,
+        status: res.code,
+        headers: header_pairs(res.headers),
+        body,
+        original_size,
+        took_ms: Some(took),
+        spec_violations: Vec::new(),
+    }
+}
+
+async fn append_body(
+    buffer: &[u8], log_entry: &mut String, headers: &[httparse::Header<'_>], label: &str,
+) -> anyhow::Result<()> {
+    match extract_body(buffer, headers).await {
+        Some(Ok(body)) => {
+            log_body(&body.bytes, log_entry, headers).await?;
+            if !body.transforms.is_empty() {
+                log_entry.push_str(&format!(
+                    "(decoded from {} bytes, {})\n",
+                    body.wire_size,
+                    body.transforms.join(", ")
+                ));
+            }
+        }
+        Some(Err(e)) => {
+            log_entry.push_str(&format!("Failed to decode {} body: {:?}\n", label, e));
+        }
+        None => {}
+    }
+
     Ok(())
 }
 
@@ -192,49 +581,11 @@ async fn format_request_log(
             req.path.unwrap_or(""),
             req.version.unwrap_or(0)
         ));
-        for header in req.headers.iter() {
-            log_entry.push_str(&format!(
-                "{}: {}\n",
-                header.name,
-                std::str::from_utf8(header.value).unwrap_or("")
-            ));
+        for (name, value) in header_pairs(req.headers) {
+            log_entry.push_str(&format!("{}: {}\n", name, value));
         }
 
-        if let Some(headers_end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
-            let body_start = headers_end + 4;
-            let content_length = req
-                .headers
-                .iter()
-                .find_map(|h| {
-                    if h.name.eq_ignore_ascii_case("content-length") {
-                        std::str::from_utf8(h.value)
-                            .ok()
-                            .and_then(|v| v.parse::<usize>().ok())
-                    } else {
-                        None
-                    }
-                })
-                .unwrap_or(0);
-
-            if let Some(body) = buffer.get(body_start..body_start + content_length) {
-                if req.headers.iter().any(|h| {
-                    h.name.eq_ignore_ascii_case("content-encoding")
-                        && h.value.eq_ignore_ascii_case(b"gzip")
-                }) {
-                    match decompress_gzip(body).await {
-                        Ok(decompressed_body) => {
-                            log_body(&decompressed_body, &mut log_entry, req.headers).await?;
-                        }
-                        Err(e) => {
-                            log_entry
-                                .push_str(&format!("Failed to decompress request body: {:?}\n", e));
-                        }
-                    }
-                } else {
-                    log_body(body, &mut log_entry, req.headers).await?;
-                }
-            }
-        }
+        append_body(buffer, &mut log_entry, req.headers, "request").await?;
     }
 
     Ok(log_entry)
@@ -257,87 +608,48 @@ async fn format_response_log(
     let mut res = httparse::Response::new(&mut headers);
     if let Ok(Status::Complete(_)) = res.parse(buffer) {
         log_entry.push_str(&format!("Status: {}\n\nHeaders:\n", res.code.unwrap_or(0)));
-        for header in res.headers.iter() {
-            log_entry.push_str(&format!(
-                "{}: {}\n",
-                header.name,
-                std::str::from_utf8(header.value).unwrap_or("")
-            ));
+        for (name, value) in header_pairs(res.headers) {
+            log_entry.push_str(&format!("{}: {}\n", name, value));
         }
 
-        if let Some(headers_end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
-            let body_start = headers_end + 4;
-            let content_length = res
-                .headers
-                .iter()
-                .find_map(|h| {
-                    if h.name.eq_ignore_ascii_case("content-length") {
-                        std::str::from_utf8(h.value)
-                            .ok()
-                            .and_then(|v| v.parse::<usize>().ok())
-                    } else {
-                        None
-                    }
-                })
-                .unwrap_or(0);
-
-            if let Some(body) = buffer.get(body_start..body_start + content_length) {
-                if res.headers.iter().any(|h| {
-                    h.name.eq_ignore_ascii_case("content-encoding")
-                        && h.value.eq_ignore_ascii_case(b"gzip")
-                }) {
-                    match decompress_gzip(body).await {
-                        Ok(decompressed_body) => {
-                            log_body(&decompressed_body, &mut log_entry, res.headers).await?;
-                        }
-                        Err(e) => {
-                            log_entry.push_str(&format!(
-                                "Failed to decompress response body: {:?}\n",
-                                e
-                            ));
-                        }
-                    }
-                } else {
-                    log_body(body, &mut log_entry, res.headers).await?;
-                }
-            }
-        }
+        append_body(buffer, &mut log_entry, res.headers, "response").await?;
     }
 
     Ok(log_entry)
 }
 
+fn format_timing_log(trace_id: &str, ttfb: i64, total: i64) -> String {
+    format!(
+        "\n----------------------------------------\n\
+         Trace ID: {}\n\
+         Time to first byte: {} ms\n\
+         Total duration: {} ms\n",
+        trace_id, ttfb, total
+    )
+}
+
 async fn log_body(
     body: &[u8], log_entry: &mut String, headers: &[httparse::Header<'_>],
 ) -> anyhow::Result<()> {
-    if !body.is_empty() {
-        log_entry.push_str("\n\nBody:\n");
-        if let Ok(body_str) = std::str::from_utf8(body) {
-            if let Ok(json_value) = serde_json::from_str::<Value>(body_str) {
-                log_entry.push_str(&format!("{}\n", serde_json::to_string_pretty(&json_value)?));
-            } else {
-                log_entry.push_str(&format!("{}\n", body_str.trim_end()));
-            }
-        } else if is_image(headers) {
-            log_entry.push_str("<image>\n");
-        } else {
-            log_entry.push_str("<binary>\n");
-        }
-    } else {
-        log_entry.push_str("\n\nBody:\n<empty>\n");
-    }
+    log_entry.push_str("\n\nBody:\n");
+    log_entry.push_str(&render_body(body, headers)?);
+    log_entry.push('\n');
     Ok(())
 }
 
-async fn decompress_gzip(data: &[u8]) -> anyhow::Result<Vec<u8>> {
-    let data = data.to_vec();
-    task::spawn_blocking(move || {
-        let mut decoder = GzDecoder::new(&data[..]);
-        let mut decompressed_data = Vec::new();
-        decoder.read_to_end(&mut decompressed_data)?;
-        Ok(decompressed_data)
-    })
-    .await?
+fn is_json(headers: &[httparse::Header<'_>]) -> bool {
+    match header_value(headers, "content-type") {
+        Some(content_type) => {
+            let mime = content_type
+                .split(';')
+                .next()
+                .unwrap_or("")
+                .trim()
+                .to_ascii_lowercase();
+            mime == "application/json" || mime.ends_with("+json")
+        }
+        None => true,
+    }
 }
 
 fn is_image(headers: &[httparse::Header<'_>]) -> bool {
@@ -370,6 +682,7 @@ async fn write_log(
 enum LogMessage {
     Request(String),
     Response(String),
+    Timing(String),
 }
 
 impl LogMessage {
@@ -377,6 +690,7 @@ impl LogMessage {
         match self {
             LogMessage::Request(log) => log.as_bytes(),
             LogMessage::Response(log) => log.as_bytes(),
+            LogMessage::Timing(log) => log.as_bytes(),
         }
     }
 }
@@ -384,3 +698,69 @@ impl LogMessage {
 fn calculate_time_diff(start: DateTime<Utc>, end: DateTime<Utc>) -> i64 {
     (end - start).num_milliseconds()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::{
+        GzEncoder,
+        ZlibEncoder,
+    };
+    use flate2::Compression;
+
+    use super::*;
+
+    #[test]
+    fn test_decode_chunked() {
+        let body = b"4\r\nWiki\r\n6;ext=1\r\npedia \r\nE\r\nin \r\n\r\nchunks.\r\n0\r\n\r\n";
+        assert_eq!(
+            decode_chunked(body).unwrap(),
+            b"Wikipedia in \r\n\r\nchunks.".to_vec()
+        );
+        assert_eq!(decode_chunked(b"5\r\nhel").unwrap(), b"hel".to_vec());
+        assert!(decode_chunked(b"zz\r\n").is_err());
+    }
+
+    #[test]
+    fn test_decode_content() {
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(b"{\"ok\":true}").unwrap();
+        let gzipped = gzip.finish().unwrap();
+
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(&gzipped).unwrap();
+        let layered = zlib.finish().unwrap();
+
+        let encodings = vec!["gzip".to_string(), "deflate".to_string()];
+        assert_eq!(
+            decode_content(layered, &encodings).unwrap(),
+            b"{\"ok\":true}".to_vec()
+        );
+        assert!(decode_content(vec![1, 2, 3], &["zstd".to_string()]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_format_response_log_decodes_chunked_gzip() {
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(b"{\"status\":\"up\"}").unwrap();
+        let gzipped = gzip.finish().unwrap();
+
+        let mut buffer = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Encoding: gzip\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n",
+            gzipped.len()
+        )
+        .into_bytes();
+        buffer.extend_from_slice(&gzipped);
+        buffer.extend_from_slice(b"\r\n0\r\n\r\n");
+
+        let log = format_response_log(&buffer, "trace", Utc::now(), 5)
+            .await
+            .unwrap();
+        assert!(log.contains("\"status\": \"up\""));
+        assert!(log.contains(&format!(
+            "decoded from {} bytes, chunked, gzip",
+            gzipped.len()
+        )));
+    }
+}
@@ -0,0 +1,82 @@
+use crate::models::message_model::{
+    MessageCode,
+    UserMessage,
+};
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+fn english(code: MessageCode) -> &'static str {
+    match code {
+        MessageCode::ForwardAlreadyRunning => {
+            "{service} is already forwarding on 127.0.0.1:{local_port}"
+        }
+        MessageCode::ForwardStarted => {
+            "{protocol} forwarding from 127.0.0.1:{local_port} -> {remote_port}:{service}"
+        }
+        MessageCode::FanOutForwardStarted => {
+            "{protocol} forwarding from 127.0.0.1:{local_port} -> {pod}"
+        }
+        MessageCode::ForwardFailed => "Failed to start {service}: {error}",
+        MessageCode::ForwardStopped => "Service port forwarding has been stopped",
+        MessageCode::InvalidProcessKey => "Invalid composite key format",
+        MessageCode::HostsFileFailed => "Failed to update the hosts file for {service}: {error}",
+    }
+}
+
+// the template of the code in the given locale. only english ships so far,
+// a translation is another catalog matched on the locale here
+pub fn message_template(code: MessageCode, _locale: &str) -> &'static str {
+    english(code)
+}
+
+// fills the `{name}` placeholders of the template. a placeholder without a
+// param is left as is, so a missing value shows instead of vanishing
+pub fn format_message(message: &UserMessage, locale: &str) -> String {
+    let mut text = message_template(message.code, locale).to_string();
+    for (name, value) in &message.params {
+        text = text.replace(&format!("{{{}}}", name), value);
+    }
+    text
+}
+
+pub fn format_messages(messages: &[UserMessage], locale: &str) -> String {
+    messages
+        .iter()
+        .map(|message| format_message(message, locale))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_message() {
+        let started = UserMessage::new(MessageCode::ForwardStarted)
+            .with("protocol", "TCP")
+            .with("local_port", 8080)
+            .with("remote_port", 80)
+            .with("service", "web");
+        assert_eq!(
+            format_message(&started, DEFAULT_LOCALE),
+            "TCP forwarding from 127.0.0.1:8080 -> 80:web"
+        );
+
+        let failed = UserMessage::new(MessageCode::HostsFileFailed).with("service", "web");
+        assert_eq!(
+            format_message(&failed, "de"),
+            "Failed to update the hosts file for web: {error}"
+        );
+
+        let stopped = UserMessage::new(MessageCode::ForwardStopped);
+        assert_eq!(
+            format_messages(&[stopped.clone(), stopped], DEFAULT_LOCALE),
+            "Service port forwarding has been stopped\nService port forwarding has been stopped"
+        );
+
+        let json = serde_json::to_value(&started).unwrap();
+        assert_eq!(json["code"], "forward_started");
+        assert_eq!(json["params"]["local_port"], "8080");
+    }
+}
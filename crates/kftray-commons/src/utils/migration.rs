@@ -8,18 +8,27 @@ use sqlx::{
     Row,
     Sqlite,
     SqliteConnection,
+    SqlitePool,
     Transaction,
 };
 
 use crate::db::get_db_pool;
+use crate::encryption::{
+    decode_config_data,
+    encode_config_data,
+};
 use crate::models::config_model::Config;
 
 pub async fn migrate_configs() -> Result<(), String> {
-    info!("Starting configuration migration.");
     let pool = get_db_pool().await.map_err(|e| {
         error!("Failed to get DB pool: {}", e);
         e.to_string()
     })?;
+    migrate_configs_with_pool(&pool).await
+}
+
+pub async fn migrate_configs_with_pool(pool: &SqlitePool) -> Result<(), String> {
+    info!("Starting configuration migration.");
     let mut conn = pool.acquire().await.map_err(|e| {
         error!("Failed to acquire connection: {}", e);
         e.to_string()
@@ -46,6 +55,10 @@ pub async fn migrate_configs() -> Result<(), String> {
             error!("Failed to get data: {}", e);
             e.to_string()
         })?;
+        let data = decode_config_data(&data).map_err(|e| {
+            error!("Failed to decode config data: {}", e);
+            e
+        })?;
         let config_json: JsonValue = serde_json::from_str(&data).map_err(|e| {
             error!("Failed to parse JSON: {}", e);
             e.to_string()
@@ -60,6 +73,11 @@ pub async fn migrate_configs() -> Result<(), String> {
             e.to_string()
         })?;
 
+        let updated_data = encode_config_data(&updated_data).map_err(|e| {
+            error!("Failed to encode config data: {}", e);
+            e
+        })?;
+
         sqlx::query("UPDATE configs SET data = ?1 WHERE id = ?2")
             .bind(updated_data)
             .bind(id)
@@ -1,8 +1,39 @@
+pub mod access_audit;
+pub mod access_log;
+pub mod app_log;
+pub mod bulk_update;
+pub mod compose_export;
 pub mod config;
 pub mod config_dir;
+pub mod config_display;
+pub mod config_history;
+pub mod config_log;
+pub mod config_snapshot;
 pub mod config_state;
+pub mod config_trash;
+pub mod config_validation;
+pub mod context_settings;
 pub mod db;
+pub mod db_mode;
+pub mod encryption;
 pub mod github;
+pub mod http_log_stream;
+pub mod http_store;
+pub mod kubectl_export;
+pub mod latency;
 pub mod logging;
+pub mod messages;
+pub mod openapi;
 pub mod migration;
+pub mod profile;
+pub mod query_log;
+pub mod schema_migration;
+pub mod search;
+pub mod service;
+pub mod session;
+pub mod settings;
+pub mod template;
+pub mod tool_import;
+pub mod usage;
 pub mod validate_configs;
+pub mod variables;
@@ -0,0 +1,539 @@
+use std::collections::HashMap;
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
+use lazy_static::lazy_static;
+use serde_json::Value;
+
+use crate::models::http_log_model::{
+    HttpLogKind,
+    HttpLogRecord,
+};
+
+// keeps recursive schemas from looping
+const MAX_SCHEMA_DEPTH: usize = 16;
+const MAX_MISMATCHES: usize = 20;
+
+lazy_static! {
+    static ref OPENAPI_SPECS: Mutex<HashMap<i64, Arc<OpenApiSpec>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug)]
+pub struct OpenApiSpec {
+    document: Value,
+    base_path: String,
+}
+
+// yaml keys can be numbers, response codes usually are
+fn yaml_to_json(value: serde_yaml::Value) -> Value {
+    match value {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(value) => Value::Bool(value),
+        serde_yaml::Value::Number(number) => {
+            if let Some(number) = number.as_i64() {
+                Value::from(number)
+            } else if let Some(number) = number.as_u64() {
+                Value::from(number)
+            } else {
+                number.as_f64().map(Value::from).unwrap_or(Value::Null)
+            }
+        }
+        serde_yaml::Value::String(value) => Value::String(value),
+        serde_yaml::Value::Sequence(items) => {
+            Value::Array(items.into_iter().map(yaml_to_json).collect())
+        }
+        serde_yaml::Value::Mapping(mapping) => Value::Object(
+            mapping
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        serde_yaml::Value::String(key) => key,
+                        key => serde_yaml::to_string(&key)
+                            .unwrap_or_default()
+                            .trim()
+                            .to_string(),
+                    };
+                    (key, yaml_to_json(value))
+                })
+                .collect(),
+        ),
+        serde_yaml::Value::Tagged(tagged) => yaml_to_json(tagged.value),
+    }
+}
+
+fn url_path(url: &str) -> &str {
+    match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("", |start| &rest[start..]),
+        None => url,
+    }
+}
+
+pub fn parse_openapi_spec(source: &str) -> Result<OpenApiSpec, String> {
+    let document = match serde_json::from_str::<Value>(source) {
+        Ok(document) => document,
+        Err(_) => serde_yaml::from_str::<serde_yaml::Value>(source)
+            .map(yaml_to_json)
+            .map_err(|e| format!("The spec is neither JSON nor YAML: {}", e))?,
+    };
+    if document.get("openapi").is_none() && document.get("swagger").is_none() {
+        return Err("Not an OpenAPI document, `openapi` is missing".to_string());
+    }
+    if !document.get("paths").is_some_and(Value::is_object) {
+        return Err("The spec has no paths".to_string());
+    }
+
+    let base_path = document
+        .get("basePath")
+        .and_then(Value::as_str)
+        .or_else(|| {
+            document
+                .pointer("/servers/0/url")
+                .and_then(Value::as_str)
+                .map(url_path)
+        })
+        .unwrap_or_default()
+        .trim_end_matches('/')
+        .to_string();
+    Ok(OpenApiSpec {
+        document,
+        base_path,
+    })
+}
+
+// the number of literal segments when the path fits the template, so
+// `/users/me` wins over `/users/{id}`
+fn template_match(template: &str, path: &str) -> Option<usize> {
+    let template: Vec<&str> = template.trim_matches('/').split('/').collect();
+    let path: Vec<&str> = path.trim_matches('/').split('/').collect();
+    if template.len() != path.len() {
+        return None;
+    }
+
+    let mut literal = 0;
+    for (expected, segment) in template.iter().zip(&path) {
+        if expected.starts_with('{') && expected.ends_with('}') {
+            if segment.is_empty() {
+                return None;
+            }
+        } else if expected == segment {
+            literal += 1;
+        } else {
+            return None;
+        }
+    }
+    Some(literal)
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "number" => value.is_number(),
+        expected => expected == json_type(value),
+    }
+}
+
+impl OpenApiSpec {
+    fn resolve<'a>(&'a self, mut value: &'a Value) -> &'a Value {
+        for _ in 0..MAX_SCHEMA_DEPTH {
+            let Some(reference) = value.get("$ref").and_then(Value::as_str) else {
+                break;
+            };
+            match reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.document.pointer(pointer))
+            {
+                Some(target) => value = target,
+                None => break,
+            }
+        }
+        value
+    }
+
+    fn find_path(&self, path: &str) -> Option<(&str, &Value)> {
+        let path = path.split('?').next().unwrap_or_default();
+        let path = path
+            .strip_prefix(self.base_path.as_str())
+            .filter(|rest| !self.base_path.is_empty() && (rest.is_empty() || rest.starts_with('/')))
+            .unwrap_or(path);
+
+        self.document
+            .get("paths")?
+            .as_object()?
+            .iter()
+            .filter_map(|(template, item)| {
+                template_match(template, path).map(|literal| (literal, template, item))
+            })
+            .max_by_key(|(literal, ..)| *literal)
+            .map(|(_, template, item)| (template.as_str(), self.resolve(item)))
+    }
+
+    // the json schema of a request body or response, for openapi 3 and 2
+    fn body_schema<'a>(&'a self, holder: &'a Value) -> Option<&'a Value> {
+        let holder = self.resolve(holder);
+        match holder.get("content").and_then(Value::as_object) {
+            Some(content) => content
+                .iter()
+                .find(|(media_type, _)| media_type.contains("json"))
+                .and_then(|(_, media_type)| media_type.get("schema")),
+            None => holder.get("schema"),
+        }
+    }
+
+    fn request_schema<'a>(&'a self, operation: &'a Value) -> Option<&'a Value> {
+        if let Some(body) = operation.get("requestBody") {
+            return self.body_schema(body);
+        }
+        operation
+            .get("parameters")?
+            .as_array()?
+            .iter()
+            .map(|parameter| self.resolve(parameter))
+            .find(|parameter| parameter.get("in").and_then(Value::as_str) == Some("body"))
+            .and_then(|parameter| parameter.get("schema"))
+    }
+
+    // returns whether fields the schema doesn't name are allowed
+    fn collect_properties<'a>(
+        &'a self, schema: &'a Value, depth: usize, properties: &mut Vec<(&'a str, &'a Value)>,
+        required: &mut Vec<&'a str>,
+    ) -> bool {
+        let schema = self.resolve(schema);
+        let mut open = schema
+            .get("additionalProperties")
+            .is_some_and(|additional| additional != &Value::Bool(false));
+        if let Some(fields) = schema.get("properties").and_then(Value::as_object) {
+            properties.extend(fields.iter().map(|(name, field)| (name.as_str(), field)));
+        }
+        if let Some(names) = schema.get("required").and_then(Value::as_array) {
+            required.extend(names.iter().filter_map(Value::as_str));
+        }
+        if depth < MAX_SCHEMA_DEPTH {
+            for part in schema
+                .get("allOf")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                open |= self.collect_properties(part, depth + 1, properties, required);
+            }
+        }
+        open
+    }
+
+    fn check_value(
+        &self, schema: &Value, value: &Value, at: &str, depth: usize, mismatches: &mut Vec<String>,
+    ) {
+        if depth > MAX_SCHEMA_DEPTH || mismatches.len() >= MAX_MISMATCHES {
+            return;
+        }
+        let schema = self.resolve(schema);
+        // which alternative was meant can't be told apart reliably
+        if schema.get("oneOf").is_some() || schema.get("anyOf").is_some() {
+            return;
+        }
+        if value.is_null() && schema.get("nullable") == Some(&Value::Bool(true)) {
+            return;
+        }
+
+        let types: Vec<&str> = match schema.get("type") {
+            Some(Value::String(expected)) => vec![expected.as_str()],
+            Some(Value::Array(expected)) => expected.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|expected| type_matches(expected, value)) {
+            mismatches.push(format!(
+                "{} should be {}, got {}",
+                at,
+                types.join(" or "),
+                json_type(value)
+            ));
+            return;
+        }
+        if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+            if !allowed.contains(value) {
+                mismatches.push(format!("{} has a value outside its enum: {}", at, value));
+            }
+        }
+
+        match value {
+            Value::Object(fields) => {
+                let (mut properties, mut required) = (Vec::new(), Vec::new());
+                let open = self.collect_properties(schema, depth, &mut properties, &mut required);
+                for name in required {
+                    if !fields.contains_key(name) {
+                        mismatches.push(format!("{} is missing required field {}", at, name));
+                    }
+                }
+                for (name, field) in fields {
+                    match properties
+                        .iter()
+                        .find(|(property, _)| *property == name.as_str())
+                    {
+                        Some((_, field_schema)) => self.check_value(
+                            field_schema,
+                            field,
+                            &format!("{}.{}", at, name),
+                            depth + 1,
+                            mismatches,
+                        ),
+                        // a schema without properties says nothing about them
+                        None if !open && !properties.is_empty() => {
+                            mismatches.push(format!("{} has unknown field {}", at, name))
+                        }
+                        None => {}
+                    }
+                }
+            }
+            Value::Array(items) => {
+                if let Some(item_schema) = schema.get("items") {
+                    for (index, item) in items.iter().enumerate() {
+                        self.check_value(
+                            item_schema,
+                            item,
+                            &format!("{}[{}]", at, index),
+                            depth + 1,
+                            mismatches,
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn check_request(&self, method: &str, path: &str, body: Option<&Value>) -> Vec<String> {
+        let Some((template, item)) = self.find_path(path) else {
+            return vec![format!("{} {} isn't in the spec", method, path)];
+        };
+        let Some(operation) = item.get(method.to_ascii_lowercase()) else {
+            return vec![format!("{} isn't defined for {}", method, template)];
+        };
+
+        let mut mismatches = Vec::new();
+        if let (Some(schema), Some(body)) = (self.request_schema(operation), body) {
+            self.check_value(schema, body, "request body", 0, &mut mismatches);
+        }
+        mismatches
+    }
+
+    // requests the spec doesn't know were flagged already, their responses
+    // aren't checked
+    pub fn check_response(
+        &self, method: &str, path: &str, status: u16, body: Option<&Value>,
+    ) -> Vec<String> {
+        let Some((template, item)) = self.find_path(path) else {
+            return Vec::new();
+        };
+        let Some(responses) = item
+            .get(method.to_ascii_lowercase())
+            .and_then(|operation| operation.get("responses"))
+        else {
+            return Vec::new();
+        };
+
+        let Some(response) = [
+            status.to_string(),
+            format!("{}XX", status / 100),
+            "default".to_string(),
+        ]
+        .iter()
+        .find_map(|code| responses.get(code)) else {
+            return vec![format!(
+                "Status {} isn't documented for {} {}",
+                status, method, template
+            )];
+        };
+
+        let mut mismatches = Vec::new();
+        if let (Some(schema), Some(body)) = (self.body_schema(response), body) {
+            self.check_value(schema, body, "response body", 0, &mut mismatches);
+        }
+        mismatches
+    }
+
+    // bodies that aren't JSON are only checked for their path and status
+    pub fn check_record(&self, record: &HttpLogRecord, method: &str, path: &str) -> Vec<String> {
+        let body = record
+            .body
+            .as_deref()
+            .and_then(|body| serde_json::from_str::<Value>(body).ok());
+        match record.kind {
+            HttpLogKind::Request => self.check_request(method, path, body.as_ref()),
+            HttpLogKind::Response => self.check_response(
+                method,
+                path,
+                record.status.unwrap_or_default(),
+                body.as_ref(),
+            ),
+        }
+    }
+}
+
+pub fn set_openapi_spec(config_id: i64, spec: OpenApiSpec) {
+    OPENAPI_SPECS
+        .lock()
+        .unwrap()
+        .insert(config_id, Arc::new(spec));
+}
+
+pub fn openapi_spec(config_id: i64) -> Option<Arc<OpenApiSpec>> {
+    OPENAPI_SPECS.lock().unwrap().get(&config_id).cloned()
+}
+
+pub fn clear_openapi_spec(config_id: Option<i64>) {
+    let mut specs = OPENAPI_SPECS.lock().unwrap();
+    match config_id {
+        Some(config_id) => {
+            specs.remove(&config_id);
+        }
+        None => specs.clear(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const SPEC: &str = r#"
+openapi: 3.0.3
+servers:
+  - url: https://shop.example.com/api
+paths:
+  /orders/{id}:
+    get:
+      responses:
+        200:
+          content:
+            application/json:
+              schema:
+                $ref: '#/components/schemas/Order'
+        404:
+          description: not found
+  /orders/latest:
+    get:
+      responses:
+        2XX:
+          description: ok
+  /orders:
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema:
+              allOf:
+                - $ref: '#/components/schemas/Order'
+                - properties:
+                    note:
+                      type: string
+      responses:
+        default:
+          description: anything
+components:
+  schemas:
+    Order:
+      type: object
+      required: [id, items]
+      properties:
+        id:
+          type: integer
+        status:
+          type: string
+          enum: [open, shipped]
+        coupon:
+          type: string
+          nullable: true
+        items:
+          type: array
+          items:
+            type: object
+            properties:
+              sku:
+                type: string
+"#;
+
+    #[test]
+    fn test_check_requests_and_responses() {
+        let spec = parse_openapi_spec(SPEC).unwrap();
+        assert!(spec
+            .check_request("GET", "/api/orders/7?full=1", None)
+            .is_empty());
+        assert_eq!(
+            spec.check_request("DELETE", "/api/orders/7", None),
+            vec!["DELETE isn't defined for /orders/{id}"]
+        );
+        assert_eq!(
+            spec.check_request("GET", "/api/carts", None),
+            vec!["GET /api/carts isn't in the spec"]
+        );
+        assert!(spec
+            .check_request(
+                "POST",
+                "/api/orders",
+                Some(&json!({"id": 1, "items": [], "note": "gift"}))
+            )
+            .is_empty());
+
+        assert!(spec
+            .check_response("GET", "/api/orders/latest", 204, None)
+            .is_empty());
+        assert_eq!(
+            spec.check_response("GET", "/api/orders/7", 500, None),
+            vec!["Status 500 isn't documented for GET /orders/{id}"]
+        );
+        let mut mismatches = spec.check_response(
+            "GET",
+            "/api/orders/7",
+            200,
+            Some(&json!({
+                "id": "7",
+                "status": "lost",
+                "coupon": null,
+                "items": [{"sku": 1}, {"sku": "a", "qty": 2}],
+                "eta": "soon"
+            })),
+        );
+        mismatches.sort();
+        assert_eq!(
+            mismatches,
+            vec![
+                "response body has unknown field eta",
+                "response body.id should be integer, got string",
+                "response body.items[0].sku should be string, got integer",
+                "response body.items[1] has unknown field qty",
+                "response body.status has a value outside its enum: \"lost\"",
+            ]
+        );
+        assert_eq!(
+            spec.check_response("GET", "/api/orders/7", 200, Some(&json!({"id": 7}))),
+            vec!["response body is missing required field items"]
+        );
+    }
+
+    #[test]
+    fn test_parse_openapi_spec() {
+        assert!(parse_openapi_spec("{\"openapi\": \"3.1.0\", \"paths\": {}}").is_ok());
+        assert!(parse_openapi_spec("name: not a spec").is_err());
+        assert!(parse_openapi_spec("{\"swagger\": \"2.0\"}").is_err());
+        assert_eq!(url_path("https://shop.example.com/api/v1"), "/api/v1");
+        assert_eq!(url_path("https://shop.example.com"), "");
+        assert_eq!(url_path("/v2"), "/v2");
+        assert_eq!(template_match("/users/{id}", "/users/7"), Some(1));
+        assert_eq!(template_match("/users/me", "/users/me"), Some(2));
+        assert_eq!(template_match("/users/{id}", "/users"), None);
+    }
+}
@@ -0,0 +1,285 @@
+use log::info;
+use sqlx::{
+    Row,
+    SqlitePool,
+};
+
+use crate::config::read_configs_with_pool;
+use crate::config_state::read_config_states_with_pool;
+use crate::db::get_db_pool;
+use crate::models::config_model::Config;
+use crate::models::profile_model::Profile;
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+pub fn config_profile(config: &Config) -> &str {
+    config
+        .profile
+        .as_deref()
+        .filter(|profile| !profile.is_empty())
+        .unwrap_or(DEFAULT_PROFILE)
+}
+
+pub async fn list_profiles() -> Result<Vec<Profile>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    list_profiles_with_pool(&pool).await
+}
+
+pub async fn list_profiles_with_pool(pool: &SqlitePool) -> Result<Vec<Profile>, String> {
+    let rows = sqlx::query("SELECT name, is_active FROM profiles ORDER BY name")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(Profile {
+                name: row.try_get("name").map_err(|e| e.to_string())?,
+                is_active: row.try_get("is_active").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+pub async fn get_active_profile() -> Result<String, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    get_active_profile_with_pool(&pool).await
+}
+
+pub async fn get_active_profile_with_pool(pool: &SqlitePool) -> Result<String, String> {
+    let row = sqlx::query("SELECT name FROM profiles WHERE is_active = true LIMIT 1")
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match row {
+        Some(row) => row.try_get("name").map_err(|e| e.to_string()),
+        None => Ok(DEFAULT_PROFILE.to_string()),
+    }
+}
+
+pub async fn create_profile(name: &str) -> Result<(), String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    create_profile_with_pool(name, &pool).await
+}
+
+pub async fn create_profile_with_pool(name: &str, pool: &SqlitePool) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+
+    sqlx::query("INSERT INTO profiles (name, is_active) VALUES (?1, false)")
+        .bind(name)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to create profile '{}': {}", name, e))?;
+
+    Ok(())
+}
+
+pub async fn delete_profile(name: &str) -> Result<(), String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    delete_profile_with_pool(name, &pool).await
+}
+
+pub async fn delete_profile_with_pool(name: &str, pool: &SqlitePool) -> Result<(), String> {
+    if name == DEFAULT_PROFILE {
+        return Err("The default profile cannot be deleted".to_string());
+    }
+
+    if get_active_profile_with_pool(pool).await? == name {
+        return Err("The active profile cannot be deleted".to_string());
+    }
+
+    let configs = read_configs_with_pool(pool).await?;
+    let in_use = configs
+        .iter()
+        .filter(|config| config_profile(config) == name)
+        .count();
+    if in_use > 0 {
+        return Err(format!(
+            "Profile '{}' still has {} config(s), move or delete them first",
+            name, in_use
+        ));
+    }
+
+    let mut transaction = pool.begin().await.map_err(|e| e.to_string())?;
+
+    sqlx::query("DELETE FROM variables WHERE profile = ?1")
+        .bind(name)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    sqlx::query("DELETE FROM profiles WHERE name = ?1")
+        .bind(name)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    transaction.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+pub async fn set_active_profile(name: &str) -> Result<(), String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    set_active_profile_with_pool(name, &pool).await
+}
+
+pub async fn set_active_profile_with_pool(name: &str, pool: &SqlitePool) -> Result<(), String> {
+    let mut transaction = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let exists = sqlx::query("SELECT name FROM profiles WHERE name = ?1")
+        .bind(name)
+        .fetch_optional(&mut *transaction)
+        .await
+        .map_err(|e| e.to_string())?
+        .is_some();
+
+    if !exists {
+        return Err(format!("Profile '{}' does not exist", name));
+    }
+
+    sqlx::query("UPDATE profiles SET is_active = (name = ?1)")
+        .bind(name)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    transaction.commit().await.map_err(|e| e.to_string())?;
+
+    info!("Active profile set to '{}'", name);
+    Ok(())
+}
+
+pub async fn read_active_profile_configs() -> Result<Vec<Config>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    read_active_profile_configs_with_pool(&pool).await
+}
+
+pub async fn read_active_profile_configs_with_pool(
+    pool: &SqlitePool,
+) -> Result<Vec<Config>, String> {
+    let active_profile = get_active_profile_with_pool(pool).await?;
+
+    Ok(read_configs_with_pool(pool)
+        .await?
+        .into_iter()
+        .filter(|config| config_profile(config) == active_profile)
+        .collect())
+}
+
+// running configs that a switch to `name` has to stop, failing when `name`
+// is not a profile so callers can check before stopping anything
+pub async fn configs_to_stop_for_switch(name: &str) -> Result<Vec<Config>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    configs_to_stop_for_switch_with_pool(name, &pool).await
+}
+
+pub async fn configs_to_stop_for_switch_with_pool(
+    name: &str, pool: &SqlitePool,
+) -> Result<Vec<Config>, String> {
+    let exists = list_profiles_with_pool(pool)
+        .await?
+        .iter()
+        .any(|profile| profile.name == name);
+    if !exists {
+        return Err(format!("Profile '{}' does not exist", name));
+    }
+
+    let running_config_ids: Vec<i64> = read_config_states_with_pool(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|state| state.is_running)
+        .map(|state| state.config_id)
+        .collect();
+
+    Ok(read_configs_with_pool(pool)
+        .await?
+        .into_iter()
+        .filter(|config| {
+            config.id.is_some_and(|id| running_config_ids.contains(&id))
+                && config_profile(config) != name
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_memory_db_pool;
+    use crate::models::config_state_model::ConfigState;
+    use crate::utils::config::create_config_with_pool;
+    use crate::utils::config_state::update_config_state_with_pool;
+
+    async fn create_running_config(profile: &str, pool: &SqlitePool) -> i64 {
+        let id = create_config_with_pool(
+            Config {
+                profile: Some(profile.to_string()),
+                ..Default::default()
+            },
+            pool,
+        )
+        .await
+        .unwrap();
+        update_config_state_with_pool(
+            &ConfigState {
+                id: None,
+                config_id: id,
+                is_running: true,
+                local_port: None,
+            },
+            pool,
+        )
+        .await
+        .unwrap();
+        id
+    }
+
+    #[tokio::test]
+    async fn test_switch_to_unknown_profile_fails() {
+        let pool = create_memory_db_pool().await.unwrap();
+        create_running_config(DEFAULT_PROFILE, &pool).await;
+
+        assert_eq!(
+            configs_to_stop_for_switch_with_pool("missing", &pool)
+                .await
+                .unwrap_err(),
+            "Profile 'missing' does not exist"
+        );
+        assert!(set_active_profile_with_pool("missing", &pool)
+            .await
+            .is_err());
+        assert_eq!(
+            get_active_profile_with_pool(&pool).await.unwrap(),
+            DEFAULT_PROFILE
+        );
+    }
+
+    #[tokio::test]
+    async fn test_switch_stops_only_other_profiles() {
+        let pool = create_memory_db_pool().await.unwrap();
+        create_profile_with_pool("staging", &pool).await.unwrap();
+        let default_id = create_running_config(DEFAULT_PROFILE, &pool).await;
+        let staging_id = create_running_config("staging", &pool).await;
+
+        let to_stop = configs_to_stop_for_switch_with_pool("staging", &pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            to_stop.iter().map(|config| config.id).collect::<Vec<_>>(),
+            vec![Some(default_id)]
+        );
+
+        let to_stop = configs_to_stop_for_switch_with_pool(DEFAULT_PROFILE, &pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            to_stop.iter().map(|config| config.id).collect::<Vec<_>>(),
+            vec![Some(staging_id)]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::{
+    HashMap,
+    VecDeque,
+};
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+
+use crate::models::query_log_model::QueryLogEntry;
+
+const MAX_QUERY_LOG_ENTRIES: usize = 500;
+
+lazy_static! {
+    static ref QUERY_LOG_REDACTION: Mutex<HashMap<i64, bool>> = Mutex::new(HashMap::new());
+    static ref QUERY_LOGS: Mutex<HashMap<i64, VecDeque<QueryLogEntry>>> =
+        Mutex::new(HashMap::new());
+}
+
+pub fn set_query_log_enabled(config_id: i64, enabled: bool, redact: bool) {
+    let mut configs = QUERY_LOG_REDACTION.lock().unwrap();
+    if enabled {
+        configs.insert(config_id, redact);
+    } else {
+        configs.remove(&config_id);
+    }
+}
+
+pub fn query_log_redaction(config_id: i64) -> Option<bool> {
+    QUERY_LOG_REDACTION.lock().unwrap().get(&config_id).copied()
+}
+
+pub fn record_query(entry: QueryLogEntry) {
+    let mut logs = QUERY_LOGS.lock().unwrap();
+    let entries = logs.entry(entry.config_id).or_default();
+
+    if entries.len() == MAX_QUERY_LOG_ENTRIES {
+        entries.pop_front();
+    }
+    entries.push_back(entry);
+}
+
+pub fn get_query_log(config_id: i64, since: Option<u64>) -> Vec<QueryLogEntry> {
+    QUERY_LOGS
+        .lock()
+        .unwrap()
+        .get(&config_id)
+        .map(|entries| {
+            entries
+                .iter()
+                .filter(|entry| since.is_none_or(|since| entry.timestamp_ms >= since))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn clear_query_log(config_id: i64) {
+    QUERY_LOGS.lock().unwrap().remove(&config_id);
+}
+
+pub fn redact_sql(statement: &str) -> String {
+    let mut redacted = String::with_capacity(statement.len());
+    let mut chars = statement.chars().peekable();
+    let mut previous: Option<char> = None;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                while let Some(c) = chars.next() {
+                    if c == '\'' {
+                        if chars.peek() == Some(&'\'') {
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                redacted.push('?');
+                previous = Some('?');
+                continue;
+            }
+            '"' | '`' => {
+                redacted.push(c);
+                for inner in chars.by_ref() {
+                    redacted.push(inner);
+                    if inner == c {
+                        break;
+                    }
+                }
+            }
+            c if c.is_ascii_digit()
+                && !previous.is_some_and(|p| p.is_alphanumeric() || p == '_' || p == '$') =>
+            {
+                while chars
+                    .peek()
+                    .is_some_and(|next| next.is_ascii_alphanumeric() || *next == '.')
+                {
+                    chars.next();
+                }
+                redacted.push('?');
+                previous = Some('?');
+                continue;
+            }
+            c => redacted.push(c),
+        }
+        previous = Some(c);
+    }
+
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::protocol_model::StreamProtocol;
+
+    #[test]
+    fn test_redact_sql() {
+        assert_eq!(
+            redact_sql("SELECT * FROM orders WHERE id = 42 AND note = 'it''s' AND t2.c1 = $1"),
+            "SELECT * FROM orders WHERE id = ? AND note = ? AND t2.c1 = $1"
+        );
+        assert_eq!(
+            redact_sql("UPDATE \"user 1\" SET score = 1.5e3"),
+            "UPDATE \"user 1\" SET score = ?"
+        );
+    }
+
+    #[test]
+    fn test_query_log_is_bounded() {
+        let config_id = 8_001;
+        for i in 0..MAX_QUERY_LOG_ENTRIES as u64 + 3 {
+            record_query(QueryLogEntry {
+                config_id,
+                timestamp_ms: i,
+                protocol: StreamProtocol::Postgres,
+                statement: "SELECT 1".to_string(),
+                params: Vec::new(),
+                duration_ms: 1,
+                rows: Some(1),
+                error: None,
+            });
+        }
+
+        assert_eq!(get_query_log(config_id, None).len(), MAX_QUERY_LOG_ENTRIES);
+        assert_eq!(get_query_log(config_id, Some(500)).len(), 3);
+    }
+}
@@ -0,0 +1,343 @@
+use log::{
+    error,
+    info,
+};
+use sqlx::{
+    Row,
+    SqlitePool,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct SchemaMigration {
+    pub version: i64,
+    pub name: &'static str,
+    pub statements: &'static [&'static str],
+}
+
+pub const SCHEMA_MIGRATIONS: &[SchemaMigration] = &[
+    SchemaMigration {
+        version: 1,
+        name: "create_configs_and_config_state",
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS configs (
+                id INTEGER PRIMARY KEY,
+                data TEXT NOT NULL
+            )",
+            "CREATE TABLE IF NOT EXISTS config_state (
+                id INTEGER PRIMARY KEY,
+                config_id INTEGER NOT NULL,
+                is_running BOOLEAN NOT NULL DEFAULT false,
+                FOREIGN KEY(config_id) REFERENCES configs(id) ON DELETE CASCADE
+            )",
+            "CREATE TRIGGER IF NOT EXISTS after_insert_config
+             AFTER INSERT ON configs
+             FOR EACH ROW
+             BEGIN
+                 INSERT INTO config_state (config_id, is_running) VALUES (NEW.id, false);
+             END;",
+            "CREATE TRIGGER IF NOT EXISTS after_delete_config
+             AFTER DELETE ON configs
+             FOR EACH ROW
+             BEGIN
+                 DELETE FROM config_state WHERE config_id = OLD.id;
+             END;",
+        ],
+    },
+    SchemaMigration {
+        version: 2,
+        name: "create_variables",
+        statements: &["CREATE TABLE IF NOT EXISTS variables (
+                id INTEGER PRIMARY KEY,
+                profile TEXT NOT NULL DEFAULT 'default',
+                name TEXT NOT NULL,
+                value TEXT NOT NULL,
+                UNIQUE(profile, name)
+            )"],
+    },
+    SchemaMigration {
+        version: 3,
+        name: "create_profiles",
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS profiles (
+                name TEXT PRIMARY KEY,
+                is_active BOOLEAN NOT NULL DEFAULT false
+            )",
+            "INSERT OR IGNORE INTO profiles (name, is_active) VALUES ('default', true)",
+        ],
+    },
+    SchemaMigration {
+        version: 4,
+        name: "create_config_usage",
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS config_usage (
+                config_id INTEGER PRIMARY KEY,
+                last_started_at INTEGER,
+                start_count INTEGER NOT NULL DEFAULT 0,
+                pinned BOOLEAN NOT NULL DEFAULT false,
+                FOREIGN KEY(config_id) REFERENCES configs(id) ON DELETE CASCADE
+            )",
+            "CREATE TRIGGER IF NOT EXISTS after_delete_config_usage
+             AFTER DELETE ON configs
+             FOR EACH ROW
+             BEGIN
+                 DELETE FROM config_usage WHERE config_id = OLD.id;
+             END;",
+        ],
+    },
+    SchemaMigration {
+        version: 5,
+        name: "create_http_transactions",
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS http_transactions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                config_id INTEGER NOT NULL,
+                trace_id TEXT NOT NULL UNIQUE,
+                requested_at INTEGER NOT NULL,
+                method TEXT,
+                path TEXT,
+                status INTEGER,
+                took_ms INTEGER,
+                request_headers TEXT NOT NULL DEFAULT '[]',
+                request_body TEXT,
+                response_headers TEXT NOT NULL DEFAULT '[]',
+                response_body TEXT,
+                FOREIGN KEY(config_id) REFERENCES configs(id) ON DELETE CASCADE
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_http_transactions_config
+             ON http_transactions (config_id, requested_at)",
+            "CREATE INDEX IF NOT EXISTS idx_http_transactions_path
+             ON http_transactions (path)",
+            "CREATE INDEX IF NOT EXISTS idx_http_transactions_status
+             ON http_transactions (status)",
+            "CREATE INDEX IF NOT EXISTS idx_http_transactions_requested_at
+             ON http_transactions (requested_at)",
+        ],
+    },
+    SchemaMigration {
+        version: 6,
+        name: "create_settings",
+        statements: &["CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            )"],
+    },
+    SchemaMigration {
+        version: 7,
+        name: "add_config_state_session",
+        statements: &["ALTER TABLE config_state ADD COLUMN session_id TEXT"],
+    },
+    SchemaMigration {
+        version: 8,
+        name: "create_context_settings",
+        statements: &["CREATE TABLE IF NOT EXISTS context_settings (
+                context TEXT PRIMARY KEY,
+                display_name TEXT,
+                color TEXT,
+                default_namespace TEXT,
+                local_address_range TEXT,
+                default_protocol TEXT
+            )"],
+    },
+    SchemaMigration {
+        version: 9,
+        name: "add_config_state_local_port",
+        statements: &["ALTER TABLE config_state ADD COLUMN local_port INTEGER"],
+    },
+    SchemaMigration {
+        version: 10,
+        name: "add_context_settings_production",
+        statements: &[
+            "ALTER TABLE context_settings ADD COLUMN production INTEGER NOT NULL DEFAULT 0",
+        ],
+    },
+    SchemaMigration {
+        version: 11,
+        name: "create_access_audit",
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS access_audit (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                config_id INTEGER,
+                context TEXT NOT NULL,
+                namespace TEXT NOT NULL,
+                target TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                user TEXT,
+                started_at INTEGER NOT NULL
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_access_audit_started_at
+                ON access_audit (started_at)",
+        ],
+    },
+    SchemaMigration {
+        version: 12,
+        name: "add_context_settings_read_only",
+        statements: &[
+            "ALTER TABLE context_settings ADD COLUMN read_only INTEGER NOT NULL DEFAULT 0",
+        ],
+    },
+    SchemaMigration {
+        version: 13,
+        name: "create_config_snapshots",
+        statements: &["CREATE TABLE IF NOT EXISTS config_snapshots (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                reason TEXT NOT NULL,
+                config_count INTEGER NOT NULL,
+                data TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            )"],
+    },
+    SchemaMigration {
+        version: 14,
+        name: "create_config_trash",
+        statements: &["CREATE TABLE IF NOT EXISTS config_trash (
+                id INTEGER PRIMARY KEY,
+                data TEXT NOT NULL,
+                deleted_at INTEGER NOT NULL
+            )"],
+    },
+    SchemaMigration {
+        version: 15,
+        name: "create_config_history",
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS config_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                config_id INTEGER NOT NULL,
+                action TEXT NOT NULL,
+                user TEXT,
+                changes TEXT NOT NULL,
+                changed_at INTEGER NOT NULL
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_config_history_config
+                ON config_history (config_id, id)",
+        ],
+    },
+];
+
+async fn ensure_migrations_table(pool: &SqlitePool) -> Result<(), String> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("Failed to create schema_migrations table: {}", e);
+        e.to_string()
+    })?;
+
+    Ok(())
+}
+
+pub async fn get_schema_version(pool: &SqlitePool) -> Result<i64, String> {
+    ensure_migrations_table(pool).await?;
+
+    let row = sqlx::query("SELECT COALESCE(MAX(version), 0) AS version FROM schema_migrations")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    row.try_get("version").map_err(|e| e.to_string())
+}
+
+pub async fn pending_schema_migrations(
+    pool: &SqlitePool,
+) -> Result<Vec<&'static SchemaMigration>, String> {
+    let current_version = get_schema_version(pool).await?;
+
+    Ok(SCHEMA_MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > current_version)
+        .collect())
+}
+
+pub async fn run_schema_migrations(
+    pool: &SqlitePool, dry_run: bool,
+) -> Result<Vec<&'static SchemaMigration>, String> {
+    let pending = pending_schema_migrations(pool).await?;
+
+    if pending.is_empty() {
+        info!("Database schema is up to date.");
+        return Ok(pending);
+    }
+
+    for migration in &pending {
+        if dry_run {
+            info!(
+                "Dry run: would apply schema migration {} ({})",
+                migration.version, migration.name
+            );
+            for statement in migration.statements {
+                info!("{}", statement);
+            }
+            continue;
+        }
+
+        info!(
+            "Applying schema migration {} ({})",
+            migration.version, migration.name
+        );
+
+        let mut transaction = pool.begin().await.map_err(|e| e.to_string())?;
+
+        for statement in migration.statements {
+            sqlx::query(statement)
+                .execute(&mut *transaction)
+                .await
+                .map_err(|e| {
+                    error!(
+                        "Schema migration {} ({}) failed: {}",
+                        migration.version, migration.name, e
+                    );
+                    e.to_string()
+                })?;
+        }
+
+        sqlx::query("INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)")
+            .bind(migration.version)
+            .bind(migration.name)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        transaction.commit().await.map_err(|e| e.to_string())?;
+    }
+
+    Ok(pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_migrations_are_ordered() {
+        let versions: Vec<i64> = SCHEMA_MIGRATIONS.iter().map(|m| m.version).collect();
+        let mut sorted = versions.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(versions, sorted);
+    }
+
+    #[tokio::test]
+    async fn test_run_schema_migrations_dry_run_and_apply() {
+        let pool = crate::db::create_db_pool(crate::db_mode::DatabaseMode::Memory)
+            .await
+            .unwrap();
+
+        let planned = run_schema_migrations(&pool, true).await.unwrap();
+        assert_eq!(planned.len(), SCHEMA_MIGRATIONS.len());
+        assert_eq!(get_schema_version(&pool).await.unwrap(), 0);
+
+        run_schema_migrations(&pool, false).await.unwrap();
+        let latest = SCHEMA_MIGRATIONS.last().unwrap().version;
+        assert_eq!(get_schema_version(&pool).await.unwrap(), latest);
+
+        assert!(run_schema_migrations(&pool, false)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
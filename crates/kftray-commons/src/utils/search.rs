@@ -0,0 +1,158 @@
+use std::cmp::Reverse;
+
+use crate::models::config_model::Config;
+use crate::utils::profile::read_active_profile_configs;
+
+const EXACT_BONUS: i64 = 100;
+const PREFIX_BONUS: i64 = 50;
+const CONSECUTIVE_BONUS: i64 = 5;
+const BOUNDARY_BONUS: i64 = 10;
+
+pub fn fuzzy_score(pattern: &str, candidate: &str) -> Option<i64> {
+    let pattern = pattern.to_lowercase();
+    let candidate = candidate.to_lowercase();
+
+    if pattern.is_empty() {
+        return Some(0);
+    }
+
+    if candidate == pattern {
+        return Some(EXACT_BONUS * 2);
+    }
+
+    if let Some(position) = candidate.find(&pattern) {
+        let bonus = if position == 0 { PREFIX_BONUS } else { 0 };
+        return Some(EXACT_BONUS + bonus - position as i64);
+    }
+
+    let candidate_chars: Vec<char> = candidate.chars().collect();
+    let mut score = 0;
+    let mut last_match: Option<usize> = None;
+    let mut index = 0;
+
+    for pattern_char in pattern.chars() {
+        let found = candidate_chars[index..]
+            .iter()
+            .position(|c| *c == pattern_char)?;
+        let position = index + found;
+
+        score += 1;
+        if last_match.is_some_and(|last| last + 1 == position) {
+            score += CONSECUTIVE_BONUS;
+        }
+        if position == 0 || matches!(candidate_chars[position - 1], '-' | '_' | '.' | '/' | ' ') {
+            score += BOUNDARY_BONUS;
+        }
+
+        last_match = Some(position);
+        index = position + 1;
+    }
+
+    Some(score)
+}
+
+fn searchable_fields(config: &Config) -> Vec<&str> {
+    let mut fields = vec![config.namespace.as_str(), config.context.as_str()];
+
+    if let Some(alias) = config.alias.as_deref() {
+        fields.push(alias);
+    }
+    if let Some(service) = config.service.as_deref() {
+        fields.push(service);
+    }
+    if let Some(target) = config.target.as_deref() {
+        fields.push(target);
+    }
+    if let Some(tags) = config.tags.as_ref() {
+        fields.extend(tags.iter().map(String::as_str));
+    }
+
+    fields
+}
+
+pub(crate) fn has_tag(config: &Config, tag: &str) -> bool {
+    config
+        .tags
+        .as_ref()
+        .is_some_and(|tags| tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+}
+
+pub fn score_config(config: &Config, query: &str) -> Option<i64> {
+    let mut total = 0;
+
+    for term in query.split_whitespace() {
+        if let Some(tag) = term.strip_prefix("tag:") {
+            if !has_tag(config, tag) {
+                return None;
+            }
+            total += EXACT_BONUS;
+            continue;
+        }
+
+        total += searchable_fields(config)
+            .into_iter()
+            .filter_map(|field| fuzzy_score(term, field))
+            .max()?;
+    }
+
+    Some(total)
+}
+
+pub fn filter_configs(configs: Vec<Config>, query: &str) -> Vec<Config> {
+    if query.trim().is_empty() {
+        return configs;
+    }
+
+    let mut scored: Vec<(i64, Config)> = configs
+        .into_iter()
+        .filter_map(|config| score_config(&config, query).map(|score| (score, config)))
+        .collect();
+
+    scored.sort_by_key(|(score, _)| Reverse(*score));
+
+    scored.into_iter().map(|(_, config)| config).collect()
+}
+
+pub async fn search_configs(query: &str) -> Result<Vec<Config>, String> {
+    let configs = read_active_profile_configs().await?;
+    Ok(filter_configs(configs, query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(alias: &str, service: &str, tags: &[&str]) -> Config {
+        Config {
+            alias: Some(alias.to_string()),
+            service: Some(service.to_string()),
+            tags: Some(tags.iter().map(|t| t.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("pg", "postgres").is_some());
+        assert!(fuzzy_score("xyz", "postgres").is_none());
+        assert!(fuzzy_score("post", "postgres") > fuzzy_score("pgs", "postgres"));
+    }
+
+    #[test]
+    fn test_filter_configs_ranks_and_filters() {
+        let configs = vec![
+            config("redis-cache", "redis", &["cache"]),
+            config("api", "orders-api", &["backend", "team-a"]),
+            config("postgres", "orders-db", &["db", "team-a"]),
+        ];
+
+        let results = filter_configs(configs.clone(), "orders");
+        assert_eq!(results.len(), 2);
+
+        let results = filter_configs(configs.clone(), "tag:team-a db");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].alias.as_deref(), Some("postgres"));
+
+        assert_eq!(filter_configs(configs, "").len(), 3);
+    }
+}
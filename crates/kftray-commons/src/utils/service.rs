@@ -0,0 +1,197 @@
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::process::Command;
+
+use log::info;
+
+use crate::utils::config_dir::get_log_folder_path;
+
+pub const LAUNCHD_LABEL: &str = "com.kftray.engine";
+pub const WINDOWS_TASK_NAME: &str = "kftray-engine";
+const UNSUPPORTED_PLATFORM: &str =
+    "Installing the engine as a service is only supported on macOS and Windows";
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn launchd_plist(program: &Path, args: &[String], log_path: &Path) -> String {
+    let arguments: String = std::iter::once(program.to_string_lossy().to_string())
+        .chain(args.iter().cloned())
+        .map(|arg| format!("        <string>{}</string>\n", escape_xml(&arg)))
+        .collect();
+    let log_path = escape_xml(&log_path.to_string_lossy());
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{LAUNCHD_LABEL}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>StandardOutPath</key>
+    <string>{log_path}</string>
+    <key>StandardErrorPath</key>
+    <string>{log_path}</string>
+</dict>
+</plist>
+"#
+    )
+}
+
+// quotes every argument for the task's command line, the same way cmd.exe
+// splits it back
+pub fn windows_command_line(program: &Path, args: &[String]) -> String {
+    std::iter::once(program.to_string_lossy().to_string())
+        .chain(args.iter().cloned())
+        .map(|arg| format!("\"{}\"", arg.replace('"', "\\\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn launchd_plist_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or_else(|| "Unable to determine home directory".to_string())?;
+    Ok(home
+        .join("Library/LaunchAgents")
+        .join(format!("{}.plist", LAUNCHD_LABEL)))
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "{} {} failed: {}",
+            program,
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// installs the headless engine so it starts at login without the app, on
+// windows this is a logon task rather than an SCM service so the engine runs
+// as the user and can read their kubeconfig and database
+pub fn install_engine_service(program: &Path, args: &[String]) -> Result<String, String> {
+    if cfg!(target_os = "macos") {
+        let plist_path = launchd_plist_path()?;
+        let log_path = get_log_folder_path()?.join("engine.log");
+        if let Some(parent) = plist_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        if let Some(parent) = log_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+
+        if plist_path.exists() {
+            let _ = run("launchctl", &["unload", &plist_path.to_string_lossy()]);
+        }
+        std::fs::write(&plist_path, launchd_plist(program, args, &log_path))
+            .map_err(|e| e.to_string())?;
+        run("launchctl", &["load", "-w", &plist_path.to_string_lossy()])?;
+
+        info!("Installed launchd agent at {}", plist_path.display());
+        Ok(plist_path.to_string_lossy().to_string())
+    } else if cfg!(target_os = "windows") {
+        let command_line = windows_command_line(program, args);
+        run(
+            "schtasks",
+            &[
+                "/Create",
+                "/F",
+                "/TN",
+                WINDOWS_TASK_NAME,
+                "/SC",
+                "ONLOGON",
+                "/RL",
+                "LIMITED",
+                "/TR",
+                &command_line,
+            ],
+        )?;
+        run("schtasks", &["/Run", "/TN", WINDOWS_TASK_NAME])?;
+
+        info!("Installed logon task {}", WINDOWS_TASK_NAME);
+        Ok(WINDOWS_TASK_NAME.to_string())
+    } else {
+        Err(UNSUPPORTED_PLATFORM.to_string())
+    }
+}
+
+pub fn uninstall_engine_service() -> Result<(), String> {
+    if cfg!(target_os = "macos") {
+        let plist_path = launchd_plist_path()?;
+        if !plist_path.exists() {
+            return Err("The engine service is not installed".to_string());
+        }
+
+        let _ = run(
+            "launchctl",
+            &["unload", "-w", &plist_path.to_string_lossy()],
+        );
+        std::fs::remove_file(&plist_path).map_err(|e| e.to_string())?;
+        info!("Removed launchd agent {}", plist_path.display());
+        Ok(())
+    } else if cfg!(target_os = "windows") {
+        let _ = run("schtasks", &["/End", "/TN", WINDOWS_TASK_NAME]);
+        run("schtasks", &["/Delete", "/F", "/TN", WINDOWS_TASK_NAME])?;
+        info!("Removed logon task {}", WINDOWS_TASK_NAME);
+        Ok(())
+    } else {
+        Err(UNSUPPORTED_PLATFORM.to_string())
+    }
+}
+
+pub fn is_engine_service_installed() -> Result<bool, String> {
+    if cfg!(target_os = "macos") {
+        Ok(launchd_plist_path()?.exists())
+    } else if cfg!(target_os = "windows") {
+        Ok(run("schtasks", &["/Query", "/TN", WINDOWS_TASK_NAME]).is_ok())
+    } else {
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_definitions() {
+        let args = vec!["daemon".to_string(), "1".to_string()];
+
+        let plist = launchd_plist(
+            Path::new("/usr/local/bin/kftui"),
+            &args,
+            Path::new("/tmp/a&b/engine.log"),
+        );
+        assert!(plist.contains("<string>/usr/local/bin/kftui</string>"));
+        assert!(plist.contains("<string>daemon</string>\n        <string>1</string>"));
+        assert!(plist.contains("<string>/tmp/a&amp;b/engine.log</string>"));
+
+        assert_eq!(
+            windows_command_line(Path::new(r"C:\Program Files\kftui.exe"), &args),
+            r#""C:\Program Files\kftui.exe" "daemon" "1""#
+        );
+    }
+}
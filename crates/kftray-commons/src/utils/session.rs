@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+
+use lazy_static::lazy_static;
+use sqlx::{
+    Row,
+    SqlitePool,
+};
+
+use crate::db::get_db_pool;
+use crate::models::session_model::SessionInfo;
+
+lazy_static! {
+    static ref SESSION_ID: String = uuid::Uuid::new_v4().simple().to_string();
+}
+
+// identifies this kftray / kftui / daemon process, so concurrent instances
+// don't take over each other's forwards
+pub fn current_session_id() -> String {
+    SESSION_ID.clone()
+}
+
+pub async fn list_sessions() -> Result<Vec<SessionInfo>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    list_sessions_with_pool(&pool).await
+}
+
+pub async fn list_sessions_with_pool(pool: &SqlitePool) -> Result<Vec<SessionInfo>, String> {
+    let rows = sqlx::query(
+        "SELECT session_id, config_id FROM config_state
+         WHERE is_running AND session_id IS NOT NULL
+         ORDER BY config_id",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut sessions: BTreeMap<String, Vec<i64>> = BTreeMap::new();
+    for row in rows {
+        let session_id: String = row.try_get("session_id").map_err(|e| e.to_string())?;
+        let config_id: i64 = row.try_get("config_id").map_err(|e| e.to_string())?;
+        sessions.entry(session_id).or_default().push(config_id);
+    }
+
+    let current = current_session_id();
+    Ok(sessions
+        .into_iter()
+        .map(|(session_id, config_ids)| SessionInfo {
+            is_current: session_id == current,
+            session_id,
+            config_ids,
+        })
+        .collect())
+}
+
+// marks every config the session had running as stopped, returning their ids
+pub async fn release_session(session_id: &str) -> Result<Vec<i64>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    release_session_with_pool(session_id, &pool).await
+}
+
+pub async fn release_session_with_pool(
+    session_id: &str, pool: &SqlitePool,
+) -> Result<Vec<i64>, String> {
+    let rows = sqlx::query(
+        "UPDATE config_state SET is_running = false, session_id = NULL
+         WHERE session_id = ?1
+         RETURNING config_id",
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    rows.into_iter()
+        .map(|row| row.try_get("config_id").map_err(|e| e.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_memory_db_pool;
+    use crate::models::config_model::Config;
+    use crate::models::config_state_model::ConfigState;
+    use crate::utils::config::insert_config_with_pool;
+    use crate::utils::config_state::update_config_state_with_pool;
+
+    #[tokio::test]
+    async fn test_sessions_from_config_states() {
+        let pool = create_memory_db_pool().await.unwrap();
+        for _ in 0..3 {
+            insert_config_with_pool(Config::default(), &pool)
+                .await
+                .unwrap();
+        }
+
+        for config_id in [1, 2] {
+            let state = ConfigState {
+                id: None,
+                config_id,
+                is_running: true,
+                local_port: None,
+            };
+            update_config_state_with_pool(&state, &pool).await.unwrap();
+        }
+        sqlx::query(
+            "UPDATE config_state SET is_running = true, session_id = 'other'
+             WHERE config_id = 3",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let sessions = list_sessions_with_pool(&pool).await.unwrap();
+        assert_eq!(sessions.len(), 2);
+        let current = sessions.iter().find(|s| s.is_current).unwrap();
+        assert_eq!(current.config_ids, vec![1, 2]);
+
+        assert_eq!(
+            release_session_with_pool("other", &pool).await.unwrap(),
+            vec![3]
+        );
+        assert_eq!(list_sessions_with_pool(&pool).await.unwrap().len(), 1);
+    }
+}
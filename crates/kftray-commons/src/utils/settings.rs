@@ -0,0 +1,267 @@
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+use log::warn;
+use serde_json::{
+    Map,
+    Value,
+};
+use sqlx::{
+    Row,
+    SqlitePool,
+};
+
+use crate::db::get_db_pool;
+use crate::models::settings_model::{
+    HotkeyBinding,
+    Settings,
+};
+
+lazy_static! {
+    static ref CURRENT_SETTINGS: RwLock<Settings> = RwLock::new(Settings::default());
+}
+
+// the last settings read from or written to the database, for code paths that
+// can't wait on a query
+pub fn current_settings() -> Settings {
+    CURRENT_SETTINGS.read().unwrap().clone()
+}
+
+fn cache_settings(settings: &Settings) {
+    *CURRENT_SETTINGS.write().unwrap() = settings.clone();
+}
+
+pub fn validate_settings(settings: &Settings) -> Result<(), String> {
+    if IpAddr::from_str(&settings.default_local_address).is_err() {
+        return Err(format!(
+            "Invalid default local address: {}",
+            settings.default_local_address
+        ));
+    }
+
+    if !["tcp", "udp"].contains(&settings.default_protocol.as_str()) {
+        return Err(format!(
+            "Invalid default protocol: {}",
+            settings.default_protocol
+        ));
+    }
+
+    if log::LevelFilter::from_str(&settings.log_level).is_err() {
+        return Err(format!("Invalid log level: {}", settings.log_level));
+    }
+
+    if settings.kube_connect_timeout_secs == 0 || settings.kube_request_timeout_secs == 0 {
+        return Err("Kubernetes API timeouts must be at least 1 second".to_string());
+    }
+
+    if settings.udp_idle_timeout_secs == 0 {
+        return Err("UDP idle timeout must be at least 1 second".to_string());
+    }
+
+    if settings.circuit_breaker_cooldown_secs == 0 {
+        return Err("Circuit breaker cool-down must be at least 1 second".to_string());
+    }
+
+    if settings.kube_qps == Some(0) {
+        return Err("Kubernetes API QPS must not be 0".to_string());
+    }
+
+    if settings.startup_retry_delay_secs == 0 {
+        return Err("Startup retry delay must be at least 1 second".to_string());
+    }
+
+    validate_hotkeys(&settings.hotkeys)?;
+
+    Ok(())
+}
+
+// the app already binds this one to show and hide the window
+pub const TOGGLE_WINDOW_HOTKEY: &str = "CmdOrCtrl+Shift+F1";
+
+fn validate_hotkeys(hotkeys: &[HotkeyBinding]) -> Result<(), String> {
+    let mut seen = HashSet::from([TOGGLE_WINDOW_HOTKEY.to_lowercase()]);
+    for hotkey in hotkeys {
+        let accelerator = hotkey.accelerator.trim();
+        if accelerator.is_empty() {
+            return Err("Hotkeys need a key combination".to_string());
+        }
+        if !seen.insert(accelerator.to_lowercase()) {
+            return Err(format!("Hotkey {} is already in use", accelerator));
+        }
+    }
+    Ok(())
+}
+
+// unknown keys are ignored and a value of the wrong type falls back to its
+// default, so a bad row never takes the whole app down
+fn settings_from_rows(rows: Vec<(String, String)>) -> Settings {
+    let Value::Object(mut merged) = serde_json::to_value(Settings::default()).unwrap_or_default()
+    else {
+        return Settings::default();
+    };
+
+    for (key, raw) in rows {
+        if !merged.contains_key(&key) {
+            continue;
+        }
+
+        let Ok(value) = serde_json::from_str::<Value>(&raw) else {
+            warn!("Ignoring unreadable setting {}: {}", key, raw);
+            continue;
+        };
+
+        let mut candidate = merged.clone();
+        candidate.insert(key.clone(), value);
+        if serde_json::from_value::<Settings>(Value::Object(candidate.clone())).is_ok() {
+            merged = candidate;
+        } else {
+            warn!("Ignoring invalid setting {}: {}", key, raw);
+        }
+    }
+
+    serde_json::from_value(Value::Object(merged)).unwrap_or_default()
+}
+
+pub async fn get_settings() -> Result<Settings, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    let settings = get_settings_with_pool(&pool).await?;
+    cache_settings(&settings);
+    Ok(settings)
+}
+
+pub async fn get_settings_with_pool(pool: &SqlitePool) -> Result<Settings, String> {
+    let rows = sqlx::query("SELECT key, value FROM settings")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let rows = rows
+        .into_iter()
+        .map(|row| {
+            let key: String = row.try_get("key").map_err(|e| e.to_string())?;
+            let value: String = row.try_get("value").map_err(|e| e.to_string())?;
+            Ok((key, value))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(settings_from_rows(rows))
+}
+
+pub async fn update_settings(settings: &Settings) -> Result<(), String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    update_settings_with_pool(settings, &pool).await?;
+    cache_settings(settings);
+    Ok(())
+}
+
+pub async fn update_settings_with_pool(
+    settings: &Settings, pool: &SqlitePool,
+) -> Result<(), String> {
+    validate_settings(settings)?;
+
+    let values: Map<String, Value> = match serde_json::to_value(settings) {
+        Ok(Value::Object(values)) => values,
+        Ok(_) => return Err("Settings must serialize to an object".to_string()),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let mut transaction = pool.begin().await.map_err(|e| e.to_string())?;
+    for (key, value) in values {
+        sqlx::query(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        )
+        .bind(&key)
+        .bind(value.to_string())
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    transaction.commit().await.map_err(|e| e.to_string())
+}
+
+pub async fn set_setting(key: &str, value: Value) -> Result<Settings, String> {
+    let mut values = match serde_json::to_value(get_settings().await?) {
+        Ok(Value::Object(values)) => values,
+        _ => return Err("Settings must serialize to an object".to_string()),
+    };
+
+    if !values.contains_key(key) {
+        return Err(format!("Unknown setting: {}", key));
+    }
+    values.insert(key.to_string(), value);
+
+    let settings: Settings = serde_json::from_value(Value::Object(values))
+        .map_err(|e| format!("Invalid value for setting {}: {}", key, e))?;
+    update_settings(&settings).await?;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::create_memory_db_pool;
+    use crate::models::settings_model::{
+        HostsBackend,
+        HotkeyAction,
+        HotkeyTarget,
+    };
+
+    #[test]
+    fn test_settings_from_rows() {
+        let settings = settings_from_rows(vec![
+            (
+                "default_local_address".to_string(),
+                "\"127.0.0.2\"".to_string(),
+            ),
+            ("hosts_backend".to_string(), "\"disabled\"".to_string()),
+            ("idle_timeout_secs".to_string(), "\"soon\"".to_string()),
+            ("removed_setting".to_string(), "true".to_string()),
+        ]);
+
+        assert_eq!(settings.default_local_address, "127.0.0.2");
+        assert_eq!(settings.hosts_backend, HostsBackend::Disabled);
+        assert_eq!(settings.idle_timeout_secs, 600);
+    }
+
+    #[tokio::test]
+    async fn test_update_and_read_settings() {
+        let pool = create_memory_db_pool().await.unwrap();
+        assert_eq!(
+            get_settings_with_pool(&pool).await.unwrap(),
+            Settings::default()
+        );
+
+        let settings = Settings {
+            default_protocol: "udp".to_string(),
+            start_retry_attempts: 3,
+            hotkeys: vec![HotkeyBinding {
+                accelerator: "CmdOrCtrl+Alt+1".to_string(),
+                target: HotkeyTarget::Tag("db".to_string()),
+                action: HotkeyAction::Toggle,
+            }],
+            ..Default::default()
+        };
+        update_settings_with_pool(&settings, &pool).await.unwrap();
+        assert_eq!(get_settings_with_pool(&pool).await.unwrap(), settings);
+
+        let invalid = Settings {
+            default_local_address: "localhost".to_string(),
+            ..Default::default()
+        };
+        assert!(update_settings_with_pool(&invalid, &pool).await.is_err());
+
+        let taken = Settings {
+            hotkeys: vec![HotkeyBinding {
+                accelerator: "cmdorctrl+shift+f1".to_string(),
+                target: HotkeyTarget::Config(1),
+                action: HotkeyAction::Start,
+            }],
+            ..Default::default()
+        };
+        assert!(update_settings_with_pool(&taken, &pool).await.is_err());
+    }
+}
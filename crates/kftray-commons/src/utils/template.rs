@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::env;
+
+use crate::models::config_model::Config;
+use crate::utils::profile::config_profile;
+use crate::utils::variables::get_variables;
+
+pub fn has_placeholders(input: &str) -> bool {
+    input.contains("${")
+}
+
+pub fn render_template(input: &str, vars: &HashMap<String, String>) -> Result<String, String> {
+    let mut output = String::with_capacity(input.len());
+    let mut missing = Vec::new();
+    let mut rest = input;
+
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        if let Some(escaped) = after.strip_prefix("${") {
+            output.push_str("${");
+            rest = escaped;
+            continue;
+        }
+
+        let Some(body) = after.strip_prefix('{') else {
+            output.push('$');
+            rest = after;
+            continue;
+        };
+
+        let Some(end) = body.find('}') else {
+            return Err(format!("Unterminated placeholder in '{}'", input));
+        };
+
+        let name = &body[..end];
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!(
+                "Invalid placeholder '${{{}}}' in '{}'",
+                name, input
+            ));
+        }
+
+        match vars.get(name).cloned().or_else(|| env::var(name).ok()) {
+            Some(value) => output.push_str(&value),
+            None => missing.push(name.to_string()),
+        }
+
+        rest = &body[end + 1..];
+    }
+
+    output.push_str(rest);
+
+    if !missing.is_empty() {
+        return Err(format!("Unresolved variables: {}", missing.join(", ")));
+    }
+
+    Ok(output)
+}
+
+fn render_optional(
+    value: &Option<String>, vars: &HashMap<String, String>,
+) -> Result<Option<String>, String> {
+    value
+        .as_deref()
+        .map(|value| render_template(value, vars))
+        .transpose()
+}
+
+pub fn apply_variables(config: &Config, vars: &HashMap<String, String>) -> Result<Config, String> {
+    let mut resolved = config.clone();
+
+    resolved.context = render_template(&config.context, vars)?;
+    resolved.namespace = render_template(&config.namespace, vars)?;
+    resolved.kubeconfig = render_optional(&config.kubeconfig, vars)?;
+    resolved.alias = render_optional(&config.alias, vars)?;
+
+    Ok(resolved)
+}
+
+fn is_templated(config: &Config) -> bool {
+    has_placeholders(&config.context)
+        || has_placeholders(&config.namespace)
+        || config.kubeconfig.as_deref().is_some_and(has_placeholders)
+        || config.alias.as_deref().is_some_and(has_placeholders)
+}
+
+pub async fn resolve_config_templates(config: Config) -> Result<Config, String> {
+    if !is_templated(&config) {
+        return Ok(config);
+    }
+
+    let vars = get_variables(config_profile(&config)).await?;
+
+    apply_variables(&config, &vars).map_err(|e| {
+        format!(
+            "Failed to resolve config {}: {}",
+            config.id.unwrap_or_default(),
+            e
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template() {
+        let mut vars = HashMap::new();
+        vars.insert("CLUSTER".to_string(), "stage".to_string());
+
+        assert_eq!(
+            render_template("eks-${CLUSTER}-ctx", &vars).unwrap(),
+            "eks-stage-ctx"
+        );
+        assert_eq!(render_template("plain", &vars).unwrap(), "plain");
+        assert_eq!(render_template("cost $5", &vars).unwrap(), "cost $5");
+        assert_eq!(render_template("$${CLUSTER}", &vars).unwrap(), "${CLUSTER}");
+    }
+
+    #[test]
+    fn test_render_template_reports_missing() {
+        let vars = HashMap::new();
+        let err = render_template("${KFTRAY_TEST_MISSING_VAR}", &vars).unwrap_err();
+        assert!(err.contains("KFTRAY_TEST_MISSING_VAR"));
+        assert!(render_template("${UNTERMINATED", &vars).is_err());
+    }
+}
@@ -0,0 +1,481 @@
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+pub const LENS_TOOL: &str = "lens";
+pub const KUBEFWD_TOOL: &str = "kubefwd";
+pub const K9S_TOOL: &str = "k9s";
+pub const KUBECTL_TOOL: &str = "kubectl";
+
+// the pod annotation k9s starts its port forwards from
+pub const K9S_PORT_FORWARD_ANNOTATION: &str = "k9scli.io/auto-port-forwards";
+
+// kubefwd hands every service its own loopback address, counting up from here
+const KUBEFWD_FIRST_ADDRESS: Ipv4Addr = Ipv4Addr::new(127, 1, 27, 1);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LensForward {
+    pub kind: String,
+    pub namespace: String,
+    pub name: String,
+    pub port: u16,
+    pub forward_port: Option<u16>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KubefwdCommand {
+    pub namespaces: Vec<String>,
+    pub contexts: Vec<String>,
+    pub selector: Option<String>,
+    pub field_selector: Option<String>,
+    pub kubeconfig: Option<String>,
+    // service port to local port
+    pub mappings: HashMap<u16, u16>,
+    pub all_namespaces: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct K9sForward {
+    pub container: String,
+    // a container port number or name
+    pub port: String,
+    pub local_port: Option<u16>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KubectlForward {
+    pub context: Option<String>,
+    pub kubeconfig: Option<String>,
+    pub namespace: Option<String>,
+    // `service`, `pod`, `deployment`..., a bare name is a pod
+    pub kind: String,
+    pub name: String,
+    pub addresses: Vec<String>,
+    // the local port, none for a random one, and the remote port or its name
+    pub ports: Vec<(Option<u16>, String)>,
+}
+
+// where Lens and its forks keep forwards between sessions
+pub fn lens_store_paths() -> Vec<PathBuf> {
+    let Some(config_dir) = dirs::config_dir() else {
+        return Vec::new();
+    };
+    ["Lens", "OpenLens", "Freelens"]
+        .iter()
+        .map(|app| config_dir.join(app).join("port-forwards.json"))
+        .collect()
+}
+
+// the given store, or the first default one that exists
+pub fn read_lens_store(path: Option<&str>) -> Result<String, String> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => lens_store_paths()
+            .into_iter()
+            .find(|path| path.exists())
+            .ok_or_else(|| "No Lens port forward store found".to_string())?,
+    };
+    std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+}
+
+// lens has stored ports both as numbers and as strings
+fn port_value(value: Option<&Value>) -> Option<u16> {
+    match value? {
+        Value::Number(number) => number.as_u64().and_then(|port| u16::try_from(port).ok()),
+        Value::String(port) => port.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+// entries without a usable port are dropped. a forward port of 0 let lens pick
+// a random one
+pub fn lens_forwards(store: &str) -> Result<Vec<LensForward>, String> {
+    let store: Value = serde_json::from_str(store)
+        .map_err(|e| format!("Failed to parse the Lens store: {}", e))?;
+    let forwards = store
+        .get("portForwards")
+        .and_then(Value::as_array)
+        .ok_or_else(|| "The Lens store has no port forwards".to_string())?;
+
+    Ok(forwards
+        .iter()
+        .filter_map(|forward| {
+            let field = |name: &str| {
+                forward
+                    .get(name)
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            Some(LensForward {
+                kind: field("kind").to_lowercase(),
+                namespace: field("namespace"),
+                name: field("name"),
+                port: port_value(forward.get("port"))?,
+                forward_port: port_value(forward.get("forwardPort")).filter(|port| *port != 0),
+            })
+        })
+        .collect())
+}
+
+// whitespace separated words, quotes group and backslashes escape
+pub fn split_command_line(command: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (Some('\''), c) => word.get_or_insert_with(String::new).push(c),
+            (_, '\\') => {
+                if let Some(escaped) = chars.next() {
+                    word.get_or_insert_with(String::new).push(escaped);
+                }
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (_, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err("Unterminated quote in the command".to_string());
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+fn parse_mapping(mapping: &str) -> Result<(u16, u16), String> {
+    let invalid = || format!("Invalid kubefwd port mapping: {}", mapping);
+    let (service_port, local_port) = mapping.split_once(':').ok_or_else(invalid)?;
+    Ok((
+        service_port.trim().parse().map_err(|_| invalid())?,
+        local_port.trim().parse().map_err(|_| invalid())?,
+    ))
+}
+
+// the flags of `kubefwd svc` that decide what gets forwarded. anything that
+// only tunes kubefwd itself is ignored
+pub fn parse_kubefwd_command(command: &str) -> Result<KubefwdCommand, String> {
+    let words = split_command_line(command)?;
+    // the flags alone are fine too
+    let start = match words
+        .iter()
+        .position(|word| matches!(word.as_str(), "svc" | "service" | "services"))
+    {
+        Some(position) => position + 1,
+        None if words.first().is_some_and(|word| word.starts_with('-')) => 0,
+        None => return Err("Expected a `kubefwd svc` command".to_string()),
+    };
+    let mut args = words[start..].iter();
+
+    let mut parsed = KubefwdCommand::default();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if arg.starts_with('-') => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next().cloned())
+                .ok_or_else(|| format!("Missing a value for {}", flag))
+        };
+        match flag {
+            "-n" | "--namespace" => parsed
+                .namespaces
+                .extend(value()?.split(',').map(|ns| ns.trim().to_string())),
+            "-x" | "--context" => parsed
+                .contexts
+                .extend(value()?.split(',').map(|ctx| ctx.trim().to_string())),
+            "-l" | "--selector" => parsed.selector = Some(value()?),
+            "-f" | "--field-selector" => parsed.field_selector = Some(value()?),
+            "-c" | "--kubeconfig" => parsed.kubeconfig = Some(value()?),
+            "-m" | "--mapping" => {
+                for mapping in value()?.split(',') {
+                    let (service_port, local_port) = parse_mapping(mapping)?;
+                    parsed.mappings.insert(service_port, local_port);
+                }
+            }
+            "-A" | "--all-namespaces" => parsed.all_namespaces = true,
+            // flags with a value that don't change what is forwarded
+            "-d" | "--domain" | "-z" | "--resync-interval" => {
+                value()?;
+            }
+            _ => {}
+        }
+    }
+    parsed.namespaces.retain(|namespace| !namespace.is_empty());
+    parsed.contexts.retain(|context| !context.is_empty());
+    Ok(parsed)
+}
+
+// the address kubefwd would give the nth service it forwards
+pub fn kubefwd_address(index: usize) -> Option<String> {
+    u32::from(KUBEFWD_FIRST_ADDRESS)
+        .checked_add(u32::try_from(index).ok()?)
+        .map(|address| Ipv4Addr::from(address).to_string())
+        .filter(|address| address.starts_with("127."))
+}
+
+// services of the first namespace answer to their bare name like kubefwd
+// does, the others are qualified so they don't clash
+pub fn kubefwd_alias(service: &str, namespace: &str, first_namespace: bool) -> String {
+    if first_namespace {
+        service.to_string()
+    } else {
+        format!("{}.{}", service, namespace)
+    }
+}
+
+fn kubectl_kind(kind: &str) -> String {
+    match kind.to_lowercase().as_str() {
+        "svc" | "service" | "services" => "service".to_string(),
+        "po" | "pod" | "pods" => "pod".to_string(),
+        "deploy" | "deployment" | "deployments" => "deployment".to_string(),
+        "sts" | "statefulset" | "statefulsets" => "statefulset".to_string(),
+        "rs" | "replicaset" | "replicasets" => "replicaset".to_string(),
+        kind => kind.split('.').next().unwrap_or(kind).to_string(),
+    }
+}
+
+// `[local]:remote`, a lone port is used on both ends and `0` or nothing
+// before the colon lets kubectl pick one
+fn parse_kubectl_port(spec: &str) -> Result<(Option<u16>, String), String> {
+    let invalid = || format!("Invalid kubectl port: {}", spec);
+    let (local_port, remote) = match spec.split_once(':') {
+        Some(("", remote)) => (None, remote),
+        Some((local_port, remote)) => {
+            let local_port: u16 = local_port.parse().map_err(|_| invalid())?;
+            ((local_port != 0).then_some(local_port), remote)
+        }
+        None => (Some(spec.parse().map_err(|_| invalid())?), spec),
+    };
+    if remote.is_empty() || remote.contains(':') {
+        return Err(invalid());
+    }
+    Ok((local_port, remote.to_string()))
+}
+
+// the command line of a running `kubectl port-forward`, global flags can
+// come before the subcommand too
+pub fn parse_kubectl_port_forward(command: &str) -> Result<KubectlForward, String> {
+    let words = split_command_line(command)?;
+    let kubectl = words
+        .iter()
+        // windows paths lose their backslashes to the splitter, so only the
+        // end of the program is compared
+        .position(|word| {
+            word.to_lowercase()
+                .trim_end_matches(".exe")
+                .ends_with("kubectl")
+        })
+        .ok_or_else(|| "Expected a `kubectl port-forward` command".to_string())?;
+    if !words[kubectl..].iter().any(|word| word == "port-forward") {
+        return Err("Expected a `kubectl port-forward` command".to_string());
+    }
+    let mut args = words[kubectl + 1..].iter();
+
+    let mut parsed = KubectlForward::default();
+    let mut positional = Vec::new();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') || arg == "-" {
+            if arg != "port-forward" || !positional.is_empty() {
+                positional.push(arg.clone());
+            }
+            continue;
+        }
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next().cloned())
+                .ok_or_else(|| format!("Missing a value for {}", flag))
+        };
+        match flag {
+            "-n" | "--namespace" => parsed.namespace = Some(value()?),
+            "--context" => parsed.context = Some(value()?),
+            "--kubeconfig" => parsed.kubeconfig = Some(value()?),
+            "--address" => parsed.addresses.extend(
+                value()?
+                    .split(',')
+                    .map(|address| address.trim().to_string()),
+            ),
+            // flags with a value that don't change what is forwarded
+            "--cluster"
+            | "--user"
+            | "-s"
+            | "--server"
+            | "--token"
+            | "--as"
+            | "--as-group"
+            | "--request-timeout"
+            | "--pod-running-timeout"
+            | "-v"
+            | "--v"
+            | "--cache-dir" => {
+                value()?;
+            }
+            _ => {}
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let target = positional
+        .next()
+        .ok_or_else(|| "The command has no resource to forward to".to_string())?;
+    let (kind, name) = match target.split_once('/') {
+        Some((kind, name)) => (kubectl_kind(kind), name.to_string()),
+        None => ("pod".to_string(), target.clone()),
+    };
+    if name.is_empty() {
+        return Err(format!("Invalid kubectl resource: {}", target));
+    }
+    parsed.kind = kind;
+    parsed.name = name;
+    parsed.ports = positional
+        .map(|spec| parse_kubectl_port(&spec))
+        .collect::<Result<_, _>>()?;
+    if parsed.ports.is_empty() {
+        return Err("The command has no ports to forward".to_string());
+    }
+    parsed.addresses.retain(|address| !address.is_empty());
+    Ok(parsed)
+}
+
+// `container::[local-port:]container-port`, several separated by commas
+pub fn k9s_forwards(annotation: &str) -> Result<Vec<K9sForward>, String> {
+    annotation
+        .split(',')
+        .map(str::trim)
+        .filter(|spec| !spec.is_empty())
+        .map(|spec| {
+            let invalid = || format!("Invalid k9s port forward: {}", spec);
+            let (container, ports) = spec.split_once("::").ok_or_else(invalid)?;
+            let (local_port, port) = match ports.split_once(':') {
+                Some((local_port, port)) => {
+                    (Some(local_port.parse().map_err(|_| invalid())?), port)
+                }
+                None => (None, ports),
+            };
+            if container.is_empty() || port.is_empty() {
+                return Err(invalid());
+            }
+            Ok(K9sForward {
+                container: container.to_string(),
+                port: port.to_string(),
+                local_port,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lens_forwards() {
+        let store = r#"{"portForwards": [
+            {"kind": "service", "namespace": "default", "name": "api", "port": 80, "forwardPort": 8080},
+            {"kind": "pod", "namespace": "db", "name": "pg-0", "port": "5432", "forwardPort": "0"},
+            {"kind": "service", "namespace": "default", "name": "broken"}
+        ], "__internal__": {}}"#;
+        let forwards = lens_forwards(store).unwrap();
+        assert_eq!(forwards.len(), 2);
+        assert_eq!(forwards[0].forward_port, Some(8080));
+        assert_eq!(forwards[1].kind, "pod");
+        assert_eq!(forwards[1].port, 5432);
+        assert_eq!(forwards[1].forward_port, None);
+        assert!(lens_forwards("{}").is_err());
+    }
+
+    #[test]
+    fn test_parse_kubefwd_command() {
+        let command = "sudo -E kubefwd svc -n team-a,team-b --namespace=shared -x 'prod east' \
+                       -l app=api -m 80:8080 --mapping 443:8443 -f metadata.name=api -v";
+        let parsed = parse_kubefwd_command(command).unwrap();
+        assert_eq!(parsed.namespaces, vec!["team-a", "team-b", "shared"]);
+        assert_eq!(parsed.contexts, vec!["prod east"]);
+        assert_eq!(parsed.selector.as_deref(), Some("app=api"));
+        assert_eq!(parsed.field_selector.as_deref(), Some("metadata.name=api"));
+        assert_eq!(parsed.mappings.get(&80), Some(&8080));
+        assert_eq!(parsed.mappings.get(&443), Some(&8443));
+
+        assert!(parse_kubefwd_command("kubefwd svc -m 80").is_err());
+        assert!(parse_kubefwd_command("kubefwd svc -n 'open").is_err());
+        assert!(parse_kubefwd_command("kubectl get pods").is_err());
+        assert_eq!(
+            parse_kubefwd_command("-n default").unwrap().namespaces,
+            vec!["default"]
+        );
+
+        assert_eq!(kubefwd_address(0).as_deref(), Some("127.1.27.1"));
+        assert_eq!(kubefwd_address(255).as_deref(), Some("127.1.28.0"));
+        assert_eq!(kubefwd_alias("api", "team-b", false), "api.team-b");
+    }
+
+    #[test]
+    fn test_parse_kubectl_port_forward() {
+        let parsed = parse_kubectl_port_forward(
+            "/usr/local/bin/kubectl --context kind-dev port-forward -n shop svc/cart \
+             8080:80 9090 :http --address=0.0.0.0,localhost",
+        )
+        .unwrap();
+        assert_eq!(parsed.context.as_deref(), Some("kind-dev"));
+        assert_eq!(parsed.namespace.as_deref(), Some("shop"));
+        assert_eq!(parsed.kind, "service");
+        assert_eq!(parsed.name, "cart");
+        assert_eq!(parsed.addresses, vec!["0.0.0.0", "localhost"]);
+        assert_eq!(
+            parsed.ports,
+            vec![
+                (Some(8080), "80".to_string()),
+                (Some(9090), "9090".to_string()),
+                (None, "http".to_string()),
+            ]
+        );
+
+        let parsed = parse_kubectl_port_forward("kubectl port-forward pg-0 0:5432").unwrap();
+        assert_eq!(parsed.kind, "pod");
+        assert_eq!(parsed.name, "pg-0");
+        assert_eq!(parsed.namespace, None);
+        assert_eq!(parsed.ports, vec![(None, "5432".to_string())]);
+
+        assert!(parse_kubectl_port_forward("kubectl get pods").is_err());
+        assert!(parse_kubectl_port_forward("kubectl port-forward svc/cart").is_err());
+        assert!(parse_kubectl_port_forward("kubectl port-forward svc/cart x:80").is_err());
+        assert!(parse_kubectl_port_forward("kubefwd svc -n port-forward").is_err());
+    }
+
+    #[test]
+    fn test_k9s_forwards() {
+        let forwards = k9s_forwards("web::8080:http, db::5432").unwrap();
+        assert_eq!(
+            forwards,
+            vec![
+                K9sForward {
+                    container: "web".to_string(),
+                    port: "http".to_string(),
+                    local_port: Some(8080),
+                },
+                K9sForward {
+                    container: "db".to_string(),
+                    port: "5432".to_string(),
+                    local_port: None,
+                },
+            ]
+        );
+        assert!(k9s_forwards("web:8080").is_err());
+        assert!(k9s_forwards("web::x:80").is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use std::cmp::Reverse;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
+use sqlx::{
+    Row,
+    SqlitePool,
+};
+
+use crate::db::get_db_pool;
+use crate::models::config_model::Config;
+use crate::models::config_usage_model::ConfigUsage;
+use crate::utils::profile::read_active_profile_configs_with_pool;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UsageOrder {
+    Recent,
+    Frequent,
+}
+
+pub async fn record_config_start(config_id: i64) -> Result<(), String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    record_config_start_with_pool(config_id, &pool).await
+}
+
+pub async fn record_config_start_with_pool(
+    config_id: i64, pool: &SqlitePool,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO config_usage (config_id, last_started_at, start_count)
+         VALUES (?1, CAST(strftime('%s', 'now') AS INTEGER), 1)
+         ON CONFLICT(config_id) DO UPDATE SET
+             last_started_at = excluded.last_started_at,
+             start_count = start_count + 1",
+    )
+    .bind(config_id)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+pub async fn set_config_pinned(config_id: i64, pinned: bool) -> Result<(), String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    set_config_pinned_with_pool(config_id, pinned, &pool).await
+}
+
+pub async fn set_config_pinned_with_pool(
+    config_id: i64, pinned: bool, pool: &SqlitePool,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO config_usage (config_id, pinned) VALUES (?1, ?2)
+         ON CONFLICT(config_id) DO UPDATE SET pinned = excluded.pinned",
+    )
+    .bind(config_id)
+    .bind(pinned)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+pub async fn read_config_usage() -> Result<Vec<ConfigUsage>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    read_config_usage_with_pool(&pool).await
+}
+
+pub async fn read_config_usage_with_pool(pool: &SqlitePool) -> Result<Vec<ConfigUsage>, String> {
+    let rows =
+        sqlx::query("SELECT config_id, last_started_at, start_count, pinned FROM config_usage")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(ConfigUsage {
+                config_id: row.try_get("config_id").map_err(|e| e.to_string())?,
+                last_started_at: row.try_get("last_started_at").map_err(|e| e.to_string())?,
+                start_count: row.try_get("start_count").map_err(|e| e.to_string())?,
+                pinned: row.try_get("pinned").map_err(|e| e.to_string())?,
+            })
+        })
+        .collect()
+}
+
+pub async fn pinned_config_ids() -> Result<HashSet<i64>, String> {
+    let usage = read_config_usage().await?;
+    Ok(usage
+        .into_iter()
+        .filter(|entry| entry.pinned)
+        .map(|entry| entry.config_id)
+        .collect())
+}
+
+pub fn order_configs_by_usage(
+    configs: Vec<Config>, usage: &[ConfigUsage], order: UsageOrder, limit: Option<usize>,
+) -> Vec<Config> {
+    let usage: HashMap<i64, &ConfigUsage> = usage.iter().map(|u| (u.config_id, u)).collect();
+
+    let mut used: Vec<(&ConfigUsage, Config)> = configs
+        .into_iter()
+        .filter_map(|config| {
+            let entry = *usage.get(&config.id?)?;
+            (entry.pinned || entry.start_count > 0).then_some((entry, config))
+        })
+        .collect();
+
+    used.sort_by_key(|(entry, _)| {
+        let metric = match order {
+            UsageOrder::Recent => entry.last_started_at.unwrap_or_default(),
+            UsageOrder::Frequent => entry.start_count,
+        };
+        (Reverse(entry.pinned), Reverse(metric))
+    });
+
+    used.into_iter()
+        .map(|(_, config)| config)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+pub async fn get_configs_by_usage(
+    order: UsageOrder, limit: Option<usize>,
+) -> Result<Vec<Config>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    get_configs_by_usage_with_pool(order, limit, &pool).await
+}
+
+pub async fn get_configs_by_usage_with_pool(
+    order: UsageOrder, limit: Option<usize>, pool: &SqlitePool,
+) -> Result<Vec<Config>, String> {
+    let configs = read_active_profile_configs_with_pool(pool).await?;
+    let usage = read_config_usage_with_pool(pool).await?;
+
+    Ok(order_configs_by_usage(configs, &usage, order, limit))
+}
+
+pub async fn get_recent_configs(limit: Option<usize>) -> Result<Vec<Config>, String> {
+    get_configs_by_usage(UsageOrder::Recent, limit).await
+}
+
+pub async fn get_frequent_configs(limit: Option<usize>) -> Result<Vec<Config>, String> {
+    get_configs_by_usage(UsageOrder::Frequent, limit).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(config_id: i64, last_started_at: i64, start_count: i64, pinned: bool) -> ConfigUsage {
+        ConfigUsage {
+            config_id,
+            last_started_at: Some(last_started_at),
+            start_count,
+            pinned,
+        }
+    }
+
+    #[test]
+    fn test_order_configs_by_usage() {
+        let configs: Vec<Config> = (1..=4)
+            .map(|id| Config {
+                id: Some(id),
+                ..Default::default()
+            })
+            .collect();
+        let usage = vec![
+            usage(1, 300, 1, false),
+            usage(2, 100, 9, false),
+            usage(3, 200, 2, true),
+        ];
+
+        let ids = |configs: Vec<Config>| configs.iter().filter_map(|c| c.id).collect::<Vec<_>>();
+
+        assert_eq!(
+            ids(order_configs_by_usage(
+                configs.clone(),
+                &usage,
+                UsageOrder::Recent,
+                None
+            )),
+            vec![3, 1, 2]
+        );
+        assert_eq!(
+            ids(order_configs_by_usage(
+                configs.clone(),
+                &usage,
+                UsageOrder::Frequent,
+                None
+            )),
+            vec![3, 2, 1]
+        );
+        assert_eq!(
+            ids(order_configs_by_usage(
+                configs,
+                &usage,
+                UsageOrder::Frequent,
+                Some(2)
+            )),
+            vec![3, 2]
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use sqlx::{
+    Row,
+    SqlitePool,
+};
+
+use crate::db::get_db_pool;
+
+pub async fn get_variables(profile: &str) -> Result<HashMap<String, String>, String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    get_variables_with_pool(profile, &pool).await
+}
+
+pub async fn get_variables_with_pool(
+    profile: &str, pool: &SqlitePool,
+) -> Result<HashMap<String, String>, String> {
+    let rows = sqlx::query("SELECT name, value FROM variables WHERE profile = ?1")
+        .bind(profile)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    rows.into_iter()
+        .map(|row| {
+            let name: String = row.try_get("name").map_err(|e| e.to_string())?;
+            let value: String = row.try_get("value").map_err(|e| e.to_string())?;
+            Ok((name, value))
+        })
+        .collect()
+}
+
+pub async fn set_variable(profile: &str, name: &str, value: &str) -> Result<(), String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    set_variable_with_pool(profile, name, value, &pool).await
+}
+
+pub async fn set_variable_with_pool(
+    profile: &str, name: &str, value: &str, pool: &SqlitePool,
+) -> Result<(), String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("Invalid variable name: {}", name));
+    }
+
+    sqlx::query(
+        "INSERT INTO variables (profile, name, value) VALUES (?1, ?2, ?3)
+         ON CONFLICT(profile, name) DO UPDATE SET value = excluded.value",
+    )
+    .bind(profile)
+    .bind(name)
+    .bind(value)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+pub async fn delete_variable(profile: &str, name: &str) -> Result<(), String> {
+    let pool = get_db_pool().await.map_err(|e| e.to_string())?;
+    delete_variable_with_pool(profile, name, &pool).await
+}
+
+pub async fn delete_variable_with_pool(
+    profile: &str, name: &str, pool: &SqlitePool,
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM variables WHERE profile = ?1 AND name = ?2")
+        .bind(profile)
+        .bind(name)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
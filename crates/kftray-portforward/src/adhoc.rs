@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::sync::atomic::{
+    AtomicI64,
+    Ordering,
+};
+use std::sync::{
+    Arc,
+    Mutex,
+};
+use std::time::Duration;
+
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::response::CustomResponse;
+use lazy_static::lazy_static;
+use log::info;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::audit::start_with_reason;
+use crate::headless::{
+    hold_until_done,
+    start_config,
+    stop_config,
+};
+use crate::models::kube::HttpLogState;
+use crate::shutdown::{
+    shutdown,
+    SHUTDOWN_GRACE,
+};
+
+// ad-hoc forwards get ids far below anything sqlite hands out, so they never
+// match a stored config
+const ADHOC_ID_BASE: i64 = -1_000_000;
+
+lazy_static! {
+    static ref NEXT_ADHOC_ID: AtomicI64 = AtomicI64::new(ADHOC_ID_BASE);
+    static ref ADHOC_FORWARDS: Mutex<HashMap<i64, Config>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdhocForwardSpec {
+    pub context: String,
+    pub namespace: String,
+    #[serde(default)]
+    pub service: Option<String>,
+    // a pod label selector, used instead of the service when set
+    #[serde(default)]
+    pub target: Option<String>,
+    // forwards through a proxy pod to this address, the service only names it
+    #[serde(default)]
+    pub remote_address: Option<String>,
+    pub remote_port: u16,
+    #[serde(default)]
+    pub local_port: Option<u16>,
+    #[serde(default)]
+    pub local_address: Option<String>,
+    #[serde(default)]
+    pub protocol: Option<String>,
+    #[serde(default)]
+    pub kubeconfig: Option<String>,
+    // required when the context is marked as production
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+pub fn is_adhoc_forward(config_id: i64) -> bool {
+    config_id <= ADHOC_ID_BASE
+}
+
+pub fn adhoc_config(config_id: i64, spec: &AdhocForwardSpec) -> Result<Config, String> {
+    if spec.remote_port == 0 {
+        return Err("Ad-hoc forward needs a remote port".to_string());
+    }
+
+    let target = spec
+        .target
+        .clone()
+        .filter(|target| !target.trim().is_empty());
+    let service = spec
+        .service
+        .clone()
+        .filter(|service| !service.trim().is_empty());
+    let remote_address = spec
+        .remote_address
+        .clone()
+        .filter(|address| !address.trim().is_empty());
+    let workload_type = match (&remote_address, &target, &service) {
+        (Some(_), _, Some(_)) => "proxy",
+        (Some(_), _, None) => return Err("Ad-hoc proxy forward needs a service name".to_string()),
+        (None, Some(_), _) => "pod",
+        (None, None, Some(_)) => "service",
+        (None, None, None) => {
+            return Err("Ad-hoc forward needs a service or a pod label".to_string())
+        }
+    };
+
+    Ok(Config {
+        id: Some(config_id),
+        alias: Some(format!(
+            "adhoc-{}",
+            target.as_deref().or(service.as_deref()).unwrap_or_default()
+        )),
+        service,
+        target,
+        namespace: spec.namespace.clone(),
+        context: spec.context.clone(),
+        kubeconfig: Some(
+            spec.kubeconfig
+                .clone()
+                .unwrap_or_else(|| "default".to_string()),
+        ),
+        workload_type: Some(workload_type.to_string()),
+        protocol: spec.protocol.clone().unwrap_or_else(|| "tcp".to_string()),
+        remote_port: Some(spec.remote_port),
+        local_port: Some(spec.local_port.unwrap_or(spec.remote_port)),
+        local_address: Some(
+            spec.local_address
+                .clone()
+                .unwrap_or_else(|| "127.0.0.1".to_string()),
+        ),
+        remote_address,
+        domain_enabled: Some(false),
+        ..Config::default()
+    })
+}
+
+pub fn list_adhoc_forwards() -> Vec<Config> {
+    let mut forwards: Vec<Config> = ADHOC_FORWARDS.lock().unwrap().values().cloned().collect();
+    forwards.sort_by_key(|config| std::cmp::Reverse(config.id));
+    forwards
+}
+
+// called from the stop paths, the forward itself is already being torn down
+pub(crate) fn take_adhoc_forwards(config_id: Option<i64>) -> Vec<Config> {
+    let mut forwards = ADHOC_FORWARDS.lock().unwrap();
+    match config_id {
+        Some(config_id) => forwards.remove(&config_id).into_iter().collect(),
+        None => forwards.drain().map(|(_, config)| config).collect(),
+    }
+}
+
+// runs a forward from a spec that is never written to the database, it only
+// lives until it is stopped or kftray shuts down
+pub async fn start_adhoc_forward(
+    spec: AdhocForwardSpec, http_log_state: Arc<HttpLogState>,
+) -> Result<(i64, Vec<CustomResponse>), String> {
+    let config_id = NEXT_ADHOC_ID.fetch_sub(1, Ordering::Relaxed);
+    let config = adhoc_config(config_id, &spec)?;
+
+    info!(
+        "Starting ad-hoc forward {} to {}/{}",
+        config_id,
+        config.namespace,
+        config.alias.as_deref().unwrap_or_default()
+    );
+    ADHOC_FORWARDS
+        .lock()
+        .unwrap()
+        .insert(config_id, config.clone());
+
+    let configs = [config.clone()];
+    let reason = spec.reason.as_deref();
+    match start_with_reason(&configs, reason, start_config(config, http_log_state)).await {
+        Ok(responses) => Ok((config_id, responses)),
+        Err(e) => {
+            take_adhoc_forwards(Some(config_id));
+            Err(e)
+        }
+    }
+}
+
+pub async fn stop_adhoc_forward(config_id: i64) -> Result<CustomResponse, String> {
+    let config = ADHOC_FORWARDS
+        .lock()
+        .unwrap()
+        .get(&config_id)
+        .cloned()
+        .ok_or_else(|| format!("No ad-hoc forward with id {}", config_id))?;
+    stop_config(&config).await
+}
+
+// a one-off tunnel from the command line, held open until the process is told
+// to stop or its ttl elapses
+pub async fn run_adhoc_forward(
+    spec: AdhocForwardSpec, ttl: Option<Duration>,
+) -> Result<(), String> {
+    let http_log_state = Arc::new(HttpLogState::new());
+    let (config_id, _) = start_adhoc_forward(spec, http_log_state).await?;
+    info!("Ad-hoc forward {} running, press Ctrl+C to stop", config_id);
+
+    hold_until_done(&[config_id], ttl).await;
+    shutdown(SHUTDOWN_GRACE).await.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_cluster::{
+        drop_forward,
+        mock_cluster,
+        MOCK_CONTEXT,
+    };
+
+    #[test]
+    fn test_adhoc_config() {
+        let spec = AdhocForwardSpec {
+            context: "kind".to_string(),
+            namespace: "default".to_string(),
+            service: Some("postgres".to_string()),
+            remote_port: 5432,
+            ..Default::default()
+        };
+        let config = adhoc_config(ADHOC_ID_BASE, &spec).unwrap();
+        assert_eq!(config.workload_type.as_deref(), Some("service"));
+        assert_eq!(config.local_port, Some(5432));
+        assert_eq!(config.protocol, "tcp");
+        assert_eq!(config.remote_address, None);
+        assert!(is_adhoc_forward(ADHOC_ID_BASE));
+        assert!(!is_adhoc_forward(1));
+
+        let by_label = AdhocForwardSpec {
+            target: Some("app=api".to_string()),
+            local_port: Some(18080),
+            ..spec.clone()
+        };
+        let config = adhoc_config(ADHOC_ID_BASE - 1, &by_label).unwrap();
+        assert_eq!(config.workload_type.as_deref(), Some("pod"));
+        assert_eq!(config.local_port, Some(18080));
+
+        let proxied = AdhocForwardSpec {
+            remote_address: Some("10.0.0.7".to_string()),
+            protocol: Some("udp".to_string()),
+            ..spec.clone()
+        };
+        let config = adhoc_config(ADHOC_ID_BASE, &proxied).unwrap();
+        assert_eq!(config.workload_type.as_deref(), Some("proxy"));
+        assert_eq!(config.remote_address.as_deref(), Some("10.0.0.7"));
+
+        let no_target = AdhocForwardSpec {
+            service: None,
+            ..spec.clone()
+        };
+        assert!(adhoc_config(ADHOC_ID_BASE, &no_target).is_err());
+        assert!(adhoc_config(
+            ADHOC_ID_BASE,
+            &AdhocForwardSpec {
+                remote_port: 0,
+                ..spec
+            }
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_start_pod_label_forward() {
+        let spec = AdhocForwardSpec {
+            context: MOCK_CONTEXT.to_string(),
+            namespace: "default".to_string(),
+            target: Some("app=api".to_string()),
+            remote_port: 8080,
+            local_port: Some(0),
+            kubeconfig: Some(mock_cluster().await),
+            ..Default::default()
+        };
+        let config_id = NEXT_ADHOC_ID.fetch_sub(1, Ordering::Relaxed);
+        let config = adhoc_config(config_id, &spec).unwrap();
+        assert_eq!(config.service, None);
+
+        let responses = start_config(config.clone(), Arc::new(HttpLogState::new()))
+            .await
+            .unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].status, 0);
+        assert_eq!(responses[0].service, "app=api");
+        assert_ne!(responses[0].local_port, 0);
+
+        drop_forward(config_id);
+    }
+}
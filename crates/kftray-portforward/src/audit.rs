@@ -0,0 +1,27 @@
+use std::future::Future;
+
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::response::CustomResponse;
+use kftray_commons::utils::access_audit::{
+    record_access_audit,
+    require_access_reason,
+};
+use log::error;
+
+// forwards into production contexts only start with a reason, which lands in
+// the access audit once they are up
+pub async fn start_with_reason<F>(
+    configs: &[Config], reason: Option<&str>, start: F,
+) -> Result<Vec<CustomResponse>, String>
+where
+    F: Future<Output = Result<Vec<CustomResponse>, String>>,
+{
+    let audit = require_access_reason(configs, reason).await?;
+    let result = start.await;
+    if result.is_ok() {
+        if let Err(e) = record_access_audit(&audit).await {
+            error!("Failed to record access audit: {}", e);
+        }
+    }
+    result
+}
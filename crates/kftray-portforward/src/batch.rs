@@ -0,0 +1,157 @@
+use std::sync::Arc;
+
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::response::{
+    CustomResponse,
+    ForwardOutcome,
+};
+use log::{
+    info,
+    warn,
+};
+use serde::Serialize;
+
+use crate::audit::start_with_reason;
+use crate::headless::{
+    start_config,
+    stop_config,
+};
+use crate::models::kube::HttpLogState;
+use crate::outcome::summarize_outcomes;
+use crate::progress::{
+    Progress,
+    ProgressKind,
+};
+use crate::startup::startup_order;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct BatchFailure {
+    pub config_id: i64,
+    pub error: String,
+}
+
+// one failed config doesn't stop the rest of the batch, so the outcome is
+// reported per config
+#[derive(Debug, Default, Serialize)]
+pub struct BatchReport {
+    pub succeeded: Vec<i64>,
+    pub failed: Vec<BatchFailure>,
+    pub responses: Vec<CustomResponse>,
+}
+
+impl BatchReport {
+    fn fail(&mut self, config_id: i64, error: String) {
+        warn!("Batch operation on config {} failed: {}", config_id, error);
+        self.failed.push(BatchFailure { config_id, error });
+    }
+
+    pub fn errors(&self) -> Vec<String> {
+        self.failed
+            .iter()
+            .map(|failure| format!("Config {}: {}", failure.config_id, failure.error))
+            .collect()
+    }
+
+    pub fn outcomes(&self) -> Vec<ForwardOutcome> {
+        let mut outcomes: Vec<ForwardOutcome> =
+            self.responses.iter().map(ForwardOutcome::from).collect();
+        outcomes.extend(self.failed.iter().map(|failure| ForwardOutcome::Failed {
+            config_id: Some(failure.config_id),
+            error: failure.error.clone(),
+        }));
+        summarize_outcomes(outcomes)
+    }
+}
+
+fn config_name(config: &Config) -> String {
+    let config_id = config.id.unwrap_or_default();
+    config
+        .alias
+        .clone()
+        .unwrap_or_else(|| config_id.to_string())
+}
+
+// starts the configs in dependency order. a reason is needed when any of them
+// targets a production context
+pub async fn start_configs_batch(
+    configs: Vec<Config>, reason: Option<&str>, http_log_state: Arc<HttpLogState>,
+) -> BatchReport {
+    let total = configs.len();
+    let progress = Progress::begin(
+        ProgressKind::StartAll,
+        total,
+        format!("Starting {} config(s)", total),
+    );
+    let mut report = BatchReport::default();
+
+    let (ordered, cyclic) = startup_order(configs);
+    for config in cyclic {
+        report.fail(
+            config.id.unwrap_or_default(),
+            "Its dependencies form a cycle".to_string(),
+        );
+        progress.advance(format!("Skipped {}", config_name(&config)));
+    }
+
+    for config in ordered {
+        let config_id = config.id.unwrap_or_default();
+        let name = config_name(&config);
+        let configs = [config.clone()];
+        let start = start_config(config, http_log_state.clone());
+        match start_with_reason(&configs, reason, start).await {
+            Ok(responses) => {
+                report.succeeded.push(config_id);
+                report.responses.extend(responses);
+                progress.advance(format!("Started {}", name));
+            }
+            Err(e) => {
+                report.fail(config_id, e);
+                progress.advance(format!("Failed to start {}", name));
+            }
+        }
+    }
+
+    info!(
+        "Batch start finished: {} started, {} failed",
+        report.succeeded.len(),
+        report.failed.len()
+    );
+    progress.finish(format!(
+        "Started {} of {} config(s)",
+        report.succeeded.len(),
+        total
+    ));
+    report
+}
+
+pub async fn stop_configs_batch(configs: &[Config]) -> BatchReport {
+    let progress = Progress::begin(
+        ProgressKind::StopAll,
+        configs.len(),
+        format!("Stopping {} config(s)", configs.len()),
+    );
+    let mut report = BatchReport::default();
+
+    for config in configs {
+        let config_id = config.id.unwrap_or_default();
+        let name = config_name(config);
+        match stop_config(config).await {
+            Ok(response) => {
+                report.succeeded.push(config_id);
+                report.responses.push(response);
+                progress.advance(format!("Stopped {}", name));
+            }
+            Err(e) => {
+                report.fail(config_id, e);
+                progress.advance(format!("Failed to stop {}", name));
+            }
+        }
+    }
+
+    progress.finish(format!(
+        "Stopped {} of {} config(s)",
+        report.succeeded.len(),
+        configs.len()
+    ));
+    report
+}
@@ -0,0 +1,248 @@
+use std::time::Duration;
+
+use kftray_commons::utils::config_state::{
+    bound_local_port,
+    get_configs_state,
+};
+use log::info;
+use serde::Serialize;
+use tokio::io::{
+    AsyncReadExt,
+    AsyncWriteExt,
+};
+use tokio::net::TcpStream;
+use tokio::time::{
+    timeout,
+    Instant,
+};
+
+use crate::readiness::probe_address;
+use crate::reload::running_config;
+
+const PING_SIZE: usize = 64;
+const BULK_CHUNK_SIZE: usize = 64 * 1024;
+// an echo that takes longer than this isn't coming
+const ECHO_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_BENCHMARK_DURATION: Duration = Duration::from_secs(300);
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub min_us: u64,
+    pub p50_us: u64,
+    pub p90_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct BenchmarkReport {
+    pub config_id: i64,
+    pub local_port: u16,
+    pub connect_us: u64,
+    pub latency: LatencyStats,
+    pub bytes_sent: u64,
+    pub bytes_echoed: u64,
+    pub throughput_mbps: f64,
+    pub duration_ms: u64,
+}
+
+// nearest rank percentiles over the round trip times
+pub fn latency_stats(mut samples: Vec<Duration>) -> LatencyStats {
+    if samples.is_empty() {
+        return LatencyStats::default();
+    }
+    samples.sort();
+    let percentile = |p: usize| {
+        let rank = (samples.len() * p).div_ceil(100).max(1);
+        samples[rank - 1].as_micros() as u64
+    };
+    LatencyStats {
+        samples: samples.len(),
+        min_us: samples[0].as_micros() as u64,
+        p50_us: percentile(50),
+        p90_us: percentile(90),
+        p99_us: percentile(99),
+        max_us: samples[samples.len() - 1].as_micros() as u64,
+    }
+}
+
+fn megabits_per_second(bytes: u64, elapsed: Duration) -> f64 {
+    if elapsed.is_zero() {
+        return 0.0;
+    }
+    (bytes as f64 * 8.0) / elapsed.as_secs_f64() / 1_000_000.0
+}
+
+// small payloads one at a time, each waits for its echo
+async fn measure_latency(stream: &mut TcpStream, until: Instant) -> Result<Vec<Duration>, String> {
+    let mut samples = Vec::new();
+    let mut payload = [0u8; PING_SIZE];
+    let mut echo = [0u8; PING_SIZE];
+    let mut sequence: u64 = 0;
+
+    while Instant::now() < until {
+        payload[..8].copy_from_slice(&sequence.to_be_bytes());
+        let sent_at = Instant::now();
+        stream
+            .write_all(&payload)
+            .await
+            .map_err(|e| format!("Failed to send: {}", e))?;
+        timeout(ECHO_TIMEOUT, stream.read_exact(&mut echo))
+            .await
+            .map_err(|_| "The target did not echo the payload back".to_string())?
+            .map_err(|e| format!("Failed to read the echo: {}", e))?;
+        if echo != payload {
+            return Err("The target answered with something other than an echo".to_string());
+        }
+        samples.push(sent_at.elapsed());
+        sequence += 1;
+    }
+    Ok(samples)
+}
+
+// writes as fast as the tunnel takes it while the echoes are read back
+async fn measure_throughput(stream: TcpStream, until: Instant) -> Result<(u64, u64), String> {
+    let (mut reader, mut writer) = stream.into_split();
+
+    let write = async move {
+        let chunk = vec![0x5a; BULK_CHUNK_SIZE];
+        let mut sent: u64 = 0;
+        while Instant::now() < until {
+            writer
+                .write_all(&chunk)
+                .await
+                .map_err(|e| format!("Failed to send: {}", e))?;
+            sent += chunk.len() as u64;
+        }
+        let _ = writer.shutdown().await;
+        Ok::<u64, String>(sent)
+    };
+
+    let read = async move {
+        let mut buffer = vec![0u8; BULK_CHUNK_SIZE];
+        let mut echoed: u64 = 0;
+        loop {
+            // echoes still in flight once writing stopped get a short grace
+            let wait = until.saturating_duration_since(Instant::now()) + ECHO_TIMEOUT;
+            match timeout(wait, reader.read(&mut buffer)).await {
+                Ok(Ok(0)) | Err(_) => break,
+                Ok(Ok(read)) => echoed += read as u64,
+                Ok(Err(e)) => return Err(format!("Failed to read the echo: {}", e)),
+            }
+        }
+        Ok::<u64, String>(echoed)
+    };
+
+    let (sent, echoed) = tokio::join!(write, read);
+    Ok((sent?, echoed?))
+}
+
+// pushes synthetic traffic through a running forward whose target echoes what
+// it gets, e.g. an echo server or socat pod. half the time goes to round trip
+// latency, the other half to bulk throughput
+pub async fn benchmark_forward(
+    config_id: i64, duration: Duration,
+) -> Result<BenchmarkReport, String> {
+    let config = running_config(config_id)
+        .ok_or_else(|| format!("Config {} is not running, start it first", config_id))?;
+    if config.protocol == "udp" {
+        return Err("Only TCP forwards can be benchmarked".to_string());
+    }
+    let duration = duration.min(MAX_BENCHMARK_DURATION);
+    let states = get_configs_state().await?;
+    let local_port = bound_local_port(&config, &states)
+        .filter(|port| *port != 0)
+        .ok_or_else(|| format!("Config {} has no local port", config_id))?;
+    let addr = probe_address(&config, local_port);
+    info!(
+        "Benchmarking config {} through {} for {}s",
+        config_id,
+        addr,
+        duration.as_secs()
+    );
+
+    let started_at = Instant::now();
+    let mut stream = timeout(ECHO_TIMEOUT, TcpStream::connect(addr))
+        .await
+        .map_err(|_| format!("Connecting to {} timed out", addr))?
+        .map_err(|e| format!("Failed to connect to {}: {}", addr, e))?;
+    let connect_us = started_at.elapsed().as_micros() as u64;
+    let _ = stream.set_nodelay(true);
+
+    let samples = measure_latency(&mut stream, started_at + duration / 2).await?;
+    drop(stream);
+
+    let stream = TcpStream::connect(addr)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", addr, e))?;
+    let bulk_started_at = Instant::now();
+    let (bytes_sent, bytes_echoed) = measure_throughput(stream, started_at + duration).await?;
+    let bulk_elapsed = bulk_started_at.elapsed();
+
+    let report = BenchmarkReport {
+        config_id,
+        local_port,
+        connect_us,
+        latency: latency_stats(samples),
+        bytes_sent,
+        bytes_echoed,
+        throughput_mbps: megabits_per_second(bytes_echoed, bulk_elapsed),
+        duration_ms: started_at.elapsed().as_millis() as u64,
+    };
+    info!(
+        "Benchmark of config {}: p50 {}us, p99 {}us, {:.1} Mbit/s",
+        config_id, report.latency.p50_us, report.latency.p99_us, report.throughput_mbps
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn test_latency_stats() {
+        let samples = (1..=100).rev().map(Duration::from_micros).collect();
+        let stats = latency_stats(samples);
+        assert_eq!(stats.samples, 100);
+        assert_eq!(stats.min_us, 1);
+        assert_eq!(stats.p50_us, 50);
+        assert_eq!(stats.p90_us, 90);
+        assert_eq!(stats.p99_us, 99);
+        assert_eq!(stats.max_us, 100);
+
+        assert_eq!(latency_stats(vec![Duration::from_micros(7)]).p99_us, 7);
+        assert_eq!(latency_stats(Vec::new()), LatencyStats::default());
+        assert_eq!(megabits_per_second(1_000_000, Duration::from_secs(8)), 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_measure_against_echo() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let (mut reader, mut writer) = stream.into_split();
+                    let _ = tokio::io::copy(&mut reader, &mut writer).await;
+                });
+            }
+        });
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let until = Instant::now() + Duration::from_millis(50);
+        assert!(!measure_latency(&mut stream, until)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let until = Instant::now() + Duration::from_millis(50);
+        let (sent, echoed) = measure_throughput(stream, until).await.unwrap();
+        assert!(sent > 0);
+        assert_eq!(sent, echoed);
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
+use lazy_static::lazy_static;
+use log::info;
+use tokio::sync::watch;
+
+lazy_static! {
+    // tokens of the starts currently in flight, by config id
+    static ref START_CANCELS: Mutex<HashMap<i64, CancelToken>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug)]
+pub struct CancelToken {
+    cancelled: Arc<watch::Sender<bool>>,
+}
+
+impl Default for CancelToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        CancelToken {
+            cancelled: Arc::new(watch::channel(false).0),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancelled.borrow()
+    }
+
+    pub async fn cancelled(&self) {
+        let mut receiver = self.cancelled.subscribe();
+        // the sender lives as long as self, so this only returns once cancelled
+        let _ = receiver.wait_for(|cancelled| *cancelled).await;
+    }
+
+    // drives the future until it finishes, or drops it at its current await
+    // and returns None once the token is cancelled
+    pub async fn run<F: Future>(&self, future: F) -> Option<F::Output> {
+        tokio::select! {
+            output = future => Some(output),
+            _ = self.cancelled() => None,
+        }
+    }
+}
+
+// keeps the token reachable through cancel_start while the start runs
+pub(crate) struct StartRegistration {
+    config_ids: Vec<i64>,
+}
+
+impl Drop for StartRegistration {
+    fn drop(&mut self) {
+        let mut cancels = START_CANCELS.lock().unwrap();
+        for config_id in &self.config_ids {
+            cancels.remove(config_id);
+        }
+    }
+}
+
+pub(crate) fn register_start(config_ids: &[i64], token: &CancelToken) -> StartRegistration {
+    let mut cancels = START_CANCELS.lock().unwrap();
+    for config_id in config_ids {
+        cancels.insert(*config_id, token.clone());
+    }
+    StartRegistration {
+        config_ids: config_ids.to_vec(),
+    }
+}
+
+pub fn is_start_in_flight(config_id: i64) -> bool {
+    START_CANCELS.lock().unwrap().contains_key(&config_id)
+}
+
+// aborts the start the config is part of, the whole batch it was started with
+// is rolled back. false when nothing was starting
+pub fn cancel_start(config_id: i64) -> bool {
+    let token = START_CANCELS.lock().unwrap().get(&config_id).cloned();
+    match token {
+        Some(token) => {
+            info!("Cancelling the start of config {}", config_id);
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cancel_start() {
+        let token = CancelToken::new();
+        let registration = register_start(&[-174], &token);
+        assert!(is_start_in_flight(-174));
+
+        let run = tokio::spawn({
+            let token = token.clone();
+            async move { token.run(std::future::pending::<()>()).await }
+        });
+        assert!(cancel_start(-174));
+        assert_eq!(run.await.unwrap(), None);
+        assert!(token.is_cancelled());
+
+        drop(registration);
+        assert!(!is_start_in_flight(-174));
+        assert!(!cancel_start(-174));
+        assert_eq!(CancelToken::new().run(async { 1 }).await, Some(1));
+    }
+}
@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{
+    BufWriter,
+    Write,
+};
+use std::net::{
+    IpAddr,
+    Ipv4Addr,
+    SocketAddr,
+    SocketAddrV4,
+};
+use std::path::PathBuf;
+use std::sync::{
+    Arc,
+    Mutex,
+};
+use std::time::{
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+use kftray_commons::utils::config_dir::get_capture_folder_path;
+use lazy_static::lazy_static;
+use log::{
+    error,
+    info,
+};
+
+const LINKTYPE_RAW: u16 = 101;
+const MAX_SEGMENT_SIZE: usize = 16 * 1024;
+
+const TCP_FIN: u8 = 0x01;
+const TCP_SYN: u8 = 0x02;
+const TCP_PSH: u8 = 0x08;
+const TCP_ACK: u8 = 0x10;
+
+lazy_static! {
+    static ref CAPTURES: Mutex<HashMap<i64, Arc<PcapWriter>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug)]
+pub struct PcapWriter {
+    path: PathBuf,
+    file: Mutex<BufWriter<File>>,
+}
+
+fn block(block_type: u32, body: &[u8]) -> Vec<u8> {
+    let padding = (4 - body.len() % 4) % 4;
+    let total_length = (12 + body.len() + padding) as u32;
+
+    let mut block = Vec::with_capacity(total_length as usize);
+    block.extend_from_slice(&block_type.to_le_bytes());
+    block.extend_from_slice(&total_length.to_le_bytes());
+    block.extend_from_slice(body);
+    block.resize(block.len() + padding, 0);
+    block.extend_from_slice(&total_length.to_le_bytes());
+    block
+}
+
+fn section_header_block() -> Vec<u8> {
+    let mut body = Vec::with_capacity(16);
+    body.extend_from_slice(&0x1A2B_3C4Du32.to_le_bytes());
+    body.extend_from_slice(&1u16.to_le_bytes());
+    body.extend_from_slice(&0u16.to_le_bytes());
+    body.extend_from_slice(&(-1i64).to_le_bytes());
+    block(0x0A0D_0D0A, &body)
+}
+
+fn interface_description_block() -> Vec<u8> {
+    let mut body = Vec::with_capacity(8);
+    body.extend_from_slice(&LINKTYPE_RAW.to_le_bytes());
+    body.extend_from_slice(&0u16.to_le_bytes());
+    body.extend_from_slice(&0u32.to_le_bytes());
+    block(0x0000_0001, &body)
+}
+
+fn enhanced_packet_block(timestamp_us: u64, packet: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(20 + packet.len());
+    body.extend_from_slice(&0u32.to_le_bytes());
+    body.extend_from_slice(&((timestamp_us >> 32) as u32).to_le_bytes());
+    body.extend_from_slice(&(timestamp_us as u32).to_le_bytes());
+    body.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+    body.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+    body.extend_from_slice(packet);
+    block(0x0000_0006, &body)
+}
+
+fn checksum(chunks: &[&[u8]]) -> u16 {
+    let mut sum: u32 = 0;
+
+    for chunk in chunks {
+        let mut words = chunk.chunks_exact(2);
+        for word in &mut words {
+            sum += u16::from_be_bytes([word[0], word[1]]) as u32;
+        }
+        if let [last] = words.remainder() {
+            sum += (*last as u32) << 8;
+        }
+    }
+
+    while sum >> 16 != 0 {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+
+    !(sum as u16)
+}
+
+pub fn build_tcp_packet(
+    src: SocketAddrV4, dst: SocketAddrV4, seq: u32, ack: u32, flags: u8, payload: &[u8],
+) -> Vec<u8> {
+    let tcp_length = 20 + payload.len();
+    let total_length = 20 + tcp_length;
+
+    let mut tcp = Vec::with_capacity(tcp_length);
+    tcp.extend_from_slice(&src.port().to_be_bytes());
+    tcp.extend_from_slice(&dst.port().to_be_bytes());
+    tcp.extend_from_slice(&seq.to_be_bytes());
+    tcp.extend_from_slice(&ack.to_be_bytes());
+    tcp.push(5 << 4);
+    tcp.push(flags);
+    tcp.extend_from_slice(&u16::MAX.to_be_bytes());
+    tcp.extend_from_slice(&[0, 0, 0, 0]);
+    tcp.extend_from_slice(payload);
+
+    let mut pseudo_header = Vec::with_capacity(12);
+    pseudo_header.extend_from_slice(&src.ip().octets());
+    pseudo_header.extend_from_slice(&dst.ip().octets());
+    pseudo_header.extend_from_slice(&[0, 6]);
+    pseudo_header.extend_from_slice(&(tcp_length as u16).to_be_bytes());
+    let tcp_checksum = checksum(&[&pseudo_header, &tcp]);
+    tcp[16..18].copy_from_slice(&tcp_checksum.to_be_bytes());
+
+    let mut ip = Vec::with_capacity(total_length);
+    ip.extend_from_slice(&[0x45, 0]);
+    ip.extend_from_slice(&(total_length as u16).to_be_bytes());
+    ip.extend_from_slice(&[0, 0, 0x40, 0, 64, 6, 0, 0]);
+    ip.extend_from_slice(&src.ip().octets());
+    ip.extend_from_slice(&dst.ip().octets());
+    let ip_checksum = checksum(&[&ip]);
+    ip[10..12].copy_from_slice(&ip_checksum.to_be_bytes());
+
+    ip.extend_from_slice(&tcp);
+    ip
+}
+
+fn timestamp_us() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or_default()
+}
+
+impl PcapWriter {
+    pub fn create(path: PathBuf) -> Result<Self, String> {
+        let mut file = BufWriter::new(File::create(&path).map_err(|e| e.to_string())?);
+        file.write_all(&section_header_block())
+            .and_then(|_| file.write_all(&interface_description_block()))
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    pub fn write_packet(&self, packet: &[u8]) {
+        let mut file = self.file.lock().unwrap();
+        if let Err(e) = file.write_all(&enhanced_packet_block(timestamp_us(), packet)) {
+            error!("Failed to write capture packet to {:?}: {}", self.path, e);
+        }
+    }
+
+    pub fn flush(&self) {
+        if let Err(e) = self.file.lock().unwrap().flush() {
+            error!("Failed to flush capture file {:?}: {}", self.path, e);
+        }
+    }
+}
+
+fn to_v4(addr: SocketAddr, fallback: Ipv4Addr) -> SocketAddrV4 {
+    match addr.ip() {
+        IpAddr::V4(ip) if !ip.is_unspecified() => SocketAddrV4::new(ip, addr.port()),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => SocketAddrV4::new(ip, addr.port()),
+            None => SocketAddrV4::new(fallback, addr.port()),
+        },
+        _ => SocketAddrV4::new(fallback, addr.port()),
+    }
+}
+
+#[derive(Debug)]
+pub struct TcpFlow {
+    writer: Arc<PcapWriter>,
+    client: SocketAddrV4,
+    server: SocketAddrV4,
+    sequence: Mutex<(u32, u32)>,
+}
+
+impl TcpFlow {
+    pub fn new(writer: Arc<PcapWriter>, client: SocketAddr, server: SocketAddr) -> Self {
+        let client = to_v4(client, Ipv4Addr::new(127, 0, 0, 2));
+        let server = to_v4(server, Ipv4Addr::LOCALHOST);
+        let client_isn = rand::random::<u32>();
+        let server_isn = rand::random::<u32>();
+
+        writer.write_packet(&build_tcp_packet(
+            client,
+            server,
+            client_isn,
+            0,
+            TCP_SYN,
+            &[],
+        ));
+        writer.write_packet(&build_tcp_packet(
+            server,
+            client,
+            server_isn,
+            client_isn.wrapping_add(1),
+            TCP_SYN | TCP_ACK,
+            &[],
+        ));
+        writer.write_packet(&build_tcp_packet(
+            client,
+            server,
+            client_isn.wrapping_add(1),
+            server_isn.wrapping_add(1),
+            TCP_ACK,
+            &[],
+        ));
+
+        Self {
+            writer,
+            client,
+            server,
+            sequence: Mutex::new((client_isn.wrapping_add(1), server_isn.wrapping_add(1))),
+        }
+    }
+
+    fn write_data(&self, from_client: bool, data: &[u8]) {
+        let mut sequence = self.sequence.lock().unwrap();
+
+        for segment in data.chunks(MAX_SEGMENT_SIZE) {
+            let (client_seq, server_seq) = *sequence;
+            let packet = if from_client {
+                build_tcp_packet(
+                    self.client,
+                    self.server,
+                    client_seq,
+                    server_seq,
+                    TCP_PSH | TCP_ACK,
+                    segment,
+                )
+            } else {
+                build_tcp_packet(
+                    self.server,
+                    self.client,
+                    server_seq,
+                    client_seq,
+                    TCP_PSH | TCP_ACK,
+                    segment,
+                )
+            };
+            self.writer.write_packet(&packet);
+
+            let advanced = segment.len() as u32;
+            if from_client {
+                sequence.0 = client_seq.wrapping_add(advanced);
+            } else {
+                sequence.1 = server_seq.wrapping_add(advanced);
+            }
+        }
+    }
+
+    pub fn client_data(&self, data: &[u8]) {
+        self.write_data(true, data);
+    }
+
+    pub fn server_data(&self, data: &[u8]) {
+        self.write_data(false, data);
+    }
+
+    pub fn close(&self) {
+        let (client_seq, server_seq) = *self.sequence.lock().unwrap();
+
+        self.writer.write_packet(&build_tcp_packet(
+            self.client,
+            self.server,
+            client_seq,
+            server_seq,
+            TCP_FIN | TCP_ACK,
+            &[],
+        ));
+        self.writer.write_packet(&build_tcp_packet(
+            self.server,
+            self.client,
+            server_seq,
+            client_seq.wrapping_add(1),
+            TCP_FIN | TCP_ACK,
+            &[],
+        ));
+        self.writer.write_packet(&build_tcp_packet(
+            self.client,
+            self.server,
+            client_seq.wrapping_add(1),
+            server_seq.wrapping_add(1),
+            TCP_ACK,
+            &[],
+        ));
+        self.writer.flush();
+    }
+}
+
+pub fn start_capture(config_id: i64) -> Result<PathBuf, String> {
+    let mut captures = CAPTURES.lock().unwrap();
+
+    if let Some(writer) = captures.get(&config_id) {
+        return Ok(writer.path().clone());
+    }
+
+    let dir = get_capture_folder_path()?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let path = dir.join(format!(
+        "{}_{}.pcapng",
+        config_id,
+        timestamp_us() / 1_000_000
+    ));
+    let writer = PcapWriter::create(path.clone())?;
+    captures.insert(config_id, Arc::new(writer));
+
+    info!("Started capture for config {} at {:?}", config_id, path);
+    Ok(path)
+}
+
+pub fn stop_capture(config_id: i64) -> Option<PathBuf> {
+    let writer = CAPTURES.lock().unwrap().remove(&config_id)?;
+    writer.flush();
+
+    info!("Stopped capture for config {}", config_id);
+    Some(writer.path().clone())
+}
+
+pub fn get_capture(config_id: i64) -> Option<Arc<PcapWriter>> {
+    CAPTURES.lock().unwrap().get(&config_id).cloned()
+}
+
+pub fn is_capture_enabled(config_id: i64) -> bool {
+    CAPTURES.lock().unwrap().contains_key(&config_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_tcp_packet_checksums() {
+        let src = SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 2), 50000);
+        let dst = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 8080);
+        let packet = build_tcp_packet(src, dst, 1, 2, TCP_PSH | TCP_ACK, b"hello");
+
+        assert_eq!(packet.len(), 45);
+        assert_eq!(u16::from_be_bytes([packet[2], packet[3]]), 45);
+        assert_eq!(checksum(&[&packet[..20]]), 0);
+
+        let mut pseudo_header = Vec::new();
+        pseudo_header.extend_from_slice(&src.ip().octets());
+        pseudo_header.extend_from_slice(&dst.ip().octets());
+        pseudo_header.extend_from_slice(&[0, 6, 0, 25]);
+        assert_eq!(checksum(&[&pseudo_header, &packet[20..]]), 0);
+    }
+
+    #[test]
+    fn test_pcapng_blocks_are_aligned() {
+        let packet = enhanced_packet_block(0, b"abc");
+        assert_eq!(packet.len() % 4, 0);
+        assert_eq!(&packet[4..8], &packet[packet.len() - 4..]);
+        assert_eq!(section_header_block().len(), 28);
+        assert_eq!(interface_description_block().len(), 20);
+    }
+}
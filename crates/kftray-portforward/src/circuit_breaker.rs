@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use kftray_commons::utils::settings::current_settings;
+use lazy_static::lazy_static;
+use log::{
+    info,
+    warn,
+};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+const CIRCUIT_EVENT_CAPACITY: usize = 64;
+
+lazy_static! {
+    static ref CIRCUITS: Mutex<HashMap<i64, Circuit>> = Mutex::new(HashMap::new());
+    static ref CIRCUIT_EVENTS: broadcast::Sender<CircuitStatus> =
+        broadcast::channel(CIRCUIT_EVENT_CAPACITY).0;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CircuitState {
+    #[default]
+    Closed,
+    Open,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CircuitStatus {
+    pub config_id: i64,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    pub retry_in_secs: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct Circuit {
+    failures: u32,
+    opened_at: Option<Instant>,
+    last_error: Option<String>,
+}
+
+pub fn circuit_cooldown() -> Duration {
+    Duration::from_secs(current_settings().circuit_breaker_cooldown_secs)
+}
+
+fn status(config_id: i64, circuit: &Circuit) -> CircuitStatus {
+    CircuitStatus {
+        config_id,
+        state: if circuit.opened_at.is_some() {
+            CircuitState::Open
+        } else {
+            CircuitState::Closed
+        },
+        consecutive_failures: circuit.failures,
+        last_error: circuit.last_error.clone(),
+        retry_in_secs: circuit.opened_at.map(|opened_at| {
+            circuit_cooldown()
+                .saturating_sub(opened_at.elapsed())
+                .as_secs()
+        }),
+    }
+}
+
+fn publish(status: CircuitStatus) {
+    // nobody listening is fine, the status can still be polled
+    let _ = CIRCUIT_EVENTS.send(status);
+}
+
+pub fn subscribe_circuit_events() -> broadcast::Receiver<CircuitStatus> {
+    CIRCUIT_EVENTS.subscribe()
+}
+
+pub fn get_circuit_status(config_id: i64) -> CircuitStatus {
+    let circuits = CIRCUITS.lock().unwrap();
+    match circuits.get(&config_id) {
+        Some(circuit) => status(config_id, circuit),
+        None => status(config_id, &Circuit::default()),
+    }
+}
+
+pub fn get_open_circuits() -> Vec<CircuitStatus> {
+    let mut open: Vec<CircuitStatus> = CIRCUITS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, circuit)| circuit.opened_at.is_some())
+        .map(|(config_id, circuit)| status(*config_id, circuit))
+        .collect();
+    open.sort_by_key(|status| status.config_id);
+    open
+}
+
+pub fn is_circuit_open(config_id: i64) -> bool {
+    CIRCUITS
+        .lock()
+        .unwrap()
+        .get(&config_id)
+        .is_some_and(|circuit| circuit.opened_at.is_some())
+}
+
+// local connections are refused right away while the circuit is open instead
+// of hanging on an upstream that keeps failing
+pub fn check_circuit(config_id: i64) -> anyhow::Result<()> {
+    let circuits = CIRCUITS.lock().unwrap();
+    let Some(circuit) = circuits.get(&config_id).filter(|c| c.opened_at.is_some()) else {
+        return Ok(());
+    };
+
+    Err(anyhow::anyhow!(
+        "Upstream for config {} is failing ({} attempts in a row, last error: {}), refusing \
+         connections until it recovers",
+        config_id,
+        circuit.failures,
+        circuit.last_error.as_deref().unwrap_or("unknown")
+    ))
+}
+
+// returns true when this failure opened the circuit, the caller then probes
+// the upstream in the background until it recovers
+pub fn record_upstream_failure(config_id: i64, error: &str) -> bool {
+    let threshold = current_settings().circuit_breaker_threshold;
+    if threshold == 0 {
+        return false;
+    }
+
+    let mut circuits = CIRCUITS.lock().unwrap();
+    let circuit = circuits.entry(config_id).or_default();
+    circuit.failures += 1;
+    circuit.last_error = Some(error.to_string());
+
+    let opened = circuit.opened_at.is_none() && circuit.failures >= threshold;
+    if opened {
+        warn!(
+            "Opening circuit for config {} after {} upstream failures: {}",
+            config_id, circuit.failures, error
+        );
+    }
+    if circuit.opened_at.is_some() || opened {
+        // a failed probe starts the cool-down over
+        circuit.opened_at = Some(Instant::now());
+        publish(status(config_id, circuit));
+    }
+    opened
+}
+
+pub fn record_upstream_success(config_id: i64) {
+    let Some(circuit) = CIRCUITS.lock().unwrap().remove(&config_id) else {
+        return;
+    };
+
+    if circuit.opened_at.is_some() {
+        info!(
+            "Upstream for config {} recovered, closing circuit",
+            config_id
+        );
+        publish(status(config_id, &Circuit::default()));
+    }
+}
+
+pub fn reset_circuit(config_id: Option<i64>) {
+    let removed: Vec<(i64, Circuit)> = {
+        let mut circuits = CIRCUITS.lock().unwrap();
+        match config_id {
+            Some(config_id) => circuits
+                .remove(&config_id)
+                .map(|c| (config_id, c))
+                .into_iter()
+                .collect(),
+            None => circuits.drain().collect(),
+        }
+    };
+
+    for (config_id, circuit) in removed {
+        if circuit.opened_at.is_some() {
+            publish(status(config_id, &Circuit::default()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit_opens_after_threshold() {
+        let config_id = -164;
+        let threshold = current_settings().circuit_breaker_threshold;
+        let mut events = subscribe_circuit_events();
+
+        for _ in 1..threshold {
+            assert!(!record_upstream_failure(config_id, "connection refused"));
+        }
+        assert!(check_circuit(config_id).is_ok());
+
+        assert!(record_upstream_failure(config_id, "connection refused"));
+        assert!(is_circuit_open(config_id));
+        assert!(check_circuit(config_id)
+            .unwrap_err()
+            .to_string()
+            .contains("connection refused"));
+        assert_eq!(events.try_recv().unwrap().state, CircuitState::Open);
+
+        record_upstream_success(config_id);
+        assert!(!is_circuit_open(config_id));
+        assert_eq!(get_circuit_status(config_id).consecutive_failures, 0);
+        assert_eq!(events.try_recv().unwrap().state, CircuitState::Closed);
+    }
+}
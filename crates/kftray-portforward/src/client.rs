@@ -4,6 +4,11 @@ use std::error::Error;
 use std::future::Future;
 use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::{
+    Arc,
+    Mutex,
+};
+use std::time::Duration;
 
 use anyhow::{
     Context,
@@ -11,7 +16,10 @@ use anyhow::{
 };
 use futures::future::select_ok;
 use hyper_openssl::client::legacy::HttpsConnector;
-use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::legacy::connect::{
+    Connect,
+    HttpConnector,
+};
 use hyper_util::rt::TokioExecutor;
 use k8s_openapi::api::core::v1::{
     Namespace,
@@ -19,8 +27,17 @@ use k8s_openapi::api::core::v1::{
     ServiceSpec,
 };
 use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
-use kftray_commons::config_dir::get_kubeconfig_paths;
+use kftray_commons::config_dir::{
+    get_kubeconfig_paths,
+    get_managed_kubeconfig_path,
+};
+use kftray_commons::context_settings::list_context_settings;
+use kftray_commons::models::config_model::Config as ForwardConfig;
+use kftray_commons::models::context_settings_model::ContextSettings;
+use kftray_commons::models::settings_model::Settings;
+use kftray_commons::settings::current_settings;
 use kube::api::ListParams;
+use kube::client::middleware::AuthLayer;
 use kube::client::ConfigExt;
 use kube::{
     config::{
@@ -31,6 +48,7 @@ use kube::{
     Api,
     Client,
 };
+use lazy_static::lazy_static;
 use log::{
     error,
     info,
@@ -47,9 +65,33 @@ use openssl::ssl::{
     SslVerifyMode,
 };
 use secrecy::ExposeSecret;
-use tower::ServiceBuilder;
+use tokio::time::Instant;
+use tower::filter::{
+    AsyncFilterLayer,
+    AsyncPredicate,
+};
+use tower::timeout::error::Elapsed;
+use tower::timeout::TimeoutLayer;
+use tower::{
+    BoxError,
+    ServiceBuilder,
+};
 
 use crate::models::kube::KubeContextInfo;
+use crate::ssm::{
+    route_through_ssm,
+    ssm_target,
+    SsmTarget,
+};
+use crate::teleport::{
+    clear_login_required,
+    is_auth_error,
+    refresh_teleport_credentials,
+    require_login,
+    resolve_tsh_commands,
+    teleport_cluster,
+    TeleportCluster,
+};
 
 trait ConfigExtClone {
     fn clone_with_invalid_certs(&self, accept_invalid_certs: bool) -> Self;
@@ -71,8 +113,111 @@ type Strategy<'a> = (&'static str, StrategyFuture<'a>);
 
 type ServiceInfo = (String, HashMap<String, String>, HashMap<String, i32>);
 
+// connect/request timeouts and the client-side request rate for kube api
+// calls, from the settings unless the config overrides them. a config qps of
+// 0 turns rate limiting off for that config
+#[derive(Clone, Debug, PartialEq)]
+pub struct KubeTuning {
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+    pub qps: Option<u32>,
+    pub ssm: Option<SsmTarget>,
+}
+
+impl KubeTuning {
+    pub fn from_settings() -> Self {
+        resolve_tuning(&current_settings(), None)
+    }
+
+    pub fn for_config(config: &ForwardConfig) -> Self {
+        resolve_tuning(&current_settings(), Some(config))
+    }
+}
+
+fn resolve_tuning(settings: &Settings, config: Option<&ForwardConfig>) -> KubeTuning {
+    let request_timeout = config
+        .and_then(|config| config.kube_request_timeout_secs)
+        .filter(|secs| *secs > 0)
+        .unwrap_or(settings.kube_request_timeout_secs)
+        .max(1);
+    let qps = config
+        .and_then(|config| config.kube_qps)
+        .or(settings.kube_qps)
+        .filter(|qps| *qps > 0);
+
+    KubeTuning {
+        connect_timeout: Duration::from_secs(settings.kube_connect_timeout_secs.max(1)),
+        request_timeout: Duration::from_secs(request_timeout),
+        qps,
+        ssm: config.and_then(ssm_target),
+    }
+}
+
+// spaces requests evenly, one slot every 1/qps seconds
+struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(qps: u32) -> Self {
+        RateLimiter {
+            interval: Duration::from_secs(1) / qps.max(1),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    fn reserve(&self, now: Instant) -> Instant {
+        let mut next = self.next.lock().unwrap();
+        let slot = (*next).max(now);
+        *next = slot + self.interval;
+        slot
+    }
+
+    async fn acquire(&self) {
+        tokio::time::sleep_until(self.reserve(Instant::now())).await;
+    }
+}
+
+lazy_static! {
+    static ref RATE_LIMITERS: Mutex<HashMap<(String, u32), Arc<RateLimiter>>> =
+        Mutex::new(HashMap::new());
+}
+
+// clients for the same api server share one budget
+fn rate_limiter(server: &str, qps: u32) -> Arc<RateLimiter> {
+    RATE_LIMITERS
+        .lock()
+        .unwrap()
+        .entry((server.to_string(), qps))
+        .or_insert_with(|| Arc::new(RateLimiter::new(qps)))
+        .clone()
+}
+
+#[derive(Clone)]
+struct RateLimit(Arc<RateLimiter>);
+
+impl<R: Send + 'static> AsyncPredicate<R> for RateLimit {
+    type Future = Pin<Box<dyn Future<Output = Result<R, BoxError>> + Send>>;
+    type Request = R;
+
+    fn check(&mut self, request: R) -> Self::Future {
+        let limiter = self.0.clone();
+        Box::pin(async move {
+            limiter.acquire().await;
+            Ok(request)
+        })
+    }
+}
+
 pub async fn create_client_with_specific_context(
     kubeconfig: Option<String>, context_name: Option<&str>,
+) -> Result<(Option<Client>, Option<Kubeconfig>, Vec<String>)> {
+    create_client_with_tuning(kubeconfig, context_name, KubeTuning::from_settings()).await
+}
+
+pub async fn create_client_with_tuning(
+    kubeconfig: Option<String>, context_name: Option<&str>, tuning: KubeTuning,
 ) -> Result<(Option<Client>, Option<Kubeconfig>, Vec<String>)> {
     // Remove PYTHONHOME and PYTHONPATH environment variables
     env::remove_var("PYTHONHOME");
@@ -82,23 +227,12 @@ pub async fn create_client_with_specific_context(
     let (merged_kubeconfig, all_contexts, mut errors) = merge_kubeconfigs(&kubeconfig_paths)?;
 
     if let Some(context_name) = context_name {
-        match create_config_with_context(&merged_kubeconfig, context_name).await {
-            Ok(config) => {
-                if let Some(client) = create_client_with_config(&config).await {
-                    return Ok((Some(client), Some(merged_kubeconfig), all_contexts));
-                } else {
-                    errors.push(format!(
-                        "Failed to create HTTPS connector for context: {}",
-                        context_name
-                    ));
-                }
-            }
-            Err(e) => {
-                errors.push(format!(
-                    "Failed to create configuration for context: {}: {}",
-                    context_name, e
-                ));
+        match connect_context(&merged_kubeconfig, context_name, &tuning).await {
+            Ok(client) => {
+                clear_login_required(context_name);
+                return Ok((Some(client), Some(merged_kubeconfig), all_contexts));
             }
+            Err(e) => errors.push(e),
         }
     } else {
         info!("No specific context provided, returning all available contexts.");
@@ -111,11 +245,86 @@ pub async fn create_client_with_specific_context(
     ))
 }
 
+async fn connect_context(
+    kubeconfig: &Kubeconfig, context_name: &str, tuning: &KubeTuning,
+) -> Result<Client, String> {
+    let kubeconfig = match &tuning.ssm {
+        Some(target) => route_through_ssm(kubeconfig, context_name, target)
+            .await
+            .map_err(|e| {
+                format!(
+                    "Failed to open the SSM tunnel for context {}: {}",
+                    context_name, e
+                )
+            })?,
+        None => kubeconfig.clone(),
+    };
+
+    let config = create_config_with_context(&kubeconfig, context_name)
+        .await
+        .map_err(|e| {
+            format!(
+                "Failed to create configuration for context: {}: {}",
+                context_name, e
+            )
+        })?;
+
+    match create_client_with_config(&config, tuning).await {
+        Err(e) if is_auth_error(&e) => match teleport_cluster(&kubeconfig, context_name) {
+            Some(cluster) => {
+                recover_teleport_client(&kubeconfig, context_name, &cluster, tuning).await
+            }
+            None => Err(e),
+        },
+        result => result,
+    }
+    .map_err(|e| format!("Failed to connect to context {}: {}", context_name, e))
+}
+
+// teleport kube certs are short-lived, tsh can reissue them as long as the
+// teleport session is valid, after that the user has to log in again
+async fn recover_teleport_client(
+    kubeconfig: &Kubeconfig, context_name: &str, cluster: &TeleportCluster, tuning: &KubeTuning,
+) -> Result<Client, String> {
+    info!(
+        "Refreshing Teleport credentials for context {}",
+        context_name
+    );
+    if let Err(e) = refresh_teleport_credentials(cluster).await {
+        warn!("tsh failed to refresh credentials: {}", e);
+        return Err(require_login(context_name, cluster));
+    }
+
+    let config = create_config_with_context(kubeconfig, context_name)
+        .await
+        .map_err(|e| e.to_string())?;
+    match create_client_with_config(&config, tuning).await {
+        Err(e) if is_auth_error(&e) => Err(require_login(context_name, cluster)),
+        result => result,
+    }
+}
+
+// the default paths also pick up the contexts kftray generated for cloud
+// clusters
+fn default_kubeconfig_paths() -> Result<Vec<PathBuf>> {
+    let managed = get_managed_kubeconfig_path()
+        .ok()
+        .filter(|path| path.exists());
+    match (get_kubeconfig_paths(), managed) {
+        (Ok(mut paths), Some(managed)) => {
+            paths.push(managed);
+            Ok(paths)
+        }
+        (Err(_), Some(managed)) => Ok(vec![managed]),
+        (paths, None) => paths,
+    }
+}
+
 fn get_kubeconfig_paths_from_option(kubeconfig: Option<String>) -> Result<Vec<PathBuf>> {
     match kubeconfig {
         Some(path) if path == "default" => {
             info!("Using default kubeconfig paths.");
-            get_kubeconfig_paths()
+            default_kubeconfig_paths()
         }
         Some(path) => {
             info!("Using provided kubeconfig paths: {}", path);
@@ -123,7 +332,7 @@ fn get_kubeconfig_paths_from_option(kubeconfig: Option<String>) -> Result<Vec<Pa
         }
         None => {
             info!("No kubeconfig path provided, using default paths.");
-            get_kubeconfig_paths()
+            default_kubeconfig_paths()
         }
     }
 }
@@ -159,6 +368,7 @@ fn merge_kubeconfigs(paths: &[PathBuf]) -> Result<(Kubeconfig, Vec<String>, Vec<
 async fn create_config_with_context(kubeconfig: &Kubeconfig, context_name: &str) -> Result<Config> {
     info!("Creating configuration for context: {}", context_name);
     let mut kubeconfig = kubeconfig.clone();
+    resolve_tsh_commands(&mut kubeconfig);
 
     for auth_info in &mut kubeconfig.auth_infos {
         if let Some(client_key_data) = &auth_info.auth_info.clone().unwrap().client_key_data {
@@ -185,13 +395,14 @@ async fn create_config_with_context(kubeconfig: &Kubeconfig, context_name: &str)
     .context("Failed to create configuration from kubeconfig")
 }
 
-async fn create_client_with_config(config: &Config) -> Option<Client> {
+async fn create_client_with_config(config: &Config, tuning: &KubeTuning) -> Result<Client, String> {
     let config_with_invalid_certs_true = config.clone_with_invalid_certs(true);
     let config_with_invalid_certs_false = config.clone_with_invalid_certs(false);
 
     let strategies = create_strategies(
         config_with_invalid_certs_true,
         config_with_invalid_certs_false,
+        tuning,
     );
 
     let futures: Vec<_> = strategies
@@ -199,100 +410,102 @@ async fn create_client_with_config(config: &Config) -> Option<Client> {
         .map(|(description, strategy)| Box::pin(try_create_client(description, strategy)))
         .collect();
 
-    match select_ok(futures).await {
-        Ok((client, _)) => Some(client),
-        Err(_) => None,
-    }
+    // every strategy failed, the last error is as good as any
+    select_ok(futures).await.map(|(client, _)| client)
 }
 
 fn create_strategies<'a>(
     config_with_invalid_certs_true: Config, config_with_invalid_certs_false: Config,
+    tuning: &'a KubeTuning,
 ) -> Vec<Strategy<'a>> {
     vec![
         (
             "OpenSSL HTTPS connector (with verification)",
             Box::pin({
                 let config = config_with_invalid_certs_false.clone();
-                async move { create_openssl_https_connector(&config, SslVerifyMode::PEER).await }
+                async move { create_openssl_https_connector(&config, SslVerifyMode::PEER, tuning).await }
             }),
         ),
         (
             "OpenSSL HTTPS connector (without verification)",
             Box::pin({
                 let config = config_with_invalid_certs_false.clone();
-                async move { create_openssl_https_connector(&config, SslVerifyMode::NONE).await }
+                async move { create_openssl_https_connector(&config, SslVerifyMode::NONE, tuning).await }
             }),
         ),
         (
             "OpenSSL HTTPS connector (accept invalid certs and without verification)",
             Box::pin({
                 let config = config_with_invalid_certs_true.clone();
-                async move { create_openssl_https_connector(&config, SslVerifyMode::NONE).await }
+                async move { create_openssl_https_connector(&config, SslVerifyMode::NONE, tuning).await }
             }),
         ),
         (
             "OpenSSL HTTPS connector (accept invalid certs and with verification)",
             Box::pin({
                 let config = config_with_invalid_certs_true.clone();
-                async move { create_openssl_https_connector(&config, SslVerifyMode::PEER).await }
+                async move { create_openssl_https_connector(&config, SslVerifyMode::PEER, tuning).await }
             }),
         ),
         (
             "Rustls HTTPS connector (accept invalid certs)",
             Box::pin({
                 let config = config_with_invalid_certs_true.clone();
-                async move { create_rustls_https_connector(&config).await }
+                async move { create_rustls_https_connector(&config, tuning).await }
             }),
         ),
         (
             "Rustls HTTPS connector (do not accept invalid certs)",
             Box::pin({
                 let config = config_with_invalid_certs_false.clone();
-                async move { create_rustls_https_connector(&config).await }
+                async move { create_rustls_https_connector(&config, tuning).await }
             }),
         ),
         (
             "Insecure HTTP connector (do not accept invalid certs)",
             Box::pin({
                 let config = config_with_invalid_certs_false.clone();
-                async move { create_insecure_http_client(&config).await }
+                async move { create_insecure_http_client(&config, tuning).await }
             }),
         ),
         (
             "Insecure HTTP connector (accept invalid certs)",
             Box::pin({
                 let config = config_with_invalid_certs_true.clone();
-                async move { create_insecure_http_client(&config).await }
+                async move { create_insecure_http_client(&config, tuning).await }
             }),
         ),
     ]
 }
 
-async fn try_create_client(description: &str, strategy: StrategyFuture<'_>) -> Result<Client, ()> {
+async fn try_create_client(
+    description: &str, strategy: StrategyFuture<'_>,
+) -> Result<Client, String> {
     info!("Attempting to create client with {}", description);
     match strategy.await {
-        Ok(client) => {
-            if test_client(&client).await.is_ok() {
+        Ok(client) => match test_client(&client).await {
+            Ok(()) => {
                 info!("Successfully created client with {}", description);
                 Ok(client)
-            } else {
-                warn!("{} failed to connect.", description);
-                Err(())
             }
-        }
+            Err(e) => {
+                warn!("{} failed to connect: {}", description, e);
+                Err(e.to_string())
+            }
+        },
         Err(e) => {
             warn!("Failed to create {}: {}", description, e);
-            Err(())
+            Err(e.to_string())
         }
     }
 }
 
 async fn create_openssl_https_connector(
-    config: &Config, verify_mode: SslVerifyMode,
+    config: &Config, verify_mode: SslVerifyMode, tuning: &KubeTuning,
 ) -> Result<Client, Box<dyn std::error::Error + Send + Sync>> {
     let mut builder = SslConnector::builder(SslMethod::tls())?;
     builder.set_verify(verify_mode);
-    let https_connector = HttpsConnector::with_connector(HttpConnector::new(), builder)?;
+    let https_connector = HttpsConnector::with_connector(http_connector(tuning), builder)?;
 
     let auth_layer = match config.auth_layer() {
         Ok(Some(layer)) => Some(layer),
@@ -306,23 +519,15 @@ async fn create_openssl_https_connector(
         }
     };
 
-    let service = ServiceBuilder::new()
-        .layer(config.base_uri_layer())
-        .option_layer(auth_layer)
-        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
-        .service(
-            hyper_util::client::legacy::Client::builder(TokioExecutor::new())
-                .build(https_connector),
-        );
-
-    let client = Client::new(service, config.default_namespace.clone());
-    Ok(client)
+    Ok(build_client(config, https_connector, auth_layer, tuning))
 }
 
 async fn create_rustls_https_connector(
-    config: &Config,
+    config: &Config, tuning: &KubeTuning,
 ) -> Result<Client, Box<dyn std::error::Error + Send + Sync>> {
-    let https_connector = config.rustls_https_connector()?;
+    let mut connector = http_connector(tuning);
+    connector.enforce_http(false);
+    let https_connector = config.rustls_https_connector_with_connector(connector)?;
 
     let auth_layer = match config.auth_layer() {
         Ok(Some(layer)) => Some(layer),
@@ -336,38 +541,81 @@ async fn create_rustls_https_connector(
         }
     };
 
-    let service = ServiceBuilder::new()
-        .layer(config.base_uri_layer())
-        .option_layer(auth_layer)
-        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
-        .service(
-            hyper_util::client::legacy::Client::builder(TokioExecutor::new())
-                .build(https_connector),
-        );
-
-    let client = Client::new(service, config.default_namespace.clone());
-    Ok(client)
+    Ok(build_client(config, https_connector, auth_layer, tuning))
 }
 
 async fn create_insecure_http_client<'a>(
-    config: &Config,
+    config: &Config, tuning: &KubeTuning,
 ) -> Result<Client, Box<dyn std::error::Error + Send + Sync>> {
-    let http_connector = HttpConnector::new();
+    let auth_layer = config
+        .auth_layer()
+        .or_else(|_| Ok::<Option<AuthLayer>, anyhow::Error>(None))?;
+
+    Ok(build_client(
+        config,
+        http_connector(tuning),
+        auth_layer,
+        tuning,
+    ))
+}
+
+fn http_connector(tuning: &KubeTuning) -> HttpConnector {
+    let mut connector = HttpConnector::new();
+    connector.set_connect_timeout(Some(tuning.connect_timeout));
+    connector
+}
+
+// the timeout only bounds the response head, so watches and upgraded
+// port-forward streams are not cut off once established
+fn build_client<C>(
+    config: &Config, connector: C, auth_layer: Option<AuthLayer>, tuning: &KubeTuning,
+) -> Client
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    let (connect_timeout, request_timeout) = (tuning.connect_timeout, tuning.request_timeout);
+    let server = config.cluster_url.to_string();
+    let rate_limit = tuning
+        .qps
+        .map(|qps| AsyncFilterLayer::new(RateLimit(rate_limiter(&server, qps))));
 
     let service = ServiceBuilder::new()
+        .map_err(move |e: BoxError| describe_timeout(e, connect_timeout, request_timeout))
+        .option_layer(rate_limit)
+        .layer(TimeoutLayer::new(tuning.request_timeout))
         .layer(config.base_uri_layer())
-        .option_layer(
-            config.auth_layer().or_else(|_| {
-                Ok::<Option<kube::client::middleware::AuthLayer>, anyhow::Error>(None)
-            })?,
-        )
+        .option_layer(auth_layer)
         .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
         .service(
-            hyper_util::client::legacy::Client::builder(TokioExecutor::new()).build(http_connector),
+            hyper_util::client::legacy::Client::builder(TokioExecutor::new()).build(connector),
         );
 
-    let client = Client::new(service, config.default_namespace.clone());
-    Ok(client)
+    Client::new(service, config.default_namespace.clone())
+}
+
+fn describe_timeout(e: BoxError, connect_timeout: Duration, request_timeout: Duration) -> BoxError {
+    let mut source: Option<&(dyn Error + 'static)> = Some(e.as_ref());
+    while let Some(error) = source {
+        if error.is::<Elapsed>() {
+            return format!(
+                "Kubernetes API request timed out after {}s",
+                request_timeout.as_secs()
+            )
+            .into();
+        }
+        if error
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|e| e.kind() == std::io::ErrorKind::TimedOut)
+        {
+            return format!(
+                "Timed out connecting to the Kubernetes API after {}s",
+                connect_timeout.as_secs()
+            )
+            .into();
+        }
+        source = error.source();
+    }
+    e
 }
 
 async fn test_client(client: &Client) -> Result<(), Box<dyn Error + Send + Sync>> {
@@ -415,20 +663,35 @@ pub async fn list_kube_contexts(
         .await
         .map_err(|err| format!("Failed to create client: {}", err))?;
 
-    if let Some(kubeconfig) = kubeconfig {
-        Ok(kubeconfig
-            .contexts
-            .into_iter()
-            .map(|c| KubeContextInfo { name: c.name })
-            .collect())
+    let names: Vec<String> = if let Some(kubeconfig) = kubeconfig {
+        kubeconfig.contexts.into_iter().map(|c| c.name).collect()
     } else if !contexts.is_empty() {
-        Ok(contexts
-            .into_iter()
-            .map(|name| KubeContextInfo { name })
-            .collect())
+        contexts
     } else {
-        Err("Failed to retrieve kubeconfig".to_string())
-    }
+        return Err("Failed to retrieve kubeconfig".to_string());
+    };
+
+    let mut settings: HashMap<String, ContextSettings> = list_context_settings()
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to read context settings: {}", e);
+            Vec::new()
+        })
+        .into_iter()
+        .map(|settings| (settings.context.clone(), settings))
+        .collect();
+
+    Ok(names
+        .into_iter()
+        .map(|name| {
+            let settings = settings.remove(&name).unwrap_or_default();
+            KubeContextInfo {
+                name,
+                display_name: settings.display_name,
+                color: settings.color,
+            }
+        })
+        .collect())
 }
 
 pub async fn list_all_namespaces(client: Client) -> Result<Vec<String>, anyhow::Error> {
@@ -502,3 +765,41 @@ fn resolve_named_port(spec: &ServiceSpec, name: &str) -> Option<i32> {
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_tuning() {
+        let settings = Settings {
+            kube_qps: Some(20),
+            ..Default::default()
+        };
+        let tuning = resolve_tuning(&settings, None);
+        assert_eq!(tuning.connect_timeout, Duration::from_secs(10));
+        assert_eq!(tuning.request_timeout, Duration::from_secs(30));
+        assert_eq!(tuning.qps, Some(20));
+
+        let config = ForwardConfig {
+            kube_request_timeout_secs: Some(5),
+            kube_qps: Some(0),
+            ..Default::default()
+        };
+        let tuning = resolve_tuning(&settings, Some(&config));
+        assert_eq!(tuning.request_timeout, Duration::from_secs(5));
+        assert_eq!(tuning.qps, None);
+    }
+
+    #[test]
+    fn test_rate_limiter_spaces_requests() {
+        let limiter = RateLimiter::new(4);
+        let now = Instant::now();
+        assert_eq!(limiter.reserve(now), now);
+        assert_eq!(limiter.reserve(now), now + Duration::from_millis(250));
+        assert_eq!(limiter.reserve(now), now + Duration::from_millis(500));
+
+        let later = now + Duration::from_secs(5);
+        assert_eq!(limiter.reserve(later), later);
+    }
+}
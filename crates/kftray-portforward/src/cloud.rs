@@ -0,0 +1,312 @@
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use kftray_commons::config_dir::{
+    get_cloud_clusters_path,
+    get_managed_kubeconfig_path,
+};
+use kube::config::Kubeconfig;
+use lazy_static::lazy_static;
+use log::{
+    info,
+    warn,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+const CLI_TIMEOUT: Duration = Duration::from_secs(120);
+
+lazy_static! {
+    // the cli tools rewrite the whole managed kubeconfig, so only one runs at a time
+    static ref MANAGED_KUBECONFIG_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "provider", rename_all = "lowercase")]
+pub enum CloudCluster {
+    Eks {
+        name: String,
+        region: String,
+        #[serde(default)]
+        profile: Option<String>,
+    },
+    Gke {
+        name: String,
+        location: String,
+        project: String,
+    },
+    Aks {
+        name: String,
+        resource_group: String,
+        #[serde(default)]
+        subscription: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManagedContext {
+    pub context: String,
+    pub cluster: CloudCluster,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CliCommand {
+    pub program: &'static str,
+    pub args: Vec<String>,
+    pub envs: Vec<(&'static str, String)>,
+}
+
+fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+// the provider cli writes the context itself, so auth keeps going through the
+// same exec plugin the cli would set up for kubectl
+pub fn credentials_command(cluster: &CloudCluster, kubeconfig: &Path) -> CliCommand {
+    let kubeconfig = kubeconfig.to_string_lossy().into_owned();
+    match cluster {
+        CloudCluster::Eks {
+            name,
+            region,
+            profile,
+        } => {
+            let mut args = strings(&[
+                "eks",
+                "update-kubeconfig",
+                "--name",
+                name,
+                "--region",
+                region,
+            ]);
+            args.extend(["--kubeconfig".to_string(), kubeconfig]);
+            if let Some(profile) = profile {
+                args.extend(["--profile".to_string(), profile.clone()]);
+            }
+            CliCommand {
+                program: "aws",
+                args,
+                envs: Vec::new(),
+            }
+        }
+        CloudCluster::Gke {
+            name,
+            location,
+            project,
+        } => CliCommand {
+            program: if cfg!(windows) {
+                "gcloud.cmd"
+            } else {
+                "gcloud"
+            },
+            // --location takes both regions and zones
+            args: strings(&[
+                "container",
+                "clusters",
+                "get-credentials",
+                name,
+                "--location",
+                location,
+                "--project",
+                project,
+            ]),
+            envs: vec![
+                ("KUBECONFIG", kubeconfig),
+                ("USE_GKE_GCLOUD_AUTH_PLUGIN", "True".to_string()),
+            ],
+        },
+        CloudCluster::Aks {
+            name,
+            resource_group,
+            subscription,
+        } => {
+            let mut args = strings(&[
+                "aks",
+                "get-credentials",
+                "--name",
+                name,
+                "--resource-group",
+                resource_group,
+                "--overwrite-existing",
+            ]);
+            args.extend(["--file".to_string(), kubeconfig]);
+            if let Some(subscription) = subscription {
+                args.extend(["--subscription".to_string(), subscription.clone()]);
+            }
+            CliCommand {
+                program: if cfg!(windows) { "az.cmd" } else { "az" },
+                args,
+                envs: Vec::new(),
+            }
+        }
+    }
+}
+
+async fn run_cli(command: &CliCommand) -> Result<(), String> {
+    let child = Command::new(command.program)
+        .args(&command.args)
+        .envs(command.envs.iter().cloned())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", command.program, e))?;
+
+    let output = tokio::time::timeout(CLI_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("{} timed out", command.program))?
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} failed: {}",
+            command.program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+fn read_managed_contexts() -> Result<Vec<ManagedContext>, String> {
+    let path = get_cloud_clusters_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let json = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&json).map_err(|e| e.to_string())
+}
+
+fn write_managed_contexts(contexts: &[ManagedContext]) -> Result<(), String> {
+    let path = get_cloud_clusters_path()?;
+    let json = serde_json::to_string_pretty(contexts).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| e.to_string())
+}
+
+// every provider cli switches current-context to the cluster it just wrote
+async fn write_credentials(cluster: &CloudCluster) -> Result<String, String> {
+    let kubeconfig_path = get_managed_kubeconfig_path()?;
+    if let Some(parent) = kubeconfig_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    run_cli(&credentials_command(cluster, &kubeconfig_path)).await?;
+
+    Kubeconfig::read_from(&kubeconfig_path)
+        .map_err(|e| e.to_string())?
+        .current_context
+        .ok_or_else(|| "The generated kubeconfig has no current context".to_string())
+}
+
+pub async fn add_cloud_cluster(cluster: CloudCluster) -> Result<ManagedContext, String> {
+    let _guard = MANAGED_KUBECONFIG_LOCK.lock().await;
+
+    let context = write_credentials(&cluster).await?;
+    let managed = ManagedContext { context, cluster };
+
+    let mut contexts = read_managed_contexts()?;
+    contexts.retain(|existing| {
+        existing.context != managed.context && existing.cluster != managed.cluster
+    });
+    contexts.push(managed.clone());
+    contexts.sort_by(|a, b| a.context.cmp(&b.context));
+    write_managed_contexts(&contexts)?;
+
+    info!("Added cloud cluster context {}", managed.context);
+    Ok(managed)
+}
+
+pub async fn list_cloud_clusters() -> Result<Vec<ManagedContext>, String> {
+    let _guard = MANAGED_KUBECONFIG_LOCK.lock().await;
+    read_managed_contexts()
+}
+
+// regenerates every managed context, picking up rotated endpoints and CAs.
+// clusters that fail keep their previous entry
+pub async fn refresh_cloud_clusters() -> Result<Vec<ManagedContext>, String> {
+    let _guard = MANAGED_KUBECONFIG_LOCK.lock().await;
+
+    let mut refreshed = Vec::new();
+    let mut errors = Vec::new();
+    for managed in read_managed_contexts()? {
+        match write_credentials(&managed.cluster).await {
+            Ok(context) => refreshed.push(ManagedContext {
+                context,
+                cluster: managed.cluster,
+            }),
+            Err(e) => {
+                warn!("Failed to refresh context {}: {}", managed.context, e);
+                errors.push(format!("{}: {}", managed.context, e));
+                refreshed.push(managed);
+            }
+        }
+    }
+    write_managed_contexts(&refreshed)?;
+
+    if errors.is_empty() {
+        Ok(refreshed)
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credentials_command() {
+        let kubeconfig = Path::new("/home/me/.kftray/kubeconfig");
+
+        let eks = credentials_command(
+            &CloudCluster::Eks {
+                name: "prod".to_string(),
+                region: "eu-west-1".to_string(),
+                profile: Some("ops".to_string()),
+            },
+            kubeconfig,
+        );
+        assert_eq!(eks.program, "aws");
+        assert_eq!(
+            eks.args,
+            strings(&[
+                "eks",
+                "update-kubeconfig",
+                "--name",
+                "prod",
+                "--region",
+                "eu-west-1",
+                "--kubeconfig",
+                "/home/me/.kftray/kubeconfig",
+                "--profile",
+                "ops",
+            ])
+        );
+
+        let gke = credentials_command(
+            &CloudCluster::Gke {
+                name: "staging".to_string(),
+                location: "europe-west4".to_string(),
+                project: "acme".to_string(),
+            },
+            kubeconfig,
+        );
+        assert_eq!(
+            gke.envs[0],
+            ("KUBECONFIG", "/home/me/.kftray/kubeconfig".to_string())
+        );
+
+        let cluster: CloudCluster =
+            serde_json::from_str(r#"{"provider":"aks","name":"dev","resource_group":"rg-dev"}"#)
+                .unwrap();
+        let aks = credentials_command(&cluster, kubeconfig);
+        assert!(aks
+            .args
+            .ends_with(&strings(&["--file", "/home/me/.kftray/kubeconfig"])));
+    }
+}
@@ -0,0 +1,829 @@
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::process::Stdio;
+use std::sync::{
+    Arc,
+    Mutex,
+};
+use std::time::Duration;
+
+use k8s_openapi::api::core::v1::{
+    Pod,
+    Secret,
+    Service,
+    ServicePort,
+    ServiceSpec,
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kftray_commons::models::config_model::Config;
+use kftray_commons::utils::access_audit::require_access_reason;
+use kftray_commons::utils::config_dir::get_cluster_network_dir;
+use kftray_commons::utils::context_settings::ensure_workloads_allowed;
+use kube::api::{
+    Api,
+    DeleteParams,
+    PostParams,
+};
+use kube::Client;
+use kube_runtime::wait::await_condition;
+use lazy_static::lazy_static;
+use log::{
+    info,
+    warn,
+};
+use rand::{
+    distributions::Alphanumeric,
+    Rng,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::adhoc::{
+    start_adhoc_forward,
+    stop_adhoc_forward,
+    AdhocForwardSpec,
+};
+use crate::client::create_client_with_specific_context;
+use crate::models::kube::HttpLogState;
+use crate::ownership::{
+    label_owned_pod,
+    ownership_labels,
+};
+
+// built from crates/kftray-server/network-relay, the release publishes the
+// same tag
+const RELAY_IMAGE: &str = "ghcr.io/hcavarsan/kftray-network-relay:1.0.0";
+const WIREGUARD_PORT: u16 = 51820;
+const INTERFACE: &str = "kftnet0";
+// the two ends of the tunnel, the relay masquerades everything from the local
+// end behind the pod ip
+const RELAY_TUNNEL_ADDRESS: &str = "10.211.0.1";
+const LOCAL_TUNNEL_ADDRESS: &str = "10.211.0.2";
+// wireguard rides on the udp relay of a proxy forward, which has less room
+// than a plain udp path
+const TUNNEL_MTU: u16 = 1280;
+const CLUSTER_DOMAIN: &str = "cluster.local";
+const DNS_SERVICES: [&str; 2] = ["kube-dns", "coredns"];
+const RELAY_READY_TIMEOUT: Duration = Duration::from_secs(180);
+const TOOL_TIMEOUT: Duration = Duration::from_secs(120);
+const PRIVATE_KEY_ENTRY: &str = "private-key";
+
+lazy_static! {
+    static ref CLUSTER_NETWORK: Mutex<NetworkState> = Mutex::new(NetworkState::Down);
+}
+
+// the lock is only held to look at the state or change it, a start takes
+// minutes with the relay deploy and the administrator prompt
+enum NetworkState {
+    Down,
+    Starting(String),
+    Up(ClusterNetwork),
+}
+
+// puts a start that ends without a network back to down, also when its
+// future is dropped halfway
+struct StartingGuard;
+
+impl Drop for StartingGuard {
+    fn drop(&mut self) {
+        let mut state = CLUSTER_NETWORK.lock().unwrap();
+        if matches!(*state, NetworkState::Starting(_)) {
+            *state = NetworkState::Down;
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ClusterNetworkRequest {
+    pub context: String,
+    // where the relay pod runs, `default` when unset
+    #[serde(default)]
+    pub namespace: Option<String>,
+    #[serde(default)]
+    pub kubeconfig: Option<String>,
+    // for clusters that don't give their service range away, e.g. `10.96.0.0/12`
+    #[serde(default)]
+    pub service_cidr: Option<String>,
+    // required when the context is marked as production
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+// what turning the network on does, for the ui to show before the system asks
+// for administrator rights
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ClusterNetworkPlan {
+    pub context: String,
+    pub namespace: String,
+    pub service_cidr: String,
+    pub dns_address: String,
+    pub interface: String,
+    // run with administrator rights, once to bring the network up and once to
+    // take it down
+    pub up_script: String,
+    pub down_script: String,
+    pub notes: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ClusterNetwork {
+    pub context: String,
+    pub namespace: String,
+    pub relay_pod: String,
+    pub service_cidr: String,
+    pub dns_address: String,
+    pub interface: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forward_id: Option<i64>,
+    #[serde(skip)]
+    kubeconfig: Option<String>,
+    #[serde(skip)]
+    down_script: Option<String>,
+}
+
+// the api server names its service range when it turns down a cluster ip
+// outside of it
+pub fn parse_service_cidr(message: &str) -> Option<String> {
+    let (_, rest) = message.split_once("The range of valid IPs is ")?;
+    let cidr: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_hexdigit() || matches!(c, '.' | ':' | '/'))
+        .collect();
+    // the message goes on after a colon
+    let cidr = cidr.trim_end_matches(':');
+    cidr.contains('/').then(|| cidr.to_string())
+}
+
+pub fn wireguard_config(
+    private_key: &str, relay_public_key: &str, tunnel_port: u16, service_cidr: &str,
+    dns_address: &str,
+) -> String {
+    format!(
+        "[Interface]\n\
+         PrivateKey = {}\n\
+         Address = {}/32\n\
+         MTU = {}\n\
+         \n\
+         [Peer]\n\
+         PublicKey = {}\n\
+         Endpoint = 127.0.0.1:{}\n\
+         AllowedIPs = {}, {}/32\n\
+         PersistentKeepalive = 25\n",
+        private_key,
+        LOCAL_TUNNEL_ADDRESS,
+        TUNNEL_MTU,
+        relay_public_key,
+        tunnel_port,
+        service_cidr,
+        dns_address
+    )
+}
+
+fn single_quoted(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+// the scripts that bring the interface up and down on the given os, names
+// under the cluster domain resolve through the cluster dns while the
+// interface is up
+pub fn network_scripts(os: &str, config_path: &Path, dns_address: &str) -> (String, String) {
+    let config_path = config_path.display().to_string();
+    match os {
+        "windows" => (
+            format!(
+                "$ErrorActionPreference = 'Stop'\n\
+                 & wireguard.exe /installtunnelservice '{}'\n\
+                 Add-DnsClientNrptRule -Namespace '.{}' -NameServers '{}'\n",
+                config_path.replace('\'', "''"),
+                CLUSTER_DOMAIN,
+                dns_address
+            ),
+            format!(
+                "Get-DnsClientNrptRule | Where-Object {{ $_.Namespace -contains '.{}' }} | \
+                 Remove-DnsClientNrptRule -Force\n\
+                 & wireguard.exe /uninstalltunnelservice {}\n",
+                CLUSTER_DOMAIN, INTERFACE
+            ),
+        ),
+        "macos" => (
+            format!(
+                "set -e\n\
+                 wg-quick up {}\n\
+                 mkdir -p /etc/resolver\n\
+                 printf 'nameserver {}\\n' > /etc/resolver/{}\n",
+                single_quoted(&config_path),
+                dns_address,
+                CLUSTER_DOMAIN
+            ),
+            format!(
+                "rm -f /etc/resolver/{}\n\
+                 wg-quick down {}\n",
+                CLUSTER_DOMAIN,
+                single_quoted(&config_path)
+            ),
+        ),
+        // the resolvectl settings go away with the link
+        _ => (
+            format!(
+                "set -e\n\
+                 wg-quick up {}\n\
+                 if command -v resolvectl >/dev/null; then\n  \
+                 resolvectl dns {} {}\n  \
+                 resolvectl domain {} '~{}'\n\
+                 fi\n",
+                single_quoted(&config_path),
+                INTERFACE,
+                dns_address,
+                INTERFACE,
+                CLUSTER_DOMAIN
+            ),
+            format!("wg-quick down {}\n", single_quoted(&config_path)),
+        ),
+    }
+}
+
+fn script_extension() -> &'static str {
+    if cfg!(windows) {
+        "ps1"
+    } else {
+        "sh"
+    }
+}
+
+fn network_config_path() -> Result<PathBuf, String> {
+    Ok(get_cluster_network_dir()?.join(format!("{}.conf", INTERFACE)))
+}
+
+async fn run_tool(program: &str, args: &[&str], input: Option<&str>) -> Result<String, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin
+            .write_all(input.as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let output = tokio::time::timeout(TOOL_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("{} timed out", program))?
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+async fn wireguard_keypair() -> Result<(String, String), String> {
+    let missing = |e: String| format!("WireGuard tools are needed for the cluster network: {}", e);
+    let private_key = run_tool("wg", &["genkey"], None).await.map_err(missing)?;
+    let public_key = run_tool("wg", &["pubkey"], Some(&private_key))
+        .await
+        .map_err(missing)?;
+    Ok((private_key, public_key))
+}
+
+// the script is written next to the wireguard config, and the os asks for
+// administrator rights before running it
+async fn run_privileged(name: &str, script: &str) -> Result<(), String> {
+    let path = get_cluster_network_dir()?.join(format!("{}.{}", name, script_extension()));
+    std::fs::write(&path, script).map_err(|e| e.to_string())?;
+    let path = path.display().to_string();
+
+    match std::env::consts::OS {
+        "windows" => {
+            let command = format!(
+                "$p = Start-Process powershell -Verb RunAs -Wait -PassThru -ArgumentList \
+                 '-NoProfile','-ExecutionPolicy','Bypass','-File','\"{}\"'; exit $p.ExitCode",
+                path.replace('\'', "''")
+            );
+            run_tool("powershell", &["-NoProfile", "-Command", &command], None).await
+        }
+        "macos" => {
+            let shell = format!("sh {}", single_quoted(&path));
+            let command = format!(
+                "do shell script \"{}\" with administrator privileges",
+                shell.replace('\\', "\\\\").replace('"', "\\\"")
+            );
+            run_tool("osascript", &["-e", &command], None).await
+        }
+        _ => run_tool("pkexec", &["sh", &path], None).await,
+    }
+    .map(|_| ())
+}
+
+fn write_private(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, contents).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+async fn network_client(context: &str, kubeconfig: Option<String>) -> Result<Client, String> {
+    let (client, _, _) = create_client_with_specific_context(kubeconfig, Some(context))
+        .await
+        .map_err(|e| e.to_string())?;
+    client.ok_or_else(|| format!("Failed to create a client for context {}", context))
+}
+
+// a dry run create with a cluster ip that is hardly ever in range
+async fn discover_service_cidr(client: &Client, namespace: &str) -> Result<String, String> {
+    let services: Api<Service> = Api::namespaced(client.clone(), namespace);
+    let probe = Service {
+        metadata: ObjectMeta {
+            name: Some("kftray-cidr-probe".to_string()),
+            ..Default::default()
+        },
+        spec: Some(ServiceSpec {
+            cluster_ip: Some("1.1.1.1".to_string()),
+            ports: Some(vec![ServicePort {
+                port: 443,
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let params = PostParams {
+        dry_run: true,
+        ..Default::default()
+    };
+
+    match services.create(&params, &probe).await {
+        Ok(_) => {
+            Err("The cluster didn't tell its service range, pass the service CIDR".to_string())
+        }
+        Err(e) => parse_service_cidr(&e.to_string()).ok_or_else(|| {
+            format!(
+                "Failed to find the service range, pass the service CIDR: {}",
+                e
+            )
+        }),
+    }
+}
+
+async fn discover_dns_address(client: &Client) -> Result<String, String> {
+    let services: Api<Service> = Api::namespaced(client.clone(), "kube-system");
+    for name in DNS_SERVICES {
+        let Ok(Some(service)) = services.get_opt(name).await else {
+            continue;
+        };
+        if let Some(address) = service
+            .spec
+            .and_then(|spec| spec.cluster_ip)
+            .filter(|address| !address.is_empty() && address != "None")
+        {
+            return Ok(address);
+        }
+    }
+    Err("No cluster DNS service found in kube-system".to_string())
+}
+
+async fn network_plan(
+    request: &ClusterNetworkRequest, client: &Client,
+) -> Result<ClusterNetworkPlan, String> {
+    let namespace = request
+        .namespace
+        .clone()
+        .filter(|namespace| !namespace.trim().is_empty())
+        .unwrap_or_else(|| "default".to_string());
+    let service_cidr = match request
+        .service_cidr
+        .clone()
+        .filter(|cidr| !cidr.trim().is_empty())
+    {
+        Some(cidr) => cidr,
+        None => discover_service_cidr(client, &namespace).await?,
+    };
+    let dns_address = discover_dns_address(client).await?;
+    let (up_script, down_script) =
+        network_scripts(std::env::consts::OS, &network_config_path()?, &dns_address);
+
+    let notes = vec![
+        format!(
+            "Deploys a privileged {} pod in {}/{}, with its WireGuard key in a secret of the same name",
+            RELAY_IMAGE, request.context, namespace
+        ),
+        format!(
+            "Asks for administrator rights to create the {} interface and route {} through it",
+            INTERFACE, service_cidr
+        ),
+        format!(
+            "Resolves names under {} through the cluster DNS at {}",
+            CLUSTER_DOMAIN, dns_address
+        ),
+        "Asks for administrator rights again to take the interface down".to_string(),
+    ];
+
+    Ok(ClusterNetworkPlan {
+        context: request.context.clone(),
+        namespace,
+        service_cidr,
+        dns_address,
+        interface: INTERFACE.to_string(),
+        up_script,
+        down_script,
+        notes,
+    })
+}
+
+pub async fn plan_cluster_network(
+    request: &ClusterNetworkRequest,
+) -> Result<ClusterNetworkPlan, String> {
+    let client = network_client(&request.context, request.kubeconfig.clone()).await?;
+    network_plan(request, &client).await
+}
+
+// the relay key stays out of the pod spec, where anyone who can read pods
+// would see it
+fn relay_secret(name: &str, private_key: &str) -> Result<Secret, String> {
+    let mut labels = ownership_labels();
+    labels.insert("app".to_string(), "kftray-network".to_string());
+    serde_json::from_value(json!({
+        "apiVersion": "v1",
+        "kind": "Secret",
+        "metadata": {
+            "name": name,
+            "labels": labels,
+        },
+        "type": "Opaque",
+        "stringData": { PRIVATE_KEY_ENTRY: private_key },
+    }))
+    .map_err(|e| e.to_string())
+}
+
+fn relay_pod(name: &str, peer_public_key: &str) -> Result<Pod, String> {
+    let mut env = [
+        ("WG_PEER", peer_public_key.to_string()),
+        ("WG_PORT", WIREGUARD_PORT.to_string()),
+        ("WG_ADDRESS", RELAY_TUNNEL_ADDRESS.to_string()),
+        ("WG_PEER_ADDRESS", LOCAL_TUNNEL_ADDRESS.to_string()),
+    ]
+    .iter()
+    .map(|(name, value)| json!({ "name": name, "value": value }))
+    .collect::<Vec<_>>();
+    env.push(json!({
+        "name": "WG_PRIVATE_KEY",
+        "valueFrom": { "secretKeyRef": { "name": name, "key": PRIVATE_KEY_ENTRY } },
+    }));
+
+    let mut pod: Pod = serde_json::from_value(json!({
+        "apiVersion": "v1",
+        "kind": "Pod",
+        "metadata": {
+            "name": name,
+            "labels": { "app": "kftray-network" },
+        },
+        "spec": {
+            "restartPolicy": "Never",
+            "containers": [{
+                "name": "relay",
+                "image": RELAY_IMAGE,
+                "env": env,
+                "securityContext": { "privileged": true },
+                "ports": [{ "containerPort": WIREGUARD_PORT, "protocol": "UDP" }],
+                "readinessProbe": {
+                    "exec": { "command": ["test", "-f", "/tmp/ready"] },
+                    "periodSeconds": 2,
+                },
+            }],
+        },
+    }))
+    .map_err(|e| e.to_string())?;
+    label_owned_pod(&mut pod);
+    Ok(pod)
+}
+
+fn relay_pod_name() -> String {
+    let suffix: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(6)
+        .map(char::from)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    format!("kftray-network-{}", suffix)
+}
+
+fn is_pod_ready(pod: Option<&Pod>) -> bool {
+    pod.and_then(|pod| pod.status.as_ref())
+        .and_then(|status| status.conditions.as_ref())
+        .is_some_and(|conditions| {
+            conditions
+                .iter()
+                .any(|condition| condition.type_ == "Ready" && condition.status == "True")
+        })
+}
+
+// deploys the relay and waits until wireguard listens in it, returns the pod ip
+async fn deploy_relay(pods: &Api<Pod>, pod: &Pod) -> Result<String, String> {
+    let name = pod.metadata.name.clone().unwrap_or_default();
+    pods.create(&PostParams::default(), pod)
+        .await
+        .map_err(|e| format!("Failed to deploy the relay pod: {}", e))?;
+
+    tokio::time::timeout(
+        RELAY_READY_TIMEOUT,
+        await_condition(pods.clone(), &name, is_pod_ready),
+    )
+    .await
+    .map_err(|_| format!("Relay pod {} didn't become ready", name))?
+    .map_err(|e| e.to_string())?;
+
+    pods.get(&name)
+        .await
+        .map_err(|e| e.to_string())?
+        .status
+        .and_then(|status| status.pod_ip)
+        .ok_or_else(|| format!("Relay pod {} has no ip", name))
+}
+
+fn free_udp_port() -> Result<u16, String> {
+    std::net::UdpSocket::bind("127.0.0.1:0")
+        .and_then(|socket| socket.local_addr())
+        .map(|address| address.port())
+        .map_err(|e| e.to_string())
+}
+
+// undoes whatever part of the network is up, from the interface to the pod
+async fn tear_down(network: &ClusterNetwork) -> Result<(), String> {
+    let mut errors = Vec::new();
+    if let Some(down_script) = &network.down_script {
+        if let Err(e) = run_privileged("down", down_script).await {
+            errors.push(format!("Failed to take {} down: {}", network.interface, e));
+        }
+    }
+    if let Some(forward_id) = network.forward_id {
+        if let Err(e) = stop_adhoc_forward(forward_id).await {
+            warn!("The tunnel forward of the cluster network is gone: {}", e);
+        }
+    }
+
+    match network_client(&network.context, network.kubeconfig.clone()).await {
+        Ok(client) => {
+            let pods: Api<Pod> = Api::namespaced(client.clone(), &network.namespace);
+            if let Err(e) = pods
+                .delete(&network.relay_pod, &DeleteParams::default())
+                .await
+            {
+                errors.push(format!(
+                    "Failed to delete relay pod {}: {}",
+                    network.relay_pod, e
+                ));
+            }
+            let secrets: Api<Secret> = Api::namespaced(client, &network.namespace);
+            match secrets
+                .delete(&network.relay_pod, &DeleteParams::default())
+                .await
+            {
+                Ok(_) => {}
+                Err(kube::Error::Api(e)) if e.code == 404 => {}
+                Err(e) => errors.push(format!(
+                    "Failed to delete relay secret {}: {}",
+                    network.relay_pod, e
+                )),
+            }
+        }
+        Err(e) => errors.push(e),
+    }
+
+    if let Ok(dir) = get_cluster_network_dir() {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+// one cluster network at a time, the interface name and the dns domain are
+// shared by all of them
+pub async fn start_cluster_network(
+    request: ClusterNetworkRequest, http_log_state: Arc<HttpLogState>,
+) -> Result<ClusterNetwork, String> {
+    {
+        let mut state = CLUSTER_NETWORK.lock().unwrap();
+        match &*state {
+            NetworkState::Up(network) => {
+                return Err(format!(
+                    "The cluster network of {} is already up, stop it first",
+                    network.context
+                ))
+            }
+            NetworkState::Starting(context) => {
+                return Err(format!(
+                    "The cluster network of {} is still starting",
+                    context
+                ))
+            }
+            NetworkState::Down => *state = NetworkState::Starting(request.context.clone()),
+        }
+    }
+    let _starting = StartingGuard;
+
+    // the relay is a privileged pod, so both guards run before the secret and
+    // the pod exist. the adhoc forward checks the reason again and audits it
+    // once the tunnel is up
+    let guarded = [Config {
+        context: request.context.clone(),
+        kubeconfig: request.kubeconfig.clone(),
+        ..Default::default()
+    }];
+    ensure_workloads_allowed(&guarded).await?;
+    require_access_reason(&guarded, request.reason.as_deref()).await?;
+
+    let client = network_client(&request.context, request.kubeconfig.clone()).await?;
+    let plan = network_plan(&request, &client).await?;
+    let (private_key, public_key) = wireguard_keypair().await?;
+    let (relay_private_key, relay_public_key) = wireguard_keypair().await?;
+
+    let mut network = ClusterNetwork {
+        context: plan.context.clone(),
+        namespace: plan.namespace.clone(),
+        relay_pod: relay_pod_name(),
+        service_cidr: plan.service_cidr.clone(),
+        dns_address: plan.dns_address.clone(),
+        interface: plan.interface.clone(),
+        forward_id: None,
+        kubeconfig: request.kubeconfig.clone(),
+        down_script: None,
+    };
+    info!(
+        "Starting the cluster network of {} through relay pod {}",
+        network.context, network.relay_pod
+    );
+
+    let result = async {
+        let secrets: Api<Secret> = Api::namespaced(client.clone(), &network.namespace);
+        secrets
+            .create(
+                &PostParams::default(),
+                &relay_secret(&network.relay_pod, &relay_private_key)?,
+            )
+            .await
+            .map_err(|e| format!("Failed to store the relay key: {}", e))?;
+
+        let pods: Api<Pod> = Api::namespaced(client, &network.namespace);
+        let pod = relay_pod(&network.relay_pod, &public_key)?;
+        let relay_address = deploy_relay(&pods, &pod).await?;
+
+        let tunnel_port = free_udp_port()?;
+        let spec = AdhocForwardSpec {
+            context: network.context.clone(),
+            namespace: network.namespace.clone(),
+            service: Some(network.relay_pod.clone()),
+            remote_address: Some(relay_address),
+            remote_port: WIREGUARD_PORT,
+            local_port: Some(tunnel_port),
+            local_address: Some("127.0.0.1".to_string()),
+            protocol: Some("udp".to_string()),
+            kubeconfig: network.kubeconfig.clone(),
+            reason: request.reason.clone(),
+            ..Default::default()
+        };
+        let (forward_id, _) = start_adhoc_forward(spec, http_log_state).await?;
+        network.forward_id = Some(forward_id);
+
+        write_private(
+            &network_config_path()?,
+            &wireguard_config(
+                &private_key,
+                &relay_public_key,
+                tunnel_port,
+                &network.service_cidr,
+                &network.dns_address,
+            ),
+        )?;
+        // set first, an up script that fails halfway leaves the interface up
+        network.down_script = Some(plan.down_script.clone());
+        run_privileged("up", &plan.up_script).await?;
+        Ok::<(), String>(())
+    }
+    .await;
+
+    if let Err(e) = result {
+        if let Err(cleanup) = tear_down(&network).await {
+            warn!("Failed to clean up the cluster network: {}", cleanup);
+        }
+        return Err(e);
+    }
+
+    info!(
+        "Cluster network of {} is up, {} routes through {}",
+        network.context, network.service_cidr, network.interface
+    );
+    *CLUSTER_NETWORK.lock().unwrap() = NetworkState::Up(network.clone());
+    Ok(network)
+}
+
+pub async fn stop_cluster_network() -> Result<(), String> {
+    let network = {
+        let mut state = CLUSTER_NETWORK.lock().unwrap();
+        match std::mem::replace(&mut *state, NetworkState::Down) {
+            NetworkState::Up(network) => network,
+            NetworkState::Starting(context) => {
+                let error = format!("The cluster network of {} is still starting", context);
+                *state = NetworkState::Starting(context);
+                return Err(error);
+            }
+            NetworkState::Down => return Err("The cluster network isn't up".to_string()),
+        }
+    };
+    info!("Stopping the cluster network of {}", network.context);
+    tear_down(&network).await
+}
+
+pub async fn get_cluster_network() -> Option<ClusterNetwork> {
+    match &*CLUSTER_NETWORK.lock().unwrap() {
+        NetworkState::Up(network) => Some(network.clone()),
+        _ => None,
+    }
+}
+
+// called on shutdown, taking the interface down asks for administrator
+// rights once more
+pub async fn close_cluster_network() {
+    if get_cluster_network().await.is_none() {
+        return;
+    }
+    if let Err(e) = stop_cluster_network().await {
+        warn!("Failed to stop the cluster network: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_service_cidr() {
+        let message = "ApiError: Service \"kftray-cidr-probe\" is invalid: spec.clusterIPs: \
+                       Invalid value: []string{\"1.1.1.1\"}: failed to allocate IP 1.1.1.1: \
+                       the provided IP (1.1.1.1) is not in the valid range. The range of valid \
+                       IPs is 10.96.0.0/12: Invalid";
+        assert_eq!(parse_service_cidr(message).as_deref(), Some("10.96.0.0/12"));
+        assert_eq!(
+            parse_service_cidr("The range of valid IPs is fd00:10:96::/108").as_deref(),
+            Some("fd00:10:96::/108")
+        );
+        assert_eq!(parse_service_cidr("forbidden"), None);
+    }
+
+    #[test]
+    fn test_network_scripts() {
+        let path = Path::new("/home/o'neil/.kftray/cluster_network/kftnet0.conf");
+        let (up, down) = network_scripts("linux", path, "10.96.0.10");
+        assert!(up.contains("wg-quick up '/home/o'\\''neil/.kftray/cluster_network/kftnet0.conf'"));
+        assert!(up.contains("resolvectl dns kftnet0 10.96.0.10"));
+        assert!(down.starts_with("wg-quick down"));
+
+        let (up, down) = network_scripts("macos", path, "10.96.0.10");
+        assert!(up.contains("printf 'nameserver 10.96.0.10\\n' > /etc/resolver/cluster.local"));
+        assert!(down.starts_with("rm -f /etc/resolver/cluster.local"));
+
+        let (up, down) = network_scripts("windows", Path::new("C:\\kftnet0.conf"), "10.96.0.10");
+        assert!(up.contains("/installtunnelservice 'C:\\kftnet0.conf'"));
+        assert!(down.contains("/uninstalltunnelservice kftnet0"));
+
+        let config = wireguard_config(
+            "cHJpdmF0ZQ==",
+            "cHVibGlj",
+            40123,
+            "10.96.0.0/12",
+            "10.96.0.10",
+        );
+        assert!(config.contains("Endpoint = 127.0.0.1:40123\n"));
+        assert!(config.contains("AllowedIPs = 10.96.0.0/12, 10.96.0.10/32\n"));
+    }
+}
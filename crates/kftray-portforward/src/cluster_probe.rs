@@ -0,0 +1,183 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+use std::sync::Mutex;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use kftray_commons::models::cluster_probe_model::ContextProbe;
+use kftray_commons::utils::access_log::now_millis;
+use lazy_static::lazy_static;
+use log::{
+    debug,
+    warn,
+};
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+
+use crate::client::create_client_with_specific_context;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+
+type WatchedContext = (String, Option<String>);
+
+lazy_static! {
+    static ref WATCHED_CONTEXTS: Mutex<HashMap<i64, WatchedContext>> = Mutex::new(HashMap::new());
+    static ref CONTEXT_PROBES: Mutex<HashMap<String, ContextProbe>> = Mutex::new(HashMap::new());
+    static ref CONTEXT_MONITOR: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+}
+
+fn is_unauthorized(error: &kube::Error) -> bool {
+    matches!(error, kube::Error::Api(response) if response.code == 401)
+}
+
+async fn run_probe(context: &str, kubeconfig: Option<String>) -> ContextProbe {
+    let mut probe = ContextProbe {
+        context: context.to_string(),
+        ..Default::default()
+    };
+
+    let client = match create_client_with_specific_context(kubeconfig, Some(context)).await {
+        Ok((Some(client), _, _)) => client,
+        Ok((None, _, _)) => {
+            probe.error = Some("Client not created".to_string());
+            return probe;
+        }
+        Err(e) => {
+            probe.error = Some(e.to_string());
+            return probe;
+        }
+    };
+
+    // /version is readable without credentials, so it only measures the round
+    // trip
+    let started = Instant::now();
+    match client.apiserver_version().await {
+        Ok(version) => {
+            probe.reachable = true;
+            probe.latency_ms = Some(started.elapsed().as_millis() as u64);
+            probe.server_version = Some(version.git_version);
+        }
+        Err(e) => {
+            probe.error = Some(e.to_string());
+            return probe;
+        }
+    }
+
+    // discovery needs an authenticated user, a 403 still means the credentials
+    // work
+    match client.list_core_api_versions().await {
+        Ok(_) => probe.authenticated = true,
+        Err(e) if is_unauthorized(&e) => {
+            probe.error = Some(format!("Credentials were rejected: {}", e));
+        }
+        Err(kube::Error::Api(response)) if response.code == 403 => probe.authenticated = true,
+        Err(e) => probe.error = Some(e.to_string()),
+    }
+
+    probe
+}
+
+pub async fn probe_context(context: &str, kubeconfig: Option<String>) -> ContextProbe {
+    let mut probe = match timeout(PROBE_TIMEOUT, run_probe(context, kubeconfig)).await {
+        Ok(probe) => probe,
+        Err(_) => ContextProbe {
+            context: context.to_string(),
+            error: Some("Cluster probe timed out".to_string()),
+            ..Default::default()
+        },
+    };
+    probe.checked_at_ms = now_millis();
+    probe
+}
+
+fn watched_contexts() -> Vec<WatchedContext> {
+    let watched = WATCHED_CONTEXTS.lock().unwrap();
+    let mut seen = HashSet::new();
+    watched
+        .values()
+        .filter(|(context, _)| seen.insert(context.clone()))
+        .cloned()
+        .collect()
+}
+
+// one monitor serves every running forward, each context is probed once per
+// round no matter how many forwards use it
+fn start_context_monitor() {
+    let mut monitor = CONTEXT_MONITOR.lock().unwrap();
+    if monitor.is_some() {
+        return;
+    }
+
+    *monitor = Some(tokio::spawn(async move {
+        loop {
+            let contexts = watched_contexts();
+            for (context, kubeconfig) in &contexts {
+                let probe = probe_context(context, kubeconfig.clone()).await;
+                match &probe.error {
+                    Some(error) => warn!("Cluster probe for context {} failed: {}", context, error),
+                    None => debug!(
+                        "Cluster probe for context {} took {}ms",
+                        context,
+                        probe.latency_ms.unwrap_or_default()
+                    ),
+                }
+                CONTEXT_PROBES
+                    .lock()
+                    .unwrap()
+                    .insert(context.clone(), probe);
+            }
+
+            tokio::time::sleep(PROBE_INTERVAL).await;
+        }
+    }));
+}
+
+pub fn watch_context(config_id: i64, context: String, kubeconfig: Option<String>) {
+    WATCHED_CONTEXTS
+        .lock()
+        .unwrap()
+        .insert(config_id, (context, kubeconfig));
+    start_context_monitor();
+}
+
+pub fn unwatch_context(config_id: Option<i64>) {
+    let remaining: HashSet<String> = {
+        let mut watched = WATCHED_CONTEXTS.lock().unwrap();
+        match config_id {
+            Some(config_id) => {
+                watched.remove(&config_id);
+            }
+            None => watched.clear(),
+        }
+        watched
+            .values()
+            .map(|(context, _)| context.clone())
+            .collect()
+    };
+
+    CONTEXT_PROBES
+        .lock()
+        .unwrap()
+        .retain(|context, _| remaining.contains(context));
+
+    if remaining.is_empty() {
+        if let Some(handle) = CONTEXT_MONITOR.lock().unwrap().take() {
+            handle.abort();
+        }
+    }
+}
+
+pub fn get_context_probes() -> Vec<ContextProbe> {
+    let mut probes: Vec<ContextProbe> = CONTEXT_PROBES.lock().unwrap().values().cloned().collect();
+    probes.sort_by(|a, b| a.context.cmp(&b.context));
+    probes
+}
+
+pub fn get_context_probe(context: &str) -> Option<ContextProbe> {
+    CONTEXT_PROBES.lock().unwrap().get(context).cloned()
+}
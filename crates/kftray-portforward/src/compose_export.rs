@@ -0,0 +1,19 @@
+use kftray_commons::utils::compose_export::compose_override;
+use log::info;
+
+use crate::reload::running_forwards;
+
+// a docker compose override for the running forwards, merged into the given
+// compose services
+pub async fn export_compose_override(services: Vec<String>) -> Result<String, String> {
+    let forwards = running_forwards().await?;
+    if forwards.is_empty() {
+        return Err("No forwards are running to expose to containers".to_string());
+    }
+    info!(
+        "Exporting {} forward(s) as a compose override for {} service(s)",
+        forwards.len(),
+        services.len()
+    );
+    Ok(compose_override(&forwards, &services))
+}
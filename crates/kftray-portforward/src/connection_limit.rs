@@ -0,0 +1,123 @@
+use std::sync::atomic::{
+    AtomicUsize,
+    Ordering,
+};
+use std::sync::Arc;
+
+use kftray_commons::models::config_model::Config;
+use tokio::sync::{
+    OwnedSemaphorePermit,
+    Semaphore,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConnectionOverflow {
+    #[default]
+    Reject,
+    Queue,
+}
+
+impl ConnectionOverflow {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionOverflow::Reject => "reject",
+            ConnectionOverflow::Queue => "queue",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "reject" => Some(ConnectionOverflow::Reject),
+            "queue" => Some(ConnectionOverflow::Queue),
+            _ => None,
+        }
+    }
+}
+
+// caps how many local connections a forward relays at once, shared by every
+// connection accepted on its listener
+#[derive(Clone, Debug)]
+pub struct ConnectionLimit {
+    max: usize,
+    overflow: ConnectionOverflow,
+    slots: Arc<Semaphore>,
+    queued: Arc<AtomicUsize>,
+}
+
+impl ConnectionLimit {
+    pub fn new(max: usize, overflow: ConnectionOverflow) -> Self {
+        ConnectionLimit {
+            max,
+            overflow,
+            slots: Arc::new(Semaphore::new(max)),
+            queued: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let max = config.max_connections.filter(|max| *max > 0)?;
+        let overflow = config
+            .connection_overflow
+            .as_deref()
+            .and_then(ConnectionOverflow::from_name)
+            .unwrap_or_default();
+        Some(ConnectionLimit::new(max as usize, overflow))
+    }
+
+    pub fn active(&self) -> usize {
+        self.max - self.slots.available_permits()
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    // the slot is held for as long as the returned permit lives
+    pub async fn acquire(&self) -> anyhow::Result<OwnedSemaphorePermit> {
+        match self.overflow {
+            ConnectionOverflow::Reject => self.slots.clone().try_acquire_owned().map_err(|_| {
+                anyhow::anyhow!(
+                    "Connection limit of {} reached, rejecting connection",
+                    self.max
+                )
+            }),
+            ConnectionOverflow::Queue => {
+                self.queued.fetch_add(1, Ordering::Relaxed);
+                let permit = self.slots.clone().acquire_owned().await;
+                self.queued.fetch_sub(1, Ordering::Relaxed);
+                Ok(permit?)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_connection_limit() {
+        let reject = ConnectionLimit::new(1, ConnectionOverflow::Reject);
+        let permit = reject.acquire().await.unwrap();
+        assert_eq!(reject.active(), 1);
+        assert!(reject.acquire().await.is_err());
+        drop(permit);
+        assert!(reject.acquire().await.is_ok());
+
+        let queue = ConnectionLimit::new(1, ConnectionOverflow::Queue);
+        let permit = queue.acquire().await.unwrap();
+        let waiting = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.acquire().await.map(|_| ()) }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(queue.queued(), 1);
+        assert!(!waiting.is_finished());
+
+        drop(permit);
+        waiting.await.unwrap().unwrap();
+        assert_eq!(queue.queued(), 0);
+    }
+}
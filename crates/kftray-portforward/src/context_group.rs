@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
+use kftray_commons::config::get_configs;
+use kftray_commons::config_state::get_configs_state;
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::response::CustomResponse;
+use kftray_commons::utils::profile::read_active_profile_configs;
+use lazy_static::lazy_static;
+use log::{
+    info,
+    warn,
+};
+use serde::Serialize;
+
+use crate::audit::start_with_reason;
+use crate::headless::{
+    start_config,
+    stop_config,
+};
+use crate::models::kube::HttpLogState;
+use crate::progress::{
+    Progress,
+    ProgressKind,
+};
+
+lazy_static! {
+    // configs each offline context had running, restarted once it's back
+    static ref OFFLINE_CONTEXTS: Mutex<HashMap<String, Vec<i64>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct OfflineContext {
+    pub context: String,
+    pub paused_config_ids: Vec<i64>,
+}
+
+pub fn is_context_offline(context: &str) -> bool {
+    OFFLINE_CONTEXTS.lock().unwrap().contains_key(context)
+}
+
+pub fn get_offline_contexts() -> Vec<OfflineContext> {
+    let mut contexts: Vec<OfflineContext> = OFFLINE_CONTEXTS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(context, config_ids)| OfflineContext {
+            context: context.clone(),
+            paused_config_ids: config_ids.clone(),
+        })
+        .collect();
+    contexts.sort_by(|a, b| a.context.cmp(&b.context));
+    contexts
+}
+
+async fn running_config_ids() -> Result<Vec<i64>, String> {
+    Ok(get_configs_state()
+        .await?
+        .into_iter()
+        .filter(|state| state.is_running)
+        .map(|state| state.config_id)
+        .collect())
+}
+
+async fn start_configs(
+    configs: Vec<Config>, http_log_state: Arc<HttpLogState>,
+) -> Result<Vec<CustomResponse>, String> {
+    let total = configs.len();
+    let progress = Progress::begin(
+        ProgressKind::StartAll,
+        total,
+        format!("Starting {} config(s)", total),
+    );
+    let mut responses = Vec::new();
+    let mut errors = Vec::new();
+    for config in configs {
+        let config_id = config.id.unwrap_or_default();
+        let name = config
+            .alias
+            .clone()
+            .unwrap_or_else(|| config_id.to_string());
+        match start_config(config, http_log_state.clone()).await {
+            Ok(started) => {
+                responses.extend(started);
+                progress.advance(format!("Started {}", name));
+            }
+            Err(e) => {
+                warn!("Failed to start config {}: {}", config_id, e);
+                errors.push(format!("Failed to start config {}: {}", config_id, e));
+                progress.advance(format!("Failed to start {}", name));
+            }
+        }
+    }
+    progress.finish(format!(
+        "Started {} of {} config(s)",
+        total - errors.len(),
+        total
+    ));
+
+    if responses.is_empty() && !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+    Ok(responses)
+}
+
+// returns the ids that were stopped along with what went wrong for the rest
+async fn stop_context_configs(
+    context: &str,
+) -> Result<(Vec<i64>, Vec<CustomResponse>, Vec<String>), String> {
+    let running = running_config_ids().await?;
+
+    let mut stopped = Vec::new();
+    let mut responses = Vec::new();
+    let mut errors = Vec::new();
+    for config in get_configs().await?.into_iter().filter(|config| {
+        config.context == context && config.id.is_some_and(|id| running.contains(&id))
+    }) {
+        let config_id = config.id.unwrap_or_default();
+        match stop_config(&config).await {
+            Ok(response) => {
+                stopped.push(config_id);
+                responses.push(response);
+            }
+            Err(e) => {
+                warn!("Failed to stop config {}: {}", config_id, e);
+                errors.push(format!("Failed to stop config {}: {}", config_id, e));
+            }
+        }
+    }
+    Ok((stopped, responses, errors))
+}
+
+// starts every config of the active profile on that context that isn't
+// already running
+pub async fn start_all_for_context(
+    context: &str, reason: Option<&str>, http_log_state: Arc<HttpLogState>,
+) -> Result<Vec<CustomResponse>, String> {
+    if is_context_offline(context) {
+        return Err(format!(
+            "Context {} is marked offline, bring it back online first",
+            context
+        ));
+    }
+
+    let running = running_config_ids().await?;
+    let configs: Vec<Config> = read_active_profile_configs()
+        .await?
+        .into_iter()
+        .filter(|config| {
+            config.context == context && !config.id.is_some_and(|id| running.contains(&id))
+        })
+        .collect();
+
+    if configs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    info!(
+        "Starting {} config(s) for context {}",
+        configs.len(),
+        context
+    );
+    start_with_reason(
+        &configs,
+        reason,
+        start_configs(configs.clone(), http_log_state),
+    )
+    .await
+}
+
+pub async fn stop_all_for_context(context: &str) -> Result<Vec<CustomResponse>, String> {
+    let (_, responses, errors) = stop_context_configs(context).await?;
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+    Ok(responses)
+}
+
+// pauses the context's running forwards, e.g. while the vpn in front of it is
+// down, and remembers them so going back online restarts exactly those
+pub async fn mark_context_offline(context: &str) -> Result<Vec<i64>, String> {
+    if is_context_offline(context) {
+        return Err(format!("Context {} is already offline", context));
+    }
+
+    let (stopped, _, errors) = stop_context_configs(context).await?;
+    info!(
+        "Context {} is offline, paused configs {:?}",
+        context, stopped
+    );
+    OFFLINE_CONTEXTS
+        .lock()
+        .unwrap()
+        .insert(context.to_string(), stopped.clone());
+
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+    Ok(stopped)
+}
+
+// the context stays offline when the reason is missing, so it can be
+// brought back with one
+pub async fn mark_context_online(
+    context: &str, reason: Option<&str>, http_log_state: Arc<HttpLogState>,
+) -> Result<Vec<CustomResponse>, String> {
+    let paused = OFFLINE_CONTEXTS
+        .lock()
+        .unwrap()
+        .get(context)
+        .cloned()
+        .ok_or_else(|| format!("Context {} is not offline", context))?;
+
+    // configs deleted or moved to another context while offline are dropped
+    let configs: Vec<Config> = get_configs()
+        .await?
+        .into_iter()
+        .filter(|config| {
+            config.context == context && config.id.is_some_and(|id| paused.contains(&id))
+        })
+        .collect();
+
+    info!(
+        "Context {} is back online, restoring {} config(s)",
+        context,
+        configs.len()
+    );
+    let start = async {
+        OFFLINE_CONTEXTS.lock().unwrap().remove(context);
+        start_configs(configs.clone(), http_log_state).await
+    };
+    start_with_reason(&configs, reason, start).await
+}
@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+
+use kftray_commons::bulk_update::update_configs_bulk;
+use kftray_commons::config::get_configs;
+use kftray_commons::context_settings::{
+    get_context_settings,
+    save_context_settings,
+};
+use kftray_commons::models::bulk_update_model::{
+    ConfigChange,
+    ConfigFilter,
+};
+use kftray_commons::models::context_settings_model::ContextSettings;
+use log::{
+    info,
+    warn,
+};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::client::create_client_with_specific_context;
+
+const MIN_SIMILARITY: f64 = 0.5;
+const MAX_SUGGESTIONS: usize = 3;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BrokenContext {
+    pub kubeconfig: Option<String>,
+    pub context: String,
+    pub config_ids: Vec<i64>,
+    pub suggestions: Vec<String>,
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+pub fn context_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (a.to_lowercase(), b.to_lowercase());
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(&a, &b) as f64 / longest as f64
+}
+
+// re-provisioned clusters usually come back under a name that only differs
+// by a suffix, a region or an account id
+pub fn suggest_contexts(missing: &str, available: &[String]) -> Vec<String> {
+    let mut scored: Vec<(f64, &String)> = available
+        .iter()
+        .map(|candidate| (context_similarity(missing, candidate), candidate))
+        .filter(|(similarity, _)| *similarity >= MIN_SIMILARITY)
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate.clone())
+        .collect()
+}
+
+async fn available_contexts(kubeconfig: Option<String>) -> Result<Vec<String>, String> {
+    let (_, _, contexts) = create_client_with_specific_context(kubeconfig, None)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(contexts)
+}
+
+// configs whose context is gone from a kubeconfig that otherwise still loads,
+// an unreadable kubeconfig isn't a rename and is left to the usual errors
+pub async fn find_broken_contexts() -> Result<Vec<BrokenContext>, String> {
+    let mut by_kubeconfig: BTreeMap<Option<String>, BTreeMap<String, Vec<i64>>> = BTreeMap::new();
+    for config in get_configs().await? {
+        by_kubeconfig
+            .entry(config.kubeconfig.clone())
+            .or_default()
+            .entry(config.context.clone())
+            .or_default()
+            .extend(config.id);
+    }
+
+    let mut broken = Vec::new();
+    for (kubeconfig, contexts) in by_kubeconfig {
+        let available = match available_contexts(kubeconfig.clone()).await {
+            Ok(available) if !available.is_empty() => available,
+            Ok(_) => continue,
+            Err(e) => {
+                warn!("Skipping kubeconfig {:?}: {}", kubeconfig, e);
+                continue;
+            }
+        };
+
+        for (context, config_ids) in contexts {
+            if available.contains(&context) {
+                continue;
+            }
+            broken.push(BrokenContext {
+                kubeconfig: kubeconfig.clone(),
+                suggestions: suggest_contexts(&context, &available),
+                context,
+                config_ids,
+            });
+        }
+    }
+    Ok(broken)
+}
+
+// settings of the old name carry over unless the new context has its own
+async fn carry_over_context_settings(from: &str, to: &str) -> Result<(), String> {
+    if get_context_settings(to).await?.is_some() {
+        return Ok(());
+    }
+    if let Some(settings) = get_context_settings(from).await? {
+        save_context_settings(ContextSettings {
+            context: to.to_string(),
+            ..settings
+        })
+        .await?;
+    }
+    Ok(())
+}
+
+// moves every config of `from` in that kubeconfig over to `to` at once
+pub async fn remap_context(
+    kubeconfig: Option<String>, from: &str, to: &str, dry_run: bool,
+) -> Result<Vec<ConfigChange>, String> {
+    let available = available_contexts(kubeconfig.clone()).await?;
+    if !available.iter().any(|context| context == to) {
+        return Err(format!("Context {} doesn't exist in the kubeconfig", to));
+    }
+
+    let filter = ConfigFilter {
+        context: Some(from.to_string()),
+        kubeconfig: kubeconfig.clone(),
+        ..Default::default()
+    };
+    let changes = update_configs_bulk(filter, json!({ "context": to }), dry_run).await?;
+
+    if !dry_run && !changes.is_empty() {
+        carry_over_context_settings(from, to).await?;
+        info!(
+            "Remapped {} config(s) from context {} to {}",
+            changes.len(),
+            from,
+            to
+        );
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest_contexts() {
+        let available = [
+            "arn:aws:eks:eu-west-1:123456789012:cluster/payments-v2",
+            "arn:aws:eks:eu-west-1:123456789012:cluster/search",
+            "kind-local",
+        ]
+        .map(String::from)
+        .to_vec();
+
+        assert_eq!(
+            suggest_contexts(
+                "arn:aws:eks:eu-west-1:123456789012:cluster/payments",
+                &available
+            )[0],
+            "arn:aws:eks:eu-west-1:123456789012:cluster/payments-v2"
+        );
+        assert!(suggest_contexts("minikube", &available).is_empty());
+        assert_eq!(context_similarity("prod", "PROD"), 1.0);
+    }
+}
@@ -14,15 +14,51 @@ use futures::stream::{
 };
 use hostsfile::HostsBuilder;
 use k8s_openapi::api::core::v1::Pod;
-use kftray_commons::config::get_config;
+use kftray_commons::config::{
+    get_config,
+    read_configs,
+};
 use kftray_commons::config_state::get_configs_state;
 use kftray_commons::models::{
     config_model::Config,
-    config_state_model::ConfigState,
+    config_state_model::{
+        ConfigState,
+        ForwardPhase,
+    },
+    hook_model::HookEvent,
+    message_model::{
+        MessageCode,
+        UserMessage,
+    },
+    protocol_model::StreamProtocol,
     response::CustomResponse,
+    settings_model::HostsBackend,
 };
+use kftray_commons::utils::access_log::set_access_log_enabled;
 use kftray_commons::utils::config_dir::get_pod_manifest_path;
+use kftray_commons::utils::config_log::{
+    remove_config_log_route,
+    set_config_log_route,
+};
 use kftray_commons::utils::config_state::update_config_state;
+use kftray_commons::utils::context_settings::{
+    apply_context_defaults,
+    ensure_workloads_allowed,
+    get_context_settings,
+};
+use kftray_commons::utils::http_store::set_http_store_enabled;
+use kftray_commons::utils::messages::{
+    format_message,
+    format_messages,
+    DEFAULT_LOCALE,
+};
+use kftray_commons::utils::query_log::set_query_log_enabled;
+use kftray_commons::utils::settings::current_settings;
+use kftray_commons::utils::template::{
+    has_placeholders,
+    resolve_config_templates,
+};
+use kftray_commons::utils::usage::record_config_start;
 use kube::api::{
     Api,
     DeleteParams,
@@ -41,11 +77,64 @@ use rand::{
 };
 use tokio::task::JoinHandle;
 
-use crate::client::create_client_with_specific_context;
+use crate::adhoc::{
+    is_adhoc_forward,
+    take_adhoc_forwards,
+};
+use crate::cancel::{
+    register_start,
+    CancelToken,
+};
+use crate::circuit_breaker::reset_circuit;
+use crate::client::{
+    create_client_with_specific_context,
+    create_client_with_tuning,
+    KubeTuning,
+};
 use crate::client::{
     get_services_with_annotation,
     list_all_namespaces,
 };
+use crate::cluster_probe::{
+    unwatch_context,
+    watch_context,
+};
+use crate::connection_limit::ConnectionLimit;
+use crate::expiry::clear_forward_ttl;
+use crate::fanout::{
+    hosts_comment,
+    remove_fan_out_hosts,
+    start_fan_out,
+    stop_fan_out,
+};
+use crate::firewall::check_firewall;
+use crate::grpc_health::{
+    clear_grpc_health,
+    start_grpc_health_monitor,
+    stop_grpc_health_monitor,
+};
+use crate::hooks::spawn_hook;
+use crate::kafka::stop_kafka_broker_forwards;
+use crate::lifecycle::{
+    get_forward_phases,
+    lock_all_configs,
+    lock_configs,
+    set_phase,
+    transition,
+};
+use crate::mdns::{
+    advertise_forward,
+    withdraw_forward,
+};
+use crate::mesh::{
+    inject_mesh_proxy,
+    mesh_failure_hint,
+    required_proxy_mesh,
+};
+use crate::mock_rules::{
+    clear_mock_rules,
+    register_mock_rules,
+};
 use crate::models::kube::{
     HttpLogState,
     Port,
@@ -53,17 +142,446 @@ use crate::models::kube::{
     Target,
     TargetSelector,
 };
-use crate::port_forward::CANCEL_NOTIFIER;
-use crate::port_forward::CHILD_PROCESSES;
+use crate::openapi_spec::{
+    load_openapi_spec,
+    unload_openapi_spec,
+};
+use crate::overrides::clear_forward_overrides;
+use crate::ownership::{
+    label_owned_pod,
+    owned_pods_selector,
+};
+use crate::pending_start::{
+    cancel_pending_start,
+    missing_target_message,
+    no_endpoints_message,
+    queue_start,
+    ready_endpoint_count,
+    target_exists,
+    PendingReason,
+};
+use crate::pod_finder::is_proxy_config;
+use crate::pod_watch::{
+    start_pod_watch,
+    stop_pod_watch,
+};
+use crate::port_forward::{
+    child_process_key,
+    listen_address,
+    parse_child_process_key,
+    CANCEL_NOTIFIER,
+    CHILD_PROCESSES,
+};
+use crate::progress::{
+    Progress,
+    ProgressKind,
+};
+use crate::proxy_diagnosis::proxy_failure_hint;
+use crate::proxy_pool::{
+    has_warm_pod,
+    make_warm_pod,
+    park_warm_pod,
+    take_warm_pod,
+    warm_pool_ttl,
+    RelayTarget,
+    WarmKey,
+    WARM_CONFIG_LABEL,
+};
+use crate::rbac::check_rbac;
+use crate::readiness::{
+    probe_address,
+    wait_until_ready,
+};
+use crate::reload::{
+    forget_running_config,
+    remember_running_config,
+    running_config,
+};
+use crate::rollback::{
+    StartEffect,
+    StartJournal,
+};
+use crate::ssh_mirror::{
+    start_ssh_mirror,
+    stop_ssh_mirror,
+};
+use crate::target_switch::clear_forward_switch;
+use crate::traffic_script::{
+    clear_traffic_script,
+    register_traffic_script,
+};
+use crate::traffic_split::{
+    clear_traffic_split,
+    register_traffic_split,
+};
+use crate::transport::Transport;
+
+fn begin_start(configs: &[Config]) -> Result<Vec<i64>, String> {
+    let config_ids: Vec<i64> = configs.iter().filter_map(|config| config.id).collect();
+    for (index, config_id) in config_ids.iter().enumerate() {
+        if let Err(e) = transition(*config_id, ForwardPhase::Starting) {
+            for started in &config_ids[..index] {
+                set_phase(*started, ForwardPhase::Stopped);
+            }
+            return Err(e);
+        }
+    }
+    Ok(config_ids)
+}
+
+fn finish_phase<T>(config_ids: &[i64], result: &Result<T, String>, done: ForwardPhase) {
+    let phase = if result.is_ok() {
+        done
+    } else {
+        ForwardPhase::Failed
+    };
+    for config_id in config_ids {
+        set_phase(*config_id, phase);
+    }
+}
+
+// a start that went through runs its configs, apart from the ones that came
+// back as failed responses
+fn finish_start_phases(config_ids: &[i64], result: &Result<Vec<CustomResponse>, String>) {
+    finish_phase(config_ids, result, ForwardPhase::Running);
+    let failed = result
+        .iter()
+        .flatten()
+        .filter(|response| response.status != 0)
+        .filter_map(|response| response.id);
+    for config_id in failed {
+        set_phase(config_id, ForwardPhase::Failed);
+    }
+}
+
+fn cancelled_start(config_ids: &[i64]) -> String {
+    let ids: Vec<String> = config_ids.iter().map(|id| id.to_string()).collect();
+    format!("Start of config(s) {} was cancelled", ids.join(", "))
+}
+
+// tears down whatever a cancelled start got to before it was dropped
+async fn undo_cancelled_start(configs: Vec<Config>, proxy: bool) {
+    for config in resolve_configs_lenient(configs).await {
+        let Some(config_id) = config.id else {
+            continue;
+        };
+        if let Err(e) = stop_port_forward_locked(config_id.to_string()).await {
+            debug!("Nothing to stop for cancelled config {}: {}", config_id, e);
+        }
+        if proxy {
+            if let Err(e) = delete_proxy_pods(&config).await {
+                warn!(
+                    "Failed to delete proxy pods of cancelled config {}: {}",
+                    config_id, e
+                );
+            }
+        }
+        set_phase(config_id, ForwardPhase::Stopped);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StartOptions {
+    // roll every config back when one of them fails
+    pub fail_fast: bool,
+    // restart a config that is already running instead of answering with the
+    // forward it has
+    pub force: bool,
+}
+
+impl Default for StartOptions {
+    fn default() -> Self {
+        StartOptions {
+            fail_fast: true,
+            force: false,
+        }
+    }
+}
 
 pub async fn start_port_forward(
     configs: Vec<Config>, protocol: &str, http_log_state: Arc<HttpLogState>,
+) -> Result<Vec<CustomResponse>, String> {
+    start_port_forward_with_cancel(
+        configs,
+        protocol,
+        http_log_state,
+        CancelToken::new(),
+        StartOptions::default(),
+    )
+    .await
+}
+
+// whether the config has a forward task registered, and if any of its tasks
+// is still running
+fn forward_liveness(config_id: i64) -> Option<bool> {
+    let processes = CHILD_PROCESSES.lock().unwrap();
+    let handles: Vec<bool> = processes
+        .iter()
+        .filter(|(key, _)| parse_child_process_key(key).is_some_and(|(id, _)| id == config_id))
+        .map(|(_, handle)| !handle.is_finished())
+        .collect();
+    (!handles.is_empty()).then(|| handles.contains(&true))
+}
+
+async fn running_forward_response(config: &Config) -> CustomResponse {
+    let state_port = get_configs_state()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .find(|state| Some(state.config_id) == config.id)
+        .and_then(|state| state.local_port);
+    let local_port = state_port.or(config.local_port).unwrap_or_default();
+
+    let message = UserMessage::new(MessageCode::ForwardAlreadyRunning)
+        .with("service", config.service.clone().unwrap_or_default())
+        .with("local_port", local_port);
+    CustomResponse {
+        id: config.id,
+        service: config.service.clone().unwrap_or_default(),
+        namespace: config.namespace.clone(),
+        local_port,
+        remote_port: config.remote_port.unwrap_or_default(),
+        context: config.context.clone(),
+        protocol: config.protocol.clone(),
+        stdout: format_message(&message, DEFAULT_LOCALE),
+        stderr: String::new(),
+        status: 0,
+        firewall_warning: None,
+        messages: vec![message],
+    }
+}
+
+// sets aside the configs that already have a live forward, answered with that
+// forward, and clears out the ones whose tasks have died or that are forced
+// to restart so they start from scratch. proxy configs take their pods down
+// with them
+async fn settle_running_configs(
+    configs: Vec<Config>, force: bool, proxy: bool,
+) -> (Vec<Config>, Vec<CustomResponse>) {
+    let mut to_start = Vec::new();
+    let mut running = Vec::new();
+
+    for config in configs {
+        let Some(config_id) = config.id else {
+            to_start.push(config);
+            continue;
+        };
+        match forward_liveness(config_id) {
+            None => {}
+            Some(true) if !force => {
+                info!(
+                    "Config {} is already running, keeping its forward",
+                    config_id
+                );
+                running.push(running_forward_response(&config).await);
+                continue;
+            }
+            Some(alive) => {
+                info!(
+                    "Clearing the {} forward of config {} before starting it",
+                    if alive { "running" } else { "dead" },
+                    config_id
+                );
+                if proxy {
+                    let service = config.service.clone().unwrap_or_default();
+                    if let Err(e) =
+                        stop_proxy_forward_locked(config_id, &config.namespace, service).await
+                    {
+                        warn!(
+                            "Failed to clear the proxy pods of config {}: {}",
+                            config_id, e
+                        );
+                    }
+                }
+                while forward_liveness(config_id).is_some() {
+                    if let Err(e) = stop_port_forward_locked(config_id.to_string()).await {
+                        warn!("Failed to clear the forward of config {}: {}", config_id, e);
+                        break;
+                    }
+                }
+                set_phase(config_id, ForwardPhase::Stopped);
+            }
+        }
+        to_start.push(config);
+    }
+
+    (to_start, running)
+}
+
+// like start_port_forward, cancelling the token or calling cancel_start for
+// one of the configs rolls the whole batch back. without fail_fast a config
+// that fails is rolled back on its own and comes back as a failed response,
+// next to the ones that started
+pub async fn start_port_forward_with_cancel(
+    configs: Vec<Config>, protocol: &str, http_log_state: Arc<HttpLogState>, cancel: CancelToken,
+    options: StartOptions,
+) -> Result<Vec<CustomResponse>, String> {
+    let config_ids: Vec<i64> = configs.iter().filter_map(|config| config.id).collect();
+    let Some(_guards) = cancel.run(lock_configs(config_ids.clone())).await else {
+        return Err(cancelled_start(&config_ids));
+    };
+    let (configs, running) = settle_running_configs(configs, options.force, false).await;
+    if configs.is_empty() {
+        return Ok(running);
+    }
+    let config_ids = begin_start(&configs)?;
+    let _registration = register_start(&config_ids, &cancel);
+    let mut journal = StartJournal::default();
+
+    match cancel
+        .run(start_port_forward_locked(
+            configs.clone(),
+            protocol,
+            http_log_state,
+            options.fail_fast,
+            &mut journal,
+        ))
+        .await
+    {
+        Some(result) => {
+            finish_start_phases(&config_ids, &result);
+            result.map(|mut responses| {
+                responses.extend(running);
+                responses
+            })
+        }
+        None => {
+            undo_cancelled_start(configs, false).await;
+            Err(cancelled_start(&config_ids))
+        }
+    }
+}
+
+// drops everything a config registered next to its forward tasks, from the
+// broker forwards and monitors to its overrides and ttl, for every config when
+// there's no id. returns the ad-hoc forwards it dropped
+async fn tear_down_config(config_id: Option<i64>) -> Vec<Config> {
+    stop_kafka_broker_forwards(config_id).await;
+    stop_fan_out(config_id);
+    reset_circuit(config_id);
+    remove_config_log_route(config_id);
+    stop_grpc_health_monitor(config_id);
+    clear_grpc_health(config_id);
+    stop_pod_watch(config_id);
+    clear_forward_switch(config_id);
+    clear_traffic_split(config_id);
+    clear_mock_rules(config_id);
+    clear_traffic_script(config_id);
+    unload_openapi_spec(config_id);
+    cancel_pending_start(config_id);
+    unwatch_context(config_id);
+    withdraw_forward(config_id);
+    stop_ssh_mirror(config_id);
+    forget_running_config(config_id);
+    clear_forward_overrides(config_id);
+    clear_forward_ttl(config_id);
+    take_adhoc_forwards(config_id)
+}
+
+// undoes what the start did for the configs, from the forward tasks to the
+// hosts entries and proxy pods, and drops what they registered
+async fn roll_back_start(config_ids: &[i64], journal: &mut StartJournal) {
+    journal.undo(config_ids).await;
+    for config_id in config_ids {
+        tear_down_config(Some(*config_id)).await;
+
+        let config_state = ConfigState {
+            id: None,
+            config_id: *config_id,
+            is_running: false,
+            local_port: None,
+        };
+        if let Err(e) = update_config_state(&config_state).await {
+            log::error!("Failed to update config state: {}", e);
+        }
+    }
+}
+
+// rolls back the configs of a batch that had errors and drops their
+// responses, the configs that started keep running. returns the failed
+// configs with their errors
+async fn roll_back_failed_configs(
+    attempted: &[Config], errors: &[(Option<i64>, String)], responses: &mut Vec<CustomResponse>,
+    journal: &mut StartJournal,
+) -> Vec<(Config, String)> {
+    let mut failed = Vec::new();
+    for config in attempted {
+        let config_errors: Vec<&str> = errors
+            .iter()
+            .filter(|(config_id, _)| *config_id == config.id)
+            .map(|(_, error)| error.as_str())
+            .collect();
+        if !config_errors.is_empty() {
+            failed.push((config.clone(), config_errors.join("\n")));
+        }
+    }
+    if failed.is_empty() {
+        return failed;
+    }
+
+    let failed_ids: Vec<i64> = failed.iter().filter_map(|(config, _)| config.id).collect();
+    roll_back_start(&failed_ids, journal).await;
+    responses.retain(|response| response.id.is_none_or(|id| !failed_ids.contains(&id)));
+    warn!(
+        "{} of {} config(s) failed to start and were rolled back",
+        failed.len(),
+        attempted.len()
+    );
+    failed
+}
+
+// what the forward points at, pod label configs have no service
+fn forward_target_name(config: &Config) -> String {
+    config
+        .service
+        .clone()
+        .filter(|service| !service.is_empty())
+        .or_else(|| config.target.clone())
+        .unwrap_or_default()
+}
+
+fn failed_start_response(config: &Config, error: String) -> CustomResponse {
+    let message = UserMessage::new(MessageCode::ForwardFailed)
+        .with("service", config.service.clone().unwrap_or_default())
+        .with("error", error);
+    CustomResponse {
+        id: config.id,
+        service: config.service.clone().unwrap_or_default(),
+        namespace: config.namespace.clone(),
+        local_port: 0,
+        remote_port: config.remote_port.unwrap_or_default(),
+        context: config.context.clone(),
+        protocol: config.protocol.clone(),
+        stdout: String::new(),
+        stderr: format_message(&message, DEFAULT_LOCALE),
+        status: 1,
+        firewall_warning: None,
+        messages: vec![message],
+    }
+}
+
+async fn start_port_forward_locked(
+    configs: Vec<Config>, protocol: &str, http_log_state: Arc<HttpLogState>, fail_fast: bool,
+    journal: &mut StartJournal,
 ) -> Result<Vec<CustomResponse>, String> {
     let mut responses = Vec::new();
-    let mut errors = Vec::new();
-    let mut child_handles = Vec::new();
+    // each error with the config it belongs to
+    let mut errors: Vec<(Option<i64>, String)> = Vec::new();
+    let mut attempted = Vec::new();
+
+    for raw_config in configs.iter() {
+        cancel_pending_start(raw_config.id);
+        let config = &match resolve_config_templates(raw_config.clone()).await {
+            Ok(config) => config,
+            Err(e) => {
+                error!("{}", e);
+                errors.push((raw_config.id, e));
+                attempted.push(raw_config.clone());
+                continue;
+            }
+        };
+        attempted.push(config.clone());
 
-    for config in configs.iter() {
         let selector = match config.workload_type.as_deref() {
             Some("pod") => TargetSelector::PodLabel(config.target.clone().unwrap_or_default()),
             _ => TargetSelector::ServiceName(config.service.clone().unwrap_or_default()),
@@ -85,16 +603,109 @@ pub async fn start_port_forward(
 
         let local_address_clone = config.local_address.clone();
 
-        let port_forward_result: Result<PortForward, anyhow::Error> = PortForward::new(
-            target,
-            config.local_port,
-            local_address_clone,
-            context_name,
-            kubeconfig.flatten(),
+        set_access_log_enabled(
             config.id.unwrap_or_default(),
-            config.workload_type.clone().unwrap_or_default(),
-        )
-        .await;
+            config.access_log.unwrap_or_default(),
+        );
+        set_http_store_enabled(
+            config.id.unwrap_or_default(),
+            config.http_store.unwrap_or_default(),
+        );
+        set_query_log_enabled(
+            config.id.unwrap_or_default(),
+            config.query_log.unwrap_or_default(),
+            config.query_log_redact.unwrap_or(true),
+        );
+
+        let settings = current_settings();
+        let mut attempt = 0;
+        let port_forward_result: Result<PortForward, anyhow::Error> = loop {
+            let result = PortForward::new(
+                target.clone(),
+                config.local_port,
+                local_address_clone.clone(),
+                context_name.clone(),
+                kubeconfig.clone().flatten(),
+                config.id.unwrap_or_default(),
+                config.workload_type.clone().unwrap_or_default(),
+                KubeTuning::for_config(config),
+            )
+            .await;
+
+            match result {
+                Err(e) if attempt < settings.start_retry_attempts => {
+                    attempt += 1;
+                    warn!(
+                        "Failed to create PortForward for config {} (attempt {} of {}): {}",
+                        config.id.unwrap_or_default(),
+                        attempt,
+                        settings.start_retry_attempts + 1,
+                        e
+                    );
+                    tokio::time::sleep(std::time::Duration::from_millis(
+                        settings.start_retry_delay_ms,
+                    ))
+                    .await;
+                }
+                result => break result,
+            }
+        }
+        .map(|port_forward| {
+            port_forward
+                .with_protocol_hint(
+                    config
+                        .app_protocol
+                        .as_deref()
+                        .and_then(StreamProtocol::from_name),
+                )
+                .with_transport(
+                    config
+                        .transport
+                        .as_deref()
+                        .and_then(Transport::from_name)
+                        .unwrap_or_default(),
+                )
+                .with_connection_limit(ConnectionLimit::from_config(config))
+        });
+
+        // proxy configs were already checked before their pod was deployed
+        if let Ok(port_forward) = &port_forward_result {
+            if config.rbac_preflight.unwrap_or_default() && !is_proxy_config(config) {
+                let client = port_forward.pod_api.clone().into_client();
+                if let Err(e) = check_rbac(config, &client).await {
+                    error!("{}", e);
+                    errors.push((config.id, e));
+                    continue;
+                }
+            }
+        }
+
+        // a missing target or a service scaled to zero would only fail once a
+        // connection comes in
+        if let Ok(port_forward) = &port_forward_result {
+            if let Some((reason, mut message)) = unstartable_target(config, port_forward).await {
+                let wait = match reason {
+                    PendingReason::Target => config.wait_for_target,
+                    PendingReason::Endpoints => {
+                        config.wait_for_endpoints.or(config.wait_for_target)
+                    }
+                };
+                if wait.unwrap_or_default() {
+                    queue_start(
+                        raw_config.clone(),
+                        reason,
+                        protocol,
+                        port_forward.pod_api.clone(),
+                        port_forward.svc_api.clone(),
+                        http_log_state.clone(),
+                    );
+                    message.push_str(", the forward starts once it's up");
+                }
+                error!("{}", message);
+                errors.push((config.id, message));
+                continue;
+            }
+        }
 
         match port_forward_result {
             Ok(port_forward) => {
@@ -126,25 +737,136 @@ pub async fn start_port_forward(
                         debug!("Port forwarding details: {:?}", port_forward);
                         debug!("Actual local port: {:?}", actual_local_port);
 
-                        let handle_key = format!(
-                            "{}_{}",
+                        let handle_key = child_process_key(
                             config.id.unwrap(),
-                            config.service.clone().unwrap_or_default()
+                            config.service.as_deref().unwrap_or_default(),
                         );
                         CHILD_PROCESSES
                             .lock()
                             .unwrap()
                             .insert(handle_key.clone(), handle);
-                        child_handles.push(handle_key.clone());
+                        journal.record(config.id.unwrap(), StartEffect::Handle(handle_key.clone()));
+
+                        if let Err(e) = set_config_log_route(config) {
+                            warn!("Config {} keeps the app log: {}", config.id.unwrap(), e);
+                        }
+                        if protocol == "tcp" {
+                            register_traffic_split(config, &port_forward);
+                            register_mock_rules(config);
+                            if let Err(e) = register_traffic_script(config) {
+                                warn!(
+                                    "Config {} runs without its traffic script: {}",
+                                    config.id.unwrap(),
+                                    e
+                                );
+                            }
+                            load_openapi_spec(config);
+                        }
+
+                        if protocol == "tcp" {
+                            if let Err(e) = wait_until_ready(config, actual_local_port).await {
+                                let mut error_message = format!(
+                                    "Readiness check failed for {}: {}",
+                                    config.service.clone().unwrap_or_default(),
+                                    e
+                                );
+                                if is_proxy_config(config) {
+                                    if let Some(hint) = proxy_failure_hint(config).await {
+                                        error_message = format!("{}. {}", error_message, hint);
+                                    }
+                                }
+                                if let Some(hint) = mesh_failure_hint(config).await {
+                                    error_message = format!("{}. {}", error_message, hint);
+                                }
+                                log::error!("{}", &error_message);
+                                errors.push((config.id, error_message));
+
+                                if let Some(handle) =
+                                    CHILD_PROCESSES.lock().unwrap().remove(&handle_key)
+                                {
+                                    handle.abort();
+                                }
+                                continue;
+                            }
+                        }
+
+                        if let Err(e) = advertise_forward(config, actual_local_port) {
+                            warn!(
+                                "Failed to advertise config {} over mDNS: {}",
+                                config.id.unwrap_or_default(),
+                                e
+                            );
+                        }
+
+                        if protocol == "tcp" {
+                            if let Err(e) = start_ssh_mirror(config, actual_local_port).await {
+                                warn!(
+                                    "Failed to mirror config {} over ssh: {}",
+                                    config.id.unwrap_or_default(),
+                                    e
+                                );
+                            }
+                        }
+
+                        start_pod_watch(&port_forward);
+                        watch_context(
+                            port_forward.config_id,
+                            config.context.clone(),
+                            config.kubeconfig.clone(),
+                        );
+
+                        if protocol == "tcp" && config.app_protocol.as_deref() == Some("grpc") {
+                            start_grpc_health_monitor(
+                                config.id.unwrap_or_default(),
+                                probe_address(config, actual_local_port),
+                                config.grpc_health_services.clone().unwrap_or_default(),
+                            );
+                        }
 
-                        if config.domain_enabled.unwrap_or_default() {
+                        let mut fan_out_forwards = Vec::new();
+                        if protocol == "tcp" && config.fan_out.unwrap_or_default() {
+                            match start_fan_out(config, &port_forward, http_log_state.clone()).await
+                            {
+                                Ok(forwards) => {
+                                    journal.record(
+                                        config.id.unwrap(),
+                                        StartEffect::HostsEntry(hosts_comment(config)),
+                                    );
+                                    fan_out_forwards = forwards
+                                }
+                                Err(e) => {
+                                    let error_message = format!(
+                                        "Fan-out failed for {}: {}",
+                                        config.service.clone().unwrap_or_default(),
+                                        e
+                                    );
+                                    log::error!("{}", &error_message);
+                                    errors.push((config.id, error_message));
+
+                                    if let Some(handle) =
+                                        CHILD_PROCESSES.lock().unwrap().remove(&handle_key)
+                                    {
+                                        handle.abort();
+                                    }
+                                    continue;
+                                }
+                            }
+                        }
+
+                        if config.domain_enabled.unwrap_or_default()
+                            && settings.hosts_backend == HostsBackend::File
+                        {
                             let hostfile_comment = format!(
                                 "kftray custom host for {} - {}",
                                 config.service.clone().unwrap_or_default(),
                                 config.id.unwrap_or_default()
                             );
 
-                            let mut hosts_builder = HostsBuilder::new(hostfile_comment);
+                            let mut hosts_builder = HostsBuilder::new(hostfile_comment.clone());
+                            journal.record(
+                                config.id.unwrap(),
+                                StartEffect::HostsEntry(hostfile_comment),
+                            );
 
                             if let Some(service_name) = &config.service {
                                 if let Some(local_address) = &config.local_address {
@@ -160,7 +882,7 @@ pub async fn start_port_forward(
                                                     service_name, e
                                                 );
                                                 log::error!("{}", &error_message);
-                                                errors.push(error_message);
+                                                errors.push((config.id, error_message));
 
                                                 if let Some(handle) = CHILD_PROCESSES
                                                     .lock()
@@ -178,7 +900,7 @@ pub async fn start_port_forward(
                                                 local_address
                                             );
                                             log::warn!("{}", &warning_message);
-                                            errors.push(warning_message);
+                                            errors.push((config.id, warning_message));
                                         }
                                     }
                                 }
@@ -189,28 +911,53 @@ pub async fn start_port_forward(
                             id: None,
                             config_id: config.id.unwrap(),
                             is_running: true,
+                            local_port: Some(actual_local_port),
                         };
                         if let Err(e) = update_config_state(&config_state).await {
                             log::error!("Failed to update config state: {}", e);
                         }
+                        remember_running_config(raw_config);
+
+                        // ad-hoc forwards have no configs row for the usage
+                        // foreign key to point at
+                        let config_id = config.id.unwrap();
+                        if !is_adhoc_forward(config_id) {
+                            if let Err(e) = record_config_start(config_id).await {
+                                log::error!("Failed to record config usage: {}", e);
+                            }
+                        }
+
+                        let firewall_warning = check_firewall(
+                            &listen_address(config.local_address.clone()),
+                            actual_local_port,
+                        )
+                        .await;
+
+                        let mut messages = vec![UserMessage::new(MessageCode::ForwardStarted)
+                            .with("protocol", protocol.to_uppercase())
+                            .with("local_port", actual_local_port)
+                            .with("remote_port", config.remote_port.unwrap_or_default())
+                            .with("service", forward_target_name(config))];
+                        messages.extend(fan_out_forwards.iter().map(|forward| {
+                            UserMessage::new(MessageCode::FanOutForwardStarted)
+                                .with("protocol", protocol.to_uppercase())
+                                .with("local_port", forward.local_port)
+                                .with("pod", &forward.pod_name)
+                        }));
 
                         responses.push(CustomResponse {
                             id: config.id,
-                            service: config.service.clone().unwrap(),
+                            service: forward_target_name(config),
                             namespace: namespace.clone(),
                             local_port: actual_local_port,
                             remote_port: config.remote_port.unwrap_or_default(),
                             context: config.context.clone(),
                             protocol: config.protocol.clone(),
-                            stdout: format!(
-                                "{} forwarding from 127.0.0.1:{} -> {:?}:{}",
-                                protocol.to_uppercase(),
-                                actual_local_port,
-                                config.remote_port.unwrap_or_default(),
-                                config.service.clone().unwrap()
-                            ),
+                            stdout: format_messages(&messages, DEFAULT_LOCALE),
                             stderr: String::new(),
                             status: 0,
+                            firewall_warning,
+                            messages,
                         });
                     }
                     Err(e) => {
@@ -226,7 +973,7 @@ pub async fn start_port_forward(
                             e
                         );
                         log::error!("{}", &error_message);
-                        errors.push(error_message);
+                        errors.push((config.id, error_message));
                     }
                 }
             }
@@ -242,18 +989,51 @@ pub async fn start_port_forward(
                     e
                 );
                 log::error!("{}", &error_message);
-                errors.push(error_message);
+                errors.push((config.id, error_message));
             }
         }
     }
 
-    if !errors.is_empty() {
-        for handle_key in child_handles {
-            if let Some(handle) = CHILD_PROCESSES.lock().unwrap().remove(&handle_key) {
-                handle.abort();
-            }
+    if !errors.is_empty() && fail_fast {
+        let config_ids: Vec<i64> = attempted.iter().filter_map(|config| config.id).collect();
+        roll_back_start(&config_ids, journal).await;
+
+        let error_message = errors
+            .iter()
+            .map(|(_, error)| error.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        for config in attempted {
+            spawn_hook(
+                config,
+                HookEvent::OnFailure,
+                None,
+                Some(error_message.clone()),
+            );
+        }
+
+        return Err(error_message);
+    }
+
+    let failed = roll_back_failed_configs(&attempted, &errors, &mut responses, journal).await;
+    for (config, error) in &failed {
+        spawn_hook(
+            config.clone(),
+            HookEvent::OnFailure,
+            None,
+            Some(error.clone()),
+        );
+    }
+
+    for response in &responses {
+        if let Some(config) = attempted.iter().find(|config| config.id == response.id) {
+            spawn_hook(
+                config.clone(),
+                HookEvent::OnStart,
+                Some(response.local_port),
+                None,
+            );
         }
-        return Err(errors.join("\n"));
     }
 
     if !responses.is_empty() {
@@ -263,21 +1043,72 @@ pub async fn start_port_forward(
         );
     }
 
+    responses.extend(
+        failed
+            .into_iter()
+            .map(|(config, error)| failed_start_response(&config, error)),
+    );
     Ok(responses)
 }
 
+// why the target of a service or pod label config can't be forwarded to yet
+async fn unstartable_target(
+    config: &Config, port_forward: &PortForward,
+) -> Option<(PendingReason, String)> {
+    if is_proxy_config(config) {
+        return None;
+    }
+    if target_exists(&port_forward.pod_api, &port_forward.svc_api, config).await == Some(false) {
+        return Some((PendingReason::Target, missing_target_message(config)));
+    }
+
+    let service = config.service.clone().unwrap_or_default();
+    let scaled_to_zero = config.workload_type.as_deref() != Some("pod")
+        && ready_endpoint_count(&port_forward.svc_api, &service).await == Some(0);
+    scaled_to_zero.then(|| (PendingReason::Endpoints, no_endpoints_message(&service)))
+}
+
+async fn resolve_configs_lenient(configs: Vec<Config>) -> Vec<Config> {
+    let mut resolved = Vec::with_capacity(configs.len());
+
+    for config in configs {
+        match resolve_config_templates(config.clone()).await {
+            Ok(config) => resolved.push(config),
+            Err(e) => {
+                warn!("{}", e);
+                resolved.push(config);
+            }
+        }
+    }
+
+    resolved
+}
+
 pub async fn stop_all_port_forward() -> Result<Vec<CustomResponse>, String> {
+    let _guards = lock_all_configs().await;
+
+    let result = stop_all_port_forward_locked().await;
+    if result.is_ok() {
+        for config_id in get_forward_phases().into_keys() {
+            set_phase(config_id, ForwardPhase::Stopped);
+        }
+    }
+    result
+}
+
+async fn stop_all_port_forward_locked() -> Result<Vec<CustomResponse>, String> {
     info!("Attempting to stop all port forwards");
 
     let mut responses = Vec::with_capacity(1024);
     CANCEL_NOTIFIER.notify_waiters();
+    let adhoc_forwards = tear_down_config(None).await;
 
     let handle_map: HashMap<String, JoinHandle<()>> = {
         let mut processes = CHILD_PROCESSES.lock().unwrap();
         processes.drain().collect()
     };
 
-    let running_configs_state = match get_configs_state().await {
+    let mut running_configs_state = match get_configs_state().await {
         Ok(states) => states
             .into_iter()
             .filter(|s| s.is_running)
@@ -290,8 +1121,8 @@ pub async fn stop_all_port_forward() -> Result<Vec<CustomResponse>, String> {
         }
     };
 
-    let configs = match kftray_commons::utils::config::get_configs().await {
-        Ok(configs) => configs,
+    let mut configs = match kftray_commons::utils::config::get_configs().await {
+        Ok(configs) => resolve_configs_lenient(configs).await,
         Err(e) => {
             let error_message = format!("Failed to retrieve configs: {}", e);
             error!("{}", error_message);
@@ -299,27 +1130,50 @@ pub async fn stop_all_port_forward() -> Result<Vec<CustomResponse>, String> {
         }
     };
 
+    // ad-hoc forwards have no stored config or state, they are cleaned up like
+    // any running config
+    running_configs_state.extend(adhoc_forwards.iter().filter_map(|config| config.id));
+    configs.extend(resolve_configs_lenient(adhoc_forwards).await);
+
     let config_map: HashMap<i64, &Config> = configs
         .iter()
         .filter_map(|c| c.id.map(|id| (id, c)))
         .collect();
 
+    let proxy_configs: Vec<&Config> = configs
+        .iter()
+        .filter(|config| running_configs_state.contains(&config.id.unwrap_or_default()))
+        .filter(|config| {
+            config.protocol == "udp" || matches!(config.workload_type.as_deref(), Some("proxy"))
+        })
+        .filter(|config| config.kubeconfig.is_some())
+        .collect();
+
+    // every forward, every proxy pod cleanup and the final state update
+    let progress = Progress::begin(
+        ProgressKind::StopAll,
+        handle_map.len() + proxy_configs.len() + 1,
+        format!("Stopping {} forward(s)", handle_map.len()),
+    );
+    let progress = &progress;
+
     let empty_str = String::new();
 
     let mut abort_handles: FuturesUnordered<_> = handle_map
         .iter()
         .map(|(composite_key, handle)| {
-            let ids: Vec<&str> = composite_key.split('_').collect();
+            let parsed_key = parse_child_process_key(composite_key);
 
             let empty_str_clone = empty_str.clone();
             let config_map_cloned = config_map.clone();
 
             async move {
-                if ids.len() != 2 {
+                let Some((config_id_parsed, service_id)) = parsed_key else {
                     error!(
                         "Invalid composite key format encountered: {}",
                         composite_key
                     );
+                    let message = UserMessage::new(MessageCode::InvalidProcessKey);
                     return CustomResponse {
                         id: None,
                         service: empty_str_clone.clone(),
@@ -329,17 +1183,24 @@ pub async fn stop_all_port_forward() -> Result<Vec<CustomResponse>, String> {
                         context: empty_str_clone.clone(),
                         protocol: empty_str_clone.clone(),
                         stdout: empty_str_clone.clone(),
-                        stderr: String::from("Invalid composite key format"),
+                        stderr: format_message(&message, DEFAULT_LOCALE),
                         status: 1,
+                        firewall_warning: None,
+                        messages: vec![message],
                     };
-                }
+                };
 
-                let config_id_str = ids[0];
-                let service_id = ids[1].to_string();
-                let config_id_parsed = config_id_str.parse::<i64>().unwrap_or_default();
+                let config_id_str = config_id_parsed.to_string();
+                let service_id = service_id.to_string();
                 let config_option = config_map_cloned.get(&config_id_parsed).cloned();
 
                 if let Some(config) = config_option {
+                    if config.fan_out.unwrap_or_default() {
+                        if let Err(e) = remove_fan_out_hosts(config) {
+                            error!("Failed to remove fan-out hosts for {}: {}", service_id, e);
+                        }
+                    }
+
                     if config.domain_enabled.unwrap_or_default() {
                         let hostfile_comment =
                             format!("kftray custom host for {} - {}", service_id, config_id_str);
@@ -347,6 +1208,9 @@ pub async fn stop_all_port_forward() -> Result<Vec<CustomResponse>, String> {
 
                         if let Err(e) = hosts_builder.write() {
                             error!("Failed to write to the hostfile for {}: {}", service_id, e);
+                            let message = UserMessage::new(MessageCode::HostsFileFailed)
+                                .with("service", &service_id)
+                                .with("error", e);
                             return CustomResponse {
                                 id: Some(config_id_parsed),
                                 service: service_id.clone(),
@@ -356,8 +1220,10 @@ pub async fn stop_all_port_forward() -> Result<Vec<CustomResponse>, String> {
                                 context: empty_str_clone.clone(),
                                 protocol: empty_str_clone.clone(),
                                 stdout: empty_str_clone.clone(),
-                                stderr: e.to_string(),
+                                stderr: format_message(&message, DEFAULT_LOCALE),
                                 status: 1,
+                                firewall_warning: None,
+                                messages: vec![message],
                             };
                         }
                     }
@@ -371,6 +1237,11 @@ pub async fn stop_all_port_forward() -> Result<Vec<CustomResponse>, String> {
                 );
                 handle.abort();
 
+                if let Some(config) = config_option {
+                    spawn_hook(config.clone(), HookEvent::OnStop, None, None);
+                }
+
+                let message = UserMessage::new(MessageCode::ForwardStopped);
                 CustomResponse {
                     id: Some(config_id_parsed),
                     service: service_id,
@@ -379,94 +1250,35 @@ pub async fn stop_all_port_forward() -> Result<Vec<CustomResponse>, String> {
                     remote_port: 0,
                     context: empty_str_clone.clone(),
                     protocol: empty_str_clone.clone(),
-                    stdout: String::from("Service port forwarding has been stopped"),
+                    stdout: format_message(&message, DEFAULT_LOCALE),
                     stderr: empty_str_clone,
                     status: 0,
+                    firewall_warning: None,
+                    messages: vec![message],
                 }
             }
         })
         .collect();
 
     while let Some(response) = abort_handles.next().await {
+        progress.advance(format!("Stopped {}", response.service));
         responses.push(response);
     }
 
-    let pod_deletion_tasks: FuturesUnordered<_> = configs
-        .iter()
-        .filter(|config| running_configs_state.contains(&config.id.unwrap_or_default()))
-        .filter(|config| {
-            config.protocol == "udp" || matches!(config.workload_type.as_deref(), Some("proxy"))
-        })
-        .filter_map(|config| {
-            config
-                .kubeconfig
-                .as_ref()
-                .map(|kubeconfig| (config, kubeconfig))
-        })
-        .map(|(config, kubeconfig)| {
-            let config_id_str = config.id.unwrap_or_default();
-            async move {
-                match create_client_with_specific_context(
-                    Some(kubeconfig.clone()),
-                    Some(&config.context),
-                )
-                .await
-                {
-                    Ok((Some(client), _, _)) => {
-                        let pods: Api<Pod> = Api::all(client.clone());
-                        let lp =
-                            ListParams::default().labels(&format!("config_id={}", config_id_str));
-
-                        if let Ok(pod_list) = pods.list(&lp).await {
-                            let username = whoami::username();
-                            let pod_prefix = format!("kftray-forward-{}", username);
-                            let delete_tasks: FuturesUnordered<_> = pod_list
-                                .items
-                                .into_iter()
-                                .filter_map(|pod| {
-                                    if let Some(pod_name) = pod.metadata.name {
-                                        if pod_name.starts_with(&pod_prefix) {
-                                            let namespace = pod
-                                                .metadata
-                                                .namespace
-                                                .unwrap_or_else(|| "default".to_string());
-                                            let pods_in_namespace: Api<Pod> =
-                                                Api::namespaced(client.clone(), &namespace);
-                                            let dp = DeleteParams {
-                                                grace_period_seconds: Some(0),
-                                                ..DeleteParams::default()
-                                            };
-
-                                            return Some(async move {
-                                                match pods_in_namespace.delete(&pod_name, &dp).await
-                                                {
-                                                    Ok(_) => info!(
-                                                        "Successfully deleted pod: {}",
-                                                        pod_name
-                                                    ),
-                                                    Err(e) => error!(
-                                                        "Failed to delete pod {}: {}",
-                                                        pod_name, e
-                                                    ),
-                                                }
-                                            });
-                                        }
-                                    }
-                                    None
-                                })
-                                .collect();
-
-                            delete_tasks.collect::<Vec<_>>().await;
-                        } else {
-                            error!("Error listing pods for config_id {}", config_id_str);
-                        }
-                    }
-                    Ok((None, _, _)) => {
-                        error!("Client not created for kubeconfig: {:?}", kubeconfig)
-                    }
-                    Err(e) => error!("Failed to create Kubernetes client: {}", e),
-                }
+    let pod_deletion_tasks: FuturesUnordered<_> = proxy_configs
+        .into_iter()
+        .map(|config| async move {
+            if let Err(e) = delete_proxy_pods(config).await {
+                error!(
+                    "Failed to delete proxy pods for config_id {}: {}",
+                    config.id.unwrap_or_default(),
+                    e
+                );
             }
+            progress.advance(format!(
+                "Removed proxy pods of config {}",
+                config.id.unwrap_or_default()
+            ));
         })
         .collect();
 
@@ -481,6 +1293,7 @@ pub async fn stop_all_port_forward() -> Result<Vec<CustomResponse>, String> {
                     id: None,
                     config_id: config_id_parsed,
                     is_running: false,
+                    local_port: None,
                 };
                 if let Err(e) = update_config_state(&config_state).await {
                     error!("Failed to update config state: {}", e);
@@ -495,6 +1308,8 @@ pub async fn stop_all_port_forward() -> Result<Vec<CustomResponse>, String> {
         .collect();
 
     update_config_tasks.collect::<Vec<_>>().await;
+    progress.advance("Updated config states");
+    progress.finish(format!("Stopped {} forward(s)", responses.len()));
 
     info!(
         "Port forward stopping process completed with {} responses",
@@ -504,9 +1319,83 @@ pub async fn stop_all_port_forward() -> Result<Vec<CustomResponse>, String> {
     Ok(responses)
 }
 
+// deletes the proxy pods this user deployed from this machine for the config,
+// in any namespace
+pub(crate) async fn delete_proxy_pods(config: &Config) -> Result<Vec<String>, String> {
+    delete_proxy_pods_matching(config, &owned_pods_selector(config.id)).await
+}
+
+pub(crate) async fn delete_proxy_pods_matching(
+    config: &Config, selector: &str,
+) -> Result<Vec<String>, String> {
+    let (client, _, _) = create_client_with_tuning(
+        config.kubeconfig.clone(),
+        Some(&config.context),
+        KubeTuning::for_config(config),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    let client = client.ok_or_else(|| "Client not created".to_string())?;
+
+    let pods: Api<Pod> = Api::all(client.clone());
+    let lp = ListParams::default().labels(selector);
+    let pod_list = pods.list(&lp).await.map_err(|e| e.to_string())?;
+
+    let delete_tasks: FuturesUnordered<_> = pod_list
+        .items
+        .into_iter()
+        .filter_map(|pod| {
+            let pod_name = pod.metadata.name?;
+            let namespace = pod
+                .metadata
+                .namespace
+                .unwrap_or_else(|| "default".to_string());
+            let pods_in_namespace: Api<Pod> = Api::namespaced(client.clone(), &namespace);
+            let dp = DeleteParams {
+                grace_period_seconds: Some(0),
+                ..DeleteParams::default()
+            };
+
+            Some(async move {
+                match pods_in_namespace.delete(&pod_name, &dp).await {
+                    Ok(_) => {
+                        info!("Successfully deleted pod: {}", pod_name);
+                        Some(pod_name)
+                    }
+                    Err(e) => {
+                        error!("Failed to delete pod {}: {}", pod_name, e);
+                        None
+                    }
+                }
+            })
+        })
+        .collect();
+
+    Ok(delete_tasks
+        .filter_map(|deleted| async move { deleted })
+        .collect()
+        .await)
+}
+
 pub async fn stop_port_forward(config_id: String) -> Result<CustomResponse, String> {
+    let Ok(id) = config_id.parse::<i64>() else {
+        return stop_port_forward_locked(config_id).await;
+    };
+
+    let _guards = lock_configs([id]).await;
+    transition(id, ForwardPhase::Stopping)?;
+
+    let result = stop_port_forward_locked(config_id).await;
+    finish_phase(&[id], &result, ForwardPhase::Stopped);
+    result
+}
+
+async fn stop_port_forward_locked(config_id: String) -> Result<CustomResponse, String> {
     let cancellation_notifier = CANCEL_NOTIFIER.clone();
     cancellation_notifier.notify_waiters();
+    if let Ok(config_id) = config_id.parse::<i64>() {
+        tear_down_config(Some(config_id)).await;
+    }
 
     let composite_key = {
         let child_processes = CHILD_PROCESSES.lock().unwrap();
@@ -528,8 +1417,9 @@ pub async fn stop_port_forward(config_id: String) -> Result<CustomResponse, Stri
             join_handle.abort();
         }
 
-        let (config_id_str, service_name) = composite_key.split_once('_').unwrap_or(("", ""));
-        let config_id_parsed = config_id_str.parse::<i64>().unwrap_or_default();
+        let (config_id_parsed, service_name) =
+            parse_child_process_key(&composite_key).unwrap_or_default();
+        let config_id_str = config_id_parsed.to_string();
 
         match kftray_commons::config::get_configs().await {
             Ok(configs) => {
@@ -537,6 +1427,16 @@ pub async fn stop_port_forward(config_id: String) -> Result<CustomResponse, Stri
                     .iter()
                     .find(|c| c.id.map_or(false, |id| id == config_id_parsed))
                 {
+                    if config.fan_out.unwrap_or_default() {
+                        if let Err(e) = remove_fan_out_hosts(config) {
+                            log::error!(
+                                "Failed to remove fan-out hosts for {}: {}",
+                                service_name,
+                                e
+                            );
+                        }
+                    }
+
                     if config.domain_enabled.unwrap_or_default() {
                         let hostfile_comment = format!(
                             "kftray custom host for {} - {}",
@@ -556,6 +1456,7 @@ pub async fn stop_port_forward(config_id: String) -> Result<CustomResponse, Stri
                                 id: None,
                                 config_id: config_id_parsed,
                                 is_running: false,
+                                local_port: None,
                             };
                             if let Err(e) = update_config_state(&config_state).await {
                                 log::error!("Failed to update config state: {}", e);
@@ -563,6 +1464,7 @@ pub async fn stop_port_forward(config_id: String) -> Result<CustomResponse, Stri
                             return Err(e.to_string());
                         }
                     }
+                    spawn_hook(config.clone(), HookEvent::OnStop, None, None);
                 } else {
                     log::warn!("Config with id '{}' not found.", config_id_str);
                 }
@@ -571,11 +1473,13 @@ pub async fn stop_port_forward(config_id: String) -> Result<CustomResponse, Stri
                     id: None,
                     config_id: config_id_parsed,
                     is_running: false,
+                    local_port: None,
                 };
                 if let Err(e) = update_config_state(&config_state).await {
                     log::error!("Failed to update config state: {}", e);
                 }
 
+                let message = UserMessage::new(MessageCode::ForwardStopped);
                 Ok(CustomResponse {
                     id: None,
                     service: service_name.to_string(),
@@ -584,9 +1488,11 @@ pub async fn stop_port_forward(config_id: String) -> Result<CustomResponse, Stri
                     remote_port: 0,
                     context: String::new(),
                     protocol: String::new(),
-                    stdout: String::from("Service port forwarding has been stopped"),
+                    stdout: format_message(&message, DEFAULT_LOCALE),
                     stderr: String::new(),
                     status: 0,
+                    firewall_warning: None,
+                    messages: vec![message],
                 })
             }
             Err(e) => {
@@ -595,6 +1501,7 @@ pub async fn stop_port_forward(config_id: String) -> Result<CustomResponse, Stri
                     id: None,
                     config_id: config_id_parsed,
                     is_running: false,
+                    local_port: None,
                 };
                 if let Err(e) = update_config_state(&config_state).await {
                     log::error!("Failed to update config state: {}", e);
@@ -608,6 +1515,7 @@ pub async fn stop_port_forward(config_id: String) -> Result<CustomResponse, Stri
             id: None,
             config_id: config_id_parsed,
             is_running: false,
+            local_port: None,
         };
         if let Err(e) = update_config_state(&config_state).await {
             log::error!("Failed to update config state: {}", e);
@@ -630,158 +1538,444 @@ fn render_json_template(template: &str, values: &HashMap<&str, String>) -> Strin
 }
 pub async fn deploy_and_forward_pod(
     configs: Vec<Config>, http_log_state: Arc<HttpLogState>,
+) -> Result<Vec<CustomResponse>, String> {
+    deploy_and_forward_pod_with_cancel(
+        configs,
+        http_log_state,
+        CancelToken::new(),
+        StartOptions::default(),
+    )
+    .await
+}
+
+// a cancelled deployment also removes the proxy pods it already created. a
+// config that is already running answers with its forward, unless forced.
+// without fail_fast a config that fails takes only its own pod down
+pub async fn deploy_and_forward_pod_with_cancel(
+    configs: Vec<Config>, http_log_state: Arc<HttpLogState>, cancel: CancelToken,
+    options: StartOptions,
+) -> Result<Vec<CustomResponse>, String> {
+    ensure_workloads_allowed(&configs).await?;
+    let config_ids: Vec<i64> = configs.iter().filter_map(|config| config.id).collect();
+    let Some(_guards) = cancel.run(lock_configs(config_ids.clone())).await else {
+        return Err(cancelled_start(&config_ids));
+    };
+    let (configs, running) = settle_running_configs(configs, options.force, true).await;
+    if configs.is_empty() {
+        return Ok(running);
+    }
+    let config_ids = begin_start(&configs)?;
+    let _registration = register_start(&config_ids, &cancel);
+
+    match cancel
+        .run(deploy_and_forward_pod_locked(
+            configs.clone(),
+            http_log_state,
+            options.fail_fast,
+        ))
+        .await
+    {
+        Some(result) => {
+            finish_start_phases(&config_ids, &result);
+            result.map(|mut responses| {
+                responses.extend(running);
+                responses
+            })
+        }
+        None => {
+            undo_cancelled_start(configs, true).await;
+            Err(cancelled_start(&config_ids))
+        }
+    }
+}
+
+async fn deploy_and_forward_pod_locked(
+    configs: Vec<Config>, http_log_state: Arc<HttpLogState>, fail_fast: bool,
 ) -> Result<Vec<CustomResponse>, String> {
     let mut responses: Vec<CustomResponse> = Vec::new();
+    // deploying the pod, waiting for it to run and forwarding to it, per config
+    let progress = Progress::begin(
+        ProgressKind::ProxyDeploy,
+        configs.len() * 3,
+        format!("Deploying {} proxy pod(s)", configs.len()),
+    );
 
-    for mut config in configs.into_iter() {
-        let context_name = Some(config.context.as_str());
-        let kubeconfig_clone = config.kubeconfig.clone();
-        let (client, _, _) = create_client_with_specific_context(kubeconfig_clone, context_name)
+    let mut journal = StartJournal::default();
+    if fail_fast {
+        // a config that fails takes the pods and forwards of the ones deployed
+        // before it down with it
+        if let Err(e) = deploy_proxy_forwards(
+            configs,
+            http_log_state,
+            &progress,
+            &mut journal,
+            &mut responses,
+        )
+        .await
+        {
+            let config_ids = journal.config_ids();
+            roll_back_start(&config_ids, &mut journal).await;
+            return Err(e);
+        }
+    } else {
+        for config in configs {
+            if let Err(e) = deploy_proxy_forwards(
+                vec![config.clone()],
+                http_log_state.clone(),
+                &progress,
+                &mut journal,
+                &mut responses,
+            )
             .await
-            .map_err(|e| {
-                log::error!("Failed to create Kubernetes client: {}", e);
-                e.to_string()
-            })?;
-
-        let client = client.ok_or_else(|| "Client not created".to_string())?;
-
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_err(|e| e.to_string())?
-            .as_secs();
-
-        let random_string: String = rand::thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(6)
-            .map(char::from)
-            .map(|c| c.to_ascii_lowercase())
-            .collect();
+            {
+                if let Some(config_id) = config.id {
+                    roll_back_start(&[config_id], &mut journal).await;
+                }
+                responses.push(failed_start_response(&config, e));
+            }
+        }
+    }
 
-        let username = whoami::username().to_lowercase();
-        let clean_username: String = username.chars().filter(|c| c.is_alphanumeric()).collect();
+    let deployed = responses
+        .iter()
+        .filter(|response| response.status == 0)
+        .count();
+    progress.finish(format!("Deployed {} proxy pod(s)", deployed));
+    Ok(responses)
+}
 
-        info!("Cleaned username: {}", clean_username);
+async fn proxy_client(config: &Config) -> Result<kube::Client, String> {
+    let context_name = Some(config.context.as_str());
+    let tuning = KubeTuning::for_config(config);
+    let (client, _, _) = create_client_with_tuning(config.kubeconfig.clone(), context_name, tuning)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to create Kubernetes client: {}", e);
+            e.to_string()
+        })?;
 
-        let protocol = config.protocol.to_string().to_lowercase();
+    client.ok_or_else(|| "Client not created".to_string())
+}
 
-        let hashed_name = format!(
-            "kftray-forward-{}-{}-{}-{}",
-            clean_username, protocol, timestamp, random_string
-        )
-        .to_lowercase();
+fn proxy_pod_name(protocol: &str) -> Result<String, String> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?
+        .as_secs();
+
+    let random_string: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(6)
+        .map(char::from)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
 
-        let config_id_str = config
-            .id
-            .map_or_else(|| "default".into(), |id| id.to_string());
+    let username = whoami::username().to_lowercase();
+    let clean_username: String = username.chars().filter(|c| c.is_alphanumeric()).collect();
 
-        if config
-            .remote_address
-            .as_ref()
-            .map_or(true, String::is_empty)
-        {
-            config.remote_address.clone_from(&config.service)
-        }
+    info!("Cleaned username: {}", clean_username);
+
+    Ok(format!(
+        "kftray-forward-{}-{}-{}-{}",
+        clean_username, protocol, timestamp, random_string
+    )
+    .to_lowercase())
+}
+
+// renders the relay pod of the config from the proxy manifest
+fn render_proxy_pod(config: &Config, hashed_name: &str, config_label: &str) -> Result<Pod, String> {
+    let mut values: HashMap<&str, String> = HashMap::new();
+    values.insert("hashed_name", hashed_name.to_string());
+    values.insert("config_id", config_label.to_string());
+    values.insert("service_name", config.service.clone().unwrap_or_default());
+    values.insert(
+        "remote_address",
+        config.remote_address.clone().unwrap_or_default(),
+    );
+    let remote_port = config.remote_port.unwrap_or_default().to_string();
+    values.insert("remote_port", remote_port.clone());
+    values.insert("local_port", remote_port);
+    values.insert("protocol", config.protocol.to_lowercase());
+
+    let manifest_path = get_pod_manifest_path().map_err(|e| e.to_string())?;
+    let mut file = File::open(manifest_path).map_err(|e| e.to_string())?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)
+        .map_err(|e| e.to_string())?;
+
+    let rendered_json = render_json_template(&contents, &values);
+    let mut pod: Pod = serde_json::from_str(&rendered_json).map_err(|e| e.to_string())?;
+    label_owned_pod(&mut pod);
+    Ok(pod)
+}
 
-        let mut values: HashMap<&str, String> = HashMap::new();
-        values.insert("hashed_name", hashed_name.clone());
-        values.insert("config_id", config_id_str);
-        values.insert("service_name", config.service.as_ref().unwrap().clone());
-        values.insert(
-            "remote_address",
-            config.remote_address.as_ref().unwrap().clone(),
+// creates the relay pod of the config and waits for it to run
+async fn deploy_proxy_pod(
+    config: &Config, pods: &Api<Pod>, hashed_name: &str, journal: &mut StartJournal,
+) -> Result<(), String> {
+    let config_label = config
+        .id
+        .map_or_else(|| "default".into(), |id| id.to_string());
+    let mut pod = render_proxy_pod(config, hashed_name, &config_label)?;
+    // a target that only takes mutual tls is reached through the mesh proxy
+    if let Some(mesh) = required_proxy_mesh(&pods.clone().into_client(), config).await {
+        info!(
+            "Joining the proxy pod of config {} to {}",
+            config.id.unwrap_or_default(),
+            mesh.name()
         );
-        values.insert("remote_port", config.remote_port.expect("None").to_string());
-        values.insert("local_port", config.remote_port.expect("None").to_string());
-        values.insert("protocol", protocol.clone());
+        inject_mesh_proxy(&mut pod, mesh);
+    }
+
+    pods.create(&kube::api::PostParams::default(), &pod)
+        .await
+        .map_err(|e| e.to_string())?;
+    journal.record(
+        config.id.unwrap_or_default(),
+        StartEffect::ProxyPod {
+            pods: pods.clone(),
+            name: hashed_name.to_string(),
+        },
+    );
+
+    kube_runtime::wait::await_condition(pods.clone(), hashed_name, conditions::is_pod_running())
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// the pool key of the relay pods of the config, none when its manifest can't
+// be rendered
+fn warm_key(config: &Config) -> Option<WarmKey> {
+    let pod = render_proxy_pod(config, "", WARM_CONFIG_LABEL).ok()?;
+    WarmKey::for_pod(&config.context, &config.namespace, &pod)
+}
+
+// a warm pod of the config's context, namespace and image, started as the
+// relay of its target. a target that needs the mesh proxy gets a pod of its own
+async fn take_warm_pod_for(config: &Config, pods: &Api<Pod>) -> Option<String> {
+    let key = warm_key(config)?;
+    if !has_warm_pod(&key) {
+        return None;
+    }
+    if required_proxy_mesh(&pods.clone().into_client(), config)
+        .await
+        .is_some()
+    {
+        return None;
+    }
+    take_warm_pod(&key, &RelayTarget::for_config(config), config.id).await
+}
+
+// deploys a relay pod that waits for a target and parks it, unless the key of
+// the config has one already or its context is read-only
+async fn park_new_warm_pod(config: &Config, pods: &Api<Pod>) -> Result<bool, String> {
+    let Some(key) = warm_key(config) else {
+        return Ok(false);
+    };
+    if has_warm_pod(&key) {
+        return Ok(false);
+    }
+    if let Err(e) = ensure_workloads_allowed(std::slice::from_ref(config)).await {
+        debug!("Not keeping a warm proxy pod: {}", e);
+        return Ok(false);
+    }
 
-        let manifest_path = get_pod_manifest_path().map_err(|e| e.to_string())?;
-        let mut file = File::open(manifest_path).map_err(|e| e.to_string())?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)
-            .map_err(|e| e.to_string())?;
+    let hashed_name = proxy_pod_name(WARM_CONFIG_LABEL)?;
+    let mut pod = render_proxy_pod(config, &hashed_name, WARM_CONFIG_LABEL)?;
+    make_warm_pod(&mut pod)?;
+    let container = pod
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.containers.first())
+        .map(|container| container.name.clone())
+        .unwrap_or_default();
+
+    pods.create(&kube::api::PostParams::default(), &pod)
+        .await
+        .map_err(|e| e.to_string())?;
+    let running = kube_runtime::wait::await_condition(
+        pods.clone(),
+        &hashed_name,
+        conditions::is_pod_running(),
+    )
+    .await;
+    if running.is_err() || !park_warm_pod(key, pods.clone(), hashed_name.clone(), container).await {
+        let params = DeleteParams {
+            grace_period_seconds: Some(0),
+            ..DeleteParams::default()
+        };
+        if let Err(e) = pods.delete(&hashed_name, &params).await {
+            warn!("Failed to delete warm proxy pod {}: {}", hashed_name, e);
+        }
+        return running.map(|_| false).map_err(|e| e.to_string());
+    }
+    Ok(true)
+}
+
+fn with_remote_address(mut config: Config) -> Config {
+    if config.remote_address.as_ref().is_none_or(String::is_empty) {
+        config.remote_address.clone_from(&config.service)
+    }
+    config
+}
 
-        let rendered_json = render_json_template(&contents, &values);
-        let pod: Pod = serde_json::from_str(&rendered_json).map_err(|e| e.to_string())?;
+async fn deploy_proxy_forwards(
+    configs: Vec<Config>, http_log_state: Arc<HttpLogState>, progress: &Progress,
+    journal: &mut StartJournal, responses: &mut Vec<CustomResponse>,
+) -> Result<(), String> {
+    for raw_config in configs.into_iter() {
+        let mut config = with_remote_address(resolve_config_templates(raw_config.clone()).await?);
+        let client = proxy_client(&config).await?;
 
+        if config.rbac_preflight.unwrap_or_default() {
+            check_rbac(&config, &client).await?;
+        }
+
+        let protocol = config.protocol.to_string().to_lowercase();
         let pods: Api<Pod> = Api::namespaced(client.clone(), &config.namespace);
 
-        match pods.create(&kube::api::PostParams::default(), &pod).await {
-            Ok(_) => {
-                if let Err(e) = kube_runtime::wait::await_condition(
-                    pods.clone(),
-                    &hashed_name,
-                    conditions::is_pod_running(),
+        // a pod parked in the same context and namespace is already running
+        let hashed_name = match take_warm_pod_for(&config, &pods).await {
+            Some(hashed_name) => {
+                progress.advance(format!("Reusing warm proxy pod {}", hashed_name));
+                journal.record(
+                    config.id.unwrap_or_default(),
+                    StartEffect::ProxyPod {
+                        pods: pods.clone(),
+                        name: hashed_name.clone(),
+                    },
+                );
+                hashed_name
+            }
+            None => {
+                let hashed_name = proxy_pod_name(&protocol)?;
+                progress.advance(format!("Deploying proxy pod {}", hashed_name));
+                deploy_proxy_pod(&config, &pods, &hashed_name, journal).await?;
+                hashed_name
+            }
+        };
+
+        progress.advance(format!("Proxy pod {} is running", hashed_name));
+        config.service = Some(hashed_name.clone());
+
+        let start_response = match protocol.as_str() {
+            "udp" => {
+                start_port_forward_locked(
+                    vec![config.clone()],
+                    "udp",
+                    http_log_state.clone(),
+                    true,
+                    journal,
                 )
                 .await
-                {
-                    let dp = DeleteParams {
-                        grace_period_seconds: Some(0),
-                        ..DeleteParams::default()
-                    };
-                    let _ = pods.delete(&hashed_name, &dp).await;
-                    return Err(e.to_string());
-                }
+            }
+            "tcp" => {
+                start_port_forward_locked(
+                    vec![config.clone()],
+                    "tcp",
+                    http_log_state.clone(),
+                    true,
+                    journal,
+                )
+                .await
+            }
+            _ => return Err("Unsupported proxy type".to_string()),
+        };
 
-                config.service = Some(hashed_name.clone());
+        match start_response {
+            Ok(mut port_forward_responses) => {
+                let response = port_forward_responses
+                    .pop()
+                    .ok_or("No response received from port forwarding")?;
+                responses.push(response);
+                progress.advance(format!("Forwarding through {}", hashed_name));
+                // the forward itself was started for the proxy pod,
+                // keep what the user actually configured
+                remember_running_config(&raw_config);
+            }
+            Err(e) => return Err(format!("Failed to start port forwarding {}", e)),
+        }
+    }
 
-                let start_response = match protocol.as_str() {
-                    "udp" => {
-                        start_port_forward(vec![config.clone()], "udp", http_log_state.clone())
-                            .await
-                    }
-                    "tcp" => {
-                        start_port_forward(vec![config.clone()], "tcp", http_log_state.clone())
-                            .await
-                    }
-                    _ => {
-                        let _ = pods
-                            .delete(&hashed_name, &kube::api::DeleteParams::default())
-                            .await;
-                        return Err("Unsupported proxy type".to_string());
-                    }
-                };
+    Ok(())
+}
 
-                match start_response {
-                    Ok(mut port_forward_responses) => {
-                        let response = port_forward_responses
-                            .pop()
-                            .ok_or("No response received from port forwarding")?;
-                        responses.push(response);
-                    }
-                    Err(e) => {
-                        let _ = pods
-                            .delete(&hashed_name, &kube::api::DeleteParams::default())
-                            .await;
-                        return Err(format!("Failed to start port forwarding {}", e));
-                    }
-                }
-            }
-            Err(e) => return Err(e.to_string()),
+// parks a relay pod for each context, namespace and image of the proxy
+// configs, so the first start of each doesn't wait for its pod to schedule.
+// read-only contexts are skipped
+pub async fn prewarm_proxy_pods(configs: Vec<Config>) -> Result<usize, String> {
+    if warm_pool_ttl().is_none() {
+        return Err("The proxy warm pool is off, set proxy_warm_pool_ttl_secs".to_string());
+    }
+
+    let mut warmed = 0;
+    for raw_config in configs.into_iter().filter(is_proxy_config) {
+        let config_id = raw_config.id.unwrap_or_default();
+        if running_config(config_id).is_some() {
+            continue;
+        }
+
+        let config = with_remote_address(resolve_config_templates(raw_config).await?);
+        let pods: Api<Pod> = Api::namespaced(proxy_client(&config).await?, &config.namespace);
+        match park_new_warm_pod(&config, &pods).await {
+            Ok(true) => warmed += 1,
+            Ok(false) => {}
+            Err(e) => warn!(
+                "Failed to prewarm a proxy pod for config {}: {}",
+                config_id, e
+            ),
         }
     }
 
-    Ok(responses)
+    Ok(warmed)
 }
 
 pub async fn stop_proxy_forward(
     config_id: i64, namespace: &str, service_name: String,
+) -> Result<CustomResponse, String> {
+    let _guards = lock_configs([config_id]).await;
+    transition(config_id, ForwardPhase::Stopping)?;
+
+    let result = stop_proxy_forward_locked(config_id, namespace, service_name).await;
+    finish_phase(&[config_id], &result, ForwardPhase::Stopped);
+    result
+}
+
+async fn stop_proxy_forward_locked(
+    config_id: i64, namespace: &str, service_name: String,
 ) -> Result<CustomResponse, String> {
     info!(
         "Attempting to stop proxy forward for service: {}",
         service_name
     );
 
-    let config = get_config(config_id).await.map_err(|e| {
-        error!("Failed to get config: {}", e);
-        e.to_string()
-    })?;
+    // a forward started with overrides has its pod where it actually runs, not
+    // where the stored config points
+    let running = running_config(config_id);
+    let is_running = running.is_some();
+    let config = match running {
+        Some(config) => config,
+        None => get_config(config_id).await.map_err(|e| {
+            error!("Failed to get config: {}", e);
+            e.to_string()
+        })?,
+    };
+    let config = resolve_config_templates(config).await?;
+    let namespace = if is_running || has_placeholders(namespace) {
+        config.namespace.as_str()
+    } else {
+        namespace
+    };
 
+    let tuning = KubeTuning::for_config(&config);
     let kubeconfig = config
         .kubeconfig
+        .clone()
         .ok_or_else(|| "Kubeconfig not found".to_string())?;
     let context_name = &config.context;
 
-    let (client, _, _) = create_client_with_specific_context(Some(kubeconfig), Some(context_name))
+    let (client, _, _) = create_client_with_tuning(Some(kubeconfig), Some(context_name), tuning)
         .await
         .map_err(|e| {
             error!("Failed to create Kubernetes client: {}", e);
@@ -792,48 +1986,54 @@ pub async fn stop_proxy_forward(
 
     let pods: Api<Pod> = Api::namespaced(client, namespace);
 
-    let lp = ListParams::default().labels(&format!("config_id={}", config_id));
+    let selector = owned_pods_selector(Some(config_id));
+    let lp = ListParams::default().labels(&selector);
 
     let pod_list = pods.list(&lp).await.map_err(|e| {
         error!("Error listing pods: {}", e);
         e.to_string()
     })?;
 
-    let username = whoami::username();
-
-    let pod_prefix = format!("kftray-forward-{}", username);
+    debug!("Looking for pods matching: {}", selector);
 
-    debug!("Looking for pods with prefix: {}", pod_prefix);
+    for pod_name in pod_list
+        .items
+        .into_iter()
+        .filter_map(|pod| pod.metadata.name)
+    {
+        info!("Found pod to stop: {}", pod_name);
 
-    for pod in pod_list.items {
-        if let Some(pod_name) = pod.metadata.name {
-            if pod_name.starts_with(&pod_prefix) {
-                info!("Found pod to stop: {}", pod_name);
-
-                let delete_options = DeleteParams {
-                    grace_period_seconds: Some(0),
-                    propagation_policy: Some(kube::api::PropagationPolicy::Background),
-                    ..Default::default()
-                };
-
-                match pods.delete(&pod_name, &delete_options).await {
-                    Ok(_) => info!("Successfully deleted pod: {}", pod_name),
-                    Err(e) => {
-                        error!("Failed to delete pod: {} with error: {}", pod_name, e);
-                        return Err(e.to_string());
-                    }
-                }
+        let delete_options = DeleteParams {
+            grace_period_seconds: Some(0),
+            propagation_policy: Some(kube::api::PropagationPolicy::Background),
+            ..Default::default()
+        };
 
-                break;
-            } else {
-                info!("Pod {} does not match prefix, skipping", pod_name);
+        match pods.delete(&pod_name, &delete_options).await {
+            Ok(_) => info!("Successfully deleted pod: {}", pod_name),
+            Err(e) => {
+                error!("Failed to delete pod: {} with error: {}", pod_name, e);
+                return Err(e.to_string());
             }
         }
     }
 
+    // the deleted pod relayed to this config's target only, the pool gets a
+    // fresh pod for the next proxy start in the namespace instead
+    if warm_pool_ttl().is_some() {
+        let mut config = with_remote_address(config.clone());
+        config.namespace = namespace.to_string();
+        let pods = pods.clone();
+        tokio::spawn(async move {
+            if let Err(e) = park_new_warm_pod(&config, &pods).await {
+                warn!("Failed to refill the proxy warm pool: {}", e);
+            }
+        });
+    }
+
     info!("Stopping port forward for service: {}", service_name);
 
-    let stop_result = stop_port_forward(config_id.to_string())
+    let stop_result = stop_port_forward_locked(config_id.to_string())
         .await
         .map_err(|e| {
             error!(
@@ -850,6 +2050,22 @@ pub async fn stop_proxy_forward(
 
 pub async fn retrieve_service_configs(
     context: &str, kubeconfig: Option<String>,
+) -> Result<Vec<Config>, String> {
+    retrieve_service_configs_with_cancel(context, kubeconfig, &CancelToken::new()).await
+}
+
+// discovery only reads from the cluster, so cancelling leaves nothing behind
+pub async fn retrieve_service_configs_with_cancel(
+    context: &str, kubeconfig: Option<String>, cancel: &CancelToken,
+) -> Result<Vec<Config>, String> {
+    cancel
+        .run(retrieve_service_configs_uncancelled(context, kubeconfig))
+        .await
+        .ok_or_else(|| format!("Discovery in context {} was cancelled", context))?
+}
+
+async fn retrieve_service_configs_uncancelled(
+    context: &str, kubeconfig: Option<String>,
 ) -> Result<Vec<Config>, String> {
     let (client_opt, _, _) = create_client_with_specific_context(kubeconfig.clone(), Some(context))
         .await
@@ -863,8 +2079,14 @@ pub async fn retrieve_service_configs(
         .map_err(|e| e.to_string())?;
 
     let concurrency_limit = 10;
+    let progress = Progress::begin(
+        ProgressKind::Discovery,
+        namespaces.len(),
+        format!("Scanning {} namespaces in {}", namespaces.len(), context),
+    );
+    let progress = &progress;
 
-    stream::iter(namespaces)
+    let mut configs = stream::iter(namespaces)
         .map(|namespace| {
             let client = client.clone();
             let context = context.to_string();
@@ -872,9 +2094,9 @@ pub async fn retrieve_service_configs(
             let annotation = annotation.to_string();
             async move {
                 let services =
-                    get_services_with_annotation(client.clone(), &namespace, &annotation)
-                        .await
-                        .map_err(|e| e.to_string())?;
+                    get_services_with_annotation(client.clone(), &namespace, &annotation).await;
+                progress.advance(namespace.clone());
+                let services = services.map_err(|e| e.to_string())?;
 
                 let mut namespace_configs = Vec::new();
 
@@ -917,7 +2139,24 @@ pub async fn retrieve_service_configs(
                 acc
             },
         )
-        .await
+        .await?;
+
+    // discovered configs only carry the global defaults, the context's win
+    if let Some(defaults) = get_context_settings(context).await? {
+        let mut existing = read_configs().await?;
+        for config in &mut configs {
+            if defaults.default_protocol.is_some() {
+                config.protocol.clear();
+            }
+            if defaults.local_address_range.is_some() {
+                config.local_address = None;
+            }
+            apply_context_defaults(config, &defaults, &existing);
+            existing.push(config.clone());
+        }
+    }
+
+    Ok(configs)
 }
 
 fn parse_configs(
@@ -948,7 +2187,7 @@ fn parse_configs(
                 alias: Some(alias),
                 local_port: Some(local_port),
                 remote_port: Some(target_port as u16),
-                protocol: "tcp".to_string(),
+                protocol: current_settings().default_protocol,
                 workload_type: Some("service".to_string()),
                 ..Default::default()
             })
@@ -971,9 +2210,106 @@ fn create_default_configs(
             alias: Some(service_name.to_string()),
             local_port: Some(port as u16),
             remote_port: Some(port as u16),
-            protocol: "tcp".to_string(),
+            protocol: current_settings().default_protocol,
             workload_type: Some("service".to_string()),
             ..Default::default()
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lifecycle::get_forward_phase;
+
+    fn test_config(config_id: i64) -> Config {
+        Config {
+            id: Some(config_id),
+            service: Some("api".to_string()),
+            local_port: Some(8080),
+            ..Default::default()
+        }
+    }
+
+    fn spawn_forward(config_id: i64) -> String {
+        let key = child_process_key(config_id, "api");
+        let handle = tokio::spawn(std::future::pending::<()>());
+        CHILD_PROCESSES.lock().unwrap().insert(key.clone(), handle);
+        key
+    }
+
+    #[tokio::test]
+    async fn test_partial_failure_keeps_the_started_configs() {
+        let (started, broken) = (test_config(9_198), test_config(9_199));
+        let mut journal = StartJournal::default();
+        journal.record(9_198, StartEffect::Handle(spawn_forward(9_198)));
+        journal.record(9_199, StartEffect::Handle(spawn_forward(9_199)));
+        let mut responses: Vec<CustomResponse> = [&started, &broken]
+            .into_iter()
+            .map(|config| CustomResponse {
+                status: 0,
+                ..failed_start_response(config, String::new())
+            })
+            .collect();
+        let errors = vec![(broken.id, "no ready endpoints".to_string())];
+
+        let failed = roll_back_failed_configs(
+            &[started.clone(), broken.clone()],
+            &errors,
+            &mut responses,
+            &mut journal,
+        )
+        .await;
+
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0.id, broken.id);
+        assert_eq!(failed[0].1, "no ready endpoints");
+        assert_eq!(
+            responses
+                .iter()
+                .map(|response| response.id)
+                .collect::<Vec<_>>(),
+            vec![started.id]
+        );
+        assert_eq!(forward_liveness(9_198), Some(true));
+        assert_eq!(forward_liveness(9_199), None);
+        assert_eq!(journal.config_ids(), vec![9_198]);
+
+        responses.push(failed_start_response(&broken, failed[0].1.clone()));
+        finish_start_phases(&[9_198, 9_199], &Ok(responses));
+        assert_eq!(get_forward_phase(9_198), ForwardPhase::Running);
+        assert_eq!(get_forward_phase(9_199), ForwardPhase::Failed);
+
+        journal.undo_all().await;
+        assert_eq!(forward_liveness(9_198), None);
+    }
+
+    #[tokio::test]
+    async fn test_start_of_a_running_config_keeps_its_forward() {
+        let key = spawn_forward(9_200);
+        let configs = vec![test_config(9_200), test_config(9_201)];
+
+        for _ in 0..2 {
+            let (to_start, responses) = settle_running_configs(configs.clone(), false, false).await;
+            assert_eq!(
+                to_start.iter().map(|config| config.id).collect::<Vec<_>>(),
+                vec![Some(9_201)]
+            );
+            assert_eq!(responses.len(), 1);
+            assert_eq!(responses[0].id, Some(9_200));
+            assert_eq!(responses[0].status, 0);
+            assert_eq!(
+                responses[0].messages[0].code,
+                MessageCode::ForwardAlreadyRunning
+            );
+        }
+        assert_eq!(forward_liveness(9_200), Some(true));
+
+        CHILD_PROCESSES
+            .lock()
+            .unwrap()
+            .remove(&key)
+            .unwrap()
+            .abort();
+    }
+}
@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use kftray_commons::config::{
+    get_config,
+    get_configs,
+};
+use kftray_commons::config_state::{
+    bound_local_port,
+    get_configs_state,
+};
+use log::{
+    debug,
+    info,
+};
+use zbus::{
+    connection,
+    fdo,
+    interface,
+    SignalContext,
+};
+
+use crate::audit::start_with_reason;
+use crate::core::stop_all_port_forward;
+use crate::headless::{
+    start_config,
+    stop_config,
+};
+use crate::lifecycle::get_forward_phase;
+use crate::models::kube::HttpLogState;
+
+pub const DBUS_NAME: &str = "org.kftray.Forwards";
+pub const DBUS_PATH: &str = "/org/kftray/Forwards";
+
+// (config id, alias, context/namespace/service, local port, phase)
+pub type ActiveForward = (i64, String, String, u16, String);
+
+struct Forwards {
+    http_log_state: Arc<HttpLogState>,
+}
+
+fn failed(e: String) -> fdo::Error {
+    fdo::Error::Failed(e)
+}
+
+async fn active_forwards() -> Result<Vec<ActiveForward>, String> {
+    let states = get_configs_state().await?;
+    let running: Vec<i64> = states
+        .iter()
+        .filter(|state| state.is_running)
+        .map(|state| state.config_id)
+        .collect();
+
+    Ok(get_configs()
+        .await?
+        .into_iter()
+        .filter_map(|config| {
+            let config_id = config.id.filter(|id| running.contains(id))?;
+            Some((
+                config_id,
+                config.alias.clone().unwrap_or_default(),
+                format!(
+                    "{}/{}/{}",
+                    config.context,
+                    config.namespace,
+                    config.service.clone().unwrap_or_default()
+                ),
+                bound_local_port(&config, &states).unwrap_or_default(),
+                format!("{:?}", get_forward_phase(config_id)),
+            ))
+        })
+        .collect())
+}
+
+impl Forwards {
+    async fn start_for(
+        &self, ctxt: &SignalContext<'_>, config_id: i64, reason: Option<&str>,
+    ) -> fdo::Result<()> {
+        let config = get_config(config_id).await.map_err(failed)?;
+        let configs = [config.clone()];
+        let start = start_config(config, self.http_log_state.clone());
+        start_with_reason(&configs, reason, start)
+            .await
+            .map_err(failed)?;
+        Self::forwards_changed(ctxt).await?;
+        Ok(())
+    }
+}
+
+#[interface(name = "org.kftray.Forwards")]
+impl Forwards {
+    async fn list_active(&self) -> fdo::Result<Vec<ActiveForward>> {
+        active_forwards().await.map_err(failed)
+    }
+
+    async fn start(
+        &self, #[zbus(signal_context)] ctxt: SignalContext<'_>, config_id: i64,
+    ) -> fdo::Result<()> {
+        self.start_for(&ctxt, config_id, None).await
+    }
+
+    // production contexts only start with a reason
+    async fn start_with_reason(
+        &self, #[zbus(signal_context)] ctxt: SignalContext<'_>, config_id: i64, reason: String,
+    ) -> fdo::Result<()> {
+        self.start_for(&ctxt, config_id, Some(&reason)).await
+    }
+
+    async fn stop(
+        &self, #[zbus(signal_context)] ctxt: SignalContext<'_>, config_id: i64,
+    ) -> fdo::Result<()> {
+        let config = get_config(config_id).await.map_err(failed)?;
+        stop_config(&config).await.map_err(failed)?;
+        Self::forwards_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    async fn stop_all(&self, #[zbus(signal_context)] ctxt: SignalContext<'_>) -> fdo::Result<u32> {
+        let responses = stop_all_port_forward().await.map_err(failed)?;
+        Self::forwards_changed(&ctxt).await?;
+        Ok(responses.len() as u32)
+    }
+
+    #[zbus(signal)]
+    async fn forwards_changed(ctxt: &SignalContext<'_>) -> zbus::Result<()>;
+}
+
+// serves org.kftray.Forwards on the session bus for as long as the process
+// lives, a missing session bus (ssh, containers) only disables the interface
+pub async fn serve_dbus(http_log_state: Arc<HttpLogState>) {
+    let forwards = Forwards { http_log_state };
+    let connection = match connection::Builder::session()
+        .and_then(|builder| builder.name(DBUS_NAME))
+        .and_then(|builder| builder.serve_at(DBUS_PATH, forwards))
+    {
+        Ok(builder) => builder.build().await,
+        Err(e) => Err(e),
+    };
+
+    match connection {
+        Ok(_connection) => {
+            info!("Serving {} on the session bus", DBUS_NAME);
+            std::future::pending::<()>().await;
+        }
+        Err(e) => debug!("D-Bus interface is not available: {}", e),
+    }
+}
@@ -0,0 +1,218 @@
+use k8s_openapi::api::core::v1::{
+    Endpoints,
+    Pod,
+    Service,
+};
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use kftray_commons::config::get_config;
+use kftray_commons::models::target_description_model::{
+    EndpointsDescription,
+    PodConditionDescription,
+    PodDescription,
+    ServiceDescription,
+    TargetDescription,
+};
+use kube::api::Api;
+use log::debug;
+
+use crate::mesh::diagnose_mesh;
+use crate::pod_finder::{
+    config_client,
+    is_proxy_config,
+    resolve_config_pod,
+};
+
+pub fn describe_service(service: &Service) -> ServiceDescription {
+    let spec = service.spec.clone().unwrap_or_default();
+
+    ServiceDescription {
+        name: service.metadata.name.clone().unwrap_or_default(),
+        service_type: spec.type_.unwrap_or_else(|| "ClusterIP".to_string()),
+        cluster_ip: spec.cluster_ip,
+        selector: spec.selector.unwrap_or_default(),
+        ports: spec
+            .ports
+            .unwrap_or_default()
+            .into_iter()
+            .map(|port| {
+                let target_port = match port.target_port {
+                    Some(IntOrString::Int(target)) => target.to_string(),
+                    Some(IntOrString::String(target)) => target,
+                    None => port.port.to_string(),
+                };
+                let name = port
+                    .name
+                    .map(|name| format!("{}:", name))
+                    .unwrap_or_default();
+                format!(
+                    "{}{}->{}/{}",
+                    name,
+                    port.port,
+                    target_port,
+                    port.protocol.unwrap_or_else(|| "TCP".to_string())
+                )
+            })
+            .collect(),
+    }
+}
+
+pub fn describe_endpoints(endpoints: &Endpoints) -> EndpointsDescription {
+    let mut description = EndpointsDescription::default();
+
+    for subset in endpoints.subsets.iter().flatten() {
+        description.ready_addresses.extend(
+            subset
+                .addresses
+                .iter()
+                .flatten()
+                .map(|address| address.ip.clone()),
+        );
+        description.not_ready_addresses.extend(
+            subset
+                .not_ready_addresses
+                .iter()
+                .flatten()
+                .map(|address| address.ip.clone()),
+        );
+    }
+
+    description
+}
+
+pub fn describe_pod(pod: &Pod) -> PodDescription {
+    let spec = pod.spec.clone().unwrap_or_default();
+    let status = pod.status.clone().unwrap_or_default();
+
+    PodDescription {
+        name: pod.metadata.name.clone().unwrap_or_default(),
+        phase: status.phase.unwrap_or_else(|| "Unknown".to_string()),
+        node: spec.node_name,
+        pod_ip: status.pod_ip,
+        labels: pod.metadata.labels.clone().unwrap_or_default(),
+        images: spec
+            .containers
+            .into_iter()
+            .filter_map(|container| container.image)
+            .collect(),
+        conditions: status
+            .conditions
+            .unwrap_or_default()
+            .into_iter()
+            .map(|condition| PodConditionDescription {
+                condition_type: condition.type_,
+                status: condition.status,
+                reason: condition.reason,
+            })
+            .collect(),
+    }
+}
+
+pub async fn describe_target(config_id: i64) -> Result<TargetDescription, String> {
+    let config = get_config(config_id).await?;
+    let client = config_client(&config).await?;
+
+    let pod_api: Api<Pod> = Api::namespaced(client.clone(), &config.namespace);
+    let svc_api: Api<Service> = Api::namespaced(client.clone(), &config.namespace);
+    let endpoints_api: Api<Endpoints> = Api::namespaced(client, &config.namespace);
+
+    let mut description = TargetDescription {
+        config_id,
+        namespace: config.namespace.clone(),
+        ..Default::default()
+    };
+
+    // proxy and pod label configs have no service of their own to describe
+    let service_name = config
+        .service
+        .clone()
+        .filter(|_| !is_proxy_config(&config) && config.workload_type.as_deref() != Some("pod"));
+    if let Some(service_name) = service_name {
+        if let Some(service) = svc_api
+            .get_opt(&service_name)
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            description.service = Some(describe_service(&service));
+            description.endpoints = endpoints_api
+                .get_opt(&service_name)
+                .await
+                .map_err(|e| e.to_string())?
+                .map(|endpoints| describe_endpoints(&endpoints));
+        }
+    }
+
+    let pod = match resolve_config_pod(&config, &pod_api, &svc_api).await {
+        Ok(pod) => Some(pod),
+        Err(e) => {
+            debug!("No target pod to describe for config {}: {}", config_id, e);
+            None
+        }
+    };
+    description.pod = pod.as_ref().map(describe_pod);
+    description.mesh = diagnose_mesh(&pod_api.into_client(), &config, pod.as_ref()).await;
+
+    if description.service.is_none() && description.pod.is_none() {
+        return Err(format!("Target of config {} was not found", config_id));
+    }
+
+    Ok(description)
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::core::v1::{
+        EndpointAddress,
+        EndpointSubset,
+        ServicePort,
+        ServiceSpec,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_describe_service_and_endpoints() {
+        let service = Service {
+            spec: Some(ServiceSpec {
+                ports: Some(vec![
+                    ServicePort {
+                        name: Some("http".to_string()),
+                        port: 80,
+                        target_port: Some(IntOrString::String("web".to_string())),
+                        ..Default::default()
+                    },
+                    ServicePort {
+                        port: 5353,
+                        protocol: Some("UDP".to_string()),
+                        ..Default::default()
+                    },
+                ]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let description = describe_service(&service);
+        assert_eq!(description.service_type, "ClusterIP");
+        assert_eq!(
+            description.ports,
+            vec!["http:80->web/TCP", "5353->5353/UDP"]
+        );
+
+        let address = |ip: &str| EndpointAddress {
+            ip: ip.to_string(),
+            ..Default::default()
+        };
+        let endpoints = Endpoints {
+            subsets: Some(vec![EndpointSubset {
+                addresses: Some(vec![address("10.0.0.4")]),
+                not_ready_addresses: Some(vec![address("10.0.0.5")]),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+
+        let description = describe_endpoints(&endpoints);
+        assert_eq!(description.ready_addresses, vec!["10.0.0.4"]);
+        assert_eq!(description.not_ready_addresses, vec!["10.0.0.5"]);
+    }
+}
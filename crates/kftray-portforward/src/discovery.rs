@@ -0,0 +1,257 @@
+use futures::stream::{
+    self,
+    StreamExt,
+};
+use kftray_commons::models::bulk_update_model::FieldChange;
+use kftray_commons::models::config_model::Config;
+use kftray_commons::utils::bulk_update::diff_configs;
+use kftray_commons::utils::config::{
+    insert_config,
+    read_configs,
+    update_config,
+};
+use log::{
+    info,
+    warn,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::cancel::CancelToken;
+use crate::core::{
+    retrieve_service_configs,
+    retrieve_service_configs_with_cancel,
+};
+use crate::progress::{
+    Progress,
+    ProgressKind,
+};
+
+// contexts scanned at once, each one already fans out over its namespaces
+const DISCOVERY_CONCURRENCY: usize = 4;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ContextDiscovery {
+    pub context: String,
+    pub configs: Vec<Config>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DiscoveryProgress {
+    pub context: String,
+    pub completed: usize,
+    pub total: usize,
+    pub configs: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// scans the contexts concurrently, a context that fails or is cancelled is
+// reported in its result instead of failing the others. the callback fires as
+// each context finishes
+pub async fn retrieve_service_configs_multi(
+    contexts: Vec<String>, kubeconfig: Option<String>, cancel: CancelToken,
+    on_progress: impl Fn(DiscoveryProgress),
+) -> Vec<ContextDiscovery> {
+    let total = contexts.len();
+    info!("Discovering service configs across {} contexts", total);
+    let progress = Progress::begin(
+        ProgressKind::Discovery,
+        total,
+        format!("Scanning {} contexts", total),
+    );
+
+    let mut results: Vec<(usize, ContextDiscovery)> = Vec::with_capacity(total);
+    let mut discoveries = stream::iter(contexts.into_iter().enumerate())
+        .map(|(index, context)| {
+            let kubeconfig = kubeconfig.clone();
+            let cancel = cancel.clone();
+            async move {
+                let result = retrieve_service_configs_with_cancel(&context, kubeconfig, &cancel);
+                let discovery = match result.await {
+                    Ok(configs) => ContextDiscovery {
+                        context,
+                        configs,
+                        error: None,
+                    },
+                    Err(e) => {
+                        warn!("Discovery in context {} failed: {}", context, e);
+                        ContextDiscovery {
+                            context,
+                            configs: Vec::new(),
+                            error: Some(e),
+                        }
+                    }
+                };
+                (index, discovery)
+            }
+        })
+        .buffer_unordered(DISCOVERY_CONCURRENCY);
+
+    while let Some((index, discovery)) = discoveries.next().await {
+        progress.advance(discovery.context.clone());
+        on_progress(DiscoveryProgress {
+            context: discovery.context.clone(),
+            completed: results.len() + 1,
+            total,
+            configs: discovery.configs.len(),
+            error: discovery.error.clone(),
+        });
+        results.push((index, discovery));
+    }
+
+    progress.finish(format!("Scanned {} contexts", total));
+
+    // back in the order the contexts were asked for
+    results.sort_by_key(|(index, _)| *index);
+    results
+        .into_iter()
+        .map(|(_, discovery)| discovery)
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscoveryStatus {
+    New,
+    Changed,
+    Unchanged,
+}
+
+// a discovered config next to what's stored. a changed one carries the stored
+// config with the discovered alias and local port, so importing it keeps
+// everything else the user set
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct DiscoveryDiffEntry {
+    pub status: DiscoveryStatus,
+    pub config: Config,
+    pub changes: Vec<FieldChange>,
+}
+
+// the same port of the same service is the same config, whatever it's called
+fn same_target(discovered: &Config, stored: &Config) -> bool {
+    discovered.context == stored.context
+        && discovered.namespace == stored.namespace
+        && discovered.service == stored.service
+        && discovered.remote_port == stored.remote_port
+        && stored.workload_type.as_deref() == Some("service")
+}
+
+pub fn diff_discovered_configs(
+    discovered: Vec<Config>, existing: &[Config],
+) -> Vec<DiscoveryDiffEntry> {
+    discovered
+        .into_iter()
+        .map(|config| {
+            let Some(stored) = existing.iter().find(|stored| same_target(&config, stored)) else {
+                return DiscoveryDiffEntry {
+                    status: DiscoveryStatus::New,
+                    config,
+                    changes: Vec::new(),
+                };
+            };
+
+            // only what the annotation decides counts as a change
+            let updated = Config {
+                alias: config.alias,
+                local_port: config.local_port,
+                ..stored.clone()
+            };
+            let changes = diff_configs(stored, &updated);
+            DiscoveryDiffEntry {
+                status: if changes.is_empty() {
+                    DiscoveryStatus::Unchanged
+                } else {
+                    DiscoveryStatus::Changed
+                },
+                config: updated,
+                changes,
+            }
+        })
+        .collect()
+}
+
+pub async fn discover_config_diff(
+    context: &str, kubeconfig: Option<String>,
+) -> Result<Vec<DiscoveryDiffEntry>, String> {
+    let discovered = retrieve_service_configs(context, kubeconfig).await?;
+    let existing = read_configs().await?;
+    Ok(diff_discovered_configs(discovered, &existing))
+}
+
+// adds the new configs and updates the changed ones, unchanged entries are
+// skipped. returns how many configs were written
+pub async fn import_discovery_diff(entries: Vec<DiscoveryDiffEntry>) -> Result<usize, String> {
+    let mut imported = 0;
+    for entry in entries {
+        match entry.status {
+            DiscoveryStatus::New => insert_config(entry.config).await?,
+            DiscoveryStatus::Changed => update_config(entry.config).await?,
+            DiscoveryStatus::Unchanged => continue,
+        }
+        imported += 1;
+    }
+    info!("Imported {} discovered configs", imported);
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service_config(id: Option<i64>, alias: &str, local_port: u16) -> Config {
+        Config {
+            id,
+            context: "kind".to_string(),
+            namespace: "shop".to_string(),
+            service: Some("cart".to_string()),
+            workload_type: Some("service".to_string()),
+            alias: Some(alias.to_string()),
+            local_port: Some(local_port),
+            remote_port: Some(8080),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_discovered_configs() {
+        let stored = Config {
+            domain_enabled: Some(true),
+            ..service_config(Some(1), "cart", 8080)
+        };
+        let other_port = Config {
+            remote_port: Some(9090),
+            ..service_config(None, "cart-metrics", 9090)
+        };
+
+        let diff = diff_discovered_configs(
+            vec![
+                service_config(None, "cart", 8080),
+                service_config(None, "cart", 18080),
+                other_port,
+            ],
+            &[stored],
+        );
+
+        let statuses: Vec<DiscoveryStatus> = diff.iter().map(|entry| entry.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                DiscoveryStatus::Unchanged,
+                DiscoveryStatus::Changed,
+                DiscoveryStatus::New
+            ]
+        );
+
+        let changed = &diff[1];
+        assert_eq!(changed.config.id, Some(1));
+        assert_eq!(changed.config.local_port, Some(18080));
+        assert_eq!(changed.config.domain_enabled, Some(true));
+        assert_eq!(changed.changes.len(), 1);
+        assert_eq!(changed.changes[0].field, "local_port");
+    }
+}
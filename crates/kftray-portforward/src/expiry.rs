@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{
+    Duration,
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+use lazy_static::lazy_static;
+use log::{
+    error,
+    info,
+};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use crate::headless::stop_config;
+use crate::reload::running_config;
+
+const EXPIRY_EVENT_CAPACITY: usize = 64;
+
+struct ForwardTtl {
+    expires_at_ms: u64,
+    ttl_secs: u64,
+    handle: JoinHandle<()>,
+}
+
+lazy_static! {
+    static ref FORWARD_TTLS: Mutex<HashMap<i64, ForwardTtl>> = Mutex::new(HashMap::new());
+    static ref EXPIRY_EVENTS: broadcast::Sender<ForwardExpiry> =
+        broadcast::channel(EXPIRY_EVENT_CAPACITY).0;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ForwardExpiry {
+    pub config_id: i64,
+    pub ttl_secs: u64,
+    pub expires_at_ms: u64,
+}
+
+// accepts plain seconds or units like 90s, 15m, 2h, 1d and combinations such
+// as 1h30m
+pub fn parse_ttl(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let invalid = || format!("Invalid duration: {}", value);
+    if value.is_empty() {
+        return Err(invalid());
+    }
+    if let Ok(secs) = value.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+
+    let mut total: u64 = 0;
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        let amount: u64 = number.parse().map_err(|_| invalid())?;
+        total = amount
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(invalid)?;
+        number.clear();
+    }
+    if !number.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(total))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+pub fn subscribe_expiry_events() -> broadcast::Receiver<ForwardExpiry> {
+    EXPIRY_EVENTS.subscribe()
+}
+
+pub fn get_forward_expiries() -> Vec<ForwardExpiry> {
+    let mut expiries: Vec<ForwardExpiry> = FORWARD_TTLS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(config_id, ttl)| ForwardExpiry {
+            config_id: *config_id,
+            ttl_secs: ttl.ttl_secs,
+            expires_at_ms: ttl.expires_at_ms,
+        })
+        .collect();
+    expiries.sort_by_key(|expiry| expiry.expires_at_ms);
+    expiries
+}
+
+// stops the running forward once the ttl elapses, a new ttl for the same
+// config replaces the previous one
+pub fn set_forward_ttl(config_id: i64, ttl: Duration) {
+    let expiry = ForwardExpiry {
+        config_id,
+        ttl_secs: ttl.as_secs(),
+        expires_at_ms: now_ms() + ttl.as_millis() as u64,
+    };
+    info!(
+        "Config {} will stop automatically in {}s",
+        config_id, expiry.ttl_secs
+    );
+
+    // held while spawning so an expiry that fires right away can't run before
+    // its entry is in place
+    let mut ttls = FORWARD_TTLS.lock().unwrap();
+    let handle = tokio::spawn({
+        let expiry = expiry.clone();
+        async move {
+            tokio::time::sleep(ttl).await;
+            // taken out before stopping so the stop path doesn't abort us
+            FORWARD_TTLS.lock().unwrap().remove(&config_id);
+
+            info!("Config {} reached its time limit, stopping", config_id);
+            match running_config(config_id) {
+                Some(config) => {
+                    if let Err(e) = stop_config(&config).await {
+                        error!("Failed to stop expired config {}: {}", config_id, e);
+                    }
+                }
+                None => info!("Expired config {} was no longer running", config_id),
+            }
+            let _ = EXPIRY_EVENTS.send(expiry);
+        }
+    });
+
+    let previous = ttls.insert(
+        config_id,
+        ForwardTtl {
+            expires_at_ms: expiry.expires_at_ms,
+            ttl_secs: expiry.ttl_secs,
+            handle,
+        },
+    );
+    if let Some(previous) = previous {
+        previous.handle.abort();
+    }
+}
+
+pub fn forward_time_left(config_id: i64) -> Option<Duration> {
+    FORWARD_TTLS
+        .lock()
+        .unwrap()
+        .get(&config_id)
+        .map(|ttl| Duration::from_millis(ttl.expires_at_ms.saturating_sub(now_ms())))
+}
+
+// resolves once every one of the configs reached its time limit
+pub async fn wait_until_expired(
+    events: &mut broadcast::Receiver<ForwardExpiry>, config_ids: &[i64],
+) {
+    let mut remaining: Vec<i64> = config_ids.to_vec();
+    while !remaining.is_empty() {
+        match events.recv().await {
+            Ok(expiry) => remaining.retain(|config_id| *config_id != expiry.config_id),
+            Err(broadcast::error::RecvError::Lagged(_)) => {
+                remaining.retain(|config_id| forward_time_left(*config_id).is_some())
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+pub(crate) fn clear_forward_ttl(config_id: Option<i64>) {
+    let removed: Vec<ForwardTtl> = {
+        let mut ttls = FORWARD_TTLS.lock().unwrap();
+        match config_id {
+            Some(config_id) => ttls.remove(&config_id).into_iter().collect(),
+            None => ttls.drain().map(|(_, ttl)| ttl).collect(),
+        }
+    };
+    for ttl in removed {
+        ttl.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ttl() {
+        assert_eq!(parse_ttl("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_ttl("2h").unwrap(), Duration::from_secs(7200));
+        assert_eq!(parse_ttl("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_ttl(" 1d ").unwrap(), Duration::from_secs(86400));
+        assert!(parse_ttl("").is_err());
+        assert!(parse_ttl("h").is_err());
+        assert!(parse_ttl("2 hours").is_err());
+        assert!(parse_ttl("10m5").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_forward_ttl_replaced_and_cleared() {
+        set_forward_ttl(-169, Duration::from_secs(3600));
+        set_forward_ttl(-169, Duration::from_secs(60));
+        let expiries: Vec<ForwardExpiry> = get_forward_expiries()
+            .into_iter()
+            .filter(|expiry| expiry.config_id == -169)
+            .collect();
+        assert_eq!(expiries.len(), 1);
+        assert_eq!(expiries[0].ttl_secs, 60);
+
+        clear_forward_ttl(Some(-169));
+        assert!(get_forward_expiries()
+            .iter()
+            .all(|expiry| expiry.config_id != -169));
+    }
+}
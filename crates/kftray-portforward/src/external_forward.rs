@@ -0,0 +1,325 @@
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use kftray_commons::config::{
+    create_config,
+    get_config,
+    get_configs,
+};
+use kftray_commons::models::config_model::Config;
+use kftray_commons::utils::tool_import::{
+    parse_kubectl_port_forward,
+    KubectlForward,
+};
+use log::{
+    info,
+    warn,
+};
+use serde::Serialize;
+use tokio::process::Command;
+
+use crate::batch::{
+    start_configs_batch,
+    BatchReport,
+};
+use crate::models::kube::HttpLogState;
+use crate::reconcile::port_in_use;
+use crate::tool_import::import_kubectl_forward;
+
+const PROCESS_COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+// how long the ports of a terminated kubectl get to come free
+const RELEASE_TIMEOUT: Duration = Duration::from_secs(5);
+const RELEASE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// a `kubectl port-forward` that runs outside of kftray
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ExternalForward {
+    pub pid: u32,
+    pub command: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    // `service/cart`, the way kubectl takes it
+    pub target: String,
+    // `local:remote`, `:remote` when kubectl picked the local port
+    pub ports: Vec<String>,
+    // configs on one of its local ports, they can't run next to it
+    pub config_ids: Vec<i64>,
+    #[serde(skip)]
+    pub(crate) forward: KubectlForward,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Adoption {
+    pub pid: u32,
+    pub config_ids: Vec<i64>,
+    pub skipped: Vec<String>,
+    pub report: BatchReport,
+}
+
+fn external_forward(pid: u32, command: &str, configs: &[Config]) -> Option<ExternalForward> {
+    let forward = parse_kubectl_port_forward(command).ok()?;
+    let ports = forward
+        .ports
+        .iter()
+        .map(|(local_port, remote)| match local_port {
+            Some(local_port) => format!("{}:{}", local_port, remote),
+            None => format!(":{}", remote),
+        })
+        .collect();
+    let config_ids = configs
+        .iter()
+        .filter(|config| {
+            forward
+                .ports
+                .iter()
+                .any(|(local_port, _)| local_port.is_some() && config.local_port == *local_port)
+        })
+        .filter_map(|config| config.id)
+        .collect();
+
+    Some(ExternalForward {
+        pid,
+        command: command.to_string(),
+        context: forward.context.clone(),
+        namespace: forward.namespace.clone(),
+        target: format!("{}/{}", forward.kind, forward.name),
+        ports,
+        config_ids,
+        forward,
+    })
+}
+
+// a config that already forwards the same target on the same local port
+fn matching_config<'a>(imported: &Config, existing: &'a [Config]) -> Option<&'a Config> {
+    existing.iter().find(|config| {
+        config.local_port == imported.local_port
+            && config.context == imported.context
+            && config.namespace == imported.namespace
+            && config.workload_type == imported.workload_type
+            && config.service == imported.service
+            && config.target == imported.target
+            && config.remote_port == imported.remote_port
+    })
+}
+
+// one `<pid> <command line>` per line, whatever isn't a kubectl port-forward
+// is left out
+pub fn external_forwards(listing: &str, configs: &[Config]) -> Vec<ExternalForward> {
+    listing
+        .lines()
+        .filter_map(|line| {
+            let (pid, command) = line.trim().split_once(char::is_whitespace)?;
+            let pid = pid.parse().ok()?;
+            if pid == std::process::id() || !command.contains("port-forward") {
+                return None;
+            }
+            external_forward(pid, command.trim(), configs)
+        })
+        .collect()
+}
+
+async fn command_output(program: &str, args: &[&str]) -> Result<String, String> {
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    let output = tokio::time::timeout(PROCESS_COMMAND_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("{} timed out", program))?
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(not(windows))]
+async fn process_listing() -> Result<String, String> {
+    command_output("ps", &["-axo", "pid=,args="]).await
+}
+
+#[cfg(windows)]
+async fn process_listing() -> Result<String, String> {
+    command_output(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-Command",
+            "Get-CimInstance Win32_Process -Filter \"Name like 'kubectl%'\" | \
+             ForEach-Object { \"$($_.ProcessId) $($_.CommandLine)\" }",
+        ],
+    )
+    .await
+}
+
+#[cfg(not(windows))]
+async fn terminate_process(pid: u32) -> Result<(), String> {
+    command_output("kill", &["-TERM", &pid.to_string()])
+        .await
+        .map(|_| ())
+}
+
+#[cfg(windows)]
+async fn terminate_process(pid: u32) -> Result<(), String> {
+    command_output("taskkill", &["/PID", &pid.to_string(), "/T", "/F"])
+        .await
+        .map(|_| ())
+}
+
+pub async fn list_external_forwards() -> Result<Vec<ExternalForward>, String> {
+    let listing = process_listing().await?;
+    let configs = get_configs().await?;
+    Ok(external_forwards(&listing, &configs))
+}
+
+async fn wait_for_release(configs: &[Config]) {
+    let deadline = tokio::time::Instant::now() + RELEASE_TIMEOUT;
+    while configs.iter().any(port_in_use) {
+        if tokio::time::Instant::now() >= deadline {
+            warn!("The ports of the adopted kubectl forward are still in use");
+            return;
+        }
+        tokio::time::sleep(RELEASE_POLL_INTERVAL).await;
+    }
+}
+
+// the configs are worked out before kubectl is stopped, so a forward kftray
+// can't take over keeps running. the context is only needed when the command
+// relies on the current one. a config that already covers a port is started
+// instead of a new one
+pub async fn adopt_external_forward(
+    pid: u32, context: Option<String>, kubeconfig: Option<String>,
+    http_log_state: Arc<HttpLogState>,
+) -> Result<Adoption, String> {
+    let existing = get_configs().await?;
+    let external = external_forwards(&process_listing().await?, &existing)
+        .into_iter()
+        .find(|external| external.pid == pid)
+        .ok_or_else(|| format!("No kubectl port-forward runs as process {}", pid))?;
+    let context = external.context.clone().or(context).ok_or_else(|| {
+        format!(
+            "Process {} forwards through the current context, pick the context to adopt it into",
+            pid
+        )
+    })?;
+
+    let import = import_kubectl_forward(&external.forward, &context, kubeconfig).await?;
+    if import.configs.is_empty() {
+        return Err(format!(
+            "Nothing to adopt from process {}: {}",
+            pid,
+            import.skipped.join("; ")
+        ));
+    }
+
+    terminate_process(pid).await?;
+    wait_for_release(&import.configs).await;
+
+    let candidates: Vec<Config> = existing
+        .into_iter()
+        .filter(|config| {
+            config
+                .id
+                .is_some_and(|id| external.config_ids.contains(&id))
+        })
+        .collect();
+    let mut configs = Vec::new();
+    for config in import.configs {
+        match matching_config(&config, &candidates) {
+            Some(matching) => configs.push(matching.clone()),
+            None => {
+                let config_id = create_config(config).await?;
+                configs.push(get_config(config_id).await?);
+            }
+        }
+    }
+    let config_ids: Vec<i64> = configs.iter().filter_map(|config| config.id).collect();
+    info!(
+        "Adopted kubectl process {} as config(s) {:?}",
+        pid, config_ids
+    );
+
+    let report = start_configs_batch(configs, None, http_log_state).await;
+    Ok(Adoption {
+        pid,
+        config_ids,
+        skipped: import.skipped,
+        report,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_external_forwards() {
+        let listing = "  412 /usr/bin/kubectl port-forward -n shop svc/cart 8080:80 :443\n\
+                       413 kubectl get pods -w\n\
+                       414 vim port-forward.md\n\
+                       415 kubectl --context prod port-forward pg-0 5432\n";
+        let configs = vec![Config {
+            id: Some(7),
+            local_port: Some(5432),
+            ..Default::default()
+        }];
+
+        let forwards = external_forwards(listing, &configs);
+        assert_eq!(forwards.len(), 2);
+        assert_eq!(forwards[0].pid, 412);
+        assert_eq!(forwards[0].target, "service/cart");
+        assert_eq!(forwards[0].ports, vec!["8080:80", ":443"]);
+        assert!(forwards[0].config_ids.is_empty());
+        assert_eq!(forwards[1].context.as_deref(), Some("prod"));
+        assert_eq!(forwards[1].target, "pod/pg-0");
+        assert_eq!(forwards[1].config_ids, vec![7]);
+    }
+
+    #[test]
+    fn test_matching_config() {
+        let imported = Config {
+            context: "prod".to_string(),
+            namespace: "shop".to_string(),
+            workload_type: Some("service".to_string()),
+            service: Some("cart".to_string()),
+            remote_port: Some(8080),
+            local_port: Some(8080),
+            ..Default::default()
+        };
+        let existing = vec![
+            Config {
+                id: Some(3),
+                service: Some("orders".to_string()),
+                ..imported.clone()
+            },
+            Config {
+                id: Some(4),
+                ..imported.clone()
+            },
+        ];
+        assert_eq!(
+            matching_config(&imported, &existing).and_then(|config| config.id),
+            Some(4)
+        );
+
+        let other_port = Config {
+            local_port: Some(9090),
+            ..imported
+        };
+        assert!(matching_config(&other_port, &existing).is_none());
+    }
+}
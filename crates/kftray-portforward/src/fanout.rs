@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
+use hostsfile::HostsBuilder;
+use k8s_openapi::api::core::v1::Pod;
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::settings_model::HostsBackend;
+use kftray_commons::utils::settings::current_settings;
+use lazy_static::lazy_static;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use tokio::task::JoinHandle;
+use tracing::info;
+
+use crate::models::kube::{
+    HttpLogState,
+    PortForward,
+    Target,
+    TargetSelector,
+};
+
+type StartedForwards = Vec<(FanOutForward, JoinHandle<()>)>;
+
+lazy_static! {
+    static ref FAN_OUT_FORWARDS: Mutex<HashMap<i64, StartedForwards>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Deserialize, PartialEq, Eq, Serialize, Debug)]
+pub struct FanOutForward {
+    pub config_id: i64,
+    pub pod_name: String,
+    pub ordinal: u32,
+    pub local_port: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+}
+
+pub fn pod_ordinal(pod: &Pod) -> Option<u32> {
+    let owned_by_statefulset = pod
+        .metadata
+        .owner_references
+        .iter()
+        .flatten()
+        .any(|owner| owner.kind == "StatefulSet");
+    if !owned_by_statefulset {
+        return None;
+    }
+
+    let (_, ordinal) = pod.metadata.name.as_deref()?.rsplit_once('-')?;
+    ordinal.parse().ok()
+}
+
+fn ordinal_port(base_port: u16, ordinal: u32) -> Option<u16> {
+    u16::try_from(u32::from(base_port) + ordinal).ok()
+}
+
+fn ordinal_alias(config: &Config, pod_name: &str) -> Option<String> {
+    if !config.domain_enabled.unwrap_or_default() {
+        return None;
+    }
+
+    config
+        .alias
+        .as_deref()
+        .filter(|alias| !alias.is_empty())
+        .map(|alias| format!("{}.{}", pod_name, alias))
+}
+
+pub(crate) fn hosts_comment(config: &Config) -> String {
+    format!(
+        "kftray fan-out hosts for {} - {}",
+        config.service.clone().unwrap_or_default(),
+        config.id.unwrap_or_default()
+    )
+}
+
+fn write_fan_out_hosts(config: &Config, forwards: &[FanOutForward]) -> Result<(), String> {
+    let aliases: Vec<&str> = forwards
+        .iter()
+        .filter_map(|forward| forward.alias.as_deref())
+        .collect();
+    if aliases.is_empty() || current_settings().hosts_backend == HostsBackend::Disabled {
+        return Ok(());
+    }
+
+    let ip_addr = config
+        .local_address
+        .as_deref()
+        .unwrap_or("127.0.0.1")
+        .parse::<IpAddr>()
+        .map_err(|e| e.to_string())?;
+
+    let mut hosts_builder = HostsBuilder::new(hosts_comment(config));
+    for alias in aliases {
+        hosts_builder.add_hostname(ip_addr, alias);
+    }
+    hosts_builder.write().map(|_| ()).map_err(|e| e.to_string())
+}
+
+pub fn remove_fan_out_hosts(config: &Config) -> Result<(), String> {
+    HostsBuilder::new(hosts_comment(config))
+        .write()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+// forwards every StatefulSet pod behind the target on `base + ordinal`, the
+// default base is the port right after the config's own local port
+pub async fn start_fan_out(
+    config: &Config, pf: &PortForward, http_log_state: Arc<HttpLogState>,
+) -> anyhow::Result<Vec<FanOutForward>> {
+    let config_id = config.id.unwrap_or_default();
+    let base_port = match config.fan_out_base_port {
+        Some(base_port) => base_port,
+        None => config
+            .local_port
+            .filter(|port| *port != 0)
+            .and_then(|port| port.checked_add(1))
+            .ok_or_else(|| anyhow::anyhow!("Fan-out needs a base port or a fixed local port"))?,
+    };
+
+    let mut pods: Vec<(u32, String)> = pf
+        .finder()
+        .list_pods(&pf.target)
+        .await?
+        .iter()
+        .filter_map(|pod| Some((pod_ordinal(pod)?, pod.metadata.name.clone()?)))
+        .collect();
+    pods.sort();
+
+    if pods.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No StatefulSet pods found for {}",
+            config.service.clone().unwrap_or_default()
+        ));
+    }
+
+    let mut started = Vec::with_capacity(pods.len());
+    for (ordinal, pod_name) in pods {
+        let result = match ordinal_port(base_port, ordinal) {
+            Some(local_port) => {
+                let forward = PortForward {
+                    target: Target {
+                        selector: TargetSelector::PodName(pod_name.clone()),
+                        port: pf.target.port.clone(),
+                        namespace: pf.target.namespace.clone(),
+                    },
+                    local_port: Some(local_port),
+                    workload_type: "pod".to_string(),
+                    connection: Default::default(),
+                    ..pf.clone()
+                };
+                forward.port_forward_tcp(http_log_state.clone()).await
+            }
+            None => Err(anyhow::anyhow!(
+                "Local port for ordinal {} is out of range",
+                ordinal
+            )),
+        };
+
+        match result {
+            Ok((local_port, handle)) => {
+                info!(
+                    "Fan-out forward for pod {} is set up on local port {}",
+                    pod_name, local_port
+                );
+                let alias = ordinal_alias(config, &pod_name);
+                started.push((
+                    FanOutForward {
+                        config_id,
+                        pod_name,
+                        ordinal,
+                        local_port,
+                        alias,
+                    },
+                    handle,
+                ));
+            }
+            Err(e) => {
+                for (_, handle) in started {
+                    handle.abort();
+                }
+                return Err(anyhow::anyhow!("Failed to forward pod {}: {}", pod_name, e));
+            }
+        }
+    }
+
+    let forwards: Vec<FanOutForward> = started.iter().map(|(forward, _)| forward.clone()).collect();
+    if let Err(e) = write_fan_out_hosts(config, &forwards) {
+        for (_, handle) in started {
+            handle.abort();
+        }
+        return Err(anyhow::anyhow!("Failed to write fan-out hosts: {}", e));
+    }
+
+    FAN_OUT_FORWARDS.lock().unwrap().insert(config_id, started);
+    Ok(forwards)
+}
+
+pub fn get_fan_out_forwards(config_id: i64) -> Vec<FanOutForward> {
+    FAN_OUT_FORWARDS
+        .lock()
+        .unwrap()
+        .get(&config_id)
+        .map(|forwards| {
+            forwards
+                .iter()
+                .map(|(forward, _)| forward.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn stop_fan_out(config_id: Option<i64>) {
+    let mut forwards = FAN_OUT_FORWARDS.lock().unwrap();
+    let stopped: Vec<_> = match config_id {
+        Some(config_id) => forwards.remove(&config_id).into_iter().flatten().collect(),
+        None => forwards.drain().flat_map(|(_, started)| started).collect(),
+    };
+
+    for (_, handle) in stopped {
+        handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{
+        ObjectMeta,
+        OwnerReference,
+    };
+
+    use super::*;
+
+    fn pod(name: &str, owner_kind: &str) -> Pod {
+        Pod {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                owner_references: Some(vec![OwnerReference {
+                    kind: owner_kind.to_string(),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_pod_ordinal() {
+        assert_eq!(pod_ordinal(&pod("kafka-2", "StatefulSet")), Some(2));
+        assert_eq!(pod_ordinal(&pod("mongo-rs-10", "StatefulSet")), Some(10));
+        assert_eq!(pod_ordinal(&pod("api-7d9f8-x2x4q", "ReplicaSet")), None);
+        assert_eq!(pod_ordinal(&pod("standalone", "StatefulSet")), None);
+
+        assert_eq!(ordinal_port(9093, 2), Some(9095));
+        assert_eq!(ordinal_port(u16::MAX, 1), None);
+    }
+}
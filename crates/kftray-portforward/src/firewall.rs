@@ -0,0 +1,251 @@
+use std::net::IpAddr;
+#[cfg(any(target_os = "macos", windows))]
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use kftray_commons::models::response::FirewallWarning;
+use lazy_static::lazy_static;
+#[cfg(any(target_os = "macos", windows))]
+use log::debug;
+use log::warn;
+#[cfg(any(target_os = "macos", windows))]
+use tokio::process::Command;
+
+#[cfg(any(target_os = "macos", windows))]
+const FIREWALL_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+// the firewall state rarely changes, no need to ask for every forward of a
+// batch
+const FIREWALL_STATUS_TTL: Duration = Duration::from_secs(30);
+
+lazy_static! {
+    static ref FIREWALL_STATUS: Mutex<Option<(Instant, FirewallStatus)>> = Mutex::new(None);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FirewallStatus {
+    Disabled,
+    // on and kftray isn't explicitly allowed, the first lan connection prompts
+    Enabled,
+    BlocksApp,
+    BlocksAllIncoming,
+    Unknown,
+}
+
+// only listeners other machines can reach go through the firewall
+pub fn is_lan_binding(address: &str) -> bool {
+    match address.parse::<IpAddr>() {
+        Ok(ip) => !ip.is_loopback(),
+        Err(_) => !address.eq_ignore_ascii_case("localhost"),
+    }
+}
+
+fn firewall_name() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "macOS application firewall"
+    } else if cfg!(windows) {
+        "Windows Defender Firewall"
+    } else {
+        "firewall"
+    }
+}
+
+pub fn firewall_warning_for(
+    status: FirewallStatus, address: &str, port: u16,
+) -> Option<FirewallWarning> {
+    let firewall = firewall_name();
+    let (blocked, message, action) = match status {
+        FirewallStatus::Disabled | FirewallStatus::Unknown => return None,
+        FirewallStatus::Enabled => (
+            false,
+            format!(
+                "The {} may prompt before other machines can connect to {}:{}",
+                firewall, address, port
+            ),
+            "Allow incoming connections for kftray when prompted".to_string(),
+        ),
+        FirewallStatus::BlocksApp => (
+            true,
+            format!(
+                "The {} blocks incoming connections to kftray, {}:{} is only reachable from this machine",
+                firewall, address, port
+            ),
+            if cfg!(target_os = "macos") {
+                "Allow kftray in System Settings > Network > Firewall > Options".to_string()
+            } else {
+                "Allow kftray in Windows Security > Firewall & network protection > Allow an app through firewall".to_string()
+            },
+        ),
+        FirewallStatus::BlocksAllIncoming => (
+            true,
+            format!(
+                "The {} blocks all incoming connections, {}:{} is only reachable from this machine",
+                firewall, address, port
+            ),
+            "Turn off blocking all incoming connections in the firewall settings, or bind to 127.0.0.1".to_string(),
+        ),
+    };
+
+    Some(FirewallWarning {
+        firewall: firewall.to_string(),
+        address: address.to_string(),
+        port,
+        blocked,
+        message,
+        action,
+    })
+}
+
+#[cfg(any(target_os = "macos", windows))]
+async fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| debug!("Failed to run {}: {}", program, e))
+        .ok()?;
+    let output = tokio::time::timeout(FIREWALL_CHECK_TIMEOUT, child.wait_with_output())
+        .await
+        .ok()?
+        .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).to_lowercase())
+}
+
+#[cfg(target_os = "macos")]
+async fn query_firewall_status() -> FirewallStatus {
+    const SOCKETFILTERFW: &str = "/usr/libexec/ApplicationFirewall/socketfilterfw";
+
+    let Some(state) = command_output(SOCKETFILTERFW, &["--getglobalstate"]).await else {
+        return FirewallStatus::Unknown;
+    };
+    if !state.contains("enabled") {
+        return FirewallStatus::Disabled;
+    }
+
+    let block_all = command_output(SOCKETFILTERFW, &["--getblockall"]).await;
+    if block_all.is_some_and(|output| output.contains("enabled")) {
+        return FirewallStatus::BlocksAllIncoming;
+    }
+
+    let Some(exe) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.to_str().map(str::to_string))
+    else {
+        return FirewallStatus::Enabled;
+    };
+    match command_output(SOCKETFILTERFW, &["--getappblocked", &exe]).await {
+        Some(output) if output.contains("blocked") => FirewallStatus::BlocksApp,
+        Some(output) if output.contains("permitted") => FirewallStatus::Disabled,
+        _ => FirewallStatus::Enabled,
+    }
+}
+
+#[cfg(windows)]
+async fn query_firewall_status() -> FirewallStatus {
+    let Some(state) = command_output("netsh", &["advfirewall", "show", "currentprofile"]).await
+    else {
+        return FirewallStatus::Unknown;
+    };
+    let enabled = state.lines().any(|line| {
+        let mut words = line.split_whitespace();
+        words.next() == Some("state") && words.next() == Some("on")
+    });
+    if !enabled {
+        return FirewallStatus::Disabled;
+    }
+    if state.contains("blockinboundalways") {
+        return FirewallStatus::BlocksAllIncoming;
+    }
+
+    // an inbound rule for the executable means the prompt was answered already
+    let exe = std::env::current_exe().ok().and_then(|exe| {
+        exe.file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+    });
+    let rules = command_output(
+        "netsh",
+        &[
+            "advfirewall",
+            "firewall",
+            "show",
+            "rule",
+            "name=all",
+            "dir=in",
+            "verbose",
+        ],
+    )
+    .await;
+    match (exe, rules) {
+        (Some(exe), Some(rules)) => {
+            let rule = rules.split("\r\n\r\n").find(|rule| rule.contains(&exe));
+            let blocks = |rule: &str| {
+                rule.lines()
+                    .any(|line| line.starts_with("action:") && line.contains("block"))
+            };
+            match rule {
+                Some(rule) if blocks(rule) => FirewallStatus::BlocksApp,
+                Some(_) => FirewallStatus::Disabled,
+                None => FirewallStatus::Enabled,
+            }
+        }
+        _ => FirewallStatus::Enabled,
+    }
+}
+
+// other platforms don't prompt, a configured firewall there is deliberate
+#[cfg(not(any(target_os = "macos", windows)))]
+async fn query_firewall_status() -> FirewallStatus {
+    FirewallStatus::Unknown
+}
+
+pub async fn firewall_status() -> FirewallStatus {
+    if let Some((checked_at, status)) = *FIREWALL_STATUS.lock().unwrap() {
+        if checked_at.elapsed() < FIREWALL_STATUS_TTL {
+            return status;
+        }
+    }
+    let status = query_firewall_status().await;
+    *FIREWALL_STATUS.lock().unwrap() = Some((Instant::now(), status));
+    status
+}
+
+// none for loopback listeners or when the firewall won't get in the way
+pub async fn check_firewall(address: &str, port: u16) -> Option<FirewallWarning> {
+    if !is_lan_binding(address) {
+        return None;
+    }
+    let warning = firewall_warning_for(firewall_status().await, address, port)?;
+    warn!("{}", warning.message);
+    Some(warning)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_firewall_warnings() {
+        assert!(!is_lan_binding("127.0.0.1"));
+        assert!(!is_lan_binding("127.0.0.42"));
+        assert!(!is_lan_binding("::1"));
+        assert!(!is_lan_binding("localhost"));
+        assert!(is_lan_binding("0.0.0.0"));
+        assert!(is_lan_binding("192.168.1.20"));
+
+        assert!(firewall_warning_for(FirewallStatus::Disabled, "0.0.0.0", 8080).is_none());
+        assert!(firewall_warning_for(FirewallStatus::Unknown, "0.0.0.0", 8080).is_none());
+
+        let prompt = firewall_warning_for(FirewallStatus::Enabled, "0.0.0.0", 8080).unwrap();
+        assert!(!prompt.blocked);
+        assert!(prompt.message.contains("0.0.0.0:8080"));
+
+        let blocked = firewall_warning_for(FirewallStatus::BlocksAllIncoming, "10.0.0.5", 80);
+        assert!(blocked.unwrap().blocked);
+    }
+}
@@ -0,0 +1,297 @@
+use std::collections::{
+    BTreeMap,
+    HashMap,
+};
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::{
+    BodyExt,
+    Full,
+};
+use hyper::client::conn::http2;
+use hyper::Request;
+use hyper_util::rt::{
+    TokioExecutor,
+    TokioIo,
+};
+use kftray_commons::models::grpc_health_model::{
+    GrpcHealthStatus,
+    ServingStatus,
+};
+use kftray_commons::utils::access_log::now_millis;
+use lazy_static::lazy_static;
+use log::{
+    debug,
+    warn,
+};
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+
+const HEALTH_CHECK_PATH: &str = "/grpc.health.v1.Health/Check";
+const GRPC_STATUS_NOT_FOUND: u32 = 5;
+const GRPC_STATUS_UNIMPLEMENTED: u32 = 12;
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+const PROBE_INTERVAL: Duration = Duration::from_secs(15);
+
+lazy_static! {
+    static ref GRPC_HEALTH: Mutex<HashMap<i64, BTreeMap<String, GrpcHealthStatus>>> =
+        Mutex::new(HashMap::new());
+    static ref GRPC_HEALTH_MONITORS: Mutex<HashMap<i64, JoinHandle<()>>> =
+        Mutex::new(HashMap::new());
+}
+
+fn read_varint(data: &[u8]) -> Option<(u64, &[u8])> {
+    let mut value = 0u64;
+    for (index, byte) in data.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7F) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Some((value, &data[index + 1..]));
+        }
+    }
+    None
+}
+
+// HealthCheckRequest { string service = 1; } in a length-prefixed gRPC frame
+pub fn encode_health_request(service: &str) -> Vec<u8> {
+    let mut message = Vec::with_capacity(service.len() + 2);
+    if !service.is_empty() {
+        message.push(0x0A);
+        let mut len = service.len();
+        while len >= 0x80 {
+            message.push((len as u8 & 0x7F) | 0x80);
+            len >>= 7;
+        }
+        message.push(len as u8);
+        message.extend_from_slice(service.as_bytes());
+    }
+
+    let mut frame = vec![0];
+    frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    frame.extend(message);
+    frame
+}
+
+// HealthCheckResponse { ServingStatus status = 1; }
+pub fn decode_health_response(body: &[u8]) -> Result<ServingStatus, String> {
+    let (header, message) = body
+        .split_at_checked(5)
+        .ok_or_else(|| "empty health check response".to_string())?;
+    if header[0] != 0 {
+        return Err("compressed health check responses are not supported".to_string());
+    }
+
+    let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    let mut rest = message
+        .get(..len)
+        .ok_or_else(|| "truncated health check response".to_string())?;
+
+    let mut status = ServingStatus::Unknown;
+    while let Some((key, remaining)) = read_varint(rest) {
+        rest = match key & 0x07 {
+            0 => {
+                let (value, remaining) = read_varint(remaining)
+                    .ok_or_else(|| "invalid health check response".to_string())?;
+                if key >> 3 == 1 {
+                    status = ServingStatus::from_code(value);
+                }
+                remaining
+            }
+            2 => {
+                let (len, remaining) = read_varint(remaining)
+                    .ok_or_else(|| "invalid health check response".to_string())?;
+                remaining
+                    .get(len as usize..)
+                    .ok_or_else(|| "truncated health check response".to_string())?
+            }
+            5 => remaining.get(4..).unwrap_or_default(),
+            1 => remaining.get(8..).unwrap_or_default(),
+            _ => return Err("invalid health check response".to_string()),
+        };
+    }
+
+    Ok(status)
+}
+
+pub async fn check_grpc_health(addr: SocketAddr, service: &str) -> Result<ServingStatus, String> {
+    let stream = TcpStream::connect(addr)
+        .await
+        .map_err(|e| format!("connect to {} failed: {}", addr, e))?;
+
+    let (mut sender, connection) = http2::handshake(TokioExecutor::new(), TokioIo::new(stream))
+        .await
+        .map_err(|e| format!("HTTP/2 handshake with {} failed: {}", addr, e))?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            debug!("gRPC health connection closed: {}", e);
+        }
+    });
+
+    let request = Request::post(format!("http://{}{}", addr, HEALTH_CHECK_PATH))
+        .header("content-type", "application/grpc")
+        .header("te", "trailers")
+        .body(Full::new(Bytes::from(encode_health_request(service))))
+        .map_err(|e| e.to_string())?;
+
+    let response = sender
+        .send_request(request)
+        .await
+        .map_err(|e| format!("health check request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "health check returned HTTP {}",
+            response.status().as_u16()
+        ));
+    }
+
+    let headers = response.headers().clone();
+    let collected = response
+        .into_body()
+        .collect()
+        .await
+        .map_err(|e| format!("health check response failed: {}", e))?;
+
+    // trailers-only responses carry the status in the headers
+    let trailers = collected.trailers().cloned().unwrap_or_default();
+    let grpc_header = |name: &str| {
+        trailers
+            .get(name)
+            .or_else(|| headers.get(name))
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+
+    match grpc_header("grpc-status").and_then(|status| status.parse::<u32>().ok()) {
+        Some(0) | None => decode_health_response(&collected.to_bytes()),
+        Some(GRPC_STATUS_NOT_FOUND) => Ok(ServingStatus::ServiceUnknown),
+        Some(GRPC_STATUS_UNIMPLEMENTED) => {
+            Err("server does not implement grpc.health.v1".to_string())
+        }
+        Some(code) => Err(format!(
+            "health check failed with grpc-status {}: {}",
+            code,
+            grpc_header("grpc-message").unwrap_or_default()
+        )),
+    }
+}
+
+async fn probe_service(config_id: i64, addr: SocketAddr, service: &str) -> GrpcHealthStatus {
+    let result = match timeout(PROBE_TIMEOUT, check_grpc_health(addr, service)).await {
+        Ok(result) => result,
+        Err(_) => Err("health check timed out".to_string()),
+    };
+
+    let (status, error) = match result {
+        Ok(status) => (status, None),
+        Err(e) => (ServingStatus::Unknown, Some(e)),
+    };
+
+    GrpcHealthStatus {
+        config_id,
+        service: service.to_string(),
+        status,
+        checked_at_ms: now_millis(),
+        error,
+    }
+}
+
+pub fn start_grpc_health_monitor(config_id: i64, addr: SocketAddr, services: Vec<String>) {
+    let services = if services.is_empty() {
+        vec![String::new()]
+    } else {
+        services
+    };
+
+    let handle = tokio::spawn(async move {
+        loop {
+            for service in &services {
+                let health = probe_service(config_id, addr, service).await;
+                if let Some(error) = &health.error {
+                    warn!(
+                        "gRPC health check for config {} service '{}' failed: {}",
+                        config_id, service, error
+                    );
+                }
+
+                GRPC_HEALTH
+                    .lock()
+                    .unwrap()
+                    .entry(config_id)
+                    .or_default()
+                    .insert(service.clone(), health);
+            }
+
+            tokio::time::sleep(PROBE_INTERVAL).await;
+        }
+    });
+
+    if let Some(previous) = GRPC_HEALTH_MONITORS
+        .lock()
+        .unwrap()
+        .insert(config_id, handle)
+    {
+        previous.abort();
+    }
+}
+
+pub fn stop_grpc_health_monitor(config_id: Option<i64>) {
+    let mut monitors = GRPC_HEALTH_MONITORS.lock().unwrap();
+    let stopped: Vec<JoinHandle<()>> = match config_id {
+        Some(config_id) => monitors.remove(&config_id).into_iter().collect(),
+        None => monitors.drain().map(|(_, handle)| handle).collect(),
+    };
+
+    for handle in stopped {
+        handle.abort();
+    }
+}
+
+pub fn get_grpc_health(config_id: i64) -> Vec<GrpcHealthStatus> {
+    GRPC_HEALTH
+        .lock()
+        .unwrap()
+        .get(&config_id)
+        .map(|services| services.values().cloned().collect())
+        .unwrap_or_default()
+}
+
+pub fn clear_grpc_health(config_id: Option<i64>) {
+    let mut health = GRPC_HEALTH.lock().unwrap();
+    match config_id {
+        Some(config_id) => {
+            health.remove(&config_id);
+        }
+        None => health.clear(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_messages() {
+        assert_eq!(encode_health_request(""), vec![0, 0, 0, 0, 0]);
+        assert_eq!(
+            encode_health_request("orders"),
+            b"\x00\x00\x00\x00\x08\x0a\x06orders".to_vec()
+        );
+
+        assert_eq!(
+            decode_health_response(b"\x00\x00\x00\x00\x02\x08\x01"),
+            Ok(ServingStatus::Serving)
+        );
+        assert_eq!(
+            decode_health_response(b"\x00\x00\x00\x00\x00"),
+            Ok(ServingStatus::Unknown)
+        );
+        assert_eq!(
+            decode_health_response(b"\x00\x00\x00\x00\x02\x08\x02"),
+            Ok(ServingStatus::NotServing)
+        );
+        assert!(decode_health_response(b"\x00\x00\x00\x00\x02\x08").is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use kftray_commons::config::get_configs;
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::response::CustomResponse;
+use kftray_commons::utils::profile::read_active_profile_configs;
+use log::{
+    error,
+    info,
+    warn,
+};
+
+use crate::core::{
+    deploy_and_forward_pod,
+    start_port_forward,
+    stop_port_forward,
+    stop_proxy_forward,
+};
+use crate::expiry::{
+    set_forward_ttl,
+    subscribe_expiry_events,
+    wait_until_expired,
+};
+use crate::models::kube::HttpLogState;
+use crate::pod_finder::is_proxy_config;
+use crate::reconcile::reconcile_config_states;
+use crate::shutdown::{
+    shutdown,
+    wait_for_shutdown_signal,
+    SHUTDOWN_GRACE,
+};
+use crate::startup::auto_start_configs;
+
+async fn engine_configs(config_ids: &[i64]) -> Result<Vec<Config>, String> {
+    if config_ids.is_empty() {
+        return read_active_profile_configs().await;
+    }
+
+    Ok(get_configs()
+        .await?
+        .into_iter()
+        .filter(|config| config.id.is_some_and(|id| config_ids.contains(&id)))
+        .collect())
+}
+
+pub async fn start_config(
+    config: Config, http_log_state: Arc<HttpLogState>,
+) -> Result<Vec<CustomResponse>, String> {
+    if is_proxy_config(&config) {
+        deploy_and_forward_pod(vec![config], http_log_state).await
+    } else {
+        let protocol = config.protocol.clone();
+        start_port_forward(vec![config], &protocol, http_log_state).await
+    }
+}
+
+pub async fn stop_config(config: &Config) -> Result<CustomResponse, String> {
+    let config_id = config.id.unwrap_or_default();
+    if is_proxy_config(config) {
+        stop_proxy_forward(
+            config_id,
+            &config.namespace,
+            config.service.clone().unwrap_or_default(),
+        )
+        .await
+    } else {
+        stop_port_forward(config_id.to_string()).await
+    }
+}
+
+// waits for the shutdown signal, or until every time limited forward expired
+pub(crate) async fn hold_until_done(config_ids: &[i64], ttl: Option<Duration>) {
+    let Some(ttl) = ttl else {
+        wait_for_shutdown_signal().await;
+        return;
+    };
+
+    let mut expiries = subscribe_expiry_events();
+    for config_id in config_ids {
+        set_forward_ttl(*config_id, ttl);
+    }
+    tokio::select! {
+        _ = wait_for_shutdown_signal() => {}
+        _ = wait_until_expired(&mut expiries, config_ids) => {
+            info!("Every forward reached its time limit");
+        }
+    }
+}
+
+// runs forwards without any UI until the process is told to stop, with no ids
+// every config of the active profile is started. with a ttl each forward stops
+// itself once it elapses
+pub async fn run_engine(
+    config_ids: Vec<i64>, ttl: Option<Duration>, reason: Option<String>,
+) -> Result<(), String> {
+    if let Err(e) = reconcile_config_states().await {
+        error!("Failed to reconcile config states: {}", e);
+    }
+
+    let configs = engine_configs(&config_ids).await?;
+    if configs.is_empty() {
+        return Err("No configs to forward".to_string());
+    }
+
+    let http_log_state = Arc::new(HttpLogState::new());
+    let report = auto_start_configs(configs, reason.as_deref(), http_log_state.clone()).await;
+    for failure in &report.failed {
+        warn!(
+            "Engine failed to start config {}: {}",
+            failure.config_id, failure.error
+        );
+    }
+    let started = report.started;
+
+    info!("Engine started {} forward(s)", started.len());
+
+    #[cfg(target_os = "linux")]
+    tokio::spawn(crate::dbus::serve_dbus(http_log_state.clone()));
+
+    hold_until_done(&started, ttl).await;
+    info!("Engine shutting down");
+    shutdown(SHUTDOWN_GRACE).await.map(|_| ())
+}
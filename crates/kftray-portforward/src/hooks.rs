@@ -0,0 +1,329 @@
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::hook_model::{
+    HookEvent,
+    HookResult,
+};
+use kftray_commons::utils::template::resolve_config_templates;
+use lazy_static::lazy_static;
+use log::{
+    error,
+    info,
+    warn,
+};
+use tokio::process::Command;
+
+const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 30;
+const MAX_HOOK_OUTPUT_BYTES: usize = 64 * 1024;
+const MAX_HOOK_RESULTS_PER_CONFIG: usize = 10;
+
+lazy_static! {
+    static ref HOOK_RESULTS: Mutex<HashMap<i64, Vec<HookResult>>> = Mutex::new(HashMap::new());
+}
+
+fn hook_command(config: &Config, event: HookEvent) -> Option<&str> {
+    let command = match event {
+        HookEvent::OnStart => config.on_start.as_deref(),
+        HookEvent::OnStop => config.on_stop.as_deref(),
+        HookEvent::OnFailure => config.on_failure.as_deref(),
+    };
+
+    command.filter(|command| !command.trim().is_empty())
+}
+
+pub fn hook_env(
+    config: &Config, event: HookEvent, local_port: Option<u16>, error: Option<&str>,
+) -> Vec<(String, String)> {
+    let mut env = vec![
+        ("KFTRAY_EVENT", event.as_str().to_string()),
+        (
+            "KFTRAY_CONFIG_ID",
+            config.id.unwrap_or_default().to_string(),
+        ),
+        ("KFTRAY_ALIAS", config.alias.clone().unwrap_or_default()),
+        ("KFTRAY_SERVICE", config.service.clone().unwrap_or_default()),
+        ("KFTRAY_TARGET", config.target.clone().unwrap_or_default()),
+        ("KFTRAY_NAMESPACE", config.namespace.clone()),
+        ("KFTRAY_CONTEXT", config.context.clone()),
+        ("KFTRAY_PROTOCOL", config.protocol.clone()),
+        (
+            "KFTRAY_WORKLOAD_TYPE",
+            config.workload_type.clone().unwrap_or_default(),
+        ),
+        (
+            "KFTRAY_LOCAL_ADDRESS",
+            config
+                .local_address
+                .clone()
+                .unwrap_or_else(|| "127.0.0.1".to_string()),
+        ),
+        (
+            "KFTRAY_LOCAL_PORT",
+            local_port
+                .or(config.local_port)
+                .unwrap_or_default()
+                .to_string(),
+        ),
+        (
+            "KFTRAY_REMOTE_PORT",
+            config.remote_port.unwrap_or_default().to_string(),
+        ),
+    ];
+
+    if let Some(error) = error {
+        env.push(("KFTRAY_ERROR", error.to_string()));
+    }
+
+    env.into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect()
+}
+
+fn capture_output(bytes: &[u8]) -> String {
+    let bytes = &bytes[..bytes.len().min(MAX_HOOK_OUTPUT_BYTES)];
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+fn shell_command(command: &str) -> Command {
+    if cfg!(target_os = "windows") {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    }
+}
+
+// the hook leads its own process group, so what it started goes down with it
+#[cfg(unix)]
+async fn kill_hook(pid: Option<u32>) {
+    let Some(pid) = pid else {
+        return;
+    };
+    let group = format!("-{}", pid);
+    if let Err(e) = Command::new("kill")
+        .args(["-KILL", "--", &group])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+    {
+        warn!("Failed to kill the processes of hook {}: {}", pid, e);
+    }
+}
+
+#[cfg(not(unix))]
+async fn kill_hook(_pid: Option<u32>) {}
+
+fn store_hook_result(result: &HookResult) {
+    let mut results = HOOK_RESULTS.lock().unwrap();
+    let entries = results.entry(result.config_id).or_default();
+
+    entries.push(result.clone());
+    if entries.len() > MAX_HOOK_RESULTS_PER_CONFIG {
+        let excess = entries.len() - MAX_HOOK_RESULTS_PER_CONFIG;
+        entries.drain(..excess);
+    }
+}
+
+pub fn get_hook_results(config_id: i64) -> Vec<HookResult> {
+    HOOK_RESULTS
+        .lock()
+        .unwrap()
+        .get(&config_id)
+        .cloned()
+        .unwrap_or_default()
+}
+
+pub async fn run_hook(
+    config: &Config, event: HookEvent, local_port: Option<u16>, error: Option<&str>,
+) -> Option<HookResult> {
+    let command = hook_command(config, event)?.to_string();
+    let config_id = config.id.unwrap_or_default();
+    let timeout = Duration::from_secs(
+        config
+            .hook_timeout_secs
+            .unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS),
+    );
+
+    info!(
+        "Running {} hook for config {}: {}",
+        event.as_str(),
+        config_id,
+        command
+    );
+
+    let started = Instant::now();
+    let mut result = HookResult {
+        config_id,
+        event,
+        command: command.clone(),
+        exit_code: None,
+        stdout: String::new(),
+        stderr: String::new(),
+        timed_out: false,
+        duration_ms: 0,
+    };
+
+    let mut shell = shell_command(&command);
+    shell
+        .envs(hook_env(config, event, local_port, error))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    shell.process_group(0);
+    let child = shell.spawn();
+
+    match child {
+        Ok(child) => {
+            let pid = child.id();
+            match tokio::time::timeout(timeout, child.wait_with_output()).await {
+                Ok(Ok(output)) => {
+                    result.exit_code = output.status.code();
+                    result.stdout = capture_output(&output.stdout);
+                    result.stderr = capture_output(&output.stderr);
+                }
+                Ok(Err(e)) => {
+                    result.stderr = format!("Failed to wait for hook: {}", e);
+                }
+                Err(_) => {
+                    kill_hook(pid).await;
+                    result.timed_out = true;
+                    result.stderr = format!("Hook timed out after {}s", timeout.as_secs());
+                }
+            }
+        }
+        Err(e) => {
+            result.stderr = format!("Failed to spawn hook: {}", e);
+        }
+    }
+
+    result.duration_ms = started.elapsed().as_millis() as u64;
+
+    if result.exit_code == Some(0) {
+        info!(
+            "{} hook for config {} finished in {}ms",
+            event.as_str(),
+            config_id,
+            result.duration_ms
+        );
+    } else if result.timed_out {
+        warn!(
+            "{} hook for config {} timed out after {}s",
+            event.as_str(),
+            config_id,
+            timeout.as_secs()
+        );
+    } else {
+        error!(
+            "{} hook for config {} failed (exit code {:?}): {}",
+            event.as_str(),
+            config_id,
+            result.exit_code,
+            result.stderr.trim()
+        );
+    }
+
+    store_hook_result(&result);
+    Some(result)
+}
+
+pub fn spawn_hook(
+    config: Config, event: HookEvent, local_port: Option<u16>, error: Option<String>,
+) {
+    if hook_command(&config, event).is_none() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let config = resolve_config_templates(config.clone())
+            .await
+            .unwrap_or(config);
+        run_hook(&config, event, local_port, error.as_deref()).await;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_env() {
+        let config = Config {
+            id: Some(7),
+            local_port: Some(8080),
+            ..Default::default()
+        };
+
+        let env: HashMap<String, String> =
+            hook_env(&config, HookEvent::OnFailure, Some(9090), Some("boom"))
+                .into_iter()
+                .collect();
+
+        assert_eq!(env["KFTRAY_EVENT"], "on_failure");
+        assert_eq!(env["KFTRAY_CONFIG_ID"], "7");
+        assert_eq!(env["KFTRAY_LOCAL_PORT"], "9090");
+        assert_eq!(env["KFTRAY_ERROR"], "boom");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_hook_captures_output_and_times_out() {
+        let mut config = Config {
+            id: Some(42),
+            on_start: Some("echo \"$KFTRAY_SERVICE\"; echo oops >&2; exit 3".to_string()),
+            on_stop: Some("sleep 5".to_string()),
+            hook_timeout_secs: Some(1),
+            ..Default::default()
+        };
+
+        let result = run_hook(&config, HookEvent::OnStart, None, None)
+            .await
+            .unwrap();
+        assert_eq!(result.exit_code, Some(3));
+        assert_eq!(result.stdout.trim(), "default-service");
+        assert_eq!(result.stderr.trim(), "oops");
+
+        let result = run_hook(&config, HookEvent::OnStop, None, None)
+            .await
+            .unwrap();
+        assert!(result.timed_out);
+
+        config.on_failure = None;
+        assert!(run_hook(&config, HookEvent::OnFailure, None, None)
+            .await
+            .is_none());
+        assert_eq!(get_hook_results(42).len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_timed_out_hook_takes_its_children_down() {
+        let marker = std::env::temp_dir().join(format!("kftray-hook-{}", std::process::id()));
+        let _ = std::fs::remove_file(&marker);
+        let config = Config {
+            id: Some(43),
+            on_start: Some(format!("(sleep 2; touch '{}') & sleep 5", marker.display())),
+            hook_timeout_secs: Some(1),
+            ..Default::default()
+        };
+
+        let result = run_hook(&config, HookEvent::OnStart, None, None)
+            .await
+            .unwrap();
+        assert!(result.timed_out);
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert!(!marker.exists());
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use kftray_commons::config::get_config;
+use kftray_commons::config_state::get_configs_state;
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::settings_model::{
+    HotkeyAction,
+    HotkeyBinding,
+    HotkeyTarget,
+};
+use kftray_commons::utils::profile::read_active_profile_configs;
+use log::info;
+
+use crate::batch::{
+    start_configs_batch,
+    stop_configs_batch,
+    BatchReport,
+};
+use crate::models::kube::HttpLogState;
+
+fn in_target(config: &Config, target: &HotkeyTarget) -> bool {
+    match target {
+        HotkeyTarget::Config(config_id) => config.id == Some(*config_id),
+        HotkeyTarget::Tag(tag) => config
+            .tags
+            .as_ref()
+            .is_some_and(|tags| tags.iter().any(|t| t.eq_ignore_ascii_case(tag))),
+        HotkeyTarget::Context(context) => config.context == *context,
+    }
+}
+
+// the configs the hotkey starts, and the running ones it stops
+pub fn hotkey_plan(
+    configs: Vec<Config>, running: &HashSet<i64>, action: HotkeyAction,
+) -> (Vec<Config>, Vec<Config>) {
+    let (up, down): (Vec<Config>, Vec<Config>) = configs
+        .into_iter()
+        .partition(|config| config.id.is_some_and(|id| running.contains(&id)));
+
+    match action {
+        HotkeyAction::Start => (down, Vec::new()),
+        HotkeyAction::Stop => (Vec::new(), up),
+        HotkeyAction::Toggle if up.is_empty() => (down, Vec::new()),
+        HotkeyAction::Toggle => (Vec::new(), up),
+    }
+}
+
+async fn target_configs(target: &HotkeyTarget) -> Result<Vec<Config>, String> {
+    match target {
+        HotkeyTarget::Config(config_id) => Ok(vec![get_config(*config_id).await?]),
+        // groups follow the active profile, like every other list
+        _ => Ok(read_active_profile_configs()
+            .await?
+            .into_iter()
+            .filter(|config| in_target(config, target))
+            .collect()),
+    }
+}
+
+// a production context still needs a reason, so those starts fail here and
+// show up in the report
+pub async fn run_hotkey(
+    hotkey: &HotkeyBinding, http_log_state: Arc<HttpLogState>,
+) -> Result<BatchReport, String> {
+    let configs = target_configs(&hotkey.target).await?;
+    if configs.is_empty() {
+        return Err(format!("Hotkey {} matches no configs", hotkey.accelerator));
+    }
+
+    let running: HashSet<i64> = get_configs_state()
+        .await?
+        .into_iter()
+        .filter(|state| state.is_running)
+        .map(|state| state.config_id)
+        .collect();
+
+    let (start, stop) = hotkey_plan(configs, &running, hotkey.action);
+    info!(
+        "Hotkey {} starts {} and stops {} config(s)",
+        hotkey.accelerator,
+        start.len(),
+        stop.len()
+    );
+
+    let mut report = stop_configs_batch(&stop).await;
+    if !start.is_empty() {
+        let started = start_configs_batch(start, None, http_log_state).await;
+        report.succeeded.extend(started.succeeded);
+        report.failed.extend(started.failed);
+        report.responses.extend(started.responses);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(id: i64) -> Config {
+        Config {
+            id: Some(id),
+            ..Default::default()
+        }
+    }
+
+    fn ids(configs: &[Config]) -> Vec<i64> {
+        configs.iter().filter_map(|config| config.id).collect()
+    }
+
+    #[test]
+    fn test_hotkey_plan() {
+        let configs = vec![config(1), config(2)];
+        let running = HashSet::from([2]);
+
+        let (start, stop) = hotkey_plan(configs.clone(), &running, HotkeyAction::Toggle);
+        assert!(start.is_empty());
+        assert_eq!(ids(&stop), vec![2]);
+
+        let (start, stop) = hotkey_plan(configs.clone(), &HashSet::new(), HotkeyAction::Toggle);
+        assert_eq!(ids(&start), vec![1, 2]);
+        assert!(stop.is_empty());
+
+        let (start, stop) = hotkey_plan(configs, &running, HotkeyAction::Start);
+        assert_eq!(ids(&start), vec![1]);
+        assert!(stop.is_empty());
+    }
+}
@@ -0,0 +1,641 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::sync::Mutex as StdMutex;
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use lazy_static::lazy_static;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{
+    info,
+    warn,
+};
+
+use crate::models::kube::{
+    HttpLogState,
+    Port,
+    PortForward,
+    Target,
+    TargetSelector,
+};
+
+const METADATA: i16 = 3;
+const FIND_COORDINATOR: i16 = 10;
+const REQUEST_HEADER_LEN: usize = 12;
+const RESPONSE_HEADER_LEN: usize = 8;
+const MAX_REWRITE_BYTES: usize = 16 * 1024 * 1024;
+
+type BrokerForwards = HashMap<(String, i32), (u16, JoinHandle<()>)>;
+
+lazy_static! {
+    static ref BROKER_FORWARDS: Mutex<HashMap<i64, BrokerForwards>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KafkaBroker {
+    pub node_id: i32,
+    pub host: String,
+    pub port: i32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum KafkaResponseChunk {
+    Raw(Vec<u8>),
+    Frame {
+        api_key: i16,
+        api_version: i16,
+        frame: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ResponseState {
+    #[default]
+    Header,
+    Skip(usize),
+    Buffer {
+        api_key: i16,
+        api_version: i16,
+        len: usize,
+    },
+    Passthrough,
+}
+
+// tracks the correlation ids of requests whose responses carry broker
+// addresses so those frames can be buffered and rewritten, everything else
+// is streamed through untouched
+#[derive(Debug, Default)]
+pub struct KafkaSession {
+    requests: HashMap<i32, (i16, i16)>,
+    request_header: Vec<u8>,
+    request_skip: usize,
+    request_passthrough: bool,
+    response: Vec<u8>,
+    response_state: ResponseState,
+}
+
+fn frame_size(header: &[u8]) -> Option<usize> {
+    let size = i32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+    usize::try_from(size).ok().filter(|size| *size >= 4)
+}
+
+impl KafkaSession {
+    pub fn client_data(&mut self, mut data: &[u8]) {
+        while !data.is_empty() && !self.request_passthrough {
+            if self.request_skip > 0 {
+                let skipped = self.request_skip.min(data.len());
+                self.request_skip -= skipped;
+                data = &data[skipped..];
+                continue;
+            }
+
+            let needed = (REQUEST_HEADER_LEN - self.request_header.len()).min(data.len());
+            self.request_header.extend_from_slice(&data[..needed]);
+            data = &data[needed..];
+            if self.request_header.len() < REQUEST_HEADER_LEN {
+                return;
+            }
+
+            let header = std::mem::take(&mut self.request_header);
+            let Some(size) = frame_size(&header).filter(|size| *size >= 8) else {
+                warn!("Invalid kafka request frame, no longer rewriting this connection");
+                self.request_passthrough = true;
+                return;
+            };
+
+            let api_key = i16::from_be_bytes([header[4], header[5]]);
+            let api_version = i16::from_be_bytes([header[6], header[7]]);
+            let correlation_id = i32::from_be_bytes([header[8], header[9], header[10], header[11]]);
+            if matches!(api_key, METADATA | FIND_COORDINATOR) {
+                self.requests.insert(correlation_id, (api_key, api_version));
+            }
+            self.request_skip = size + 4 - REQUEST_HEADER_LEN;
+        }
+    }
+
+    pub fn server_data(&mut self, mut data: &[u8]) -> Vec<KafkaResponseChunk> {
+        let mut chunks = Vec::new();
+
+        while !data.is_empty() {
+            match self.response_state {
+                ResponseState::Passthrough => {
+                    chunks.push(KafkaResponseChunk::Raw(data.to_vec()));
+                    break;
+                }
+                ResponseState::Skip(remaining) => {
+                    let len = remaining.min(data.len());
+                    chunks.push(KafkaResponseChunk::Raw(data[..len].to_vec()));
+                    data = &data[len..];
+                    self.response_state = match remaining - len {
+                        0 => ResponseState::Header,
+                        remaining => ResponseState::Skip(remaining),
+                    };
+                }
+                ResponseState::Buffer {
+                    api_key,
+                    api_version,
+                    len,
+                } => {
+                    let needed = (len - self.response.len()).min(data.len());
+                    self.response.extend_from_slice(&data[..needed]);
+                    data = &data[needed..];
+                    if self.response.len() == len {
+                        chunks.push(KafkaResponseChunk::Frame {
+                            api_key,
+                            api_version,
+                            frame: std::mem::take(&mut self.response),
+                        });
+                        self.response_state = ResponseState::Header;
+                    }
+                }
+                ResponseState::Header => {
+                    let needed = (RESPONSE_HEADER_LEN - self.response.len()).min(data.len());
+                    self.response.extend_from_slice(&data[..needed]);
+                    data = &data[needed..];
+                    if self.response.len() < RESPONSE_HEADER_LEN {
+                        break;
+                    }
+
+                    let Some(size) = frame_size(&self.response) else {
+                        warn!("Invalid kafka response frame, no longer rewriting this connection");
+                        chunks.push(KafkaResponseChunk::Raw(std::mem::take(&mut self.response)));
+                        self.response_state = ResponseState::Passthrough;
+                        continue;
+                    };
+
+                    let header = &self.response;
+                    let correlation_id =
+                        i32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+                    self.response_state = match self.requests.remove(&correlation_id) {
+                        Some((api_key, api_version)) if size + 4 <= MAX_REWRITE_BYTES => {
+                            ResponseState::Buffer {
+                                api_key,
+                                api_version,
+                                len: size + 4,
+                            }
+                        }
+                        _ => {
+                            chunks
+                                .push(KafkaResponseChunk::Raw(std::mem::take(&mut self.response)));
+                            match size + 4 - RESPONSE_HEADER_LEN {
+                                0 => ResponseState::Header,
+                                remaining => ResponseState::Skip(remaining),
+                            }
+                        }
+                    };
+                }
+            }
+        }
+
+        chunks
+    }
+}
+
+struct FrameRewriter<'a> {
+    input: &'a [u8],
+    position: usize,
+    output: Vec<u8>,
+    flexible: bool,
+}
+
+impl<'a> FrameRewriter<'a> {
+    fn new(input: &'a [u8], flexible: bool) -> Self {
+        Self {
+            input,
+            position: 0,
+            output: Vec::with_capacity(input.len()),
+            flexible,
+        }
+    }
+
+    fn read(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .input
+            .get(self.position..self.position + len)
+            .ok_or_else(|| "truncated kafka response".to_string())?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn copy(&mut self, len: usize) -> Result<(), String> {
+        let bytes = self.read(len)?;
+        self.output.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn read_i32(&mut self) -> Result<i32, String> {
+        let bytes = self.read(4)?;
+        Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_uvarint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..35).step_by(7) {
+            let byte = self.read(1)?[0];
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("invalid varint in kafka response".to_string())
+    }
+
+    fn write_uvarint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.output.push((value as u8 & 0x7F) | 0x80);
+            value >>= 7;
+        }
+        self.output.push(value as u8);
+    }
+
+    fn copy_uvarint(&mut self) -> Result<u64, String> {
+        let value = self.read_uvarint()?;
+        self.write_uvarint(value);
+        Ok(value)
+    }
+
+    fn copy_array_len(&mut self) -> Result<usize, String> {
+        if self.flexible {
+            return Ok(self.copy_uvarint()?.saturating_sub(1) as usize);
+        }
+
+        let len = self.read_i32()?;
+        self.output.extend_from_slice(&len.to_be_bytes());
+        Ok(usize::try_from(len).unwrap_or_default())
+    }
+
+    fn read_string(&mut self) -> Result<Option<&'a [u8]>, String> {
+        let len = if self.flexible {
+            match self.read_uvarint()? {
+                0 => return Ok(None),
+                len => len as usize - 1,
+            }
+        } else {
+            let bytes = self.read(2)?;
+            match i16::from_be_bytes([bytes[0], bytes[1]]) {
+                len if len < 0 => return Ok(None),
+                len => len as usize,
+            }
+        };
+
+        self.read(len).map(Some)
+    }
+
+    fn write_string(&mut self, value: &[u8]) {
+        if self.flexible {
+            self.write_uvarint(value.len() as u64 + 1);
+        } else {
+            self.output
+                .extend_from_slice(&(value.len() as i16).to_be_bytes());
+        }
+        self.output.extend_from_slice(value);
+    }
+
+    fn copy_string(&mut self) -> Result<(), String> {
+        let start = self.position;
+        self.read_string()?;
+        self.output
+            .extend_from_slice(&self.input[start..self.position]);
+        Ok(())
+    }
+
+    fn copy_tagged_fields(&mut self) -> Result<(), String> {
+        if !self.flexible {
+            return Ok(());
+        }
+
+        for _ in 0..self.copy_uvarint()? {
+            self.copy_uvarint()?;
+            let size = self.copy_uvarint()?;
+            self.copy(size as usize)?;
+        }
+        Ok(())
+    }
+
+    fn endpoint(
+        &mut self, rewrite: &mut impl FnMut(&KafkaBroker) -> Option<(String, i32)>,
+    ) -> Result<(), String> {
+        let node_id = self.read_i32()?;
+        let host = self.read_string()?.unwrap_or_default();
+        let port = self.read_i32()?;
+
+        let broker = KafkaBroker {
+            node_id,
+            host: String::from_utf8_lossy(host).into_owned(),
+            port,
+        };
+
+        self.output.extend_from_slice(&node_id.to_be_bytes());
+        match rewrite(&broker) {
+            Some((host, port)) => {
+                self.write_string(host.as_bytes());
+                self.output.extend_from_slice(&port.to_be_bytes());
+            }
+            None => {
+                self.write_string(host);
+                self.output.extend_from_slice(&port.to_be_bytes());
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.output.extend_from_slice(&self.input[self.position..]);
+
+        let mut frame = (self.output.len() as i32).to_be_bytes().to_vec();
+        frame.extend(self.output);
+        frame
+    }
+}
+
+fn rewrite_endpoints(
+    api_key: i16, api_version: i16, frame: &[u8],
+    mut rewrite: impl FnMut(&KafkaBroker) -> Option<(String, i32)>,
+) -> Result<Vec<u8>, String> {
+    let flexible = match api_key {
+        METADATA => api_version >= 9,
+        FIND_COORDINATOR => api_version >= 3,
+        _ => return Ok(frame.to_vec()),
+    };
+
+    let mut rewriter = FrameRewriter::new(frame.get(4..).unwrap_or_default(), flexible);
+    rewriter.copy(4)?;
+    rewriter.copy_tagged_fields()?;
+
+    match api_key {
+        METADATA => {
+            if api_version >= 3 {
+                rewriter.copy(4)?;
+            }
+            for _ in 0..rewriter.copy_array_len()? {
+                rewriter.endpoint(&mut rewrite)?;
+                if api_version >= 1 {
+                    rewriter.copy_string()?;
+                }
+                rewriter.copy_tagged_fields()?;
+            }
+        }
+        _ if api_version >= 4 => {
+            rewriter.copy(4)?;
+            for _ in 0..rewriter.copy_array_len()? {
+                rewriter.copy_string()?;
+                rewriter.endpoint(&mut rewrite)?;
+                rewriter.copy(2)?;
+                rewriter.copy_string()?;
+                rewriter.copy_tagged_fields()?;
+            }
+        }
+        _ => {
+            if api_version >= 1 {
+                rewriter.copy(4)?;
+            }
+            rewriter.copy(2)?;
+            if api_version >= 1 {
+                rewriter.copy_string()?;
+            }
+            rewriter.endpoint(&mut rewrite)?;
+        }
+    }
+
+    Ok(rewriter.finish())
+}
+
+pub fn parse_kafka_brokers(
+    api_key: i16, api_version: i16, frame: &[u8],
+) -> Result<Vec<KafkaBroker>, String> {
+    let mut brokers = Vec::new();
+    rewrite_endpoints(api_key, api_version, frame, |broker| {
+        if !broker.host.is_empty() && broker.port > 0 {
+            brokers.push(broker.clone());
+        }
+        None
+    })?;
+    Ok(brokers)
+}
+
+pub fn rewrite_kafka_brokers(
+    api_key: i16, api_version: i16, frame: &[u8], local_host: &str,
+    local_ports: &HashMap<(String, i32), u16>,
+) -> Result<Vec<u8>, String> {
+    rewrite_endpoints(api_key, api_version, frame, |broker| {
+        local_ports
+            .get(&(broker.host.clone(), broker.port))
+            .map(|port| (local_host.to_string(), i32::from(*port)))
+    })
+}
+
+fn broker_pod_name(host: &str) -> Option<&str> {
+    if host.parse::<IpAddr>().is_ok() {
+        return None;
+    }
+    host.split('.').next().filter(|name| !name.is_empty())
+}
+
+fn advertised_host(pf: &PortForward) -> String {
+    match pf.local_address.as_deref() {
+        None | Some("0.0.0.0") => "127.0.0.1".to_string(),
+        Some(address) => address.to_string(),
+    }
+}
+
+// boxed to break the type cycle with the connection task that awaits it
+fn broker_local_port<'a>(
+    pf: &'a PortForward, broker: &'a KafkaBroker,
+) -> BoxFuture<'a, anyhow::Result<u16>> {
+    async move {
+        let mut forwards = BROKER_FORWARDS.lock().await;
+        let brokers = forwards.entry(pf.config_id).or_default();
+        let key = (broker.host.clone(), broker.port);
+        if let Some((port, _)) = brokers.get(&key) {
+            return Ok(*port);
+        }
+
+        let pod_name = broker_pod_name(&broker.host).ok_or_else(|| {
+            anyhow::anyhow!("Cannot map kafka broker address '{}' to a pod", broker.host)
+        })?;
+
+        let sibling = PortForward {
+            target: Target {
+                selector: TargetSelector::PodName(pod_name.to_string()),
+                port: Port::from(broker.port),
+                namespace: pf.target.namespace.clone(),
+            },
+            local_port: None,
+            workload_type: "pod".to_string(),
+            connection: Arc::new(Mutex::new(None)),
+            ..pf.clone()
+        };
+
+        let (port, handle) = sibling
+            .port_forward_tcp(Arc::new(HttpLogState::new()))
+            .await?;
+        info!(
+            "Forwarding kafka broker {} ({}:{}) on local port {}",
+            broker.node_id, broker.host, broker.port, port
+        );
+
+        brokers.insert(key, (port, handle));
+        Ok(port)
+    }
+    .boxed()
+}
+
+async fn rewrite_frame(
+    pf: &PortForward, api_key: i16, api_version: i16, frame: Vec<u8>,
+) -> Vec<u8> {
+    let brokers = match parse_kafka_brokers(api_key, api_version, &frame) {
+        Ok(brokers) => brokers,
+        Err(e) => {
+            warn!("Failed to parse kafka response: {}", e);
+            return frame;
+        }
+    };
+
+    let mut local_ports = HashMap::new();
+    for broker in brokers {
+        match broker_local_port(pf, &broker).await {
+            Ok(port) => {
+                local_ports.insert((broker.host, broker.port), port);
+            }
+            Err(e) => warn!("Failed to forward kafka broker {}: {}", broker.node_id, e),
+        }
+    }
+
+    rewrite_kafka_brokers(
+        api_key,
+        api_version,
+        &frame,
+        &advertised_host(pf),
+        &local_ports,
+    )
+    .unwrap_or_else(|e| {
+        warn!("Failed to rewrite kafka response: {}", e);
+        frame
+    })
+}
+
+pub(crate) async fn rewrite_kafka_responses(
+    pf: &PortForward, session: &StdMutex<KafkaSession>, data: &[u8],
+) -> Vec<u8> {
+    let chunks = session.lock().unwrap().server_data(data);
+
+    let mut rewritten = Vec::with_capacity(data.len());
+    for chunk in chunks {
+        match chunk {
+            KafkaResponseChunk::Raw(bytes) => rewritten.extend(bytes),
+            KafkaResponseChunk::Frame {
+                api_key,
+                api_version,
+                frame,
+            } => rewritten.extend(rewrite_frame(pf, api_key, api_version, frame).await),
+        }
+    }
+    rewritten
+}
+
+pub async fn stop_kafka_broker_forwards(config_id: Option<i64>) {
+    let mut forwards = BROKER_FORWARDS.lock().await;
+    let stopped: Vec<BrokerForwards> = match config_id {
+        Some(config_id) => forwards.remove(&config_id).into_iter().collect(),
+        None => forwards.drain().map(|(_, brokers)| brokers).collect(),
+    };
+
+    for (_, handle) in stopped
+        .into_iter()
+        .flat_map(|brokers| brokers.into_values())
+    {
+        handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata_v1() -> Vec<u8> {
+        let mut body = 7i32.to_be_bytes().to_vec();
+        body.extend_from_slice(&1i32.to_be_bytes());
+        body.extend_from_slice(&0i32.to_be_bytes());
+        let host = b"kafka-0.kafka-headless.data.svc.cluster.local";
+        body.extend_from_slice(&(host.len() as i16).to_be_bytes());
+        body.extend_from_slice(host);
+        body.extend_from_slice(&9092i32.to_be_bytes());
+        body.extend_from_slice(&(-1i16).to_be_bytes());
+        body.extend_from_slice(b"\x00\x00\x00\x01topics");
+
+        let mut frame = (body.len() as i32).to_be_bytes().to_vec();
+        frame.extend(body);
+        frame
+    }
+
+    #[test]
+    fn test_rewrite_metadata_brokers() {
+        let frame = metadata_v1();
+        let brokers = parse_kafka_brokers(METADATA, 1, &frame).unwrap();
+        assert_eq!(
+            brokers,
+            vec![KafkaBroker {
+                node_id: 0,
+                host: "kafka-0.kafka-headless.data.svc.cluster.local".to_string(),
+                port: 9092,
+            }]
+        );
+        assert_eq!(broker_pod_name(&brokers[0].host), Some("kafka-0"));
+
+        let local_ports = HashMap::from([((brokers[0].host.clone(), 9092), 40001)]);
+        let rewritten =
+            rewrite_kafka_brokers(METADATA, 1, &frame, "127.0.0.1", &local_ports).unwrap();
+
+        let mut expected = 7i32.to_be_bytes().to_vec();
+        expected.extend_from_slice(&1i32.to_be_bytes());
+        expected.extend_from_slice(&0i32.to_be_bytes());
+        expected.extend_from_slice(b"\x00\x09127.0.0.1");
+        expected.extend_from_slice(&40001i32.to_be_bytes());
+        expected.extend_from_slice(&(-1i16).to_be_bytes());
+        expected.extend_from_slice(b"\x00\x00\x00\x01topics");
+        assert_eq!(&rewritten[4..], &expected[..]);
+        assert_eq!(
+            i32::from_be_bytes([rewritten[0], rewritten[1], rewritten[2], rewritten[3]]) as usize,
+            expected.len()
+        );
+    }
+
+    #[test]
+    fn test_session_buffers_only_tracked_responses() {
+        let mut session = KafkaSession::default();
+
+        let mut request = 10i32.to_be_bytes().to_vec();
+        request.extend_from_slice(&METADATA.to_be_bytes());
+        request.extend_from_slice(&1i16.to_be_bytes());
+        request.extend_from_slice(&7i32.to_be_bytes());
+        request.extend_from_slice(&[0xFF, 0xFF]);
+        session.client_data(&request[..5]);
+        session.client_data(&request[5..]);
+
+        let mut untracked = 6i32.to_be_bytes().to_vec();
+        untracked.extend_from_slice(&3i32.to_be_bytes());
+        untracked.extend_from_slice(&[0, 0]);
+
+        let metadata = metadata_v1();
+        let mut server = untracked.clone();
+        server.extend_from_slice(&metadata[..20]);
+
+        assert_eq!(
+            session.server_data(&server),
+            vec![
+                KafkaResponseChunk::Raw(untracked[..8].to_vec()),
+                KafkaResponseChunk::Raw(untracked[8..].to_vec()),
+            ]
+        );
+        assert_eq!(
+            session.server_data(&metadata[20..]),
+            vec![KafkaResponseChunk::Frame {
+                api_key: METADATA,
+                api_version: 1,
+                frame: metadata,
+            }]
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use k8s_openapi::api::core::v1::Service;
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use kftray_commons::config::get_config;
+use kftray_commons::models::config_model::Config;
+use kftray_commons::utils::kubectl_export::{
+    kubectl_commands,
+    kubectl_script,
+};
+use kftray_commons::utils::template::resolve_config_templates;
+use kube::Api;
+use log::{
+    info,
+    warn,
+};
+
+use crate::pod_finder::config_client;
+use crate::reload::running_forwards;
+
+// kftray forwards to the port on the pod while `kubectl port-forward svc/..`
+// takes the service port, so this finds the service port that lands on it.
+// the pod port is kept when the service can't be read
+async fn service_port(config: &Config) -> Option<u16> {
+    let remote_port = i32::from(config.remote_port?);
+    let client = config_client(config)
+        .await
+        .map_err(|e| warn!("Failed to look up the service of {:?}: {}", config.id, e))
+        .ok()?;
+    let services: Api<Service> = Api::namespaced(client, &config.namespace);
+    let service = services.get_opt(config.service.as_deref()?).await.ok()??;
+
+    let ports = service.spec?.ports?;
+    ports
+        .iter()
+        .find(|port| match &port.target_port {
+            Some(IntOrString::Int(target)) => *target == remote_port,
+            Some(IntOrString::String(_)) => false,
+            None => port.port == remote_port,
+        })
+        .or_else(|| ports.iter().find(|port| port.port == remote_port))
+        .and_then(|port| u16::try_from(port.port).ok())
+}
+
+// the given configs, or every running forward with the port it actually got
+async fn export_configs(config_ids: Option<Vec<i64>>) -> Result<Vec<Config>, String> {
+    let mut configs = match config_ids {
+        Some(config_ids) => {
+            let mut configs = Vec::with_capacity(config_ids.len());
+            for config_id in config_ids {
+                configs.push(resolve_config_templates(get_config(config_id).await?).await?);
+            }
+            configs
+        }
+        None => running_forwards().await?,
+    };
+    if configs.is_empty() {
+        return Err("No forwards to export, start some or pick configs".to_string());
+    }
+
+    for config in &mut configs {
+        if matches!(config.workload_type.as_deref(), Some("service") | None) {
+            if let Some(port) = service_port(config).await {
+                config.remote_port = Some(port);
+            }
+        }
+    }
+    Ok(configs)
+}
+
+// kubectl commands that reproduce the forwards without kftray, one per line or
+// as a script that runs them all
+pub async fn export_kubectl(config_ids: Option<Vec<i64>>, script: bool) -> Result<String, String> {
+    let configs = export_configs(config_ids).await?;
+    info!(
+        "Exporting {} forward(s) as kubectl {}",
+        configs.len(),
+        if script { "script" } else { "commands" }
+    );
+    Ok(if script {
+        kubectl_script(&configs)
+    } else {
+        kubectl_commands(&configs)
+    })
+}
@@ -1,12 +1,153 @@
+pub mod adhoc;
+pub mod audit;
+pub mod batch;
+pub mod benchmark;
+pub mod cancel;
+pub mod capture;
+pub mod circuit_breaker;
 pub mod client;
+pub mod cloud;
+pub mod cluster_network;
+pub mod cluster_probe;
+pub mod compose_export;
+pub mod connection_limit;
+pub mod context_group;
+pub mod context_migration;
 pub mod core;
+#[cfg(target_os = "linux")]
+pub mod dbus;
+pub mod describe;
+pub mod discovery;
+pub mod expiry;
+pub mod external_forward;
+pub mod fanout;
+pub mod firewall;
+pub mod grpc_health;
+pub mod headless;
+pub mod hooks;
+pub mod hotkeys;
+pub mod kafka;
+pub mod kubectl_export;
+pub mod lifecycle;
+pub mod mappings;
+pub mod mdns;
+pub mod mesh;
+pub mod mock_rules;
 pub mod models;
+pub mod openapi_spec;
+pub mod outcome;
+pub mod overrides;
+pub mod ownership;
+pub mod pac;
+pub mod pending_start;
 pub mod pod_finder;
+pub mod pod_logs;
+pub mod pod_watch;
 pub mod port_forward;
+pub mod port_manifest;
+pub mod profile;
+pub mod progress;
+pub mod protocol;
+pub mod proxy_diagnosis;
+pub mod proxy_pool;
+pub mod quick_connect;
+pub mod rbac;
+pub mod readiness;
+pub mod reconcile;
+pub mod reload;
+pub mod rollback;
+pub mod session;
+pub mod shutdown;
+pub mod smoke_test;
+pub mod spdy;
+pub mod ssh_mirror;
+pub mod ssm;
+pub mod startup;
+pub mod target_switch;
+pub mod teleport;
+#[cfg(test)]
+mod test_cluster;
+pub mod tool_import;
+pub mod traffic;
+pub mod traffic_script;
+pub mod traffic_split;
+pub mod transport;
+pub mod tray_menu;
+pub mod udp;
+pub mod validation;
+pub mod wire;
 
 pub use core::*;
 
+pub use adhoc::*;
+pub use audit::*;
+pub use batch::*;
+pub use benchmark::*;
+pub use cancel::*;
+pub use capture::*;
+pub use circuit_breaker::*;
 pub use client::*;
+pub use cloud::*;
+pub use cluster_network::*;
+pub use cluster_probe::*;
+pub use compose_export::*;
+pub use connection_limit::*;
+pub use context_group::*;
+pub use context_migration::*;
+pub use describe::*;
+pub use discovery::*;
+pub use expiry::*;
+pub use external_forward::*;
+pub use fanout::*;
+pub use firewall::*;
+pub use grpc_health::*;
+pub use headless::*;
+pub use hooks::*;
+pub use hotkeys::*;
+pub use kafka::*;
+pub use kubectl_export::*;
+pub use lifecycle::*;
+pub use mappings::*;
+pub use mdns::*;
+pub use mesh::*;
+pub use mock_rules::*;
 pub use models::*;
+pub use outcome::*;
+pub use overrides::*;
+pub use ownership::*;
+pub use pac::*;
+pub use pending_start::*;
 pub use pod_finder::*;
+pub use pod_logs::*;
+pub use pod_watch::*;
 pub use port_forward::*;
+pub use port_manifest::*;
+pub use profile::*;
+pub use progress::*;
+pub use protocol::*;
+pub use proxy_diagnosis::*;
+pub use proxy_pool::*;
+pub use quick_connect::*;
+pub use rbac::*;
+pub use readiness::*;
+pub use reconcile::*;
+pub use reload::*;
+pub use rollback::*;
+pub use session::*;
+pub use shutdown::*;
+pub use smoke_test::*;
+pub use spdy::*;
+pub use ssh_mirror::*;
+pub use ssm::*;
+pub use startup::*;
+pub use target_switch::*;
+pub use teleport::*;
+pub use tool_import::*;
+pub use traffic::*;
+pub use traffic_script::*;
+pub use traffic_split::*;
+pub use transport::*;
+pub use tray_menu::*;
+pub use udp::*;
+pub use validation::*;
+pub use wire::*;
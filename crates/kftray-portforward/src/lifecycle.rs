@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
+use kftray_commons::models::config_state_model::ForwardPhase;
+use lazy_static::lazy_static;
+use log::debug;
+use tokio::sync::{
+    Mutex as AsyncMutex,
+    OwnedMutexGuard,
+};
+
+lazy_static! {
+    static ref CONFIG_LOCKS: Mutex<HashMap<i64, Arc<AsyncMutex<()>>>> = Mutex::new(HashMap::new());
+    static ref FORWARD_PHASES: Mutex<HashMap<i64, ForwardPhase>> = Mutex::new(HashMap::new());
+}
+
+pub fn can_transition(from: ForwardPhase, to: ForwardPhase) -> bool {
+    use ForwardPhase::*;
+
+    // stopping an already stopped config is allowed so stale persisted state
+    // can always be cleared
+    matches!(
+        (from, to),
+        (Stopped | Failed, Starting)
+            | (Starting, Running | Failed)
+            | (Running | Stopped | Failed, Stopping)
+            | (Stopping, Stopped | Failed)
+    )
+}
+
+fn config_lock(config_id: i64) -> Arc<AsyncMutex<()>> {
+    CONFIG_LOCKS
+        .lock()
+        .unwrap()
+        .entry(config_id)
+        .or_default()
+        .clone()
+}
+
+// locks are always taken in ascending id order so overlapping batches can't
+// deadlock each other
+pub(crate) async fn lock_configs(
+    config_ids: impl IntoIterator<Item = i64>,
+) -> Vec<OwnedMutexGuard<()>> {
+    let mut config_ids: Vec<i64> = config_ids.into_iter().collect();
+    config_ids.sort_unstable();
+    config_ids.dedup();
+
+    let mut guards = Vec::with_capacity(config_ids.len());
+    for config_id in config_ids {
+        guards.push(config_lock(config_id).lock_owned().await);
+    }
+    guards
+}
+
+pub(crate) async fn lock_all_configs() -> Vec<OwnedMutexGuard<()>> {
+    let config_ids: Vec<i64> = CONFIG_LOCKS.lock().unwrap().keys().copied().collect();
+    lock_configs(config_ids).await
+}
+
+pub(crate) fn transition(config_id: i64, to: ForwardPhase) -> Result<(), String> {
+    let mut phases = FORWARD_PHASES.lock().unwrap();
+    let from = phases.get(&config_id).copied().unwrap_or_default();
+
+    if !can_transition(from, to) {
+        return Err(match from {
+            ForwardPhase::Running => format!("Config {} is already running", config_id),
+            _ => format!("Config {} can't go from {:?} to {:?}", config_id, from, to),
+        });
+    }
+
+    debug!("Config {} moved from {:?} to {:?}", config_id, from, to);
+    phases.insert(config_id, to);
+    Ok(())
+}
+
+// used once an operation has finished, whatever the phase was before
+pub(crate) fn set_phase(config_id: i64, phase: ForwardPhase) {
+    FORWARD_PHASES.lock().unwrap().insert(config_id, phase);
+}
+
+pub fn get_forward_phase(config_id: i64) -> ForwardPhase {
+    FORWARD_PHASES
+        .lock()
+        .unwrap()
+        .get(&config_id)
+        .copied()
+        .unwrap_or_default()
+}
+
+pub fn get_forward_phases() -> HashMap<i64, ForwardPhase> {
+    FORWARD_PHASES.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phase_transitions() {
+        use ForwardPhase::*;
+
+        assert!(can_transition(Stopped, Starting));
+        assert!(can_transition(Failed, Starting));
+        assert!(!can_transition(Running, Starting));
+        assert!(!can_transition(Stopping, Starting));
+        assert!(can_transition(Starting, Running));
+        assert!(can_transition(Stopped, Stopping));
+        assert!(!can_transition(Starting, Stopping));
+        assert!(can_transition(Stopping, Stopped));
+    }
+
+    #[tokio::test]
+    async fn test_config_lock_serializes() {
+        let guards = lock_configs([9_101, 9_100, 9_101]).await;
+        assert_eq!(guards.len(), 2);
+        assert!(config_lock(9_100).try_lock().is_err());
+
+        drop(guards);
+        assert!(config_lock(9_100).try_lock().is_ok());
+    }
+}
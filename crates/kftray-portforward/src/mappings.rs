@@ -0,0 +1,237 @@
+use std::path::Path;
+use std::sync::atomic::{
+    AtomicBool,
+    AtomicU64,
+    Ordering,
+};
+use std::time::{
+    Duration,
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+use kftray_commons::models::config_model::Config;
+use kftray_commons::utils::config_dir::get_forward_mappings_path;
+use lazy_static::lazy_static;
+use log::{
+    debug,
+    warn,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use tokio::sync::broadcast;
+
+use crate::reload::running_forwards;
+
+// bumped when a field changes meaning or goes away, added fields don't
+pub const MAPPINGS_SCHEMA_VERSION: u32 = 1;
+const MAPPINGS_EVENT_CAPACITY: usize = 16;
+// the starts and stops of one batch end up in a single write
+const PUBLISH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+lazy_static! {
+    static ref MAPPINGS_EVENTS: broadcast::Sender<ForwardMappings> =
+        broadcast::channel(MAPPINGS_EVENT_CAPACITY).0;
+}
+
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static PUBLISH_SCHEDULED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForwardMapping {
+    pub config_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    pub context: String,
+    pub namespace: String,
+    pub workload_type: String,
+    // the service, pod selector or proxied address
+    pub target: String,
+    pub protocol: String,
+    pub local_address: String,
+    pub local_port: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_port: Option<u16>,
+    // set when the alias resolves through the hosts file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForwardMappings {
+    pub schema_version: u32,
+    // goes up with every publish of this kftray run
+    pub generation: u64,
+    pub updated_at: u64,
+    pub forwards: Vec<ForwardMapping>,
+}
+
+// forwards still waiting for a port have nothing to map yet
+pub fn forward_mapping(config: &Config) -> Option<ForwardMapping> {
+    let workload_type = config
+        .workload_type
+        .clone()
+        .unwrap_or_else(|| "service".to_string());
+    let target = match workload_type.as_str() {
+        "pod" => config.target.clone(),
+        "proxy" => config.remote_address.clone(),
+        _ => config.service.clone(),
+    };
+
+    Some(ForwardMapping {
+        config_id: config.id?,
+        alias: config.alias.clone().filter(|alias| !alias.is_empty()),
+        context: config.context.clone(),
+        namespace: config.namespace.clone(),
+        workload_type,
+        target: target.unwrap_or_default(),
+        protocol: config.protocol.clone(),
+        local_address: config
+            .local_address
+            .clone()
+            .filter(|address| !address.is_empty())
+            .unwrap_or_else(|| "127.0.0.1".to_string()),
+        local_port: config.local_port.filter(|port| *port != 0)?,
+        remote_port: config.remote_port,
+        hostname: config
+            .alias
+            .clone()
+            .filter(|alias| config.domain_enabled == Some(true) && !alias.is_empty()),
+    })
+}
+
+pub fn forward_mappings(forwards: &[Config], generation: u64) -> ForwardMappings {
+    ForwardMappings {
+        schema_version: MAPPINGS_SCHEMA_VERSION,
+        generation,
+        updated_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default(),
+        forwards: forwards.iter().filter_map(forward_mapping).collect(),
+    }
+}
+
+// written next to the file and renamed over it, so a reader never sees half
+// of one
+pub(crate) fn write_json_file<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    let staging = path.with_extension("json.tmp");
+    std::fs::write(&staging, json).map_err(|e| e.to_string())?;
+    std::fs::rename(&staging, path)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+pub async fn get_forward_mappings() -> Result<ForwardMappings, String> {
+    let forwards = running_forwards().await?;
+    Ok(forward_mappings(
+        &forwards,
+        GENERATION.load(Ordering::SeqCst),
+    ))
+}
+
+// rewrites the mappings file and tells the subscribers
+pub async fn publish_forward_mappings() -> Result<ForwardMappings, String> {
+    let forwards = running_forwards().await?;
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let mappings = forward_mappings(&forwards, generation);
+    write_json_file(&get_forward_mappings_path()?, &mappings)?;
+    debug!(
+        "Published {} forward mapping(s), generation {}",
+        mappings.forwards.len(),
+        generation
+    );
+    let _ = MAPPINGS_EVENTS.send(mappings.clone());
+    Ok(mappings)
+}
+
+pub fn subscribe_forward_mappings() -> broadcast::Receiver<ForwardMappings> {
+    MAPPINGS_EVENTS.subscribe()
+}
+
+// called whenever the running forwards change, changes that come in while a
+// publish is pending are picked up by it
+pub(crate) fn schedule_mappings_publish() {
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    if PUBLISH_SCHEDULED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    runtime.spawn(async {
+        tokio::time::sleep(PUBLISH_DEBOUNCE).await;
+        PUBLISH_SCHEDULED.store(false, Ordering::SeqCst);
+        if let Err(e) = publish_forward_mappings().await {
+            warn!("Failed to publish forward mappings: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forward_mappings() {
+        let forwards = vec![
+            Config {
+                id: Some(1),
+                alias: Some("api.local".to_string()),
+                service: Some("api".to_string()),
+                workload_type: Some("service".to_string()),
+                protocol: "tcp".to_string(),
+                local_port: Some(8080),
+                remote_port: Some(80),
+                domain_enabled: Some(true),
+                local_address: Some("127.0.0.2".to_string()),
+                ..Default::default()
+            },
+            Config {
+                id: Some(2),
+                workload_type: Some("proxy".to_string()),
+                remote_address: Some("db.internal".to_string()),
+                local_port: Some(5432),
+                ..Default::default()
+            },
+            Config {
+                id: Some(3),
+                local_port: Some(0),
+                ..Default::default()
+            },
+        ];
+        let mappings = forward_mappings(&forwards, 7);
+        assert_eq!(mappings.schema_version, MAPPINGS_SCHEMA_VERSION);
+        assert_eq!(mappings.generation, 7);
+        assert_eq!(mappings.forwards.len(), 2);
+
+        let api = &mappings.forwards[0];
+        assert_eq!(api.target, "api");
+        assert_eq!(api.local_address, "127.0.0.2");
+        assert_eq!(api.hostname.as_deref(), Some("api.local"));
+
+        let db = &mappings.forwards[1];
+        assert_eq!(db.target, "db.internal");
+        assert_eq!(db.local_address, "127.0.0.1");
+        assert_eq!(db.hostname, None);
+    }
+
+    #[test]
+    fn test_write_json_file() {
+        let dir = std::env::temp_dir().join(format!("kftray-mappings-{}", std::process::id()));
+        let path = dir.join("nested").join("mappings.json");
+
+        write_json_file(&path, &forward_mappings(&[], 1)).unwrap();
+        write_json_file(&path, &forward_mappings(&[], 2)).unwrap();
+
+        let written: ForwardMappings =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written.generation, 2);
+        assert!(!path.with_extension("json.tmp").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
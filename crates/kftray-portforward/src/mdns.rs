@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+use kftray_commons::models::config_model::Config;
+use lazy_static::lazy_static;
+use log::{
+    debug,
+    info,
+};
+use mdns_sd::{
+    ServiceDaemon,
+    ServiceInfo,
+};
+
+const MDNS_SERVICE_TYPE: &str = "_http._tcp.local.";
+
+lazy_static! {
+    static ref MDNS_DAEMON: Mutex<Option<ServiceDaemon>> = Mutex::new(None);
+    static ref ADVERTISED: Mutex<HashMap<i64, String>> = Mutex::new(HashMap::new());
+}
+
+pub fn is_lan_reachable(addr: IpAddr) -> bool {
+    !addr.is_loopback()
+}
+
+// dots would turn the alias into extra dns labels
+pub fn mdns_instance_name(config: &Config) -> String {
+    config
+        .alias
+        .as_deref()
+        .filter(|alias| !alias.is_empty())
+        .or(config.service.as_deref())
+        .unwrap_or("kftray")
+        .replace('.', "-")
+}
+
+fn mdns_host_name() -> String {
+    let host = whoami::fallible::hostname().unwrap_or_else(|_| "kftray".to_string());
+    format!("{}.local.", host.replace('.', "-"))
+}
+
+fn with_daemon<T>(f: impl FnOnce(&ServiceDaemon) -> Result<T, String>) -> Result<T, String> {
+    let mut daemon = MDNS_DAEMON.lock().unwrap();
+    if daemon.is_none() {
+        *daemon = Some(ServiceDaemon::new().map_err(|e| e.to_string())?);
+    }
+    f(daemon.as_ref().unwrap())
+}
+
+// only forwards bound to a non-loopback address are advertised, anything else
+// is unreachable from the network anyway
+pub fn advertise_forward(config: &Config, local_port: u16) -> Result<(), String> {
+    if !config.mdns_advertise.unwrap_or_default() {
+        return Ok(());
+    }
+
+    let addr = config
+        .local_address
+        .as_deref()
+        .unwrap_or("127.0.0.1")
+        .parse::<IpAddr>()
+        .map_err(|e| e.to_string())?;
+    if !is_lan_reachable(addr) {
+        debug!(
+            "Not advertising config {} over mDNS, {} is a loopback address",
+            config.id.unwrap_or_default(),
+            addr
+        );
+        return Ok(());
+    }
+
+    let properties = HashMap::from([
+        ("path".to_string(), "/".to_string()),
+        (
+            "config_id".to_string(),
+            config.id.unwrap_or_default().to_string(),
+        ),
+        (
+            "service".to_string(),
+            config.service.clone().unwrap_or_default(),
+        ),
+        ("namespace".to_string(), config.namespace.clone()),
+    ]);
+
+    let instance_name = mdns_instance_name(config);
+    let service_info = if addr.is_unspecified() {
+        ServiceInfo::new(
+            MDNS_SERVICE_TYPE,
+            &instance_name,
+            &mdns_host_name(),
+            (),
+            local_port,
+            properties,
+        )
+        .map(ServiceInfo::enable_addr_auto)
+    } else {
+        ServiceInfo::new(
+            MDNS_SERVICE_TYPE,
+            &instance_name,
+            &mdns_host_name(),
+            addr,
+            local_port,
+            properties,
+        )
+    }
+    .map_err(|e| e.to_string())?;
+
+    let fullname = service_info.get_fullname().to_string();
+    with_daemon(|daemon| daemon.register(service_info).map_err(|e| e.to_string()))?;
+
+    info!("Advertising {} on port {} over mDNS", fullname, local_port);
+    ADVERTISED
+        .lock()
+        .unwrap()
+        .insert(config.id.unwrap_or_default(), fullname);
+    Ok(())
+}
+
+pub fn withdraw_forward(config_id: Option<i64>) {
+    let withdrawn: Vec<String> = {
+        let mut advertised = ADVERTISED.lock().unwrap();
+        match config_id {
+            Some(config_id) => advertised.remove(&config_id).into_iter().collect(),
+            None => advertised.drain().map(|(_, fullname)| fullname).collect(),
+        }
+    };
+
+    if withdrawn.is_empty() {
+        return;
+    }
+
+    if let Some(daemon) = MDNS_DAEMON.lock().unwrap().as_ref() {
+        for fullname in withdrawn {
+            if let Err(e) = daemon.unregister(&fullname) {
+                debug!("Failed to withdraw {} from mDNS: {}", fullname, e);
+            }
+        }
+    }
+}
+
+pub fn get_advertised_forwards() -> HashMap<i64, String> {
+    ADVERTISED.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mdns_instance_name() {
+        let config = Config {
+            alias: Some("api.staging".to_string()),
+            service: Some("api".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(mdns_instance_name(&config), "api-staging");
+
+        let config = Config {
+            alias: Some(String::new()),
+            ..config
+        };
+        assert_eq!(mdns_instance_name(&config), "api");
+
+        assert!(is_lan_reachable("0.0.0.0".parse().unwrap()));
+        assert!(is_lan_reachable("192.168.1.20".parse().unwrap()));
+        assert!(!is_lan_reachable("127.0.0.2".parse().unwrap()));
+    }
+}
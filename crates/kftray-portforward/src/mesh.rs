@@ -0,0 +1,353 @@
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+use k8s_openapi::api::core::v1::{
+    Namespace,
+    Pod,
+    Service,
+};
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::mesh_model::{
+    MeshDiagnosis,
+    ServiceMesh,
+};
+use kube::api::{
+    Api,
+    ApiResource,
+    DynamicObject,
+    GroupVersionKind,
+    ListParams,
+};
+use kube::Client;
+use log::debug;
+use serde_json::Value;
+
+use crate::pod_finder::{
+    config_client,
+    is_proxy_config,
+    resolve_config_pod,
+};
+
+const ISTIO_PROXY: &str = "istio-proxy";
+const LINKERD_PROXY: &str = "linkerd-proxy";
+// mesh wide policies live in the istio root namespace
+const ISTIO_ROOT_NAMESPACE: &str = "istio-system";
+const LINKERD_INBOUND_POLICY: &str = "config.linkerd.io/default-inbound-policy";
+
+// the mesh proxy next to the app, native sidecars run as init containers
+pub fn pod_mesh(pod: &Pod) -> Option<ServiceMesh> {
+    let spec = pod.spec.as_ref()?;
+    spec.containers
+        .iter()
+        .chain(spec.init_containers.iter().flatten())
+        .find_map(|container| match container.name.as_str() {
+            ISTIO_PROXY => Some(ServiceMesh::Istio),
+            LINKERD_PROXY => Some(ServiceMesh::Linkerd),
+            _ => None,
+        })
+}
+
+pub fn namespace_mesh(namespace: &Namespace) -> Option<ServiceMesh> {
+    let labels = namespace.metadata.labels.as_ref();
+    let annotations = namespace.metadata.annotations.as_ref();
+    let istio = labels.is_some_and(|labels| {
+        labels.get("istio-injection").map(String::as_str) == Some("enabled")
+            || labels.contains_key("istio.io/rev")
+    });
+    let linkerd = annotations
+        .and_then(|annotations| annotations.get("linkerd.io/inject"))
+        .is_some_and(|inject| inject == "enabled");
+
+    match (istio, linkerd) {
+        (true, _) => Some(ServiceMesh::Istio),
+        (false, true) => Some(ServiceMesh::Linkerd),
+        (false, false) => None,
+    }
+}
+
+// the mode of a namespace wide PeerAuthentication. policies with a selector
+// only cover some workloads, and an unset mode defers to the mesh wide one
+pub fn peer_authentication_mode(policy: &Value) -> Option<String> {
+    let spec = policy.get("spec")?;
+    if spec.get("selector").is_some() {
+        return None;
+    }
+    spec.get("mtls")?
+        .get("mode")?
+        .as_str()
+        .map(str::to_uppercase)
+        .filter(|mode| mode != "UNSET")
+}
+
+pub fn linkerd_policy_strict(policy: &str) -> bool {
+    matches!(policy, "all-authenticated" | "cluster-authenticated")
+}
+
+// the namespace a proxy forward connects into, `db.payments` and
+// `db.payments.svc.cluster.local` are in payments
+pub fn remote_namespace(config: &Config) -> String {
+    let remote_address = config.remote_address.as_deref().unwrap_or_default();
+    if remote_address.parse::<IpAddr>().is_ok() {
+        return config.namespace.clone();
+    }
+    let labels: Vec<&str> = remote_address.split('.').collect();
+    match labels.as_slice() {
+        [_, namespace] => namespace.to_string(),
+        [_, namespace, "svc", ..] => namespace.to_string(),
+        _ => config.namespace.clone(),
+    }
+}
+
+async fn istio_namespace_mode(client: &Client, namespace: &str) -> Option<String> {
+    let resource = ApiResource::from_gvk(&GroupVersionKind::gvk(
+        "security.istio.io",
+        "v1beta1",
+        "PeerAuthentication",
+    ));
+    let policies: Api<DynamicObject> = Api::namespaced_with(client.clone(), namespace, &resource);
+    // clusters without istio have no such resource
+    let policies = policies
+        .list(&ListParams::default())
+        .await
+        .map_err(|e| debug!("No PeerAuthentications in {}: {}", namespace, e))
+        .ok()?;
+    policies
+        .items
+        .iter()
+        .find_map(|policy| peer_authentication_mode(&policy.data))
+}
+
+async fn istio_strict(client: &Client, namespace: &str) -> bool {
+    for namespace in [namespace, ISTIO_ROOT_NAMESPACE] {
+        if let Some(mode) = istio_namespace_mode(client, namespace).await {
+            return mode == "STRICT";
+        }
+    }
+    false
+}
+
+fn linkerd_strict(namespace: Option<&Namespace>, pod: Option<&Pod>) -> bool {
+    let policy = |annotations: Option<&BTreeMap<String, String>>| {
+        annotations.and_then(|annotations| annotations.get(LINKERD_INBOUND_POLICY).cloned())
+    };
+    pod.and_then(|pod| policy(pod.metadata.annotations.as_ref()))
+        .or_else(|| namespace.and_then(|namespace| policy(namespace.metadata.annotations.as_ref())))
+        .is_some_and(|policy| linkerd_policy_strict(&policy))
+}
+
+pub fn mesh_diagnosis(
+    mesh: ServiceMesh, strict: bool, sidecar: bool, proxy: bool,
+) -> MeshDiagnosis {
+    let name = mesh.name();
+    let (explanation, remediation) = match (proxy, strict) {
+        (true, true) => (
+            format!(
+                "The target only accepts {} mutual TLS, a proxy pod outside the mesh gets its \
+                 connections reset",
+                name
+            ),
+            vec![
+                "kftray adds the mesh proxy to its proxy pods for such targets, check that the \
+                 mesh injector is running and allowed in the proxy namespace"
+                    .to_string(),
+                "Forward to the service or pod directly, port-forwards enter the pod behind the \
+                 mesh proxy"
+                    .to_string(),
+                match mesh {
+                    ServiceMesh::Istio => "Add a PERMISSIVE PeerAuthentication for the target \
+                                           workload"
+                        .to_string(),
+                    ServiceMesh::Linkerd => format!(
+                        "Set {} to all-unauthenticated on the target workload",
+                        LINKERD_INBOUND_POLICY
+                    ),
+                },
+            ],
+        ),
+        (false, _) if sidecar => (
+            format!(
+                "The pod runs the {} proxy, port-forwards skip it and reach the app on the \
+                 loopback of the pod",
+                name
+            ),
+            vec![
+                "Make the app listen on 0.0.0.0 or 127.0.0.1, an app bound to the pod ip \
+                 can't be reached through a port-forward"
+                    .to_string(),
+                "Use a proxy forward to the service instead, it goes through the mesh like any \
+                 other client"
+                    .to_string(),
+            ],
+        ),
+        _ => (
+            format!(
+                "The namespace is part of {} but doesn't enforce mutual TLS",
+                name
+            ),
+            Vec::new(),
+        ),
+    };
+
+    MeshDiagnosis {
+        mesh,
+        strict,
+        sidecar,
+        explanation,
+        remediation,
+    }
+}
+
+pub async fn diagnose_mesh(
+    client: &Client, config: &Config, pod: Option<&Pod>,
+) -> Option<MeshDiagnosis> {
+    let proxy = is_proxy_config(config);
+    let target_namespace = match proxy {
+        true => remote_namespace(config),
+        false => config.namespace.clone(),
+    };
+    // the proxy pod is kftray's own, what matters is the pod it connects to
+    let pod = pod.filter(|_| !proxy);
+
+    let namespaces: Api<Namespace> = Api::all(client.clone());
+    let namespace = namespaces.get_opt(&target_namespace).await.ok().flatten();
+    let sidecar = pod.and_then(pod_mesh);
+    let mesh = sidecar.or_else(|| namespace.as_ref().and_then(namespace_mesh))?;
+
+    let strict = match mesh {
+        ServiceMesh::Istio => istio_strict(client, &target_namespace).await,
+        ServiceMesh::Linkerd => linkerd_strict(namespace.as_ref(), pod),
+    };
+    Some(mesh_diagnosis(mesh, strict, sidecar.is_some(), proxy))
+}
+
+// the mesh a proxy pod has to join to reach the target of the config
+pub(crate) async fn required_proxy_mesh(client: &Client, config: &Config) -> Option<ServiceMesh> {
+    diagnose_mesh(client, config, None)
+        .await
+        .filter(|diagnosis| diagnosis.strict)
+        .map(|diagnosis| diagnosis.mesh)
+}
+
+pub fn inject_mesh_proxy(pod: &mut Pod, mesh: ServiceMesh) {
+    match mesh {
+        ServiceMesh::Istio => {
+            pod.metadata
+                .labels
+                .get_or_insert_with(BTreeMap::new)
+                .insert("sidecar.istio.io/inject".to_string(), "true".to_string());
+        }
+        ServiceMesh::Linkerd => {
+            pod.metadata
+                .annotations
+                .get_or_insert_with(BTreeMap::new)
+                .insert("linkerd.io/inject".to_string(), "enabled".to_string());
+        }
+    }
+}
+
+// why a forward to a meshed target fails, none when no mesh is involved
+pub async fn mesh_failure_hint(config: &Config) -> Option<String> {
+    let client = config_client(config).await.ok()?;
+    let pods: Api<Pod> = Api::namespaced(client.clone(), &config.namespace);
+    let services: Api<Service> = Api::namespaced(client.clone(), &config.namespace);
+    let pod = resolve_config_pod(config, &pods, &services).await.ok();
+
+    let diagnosis = diagnose_mesh(&client, config, pod.as_ref())
+        .await
+        .filter(|diagnosis| diagnosis.strict || diagnosis.sidecar)?;
+    let mut hint = diagnosis.explanation;
+    for option in diagnosis.remediation {
+        hint.push_str(&format!("\n- {}", option));
+    }
+    Some(hint)
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::core::v1::{
+        Container,
+        PodSpec,
+    };
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_mesh_detection() {
+        let container = |name: &str| Container {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let mut pod = Pod {
+            spec: Some(PodSpec {
+                containers: vec![container("app")],
+                init_containers: Some(vec![container("istio-proxy")]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(pod_mesh(&pod), Some(ServiceMesh::Istio));
+        pod.spec.as_mut().unwrap().init_containers = None;
+        assert_eq!(pod_mesh(&pod), None);
+
+        inject_mesh_proxy(&mut pod, ServiceMesh::Linkerd);
+        assert_eq!(
+            pod.metadata.annotations.unwrap().get("linkerd.io/inject"),
+            Some(&"enabled".to_string())
+        );
+
+        let namespace = Namespace {
+            metadata: ObjectMeta {
+                labels: Some(BTreeMap::from([(
+                    "istio.io/rev".to_string(),
+                    "1-22".to_string(),
+                )])),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(namespace_mesh(&namespace), Some(ServiceMesh::Istio));
+        assert_eq!(namespace_mesh(&Namespace::default()), None);
+
+        assert_eq!(
+            peer_authentication_mode(&json!({"spec": {"mtls": {"mode": "strict"}}})).as_deref(),
+            Some("STRICT")
+        );
+        assert_eq!(
+            peer_authentication_mode(&json!({
+                "spec": {"selector": {"matchLabels": {"app": "db"}}, "mtls": {"mode": "STRICT"}}
+            })),
+            None
+        );
+        assert_eq!(
+            peer_authentication_mode(&json!({"spec": {"mtls": {"mode": "UNSET"}}})),
+            None
+        );
+        assert!(linkerd_policy_strict("cluster-authenticated"));
+        assert!(!linkerd_policy_strict("all-unauthenticated"));
+    }
+
+    #[test]
+    fn test_remote_namespace() {
+        let config = |remote_address: &str| Config {
+            namespace: "default".to_string(),
+            remote_address: Some(remote_address.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(remote_namespace(&config("db.payments")), "payments");
+        assert_eq!(
+            remote_namespace(&config("db.payments.svc.cluster.local")),
+            "payments"
+        );
+        assert_eq!(remote_namespace(&config("db")), "default");
+        assert_eq!(remote_namespace(&config("10.0.0.7")), "default");
+
+        let diagnosis = mesh_diagnosis(ServiceMesh::Linkerd, true, false, true);
+        assert_eq!(diagnosis.remediation.len(), 3);
+        assert!(diagnosis.remediation[2].contains(LINKERD_INBOUND_POLICY));
+        assert!(mesh_diagnosis(ServiceMesh::Istio, false, false, false)
+            .remediation
+            .is_empty());
+    }
+}
@@ -0,0 +1,529 @@
+use std::collections::{
+    BTreeMap,
+    HashMap,
+};
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::mock_rule_model::{
+    MockAction,
+    MockRule,
+};
+use lazy_static::lazy_static;
+use log::{
+    debug,
+    info,
+    warn,
+};
+
+use crate::traffic_script::{
+    RequestVerdict,
+    ScriptRequest,
+    SentRequests,
+    TrafficScript,
+    MAX_SCRIPT_BODY,
+};
+
+// a request head that doesn't fit is relayed untouched
+pub(crate) const MAX_HEAD_SIZE: usize = 64 * 1024;
+pub(crate) const MAX_HEADERS: usize = 96;
+
+lazy_static! {
+    static ref MOCK_RULES: Mutex<HashMap<i64, Vec<MockRule>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct RequestHead {
+    pub method: String,
+    pub path: String,
+    pub version: u8,
+    pub headers: Vec<(String, Vec<u8>)>,
+    pub head_len: usize,
+}
+
+pub(crate) fn find_header<'a>(headers: &'a [(String, Vec<u8>)], name: &str) -> Option<&'a [u8]> {
+    headers
+        .iter()
+        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_slice())
+}
+
+pub(crate) fn content_length(headers: &[(String, Vec<u8>)]) -> Option<usize> {
+    find_header(headers, "content-length")
+        .and_then(|value| std::str::from_utf8(value).ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+impl RequestHead {
+    fn header(&self, name: &str) -> Option<&[u8]> {
+        find_header(&self.headers, name)
+    }
+
+    // chunked bodies and upgraded connections can't be followed request by
+    // request, the rest of the connection is relayed untouched
+    fn ends_tracking(&self) -> bool {
+        self.header("transfer-encoding").is_some()
+            || self.header("upgrade").is_some()
+            || self.method.eq_ignore_ascii_case("CONNECT")
+    }
+}
+
+// none while the head is incomplete
+pub fn parse_request_head(data: &[u8]) -> Result<Option<RequestHead>, String> {
+    let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    let mut request = httparse::Request::new(&mut headers);
+    match request.parse(data).map_err(|e| e.to_string())? {
+        httparse::Status::Partial => Ok(None),
+        httparse::Status::Complete(head_len) => Ok(Some(RequestHead {
+            method: request.method.unwrap_or_default().to_string(),
+            path: request.path.unwrap_or_default().to_string(),
+            version: request.version.unwrap_or(1),
+            headers: request
+                .headers
+                .iter()
+                .map(|header| (header.name.to_string(), header.value.to_vec()))
+                .collect(),
+            head_len,
+        })),
+    }
+}
+
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let path = path.split('?').next().unwrap_or_default();
+    match pattern.strip_suffix('*') {
+        Some(prefix) => path.starts_with(prefix),
+        None => path == pattern,
+    }
+}
+
+fn header_matches(condition: &str, head: &RequestHead) -> bool {
+    let (name, value) = match condition.split_once(':') {
+        Some((name, value)) => (name.trim(), Some(value.trim())),
+        None => (condition.trim(), None),
+    };
+    match (head.header(name), value) {
+        (Some(found), Some(value)) => String::from_utf8_lossy(found).trim() == value,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
+pub fn rule_matches(rule: &MockRule, head: &RequestHead) -> bool {
+    rule.method
+        .as_deref()
+        .is_none_or(|method| method.eq_ignore_ascii_case(&head.method))
+        && rule
+            .path
+            .as_deref()
+            .is_none_or(|pattern| path_matches(pattern, &head.path))
+        && rule
+            .header
+            .as_deref()
+            .is_none_or(|condition| header_matches(condition, head))
+}
+
+pub(crate) fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        409 => "Conflict",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "",
+    }
+}
+
+pub fn mock_response(status: u16, headers: &BTreeMap<String, String>, body: &str) -> Vec<u8> {
+    let mut response = format!("HTTP/1.1 {} {}\r\n", status, reason_phrase(status));
+    for (name, value) in headers {
+        if !name.eq_ignore_ascii_case("content-length") {
+            response.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+    response.push_str("x-kftray-mock: true\r\n");
+    response.push_str(&format!("content-length: {}\r\n\r\n", body.len()));
+    response.push_str(body);
+    response.into_bytes()
+}
+
+pub fn modified_head(
+    head: &RequestHead, path: Option<&str>, set_headers: &BTreeMap<String, String>,
+    remove_headers: &[String],
+) -> Vec<u8> {
+    let replaced = |name: &str| {
+        set_headers.keys().any(|set| set.eq_ignore_ascii_case(name))
+            || remove_headers
+                .iter()
+                .any(|removed| removed.eq_ignore_ascii_case(name))
+    };
+
+    let mut modified = format!(
+        "{} {} HTTP/1.{}\r\n",
+        head.method,
+        path.unwrap_or(&head.path),
+        head.version
+    )
+    .into_bytes();
+    for (name, value) in head.headers.iter().filter(|(name, _)| !replaced(name)) {
+        modified.extend_from_slice(name.as_bytes());
+        modified.extend_from_slice(b": ");
+        modified.extend_from_slice(value);
+        modified.extend_from_slice(b"\r\n");
+    }
+    for (name, value) in set_headers {
+        modified.extend_from_slice(format!("{}: {}\r\n", name, value).as_bytes());
+    }
+    modified.extend_from_slice(b"\r\n");
+    modified
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Relayed {
+    Upstream(Vec<u8>),
+    Client(Vec<u8>),
+}
+
+struct HeldRequest {
+    head: RequestHead,
+    head_bytes: Vec<u8>,
+    body: Vec<u8>,
+}
+
+// follows the requests of one client connection and applies the rules, then
+// the traffic script, to each of them
+pub struct MockRelay {
+    rules: Vec<MockRule>,
+    script: Option<Arc<TrafficScript>>,
+    sent: SentRequests,
+    pending: Vec<u8>,
+    body_left: usize,
+    drop_body: bool,
+    // a request waiting for its whole body before the script sees it
+    held: Option<HeldRequest>,
+    passthrough: bool,
+}
+
+impl MockRelay {
+    pub fn new(rules: Vec<MockRule>) -> Self {
+        MockRelay {
+            rules,
+            script: None,
+            sent: SentRequests::default(),
+            pending: Vec::new(),
+            body_left: 0,
+            drop_body: false,
+            held: None,
+            passthrough: false,
+        }
+    }
+
+    pub fn with_script(mut self, script: Arc<TrafficScript>, sent: SentRequests) -> Self {
+        self.script = Some(script);
+        self.sent = sent;
+        self
+    }
+
+    // requests that go to the pod, for on_response to pair its responses with
+    fn remember(&self, request: ScriptRequest) {
+        if self
+            .script
+            .as_ref()
+            .is_some_and(|script| script.handles_responses())
+        {
+            self.sent.lock().unwrap().push_back(request);
+        }
+    }
+
+    fn release_held(&mut self) -> Vec<Relayed> {
+        let (Some(held), Some(script)) = (self.held.take(), self.script.clone()) else {
+            return Vec::new();
+        };
+        let request = ScriptRequest::new(&held.head, &held.body);
+        // binary bodies aren't handed to the script
+        let verdict = match std::str::from_utf8(&held.body) {
+            Ok(_) => script.on_request(&request),
+            Err(_) => Ok(None),
+        };
+        match verdict {
+            Ok(Some(RequestVerdict::Respond(response))) => {
+                vec![Relayed::Client(response.to_bytes())]
+            }
+            Ok(Some(RequestVerdict::Forward(changed))) => {
+                let bytes = changed.to_bytes();
+                self.remember(changed);
+                vec![Relayed::Upstream(bytes)]
+            }
+            Ok(None) | Err(_) => {
+                if let Err(e) = verdict {
+                    warn!(
+                        "Traffic script failed on {} {}: {}",
+                        request.method, request.path, e
+                    );
+                }
+                self.remember(request);
+                vec![Relayed::Upstream([held.head_bytes, held.body].concat())]
+            }
+        }
+    }
+
+    pub fn feed(&mut self, data: &[u8]) -> Vec<Relayed> {
+        let mut relayed = Vec::new();
+        let mut data = data.to_vec();
+        while !data.is_empty() {
+            if self.passthrough {
+                relayed.push(Relayed::Upstream(data));
+                break;
+            }
+
+            if self.body_left > 0 {
+                let body = data.len().min(self.body_left);
+                let rest = data.split_off(body);
+                self.body_left -= body;
+                match &mut self.held {
+                    Some(held) => held.body.append(&mut data),
+                    None if !self.drop_body => relayed.push(Relayed::Upstream(data)),
+                    None => {}
+                }
+                if self.body_left == 0 {
+                    relayed.extend(self.release_held());
+                }
+                data = rest;
+                continue;
+            }
+
+            self.pending.append(&mut data);
+            let head = match parse_request_head(&self.pending) {
+                Ok(Some(head)) => head,
+                Ok(None) if self.pending.len() <= MAX_HEAD_SIZE => break,
+                Ok(None) | Err(_) => {
+                    debug!("Not an HTTP/1 request, relaying the connection untouched");
+                    self.passthrough = true;
+                    data = std::mem::take(&mut self.pending);
+                    continue;
+                }
+            };
+
+            // what follows the head is its body and any pipelined request
+            let mut head_bytes = std::mem::take(&mut self.pending);
+            data = head_bytes.split_off(head.head_len);
+            if head.ends_tracking() {
+                self.passthrough = true;
+                self.remember(ScriptRequest::new(&head, &[]));
+                relayed.push(Relayed::Upstream(head_bytes));
+                continue;
+            }
+
+            self.body_left = content_length(&head.headers).unwrap_or(0);
+            self.drop_body = false;
+            let action = self
+                .rules
+                .iter()
+                .find(|rule| rule_matches(rule, &head))
+                .map(|rule| rule.action.clone());
+            match action {
+                Some(MockAction::Respond {
+                    status,
+                    headers,
+                    body,
+                }) => {
+                    debug!("Mocking {} {}", head.method, head.path);
+                    self.drop_body = true;
+                    relayed.push(Relayed::Client(mock_response(status, &headers, &body)));
+                }
+                Some(MockAction::Modify {
+                    path,
+                    set_headers,
+                    remove_headers,
+                }) => {
+                    self.remember(ScriptRequest::new(&head, &[]));
+                    relayed.push(Relayed::Upstream(modified_head(
+                        &head,
+                        path.as_deref(),
+                        &set_headers,
+                        &remove_headers,
+                    )));
+                }
+                Some(MockAction::Pass) => {
+                    self.remember(ScriptRequest::new(&head, &[]));
+                    relayed.push(Relayed::Upstream(head_bytes));
+                }
+                None => match &self.script {
+                    Some(script)
+                        if script.handles_requests() && self.body_left <= MAX_SCRIPT_BODY =>
+                    {
+                        self.held = Some(HeldRequest {
+                            head,
+                            head_bytes,
+                            body: Vec::new(),
+                        });
+                        if self.body_left == 0 {
+                            relayed.extend(self.release_held());
+                        }
+                    }
+                    _ => {
+                        self.remember(ScriptRequest::new(&head, &[]));
+                        relayed.push(Relayed::Upstream(head_bytes));
+                    }
+                },
+            }
+        }
+        relayed
+    }
+}
+
+// a config without rules drops the ones of an earlier start
+pub(crate) fn register_mock_rules(config: &Config) {
+    let Some(config_id) = config.id else {
+        return;
+    };
+    let rules = config.mock_rules.clone().unwrap_or_default();
+    if rules.is_empty() {
+        clear_mock_rules(Some(config_id));
+        return;
+    }
+    info!("Config {} applies {} mock rules", config_id, rules.len());
+    MOCK_RULES.lock().unwrap().insert(config_id, rules);
+}
+
+// none when the forward has neither rules nor a script
+pub(crate) fn mock_relay(
+    config_id: i64, script: Option<Arc<TrafficScript>>, sent: SentRequests,
+) -> Option<MockRelay> {
+    let rules = MOCK_RULES
+        .lock()
+        .unwrap()
+        .get(&config_id)
+        .cloned()
+        .unwrap_or_default();
+    if rules.is_empty() && script.is_none() {
+        return None;
+    }
+
+    let relay = MockRelay::new(rules);
+    Some(match script {
+        Some(script) => relay.with_script(script, sent),
+        None => relay,
+    })
+}
+
+pub(crate) fn clear_mock_rules(config_id: Option<i64>) {
+    let mut rules = MOCK_RULES.lock().unwrap();
+    match config_id {
+        Some(config_id) => {
+            rules.remove(&config_id);
+        }
+        None => rules.clear(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn respond(path: &str, status: u16) -> MockRule {
+        MockRule {
+            method: None,
+            path: Some(path.to_string()),
+            header: None,
+            action: MockAction::Respond {
+                status,
+                headers: BTreeMap::new(),
+                body: "{}".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        let head = parse_request_head(
+            b"GET /api/orders/7?expand=1 HTTP/1.1\r\nHost: shop\r\nX-Tenant: acme\r\n\r\n",
+        )
+        .unwrap()
+        .unwrap();
+        let mut rule = respond("/api/orders/*", 500);
+        assert!(rule_matches(&rule, &head));
+
+        rule.method = Some("post".to_string());
+        assert!(!rule_matches(&rule, &head));
+        rule.method = Some("get".to_string());
+        rule.header = Some("x-tenant: acme".to_string());
+        assert!(rule_matches(&rule, &head));
+        rule.header = Some("x-tenant: other".to_string());
+        assert!(!rule_matches(&rule, &head));
+        rule.header = Some("X-Tenant".to_string());
+        assert!(rule_matches(&rule, &head));
+
+        assert!(!rule_matches(&respond("/api/orders", 500), &head));
+        assert!(rule_matches(&respond("/api/orders/7", 500), &head));
+        assert_eq!(parse_request_head(b"GET /api HTTP/1.1\r\nHo"), Ok(None));
+        assert!(parse_request_head(b"\x16\x03\x01\x02\x00").is_err());
+    }
+
+    #[test]
+    fn test_mock_relay() {
+        let mut relay = MockRelay::new(vec![
+            respond("/fail", 503),
+            MockRule {
+                method: None,
+                path: Some("/v1/*".to_string()),
+                header: None,
+                action: MockAction::Modify {
+                    path: Some("/v2/users".to_string()),
+                    set_headers: BTreeMap::from([("X-Debug".to_string(), "1".to_string())]),
+                    remove_headers: vec!["cookie".to_string()],
+                },
+            },
+        ]);
+
+        // the mocked body is dropped, the pipelined request after it goes on
+        let relayed = relay.feed(b"POST /fail HTTP/1.1\r\nContent-Length: 5\r\n\r\nhel");
+        assert_eq!(relayed.len(), 1);
+        let Relayed::Client(response) = &relayed[0] else {
+            panic!("expected a mock response");
+        };
+        assert!(response.starts_with(b"HTTP/1.1 503 Service Unavailable\r\n"));
+        assert!(response.ends_with(b"content-length: 2\r\n\r\n{}"));
+        assert_eq!(
+            relay.feed(b"loGET /ok HTTP/1.1\r\nContent-Length: 2\r\n\r\nhi"),
+            vec![
+                Relayed::Upstream(b"GET /ok HTTP/1.1\r\nContent-Length: 2\r\n\r\n".to_vec()),
+                Relayed::Upstream(b"hi".to_vec()),
+            ]
+        );
+
+        // heads split across reads are put back together
+        assert!(relay
+            .feed(b"GET /v1/users HTTP/1.1\r\nCookie: a=b\r\n")
+            .is_empty());
+        assert_eq!(
+            relay.feed(b"Host: api\r\n\r\n"),
+            vec![Relayed::Upstream(
+                b"GET /v2/users HTTP/1.1\r\nHost: api\r\nX-Debug: 1\r\n\r\n".to_vec()
+            )]
+        );
+
+        let mut relay = MockRelay::new(vec![respond("*", 200)]);
+        assert_eq!(
+            relay.feed(b"\x16\x03\x01"),
+            vec![Relayed::Upstream(b"\x16\x03\x01".to_vec())]
+        );
+        assert_eq!(
+            relay.feed(b"GET / HTTP/1.1\r\n\r\n"),
+            vec![Relayed::Upstream(b"GET / HTTP/1.1\r\n\r\n".to_vec())]
+        );
+    }
+}
@@ -11,6 +11,7 @@ use k8s_openapi::api::core::v1::{
     Service,
 };
 use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use kftray_commons::models::protocol_model::StreamProtocol;
 use kube::api::Api;
 use serde::{
     Deserialize,
@@ -19,6 +20,9 @@ use serde::{
 use tokio::sync::Mutex;
 use tracing::debug;
 
+use crate::connection_limit::ConnectionLimit;
+use crate::transport::Transport;
+
 impl NameSpace {
     pub fn name_any(&self) -> String {
         self.0.clone().unwrap_or_else(|| "default".to_string())
@@ -95,7 +99,7 @@ impl Target {
     }
 }
 
-fn is_pod_ready(pod: &&Pod) -> bool {
+pub(crate) fn is_pod_ready(pod: &&Pod) -> bool {
     let conditions = pod.status.as_ref().and_then(|s| s.conditions.as_ref());
 
     let is_ready = conditions
@@ -113,6 +117,12 @@ fn is_pod_ready(pod: &&Pod) -> bool {
 #[derive(Serialize, Deserialize)]
 pub struct KubeContextInfo {
     pub name: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -148,12 +158,17 @@ pub struct PortForward {
     pub config_id: i64,
     pub workload_type: String,
     pub connection: Arc<Mutex<Option<tokio::net::TcpStream>>>,
+    pub protocol_hint: Option<StreamProtocol>,
+    pub transport: Transport,
+    pub negotiated_transport: Arc<std::sync::Mutex<Option<Transport>>>,
+    pub connection_limit: Option<ConnectionLimit>,
 }
 
 #[derive(Clone, Debug)]
 pub enum TargetSelector {
     ServiceName(String),
     PodLabel(String),
+    PodName(String),
 }
 
 #[derive(Clone, Debug)]
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::sync::atomic::{
+    AtomicU64,
+    Ordering,
+};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use http_body_util::{
+    BodyExt,
+    Empty,
+};
+use hyper::body::Bytes;
+use hyper_openssl::client::legacy::HttpsConnector;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+use kftray_commons::models::config_model::Config;
+use kftray_commons::utils::openapi::{
+    clear_openapi_spec,
+    parse_openapi_spec,
+    set_openapi_spec,
+    OpenApiSpec,
+};
+use lazy_static::lazy_static;
+use log::{
+    info,
+    warn,
+};
+use openssl::ssl::{
+    SslConnector,
+    SslMethod,
+};
+use tokio::task::JoinHandle;
+
+const SPEC_FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+struct SpecLoad {
+    id: u64,
+    handle: JoinHandle<()>,
+}
+
+static NEXT_LOAD_ID: AtomicU64 = AtomicU64::new(0);
+
+lazy_static! {
+    static ref SPEC_LOADS: Mutex<HashMap<i64, SpecLoad>> = Mutex::new(HashMap::new());
+}
+
+async fn fetch_spec(url: &str) -> Result<String, String> {
+    let mut http = HttpConnector::new();
+    http.enforce_http(false);
+    let ssl = SslConnector::builder(SslMethod::tls()).map_err(|e| e.to_string())?;
+    let https = HttpsConnector::with_connector(http, ssl).map_err(|e| e.to_string())?;
+    let client: Client<_, Empty<Bytes>> = Client::builder(TokioExecutor::new()).build(https);
+
+    let uri: hyper::Uri = url
+        .parse()
+        .map_err(|e: hyper::http::uri::InvalidUri| e.to_string())?;
+    let fetch = async {
+        let response = client.get(uri).await.map_err(|e| e.to_string())?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("{} answered {}", url, status));
+        }
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .map_err(|e| e.to_string())?
+            .to_bytes();
+        String::from_utf8(body.to_vec()).map_err(|e| e.to_string())
+    };
+    tokio::time::timeout(SPEC_FETCH_TIMEOUT, fetch)
+        .await
+        .map_err(|_| format!("Timed out fetching {}", url))?
+}
+
+async fn read_spec(location: &str) -> Result<OpenApiSpec, String> {
+    let source = if location.starts_with("http://") || location.starts_with("https://") {
+        fetch_spec(location).await?
+    } else {
+        tokio::fs::read_to_string(location)
+            .await
+            .map_err(|e| format!("{}: {}", location, e))?
+    };
+    parse_openapi_spec(&source).map_err(|e| format!("{}: {}", location, e))
+}
+
+// a spec behind a URL loads in the background so it doesn't hold up the
+// start, a stop or rollback before it arrives drops it. a config without a
+// spec drops the one of an earlier start
+pub(crate) fn load_openapi_spec(config: &Config) {
+    let Some(config_id) = config.id else {
+        return;
+    };
+    unload_openapi_spec(Some(config_id));
+    let Some(location) = config
+        .openapi_spec
+        .as_deref()
+        .map(str::trim)
+        .filter(|location| !location.is_empty())
+        .map(str::to_string)
+    else {
+        return;
+    };
+
+    let id = NEXT_LOAD_ID.fetch_add(1, Ordering::Relaxed);
+    // held while spawning, so the load can't finish before it is tracked
+    let mut loads = SPEC_LOADS.lock().unwrap();
+    let handle = tokio::spawn(async move {
+        let spec = read_spec(&location).await;
+        let mut loads = SPEC_LOADS.lock().unwrap();
+        if loads.get(&config_id).is_none_or(|load| load.id != id) {
+            return;
+        }
+        loads.remove(&config_id);
+        match spec {
+            Ok(spec) => {
+                info!(
+                    "Config {} checks its traffic against {}",
+                    config_id, location
+                );
+                set_openapi_spec(config_id, spec);
+            }
+            Err(e) => warn!(
+                "Config {} logs traffic without checking its OpenAPI spec: {}",
+                config_id, e
+            ),
+        }
+    });
+    loads.insert(config_id, SpecLoad { id, handle });
+}
+
+pub(crate) fn unload_openapi_spec(config_id: Option<i64>) {
+    let mut loads = SPEC_LOADS.lock().unwrap();
+    let stopped: Vec<SpecLoad> = match config_id {
+        Some(config_id) => loads.remove(&config_id).into_iter().collect(),
+        None => loads.drain().map(|(_, load)| load).collect(),
+    };
+    for load in stopped {
+        load.handle.abort();
+    }
+    clear_openapi_spec(config_id);
+}
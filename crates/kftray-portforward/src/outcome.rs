@@ -0,0 +1,100 @@
+use kftray_commons::models::response::{
+    CustomResponse,
+    ForwardOutcome,
+};
+
+// an error fails every config the call was for, since the start rolls them
+// all back
+pub fn forward_outcomes(
+    result: &Result<Vec<CustomResponse>, String>, config_ids: &[i64],
+) -> Vec<ForwardOutcome> {
+    match result {
+        Ok(responses) => responses.iter().map(ForwardOutcome::from).collect(),
+        Err(e) if config_ids.is_empty() => vec![ForwardOutcome::Failed {
+            config_id: None,
+            error: e.clone(),
+        }],
+        Err(e) => config_ids
+            .iter()
+            .map(|config_id| ForwardOutcome::Failed {
+                config_id: Some(*config_id),
+                error: e.clone(),
+            })
+            .collect(),
+    }
+}
+
+// folds a mix of failures and successes into a single partial failure, a
+// batch that went the same way for every config is left as it is
+pub fn summarize_outcomes(outcomes: Vec<ForwardOutcome>) -> Vec<ForwardOutcome> {
+    let (failed, succeeded): (Vec<_>, Vec<_>) =
+        outcomes.into_iter().partition(ForwardOutcome::is_failure);
+
+    match (succeeded.is_empty(), failed.is_empty()) {
+        (true, _) => failed,
+        (_, true) => succeeded,
+        _ => vec![ForwardOutcome::PartialFailure { succeeded, failed }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(id: i64, local_port: u16, status: i32) -> CustomResponse {
+        CustomResponse {
+            id: Some(id),
+            service: "web".to_string(),
+            namespace: "shop".to_string(),
+            local_port,
+            remote_port: 80,
+            context: "kind".to_string(),
+            stdout: String::new(),
+            stderr: "boom".to_string(),
+            status,
+            protocol: "tcp".to_string(),
+            firewall_warning: None,
+            messages: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_forward_outcomes() {
+        let result = Ok(vec![response(1, 8080, 0), response(2, 0, 0)]);
+        let outcomes = forward_outcomes(&result, &[1, 2]);
+        assert!(matches!(
+            outcomes[0],
+            ForwardOutcome::Started {
+                actual_port: 8080,
+                ..
+            }
+        ));
+        assert!(matches!(outcomes[1], ForwardOutcome::Stopped { .. }));
+
+        let outcomes = forward_outcomes(&Err("no cluster".to_string()), &[1, 2]);
+        assert_eq!(outcomes.len(), 2);
+        assert!(outcomes.iter().all(ForwardOutcome::is_failure));
+
+        let mixed = vec![
+            ForwardOutcome::from(&response(1, 8080, 0)),
+            ForwardOutcome::from(&response(2, 0, 1)),
+        ];
+        let summary = summarize_outcomes(mixed);
+        assert_eq!(summary.len(), 1);
+        let ForwardOutcome::PartialFailure { succeeded, failed } = &summary[0] else {
+            panic!("expected a partial failure");
+        };
+        assert_eq!(succeeded.len(), 1);
+        assert_eq!(
+            failed[0],
+            ForwardOutcome::Failed {
+                config_id: Some(2),
+                error: "boom".to_string()
+            }
+        );
+
+        let json = serde_json::to_value(&summary[0]).unwrap();
+        assert_eq!(json["outcome"], "partial_failure");
+        assert_eq!(json["succeeded"][0]["outcome"], "started");
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
+use kftray_commons::config::get_config;
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::response::CustomResponse;
+use lazy_static::lazy_static;
+use log::info;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::audit::start_with_reason;
+use crate::headless::start_config;
+use crate::models::kube::HttpLogState;
+
+lazy_static! {
+    static ref FORWARD_OVERRIDES: Mutex<HashMap<i64, ForwardOverrides>> =
+        Mutex::new(HashMap::new());
+}
+
+// values a forward runs with instead of the stored ones, they only live as
+// long as the forward and are never written back to the config
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForwardOverrides {
+    #[serde(default)]
+    pub local_port: Option<u16>,
+    #[serde(default)]
+    pub namespace: Option<String>,
+    #[serde(default)]
+    pub context: Option<String>,
+}
+
+impl ForwardOverrides {
+    pub fn is_empty(&self) -> bool {
+        self.local_port.is_none() && self.namespace.is_none() && self.context.is_none()
+    }
+}
+
+pub fn apply_overrides(mut config: Config, overrides: &ForwardOverrides) -> Config {
+    if let Some(local_port) = overrides.local_port {
+        config.local_port = Some(local_port);
+    }
+    if let Some(namespace) = overrides.namespace.as_deref().map(str::trim) {
+        if !namespace.is_empty() {
+            config.namespace = namespace.to_string();
+        }
+    }
+    if let Some(context) = overrides.context.as_deref().map(str::trim) {
+        if !context.is_empty() {
+            config.context = context.to_string();
+        }
+    }
+    config
+}
+
+pub fn get_forward_overrides(config_id: i64) -> Option<ForwardOverrides> {
+    FORWARD_OVERRIDES.lock().unwrap().get(&config_id).cloned()
+}
+
+pub(crate) fn clear_forward_overrides(config_id: Option<i64>) {
+    let mut overrides = FORWARD_OVERRIDES.lock().unwrap();
+    match config_id {
+        Some(config_id) => {
+            overrides.remove(&config_id);
+        }
+        None => overrides.clear(),
+    }
+}
+
+pub async fn start_with_overrides(
+    config_id: i64, overrides: ForwardOverrides, reason: Option<&str>,
+    http_log_state: Arc<HttpLogState>,
+) -> Result<Vec<CustomResponse>, String> {
+    let config = apply_overrides(get_config(config_id).await?, &overrides);
+    if !overrides.is_empty() {
+        info!(
+            "Starting config {} with overrides {:?}",
+            config_id, overrides
+        );
+    }
+
+    let configs = [config.clone()];
+    let result = start_with_reason(&configs, reason, start_config(config, http_log_state)).await;
+
+    if result.is_ok() && !overrides.is_empty() {
+        FORWARD_OVERRIDES
+            .lock()
+            .unwrap()
+            .insert(config_id, overrides);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_overrides() {
+        let stored = Config {
+            id: Some(1),
+            local_port: Some(8080),
+            namespace: "default".to_string(),
+            context: "prod".to_string(),
+            ..Default::default()
+        };
+
+        let overrides = ForwardOverrides {
+            local_port: Some(18080),
+            namespace: Some(" staging ".to_string()),
+            context: Some(String::new()),
+        };
+        let config = apply_overrides(stored.clone(), &overrides);
+        assert_eq!(config.local_port, Some(18080));
+        assert_eq!(config.namespace, "staging");
+        assert_eq!(config.context, "prod");
+
+        assert!(ForwardOverrides::default().is_empty());
+        assert_eq!(
+            apply_overrides(stored.clone(), &ForwardOverrides::default()),
+            stored
+        );
+    }
+}
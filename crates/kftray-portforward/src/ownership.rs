@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use k8s_openapi::api::core::v1::Pod;
+use kftray_commons::utils::config_dir::get_config_dir;
+use kftray_commons::utils::session::current_session_id;
+use lazy_static::lazy_static;
+use log::warn;
+
+pub const OWNER_LABEL: &str = "kftray.app/owner";
+pub const MACHINE_LABEL: &str = "kftray.app/machine-id";
+pub const SESSION_LABEL: &str = "kftray.app/session-id";
+pub const CONFIG_LABEL: &str = "config_id";
+
+const MAX_LABEL_VALUE_LEN: usize = 63;
+
+lazy_static! {
+    static ref MACHINE_ID: String = label_value(&load_machine_id());
+}
+
+// label values are at most 63 alphanumerics, '-', '_' or '.', starting and
+// ending with an alphanumeric
+pub fn label_value(raw: &str) -> String {
+    let value: String = raw
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .take(MAX_LABEL_VALUE_LEN)
+        .collect();
+
+    let value = value.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+    if value.is_empty() {
+        "unknown".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+// the os machine id where there is one, otherwise an id generated once and
+// kept in the config dir
+fn load_machine_id() -> String {
+    for path in ["/etc/machine-id", "/var/lib/dbus/machine-id"] {
+        if let Ok(id) = std::fs::read_to_string(path) {
+            if !id.trim().is_empty() {
+                return id.trim().to_string();
+            }
+        }
+    }
+
+    let path = match get_config_dir() {
+        Ok(dir) => dir.join("machine-id"),
+        Err(e) => {
+            warn!("Failed to locate the config dir for the machine id: {}", e);
+            return whoami::devicename();
+        }
+    };
+    if let Ok(id) = std::fs::read_to_string(&path) {
+        if !id.trim().is_empty() {
+            return id.trim().to_string();
+        }
+    }
+
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    if let Err(e) = path
+        .parent()
+        .map(std::fs::create_dir_all)
+        .transpose()
+        .and_then(|_| std::fs::write(&path, &id))
+    {
+        warn!("Failed to persist machine id to {}: {}", path.display(), e);
+    }
+    id
+}
+
+pub fn owner_id() -> String {
+    label_value(&whoami::username())
+}
+
+pub fn machine_id() -> String {
+    MACHINE_ID.clone()
+}
+
+pub fn session_id() -> String {
+    label_value(&current_session_id())
+}
+
+pub fn ownership_labels() -> BTreeMap<String, String> {
+    BTreeMap::from([
+        (OWNER_LABEL.to_string(), owner_id()),
+        (MACHINE_LABEL.to_string(), machine_id()),
+        (SESSION_LABEL.to_string(), session_id()),
+    ])
+}
+
+// selects the proxy pods this user deployed from this machine, regardless of
+// the session they were created in
+pub fn owned_pods_selector(config_id: Option<i64>) -> String {
+    let mut selector = format!(
+        "{}={},{}={}",
+        OWNER_LABEL,
+        owner_id(),
+        MACHINE_LABEL,
+        machine_id()
+    );
+    if let Some(config_id) = config_id {
+        selector.push_str(&format!(",{}={}", CONFIG_LABEL, config_id));
+    }
+    selector
+}
+
+pub fn session_pods_selector(config_id: Option<i64>, session_id: &str) -> String {
+    format!(
+        "{},{}={}",
+        owned_pods_selector(config_id),
+        SESSION_LABEL,
+        label_value(session_id)
+    )
+}
+
+pub fn label_owned_pod(pod: &mut Pod) {
+    pod.metadata
+        .labels
+        .get_or_insert_with(BTreeMap::new)
+        .extend(ownership_labels());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label_value() {
+        assert_eq!(label_value("Jane.Doe"), "jane.doe");
+        assert_eq!(label_value("CORP\\jane doe"), "corp-jane-doe");
+        assert_eq!(label_value("_jörg_"), "j-rg");
+        assert_eq!(label_value("@@@"), "unknown");
+        assert_eq!(label_value(&"a".repeat(80)).len(), MAX_LABEL_VALUE_LEN);
+    }
+}
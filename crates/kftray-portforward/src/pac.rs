@@ -0,0 +1,414 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+
+use kftray_commons::config_state::get_configs_state;
+use lazy_static::lazy_static;
+use log::{
+    debug,
+    info,
+    warn,
+};
+use serde::Serialize;
+use tokio::io::{
+    copy_bidirectional,
+    AsyncReadExt,
+    AsyncWriteExt,
+};
+use tokio::net::{
+    TcpListener,
+    TcpStream,
+};
+use tokio::task::JoinHandle;
+
+use crate::reload::running_configs;
+
+pub const PAC_PATH: &str = "/proxy.pac";
+const MAX_REQUEST_HEAD: usize = 16 * 1024;
+
+lazy_static! {
+    static ref PAC_PROXY: Mutex<Option<(SocketAddr, JoinHandle<()>)>> = Mutex::new(None);
+}
+
+// an alias domain and the local end of the forward it maps to
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PacRoute {
+    pub host: String,
+    pub address: String,
+    pub local_port: u16,
+    pub remote_port: u16,
+}
+
+// aliases with characters a hostname can't have are left to the hosts file
+fn alias_host(alias: &str) -> Option<String> {
+    let host = alias.trim().to_lowercase();
+    let valid = !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.');
+    valid.then_some(host)
+}
+
+pub async fn pac_routes() -> Result<Vec<PacRoute>, String> {
+    // the state has the port a forward actually got when it asked for any
+    let ports: HashMap<i64, u16> = get_configs_state()
+        .await?
+        .into_iter()
+        .filter(|state| state.is_running)
+        .filter_map(|state| state.local_port.map(|port| (state.config_id, port)))
+        .collect();
+
+    let mut routes: Vec<PacRoute> = running_configs()
+        .into_iter()
+        .filter_map(|config| {
+            let host = alias_host(config.alias.as_deref()?)?;
+            let local_port = config
+                .id
+                .and_then(|id| ports.get(&id).copied())
+                .or(config.local_port)
+                .filter(|port| *port != 0)?;
+            Some(PacRoute {
+                host,
+                address: config
+                    .local_address
+                    .clone()
+                    .unwrap_or_else(|| "127.0.0.1".to_string()),
+                local_port,
+                remote_port: config.remote_port.unwrap_or_default(),
+            })
+        })
+        .collect();
+    routes.sort_by(|a, b| a.host.cmp(&b.host).then(a.remote_port.cmp(&b.remote_port)));
+    Ok(routes)
+}
+
+pub fn pac_file(routes: &[PacRoute], proxy: SocketAddr) -> String {
+    let mut hosts: Vec<&str> = routes.iter().map(|route| route.host.as_str()).collect();
+    hosts.dedup();
+
+    let mut pac = String::from("function FindProxyForURL(url, host) {\n");
+    pac.push_str("  host = host.toLowerCase();\n");
+    for host in hosts {
+        pac.push_str(&format!(
+            "  if (host == \"{}\") return \"PROXY {}\";\n",
+            host, proxy
+        ));
+    }
+    pac.push_str("  return \"DIRECT\";\n}\n");
+    pac
+}
+
+// the forward for the port the browser asked for, or the only one the host has
+fn find_route<'a>(routes: &'a [PacRoute], host: &str, port: u16) -> Option<&'a PacRoute> {
+    let host = host.to_lowercase();
+    let matching: Vec<&PacRoute> = routes.iter().filter(|route| route.host == host).collect();
+    matching
+        .iter()
+        .find(|route| route.remote_port == port || route.local_port == port)
+        .or_else(|| (matching.len() == 1).then(|| &matching[0]))
+        .copied()
+}
+
+fn split_host_port(authority: &str, default_port: u16) -> (String, u16) {
+    match authority.rsplit_once(':') {
+        Some((host, port)) => match port.parse() {
+            Ok(port) => (host.to_string(), port),
+            Err(_) => (authority.to_string(), default_port),
+        },
+        None => (authority.to_string(), default_port),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ProxyRequest {
+    Pac,
+    Connect {
+        host: String,
+        port: u16,
+    },
+    // a plain http request, path is what the forward gets in origin form
+    Http {
+        host: String,
+        port: u16,
+        path: String,
+    },
+}
+
+fn parse_request_line(line: &str) -> Option<ProxyRequest> {
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?;
+    let target = parts.next()?;
+
+    if method.eq_ignore_ascii_case("CONNECT") {
+        let (host, port) = split_host_port(target, 443);
+        return Some(ProxyRequest::Connect { host, port });
+    }
+    if target == PAC_PATH {
+        return Some(ProxyRequest::Pac);
+    }
+
+    let rest = target.strip_prefix("http://")?;
+    let (authority, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = split_host_port(authority, 80);
+    Some(ProxyRequest::Http {
+        host,
+        port,
+        path: path.to_string(),
+    })
+}
+
+// origin form request line, hop-by-hop proxy headers dropped and one request
+// per connection, the next one may be for another forward
+fn rewrite_request_head(head: &str, path: &str) -> String {
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("GET");
+    let _ = parts.next();
+    let version = parts.next().unwrap_or("HTTP/1.1");
+
+    let mut rewritten = format!("{} {} {}\r\n", method, path, version);
+    for line in lines.filter(|line| !line.is_empty()) {
+        let name = line.split(':').next().unwrap_or_default().trim();
+        if name.eq_ignore_ascii_case("proxy-connection")
+            || name.eq_ignore_ascii_case("proxy-authorization")
+            || name.eq_ignore_ascii_case("connection")
+        {
+            continue;
+        }
+        rewritten.push_str(line);
+        rewritten.push_str("\r\n");
+    }
+    rewritten.push_str("Connection: close\r\n\r\n");
+    rewritten
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+// reads until the end of the request head, returns it along with whatever of
+// the body already came in
+async fn read_request_head(stream: &mut TcpStream) -> Result<(String, Vec<u8>), String> {
+    let mut buffer = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 4096];
+    loop {
+        if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            let rest = buffer.split_off(end + 4);
+            return Ok((String::from_utf8_lossy(&buffer).into_owned(), rest));
+        }
+        if buffer.len() > MAX_REQUEST_HEAD {
+            return Err("Request head is too large".to_string());
+        }
+        let read = stream.read(&mut chunk).await.map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Err("Connection closed before the request head".to_string());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+}
+
+async fn handle_connection(mut client: TcpStream, proxy: SocketAddr) -> Result<(), String> {
+    let (head, rest) = read_request_head(&mut client).await?;
+    let request_line = head.lines().next().unwrap_or_default();
+    let Some(request) = parse_request_line(request_line) else {
+        respond(
+            &mut client,
+            "400 Bad Request",
+            "text/plain",
+            "Bad request\n",
+        )
+        .await;
+        return Ok(());
+    };
+
+    let routes = pac_routes().await?;
+    let (host, port) = match &request {
+        ProxyRequest::Pac => {
+            let pac = pac_file(&routes, proxy);
+            respond(
+                &mut client,
+                "200 OK",
+                "application/x-ns-proxy-autoconfig",
+                &pac,
+            )
+            .await;
+            return Ok(());
+        }
+        ProxyRequest::Connect { host, port } | ProxyRequest::Http { host, port, .. } => {
+            (host.clone(), *port)
+        }
+    };
+
+    let Some(route) = find_route(&routes, &host, port) else {
+        let body = format!("No running forward for {}:{}\n", host, port);
+        respond(&mut client, "502 Bad Gateway", "text/plain", &body).await;
+        return Ok(());
+    };
+    debug!(
+        "Proxying {}:{} to {}:{}",
+        host, port, route.address, route.local_port
+    );
+
+    let mut upstream = match TcpStream::connect((route.address.as_str(), route.local_port)).await {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            let body = format!("Forward for {} is not accepting connections: {}\n", host, e);
+            respond(&mut client, "502 Bad Gateway", "text/plain", &body).await;
+            return Ok(());
+        }
+    };
+
+    match request {
+        ProxyRequest::Connect { .. } => {
+            client
+                .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        ProxyRequest::Http { path, .. } => {
+            upstream
+                .write_all(rewrite_request_head(&head, &path).as_bytes())
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        ProxyRequest::Pac => {}
+    }
+    if !rest.is_empty() {
+        upstream.write_all(&rest).await.map_err(|e| e.to_string())?;
+    }
+
+    copy_bidirectional(&mut client, &mut upstream)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn pac_proxy_address() -> Option<SocketAddr> {
+    PAC_PROXY
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|(address, _)| *address)
+}
+
+pub fn pac_url(address: SocketAddr) -> String {
+    format!("http://{}{}", address, PAC_PATH)
+}
+
+// serves the pac file and proxies the alias domains in it to their forwards,
+// port 0 picks a free one. a running proxy is replaced
+pub async fn start_pac_proxy(port: u16) -> Result<SocketAddr, String> {
+    stop_pac_proxy();
+
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .map_err(|e| format!("Failed to bind the PAC proxy to port {}: {}", port, e))?;
+    let address = listener.local_addr().map_err(|e| e.to_string())?;
+    info!("PAC file served at {}", pac_url(address));
+
+    let handle = tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((client, _)) => {
+                    tokio::spawn(async move {
+                        if let Err(e) = handle_connection(client, address).await {
+                            debug!("PAC proxy connection ended: {}", e);
+                        }
+                    });
+                }
+                Err(e) => warn!("PAC proxy failed to accept a connection: {}", e),
+            }
+        }
+    });
+
+    *PAC_PROXY.lock().unwrap() = Some((address, handle));
+    Ok(address)
+}
+
+pub fn stop_pac_proxy() {
+    if let Some((address, handle)) = PAC_PROXY.lock().unwrap().take() {
+        info!("Stopping the PAC proxy on {}", address);
+        handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(host: &str, local_port: u16, remote_port: u16) -> PacRoute {
+        PacRoute {
+            host: host.to_string(),
+            address: "127.0.0.1".to_string(),
+            local_port,
+            remote_port,
+        }
+    }
+
+    #[test]
+    fn test_pac_file_and_routes() {
+        let routes = vec![
+            route("grafana.internal", 13000, 80),
+            route("postgres", 15432, 5432),
+            route("postgres", 15433, 5433),
+        ];
+        let pac = pac_file(&routes, "127.0.0.1:8118".parse().unwrap());
+        assert!(pac.contains("if (host == \"grafana.internal\") return \"PROXY 127.0.0.1:8118\";"));
+        assert_eq!(pac.matches("postgres").count(), 1);
+        assert!(pac.ends_with("return \"DIRECT\";\n}\n"));
+
+        assert_eq!(
+            find_route(&routes, "Grafana.Internal", 443)
+                .unwrap()
+                .local_port,
+            13000
+        );
+        assert_eq!(
+            find_route(&routes, "postgres", 5433).unwrap().local_port,
+            15433
+        );
+        assert!(find_route(&routes, "postgres", 80).is_none());
+        assert!(alias_host("my service").is_none());
+    }
+
+    #[test]
+    fn test_parse_and_rewrite_requests() {
+        assert_eq!(
+            parse_request_line("CONNECT grafana.internal:443 HTTP/1.1"),
+            Some(ProxyRequest::Connect {
+                host: "grafana.internal".to_string(),
+                port: 443
+            })
+        );
+        assert_eq!(
+            parse_request_line("GET /proxy.pac HTTP/1.1"),
+            Some(ProxyRequest::Pac)
+        );
+        assert_eq!(
+            parse_request_line("GET http://api:8080/v1/users?id=1 HTTP/1.1"),
+            Some(ProxyRequest::Http {
+                host: "api".to_string(),
+                port: 8080,
+                path: "/v1/users?id=1".to_string()
+            })
+        );
+        assert!(parse_request_line("GET /index.html HTTP/1.1").is_none());
+
+        let head = "GET http://api/ HTTP/1.1\r\nHost: api\r\nProxy-Connection: keep-alive\r\nConnection: keep-alive\r\n\r\n";
+        assert_eq!(
+            rewrite_request_head(head, "/"),
+            "GET / HTTP/1.1\r\nHost: api\r\nConnection: close\r\n\r\n"
+        );
+    }
+}
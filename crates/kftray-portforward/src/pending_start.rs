@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::sync::{
+    Arc,
+    Mutex,
+};
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use k8s_openapi::api::core::v1::{
+    Endpoints,
+    Pod,
+    Service,
+};
+use kftray_commons::models::config_model::Config;
+use kube::api::Api;
+use lazy_static::lazy_static;
+use log::{
+    debug,
+    error,
+    info,
+};
+use serde::Serialize;
+use tokio::task::JoinHandle;
+
+use crate::core::start_port_forward;
+use crate::models::kube::HttpLogState;
+use crate::pod_finder::{
+    config_target,
+    TargetPodFinder,
+};
+
+const PENDING_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PendingReason {
+    // the service or the pods of the config don't exist yet
+    Target,
+    // the service exists without ready endpoints
+    Endpoints,
+}
+
+struct PendingForward {
+    target: String,
+    namespace: String,
+    reason: PendingReason,
+    since: Instant,
+    handle: JoinHandle<()>,
+}
+
+lazy_static! {
+    static ref PENDING_STARTS: Mutex<HashMap<i64, PendingForward>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PendingStart {
+    pub config_id: i64,
+    pub target: String,
+    pub namespace: String,
+    pub reason: PendingReason,
+    pub waiting_secs: u64,
+}
+
+pub fn ready_addresses(endpoints: &Endpoints) -> usize {
+    endpoints
+        .subsets
+        .iter()
+        .flatten()
+        .map(|subset| subset.addresses.as_ref().map_or(0, Vec::len))
+        .sum()
+}
+
+pub fn no_endpoints_message(service: &str) -> String {
+    format!(
+        "Service {} has 0 ready endpoints (replicas scaled to 0?)",
+        service
+    )
+}
+
+pub fn missing_target_message(config: &Config) -> String {
+    match config.workload_type.as_deref() {
+        Some("pod") => format!(
+            "No pods match label selector '{}' in namespace '{}'",
+            config.target.clone().unwrap_or_default(),
+            config.namespace
+        ),
+        _ => format!(
+            "Service '{}' not found in namespace '{}'",
+            config.service.clone().unwrap_or_default(),
+            config.namespace
+        ),
+    }
+}
+
+// none when there is no service with a selector to count for, the pod finder
+// then falls back to an `app` label. errors don't hold up a start either
+pub(crate) async fn ready_endpoint_count(services: &Api<Service>, service: &str) -> Option<usize> {
+    let found = services
+        .get_opt(service)
+        .await
+        .map_err(|e| debug!("Failed to get service {}: {}", service, e))
+        .ok()
+        .flatten()?;
+    found
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.selector.as_ref())
+        .filter(|selector| !selector.is_empty())?;
+
+    let namespace = found.metadata.namespace.unwrap_or_default();
+    let endpoints: Api<Endpoints> = Api::namespaced(services.clone().into_client(), &namespace);
+    let endpoints = endpoints
+        .get_opt(service)
+        .await
+        .map_err(|e| debug!("Failed to get the endpoints of {}: {}", service, e))
+        .ok()?;
+    Some(endpoints.as_ref().map_or(0, ready_addresses))
+}
+
+// a missing service still counts as there when pods carry its name as their
+// `app` label, that's what the pod finder forwards to. none on errors
+pub(crate) async fn target_exists(
+    pods: &Api<Pod>, services: &Api<Service>, config: &Config,
+) -> Option<bool> {
+    if config.workload_type.as_deref() != Some("pod") {
+        let service = config.service.clone().unwrap_or_default();
+        let found = services
+            .get_opt(&service)
+            .await
+            .map_err(|e| debug!("Failed to get service {}: {}", service, e))
+            .ok()?;
+        if found.is_some() {
+            return Some(true);
+        }
+    }
+
+    let finder = TargetPodFinder {
+        pod_api: pods,
+        svc_api: services,
+    };
+    finder
+        .list_pods(&config_target(config))
+        .await
+        .map_err(|e| debug!("Failed to list the pods of the target: {}", e))
+        .ok()
+        .map(|pods| !pods.is_empty())
+}
+
+// a target that appeared may still have to get ready endpoints before the
+// start can go through
+async fn ready_to_start(
+    reason: PendingReason, pods: &Api<Pod>, services: &Api<Service>, config: &Config,
+) -> bool {
+    let service = config.service.clone().unwrap_or_default();
+    let endpoints_ready = || async {
+        config.workload_type.as_deref() == Some("pod")
+            || ready_endpoint_count(services, &service).await != Some(0)
+    };
+    match reason {
+        PendingReason::Target => {
+            target_exists(pods, services, config).await.unwrap_or(false) && endpoints_ready().await
+        }
+        PendingReason::Endpoints => endpoints_ready().await,
+    }
+}
+
+// polls the target of the config and starts it once it can be. the config is
+// kept unresolved so the start resolves its templates again
+pub(crate) fn queue_start(
+    config: Config, reason: PendingReason, protocol: &str, pods: Api<Pod>, services: Api<Service>,
+    http_log_state: Arc<HttpLogState>,
+) {
+    let Some(config_id) = config.id else {
+        return;
+    };
+    cancel_pending_start(Some(config_id));
+
+    let target = match config.workload_type.as_deref() {
+        Some("pod") => config.target.clone().unwrap_or_default(),
+        _ => config.service.clone().unwrap_or_default(),
+    };
+    let namespace = config.namespace.clone();
+    let protocol = protocol.to_string();
+    let handle = tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(PENDING_POLL_INTERVAL).await;
+            if ready_to_start(reason, &pods, &services, &config).await {
+                break;
+            }
+        }
+
+        PENDING_STARTS.lock().unwrap().remove(&config_id);
+        info!("The target of config {} is up, starting it", config_id);
+        if let Err(e) = start_port_forward(vec![config], &protocol, http_log_state).await {
+            error!("Failed to start config {}: {}", config_id, e);
+        }
+    });
+
+    info!(
+        "Config {} waits for {} in {} to start",
+        config_id, target, namespace
+    );
+    PENDING_STARTS.lock().unwrap().insert(
+        config_id,
+        PendingForward {
+            target,
+            namespace,
+            reason,
+            since: Instant::now(),
+            handle,
+        },
+    );
+}
+
+pub fn cancel_pending_start(config_id: Option<i64>) {
+    let cancelled: Vec<PendingForward> = {
+        let mut pending = PENDING_STARTS.lock().unwrap();
+        match config_id {
+            Some(config_id) => pending.remove(&config_id).into_iter().collect(),
+            None => pending.drain().map(|(_, forward)| forward).collect(),
+        }
+    };
+
+    for forward in cancelled {
+        forward.handle.abort();
+    }
+}
+
+pub fn get_pending_starts() -> Vec<PendingStart> {
+    let mut pending: Vec<PendingStart> = PENDING_STARTS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(config_id, forward)| PendingStart {
+            config_id: *config_id,
+            target: forward.target.clone(),
+            namespace: forward.namespace.clone(),
+            reason: forward.reason,
+            waiting_secs: forward.since.elapsed().as_secs(),
+        })
+        .collect();
+    pending.sort_by_key(|forward| forward.config_id);
+    pending
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::core::v1::{
+        EndpointAddress,
+        EndpointSubset,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_ready_addresses() {
+        let address = |ip: &str| EndpointAddress {
+            ip: ip.to_string(),
+            ..Default::default()
+        };
+        let endpoints = Endpoints {
+            subsets: Some(vec![
+                EndpointSubset {
+                    addresses: Some(vec![address("10.0.0.1"), address("10.0.0.2")]),
+                    not_ready_addresses: Some(vec![address("10.0.0.3")]),
+                    ..Default::default()
+                },
+                EndpointSubset {
+                    not_ready_addresses: Some(vec![address("10.0.0.4")]),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(ready_addresses(&endpoints), 2);
+        assert_eq!(ready_addresses(&Endpoints::default()), 0);
+        assert_eq!(
+            no_endpoints_message("cart"),
+            "Service cart has 0 ready endpoints (replicas scaled to 0?)"
+        );
+    }
+
+    #[test]
+    fn test_missing_target_message() {
+        let mut config = Config {
+            namespace: "dev".to_string(),
+            service: Some("cart".to_string()),
+            workload_type: Some("service".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            missing_target_message(&config),
+            "Service 'cart' not found in namespace 'dev'"
+        );
+        config.workload_type = Some("pod".to_string());
+        config.target = Some("app=cart".to_string());
+        assert_eq!(
+            missing_target_message(&config),
+            "No pods match label selector 'app=cart' in namespace 'dev'"
+        );
+    }
+}
@@ -1,17 +1,34 @@
 use anyhow::Result;
+use k8s_openapi::api::core::v1::{
+    Pod,
+    Service,
+};
+use kftray_commons::models::config_model::Config;
 use kube::api::{
     Api,
     ListParams,
 };
+use kube::Client;
 use tracing::debug;
 
+use crate::client::{
+    create_client_with_tuning,
+    KubeTuning,
+};
 use crate::models::kube::{
     AnyReady,
+    NameSpace,
     PodSelection,
+    Port,
     Target,
     TargetPod,
     TargetSelector,
 };
+use crate::ownership::owned_pods_selector;
+use crate::pod_watch::{
+    get_target_pod_status,
+    pick_pod,
+};
 pub struct TargetPodFinder<'a> {
     pub pod_api: &'a Api<k8s_openapi::api::core::v1::Pod>,
     pub svc_api: &'a Api<k8s_openapi::api::core::v1::Service>,
@@ -29,9 +46,43 @@ impl<'a> TargetPodFinder<'a> {
             TargetSelector::PodLabel(label) => {
                 self.find_pod_by_label(label, &ready_pod, target).await
             }
+            TargetSelector::PodName(name) => {
+                let pod = self.pod_api.get(name).await?;
+                target.find(&pod, None)
+            }
         }
     }
 
+    pub(crate) async fn list_pods(&self, target: &Target) -> Result<Vec<Pod>> {
+        let label_selector_str = match &target.selector {
+            TargetSelector::ServiceName(name) => match self.svc_api.get(name).await {
+                Ok(service) => service
+                    .spec
+                    .and_then(|spec| spec.selector)
+                    .map(|selector| {
+                        selector
+                            .iter()
+                            .map(|(key, value)| format!("{}={}", key, value))
+                            .collect::<Vec<_>>()
+                            .join(",")
+                    })
+                    .ok_or_else(|| anyhow::anyhow!("No selector found for service '{}'", name))?,
+                Err(kube::Error::Api(kube::error::ErrorResponse { code: 404, .. })) => {
+                    format!("app={}", name)
+                }
+                Err(e) => return Err(anyhow::anyhow!("Error finding service '{}': {}", name, e)),
+            },
+            TargetSelector::PodLabel(label) => label.clone(),
+            TargetSelector::PodName(name) => return Ok(vec![self.pod_api.get(name).await?]),
+        };
+
+        let pods = self
+            .pod_api
+            .list(&ListParams::default().labels(&label_selector_str))
+            .await?;
+        Ok(pods.items)
+    }
+
     async fn find_pod_by_service_name(
         &self, name: &str, ready_pod: &AnyReady, target: &Target,
     ) -> Result<TargetPod> {
@@ -102,3 +153,64 @@ impl<'a> TargetPodFinder<'a> {
         target.find(pod, None)
     }
 }
+
+pub(crate) fn is_proxy_config(config: &Config) -> bool {
+    config.protocol == "udp" || config.workload_type.as_deref() == Some("proxy")
+}
+
+pub(crate) fn config_target(config: &Config) -> Target {
+    let selector = match config.workload_type.as_deref() {
+        Some("pod") => TargetSelector::PodLabel(config.target.clone().unwrap_or_default()),
+        _ => TargetSelector::ServiceName(config.service.clone().unwrap_or_default()),
+    };
+
+    Target {
+        selector,
+        port: Port::from(config.remote_port.unwrap_or_default() as i32),
+        namespace: NameSpace(Some(config.namespace.clone())),
+    }
+}
+
+pub(crate) async fn config_client(config: &Config) -> Result<Client, String> {
+    let (client, _, _) = create_client_with_tuning(
+        config.kubeconfig.clone(),
+        Some(&config.context),
+        KubeTuning::for_config(config),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    client.ok_or_else(|| "Client not created".to_string())
+}
+
+// resolves the pod a config forwards to, outside of a running forward
+pub(crate) async fn resolve_config_pod(
+    config: &Config, pod_api: &Api<Pod>, svc_api: &Api<Service>,
+) -> Result<Pod, String> {
+    let config_id = config.id.unwrap_or_default();
+
+    // proxy pods are labelled with their owner and the config they were
+    // deployed for
+    if is_proxy_config(config) {
+        let pods = pod_api
+            .list(&ListParams::default().labels(&owned_pods_selector(Some(config_id))))
+            .await
+            .map_err(|e| e.to_string())?;
+        return pick_pod(pods.items)
+            .ok_or_else(|| format!("No kftray proxy pod found for config {}", config_id));
+    }
+
+    // a running forward already knows which pod it is watching
+    if let Some(status) = get_target_pod_status(config_id).filter(|s| !s.pod_name.is_empty()) {
+        return pod_api
+            .get(&status.pod_name)
+            .await
+            .map_err(|e| e.to_string());
+    }
+
+    let finder = TargetPodFinder { pod_api, svc_api };
+    let pods = finder
+        .list_pods(&config_target(config))
+        .await
+        .map_err(|e| e.to_string())?;
+    pick_pod(pods).ok_or_else(|| "No pods found for the target".to_string())
+}
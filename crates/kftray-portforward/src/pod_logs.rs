@@ -0,0 +1,55 @@
+use futures::stream::BoxStream;
+use futures::{
+    AsyncBufReadExt,
+    StreamExt,
+    TryStreamExt,
+};
+use k8s_openapi::api::core::v1::{
+    Pod,
+    Service,
+};
+use kftray_commons::config::get_config;
+use kube::api::{
+    Api,
+    LogParams,
+};
+
+use crate::pod_finder::{
+    config_client,
+    resolve_config_pod,
+};
+
+pub struct PodLogStream {
+    pub pod_name: String,
+    pub lines: BoxStream<'static, Result<String, String>>,
+}
+
+pub async fn stream_pod_logs(
+    config_id: i64, follow: bool, tail_lines: Option<i64>,
+) -> Result<PodLogStream, String> {
+    let config = get_config(config_id).await?;
+
+    let client = config_client(&config).await?;
+    let pod_api: Api<Pod> = Api::namespaced(client.clone(), &config.namespace);
+    let svc_api: Api<Service> = Api::namespaced(client, &config.namespace);
+    let pod_name = resolve_config_pod(&config, &pod_api, &svc_api)
+        .await?
+        .metadata
+        .name
+        .unwrap_or_default();
+
+    let params = LogParams {
+        follow,
+        tail_lines,
+        ..Default::default()
+    };
+    let reader = pod_api
+        .log_stream(&pod_name, &params)
+        .await
+        .map_err(|e| format!("Failed to stream logs of pod {}: {}", pod_name, e))?;
+
+    Ok(PodLogStream {
+        pod_name,
+        lines: reader.lines().map_err(|e| e.to_string()).boxed(),
+    })
+}
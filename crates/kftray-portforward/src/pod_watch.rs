@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use k8s_openapi::api::core::v1::{
+    Event,
+    Pod,
+};
+use kftray_commons::models::pod_status_model::{
+    PodEvent,
+    TargetPodStatus,
+};
+use kftray_commons::utils::access_log::now_millis;
+use kube::api::{
+    Api,
+    ListParams,
+};
+use lazy_static::lazy_static;
+use log::debug;
+use tokio::task::JoinHandle;
+
+use crate::models::kube::{
+    is_pod_ready,
+    PortForward,
+};
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(10);
+const MAX_POD_EVENTS: usize = 10;
+
+lazy_static! {
+    static ref POD_STATUSES: Mutex<HashMap<i64, TargetPodStatus>> = Mutex::new(HashMap::new());
+    static ref POD_WATCHES: Mutex<HashMap<i64, JoinHandle<()>>> = Mutex::new(HashMap::new());
+}
+
+// prefers the pod connections are routed to, falling back to an unready one
+// so a crash-looping target is still reported
+pub(crate) fn pick_pod(pods: Vec<Pod>) -> Option<Pod> {
+    match pods.iter().position(|pod| is_pod_ready(&pod)) {
+        Some(index) => pods.into_iter().nth(index),
+        None => pods.into_iter().next(),
+    }
+}
+
+pub fn summarize_pod(config_id: i64, pod: &Pod) -> TargetPodStatus {
+    let status = pod.status.clone().unwrap_or_default();
+    let containers = status.container_statuses.unwrap_or_default();
+
+    TargetPodStatus {
+        config_id,
+        pod_name: pod.metadata.name.clone().unwrap_or_default(),
+        phase: status.phase.unwrap_or_else(|| "Unknown".to_string()),
+        ready: is_pod_ready(&pod),
+        restart_count: containers.iter().map(|c| c.restart_count).sum(),
+        waiting_reason: containers.iter().find_map(|c| {
+            let state = c.state.as_ref()?;
+            state
+                .waiting
+                .as_ref()
+                .and_then(|waiting| waiting.reason.clone())
+                .or_else(|| {
+                    state
+                        .terminated
+                        .as_ref()
+                        .and_then(|terminated| terminated.reason.clone())
+                })
+        }),
+        events: Vec::new(),
+        updated_at_ms: now_millis(),
+        error: None,
+    }
+}
+
+pub fn summarize_events(events: Vec<Event>) -> Vec<PodEvent> {
+    let mut events: Vec<PodEvent> = events
+        .into_iter()
+        .map(|event| PodEvent {
+            last_seen_ms: event
+                .last_timestamp
+                .map(|time| time.0)
+                .or(event.event_time.map(|time| time.0))
+                .or(event.metadata.creation_timestamp.map(|time| time.0))
+                .map(|time| time.timestamp_millis())
+                .unwrap_or_default(),
+            kind: event.type_.unwrap_or_default(),
+            reason: event.reason.unwrap_or_default(),
+            message: event.message.unwrap_or_default(),
+            count: event.count.unwrap_or(1),
+        })
+        .collect();
+
+    events.sort_by_key(|event| std::cmp::Reverse(event.last_seen_ms));
+    events.truncate(MAX_POD_EVENTS);
+    events
+}
+
+async fn fetch_status(pf: &PortForward) -> anyhow::Result<TargetPodStatus> {
+    let pod = pick_pod(pf.finder().list_pods(&pf.target).await?)
+        .ok_or_else(|| anyhow::anyhow!("No pods found for the target"))?;
+    let mut status = summarize_pod(pf.config_id, &pod);
+
+    let events: Api<Event> = Api::namespaced(
+        pf.pod_api.clone().into_client(),
+        &pf.target.namespace.name_any(),
+    );
+    let field_selector = format!(
+        "involvedObject.kind=Pod,involvedObject.name={}",
+        status.pod_name
+    );
+    status.events = summarize_events(
+        events
+            .list(&ListParams::default().fields(&field_selector))
+            .await?
+            .items,
+    );
+
+    Ok(status)
+}
+
+pub fn start_pod_watch(pf: &PortForward) {
+    let pf = pf.clone();
+    let config_id = pf.config_id;
+
+    let handle = tokio::spawn(async move {
+        loop {
+            let status = fetch_status(&pf).await.unwrap_or_else(|e| {
+                debug!(
+                    "Failed to read target pod status for config {}: {}",
+                    config_id, e
+                );
+                TargetPodStatus {
+                    config_id,
+                    updated_at_ms: now_millis(),
+                    error: Some(e.to_string()),
+                    ..Default::default()
+                }
+            });
+            POD_STATUSES.lock().unwrap().insert(config_id, status);
+
+            tokio::time::sleep(WATCH_INTERVAL).await;
+        }
+    });
+
+    if let Some(previous) = POD_WATCHES.lock().unwrap().insert(config_id, handle) {
+        previous.abort();
+    }
+}
+
+pub fn stop_pod_watch(config_id: Option<i64>) {
+    let stopped: Vec<JoinHandle<()>> = {
+        let mut watches = POD_WATCHES.lock().unwrap();
+        match config_id {
+            Some(config_id) => watches.remove(&config_id).into_iter().collect(),
+            None => watches.drain().map(|(_, handle)| handle).collect(),
+        }
+    };
+
+    let mut statuses = POD_STATUSES.lock().unwrap();
+    match config_id {
+        Some(config_id) => {
+            statuses.remove(&config_id);
+        }
+        None => statuses.clear(),
+    }
+
+    for handle in stopped {
+        handle.abort();
+    }
+}
+
+pub fn get_target_pod_status(config_id: i64) -> Option<TargetPodStatus> {
+    POD_STATUSES.lock().unwrap().get(&config_id).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::core::v1::{
+        ContainerState,
+        ContainerStateWaiting,
+        ContainerStatus,
+        PodStatus,
+    };
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+
+    use super::*;
+
+    #[test]
+    fn test_summarize_crash_looping_pod() {
+        let pod = Pod {
+            metadata: ObjectMeta {
+                name: Some("api-0".to_string()),
+                ..Default::default()
+            },
+            status: Some(PodStatus {
+                phase: Some("Running".to_string()),
+                container_statuses: Some(vec![ContainerStatus {
+                    name: "api".to_string(),
+                    restart_count: 7,
+                    state: Some(ContainerState {
+                        waiting: Some(ContainerStateWaiting {
+                            reason: Some("CrashLoopBackOff".to_string()),
+                            message: None,
+                        }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let status = summarize_pod(3, &pod);
+        assert_eq!(status.pod_name, "api-0");
+        assert_eq!(status.phase, "Running");
+        assert!(!status.ready);
+        assert_eq!(status.restart_count, 7);
+        assert_eq!(status.waiting_reason.as_deref(), Some("CrashLoopBackOff"));
+    }
+}
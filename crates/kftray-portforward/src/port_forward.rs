@@ -1,8 +1,18 @@
 use std::collections::HashMap;
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::atomic::{
+    AtomicU64,
+    Ordering,
+};
 use std::sync::Mutex as StdMutex;
-use std::time::Duration;
+use std::sync::{
+    Arc,
+    OnceLock,
+};
+use std::time::{
+    Duration,
+    Instant,
+};
 
 use anyhow::Context;
 use futures::TryStreamExt;
@@ -10,6 +20,17 @@ use kftray_commons::logging::{
     create_log_file_path,
     Logger,
 };
+use kftray_commons::models::access_log_model::AccessLogEntry;
+use kftray_commons::models::protocol_model::StreamProtocol;
+use kftray_commons::utils::access_log::{
+    append_access_log,
+    is_access_log_enabled,
+    now_millis,
+};
+use kftray_commons::utils::config_log::spawn_for_config;
+use kftray_commons::utils::query_log::query_log_redaction;
+use kftray_commons::utils::session::current_session_id;
+use kftray_commons::utils::settings::current_settings;
 use kube::{
     api::Api,
     Client,
@@ -17,6 +38,7 @@ use kube::{
 use lazy_static::lazy_static;
 use tokio::net::TcpStream;
 use tokio::net::UdpSocket as TokioUdpSocket;
+use tokio::sync::mpsc;
 use tokio::sync::Mutex;
 use tokio::sync::Notify;
 use tokio::{
@@ -34,14 +56,76 @@ use tracing::{
     error,
     info,
     trace,
+    warn,
 };
 
+use crate::capture::{
+    get_capture,
+    TcpFlow,
+};
+use crate::circuit_breaker::{
+    check_circuit,
+    circuit_cooldown,
+    is_circuit_open,
+    record_upstream_failure,
+    record_upstream_success,
+};
+use crate::client::{
+    create_client_with_tuning,
+    KubeTuning,
+};
+use crate::connection_limit::ConnectionLimit;
+use crate::kafka::{
+    rewrite_kafka_responses,
+    KafkaSession,
+};
+use crate::mock_rules::{
+    mock_relay,
+    MockRelay,
+    Relayed,
+};
 use crate::models::kube::HttpLogState;
 use crate::models::kube::{
     PortForward,
     Target,
 };
 use crate::pod_finder::TargetPodFinder;
+use crate::protocol::{
+    record_detected_protocol,
+    sniff_protocol,
+    sniff_server_greeting,
+    MAX_SNIFF_READS,
+};
+use crate::spdy::spdy_portforward;
+use crate::target_switch::switched_forward;
+use crate::traffic::{
+    publish_connection_log,
+    traffic_counters,
+    TrafficCounters,
+};
+use crate::traffic_script::{
+    traffic_script,
+    ResponseRelay,
+    SentRequests,
+};
+use crate::traffic_split::split_forward;
+use crate::transport::{
+    Transport,
+    UpstreamStream,
+};
+use crate::udp::{
+    read_udp_frame,
+    register_udp_association,
+    remove_udp_association,
+    udp_reconnect_delay,
+    write_udp_frame,
+    UdpAssociation,
+    UDP_RECONNECT_ATTEMPTS,
+};
+use crate::wire::{
+    wire_decoder,
+    WireDecoder,
+};
 
 lazy_static! {
     pub static ref CHILD_PROCESSES: Arc<StdMutex<HashMap<String, JoinHandle<()>>>> =
@@ -49,17 +133,144 @@ lazy_static! {
     pub static ref CANCEL_NOTIFIER: Arc<Notify> = Arc::new(Notify::new());
 }
 
+// where a forward listens, its own address or the default from settings
+pub(crate) fn listen_address(local_address: Option<String>) -> String {
+    local_address.unwrap_or_else(|| current_settings().default_local_address)
+}
+
+// handles are keyed `{config_id}_{service}@{session_id}`
+pub fn child_process_key(config_id: i64, service: &str) -> String {
+    format!("{}_{}@{}", config_id, service, current_session_id())
+}
+
+pub fn parse_child_process_key(key: &str) -> Option<(i64, &str)> {
+    let (config_id, rest) = key.split_once('_')?;
+    let service = rest.rsplit_once('@').map_or(rest, |(service, _)| service);
+    Some((config_id.parse().ok()?, service))
+}
+
 const BUFFER_SIZE: usize = 131072;
+const UDP_ASSOCIATION_QUEUE: usize = 256;
+
+#[derive(Debug, Default)]
+struct ConnectionStats {
+    config_id: i64,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    traffic: Option<Arc<TrafficCounters>>,
+    capture: Option<TcpFlow>,
+    protocol: OnceLock<StreamProtocol>,
+    sniff: StdMutex<(Vec<u8>, usize)>,
+    decoder: StdMutex<Option<Box<dyn WireDecoder>>>,
+    kafka: Option<StdMutex<KafkaSession>>,
+}
+
+impl ConnectionStats {
+    fn record_in(&self, data: &[u8]) {
+        self.bytes_in
+            .fetch_add(data.len() as u64, Ordering::Relaxed);
+        if let Some(traffic) = &self.traffic {
+            traffic.record_in(data.len());
+        }
+        if let Some(capture) = &self.capture {
+            capture.client_data(data);
+        }
+        if let Some(kafka) = &self.kafka {
+            kafka.lock().unwrap().client_data(data);
+        }
+
+        if self.protocol.get().is_some() {
+            self.decode(|decoder| decoder.client_data(data));
+        } else {
+            self.sniff(data, false);
+        }
+    }
+
+    fn record_out(&self, data: &[u8]) {
+        self.bytes_out
+            .fetch_add(data.len() as u64, Ordering::Relaxed);
+        if let Some(traffic) = &self.traffic {
+            traffic.record_out(data.len());
+        }
+        if let Some(capture) = &self.capture {
+            capture.server_data(data);
+        }
+
+        if self.protocol.get().is_some() {
+            self.decode(|decoder| decoder.server_data(data));
+        } else if self.sniff.lock().unwrap().0.is_empty() {
+            if let Some(protocol) = sniff_server_greeting(data) {
+                if self.detected(protocol) {
+                    self.decode(|decoder| decoder.server_data(data));
+                }
+            }
+        }
+    }
+
+    fn sniff(&self, data: &[u8], complete: bool) {
+        let mut sniff = self.sniff.lock().unwrap();
+        let (buffer, reads) = &mut *sniff;
+        if !data.is_empty() {
+            buffer.extend_from_slice(data);
+            *reads += 1;
+        } else if buffer.is_empty() {
+            return;
+        }
+
+        if let Some(protocol) = sniff_protocol(buffer, complete || *reads >= MAX_SNIFF_READS) {
+            let buffered = std::mem::take(buffer);
+            drop(sniff);
+
+            if self.detected(protocol) {
+                self.decode(|decoder| decoder.client_data(&buffered));
+            }
+        }
+    }
+
+    fn detected(&self, protocol: StreamProtocol) -> bool {
+        if self.protocol.set(protocol).is_err() {
+            return false;
+        }
+
+        debug!(
+            "Detected {} on connection for config {}",
+            protocol.as_str(),
+            self.config_id
+        );
+        record_detected_protocol(self.config_id, protocol);
+
+        if let Some(redact) = query_log_redaction(self.config_id) {
+            *self.decoder.lock().unwrap() = wire_decoder(protocol, self.config_id, redact);
+        }
+        true
+    }
+
+    fn decode(&self, decode: impl FnOnce(&mut dyn WireDecoder)) {
+        if let Some(decoder) = self.decoder.lock().unwrap().as_mut() {
+            decode(decoder.as_mut());
+        }
+    }
+
+    fn finish_sniffing(&self) {
+        if self.protocol.get().is_none() {
+            self.sniff(&[], true);
+        }
+    }
+
+    fn protocol(&self) -> Option<StreamProtocol> {
+        self.protocol.get().copied()
+    }
+}
 
 impl PortForward {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         target: Target, local_port: impl Into<Option<u16>>,
         local_address: impl Into<Option<String>>, context_name: Option<String>,
-        kubeconfig: Option<String>, config_id: i64, workload_type: String,
+        kubeconfig: Option<String>, config_id: i64, workload_type: String, tuning: KubeTuning,
     ) -> anyhow::Result<Self> {
         let (client, _, _) = if let Some(ref context_name) = context_name {
-            crate::client::create_client_with_specific_context(kubeconfig, Some(context_name))
-                .await?
+            create_client_with_tuning(kubeconfig, Some(context_name), tuning).await?
         } else {
             (Some(Client::try_default().await?), None, Vec::new())
         };
@@ -82,9 +293,43 @@ impl PortForward {
             config_id,
             workload_type,
             connection: Arc::new(Mutex::new(None)),
+            protocol_hint: None,
+            transport: Transport::Auto,
+            negotiated_transport: Arc::new(StdMutex::new(None)),
+            connection_limit: None,
         })
     }
 
+    pub fn with_protocol_hint(mut self, protocol_hint: Option<StreamProtocol>) -> Self {
+        self.protocol_hint = protocol_hint;
+        self
+    }
+
+    pub fn with_transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    pub fn with_connection_limit(mut self, connection_limit: Option<ConnectionLimit>) -> Self {
+        self.connection_limit = connection_limit;
+        self
+    }
+
+    // the forward with the upstream of another one, the listener side like
+    // the config id, stats and connection limit stays
+    pub(crate) fn retargeted(&self, other: &PortForward) -> PortForward {
+        PortForward {
+            target: other.target.clone(),
+            pod_api: other.pod_api.clone(),
+            svc_api: other.svc_api.clone(),
+            context_name: other.context_name.clone(),
+            workload_type: other.workload_type.clone(),
+            transport: other.transport,
+            negotiated_transport: other.negotiated_transport.clone(),
+            ..self.clone()
+        }
+    }
+
     pub fn local_port(&self) -> u16 {
         self.local_port.unwrap_or(0)
     }
@@ -96,9 +341,7 @@ impl PortForward {
     pub async fn port_forward_tcp(
         self, http_log_state: Arc<HttpLogState>,
     ) -> anyhow::Result<(u16, tokio::task::JoinHandle<()>)> {
-        let local_addr = self
-            .local_address()
-            .unwrap_or_else(|| "127.0.0.1".to_string());
+        let local_addr = listen_address(self.local_address());
 
         let addr = format!("{}:{}", local_addr, self.local_port())
             .parse::<SocketAddr>()
@@ -107,19 +350,24 @@ impl PortForward {
         let bind = TcpListener::bind(addr).await?;
 
         let port = bind.local_addr()?.port();
+        let listen_addr = SocketAddr::new(addr.ip(), port);
 
         trace!(port, "Bound to local address and port");
 
+        let config_id = self.config_id;
+
         let server = {
             let cancel_notifier = CANCEL_NOTIFIER.clone();
             let http_log_state = http_log_state.clone();
             TcpListenerStream::new(bind).try_for_each(move |client_conn| {
-                let pf = self.clone();
+                // a switch replaces the target, splits included
+                let pf = switched_forward(&self).unwrap_or_else(|| split_forward(&self));
                 let client_conn = Arc::new(Mutex::new(client_conn));
                 let http_log_state = http_log_state.clone();
                 let cancel_notifier = cancel_notifier.clone();
                 async move {
-                    if let Ok(peer_addr) = client_conn.lock().await.peer_addr() {
+                    let peer_addr = client_conn.lock().await.peer_addr().ok();
+                    if let Some(peer_addr) = peer_addr {
                         trace!(%peer_addr, "new connection");
                     }
 
@@ -130,16 +378,79 @@ impl PortForward {
 
                     let cancel_notifier_clone = cancel_notifier.clone();
 
-                    tokio::spawn(async move {
-                        if let Err(e) = pf
-                            .forward_connection(client_conn, http_log_state, cancel_notifier_clone)
-                            .await
-                        {
+                    spawn_for_config(pf.config_id, async move {
+                        let config_id = pf.config_id;
+                        let started_at = now_millis();
+                        let started = Instant::now();
+                        let traffic = traffic_counters(config_id);
+                        let _open = traffic.open();
+                        let stats = Arc::new(ConnectionStats {
+                            config_id,
+                            traffic: Some(traffic),
+                            capture: get_capture(config_id).map(|writer| {
+                                TcpFlow::new(writer, peer_addr.unwrap_or(listen_addr), listen_addr)
+                            }),
+                            kafka: (pf.protocol_hint == Some(StreamProtocol::Kafka))
+                                .then(|| StdMutex::new(KafkaSession::default())),
+                            ..Default::default()
+                        });
+                        if let Some(protocol) = pf.protocol_hint {
+                            stats.detected(protocol);
+                        }
+
+                        // rejected connections are closed right away, queued
+                        // ones wait here until another
+                        // connection of the forward ends
+                        let slot = match &pf.connection_limit {
+                            Some(limit) => limit.acquire().await.map(Some),
+                            None => Ok(None),
+                        };
+                        let result = match slot {
+                            Ok(_slot) => {
+                                pf.forward_connection(
+                                    client_conn,
+                                    http_log_state,
+                                    cancel_notifier_clone,
+                                    stats.clone(),
+                                )
+                                .await
+                            }
+                            Err(e) => {
+                                warn!("Config {}: {}", config_id, e);
+                                Err(e)
+                            }
+                        };
+
+                        if let Err(e) = &result {
                             error!(
                                 error = e.as_ref() as &dyn std::error::Error,
                                 "failed to forward connection"
                             );
                         }
+
+                        if let Some(capture) = &stats.capture {
+                            capture.close();
+                        }
+                        stats.finish_sniffing();
+
+                        let entry = AccessLogEntry {
+                            config_id,
+                            timestamp_ms: started_at,
+                            client_addr: peer_addr.map(|addr| addr.to_string()).unwrap_or_default(),
+                            local_port: port,
+                            duration_ms: started.elapsed().as_millis() as u64,
+                            bytes_in: stats.bytes_in.load(Ordering::Relaxed),
+                            bytes_out: stats.bytes_out.load(Ordering::Relaxed),
+                            error: result.err().map(|e| e.to_string()),
+                            protocol: stats.protocol(),
+                        };
+
+                        if is_access_log_enabled(config_id) {
+                            if let Err(e) = append_access_log(&entry).await {
+                                error!("Failed to write access log: {}", e);
+                            }
+                        }
+                        publish_connection_log(entry);
                     });
 
                     Ok(())
@@ -149,7 +460,7 @@ impl PortForward {
 
         Ok((
             port,
-            tokio::spawn(async {
+            spawn_for_config(config_id, async {
                 if let Err(e) = server.await {
                     error!(error = &e as &dyn std::error::Error, "server error");
                 }
@@ -164,10 +475,74 @@ impl PortForward {
         }
     }
 
-    async fn forward_connection(
-        self, client_conn: Arc<Mutex<TcpStream>>, http_log_state: Arc<HttpLogState>,
-        cancel_notifier: Arc<Notify>,
-    ) -> anyhow::Result<()> {
+    async fn websocket_upstream(
+        &self, pod_name: &str, pod_port: u16,
+    ) -> anyhow::Result<Box<dyn UpstreamStream>> {
+        let mut forwarder = self.pod_api.portforward(pod_name, &[pod_port]).await?;
+        let stream = forwarder
+            .take_stream(pod_port)
+            .context("port not found in forwarder")?;
+        Ok(Box::new(stream))
+    }
+
+    async fn spdy_upstream(
+        &self, pod_name: &str, pod_port: u16,
+    ) -> anyhow::Result<Box<dyn UpstreamStream>> {
+        let client = self.pod_api.clone().into_client();
+        let namespace = self.target.namespace.name_any();
+        let stream = spdy_portforward(&client, &namespace, pod_name, pod_port).await?;
+        Ok(Box::new(stream))
+    }
+
+    // auto tries websockets first and sticks with spdy for the rest of the
+    // forward once the upgrade is refused, e.g. by a proxy in front of the api
+    async fn open_upstream(
+        &self, pod_name: &str, pod_port: u16,
+    ) -> anyhow::Result<Box<dyn UpstreamStream>> {
+        let transport = match self.transport {
+            Transport::Auto => self
+                .negotiated_transport
+                .lock()
+                .unwrap()
+                .unwrap_or(Transport::Auto),
+            transport => transport,
+        };
+
+        match transport {
+            Transport::WebSocket => self.websocket_upstream(pod_name, pod_port).await,
+            Transport::Spdy => self.spdy_upstream(pod_name, pod_port).await,
+            Transport::Auto => match self.websocket_upstream(pod_name, pod_port).await {
+                Ok(stream) => {
+                    *self.negotiated_transport.lock().unwrap() = Some(Transport::WebSocket);
+                    Ok(stream)
+                }
+                Err(websocket_error) => {
+                    debug!(
+                        "WebSocket port-forward for config {} failed, trying SPDY: {}",
+                        self.config_id, websocket_error
+                    );
+                    let stream =
+                        self.spdy_upstream(pod_name, pod_port)
+                            .await
+                            .map_err(|spdy_error| {
+                                anyhow::anyhow!(
+                                    "Port-forward failed over WebSocket ({}) and SPDY ({})",
+                                    websocket_error,
+                                    spdy_error
+                                )
+                            })?;
+                    info!(
+                        "Config {} falls back to SPDY port-forwarding",
+                        self.config_id
+                    );
+                    *self.negotiated_transport.lock().unwrap() = Some(Transport::Spdy);
+                    Ok(stream)
+                }
+            },
+        }
+    }
+
+    async fn connect_target(&self) -> anyhow::Result<(String, u16, Box<dyn UpstreamStream>)> {
         let target = self.finder().find(&self.target).await?;
 
         debug!("Forwarding connection to target pod");
@@ -178,13 +553,52 @@ impl PortForward {
         debug!("Pod name: {}", pod_name);
         debug!("Pod port: {}", pod_port);
 
-        let mut forwarder = self.pod_api.portforward(&pod_name, &[pod_port]).await?;
+        let upstream = self.open_upstream(&pod_name, pod_port).await?;
+        Ok((pod_name, pod_port, upstream))
+    }
 
-        debug!("Forwarder created");
+    // while the circuit is open the upstream is dialed once per cool-down,
+    // the first connection that goes through closes it again
+    async fn probe_until_recovered(self) {
+        loop {
+            tokio::time::sleep(circuit_cooldown()).await;
+            if !is_circuit_open(self.config_id) {
+                return;
+            }
 
-        let upstream_conn = forwarder
-            .take_stream(pod_port)
-            .context("port not found in forwarder")?;
+            match self.connect_target().await {
+                Ok(_) => {
+                    record_upstream_success(self.config_id);
+                    return;
+                }
+                Err(e) => {
+                    debug!("Probe for config {} failed: {}", self.config_id, e);
+                    record_upstream_failure(self.config_id, &e.to_string());
+                }
+            }
+        }
+    }
+
+    async fn forward_connection(
+        self, client_conn: Arc<Mutex<TcpStream>>, http_log_state: Arc<HttpLogState>,
+        cancel_notifier: Arc<Notify>, stats: Arc<ConnectionStats>,
+    ) -> anyhow::Result<()> {
+        check_circuit(self.config_id)?;
+
+        let (pod_name, pod_port, upstream_conn) = match self.connect_target().await {
+            Ok(connected) => {
+                record_upstream_success(self.config_id);
+                connected
+            }
+            Err(e) => {
+                if record_upstream_failure(self.config_id, &e.to_string()) {
+                    spawn_for_config(self.config_id, self.clone().probe_until_recovered());
+                }
+                return Err(e);
+            }
+        };
+
+        debug!("Forwarder created");
 
         let local_port = self.local_port();
         debug!("Local port: {}", local_port);
@@ -197,7 +611,7 @@ impl PortForward {
 
         let logger = if workload_type == "service" || workload_type == "pod" {
             let log_file_path = create_log_file_path(config_id, local_port).await?;
-            let logger = Logger::new(log_file_path).await?;
+            let logger = Logger::new(log_file_path, config_id).await?;
             Some(logger)
         } else {
             None
@@ -211,6 +625,15 @@ impl PortForward {
         debug!("Request ID created");
         debug!("Request ID: {:?}", request_id);
 
+        let script = traffic_script(config_id);
+        let sent_requests = SentRequests::default();
+        let response_relay = script
+            .clone()
+            .filter(|script| script.handles_responses())
+            .map(|script| ResponseRelay::new(script, sent_requests.clone()));
+        let mock_relay = mock_relay(config_id, script, sent_requests);
+        let (mock_tx, mock_rx) = mpsc::unbounded_channel();
+
         let mut client_conn_guard = client_conn.lock().await;
         client_conn_guard.set_nodelay(true)?;
         let (mut client_reader, mut client_writer) = tokio::io::split(&mut *client_conn_guard);
@@ -224,6 +647,9 @@ impl PortForward {
             &http_log_state,
             Arc::clone(&request_id),
             cancel_notifier.clone(),
+            &stats,
+            mock_relay,
+            mock_tx,
         );
 
         let upstream_to_client = self.create_upstream_to_client_task(
@@ -233,6 +659,9 @@ impl PortForward {
             &http_log_state,
             Arc::clone(&request_id),
             cancel_notifier.clone(),
+            &stats,
+            mock_rx,
+            response_relay,
         );
 
         let join_result = tokio::try_join!(client_to_upstream, upstream_to_client);
@@ -256,6 +685,10 @@ impl PortForward {
             }
         }
 
+        if let Some(logger) = &logger {
+            logger.finish();
+        }
+
         drop(client_conn_guard);
         drop(upstream_reader);
         trace!(local_port, pod_port, pod_name = %pod_name, "connection fully closed");
@@ -263,6 +696,7 @@ impl PortForward {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn create_client_to_upstream_task<'a>(
         &'a self, client_reader: &'a mut tokio::io::ReadHalf<&mut TcpStream>,
         upstream_writer: &'a mut tokio::io::WriteHalf<
@@ -270,9 +704,12 @@ impl PortForward {
         >,
         logger: Option<Logger>, http_log_state: &HttpLogState,
         request_id: Arc<Mutex<Option<String>>>, cancel_notifier: Arc<Notify>,
+        stats: &'a ConnectionStats, mut mock_relay: Option<MockRelay>,
+        mock_tx: mpsc::UnboundedSender<Vec<u8>>,
     ) -> anyhow::Result<()> {
         let mut buffer = [0; BUFFER_SIZE];
-        let mut timeout_duration = Duration::from_secs(600);
+        let idle_timeout = Duration::from_secs(current_settings().idle_timeout_secs);
+        let mut timeout_duration = idle_timeout;
         let mut request_buffer = Vec::new();
 
         loop {
@@ -295,9 +732,13 @@ impl PortForward {
                     }
 
                     trace!("Read {} bytes from client", n);
+                    stats.record_in(&buffer[..n]);
                     request_buffer.extend_from_slice(&buffer[..n]);
 
-                        if http_log_state.get_http_logs(self.config_id).await {
+                        let is_http1 = stats
+                            .protocol()
+                            .is_none_or(|protocol| protocol == StreamProtocol::Http1);
+                        if is_http1 && http_log_state.get_http_logs(self.config_id).await {
                             if let Some(logger) = &logger {
                                 let mut req_id_guard = request_id.lock().await;
                                 let new_request_id =
@@ -307,9 +748,30 @@ impl PortForward {
                             }
                         }
 
-                        if let Err(e) = upstream_writer.write_all(&request_buffer).await {
-                            error!("Error writing to upstream: {:?}", e);
-                            return Err(e.into());
+                        // mocked requests are answered from here, the rest goes on
+                        let relayed = match &mut mock_relay {
+                            Some(relay) => relay.feed(&request_buffer),
+                            None => vec![Relayed::Upstream(std::mem::take(&mut request_buffer))],
+                        };
+                        for relayed in relayed {
+                            match relayed {
+                                Relayed::Upstream(data) => {
+                                    if let Err(e) = upstream_writer.write_all(&data).await {
+                                        error!("Error writing to upstream: {:?}", e);
+                                        return Err(e.into());
+                                    }
+                                }
+                                Relayed::Client(response) => {
+                                    if let Some(logger) = &logger {
+                                        if let Some(req_id) = &*request_id.lock().await {
+                                            logger
+                                                .log_response(response.clone().into(), req_id.clone())
+                                                .await;
+                                        }
+                                    }
+                                    let _ = mock_tx.send(response);
+                                }
+                            }
                         }
                         request_buffer.clear();
                 },
@@ -320,7 +782,7 @@ impl PortForward {
                 }
             }
 
-            timeout_duration = Duration::from_secs(600);
+            timeout_duration = idle_timeout;
         }
 
         if let Err(e) = upstream_writer.shutdown().await {
@@ -330,6 +792,7 @@ impl PortForward {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn create_upstream_to_client_task<'a>(
         &'a self,
         upstream_reader: &'a mut tokio::io::ReadHalf<
@@ -337,10 +800,12 @@ impl PortForward {
         >,
         client_writer: &'a mut tokio::io::WriteHalf<&mut TcpStream>, logger: Option<Logger>,
         http_log_state: &HttpLogState, request_id: Arc<Mutex<Option<String>>>,
-        cancel_notifier: Arc<Notify>,
+        cancel_notifier: Arc<Notify>, stats: &'a ConnectionStats,
+        mut mock_rx: mpsc::UnboundedReceiver<Vec<u8>>, mut response_relay: Option<ResponseRelay>,
     ) -> anyhow::Result<()> {
         let mut buffer = [0; BUFFER_SIZE];
-        let mut timeout_duration = Duration::from_secs(600);
+        let idle_timeout = Duration::from_secs(current_settings().idle_timeout_secs);
+        let mut timeout_duration = idle_timeout;
         let mut response_buffer = Vec::new();
 
         loop {
@@ -363,6 +828,7 @@ impl PortForward {
                     }
 
                     trace!("Read {} bytes from upstream", n);
+                    stats.record_out(&buffer[..n]);
                     response_buffer.extend_from_slice(&buffer[..n]);
 
                         if http_log_state.get_http_logs(self.config_id).await {
@@ -377,6 +843,15 @@ impl PortForward {
                             }
                         }
 
+                        if let Some(kafka) = &stats.kafka {
+                            response_buffer =
+                                rewrite_kafka_responses(self, kafka, &response_buffer).await;
+                        }
+
+                        if let Some(relay) = &mut response_relay {
+                            response_buffer = relay.feed(&response_buffer);
+                        }
+
                         if let Err(e) = client_writer.write_all(&response_buffer).await {
                             error!("Error writing to client: {:?}", e);
                             return Err(e.into());
@@ -385,7 +860,13 @@ impl PortForward {
                         response_buffer.clear();
 
 
-                    timeout_duration = Duration::from_secs(600);
+                    timeout_duration = idle_timeout;
+                },
+                Some(response) = mock_rx.recv() => {
+                    if let Err(e) = client_writer.write_all(&response).await {
+                        error!("Error writing to client: {:?}", e);
+                        return Err(e.into());
+                    }
                 },
                 _ = cancel_notifier.notified() => {
                     trace!("Upstream to client task cancelled");
@@ -449,9 +930,7 @@ impl PortForward {
     }
 
     pub async fn port_forward_udp(self) -> anyhow::Result<(u16, JoinHandle<()>)> {
-        let local_address = self
-            .local_address()
-            .unwrap_or_else(|| "127.0.0.1".to_string());
+        let local_address = listen_address(self.local_address());
 
         let local_udp_addr = format!("{}:{}", local_address, self.local_port());
 
@@ -465,108 +944,199 @@ impl PortForward {
 
         info!("Local UDP socket bound to {}", local_udp_addr);
 
-        let target = self.finder().find(&self.target).await?;
-
-        let (pod_name, pod_port) = target.into_parts();
+        // fail the start right away when there's no pod to relay to
+        self.finder().find(&self.target).await?;
 
-        let mut port_forwarder = self
-            .pod_api
-            .portforward(&pod_name, &[pod_port])
-            .await
-            .context("Failed to start port forwarding to pod")?;
+        let handle = spawn_for_config(self.config_id, async move {
+            let mut udp_buffer = [0u8; BUFFER_SIZE];
+            let mut associations: HashMap<
+                SocketAddr,
+                (mpsc::Sender<Vec<u8>>, Arc<UdpAssociation>),
+            > = HashMap::new();
 
-        let (mut tcp_read, mut tcp_write) = tokio::io::split(
-            port_forwarder
-                .take_stream(pod_port)
-                .context("port not found in forwarder")?,
-        );
+            loop {
+                let (len, peer) = match local_udp_socket.recv_from(&mut udp_buffer).await {
+                    Ok(received) => received,
+                    // icmp errors for an earlier send, reported by some platforms
+                    Err(e)
+                        if matches!(
+                            e.kind(),
+                            std::io::ErrorKind::ConnectionReset
+                                | std::io::ErrorKind::ConnectionRefused
+                        ) =>
+                    {
+                        debug!("Ignoring UDP socket error: {:?}", e);
+                        continue;
+                    }
+                    Err(e) => {
+                        error!("Failed to receive from UDP socket: {:?}", e);
+                        break;
+                    }
+                };
+
+                associations.retain(|_, (sender, _)| !sender.is_closed());
+                let (sender, association) = associations.entry(peer).or_insert_with(|| {
+                    debug!("New UDP association for {}", peer);
+                    let (sender, datagrams) = mpsc::channel(UDP_ASSOCIATION_QUEUE);
+                    let association = register_udp_association(self.config_id, peer);
+                    spawn_for_config(
+                        self.config_id,
+                        self.clone().relay_udp_association(
+                            local_udp_socket.clone(),
+                            datagrams,
+                            association.clone(),
+                        ),
+                    );
+                    (sender, association)
+                });
+
+                // like any udp hop, datagrams are dropped rather than queued
+                // without bound while the upstream is being re-established
+                if sender.try_send(udp_buffer[..len].to_vec()).is_err() {
+                    association.record_dropped();
+                }
+            }
+        });
 
-        let local_udp_socket_read = local_udp_socket.clone();
+        Ok((local_port, handle))
+    }
 
-        let local_udp_socket_write = local_udp_socket;
+    async fn open_udp_upstream(&self) -> anyhow::Result<Box<dyn UpstreamStream>> {
+        let (_, _, upstream) = self
+            .connect_target()
+            .await
+            .context("Failed to start port forwarding to pod")?;
+        Ok(upstream)
+    }
 
-        let handle = tokio::spawn(async move {
-            let mut udp_buffer = [0u8; BUFFER_SIZE];
-            let mut peer: Option<std::net::SocketAddr> = None;
+    // relays one peer over its own upstream stream, which is opened again
+    // against whatever pod backs the target when it dies, e.g. after the proxy
+    // pod restarted, and gives up after the association was idle for too long
+    async fn relay_udp_association(
+        self, socket: Arc<TokioUdpSocket>, mut datagrams: mpsc::Receiver<Vec<u8>>,
+        association: Arc<UdpAssociation>,
+    ) {
+        let peer = association.peer();
+        let idle_timeout = Duration::from_secs(current_settings().udp_idle_timeout_secs);
+        let mut upstream: Option<(JoinHandle<()>, _)> = None;
+        let mut pending: Option<Vec<u8>> = None;
+        let mut failures = 0;
+        let mut connected_before = false;
 
-            loop {
-                tokio::select! {
-                    result = local_udp_socket_read.recv_from(&mut udp_buffer) => {
-                        match result {
-                            Ok((len, src)) => {
-                                peer = Some(src);
-
-                                let packet_len = (len as u32).to_be_bytes();
-                                if let Err(e) = tcp_write.write_all(&packet_len).await {
-                                    error!("Failed to write packet length to TCP stream: {:?}", e);
-                                    break;
-                                }
-                                if let Err(e) = tcp_write.write_all(&udp_buffer[..len]).await {
-                                    error!("Failed to write UDP packet to TCP stream: {:?}", e);
-                                    break;
-                                }
-                                if let Err(e) = tcp_write.flush().await {
-                                    error!("Failed to flush TCP stream: {:?}", e);
-                                    break;
-                                }
-                            },
-                            Err(e) => {
-                                error!("Failed to receive from UDP socket: {:?}", e);
-                                break;
-                            }
+        loop {
+            if upstream.is_none() {
+                match self.open_udp_upstream().await {
+                    Ok(stream) => {
+                        if connected_before {
+                            info!("Re-established UDP upstream for {}", peer);
+                            association.record_reconnect();
                         }
-                    },
-
-                    result = Self::read_tcp_length_and_packet(&mut tcp_read) => {
-                        match result {
-                            Ok(Some(packet)) => {
-                                if let Some(peer) = peer {
-                                    if let Err(e) = local_udp_socket_write.send_to(&packet, &peer).await {
-                                        error!("Failed to send UDP packet to peer: {:?}", e);
-                                        break;
-                                    }
-                                } else {
-                                    error!("No UDP peer to send to");
-                                    break;
-                                }
-                            },
-                            Ok(None) => {
-                                break;
-                            }
-                            Err(e) => {
-                                error!("Failed to read from TCP stream or send to UDP socket: {:?}", e);
+                        connected_before = true;
+                        let (upstream_read, upstream_write) = tokio::io::split(stream);
+                        let reader = spawn_for_config(
+                            self.config_id,
+                            relay_udp_responses(upstream_read, socket.clone(), association.clone()),
+                        );
+                        upstream = Some((reader, upstream_write));
+                    }
+                    Err(e) if failures < UDP_RECONNECT_ATTEMPTS && !datagrams.is_closed() => {
+                        warn!("Failed to open UDP upstream for {}: {:?}", peer, e);
+                        tokio::time::sleep(udp_reconnect_delay(failures)).await;
+                        failures += 1;
+                        continue;
+                    }
+                    Err(e) => {
+                        error!(
+                            "Giving up on UDP upstream for {} after {} attempts: {:?}",
+                            peer, failures, e
+                        );
+                        break;
+                    }
+                }
+            }
+            let Some((reader, upstream_write)) = upstream.as_mut() else {
+                continue;
+            };
+
+            let datagram = match pending.take() {
+                Some(datagram) => Some(datagram),
+                None => {
+                    let idle_deadline =
+                        tokio::time::Instant::from_std(association.last_active()) + idle_timeout;
+                    tokio::select! {
+                        datagram = datagrams.recv() => match datagram {
+                            Some(datagram) => Some(datagram),
+                            None => break,
+                        },
+                        _ = reader => {
+                            warn!("UDP upstream for {} closed, re-establishing", peer);
+                            upstream = None;
+                            continue;
+                        }
+                        _ = tokio::time::sleep_until(idle_deadline) => {
+                            if association.last_active().elapsed() >= idle_timeout {
+                                debug!("UDP association for {} idle, closing", peer);
                                 break;
                             }
+                            None
                         }
                     }
                 }
-            }
+            };
 
-            if let Err(e) = tcp_write.shutdown().await {
-                error!("Error shutting down TCP writer: {:?}", e);
+            if let Some(datagram) = datagram {
+                match write_udp_frame(upstream_write, &datagram).await {
+                    Ok(()) => {
+                        association.record_in(datagram.len());
+                        failures = 0;
+                    }
+                    Err(e) => {
+                        debug!("UDP upstream for {} is gone: {:?}", peer, e);
+                        pending = Some(datagram);
+                        if let Some((reader, _)) = upstream.take() {
+                            reader.abort();
+                        }
+                    }
+                }
             }
-        });
+        }
 
-        Ok((local_port, handle))
+        if let Some((reader, mut upstream_write)) = upstream {
+            reader.abort();
+            if let Err(e) = upstream_write.shutdown().await {
+                debug!("Error shutting down UDP upstream for {}: {:?}", peer, e);
+            }
+        }
+        remove_udp_association(self.config_id, &association);
     }
 
     pub async fn read_tcp_length_and_packet(
         tcp_read: &mut (impl AsyncReadExt + Unpin),
     ) -> anyhow::Result<Option<Vec<u8>>> {
-        let mut len_bytes = [0u8; 4];
-
-        if tcp_read.read_exact(&mut len_bytes).await.is_err() {
-            return Ok(None);
-        }
-
-        let len = u32::from_be_bytes(len_bytes) as usize;
-
-        let mut packet = vec![0u8; len];
+        Ok(read_udp_frame(tcp_read).await?)
+    }
+}
 
-        if tcp_read.read_exact(&mut packet).await.is_err() {
-            return Ok(None);
+// frames are read in their own task, a read cut short by select! would lose
+// its place in the length prefixed stream
+async fn relay_udp_responses(
+    mut upstream_read: impl AsyncReadExt + Unpin, socket: Arc<TokioUdpSocket>,
+    association: Arc<UdpAssociation>,
+) {
+    let peer = association.peer();
+    loop {
+        let packet = match read_udp_frame(&mut upstream_read).await {
+            Ok(Some(packet)) => packet,
+            Ok(None) => break,
+            Err(e) => {
+                warn!("Dropping UDP upstream for {}: {:?}", peer, e);
+                break;
+            }
+        };
+        if let Err(e) = socket.send_to(&packet, peer).await {
+            error!("Failed to send UDP packet to {}: {:?}", peer, e);
+            continue;
         }
-
-        Ok(Some(packet))
+        association.record_out(packet.len());
     }
 }
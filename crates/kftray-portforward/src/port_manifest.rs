@@ -0,0 +1,188 @@
+use std::time::{
+    Duration,
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+use kftray_commons::models::config_state_model::ForwardPhase;
+use kftray_commons::utils::config_dir::get_port_manifest_path;
+use lazy_static::lazy_static;
+use log::warn;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::circuit_breaker::is_circuit_open;
+use crate::lifecycle::get_forward_phase;
+use crate::mappings::{
+    forward_mapping,
+    subscribe_forward_mappings,
+    write_json_file,
+};
+use crate::pod_watch::get_target_pod_status;
+use crate::reload::running_forwards;
+
+pub const PORT_MANIFEST_VERSION: u32 = 1;
+const PORT_MANIFEST_EVENT_CAPACITY: usize = 16;
+// health changes without forwards starting or stopping, so it's looked at on
+// this interval as well
+const PORT_MANIFEST_REFRESH: Duration = Duration::from_secs(5);
+
+lazy_static! {
+    static ref PORT_MANIFEST_EVENTS: broadcast::Sender<PortManifest> =
+        broadcast::channel(PORT_MANIFEST_EVENT_CAPACITY).0;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PortHealth {
+    Healthy,
+    Degraded,
+    #[default]
+    Starting,
+    Stopping,
+    Failed,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortManifestEntry {
+    pub config_id: i64,
+    pub label: String,
+    pub local_address: String,
+    pub local_port: u16,
+    pub protocol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_protocol: Option<String>,
+    pub health: PortHealth,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortManifest {
+    pub version: u32,
+    pub updated_at: u64,
+    pub ports: Vec<PortManifestEntry>,
+}
+
+// a running forward whose upstream keeps failing or whose pod isn't ready
+// still listens, it just won't answer well
+pub fn port_health(phase: ForwardPhase, circuit_open: bool, pod_ready: Option<bool>) -> PortHealth {
+    match phase {
+        ForwardPhase::Starting => PortHealth::Starting,
+        ForwardPhase::Stopping => PortHealth::Stopping,
+        ForwardPhase::Failed | ForwardPhase::Stopped => PortHealth::Failed,
+        ForwardPhase::Running if circuit_open || pod_ready == Some(false) => PortHealth::Degraded,
+        ForwardPhase::Running => PortHealth::Healthy,
+    }
+}
+
+pub(crate) fn forward_health(config_id: i64) -> PortHealth {
+    let pod_ready =
+        get_target_pod_status(config_id).map(|status| status.ready && status.error.is_none());
+    port_health(
+        get_forward_phase(config_id),
+        is_circuit_open(config_id),
+        pod_ready,
+    )
+}
+
+pub async fn get_port_manifest() -> Result<PortManifest, String> {
+    let forwards = running_forwards().await?;
+    let ports = forwards
+        .iter()
+        .filter_map(|config| {
+            let mapping = forward_mapping(config)?;
+            Some(PortManifestEntry {
+                config_id: mapping.config_id,
+                label: mapping.alias.unwrap_or(mapping.target),
+                local_address: mapping.local_address,
+                local_port: mapping.local_port,
+                protocol: mapping.protocol,
+                app_protocol: config.app_protocol.clone(),
+                health: forward_health(mapping.config_id),
+            })
+        })
+        .collect();
+
+    Ok(PortManifest {
+        version: PORT_MANIFEST_VERSION,
+        updated_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default(),
+        ports,
+    })
+}
+
+pub fn subscribe_port_manifest() -> broadcast::Receiver<PortManifest> {
+    PORT_MANIFEST_EVENTS.subscribe()
+}
+
+// keeps the manifest file current for as long as kftray runs. it's rewritten,
+// and subscribers told, only when a port or its health changed
+pub async fn watch_port_manifest() {
+    let mut mapping_events = subscribe_forward_mappings();
+    let mut written: Option<Vec<PortManifestEntry>> = None;
+
+    loop {
+        match get_port_manifest().await {
+            Ok(manifest) if written.as_ref() != Some(&manifest.ports) => {
+                let written_file =
+                    get_port_manifest_path().and_then(|path| write_json_file(&path, &manifest));
+                match written_file {
+                    Ok(()) => {
+                        written = Some(manifest.ports.clone());
+                        let _ = PORT_MANIFEST_EVENTS.send(manifest);
+                    }
+                    Err(e) => warn!("Failed to write the port manifest: {}", e),
+                }
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to build the port manifest: {}", e),
+        }
+
+        tokio::select! {
+            event = mapping_events.recv() => {
+                if let Err(RecvError::Closed) = event {
+                    break;
+                }
+            }
+            _ = tokio::time::sleep(PORT_MANIFEST_REFRESH) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_port_health() {
+        assert_eq!(
+            port_health(ForwardPhase::Running, false, None),
+            PortHealth::Healthy
+        );
+        assert_eq!(
+            port_health(ForwardPhase::Running, false, Some(true)),
+            PortHealth::Healthy
+        );
+        assert_eq!(
+            port_health(ForwardPhase::Running, true, Some(true)),
+            PortHealth::Degraded
+        );
+        assert_eq!(
+            port_health(ForwardPhase::Running, false, Some(false)),
+            PortHealth::Degraded
+        );
+        assert_eq!(
+            port_health(ForwardPhase::Starting, true, None),
+            PortHealth::Starting
+        );
+        assert_eq!(
+            port_health(ForwardPhase::Failed, false, None),
+            PortHealth::Failed
+        );
+    }
+}
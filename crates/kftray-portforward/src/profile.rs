@@ -0,0 +1,65 @@
+use kftray_commons::models::response::CustomResponse;
+use kftray_commons::utils::profile::{
+    configs_to_stop_for_switch,
+    get_active_profile,
+    set_active_profile,
+};
+use log::{
+    error,
+    info,
+};
+
+use crate::core::{
+    stop_port_forward,
+    stop_proxy_forward,
+};
+
+pub async fn switch_profile(name: &str) -> Result<Vec<CustomResponse>, String> {
+    let active_profile = get_active_profile().await?;
+    if active_profile == name {
+        return Ok(Vec::new());
+    }
+
+    // an unknown name has to fail before anything is stopped, otherwise the
+    // old profile's forwards go down and the switch never happens
+    let to_stop = configs_to_stop_for_switch(name).await?;
+
+    info!("Switching profile from '{}' to '{}'", active_profile, name);
+
+    let mut responses = Vec::new();
+    let mut errors = Vec::new();
+
+    for config in &to_stop {
+        let config_id = config.id.unwrap_or_default();
+        let owns_pod =
+            config.protocol == "udp" || matches!(config.workload_type.as_deref(), Some("proxy"));
+
+        let result = if owns_pod {
+            stop_proxy_forward(
+                config_id,
+                &config.namespace,
+                config.service.clone().unwrap_or_default(),
+            )
+            .await
+        } else {
+            stop_port_forward(config_id.to_string()).await
+        };
+
+        match result {
+            Ok(response) => responses.push(response),
+            Err(e) => {
+                let error_message = format!("Failed to stop config {}: {}", config_id, e);
+                error!("{}", error_message);
+                errors.push(error_message);
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+
+    set_active_profile(name).await?;
+
+    Ok(responses)
+}
@@ -0,0 +1,140 @@
+use std::sync::atomic::{
+    AtomicBool,
+    AtomicU64,
+    AtomicUsize,
+    Ordering,
+};
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+const PROGRESS_EVENT_CAPACITY: usize = 256;
+
+lazy_static! {
+    static ref NEXT_OPERATION_ID: AtomicU64 = AtomicU64::new(1);
+    static ref PROGRESS_EVENTS: broadcast::Sender<ProgressEvent> =
+        broadcast::channel(PROGRESS_EVENT_CAPACITY).0;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressKind {
+    StartAll,
+    StopAll,
+    Discovery,
+    ProxyDeploy,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ProgressEvent {
+    pub operation_id: u64,
+    pub kind: ProgressKind,
+    pub step: usize,
+    pub total: usize,
+    pub label: String,
+    pub done: bool,
+}
+
+pub fn subscribe_progress() -> broadcast::Receiver<ProgressEvent> {
+    PROGRESS_EVENTS.subscribe()
+}
+
+// one long running operation, every step it takes goes out as a progress
+// event. an operation dropped without finishing still reports itself done
+pub struct Progress {
+    operation_id: u64,
+    kind: ProgressKind,
+    total: AtomicUsize,
+    step: AtomicUsize,
+    done: AtomicBool,
+}
+
+impl Progress {
+    pub fn begin(kind: ProgressKind, total: usize, label: impl Into<String>) -> Self {
+        let progress = Progress {
+            operation_id: NEXT_OPERATION_ID.fetch_add(1, Ordering::Relaxed),
+            kind,
+            total: AtomicUsize::new(total),
+            step: AtomicUsize::new(0),
+            done: AtomicBool::new(false),
+        };
+        progress.emit(0, label.into(), false);
+        progress
+    }
+
+    pub fn operation_id(&self) -> u64 {
+        self.operation_id
+    }
+
+    // for operations that only learn how much work there is once under way
+    pub fn set_total(&self, total: usize) {
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    pub fn advance(&self, label: impl Into<String>) {
+        let step = self.step.fetch_add(1, Ordering::Relaxed) + 1;
+        self.emit(step, label.into(), false);
+    }
+
+    pub fn finish(&self, label: impl Into<String>) {
+        if !self.done.swap(true, Ordering::Relaxed) {
+            let step = self.step.load(Ordering::Relaxed);
+            self.emit(step, label.into(), true);
+        }
+    }
+
+    fn emit(&self, step: usize, label: String, done: bool) {
+        // nobody listening is fine, progress is only informational
+        let _ = PROGRESS_EVENTS.send(ProgressEvent {
+            operation_id: self.operation_id,
+            kind: self.kind,
+            step,
+            total: self.total.load(Ordering::Relaxed).max(step),
+            label,
+            done,
+        });
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.finish("Ended");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_events() {
+        let mut events = subscribe_progress();
+        let progress = Progress::begin(ProgressKind::Discovery, 2, "Scanning");
+        let operation_id = progress.operation_id();
+        progress.advance("default");
+        progress.advance("kube-system");
+        progress.advance("monitoring");
+        drop(progress);
+
+        let events: Vec<ProgressEvent> = std::iter::from_fn(|| events.try_recv().ok())
+            .filter(|event| event.operation_id == operation_id)
+            .collect();
+        let steps: Vec<(usize, usize, bool)> = events
+            .iter()
+            .map(|event| (event.step, event.total, event.done))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                (0, 2, false),
+                (1, 2, false),
+                (2, 2, false),
+                (3, 3, false),
+                (3, 3, true)
+            ]
+        );
+        assert_eq!(events[1].label, "default");
+        assert_eq!(events[4].label, "Ended");
+    }
+}
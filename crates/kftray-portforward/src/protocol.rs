@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use kftray_commons::models::protocol_model::{
+    ProtocolStats,
+    StreamProtocol,
+};
+use lazy_static::lazy_static;
+
+pub const MAX_SNIFF_BYTES: usize = 4096;
+pub const MAX_SNIFF_READS: usize = 4;
+
+const HTTP2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+const GRPC_CONTENT_TYPE: &[u8] = b"application/grpc";
+// "application/grpc" as an HPACK Huffman-coded header value
+const GRPC_CONTENT_TYPE_HUFFMAN: &[u8] = &[
+    0x1D, 0x75, 0xD0, 0x62, 0x0D, 0x26, 0x3D, 0x4C, 0x4D, 0x65, 0x64,
+];
+
+const POSTGRES_PROTOCOL_V3: u32 = 196608;
+const POSTGRES_SSL_REQUEST: u32 = 80877103;
+const POSTGRES_GSSENC_REQUEST: u32 = 80877104;
+
+const REDIS_INLINE_COMMANDS: &[&str] = &["PING", "HELLO", "AUTH", "INFO", "SELECT", "QUIT"];
+
+lazy_static! {
+    static ref DETECTED_PROTOCOLS: Mutex<HashMap<i64, ProtocolStats>> = Mutex::new(HashMap::new());
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+fn is_prefix_of(data: &[u8], expected: &[u8]) -> bool {
+    let len = data.len().min(expected.len());
+    data[..len] == expected[..len]
+}
+
+fn sniff_http2(data: &[u8], complete: bool) -> Option<StreamProtocol> {
+    if contains(data, GRPC_CONTENT_TYPE) || contains(data, GRPC_CONTENT_TYPE_HUFFMAN) {
+        return Some(StreamProtocol::Grpc);
+    }
+
+    (complete || data.len() >= MAX_SNIFF_BYTES).then_some(StreamProtocol::Http2)
+}
+
+fn sniff_tls(data: &[u8]) -> Option<bool> {
+    match data {
+        [0x16] | [0x16, 0x03] => None,
+        [0x16, 0x03, minor, ..] => Some(*minor <= 0x04),
+        _ => Some(false),
+    }
+}
+
+fn sniff_postgres(data: &[u8]) -> Option<bool> {
+    if data.len() < 8 {
+        return None;
+    }
+
+    let length = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+    let code = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+
+    Some(
+        (8..=10_000).contains(&length)
+            && matches!(
+                code,
+                POSTGRES_PROTOCOL_V3 | POSTGRES_SSL_REQUEST | POSTGRES_GSSENC_REQUEST
+            ),
+    )
+}
+
+fn first_line(data: &[u8]) -> Option<&str> {
+    let end = data.windows(2).position(|window| window == b"\r\n")?;
+    std::str::from_utf8(&data[..end]).ok()
+}
+
+fn sniff_http1(line: &str) -> bool {
+    let mut parts = line.split(' ');
+    let method = parts.next().unwrap_or_default();
+
+    !method.is_empty()
+        && method.bytes().all(|b| b.is_ascii_uppercase())
+        && parts.next().is_some()
+        && matches!(parts.next(), Some("HTTP/1.0") | Some("HTTP/1.1"))
+}
+
+fn sniff_redis(line: &str) -> bool {
+    if let Some(count) = line.strip_prefix('*') {
+        return !count.is_empty() && count.bytes().all(|b| b.is_ascii_digit());
+    }
+
+    let command = line.split(' ').next().unwrap_or_default();
+    REDIS_INLINE_COMMANDS
+        .iter()
+        .any(|known| known.eq_ignore_ascii_case(command))
+}
+
+/// Returns `None` while the bytes seen so far are inconclusive. Passing
+/// `complete` forces a best guess from whatever has been buffered.
+pub fn sniff_protocol(data: &[u8], complete: bool) -> Option<StreamProtocol> {
+    if data.is_empty() {
+        return complete.then_some(StreamProtocol::Unknown);
+    }
+
+    if is_prefix_of(data, HTTP2_PREFACE) {
+        if data.len() < HTTP2_PREFACE.len() {
+            return complete.then_some(StreamProtocol::Unknown);
+        }
+        return sniff_http2(data, complete);
+    }
+
+    let mut inconclusive = false;
+
+    match sniff_tls(data) {
+        Some(true) => return Some(StreamProtocol::Tls),
+        Some(false) => {}
+        None => inconclusive = true,
+    }
+
+    match sniff_postgres(data) {
+        Some(true) => return Some(StreamProtocol::Postgres),
+        Some(false) => {}
+        None => inconclusive = true,
+    }
+
+    match first_line(data) {
+        Some(line) if sniff_http1(line) => return Some(StreamProtocol::Http1),
+        Some(line) if sniff_redis(line) => return Some(StreamProtocol::Redis),
+        Some(_) => {}
+        None => inconclusive |= data.len() < MAX_SNIFF_BYTES,
+    }
+
+    (complete || !inconclusive).then_some(StreamProtocol::Unknown)
+}
+
+pub fn sniff_server_greeting(data: &[u8]) -> Option<StreamProtocol> {
+    match data {
+        [_, _, _, 0, 0x0A, version, ..] if version.is_ascii_digit() => Some(StreamProtocol::Mysql),
+        _ => None,
+    }
+}
+
+pub fn record_detected_protocol(config_id: i64, protocol: StreamProtocol) {
+    let mut detected = DETECTED_PROTOCOLS.lock().unwrap();
+    let stats = detected.entry(config_id).or_insert_with(|| ProtocolStats {
+        config_id,
+        ..Default::default()
+    });
+
+    *stats.connections.entry(protocol).or_default() += 1;
+    stats.last_detected = Some(protocol);
+}
+
+pub fn get_protocol_stats(config_id: i64) -> ProtocolStats {
+    DETECTED_PROTOCOLS
+        .lock()
+        .unwrap()
+        .get(&config_id)
+        .cloned()
+        .unwrap_or_else(|| ProtocolStats {
+            config_id,
+            ..Default::default()
+        })
+}
+
+pub fn clear_protocol_stats(config_id: i64) {
+    DETECTED_PROTOCOLS.lock().unwrap().remove(&config_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_protocol() {
+        let cases: &[(&[u8], StreamProtocol)] = &[
+            (
+                b"GET /health HTTP/1.1\r\nHost: x\r\n\r\n",
+                StreamProtocol::Http1,
+            ),
+            (
+                b"\x16\x03\x01\x02\x00\x01\x00\x01\xfc\x03\x03",
+                StreamProtocol::Tls,
+            ),
+            (
+                b"\x00\x00\x00\x08\x04\xd2\x16\x2f",
+                StreamProtocol::Postgres,
+            ),
+            (b"*1\r\n$4\r\nPING\r\n", StreamProtocol::Redis),
+            (b"ping\r\n", StreamProtocol::Redis),
+            (b"SSH-2.0-OpenSSH_9.6\r\n", StreamProtocol::Unknown),
+        ];
+
+        for (data, expected) in cases {
+            assert_eq!(sniff_protocol(data, false), Some(*expected));
+        }
+    }
+
+    #[test]
+    fn test_sniff_http2_and_grpc() {
+        let mut data = HTTP2_PREFACE.to_vec();
+        data.extend_from_slice(&[0, 0, 0, 4, 0, 0, 0, 0, 0]);
+
+        assert_eq!(sniff_protocol(&data, false), None);
+        assert_eq!(sniff_protocol(&data, true), Some(StreamProtocol::Http2));
+
+        data.extend_from_slice(GRPC_CONTENT_TYPE_HUFFMAN);
+        assert_eq!(sniff_protocol(&data, false), Some(StreamProtocol::Grpc));
+    }
+
+    #[test]
+    fn test_sniff_waits_for_more_data() {
+        assert_eq!(sniff_protocol(b"GET /", false), None);
+        assert_eq!(sniff_protocol(b"\x16", false), None);
+        assert_eq!(sniff_protocol(b"PRI * ", false), None);
+        assert_eq!(
+            sniff_protocol(b"GET /", true),
+            Some(StreamProtocol::Unknown)
+        );
+    }
+
+    #[test]
+    fn test_sniff_server_greeting() {
+        assert_eq!(
+            sniff_server_greeting(b"\x4a\0\0\0\x0a8.0.36\0"),
+            Some(StreamProtocol::Mysql)
+        );
+        assert_eq!(sniff_server_greeting(b"SSH-2.0-OpenSSH\r\n"), None);
+    }
+}
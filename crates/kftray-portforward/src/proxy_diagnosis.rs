@@ -0,0 +1,959 @@
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use k8s_openapi::api::core::v1::{
+    Endpoints,
+    Namespace,
+    Pod,
+    Service,
+};
+use k8s_openapi::api::networking::v1::{
+    NetworkPolicy,
+    NetworkPolicyPeer,
+    NetworkPolicyPort,
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use kftray_commons::config::get_config;
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::proxy_diagnosis_model::{
+    CheckStatus,
+    DiagnosticCheck,
+    ProxyDiagnosis,
+    ProxyFailureCause,
+};
+use kube::api::{
+    Api,
+    AttachParams,
+    ListParams,
+};
+use kube::Client;
+use log::debug;
+use tokio::io::AsyncReadExt;
+
+use crate::mesh::remote_namespace;
+use crate::pod_finder::{
+    config_client,
+    is_proxy_config,
+    resolve_config_pod,
+};
+
+const EXEC_TIMEOUT: Duration = Duration::from_secs(15);
+const CONNECT_TIMEOUT_SECS: u32 = 3;
+const NAMESPACE_NAME_LABEL: &str = "kubernetes.io/metadata.name";
+
+const DNS_CHECK: &str = "dns";
+const DNS_EGRESS_CHECK: &str = "dns_egress";
+const SERVICE_CHECK: &str = "service";
+const ENDPOINTS_CHECK: &str = "endpoints";
+const EGRESS_CHECK: &str = "egress_policy";
+const INGRESS_CHECK: &str = "ingress_policy";
+const CONNECT_CHECK: &str = "connect";
+
+// one end of a connection, the way NetworkPolicies select it
+#[derive(Clone, Debug, Default)]
+pub struct PolicyPeer {
+    pub namespace: String,
+    pub namespace_labels: BTreeMap<String, String>,
+    pub pod_labels: BTreeMap<String, String>,
+    pub ip: Option<IpAddr>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PolicyPort {
+    pub number: u16,
+    // named target ports are matched by name
+    pub name: Option<String>,
+    pub protocol: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PolicyVerdict {
+    // no policy selects the pod
+    Unrestricted,
+    Allowed(String),
+    Blocked(Vec<String>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PolicyDirection {
+    Ingress,
+    Egress,
+}
+
+pub fn selector_matches(selector: &LabelSelector, labels: &BTreeMap<String, String>) -> bool {
+    let labels_match = selector
+        .match_labels
+        .iter()
+        .flatten()
+        .all(|(key, value)| labels.get(key) == Some(value));
+    let expressions_match = selector
+        .match_expressions
+        .iter()
+        .flatten()
+        .all(|expression| {
+            let value = labels.get(&expression.key);
+            let values = expression.values.as_deref().unwrap_or_default();
+            match expression.operator.as_str() {
+                "In" => value.is_some_and(|value| values.contains(value)),
+                "NotIn" => !value.is_some_and(|value| values.contains(value)),
+                "Exists" => value.is_some(),
+                "DoesNotExist" => value.is_none(),
+                _ => false,
+            }
+        });
+    labels_match && expressions_match
+}
+
+pub fn cidr_contains(cidr: &str, ip: IpAddr) -> bool {
+    let (network, prefix) = cidr.split_once('/').unwrap_or((cidr, ""));
+    let Ok(network) = network.parse::<IpAddr>() else {
+        return false;
+    };
+    let (network, ip, bits) = match (network, ip) {
+        (IpAddr::V4(network), IpAddr::V4(ip)) => {
+            (u32::from(network) as u128, u32::from(ip) as u128, 32)
+        }
+        (IpAddr::V6(network), IpAddr::V6(ip)) => (u128::from(network), u128::from(ip), 128),
+        _ => return false,
+    };
+    let prefix = match prefix {
+        "" => bits,
+        prefix => match prefix.parse::<u32>() {
+            Ok(prefix) if prefix <= bits => prefix,
+            _ => return false,
+        },
+    };
+    let shift = bits - prefix;
+    shift == 128 || (network >> shift) == (ip >> shift)
+}
+
+fn peer_matches(policy_namespace: &str, peer: &NetworkPolicyPeer, other: &PolicyPeer) -> bool {
+    if let Some(block) = &peer.ip_block {
+        return other.ip.is_some_and(|ip| {
+            cidr_contains(&block.cidr, ip)
+                && !block
+                    .except
+                    .iter()
+                    .flatten()
+                    .any(|except| cidr_contains(except, ip))
+        });
+    }
+    let namespace_matches = match &peer.namespace_selector {
+        Some(selector) => selector_matches(selector, &other.namespace_labels),
+        None => other.namespace == policy_namespace,
+    };
+    let pod_matches = match &peer.pod_selector {
+        Some(selector) => selector_matches(selector, &other.pod_labels),
+        None => true,
+    };
+    namespace_matches && pod_matches
+}
+
+fn port_matches(ports: Option<&Vec<NetworkPolicyPort>>, port: &PolicyPort) -> bool {
+    let Some(ports) = ports.filter(|ports| !ports.is_empty()) else {
+        return true;
+    };
+    ports.iter().any(|policy_port| {
+        let protocol = policy_port.protocol.as_deref().unwrap_or("TCP");
+        if !protocol.eq_ignore_ascii_case(&port.protocol) {
+            return false;
+        }
+        match &policy_port.port {
+            None => true,
+            Some(IntOrString::Int(number)) => {
+                let number = *number;
+                let end = policy_port.end_port.unwrap_or(number);
+                (number..=end).contains(&(port.number as i32))
+            }
+            Some(IntOrString::String(name)) => port.name.as_deref() == Some(name.as_str()),
+        }
+    })
+}
+
+fn policy_applies(policy: &NetworkPolicy, direction: PolicyDirection) -> bool {
+    let Some(spec) = &policy.spec else {
+        return false;
+    };
+    match &spec.policy_types {
+        Some(types) => types.iter().any(|policy_type| match direction {
+            PolicyDirection::Ingress => policy_type == "Ingress",
+            PolicyDirection::Egress => policy_type == "Egress",
+        }),
+        // without types a policy always restricts ingress, and egress once it
+        // has egress rules
+        None => direction == PolicyDirection::Ingress || spec.egress.is_some(),
+    }
+}
+
+fn policy_name(policy: &NetworkPolicy) -> String {
+    format!(
+        "{}/{}",
+        policy.metadata.namespace.clone().unwrap_or_default(),
+        policy.metadata.name.clone().unwrap_or_default()
+    )
+}
+
+// `selected` is the pod the policies apply to, the destination for ingress
+// and the source for egress. traffic passes when any policy selecting the pod
+// allows it
+pub fn policy_verdict(
+    policies: &[NetworkPolicy], direction: PolicyDirection, selected: &PolicyPeer,
+    other: &PolicyPeer, port: &PolicyPort,
+) -> PolicyVerdict {
+    let selecting: Vec<&NetworkPolicy> = policies
+        .iter()
+        .filter(|policy| policy.metadata.namespace.as_deref() == Some(selected.namespace.as_str()))
+        .filter(|policy| policy_applies(policy, direction))
+        .filter(|policy| {
+            policy
+                .spec
+                .as_ref()
+                .is_some_and(|spec| selector_matches(&spec.pod_selector, &selected.pod_labels))
+        })
+        .collect();
+    if selecting.is_empty() {
+        return PolicyVerdict::Unrestricted;
+    }
+
+    let allows = |policy: &NetworkPolicy| {
+        let spec = policy.spec.as_ref().unwrap();
+        let namespace = selected.namespace.as_str();
+        let peers_match = |peers: Option<&Vec<NetworkPolicyPeer>>| match peers {
+            None => true,
+            Some(peers) if peers.is_empty() => true,
+            Some(peers) => peers
+                .iter()
+                .any(|peer| peer_matches(namespace, peer, other)),
+        };
+        match direction {
+            PolicyDirection::Ingress => spec.ingress.iter().flatten().any(|rule| {
+                port_matches(rule.ports.as_ref(), port) && peers_match(rule.from.as_ref())
+            }),
+            PolicyDirection::Egress => spec.egress.iter().flatten().any(|rule| {
+                port_matches(rule.ports.as_ref(), port) && peers_match(rule.to.as_ref())
+            }),
+        }
+    };
+
+    match selecting.iter().find(|policy| allows(policy)) {
+        Some(policy) => PolicyVerdict::Allowed(policy_name(policy)),
+        None => {
+            PolicyVerdict::Blocked(selecting.iter().map(|policy| policy_name(policy)).collect())
+        }
+    }
+}
+
+fn check(name: &str, status: CheckStatus, detail: impl Into<String>) -> DiagnosticCheck {
+    DiagnosticCheck {
+        name: name.to_string(),
+        status,
+        detail: detail.into(),
+    }
+}
+
+fn verdict_check(name: &str, verdict: &PolicyVerdict) -> DiagnosticCheck {
+    match verdict {
+        PolicyVerdict::Unrestricted => check(name, CheckStatus::Passed, "No NetworkPolicy applies"),
+        PolicyVerdict::Allowed(policy) => {
+            check(name, CheckStatus::Passed, format!("Allowed by {}", policy))
+        }
+        PolicyVerdict::Blocked(policies) => check(
+            name,
+            CheckStatus::Failed,
+            format!("Dropped by {}", policies.join(", ")),
+        ),
+    }
+}
+
+// the first failed check in order of how much it explains. a connection that
+// went through overrules whatever the policies look like
+pub fn diagnosis_cause(checks: &[DiagnosticCheck]) -> Option<ProxyFailureCause> {
+    let failed = |name: &str| {
+        checks
+            .iter()
+            .any(|check| check.name == name && check.status == CheckStatus::Failed)
+    };
+    let connected = checks
+        .iter()
+        .any(|check| check.name == CONNECT_CHECK && check.status == CheckStatus::Passed);
+    if connected {
+        return None;
+    }
+
+    [
+        (DNS_EGRESS_CHECK, ProxyFailureCause::NetworkPolicy),
+        (DNS_CHECK, ProxyFailureCause::DnsResolution),
+        (SERVICE_CHECK, ProxyFailureCause::MissingService),
+        (ENDPOINTS_CHECK, ProxyFailureCause::NoEndpoints),
+        (EGRESS_CHECK, ProxyFailureCause::NetworkPolicy),
+        (INGRESS_CHECK, ProxyFailureCause::NetworkPolicy),
+        (CONNECT_CHECK, ProxyFailureCause::ConnectionFailed),
+    ]
+    .into_iter()
+    .find(|(name, _)| failed(name))
+    .map(|(_, cause)| cause)
+}
+
+pub fn diagnosis_summary(
+    cause: Option<ProxyFailureCause>, remote_address: &str, remote_port: u16,
+    blocking_policies: &[String],
+) -> String {
+    match cause {
+        Some(ProxyFailureCause::DnsResolution) => format!(
+            "{} doesn't resolve from the proxy pod, check the name and namespace of the remote \
+             address",
+            remote_address
+        ),
+        Some(ProxyFailureCause::MissingService) => {
+            format!("There is no service behind {}", remote_address)
+        }
+        Some(ProxyFailureCause::NoEndpoints) => format!(
+            "{} has no ready endpoints, its pods aren't running or not ready",
+            remote_address
+        ),
+        Some(ProxyFailureCause::NetworkPolicy) => format!(
+            "NetworkPolicy {} drops the traffic of the proxy pod to {}",
+            blocking_policies.join(", "),
+            remote_address
+        ),
+        Some(ProxyFailureCause::ConnectionFailed) => format!(
+            "Nothing accepts connections on {}:{}, check the remote port and that the app listens \
+             on it",
+            remote_address, remote_port
+        ),
+        None => format!(
+            "The proxy pod reaches {}:{}, the failure is past the connection",
+            remote_address, remote_port
+        ),
+    }
+}
+
+// the service a remote address names: `db`, `db.payments` or
+// `db.payments.svc.cluster.local`
+fn remote_service(config: &Config) -> Option<(String, String)> {
+    let remote_address = config.remote_address.as_deref()?;
+    if remote_address.parse::<IpAddr>().is_ok() {
+        return None;
+    }
+    let labels: Vec<&str> = remote_address.split('.').collect();
+    match labels.as_slice() {
+        [name] | [name, _] | [name, _, "svc", ..] => {
+            Some((name.to_string(), remote_namespace(config)))
+        }
+        _ => None,
+    }
+}
+
+async fn exec_in_pod(
+    pods: &Api<Pod>, pod: &str, container: &str, command: Vec<String>,
+) -> Result<(bool, String), String> {
+    let params = AttachParams::default().container(container).stderr(true);
+    let run = async {
+        let mut process = pods
+            .exec(pod, command, &params)
+            .await
+            .map_err(|e| e.to_string())?;
+        let mut output = String::new();
+        if let Some(mut stdout) = process.stdout() {
+            let _ = stdout.read_to_string(&mut output).await;
+        }
+        if let Some(mut stderr) = process.stderr() {
+            let _ = stderr.read_to_string(&mut output).await;
+        }
+        let status = match process.take_status() {
+            Some(status) => status.await,
+            None => None,
+        };
+        let success = status.and_then(|status| status.status).as_deref() == Some("Success");
+        Ok((success, output.trim().to_string()))
+    };
+    tokio::time::timeout(EXEC_TIMEOUT, run)
+        .await
+        .map_err(|_| format!("Running a command in {} timed out", pod))?
+}
+
+fn last_line(output: &str) -> &str {
+    output.lines().last().unwrap_or_default()
+}
+
+async fn namespace_labels(client: &Client, namespace: &str) -> BTreeMap<String, String> {
+    let namespaces: Api<Namespace> = Api::all(client.clone());
+    let mut labels = namespaces
+        .get_opt(namespace)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|namespace| namespace.metadata.labels)
+        .unwrap_or_default();
+    labels
+        .entry(NAMESPACE_NAME_LABEL.to_string())
+        .or_insert_with(|| namespace.to_string());
+    labels
+}
+
+async fn list_policies(client: &Client, namespaces: &[&str]) -> Result<Vec<NetworkPolicy>, String> {
+    let mut namespaces = namespaces.to_vec();
+    namespaces.dedup();
+    let mut policies = Vec::new();
+    for namespace in namespaces {
+        let api: Api<NetworkPolicy> = Api::namespaced(client.clone(), namespace);
+        let list = api
+            .list(&ListParams::default())
+            .await
+            .map_err(|e| e.to_string())?;
+        policies.extend(list.items);
+    }
+    Ok(policies)
+}
+
+// the port the backend pods see, a named target port is looked up on them
+fn backend_port(service: &Service, backend: Option<&Pod>, config: &Config) -> PolicyPort {
+    let remote_port = config.remote_port.unwrap_or_default();
+    let protocol = config.protocol.to_uppercase();
+    let target_port = service
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.ports.as_ref())
+        .and_then(|ports| ports.iter().find(|port| port.port == remote_port as i32))
+        .and_then(|port| port.target_port.clone());
+
+    match target_port {
+        Some(IntOrString::Int(number)) => PolicyPort {
+            number: number as u16,
+            name: None,
+            protocol,
+        },
+        Some(IntOrString::String(name)) => {
+            let number = backend
+                .and_then(|pod| pod.spec.as_ref())
+                .into_iter()
+                .flat_map(|spec| spec.containers.iter())
+                .flat_map(|container| container.ports.iter().flatten())
+                .find(|port| port.name.as_deref() == Some(name.as_str()))
+                .map(|port| port.container_port as u16)
+                .unwrap_or(remote_port);
+            PolicyPort {
+                number,
+                name: Some(name),
+                protocol,
+            }
+        }
+        None => PolicyPort {
+            number: remote_port,
+            name: None,
+            protocol,
+        },
+    }
+}
+
+fn proxy_container(pod: &Pod) -> String {
+    let name = pod.metadata.name.clone().unwrap_or_default();
+    let containers = pod
+        .spec
+        .as_ref()
+        .map(|spec| spec.containers.as_slice())
+        .unwrap_or_default();
+    containers
+        .iter()
+        .find(|container| container.name == name)
+        .or_else(|| containers.first())
+        .map(|container| container.name.clone())
+        .unwrap_or(name)
+}
+
+// runs the checks from the proxy pod the config forwards through
+pub async fn diagnose_proxy_pod(
+    client: &Client, config: &Config, proxy_pod: &Pod,
+) -> Result<ProxyDiagnosis, String> {
+    let remote_address = config
+        .remote_address
+        .clone()
+        .filter(|address| !address.is_empty())
+        .ok_or_else(|| "The config has no remote address".to_string())?;
+    let remote_port = config
+        .remote_port
+        .ok_or_else(|| "The config has no remote port".to_string())?;
+    let pod_name = proxy_pod.metadata.name.clone().unwrap_or_default();
+    let container = proxy_container(proxy_pod);
+    let pods: Api<Pod> = Api::namespaced(client.clone(), &config.namespace);
+    let target_namespace = remote_namespace(config);
+    let mut checks = Vec::new();
+    let mut blocking_policies = Vec::new();
+
+    let source = PolicyPeer {
+        namespace: config.namespace.clone(),
+        namespace_labels: namespace_labels(client, &config.namespace).await,
+        pod_labels: proxy_pod.metadata.labels.clone().unwrap_or_default(),
+        ip: proxy_pod
+            .status
+            .as_ref()
+            .and_then(|status| status.pod_ip.as_deref())
+            .and_then(|ip| ip.parse().ok()),
+    };
+    let mut destination = PolicyPeer {
+        namespace: target_namespace.clone(),
+        namespace_labels: namespace_labels(client, &target_namespace).await,
+        ip: remote_address.parse().ok(),
+        ..Default::default()
+    };
+    let mut port = PolicyPort {
+        number: remote_port,
+        name: None,
+        protocol: config.protocol.to_uppercase(),
+    };
+
+    let policies = list_policies(client, &[&config.namespace, &target_namespace])
+        .await
+        .map_err(|e| debug!("Failed to list NetworkPolicies: {}", e))
+        .ok();
+
+    if destination.ip.is_some() {
+        checks.push(check(
+            DNS_CHECK,
+            CheckStatus::Skipped,
+            "The remote address is an ip",
+        ));
+    } else {
+        let lookup = vec!["nslookup".to_string(), remote_address.clone()];
+        let check_result = match exec_in_pod(&pods, &pod_name, &container, lookup).await {
+            Ok((true, _)) => check(
+                DNS_CHECK,
+                CheckStatus::Passed,
+                "Resolves from the proxy pod",
+            ),
+            Ok((false, output)) => check(DNS_CHECK, CheckStatus::Failed, last_line(&output)),
+            Err(e) => check(DNS_CHECK, CheckStatus::Skipped, e),
+        };
+        let dns_failed = check_result.status == CheckStatus::Failed;
+        checks.push(check_result);
+
+        if let Some(policies) = policies.as_ref().filter(|_| dns_failed) {
+            let cluster_dns = PolicyPeer {
+                namespace: "kube-system".to_string(),
+                namespace_labels: namespace_labels(client, "kube-system").await,
+                pod_labels: BTreeMap::from([("k8s-app".to_string(), "kube-dns".to_string())]),
+                ip: None,
+            };
+            let dns_port = PolicyPort {
+                number: 53,
+                name: None,
+                protocol: "UDP".to_string(),
+            };
+            let verdict = policy_verdict(
+                policies,
+                PolicyDirection::Egress,
+                &source,
+                &cluster_dns,
+                &dns_port,
+            );
+            checks.push(verdict_check(DNS_EGRESS_CHECK, &verdict));
+            if let PolicyVerdict::Blocked(policies) = verdict {
+                blocking_policies = policies;
+            }
+        }
+    }
+
+    if let Some((service_name, namespace)) = remote_service(config) {
+        let services: Api<Service> = Api::namespaced(client.clone(), &namespace);
+        match services.get_opt(&service_name).await {
+            Ok(None) => checks.push(check(
+                SERVICE_CHECK,
+                CheckStatus::Failed,
+                format!("No service {} in {}", service_name, namespace),
+            )),
+            Ok(Some(service)) => {
+                checks.push(check(
+                    SERVICE_CHECK,
+                    CheckStatus::Passed,
+                    "The service exists",
+                ));
+                let selector = service
+                    .spec
+                    .as_ref()
+                    .and_then(|spec| spec.selector.clone())
+                    .filter(|selector| !selector.is_empty());
+                match selector {
+                    None => checks.push(check(
+                        ENDPOINTS_CHECK,
+                        CheckStatus::Skipped,
+                        "The service has no selector, its endpoints are managed elsewhere",
+                    )),
+                    Some(selector) => {
+                        let endpoints: Api<Endpoints> = Api::namespaced(client.clone(), &namespace);
+                        let ready = endpoints
+                            .get_opt(&service_name)
+                            .await
+                            .ok()
+                            .flatten()
+                            .and_then(|endpoints| endpoints.subsets)
+                            .into_iter()
+                            .flatten()
+                            .map(|subset| subset.addresses.map_or(0, |addresses| addresses.len()))
+                            .sum::<usize>();
+                        checks.push(match ready {
+                            0 => check(ENDPOINTS_CHECK, CheckStatus::Failed, "No ready endpoints"),
+                            ready => check(
+                                ENDPOINTS_CHECK,
+                                CheckStatus::Passed,
+                                format!("{} ready endpoint(s)", ready),
+                            ),
+                        });
+
+                        let selector = selector
+                            .iter()
+                            .map(|(key, value)| format!("{}={}", key, value))
+                            .collect::<Vec<_>>()
+                            .join(",");
+                        let backends: Api<Pod> = Api::namespaced(client.clone(), &namespace);
+                        let backend = backends
+                            .list(&ListParams::default().labels(&selector))
+                            .await
+                            .ok()
+                            .and_then(|pods| pods.items.into_iter().next());
+                        port = backend_port(&service, backend.as_ref(), config);
+                        if let Some(backend) = backend {
+                            destination.pod_labels = backend.metadata.labels.unwrap_or_default();
+                            destination.ip = backend
+                                .status
+                                .and_then(|status| status.pod_ip)
+                                .and_then(|ip| ip.parse().ok());
+                        }
+                    }
+                }
+            }
+            Err(e) => checks.push(check(SERVICE_CHECK, CheckStatus::Skipped, e.to_string())),
+        }
+    }
+
+    match &policies {
+        None => checks.push(check(
+            EGRESS_CHECK,
+            CheckStatus::Skipped,
+            "NetworkPolicies can't be listed",
+        )),
+        Some(policies) => {
+            let egress = policy_verdict(
+                policies,
+                PolicyDirection::Egress,
+                &source,
+                &destination,
+                &port,
+            );
+            checks.push(verdict_check(EGRESS_CHECK, &egress));
+            // the pods behind an external name or an ip aren't known
+            let ingress = match destination.pod_labels.is_empty() {
+                true => None,
+                false => Some(policy_verdict(
+                    policies,
+                    PolicyDirection::Ingress,
+                    &destination,
+                    &source,
+                    &port,
+                )),
+            };
+            if let Some(ingress) = &ingress {
+                checks.push(verdict_check(INGRESS_CHECK, ingress));
+            }
+            // a dns lookup dropped by policy only names the policies behind
+            // that
+            if blocking_policies.is_empty() {
+                for verdict in [Some(egress), ingress].into_iter().flatten() {
+                    if let PolicyVerdict::Blocked(policies) = verdict {
+                        blocking_policies.extend(policies);
+                    }
+                }
+            }
+        }
+    }
+
+    // busybox nc has no zero-io mode, an empty stdin closes right after
+    // connecting
+    let connect = vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        format!("nc -w {} \"$0\" \"$1\" </dev/null", CONNECT_TIMEOUT_SECS),
+        remote_address.clone(),
+        remote_port.to_string(),
+    ];
+    if config.protocol.eq_ignore_ascii_case("tcp") {
+        checks.push(
+            match exec_in_pod(&pods, &pod_name, &container, connect).await {
+                Ok((true, _)) => check(CONNECT_CHECK, CheckStatus::Passed, "Connected"),
+                Ok((false, output)) => check(
+                    CONNECT_CHECK,
+                    CheckStatus::Failed,
+                    match last_line(&output) {
+                        "" => "The connection failed",
+                        line => line,
+                    },
+                ),
+                Err(e) => check(CONNECT_CHECK, CheckStatus::Skipped, e),
+            },
+        );
+    }
+
+    let cause = diagnosis_cause(&checks);
+    let summary = diagnosis_summary(cause, &remote_address, remote_port, &blocking_policies);
+
+    Ok(ProxyDiagnosis {
+        config_id: config.id.unwrap_or_default(),
+        pod_name,
+        remote_address,
+        remote_port,
+        checks,
+        cause,
+        blocking_policies,
+        summary,
+    })
+}
+
+pub async fn diagnose_proxy(config_id: i64) -> Result<ProxyDiagnosis, String> {
+    let config = get_config(config_id).await?;
+    if !is_proxy_config(&config) {
+        return Err(format!(
+            "Config {} doesn't forward through a proxy pod",
+            config_id
+        ));
+    }
+    let client = config_client(&config).await?;
+    let pods: Api<Pod> = Api::namespaced(client.clone(), &config.namespace);
+    let services: Api<Service> = Api::namespaced(client.clone(), &config.namespace);
+    let proxy_pod = resolve_config_pod(&config, &pods, &services).await?;
+    diagnose_proxy_pod(&client, &config, &proxy_pod).await
+}
+
+// why a proxy forward can't reach its remote address, while a start still has
+// the proxy pod around
+pub(crate) async fn proxy_failure_hint(config: &Config) -> Option<String> {
+    let client = config_client(config).await.ok()?;
+    let pods: Api<Pod> = Api::namespaced(client.clone(), &config.namespace);
+    let proxy_pod = pods
+        .get_opt(config.service.as_deref()?)
+        .await
+        .ok()
+        .flatten()?;
+    match diagnose_proxy_pod(&client, config, &proxy_pod).await {
+        Ok(diagnosis) => diagnosis.cause.map(|_| diagnosis.summary),
+        Err(e) => {
+            debug!(
+                "Failed to diagnose the proxy pod {}: {}",
+                config.service.as_deref()?,
+                e
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::networking::v1::{
+        IPBlock,
+        NetworkPolicyEgressRule,
+        NetworkPolicyIngressRule,
+        NetworkPolicySpec,
+    };
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{
+        LabelSelectorRequirement,
+        ObjectMeta,
+    };
+
+    use super::*;
+
+    fn labels(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn selector(pairs: &[(&str, &str)]) -> LabelSelector {
+        LabelSelector {
+            match_labels: Some(labels(pairs)),
+            ..Default::default()
+        }
+    }
+
+    fn policy(namespace: &str, name: &str, spec: NetworkPolicySpec) -> NetworkPolicy {
+        NetworkPolicy {
+            metadata: ObjectMeta {
+                namespace: Some(namespace.to_string()),
+                name: Some(name.to_string()),
+                ..Default::default()
+            },
+            spec: Some(spec),
+        }
+    }
+
+    #[test]
+    fn test_selector_and_cidr() {
+        let selector = LabelSelector {
+            match_labels: Some(labels(&[("app", "db")])),
+            match_expressions: Some(vec![LabelSelectorRequirement {
+                key: "tier".to_string(),
+                operator: "NotIn".to_string(),
+                values: Some(vec!["cache".to_string()]),
+            }]),
+        };
+        assert!(selector_matches(&selector, &labels(&[("app", "db")])));
+        assert!(!selector_matches(
+            &selector,
+            &labels(&[("app", "db"), ("tier", "cache")])
+        ));
+        assert!(selector_matches(
+            &LabelSelector::default(),
+            &BTreeMap::new()
+        ));
+
+        let ip = "10.1.2.3".parse().unwrap();
+        assert!(cidr_contains("10.0.0.0/8", ip));
+        assert!(!cidr_contains("10.1.3.0/24", ip));
+        assert!(cidr_contains("0.0.0.0/0", ip));
+        assert!(!cidr_contains("fd00::/8", ip));
+    }
+
+    #[test]
+    fn test_policy_verdict() {
+        let proxy = PolicyPeer {
+            namespace: "default".to_string(),
+            namespace_labels: labels(&[(NAMESPACE_NAME_LABEL, "default")]),
+            pod_labels: labels(&[("app", "kftray-proxy")]),
+            ip: Some("10.1.0.5".parse().unwrap()),
+        };
+        let db = PolicyPeer {
+            namespace: "payments".to_string(),
+            namespace_labels: labels(&[(NAMESPACE_NAME_LABEL, "payments")]),
+            pod_labels: labels(&[("app", "db")]),
+            ip: Some("10.1.0.9".parse().unwrap()),
+        };
+        let port = PolicyPort {
+            number: 5432,
+            name: None,
+            protocol: "TCP".to_string(),
+        };
+
+        let deny_all = policy(
+            "payments",
+            "deny-all",
+            NetworkPolicySpec {
+                pod_selector: LabelSelector::default(),
+                policy_types: Some(vec!["Ingress".to_string()]),
+                ..Default::default()
+            },
+        );
+        let from_default = policy(
+            "payments",
+            "from-default",
+            NetworkPolicySpec {
+                pod_selector: selector(&[("app", "db")]),
+                ingress: Some(vec![NetworkPolicyIngressRule {
+                    from: Some(vec![NetworkPolicyPeer {
+                        namespace_selector: Some(selector(&[(NAMESPACE_NAME_LABEL, "default")])),
+                        ..Default::default()
+                    }]),
+                    ports: Some(vec![NetworkPolicyPort {
+                        port: Some(IntOrString::Int(5432)),
+                        ..Default::default()
+                    }]),
+                }]),
+                ..Default::default()
+            },
+        );
+        let egress_to_block = policy(
+            "default",
+            "egress",
+            NetworkPolicySpec {
+                pod_selector: LabelSelector::default(),
+                egress: Some(vec![NetworkPolicyEgressRule {
+                    to: Some(vec![NetworkPolicyPeer {
+                        ip_block: Some(IPBlock {
+                            cidr: "10.1.0.0/16".to_string(),
+                            except: Some(vec!["10.1.0.8/30".to_string()]),
+                        }),
+                        ..Default::default()
+                    }]),
+                    ports: None,
+                }]),
+                ..Default::default()
+            },
+        );
+
+        let ingress = |policies: &[NetworkPolicy]| {
+            policy_verdict(policies, PolicyDirection::Ingress, &db, &proxy, &port)
+        };
+        assert_eq!(ingress(&[]), PolicyVerdict::Unrestricted);
+        assert_eq!(
+            ingress(std::slice::from_ref(&deny_all)),
+            PolicyVerdict::Blocked(vec!["payments/deny-all".to_string()])
+        );
+        assert_eq!(
+            ingress(&[deny_all, from_default.clone()]),
+            PolicyVerdict::Allowed("payments/from-default".to_string())
+        );
+        let other_port = PolicyPort {
+            number: 80,
+            ..port.clone()
+        };
+        assert!(matches!(
+            policy_verdict(
+                &[from_default],
+                PolicyDirection::Ingress,
+                &db,
+                &proxy,
+                &other_port
+            ),
+            PolicyVerdict::Blocked(_)
+        ));
+
+        // 10.1.0.9 falls in the excepted range
+        assert_eq!(
+            policy_verdict(
+                &[egress_to_block],
+                PolicyDirection::Egress,
+                &proxy,
+                &db,
+                &port
+            ),
+            PolicyVerdict::Blocked(vec!["default/egress".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_diagnosis_cause() {
+        let checks = vec![
+            check(DNS_CHECK, CheckStatus::Passed, ""),
+            check(ENDPOINTS_CHECK, CheckStatus::Failed, ""),
+            check(INGRESS_CHECK, CheckStatus::Failed, ""),
+            check(CONNECT_CHECK, CheckStatus::Failed, ""),
+        ];
+        assert_eq!(
+            diagnosis_cause(&checks),
+            Some(ProxyFailureCause::NoEndpoints)
+        );
+        assert_eq!(
+            diagnosis_cause(&checks[2..]),
+            Some(ProxyFailureCause::NetworkPolicy)
+        );
+        assert_eq!(
+            diagnosis_cause(&[
+                check(INGRESS_CHECK, CheckStatus::Failed, ""),
+                check(CONNECT_CHECK, CheckStatus::Passed, ""),
+            ]),
+            None
+        );
+
+        let config = |remote_address: &str| Config {
+            namespace: "default".to_string(),
+            remote_address: Some(remote_address.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            remote_service(&config("db.payments.svc.cluster.local")),
+            Some(("db".to_string(), "payments".to_string()))
+        );
+        assert_eq!(remote_service(&config("api.example.com")), None);
+        assert_eq!(remote_service(&config("10.0.0.1")), None);
+    }
+}
@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use k8s_openapi::api::core::v1::Pod;
+use kftray_commons::models::config_model::Config;
+use kftray_commons::utils::settings::current_settings;
+use kube::api::{
+    Api,
+    AttachParams,
+    DeleteParams,
+    Patch,
+    PatchParams,
+};
+use lazy_static::lazy_static;
+use log::{
+    debug,
+    info,
+    warn,
+};
+use serde_json::json;
+use tokio::io::AsyncReadExt;
+
+use crate::ownership::CONFIG_LABEL;
+
+// the config label of a pod that sits in the pool, so stopping a config never
+// picks it up as its own
+pub const WARM_CONFIG_LABEL: &str = "warm";
+const RELAY_ENV: [&str; 4] = ["REMOTE_ADDRESS", "REMOTE_PORT", "LOCAL_PORT", "PROXY_TYPE"];
+const BIND_TIMEOUT: Duration = Duration::from_secs(15);
+
+// a warm pod runs this instead of the relay, which only starts once a forward
+// has written its target
+const WAIT_SCRIPT: &str = r#"while [ ! -f /tmp/kftray-target ]; do sleep 0.2; done
+{ read -r REMOTE_ADDRESS; read -r REMOTE_PORT; read -r LOCAL_PORT; read -r PROXY_TYPE; } < /tmp/kftray-target
+export REMOTE_ADDRESS REMOTE_PORT LOCAL_PORT PROXY_TYPE
+exec /kftray-server
+"#;
+
+// writes the target into a warm pod and waits for the relay to replace the
+// wait script as the container's process
+const BIND_SCRIPT: &str = r#"printf '%s\n' "$1" "$2" "$3" "$4" > /tmp/kftray-target.tmp
+mv /tmp/kftray-target.tmp /tmp/kftray-target
+for _ in $(seq 50); do
+  [ "$(cat /proc/1/comm)" = kftray-server ] && exit 0
+  sleep 0.1
+done
+exit 1
+"#;
+
+// what a relay pod forwards to, handed to a warm pod when a forward takes it
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RelayTarget {
+    pub remote_address: String,
+    pub remote_port: u16,
+    pub protocol: String,
+}
+
+impl RelayTarget {
+    pub fn for_config(config: &Config) -> Self {
+        let remote_address = config
+            .remote_address
+            .clone()
+            .filter(|address| !address.is_empty())
+            .or_else(|| config.service.clone())
+            .unwrap_or_default();
+        RelayTarget {
+            remote_address,
+            remote_port: config.remote_port.unwrap_or_default(),
+            protocol: config.protocol.to_lowercase(),
+        }
+    }
+}
+
+// the pods a warm pod can stand in for: any target, as long as the pod runs
+// in the same place with the same image
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WarmKey {
+    pub context: String,
+    pub namespace: String,
+    pub image: String,
+}
+
+impl WarmKey {
+    pub fn for_pod(context: &str, namespace: &str, pod: &Pod) -> Option<Self> {
+        let image = pod.spec.as_ref()?.containers.first()?.image.clone()?;
+        Some(WarmKey {
+            context: context.to_string(),
+            namespace: namespace.to_string(),
+            image,
+        })
+    }
+}
+
+// turns a rendered relay pod into one that waits for its target, with the
+// target of the config it was rendered from taken out
+pub fn make_warm_pod(pod: &mut Pod) -> Result<(), String> {
+    pod.metadata
+        .labels
+        .get_or_insert_with(Default::default)
+        .insert(CONFIG_LABEL.to_string(), WARM_CONFIG_LABEL.to_string());
+
+    let container = pod
+        .spec
+        .as_mut()
+        .and_then(|spec| spec.containers.first_mut())
+        .ok_or("The proxy pod manifest has no container")?;
+    container.command = Some(vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        WAIT_SCRIPT.to_string(),
+    ]);
+    container.args = None;
+    if let Some(env) = container.env.as_mut() {
+        env.retain(|var| !RELAY_ENV.contains(&var.name.as_str()));
+    }
+    Ok(())
+}
+
+struct WarmPod {
+    name: String,
+    container: String,
+    pods: Api<Pod>,
+}
+
+lazy_static! {
+    // one parked relay pod per context, namespace and image
+    static ref WARM_PODS: Mutex<HashMap<WarmKey, WarmPod>> = Mutex::new(HashMap::new());
+}
+
+pub fn warm_pool_ttl() -> Option<Duration> {
+    let ttl_secs = current_settings().proxy_warm_pool_ttl_secs;
+    (ttl_secs > 0).then(|| Duration::from_secs(ttl_secs))
+}
+
+pub fn has_warm_pod(key: &WarmKey) -> bool {
+    WARM_PODS.lock().unwrap().contains_key(key)
+}
+
+async fn delete_warm_pod(warm: WarmPod) {
+    let params = DeleteParams {
+        grace_period_seconds: Some(0),
+        ..DeleteParams::default()
+    };
+    match warm.pods.delete(&warm.name, &params).await {
+        Ok(_) => info!("Deleted warm proxy pod {}", warm.name),
+        Err(e) => warn!("Failed to delete warm proxy pod {}: {}", warm.name, e),
+    }
+}
+
+async fn bind_warm_pod(
+    warm: &WarmPod, target: &RelayTarget, config_id: Option<i64>,
+) -> Result<(), String> {
+    let command = vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        BIND_SCRIPT.to_string(),
+        "bind".to_string(),
+        target.remote_address.clone(),
+        target.remote_port.to_string(),
+        target.remote_port.to_string(),
+        target.protocol.clone(),
+    ];
+    let params = AttachParams::default()
+        .container(&warm.container)
+        .stderr(true);
+    let run = async {
+        let mut process = warm
+            .pods
+            .exec(&warm.name, command, &params)
+            .await
+            .map_err(|e| e.to_string())?;
+        let mut output = String::new();
+        if let Some(mut stdout) = process.stdout() {
+            let _ = stdout.read_to_string(&mut output).await;
+        }
+        if let Some(mut stderr) = process.stderr() {
+            let _ = stderr.read_to_string(&mut output).await;
+        }
+        let status = match process.take_status() {
+            Some(status) => status.await,
+            None => None,
+        };
+        match status.and_then(|status| status.status).as_deref() {
+            Some("Success") => Ok(()),
+            _ => Err(format!("the relay didn't start: {}", output.trim())),
+        }
+    };
+    tokio::time::timeout(BIND_TIMEOUT, run)
+        .await
+        .map_err(|_| "timed out".to_string())??;
+
+    // from here on the pod belongs to the config, a stop deletes it
+    let config_label = config_id.map_or_else(|| "default".to_string(), |id| id.to_string());
+    warm.pods
+        .patch(
+            &warm.name,
+            &PatchParams::default(),
+            &Patch::Merge(json!({ "metadata": { "labels": { CONFIG_LABEL: config_label } } })),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// the parked pod of the key when it's still running, taken out of the pool
+// and started as the relay of the target
+pub async fn take_warm_pod(
+    key: &WarmKey, target: &RelayTarget, config_id: Option<i64>,
+) -> Option<String> {
+    let warm = WARM_PODS.lock().unwrap().remove(key)?;
+
+    let running = match warm.pods.get(&warm.name).await {
+        Ok(pod) => pod
+            .status
+            .and_then(|status| status.phase)
+            .is_some_and(|phase| phase == "Running"),
+        Err(e) => {
+            debug!("Warm proxy pod {} is gone: {}", warm.name, e);
+            return None;
+        }
+    };
+    if !running {
+        delete_warm_pod(warm).await;
+        return None;
+    }
+
+    if let Err(e) = bind_warm_pod(&warm, target, config_id).await {
+        warn!("Failed to take warm proxy pod {}: {}", warm.name, e);
+        delete_warm_pod(warm).await;
+        return None;
+    }
+
+    info!(
+        "Reusing warm proxy pod {} in {}/{}",
+        warm.name, key.context, key.namespace
+    );
+    Some(warm.name)
+}
+
+// keeps a pod made with `make_warm_pod` running for the next proxy start of
+// its key, in place of the pod the key had parked. false when the pool is off
+// and the caller should delete the pod itself
+pub async fn park_warm_pod(key: WarmKey, pods: Api<Pod>, name: String, container: String) -> bool {
+    let Some(ttl) = warm_pool_ttl() else {
+        return false;
+    };
+
+    let replaced = WARM_PODS.lock().unwrap().insert(
+        key.clone(),
+        WarmPod {
+            name: name.clone(),
+            container,
+            pods,
+        },
+    );
+    if let Some(replaced) = replaced.filter(|replaced| replaced.name != name) {
+        delete_warm_pod(replaced).await;
+    }
+    info!(
+        "Parked proxy pod {} in {}/{} for {}s",
+        name,
+        key.context,
+        key.namespace,
+        ttl.as_secs()
+    );
+
+    tokio::spawn(async move {
+        tokio::time::sleep(ttl).await;
+        let expired = {
+            let mut warm_pods = WARM_PODS.lock().unwrap();
+            match warm_pods.get(&key) {
+                Some(warm) if warm.name == name => warm_pods.remove(&key),
+                _ => None,
+            }
+        };
+        if let Some(expired) = expired {
+            delete_warm_pod(expired).await;
+        }
+    });
+    true
+}
+
+// the contexts with a parked pod, and the names of their pods
+pub fn warm_pods() -> HashMap<String, Vec<String>> {
+    let mut warm_pods: HashMap<String, Vec<String>> = HashMap::new();
+    for (key, warm) in WARM_PODS.lock().unwrap().iter() {
+        warm_pods
+            .entry(key.context.clone())
+            .or_default()
+            .push(warm.name.clone());
+    }
+    warm_pods
+}
+
+pub async fn drain_warm_pods() {
+    let drained: Vec<WarmPod> = WARM_PODS
+        .lock()
+        .unwrap()
+        .drain()
+        .map(|(_, warm)| warm)
+        .collect();
+    for warm in drained {
+        delete_warm_pod(warm).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relay_target_falls_back_to_service() {
+        let config = Config {
+            namespace: "shop".to_string(),
+            service: Some("cart".to_string()),
+            remote_address: Some(String::new()),
+            remote_port: Some(8080),
+            protocol: "TCP".to_string(),
+            ..Default::default()
+        };
+        let target = RelayTarget::for_config(&config);
+        assert_eq!(target.remote_address, "cart");
+        assert_eq!(target.protocol, "tcp");
+
+        let config = Config {
+            remote_address: Some("db.internal".to_string()),
+            ..config
+        };
+        assert_eq!(
+            RelayTarget::for_config(&config).remote_address,
+            "db.internal"
+        );
+    }
+
+    fn relay_pod() -> Pod {
+        serde_json::from_value(json!({
+            "metadata": {
+                "name": "kftray-forward-warm",
+                "labels": { "app": "kftray-forward-warm", "config_id": "7" }
+            },
+            "spec": {
+                "containers": [{
+                    "name": "kftray-forward-warm",
+                    "image": "ghcr.io/hcavarsan/kftray-server:latest",
+                    "env": [
+                        { "name": "LOCAL_PORT", "value": "5432" },
+                        { "name": "REMOTE_PORT", "value": "5432" },
+                        { "name": "REMOTE_ADDRESS", "value": "db" },
+                        { "name": "PROXY_TYPE", "value": "tcp" },
+                        { "name": "RUST_LOG", "value": "DEBUG" }
+                    ]
+                }]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_warm_key_leaves_the_target_out() {
+        let pod = relay_pod();
+        let key = WarmKey::for_pod("dev", "shop", &pod).unwrap();
+        assert_eq!(key.image, "ghcr.io/hcavarsan/kftray-server:latest");
+
+        let mut other = relay_pod();
+        other.spec.as_mut().unwrap().containers[0].env = None;
+        assert_eq!(WarmKey::for_pod("dev", "shop", &other), Some(key.clone()));
+        assert_ne!(WarmKey::for_pod("dev", "payments", &other), Some(key));
+    }
+
+    #[test]
+    fn test_make_warm_pod() {
+        let mut pod = relay_pod();
+        make_warm_pod(&mut pod).unwrap();
+
+        let labels = pod.metadata.labels.as_ref().unwrap();
+        assert_eq!(labels[CONFIG_LABEL], WARM_CONFIG_LABEL);
+        let container = &pod.spec.as_ref().unwrap().containers[0];
+        assert_eq!(container.command.as_ref().unwrap()[..2], ["sh", "-c"]);
+        let env: Vec<&str> = container
+            .env
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|var| var.name.as_str())
+            .collect();
+        assert_eq!(env, ["RUST_LOG"]);
+    }
+}
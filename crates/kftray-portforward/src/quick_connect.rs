@@ -0,0 +1,203 @@
+use std::collections::HashSet;
+
+use kftray_commons::config_state::get_configs_state;
+use kftray_commons::models::config_model::Config;
+use kftray_commons::utils::config_display::config_label;
+use kftray_commons::utils::profile::read_active_profile_configs;
+use kftray_commons::utils::search::{
+    filter_configs,
+    score_config,
+};
+use kftray_commons::utils::usage::get_recent_configs;
+use serde::Serialize;
+
+use crate::adhoc::AdhocForwardSpec;
+
+const QUICK_CONNECT_LIMIT: usize = 20;
+const RECENT_LIMIT: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuickConnectSource {
+    Adhoc,
+    Recent,
+    Search,
+}
+
+// a config to start, or for an ad-hoc query the spec of a forward that isn't
+// saved anywhere
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct QuickConnectItem {
+    pub source: QuickConnectSource,
+    pub label: String,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_id: Option<i64>,
+    pub running: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spec: Option<AdhocForwardSpec>,
+}
+
+// `context/namespace/service:port`, with an optional `:local port` after it.
+// contexts like eks arns hold slashes of their own, so the parts are taken
+// from the right
+pub fn parse_adhoc_query(query: &str) -> Option<AdhocForwardSpec> {
+    let query = query.trim();
+    if query.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let mut parts = query.rsplitn(3, '/');
+    let target = parts.next()?;
+    let namespace = parts.next()?;
+    let context = parts.next()?;
+
+    let mut target = target.split(':');
+    let service = target.next()?;
+    let remote_port = target.next()?.parse::<u16>().ok()?;
+    let local_port = match target.next() {
+        Some(port) => Some(port.parse::<u16>().ok()?),
+        None => None,
+    };
+    if target.next().is_some() || [context, namespace, service].contains(&"") {
+        return None;
+    }
+
+    Some(AdhocForwardSpec {
+        context: context.to_string(),
+        namespace: namespace.to_string(),
+        service: Some(service.to_string()),
+        remote_port,
+        local_port,
+        ..AdhocForwardSpec::default()
+    })
+}
+
+fn config_item(
+    config: &Config, source: QuickConnectSource, running: &HashSet<i64>,
+) -> QuickConnectItem {
+    QuickConnectItem {
+        source,
+        label: config_label(config),
+        detail: format!(
+            "{}/{}/{}:{}",
+            config.context,
+            config.namespace,
+            config
+                .service
+                .as_deref()
+                .or(config.target.as_deref())
+                .unwrap_or_default(),
+            config.remote_port.unwrap_or_default()
+        ),
+        config_id: config.id,
+        running: config.id.is_some_and(|id| running.contains(&id)),
+        spec: None,
+    }
+}
+
+pub fn quick_connect_items(
+    query: &str, configs: Vec<Config>, recent: &[Config], running: &HashSet<i64>,
+) -> Vec<QuickConnectItem> {
+    let mut items = Vec::new();
+
+    if let Some(spec) = parse_adhoc_query(query) {
+        items.push(QuickConnectItem {
+            source: QuickConnectSource::Adhoc,
+            label: format!("forward {}", query.trim()),
+            detail: "ad-hoc, not saved".to_string(),
+            config_id: None,
+            running: false,
+            spec: Some(spec),
+        });
+    }
+
+    let mut listed = HashSet::new();
+    for config in recent {
+        if score_config(config, query).is_some() {
+            listed.extend(config.id);
+            items.push(config_item(config, QuickConnectSource::Recent, running));
+        }
+    }
+
+    let rest: Vec<Config> = configs
+        .into_iter()
+        .filter(|config| config.id.is_some_and(|id| !listed.contains(&id)))
+        .collect();
+    items.extend(
+        filter_configs(rest, query)
+            .iter()
+            .map(|config| config_item(config, QuickConnectSource::Search, running)),
+    );
+
+    items.truncate(QUICK_CONNECT_LIMIT);
+    items
+}
+
+// an empty query lists the recent configs first, followed by the rest
+pub async fn quick_connect(query: &str) -> Result<Vec<QuickConnectItem>, String> {
+    let configs = read_active_profile_configs().await?;
+    let recent = get_recent_configs(Some(RECENT_LIMIT)).await?;
+    let running: HashSet<i64> = get_configs_state()
+        .await?
+        .into_iter()
+        .filter(|state| state.is_running)
+        .map(|state| state.config_id)
+        .collect();
+
+    Ok(quick_connect_items(query, configs, &recent, &running))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(id: i64, alias: &str) -> Config {
+        Config {
+            id: Some(id),
+            alias: Some(alias.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_adhoc_query() {
+        let spec = parse_adhoc_query("kind/shop/cart:8080").unwrap();
+        assert_eq!(spec.context, "kind");
+        assert_eq!(spec.namespace, "shop");
+        assert_eq!(spec.service.as_deref(), Some("cart"));
+        assert_eq!(spec.remote_port, 8080);
+        assert_eq!(spec.local_port, None);
+
+        let spec =
+            parse_adhoc_query("arn:aws:eks:eu-west-1:1:cluster/prod/db/pg:5432:15432").unwrap();
+        assert_eq!(spec.context, "arn:aws:eks:eu-west-1:1:cluster/prod");
+        assert_eq!(spec.local_port, Some(15432));
+
+        assert!(parse_adhoc_query("shop/cart:8080").is_none());
+        assert!(parse_adhoc_query("kind/shop/cart").is_none());
+        assert!(parse_adhoc_query("kind/shop/cart:http").is_none());
+        assert!(parse_adhoc_query("kind//cart:8080").is_none());
+    }
+
+    #[test]
+    fn test_quick_connect_items() {
+        let configs = vec![
+            config(1, "postgres"),
+            config(2, "api"),
+            config(3, "pgadmin"),
+        ];
+        let recent = vec![config(3, "pgadmin")];
+        let running = HashSet::from([1]);
+
+        let items = quick_connect_items("pg", configs.clone(), &recent, &running);
+        let ids: Vec<Option<i64>> = items.iter().map(|item| item.config_id).collect();
+        assert_eq!(ids, vec![Some(3), Some(1)]);
+        assert_eq!(items[0].source, QuickConnectSource::Recent);
+        assert!(items[1].running);
+
+        let items = quick_connect_items("kind/shop/cart:8080", configs, &recent, &running);
+        assert_eq!(items[0].source, QuickConnectSource::Adhoc);
+        assert!(items[0].spec.is_some());
+    }
+}
@@ -0,0 +1,154 @@
+use k8s_openapi::api::authorization::v1::{
+    ResourceAttributes,
+    SelfSubjectAccessReview,
+    SelfSubjectAccessReviewSpec,
+};
+use kftray_commons::models::config_model::Config;
+use kube::api::{
+    Api,
+    PostParams,
+};
+use kube::Client;
+
+use crate::pod_finder::is_proxy_config;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RequiredAccess {
+    pub verb: &'static str,
+    pub resource: &'static str,
+    pub subresource: Option<&'static str>,
+}
+
+impl RequiredAccess {
+    const fn new(
+        verb: &'static str, resource: &'static str, subresource: Option<&'static str>,
+    ) -> Self {
+        Self {
+            verb,
+            resource,
+            subresource,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self.subresource {
+            Some(subresource) => format!("{} {}/{}", self.verb, self.resource, subresource),
+            None => format!("{} {}", self.verb, self.resource),
+        }
+    }
+}
+
+// the verbs a forward of this config needs in its namespace
+pub fn required_access(config: &Config) -> Vec<RequiredAccess> {
+    let mut access = vec![
+        RequiredAccess::new("create", "pods", Some("portforward")),
+        RequiredAccess::new("list", "pods", None),
+    ];
+
+    if is_proxy_config(config) {
+        access.push(RequiredAccess::new("create", "pods", None));
+        access.push(RequiredAccess::new("delete", "pods", None));
+    } else if config.workload_type.as_deref() != Some("pod") {
+        access.push(RequiredAccess::new("get", "services", None));
+        access.push(RequiredAccess::new("list", "services", None));
+    }
+
+    access
+}
+
+async fn is_allowed(
+    client: &Client, namespace: &str, access: &RequiredAccess,
+) -> Result<bool, String> {
+    let review = SelfSubjectAccessReview {
+        spec: SelfSubjectAccessReviewSpec {
+            resource_attributes: Some(ResourceAttributes {
+                namespace: Some(namespace.to_string()),
+                verb: Some(access.verb.to_string()),
+                resource: Some(access.resource.to_string()),
+                subresource: access.subresource.map(str::to_string),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let reviews: Api<SelfSubjectAccessReview> = Api::all(client.clone());
+    let review = reviews
+        .create(&PostParams::default(), &review)
+        .await
+        .map_err(|e| {
+            format!(
+                "Failed to run access review for {}: {}",
+                access.describe(),
+                e
+            )
+        })?;
+
+    Ok(review.status.is_some_and(|status| status.allowed))
+}
+
+pub async fn check_rbac(config: &Config, client: &Client) -> Result<(), String> {
+    let mut missing = Vec::new();
+    for access in required_access(config) {
+        if !is_allowed(client, &config.namespace, &access).await? {
+            missing.push(access.describe());
+        }
+    }
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    Err(format!(
+        "You lack permission to {} in namespace {} (context {})",
+        missing.join(", "),
+        config.namespace,
+        config.context
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_access() {
+        let describe = |config: &Config| -> Vec<String> {
+            required_access(config)
+                .iter()
+                .map(RequiredAccess::describe)
+                .collect()
+        };
+
+        let service = Config {
+            workload_type: Some("service".to_string()),
+            protocol: "tcp".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            describe(&service),
+            vec![
+                "create pods/portforward",
+                "list pods",
+                "get services",
+                "list services"
+            ]
+        );
+
+        let proxy = Config {
+            workload_type: Some("proxy".to_string()),
+            protocol: "tcp".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            describe(&proxy),
+            vec![
+                "create pods/portforward",
+                "list pods",
+                "create pods",
+                "delete pods"
+            ]
+        );
+    }
+}
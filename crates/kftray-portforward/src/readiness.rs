@@ -0,0 +1,250 @@
+use std::net::{
+    IpAddr,
+    Ipv4Addr,
+    SocketAddr,
+};
+use std::time::Duration;
+
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::grpc_health_model::ServingStatus;
+use kftray_commons::utils::settings::current_settings;
+use log::{
+    debug,
+    info,
+};
+use tokio::io::{
+    AsyncReadExt,
+    AsyncWriteExt,
+};
+use tokio::net::TcpStream;
+use tokio::time::{
+    sleep,
+    timeout,
+    Instant,
+};
+
+use crate::grpc_health::check_grpc_health;
+
+const READINESS_RETRY_INTERVAL: Duration = Duration::from_millis(500);
+const TCP_PROBE_GRACE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadinessCheck {
+    Tcp,
+    Http { path: String },
+    Grpc { service: String },
+}
+
+impl ReadinessCheck {
+    pub fn from_config(config: &Config) -> Option<Self> {
+        match config.readiness_check.as_deref()? {
+            "tcp" => Some(ReadinessCheck::Tcp),
+            "http" => Some(ReadinessCheck::Http {
+                path: config
+                    .readiness_path
+                    .clone()
+                    .filter(|path| !path.is_empty())
+                    .unwrap_or_else(|| "/".to_string()),
+            }),
+            "grpc" => Some(ReadinessCheck::Grpc {
+                service: config
+                    .grpc_health_services
+                    .iter()
+                    .flatten()
+                    .next()
+                    .cloned()
+                    .unwrap_or_default(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn probe_address(config: &Config, local_port: u16) -> SocketAddr {
+    let ip = config
+        .local_address
+        .as_deref()
+        .and_then(|address| address.parse::<IpAddr>().ok())
+        .filter(|ip| !ip.is_unspecified())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+
+    SocketAddr::new(ip, local_port)
+}
+
+pub fn parse_http_status(response: &[u8]) -> Option<u16> {
+    let line_end = response.iter().position(|b| *b == b'\n')?;
+    let status_line = std::str::from_utf8(&response[..line_end]).ok()?;
+    let mut parts = status_line.split_whitespace();
+
+    if !parts.next()?.starts_with("HTTP/") {
+        return None;
+    }
+
+    parts.next()?.parse().ok()
+}
+
+async fn probe_tcp(addr: SocketAddr) -> Result<(), String> {
+    let mut stream = TcpStream::connect(addr)
+        .await
+        .map_err(|e| format!("connect to {} failed: {}", addr, e))?;
+
+    let mut buf = [0u8; 1];
+    match timeout(TCP_PROBE_GRACE, stream.read(&mut buf)).await {
+        Err(_) | Ok(Ok(1..)) => Ok(()),
+        Ok(Ok(_)) => Err(format!("connection to {} was closed by the backend", addr)),
+        Ok(Err(e)) => Err(format!("connection to {} failed: {}", addr, e)),
+    }
+}
+
+async fn probe_http(addr: SocketAddr, host: &str, path: &str) -> Result<(), String> {
+    let mut stream = TcpStream::connect(addr)
+        .await
+        .map_err(|e| format!("connect to {} failed: {}", addr, e))?;
+
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: kftray-readiness\r\nConnection: close\r\n\r\n",
+        path, host
+    );
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| format!("request to {} failed: {}", addr, e))?;
+
+    let mut response = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !response.contains(&b'\n') {
+        let read = stream
+            .read(&mut buf)
+            .await
+            .map_err(|e| format!("response from {} failed: {}", addr, e))?;
+        if read == 0 {
+            break;
+        }
+        response.extend_from_slice(&buf[..read]);
+    }
+
+    match parse_http_status(&response) {
+        Some(status) if (200..300).contains(&status) => Ok(()),
+        Some(status) => Err(format!("GET {} returned HTTP {}", path, status)),
+        None => Err(format!("GET {} returned an invalid HTTP response", path)),
+    }
+}
+
+async fn probe_grpc(addr: SocketAddr, service: &str) -> Result<(), String> {
+    match check_grpc_health(addr, service).await? {
+        ServingStatus::Serving => Ok(()),
+        status => Err(format!("gRPC health status is {:?}", status)),
+    }
+}
+
+pub(crate) async fn probe_once(
+    check: &ReadinessCheck, addr: SocketAddr, host: &str,
+) -> Result<(), String> {
+    match check {
+        ReadinessCheck::Tcp => probe_tcp(addr).await,
+        ReadinessCheck::Http { path } => probe_http(addr, host, path).await,
+        ReadinessCheck::Grpc { service } => probe_grpc(addr, service).await,
+    }
+}
+
+// the host header http probes send, the alias when it resolves to the forward
+pub(crate) fn probe_host(config: &Config, addr: SocketAddr) -> String {
+    config
+        .alias
+        .clone()
+        .filter(|alias| !alias.is_empty() && config.domain_enabled.unwrap_or_default())
+        .unwrap_or_else(|| addr.ip().to_string())
+}
+
+pub async fn wait_until_ready(config: &Config, local_port: u16) -> Result<(), String> {
+    let Some(check) = ReadinessCheck::from_config(config) else {
+        return Ok(());
+    };
+
+    let addr = probe_address(config, local_port);
+    let host = probe_host(config, addr);
+    let limit = Duration::from_secs(
+        config
+            .readiness_timeout_secs
+            .unwrap_or_else(|| current_settings().readiness_timeout_secs),
+    );
+    let deadline = Instant::now() + limit;
+
+    info!(
+        "Waiting for {:?} readiness on {} for config {}",
+        check,
+        addr,
+        config.id.unwrap_or_default()
+    );
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let attempt = probe_once(&check, addr, &host);
+
+        let last_error = match timeout(remaining, attempt).await {
+            Ok(Ok(())) => return Ok(()),
+            Ok(Err(e)) => e,
+            Err(_) => "probe did not complete".to_string(),
+        };
+
+        debug!("Readiness probe failed: {}", last_error);
+
+        if Instant::now() + READINESS_RETRY_INTERVAL >= deadline {
+            return Err(format!(
+                "Forward did not become ready within {}s: {}",
+                limit.as_secs(),
+                last_error
+            ));
+        }
+
+        sleep(READINESS_RETRY_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_http_status() {
+        assert_eq!(parse_http_status(b"HTTP/1.1 204 No Content\r\n"), Some(204));
+        assert_eq!(parse_http_status(b"HTTP/1.0 503\r\n\r\n"), Some(503));
+        assert_eq!(parse_http_status(b"SSH-2.0-OpenSSH\r\n"), None);
+        assert_eq!(parse_http_status(b"HTTP/1.1 200"), None);
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_ready_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0u8; 1024];
+                let read = socket.read(&mut buf).await.unwrap_or_default();
+                let status = if buf[..read].starts_with(b"GET /healthz ") {
+                    "200 OK"
+                } else {
+                    "404 Not Found"
+                };
+                let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status);
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let mut config = Config {
+            readiness_check: Some("http".to_string()),
+            readiness_path: Some("/healthz".to_string()),
+            readiness_timeout_secs: Some(2),
+            ..Default::default()
+        };
+        assert!(wait_until_ready(&config, port).await.is_ok());
+
+        config.readiness_path = Some("/missing".to_string());
+        config.readiness_timeout_secs = Some(1);
+        let err = wait_until_ready(&config, port).await.unwrap_err();
+        assert!(err.contains("HTTP 404"));
+    }
+}
@@ -0,0 +1,168 @@
+use std::net::{
+    TcpListener,
+    UdpSocket,
+};
+
+use hostsfile::HostsBuilder;
+use kftray_commons::config::get_config;
+use kftray_commons::config_state::{
+    get_configs_state,
+    update_config_state,
+};
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::config_state_model::ConfigState;
+use kftray_commons::models::reconcile_model::ReconcileReport;
+use log::{
+    info,
+    warn,
+};
+
+use crate::core::delete_proxy_pods;
+use crate::fanout::remove_fan_out_hosts;
+use crate::pod_finder::is_proxy_config;
+use crate::port_forward::{
+    listen_address,
+    CHILD_PROCESSES,
+};
+
+fn has_local_handle(config_id: i64) -> bool {
+    let prefix = format!("{}_", config_id);
+    CHILD_PROCESSES
+        .lock()
+        .unwrap()
+        .keys()
+        .any(|key| key.starts_with(&prefix))
+}
+
+// a port that can't be bound is still served, most likely by another kftray
+// or kftui instance that owns the forward
+pub(crate) fn port_in_use(config: &Config) -> bool {
+    let Some(port) = config.local_port.filter(|port| *port != 0) else {
+        return false;
+    };
+    let address = listen_address(config.local_address.clone());
+
+    match config.protocol.as_str() {
+        "udp" => UdpSocket::bind((address.as_str(), port)).is_err(),
+        _ => TcpListener::bind((address.as_str(), port)).is_err(),
+    }
+}
+
+pub(crate) fn clean_hosts(config: &Config) -> Result<(), String> {
+    let hostfile_comment = format!(
+        "kftray custom host for {} - {}",
+        config.service.clone().unwrap_or_default(),
+        config.id.unwrap_or_default()
+    );
+    HostsBuilder::new(&hostfile_comment)
+        .write()
+        .map_err(|e| e.to_string())?;
+    remove_fan_out_hosts(config)
+}
+
+async fn repair(config: &Config, report: &mut ReconcileReport) {
+    let config_id = config.id.unwrap_or_default();
+
+    if let Err(e) = update_config_state(&ConfigState {
+        id: None,
+        config_id,
+        is_running: false,
+        local_port: None,
+    })
+    .await
+    {
+        report
+            .errors
+            .push(format!("Failed to stop config {}: {}", config_id, e));
+        return;
+    }
+    report.stopped_configs.push(config_id);
+
+    if config.domain_enabled.unwrap_or_default() || config.fan_out.unwrap_or_default() {
+        match clean_hosts(config) {
+            Ok(()) => report.cleaned_hosts.push(config_id),
+            Err(e) => report.errors.push(format!(
+                "Failed to clean hosts entries of config {}: {}",
+                config_id, e
+            )),
+        }
+    }
+
+    if is_proxy_config(config) && config.kubeconfig.is_some() {
+        match delete_proxy_pods(config).await {
+            Ok(deleted) => report.deleted_proxy_pods.extend(deleted),
+            Err(e) => report.errors.push(format!(
+                "Failed to delete proxy pods of config {}: {}",
+                config_id, e
+            )),
+        }
+    }
+}
+
+// marks configs left "running" by a crashed session as stopped and cleans up
+// what they left behind
+pub async fn reconcile_config_states() -> Result<ReconcileReport, String> {
+    let mut report = ReconcileReport::default();
+
+    for state in get_configs_state().await? {
+        if !state.is_running || has_local_handle(state.config_id) {
+            continue;
+        }
+
+        let config = match get_config(state.config_id).await {
+            Ok(config) => Config {
+                local_port: state.local_port.or(config.local_port),
+                ..config
+            },
+            Err(e) => {
+                report
+                    .errors
+                    .push(format!("Could not read config {}: {}", state.config_id, e));
+                continue;
+            }
+        };
+
+        if port_in_use(&config) {
+            continue;
+        }
+
+        repair(&config, &mut report).await;
+    }
+
+    if report.is_clean() {
+        info!("Config states are consistent, nothing to reconcile");
+    } else {
+        warn!(
+            "Reconciled stale config states: stopped {:?}, hosts {:?}, proxy pods {:?}",
+            report.stopped_configs, report.cleaned_hosts, report.deleted_proxy_pods
+        );
+        for error in &report.errors {
+            warn!("Reconciliation error: {}", error);
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_port_in_use() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = Config {
+            protocol: "tcp".to_string(),
+            local_port: Some(listener.local_addr().unwrap().port()),
+            local_address: Some("127.0.0.1".to_string()),
+            ..Default::default()
+        };
+        assert!(port_in_use(&config));
+
+        let unknown_port = Config {
+            local_port: Some(0),
+            ..config
+        };
+        assert!(!port_in_use(&unknown_port));
+    }
+}
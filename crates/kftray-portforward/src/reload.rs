@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
+use kftray_commons::config::get_config;
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::reload_model::PendingReload;
+use kftray_commons::models::response::CustomResponse;
+use kftray_commons::utils::config_state::{
+    bound_local_port,
+    get_configs_state,
+};
+use kftray_commons::utils::template::resolve_config_templates;
+use lazy_static::lazy_static;
+use log::info;
+use serde_json::Value;
+
+use crate::core::{
+    deploy_and_forward_pod,
+    start_port_forward,
+    stop_port_forward,
+    stop_proxy_forward,
+};
+use crate::expiry::{
+    forward_time_left,
+    set_forward_ttl,
+};
+use crate::mappings::schedule_mappings_publish;
+use crate::models::kube::HttpLogState;
+use crate::pod_finder::is_proxy_config;
+
+lazy_static! {
+    // the configs running forwards were started with, before template resolution
+    static ref RUNNING_CONFIGS: Mutex<HashMap<i64, Config>> = Mutex::new(HashMap::new());
+}
+
+pub(crate) fn remember_running_config(config: &Config) {
+    if let Some(config_id) = config.id {
+        RUNNING_CONFIGS
+            .lock()
+            .unwrap()
+            .insert(config_id, config.clone());
+        schedule_mappings_publish();
+    }
+}
+
+pub(crate) fn running_config(config_id: i64) -> Option<Config> {
+    RUNNING_CONFIGS.lock().unwrap().get(&config_id).cloned()
+}
+
+pub(crate) fn running_configs() -> Vec<Config> {
+    RUNNING_CONFIGS.lock().unwrap().values().cloned().collect()
+}
+
+// what the running forwards actually serve, templates resolved and on the
+// local port they got, ordered by config id
+pub(crate) async fn running_forwards() -> Result<Vec<Config>, String> {
+    let states = get_configs_state().await?;
+    let mut forwards = Vec::new();
+    for config in running_configs() {
+        let mut config = resolve_config_templates(config).await?;
+        config.local_port = bound_local_port(&config, &states).or(config.local_port);
+        forwards.push(config);
+    }
+    forwards.sort_by_key(|config| config.id);
+    Ok(forwards)
+}
+
+pub(crate) fn forget_running_config(config_id: Option<i64>) {
+    let mut running = RUNNING_CONFIGS.lock().unwrap();
+    match config_id {
+        Some(config_id) => {
+            running.remove(&config_id);
+        }
+        None => running.clear(),
+    }
+    drop(running);
+    schedule_mappings_publish();
+}
+
+pub fn changed_fields(old: &Config, new: &Config) -> Vec<String> {
+    let (Ok(Value::Object(old)), Ok(Value::Object(new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return Vec::new();
+    };
+
+    let mut fields: Vec<String> = old
+        .keys()
+        .chain(new.keys())
+        .filter(|key| old.get(*key) != new.get(*key))
+        .cloned()
+        .collect();
+    fields.sort();
+    fields.dedup();
+    fields
+}
+
+pub async fn pending_reload(config_id: i64) -> Result<Option<PendingReload>, String> {
+    let Some(running) = RUNNING_CONFIGS.lock().unwrap().get(&config_id).cloned() else {
+        return Ok(None);
+    };
+
+    let changed_fields = changed_fields(&running, &get_config(config_id).await?);
+    if changed_fields.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(PendingReload {
+        config_id,
+        changed_fields,
+    }))
+}
+
+pub async fn pending_reloads() -> Result<Vec<PendingReload>, String> {
+    let mut config_ids: Vec<i64> = RUNNING_CONFIGS.lock().unwrap().keys().copied().collect();
+    config_ids.sort_unstable();
+
+    let mut pending = Vec::new();
+    for config_id in config_ids {
+        pending.extend(pending_reload(config_id).await?);
+    }
+    Ok(pending)
+}
+
+// restarts a running forward with the values currently stored for its config
+pub async fn reload_config(
+    config_id: i64, http_log_state: Arc<HttpLogState>,
+) -> Result<Vec<CustomResponse>, String> {
+    let Some(running) = RUNNING_CONFIGS.lock().unwrap().get(&config_id).cloned() else {
+        return Err(format!("Config {} is not running", config_id));
+    };
+    let config = get_config(config_id).await?;
+
+    info!(
+        "Reloading config {} with changed fields {:?}",
+        config_id,
+        changed_fields(&running, &config)
+    );
+
+    // a time limited forward keeps counting down across the restart
+    let time_left = forward_time_left(config_id);
+
+    if is_proxy_config(&running) {
+        stop_proxy_forward(
+            config_id,
+            &running.namespace,
+            running.service.clone().unwrap_or_default(),
+        )
+        .await?;
+    } else {
+        stop_port_forward(config_id.to_string()).await?;
+    }
+
+    let result = if is_proxy_config(&config) {
+        deploy_and_forward_pod(vec![config], http_log_state).await
+    } else {
+        let protocol = config.protocol.clone();
+        start_port_forward(vec![config], &protocol, http_log_state).await
+    };
+    if let (Ok(_), Some(time_left)) = (&result, time_left) {
+        set_forward_ttl(config_id, time_left);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_fields() {
+        let running = Config {
+            id: Some(1),
+            local_port: Some(8080),
+            ..Default::default()
+        };
+        assert!(changed_fields(&running, &running.clone()).is_empty());
+
+        let edited = Config {
+            local_port: Some(8081),
+            tags: Some(vec!["db".to_string()]),
+            ..running.clone()
+        };
+        assert_eq!(
+            changed_fields(&running, &edited),
+            vec!["local_port", "tags"]
+        );
+    }
+}
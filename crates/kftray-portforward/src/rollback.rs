@@ -0,0 +1,128 @@
+use hostsfile::HostsBuilder;
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::{
+    Api,
+    DeleteParams,
+};
+use log::{
+    debug,
+    warn,
+};
+
+use crate::port_forward::CHILD_PROCESSES;
+
+// something a start left behind that has to go when the start fails
+pub enum StartEffect {
+    // the key of the forward task in CHILD_PROCESSES
+    Handle(String),
+    // the comment the hosts entries were written under
+    HostsEntry(String),
+    ProxyPod { pods: Api<Pod>, name: String },
+}
+
+impl StartEffect {
+    async fn undo(self) {
+        match self {
+            StartEffect::Handle(key) => {
+                if let Some(handle) = CHILD_PROCESSES.lock().unwrap().remove(&key) {
+                    handle.abort();
+                }
+            }
+            StartEffect::HostsEntry(comment) => {
+                if let Err(e) = HostsBuilder::new(&comment).write() {
+                    warn!("Failed to remove the hosts entries of {}: {}", comment, e);
+                }
+            }
+            StartEffect::ProxyPod { pods, name } => {
+                let params = DeleteParams {
+                    grace_period_seconds: Some(0),
+                    ..DeleteParams::default()
+                };
+                if let Err(e) = pods.delete(&name, &params).await {
+                    warn!("Failed to delete proxy pod {}: {}", name, e);
+                }
+            }
+        }
+    }
+}
+
+// the side effects of a start per config, so a failed start can take back
+// everything it did instead of only the forward tasks
+#[derive(Default)]
+pub struct StartJournal {
+    effects: Vec<(i64, StartEffect)>,
+}
+
+impl StartJournal {
+    pub fn record(&mut self, config_id: i64, effect: StartEffect) {
+        self.effects.push((config_id, effect));
+    }
+
+    pub fn config_ids(&self) -> Vec<i64> {
+        let mut config_ids: Vec<i64> = self.effects.iter().map(|(id, _)| *id).collect();
+        config_ids.sort_unstable();
+        config_ids.dedup();
+        config_ids
+    }
+
+    // undoes the effects of the configs, the last one first, and forgets
+    // them. the effects of other configs stay
+    pub async fn undo(&mut self, config_ids: &[i64]) {
+        let (undo, keep): (Vec<_>, Vec<_>) = std::mem::take(&mut self.effects)
+            .into_iter()
+            .partition(|(config_id, _)| config_ids.contains(config_id));
+        self.effects = keep;
+
+        debug!(
+            "Undoing {} start effect(s) of config(s) {:?}",
+            undo.len(),
+            config_ids
+        );
+        for (_, effect) in undo.into_iter().rev() {
+            effect.undo().await;
+        }
+    }
+
+    pub async fn undo_all(&mut self) {
+        let config_ids = self.config_ids();
+        self.undo(&config_ids).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn_handle(key: &str) {
+        let handle = tokio::spawn(std::future::pending::<()>());
+        CHILD_PROCESSES
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), handle);
+    }
+
+    #[tokio::test]
+    async fn test_undo_only_touches_the_given_configs() {
+        spawn_handle("rollback-test-1");
+        spawn_handle("rollback-test-2");
+
+        let mut journal = StartJournal::default();
+        journal.record(1, StartEffect::Handle("rollback-test-1".to_string()));
+        journal.record(2, StartEffect::Handle("rollback-test-2".to_string()));
+
+        journal.undo(&[1]).await;
+        {
+            let processes = CHILD_PROCESSES.lock().unwrap();
+            assert!(!processes.contains_key("rollback-test-1"));
+            assert!(processes.contains_key("rollback-test-2"));
+        }
+        assert_eq!(journal.config_ids(), vec![2]);
+
+        journal.undo_all().await;
+        assert!(!CHILD_PROCESSES
+            .lock()
+            .unwrap()
+            .contains_key("rollback-test-2"));
+        assert!(journal.config_ids().is_empty());
+    }
+}
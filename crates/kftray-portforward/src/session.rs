@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use kftray_commons::config::get_config;
+use kftray_commons::config_state::{
+    bound_local_port,
+    get_configs_state,
+};
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::reconcile_model::ReconcileReport;
+use kftray_commons::models::response::CustomResponse;
+use kftray_commons::models::session_model::SessionInfo;
+use kftray_commons::utils::session::{
+    current_session_id,
+    list_sessions,
+    release_session,
+};
+use log::{
+    info,
+    warn,
+};
+
+use crate::audit::start_with_reason;
+use crate::core::delete_proxy_pods_matching;
+use crate::headless::start_config;
+use crate::models::kube::HttpLogState;
+use crate::ownership::session_pods_selector;
+use crate::pod_finder::is_proxy_config;
+use crate::reconcile::{
+    clean_hosts,
+    port_in_use,
+};
+
+pub async fn get_sessions() -> Result<Vec<SessionInfo>, String> {
+    list_sessions().await
+}
+
+async fn session_configs(session_id: &str) -> Result<Vec<Config>, String> {
+    if session_id == current_session_id() {
+        return Err("The current session can't be cleaned up or adopted".to_string());
+    }
+
+    let session = list_sessions()
+        .await?
+        .into_iter()
+        .find(|session| session.session_id == session_id)
+        .ok_or_else(|| format!("Session {} has no running configs", session_id))?;
+
+    let mut configs = Vec::with_capacity(session.config_ids.len());
+    for config_id in session.config_ids {
+        configs.push(get_config(config_id).await?);
+    }
+    Ok(configs)
+}
+
+// releases the state rows, hosts entries and proxy pods of another session
+// once it's gone, a session still holding its ports is left alone
+pub async fn clean_session(session_id: &str) -> Result<ReconcileReport, String> {
+    let states = get_configs_state().await?;
+    let configs: Vec<Config> = session_configs(session_id)
+        .await?
+        .into_iter()
+        .map(|config| Config {
+            local_port: bound_local_port(&config, &states),
+            ..config
+        })
+        .collect();
+    if let Some(config) = configs.iter().find(|config| port_in_use(config)) {
+        return Err(format!(
+            "Session {} still serves config {} on port {}",
+            session_id,
+            config.id.unwrap_or_default(),
+            config.local_port.unwrap_or_default()
+        ));
+    }
+
+    let mut report = ReconcileReport {
+        stopped_configs: release_session(session_id).await?,
+        ..Default::default()
+    };
+
+    for config in &configs {
+        let config_id = config.id.unwrap_or_default();
+
+        if config.domain_enabled.unwrap_or_default() || config.fan_out.unwrap_or_default() {
+            match clean_hosts(config) {
+                Ok(()) => report.cleaned_hosts.push(config_id),
+                Err(e) => report.errors.push(format!(
+                    "Failed to clean hosts entries of config {}: {}",
+                    config_id, e
+                )),
+            }
+        }
+
+        if is_proxy_config(config) && config.kubeconfig.is_some() {
+            let selector = session_pods_selector(config.id, session_id);
+            match delete_proxy_pods_matching(config, &selector).await {
+                Ok(deleted) => report.deleted_proxy_pods.extend(deleted),
+                Err(e) => report.errors.push(format!(
+                    "Failed to delete proxy pods of config {}: {}",
+                    config_id, e
+                )),
+            }
+        }
+    }
+
+    info!(
+        "Cleaned up session {}: stopped {:?}, proxy pods {:?}",
+        session_id, report.stopped_configs, report.deleted_proxy_pods
+    );
+    Ok(report)
+}
+
+// cleans up another session and restarts its forwards in this one. the
+// reason is checked before anything of the other session is released
+pub async fn adopt_session(
+    session_id: &str, reason: Option<&str>, http_log_state: Arc<HttpLogState>,
+) -> Result<Vec<CustomResponse>, String> {
+    let configs = session_configs(session_id).await?;
+    start_with_reason(
+        &configs,
+        reason,
+        restart_session(session_id, http_log_state),
+    )
+    .await
+}
+
+async fn restart_session(
+    session_id: &str, http_log_state: Arc<HttpLogState>,
+) -> Result<Vec<CustomResponse>, String> {
+    let report = clean_session(session_id).await?;
+
+    let mut responses = Vec::new();
+    let mut errors = report.errors;
+    for config_id in report.stopped_configs {
+        let result = match get_config(config_id).await {
+            Ok(config) => start_config(config, http_log_state.clone()).await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(started) => responses.extend(started),
+            Err(e) => {
+                warn!("Failed to adopt config {}: {}", config_id, e);
+                errors.push(format!("Failed to adopt config {}: {}", config_id, e));
+            }
+        }
+    }
+
+    if responses.is_empty() && !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+    Ok(responses)
+}
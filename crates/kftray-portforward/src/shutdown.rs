@@ -0,0 +1,119 @@
+use std::sync::atomic::{
+    AtomicBool,
+    Ordering,
+};
+use std::time::Duration;
+
+use kftray_commons::models::response::CustomResponse;
+use kftray_commons::utils::db::get_db_pool;
+use log::{
+    error,
+    info,
+    warn,
+};
+use tokio::time::timeout;
+
+use crate::cluster_network::close_cluster_network;
+use crate::core::stop_all_port_forward;
+use crate::port_forward::CHILD_PROCESSES;
+use crate::proxy_pool::drain_warm_pods;
+use crate::ssm::close_ssm_tunnels;
+
+pub const SHUTDOWN_GRACE: Duration = Duration::from_secs(15);
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+pub fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.load(Ordering::SeqCst)
+}
+
+fn abort_remaining_forwards() {
+    let handles: Vec<_> = CHILD_PROCESSES.lock().unwrap().drain().collect();
+    for (key, handle) in handles {
+        warn!("Aborting port forward {} after shutdown grace period", key);
+        handle.abort();
+    }
+}
+
+// takes the cluster network down and stops every forward, hosts entries,
+// proxy pods and config states included, then the ssm tunnels and the parked
+// proxy pods, and closes the database so nothing is left half written. only
+// runs once
+pub async fn shutdown(grace: Duration) -> Result<Vec<CustomResponse>, String> {
+    if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+        info!("Shutdown already in progress");
+        return Ok(Vec::new());
+    }
+
+    close_cluster_network().await;
+    info!("Shutting down, stopping all port forwards");
+    let result = match timeout(grace, stop_all_port_forward()).await {
+        Ok(result) => result,
+        Err(_) => {
+            abort_remaining_forwards();
+            Err(format!(
+                "Stopping port forwards took longer than {}s",
+                grace.as_secs()
+            ))
+        }
+    };
+    close_ssm_tunnels().await;
+    drain_warm_pods().await;
+
+    match get_db_pool().await {
+        Ok(pool) => pool.close().await,
+        Err(e) => error!("Failed to flush database on shutdown: {}", e),
+    }
+
+    result
+}
+
+pub async fn wait_for_shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{
+            signal,
+            SignalKind,
+        };
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+                return;
+            }
+            Err(e) => error!("Failed to listen for SIGTERM: {}", e),
+        }
+    }
+
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        error!("Failed to listen for Ctrl-C: {}", e);
+        std::future::pending::<()>().await;
+    }
+}
+
+// runs `on_done` once the forwards are cleaned up, so the caller decides how
+// to exit (tauri app handle, restoring the terminal, ...)
+pub async fn shutdown_on_signal<F>(on_done: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    wait_for_shutdown_signal().await;
+    info!("Received shutdown signal");
+
+    if let Err(e) = shutdown(SHUTDOWN_GRACE).await {
+        error!("Failed to shut down cleanly: {}", e);
+    }
+    on_done();
+}
+
+// for callers already inside the tokio runtime, the tauri setup spawns
+// `shutdown_on_signal` on its own runtime instead
+pub fn install_shutdown_handler<F>(on_done: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    tokio::spawn(shutdown_on_signal(on_done));
+}
@@ -0,0 +1,224 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use kftray_commons::config::get_config;
+use kftray_commons::models::config_model::Config;
+use kftray_commons::utils::settings::current_settings;
+use log::{
+    info,
+    warn,
+};
+use serde::Serialize;
+use tokio::time::{
+    timeout,
+    Instant,
+};
+
+use crate::audit::start_with_reason;
+use crate::cancel::is_start_in_flight;
+use crate::headless::{
+    start_config,
+    stop_config,
+};
+use crate::models::kube::HttpLogState;
+use crate::readiness::{
+    probe_address,
+    probe_host,
+    probe_once,
+    ReadinessCheck,
+};
+use crate::reload::running_config;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ForwardTestReport {
+    pub config_id: i64,
+    pub success: bool,
+    pub probe: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_port: Option<u16>,
+    pub start_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub probe_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_ms: Option<u64>,
+    pub total_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub diagnostics: Vec<String>,
+}
+
+fn millis(since: Instant) -> u64 {
+    since.elapsed().as_millis() as u64
+}
+
+// the configured readiness check, a plain connect otherwise. udp has nothing
+// to connect to
+fn test_check(config: &Config) -> Option<ReadinessCheck> {
+    if config.protocol == "udp" {
+        return None;
+    }
+    Some(ReadinessCheck::from_config(config).unwrap_or(ReadinessCheck::Tcp))
+}
+
+fn describe_check(check: Option<&ReadinessCheck>) -> String {
+    match check {
+        None => "none".to_string(),
+        Some(ReadinessCheck::Tcp) => "tcp connect".to_string(),
+        Some(ReadinessCheck::Http { path }) => format!("http GET {}", path),
+        Some(ReadinessCheck::Grpc { service }) if service.is_empty() => "grpc health".to_string(),
+        Some(ReadinessCheck::Grpc { service }) => format!("grpc health {}", service),
+    }
+}
+
+// a copy that leaves no trace: any free loopback port, no hosts entry and no
+// readiness wait, the probe is what the test is about
+fn transient_config(config: &Config) -> Config {
+    let mut config = config.clone();
+    config.local_port = Some(0);
+    config.local_address = Some("127.0.0.1".to_string());
+    config.domain_enabled = Some(false);
+    config.readiness_check = None;
+    config
+}
+
+// starts the forward on a throwaway port, probes it the way its protocol
+// allows and tears it down again, whether or not the probe worked
+pub async fn test_forward(
+    config_id: i64, reason: Option<&str>, http_log_state: Arc<HttpLogState>,
+) -> Result<ForwardTestReport, String> {
+    if running_config(config_id).is_some() || is_start_in_flight(config_id) {
+        return Err(format!(
+            "Config {} is already running, stop it before testing it",
+            config_id
+        ));
+    }
+
+    let config = get_config(config_id).await?;
+    let check = test_check(&config);
+    let transient = transient_config(&config);
+    let mut report = ForwardTestReport {
+        config_id,
+        probe: describe_check(check.as_ref()),
+        ..Default::default()
+    };
+    info!("Testing config {} with a {} probe", config_id, report.probe);
+
+    let started_at = Instant::now();
+    let configs = [transient.clone()];
+    let start = start_config(transient.clone(), http_log_state);
+    let start = start_with_reason(&configs, reason, start).await;
+    report.start_ms = millis(started_at);
+
+    let responses = match start {
+        Ok(responses) => responses,
+        Err(e) => {
+            report.error = Some(format!("Failed to start: {}", e));
+            report.total_ms = millis(started_at);
+            return Ok(report);
+        }
+    };
+    report.local_port = responses.first().map(|response| response.local_port);
+    report.diagnostics.extend(
+        responses
+            .iter()
+            .flat_map(|response| response.stdout.lines())
+            .map(str::to_string),
+    );
+
+    if let (Some(check), Some(local_port)) = (&check, report.local_port) {
+        let addr = probe_address(&transient, local_port);
+        let host = probe_host(&config, addr);
+        let limit = Duration::from_secs(
+            config
+                .readiness_timeout_secs
+                .unwrap_or_else(|| current_settings().readiness_timeout_secs),
+        );
+
+        let probed_at = Instant::now();
+        let probe = match timeout(limit, probe_once(check, addr, &host)).await {
+            Ok(result) => result,
+            Err(_) => Err(format!(
+                "probe did not complete within {}s",
+                limit.as_secs()
+            )),
+        };
+        report.probe_ms = Some(millis(probed_at));
+        if let Err(e) = probe {
+            report.error = Some(format!("Probe failed: {}", e));
+        }
+    } else if check.is_some() {
+        report.error = Some("The start reported no local port to probe".to_string());
+    } else {
+        report
+            .diagnostics
+            .push("Started without probing, UDP forwards have nothing to connect to".to_string());
+    }
+
+    let stopped_at = Instant::now();
+    match stop_config(&transient).await {
+        Ok(response) if response.status != 0 => {
+            report
+                .diagnostics
+                .push(format!("Failed to stop cleanly: {}", response.stderr));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            warn!(
+                "Failed to stop the test forward for config {}: {}",
+                config_id, e
+            );
+            report
+                .diagnostics
+                .push(format!("Failed to stop cleanly: {}", e));
+        }
+    }
+    report.stop_ms = Some(millis(stopped_at));
+    report.total_ms = millis(started_at);
+    report.success = report.error.is_none();
+
+    info!(
+        "Test of config {} {} in {}ms",
+        config_id,
+        if report.success { "passed" } else { "failed" },
+        report.total_ms
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transient_config_and_probe() {
+        let config = Config {
+            local_port: Some(8080),
+            local_address: Some("127.0.0.5".to_string()),
+            domain_enabled: Some(true),
+            alias: Some("api.local".to_string()),
+            readiness_check: Some("http".to_string()),
+            readiness_path: Some("/healthz".to_string()),
+            ..Default::default()
+        };
+        let transient = transient_config(&config);
+        assert_eq!(transient.local_port, Some(0));
+        assert_eq!(transient.local_address.as_deref(), Some("127.0.0.1"));
+        assert_eq!(transient.domain_enabled, Some(false));
+        assert_eq!(transient.alias, config.alias);
+
+        assert_eq!(
+            describe_check(test_check(&config).as_ref()),
+            "http GET /healthz"
+        );
+        assert_eq!(
+            describe_check(test_check(&transient).as_ref()),
+            "tcp connect"
+        );
+
+        let udp = Config {
+            protocol: "udp".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(describe_check(test_check(&udp).as_ref()), "none");
+    }
+}
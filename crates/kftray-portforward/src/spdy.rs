@@ -0,0 +1,349 @@
+use std::io;
+
+use anyhow::Context;
+use flate2::{
+    Compress,
+    Compression,
+    FlushCompress,
+};
+use hyper::header::{
+    CONNECTION,
+    UPGRADE,
+};
+use hyper::{
+    Request,
+    StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use kube::client::Body;
+use kube::Client;
+use log::{
+    debug,
+    error,
+    trace,
+};
+use tokio::io::{
+    AsyncRead,
+    AsyncReadExt,
+    AsyncWriteExt,
+    DuplexStream,
+};
+use tokio::sync::mpsc;
+
+pub const SPDY_PROTOCOL: &str = "SPDY/3.1";
+const PORT_FORWARD_PROTOCOL: &str = "portforward.k8s.io";
+const STREAM_PROTOCOL_HEADER: &str = "X-Stream-Protocol-Version";
+
+const SPDY_VERSION: u16 = 3;
+const SYN_STREAM: u16 = 1;
+const RST_STREAM: u16 = 3;
+const PING: u16 = 6;
+const GOAWAY: u16 = 7;
+const FLAG_FIN: u8 = 0x01;
+
+const ERROR_STREAM_ID: u32 = 1;
+const DATA_STREAM_ID: u32 = 3;
+const MAX_DATA_FRAME: usize = 32 * 1024;
+const DUPLEX_BUFFER: usize = 1024 * 1024;
+const PENDING_FRAMES: usize = 64;
+
+#[derive(Debug, PartialEq)]
+pub enum Frame {
+    Control {
+        frame_type: u16,
+        flags: u8,
+        payload: Vec<u8>,
+    },
+    Data {
+        stream_id: u32,
+        flags: u8,
+        payload: Vec<u8>,
+    },
+}
+
+pub fn control_frame(frame_type: u16, flags: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(8 + payload.len());
+    frame.extend_from_slice(&(0x8000 | SPDY_VERSION).to_be_bytes());
+    frame.extend_from_slice(&frame_type.to_be_bytes());
+    frame.push(flags);
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes()[1..]);
+    frame.extend_from_slice(payload);
+    frame
+}
+
+pub fn data_frame(stream_id: u32, flags: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(8 + payload.len());
+    frame.extend_from_slice(&(stream_id & 0x7FFF_FFFF).to_be_bytes());
+    frame.push(flags);
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes()[1..]);
+    frame.extend_from_slice(payload);
+    frame
+}
+
+// the name/value block before compression, names have to be lowercase
+pub fn header_block(headers: &[(&str, &str)]) -> Vec<u8> {
+    let mut block = (headers.len() as u32).to_be_bytes().to_vec();
+    for (name, value) in headers {
+        block.extend_from_slice(&(name.len() as u32).to_be_bytes());
+        block.extend_from_slice(name.to_lowercase().as_bytes());
+        block.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        block.extend_from_slice(value.as_bytes());
+    }
+    block
+}
+
+pub async fn read_frame(reader: &mut (impl AsyncRead + Unpin)) -> io::Result<Frame> {
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header).await?;
+
+    let flags = header[4];
+    let length = u32::from_be_bytes([0, header[5], header[6], header[7]]) as usize;
+    let mut payload = vec![0u8; length];
+    reader.read_exact(&mut payload).await?;
+
+    if header[0] & 0x80 != 0 {
+        Ok(Frame::Control {
+            frame_type: u16::from_be_bytes([header[2], header[3]]),
+            flags,
+            payload,
+        })
+    } else {
+        Ok(Frame::Data {
+            stream_id: u32::from_be_bytes([header[0], header[1], header[2], header[3]]),
+            flags,
+            payload,
+        })
+    }
+}
+
+// header blocks share one zlib stream per connection. nothing here refers
+// back into the spdy dictionary, so the peer inflates them fine without it
+struct HeaderCompressor(Compress);
+
+impl HeaderCompressor {
+    fn new() -> Self {
+        HeaderCompressor(Compress::new(Compression::default(), true))
+    }
+
+    fn compress(&mut self, block: &[u8]) -> io::Result<Vec<u8>> {
+        let mut output = Vec::with_capacity(block.len() + 64);
+        let before = self.0.total_in();
+        loop {
+            let consumed = (self.0.total_in() - before) as usize;
+            self.0
+                .compress_vec(&block[consumed..], &mut output, FlushCompress::Sync)
+                .map_err(io::Error::other)?;
+            if (self.0.total_in() - before) as usize == block.len()
+                && output.len() < output.capacity()
+            {
+                return Ok(output);
+            }
+            output.reserve(64);
+        }
+    }
+}
+
+fn syn_stream(
+    compressor: &mut HeaderCompressor, stream_id: u32, flags: u8, headers: &[(&str, &str)],
+) -> io::Result<Vec<u8>> {
+    let mut payload = Vec::new();
+    payload.extend_from_slice(&stream_id.to_be_bytes());
+    payload.extend_from_slice(&0u32.to_be_bytes());
+    payload.extend_from_slice(&[0, 0]);
+    payload.extend(compressor.compress(&header_block(headers))?);
+    Ok(control_frame(SYN_STREAM, flags, &payload))
+}
+
+// opens a port-forward stream over SPDY/3.1, the transport kubectl used before
+// websockets. every connection gets its own upgrade with an error stream and a
+// data stream, like kubectl does
+pub async fn spdy_portforward(
+    client: &Client, namespace: &str, pod_name: &str, port: u16,
+) -> anyhow::Result<DuplexStream> {
+    let request = Request::post(format!(
+        "/api/v1/namespaces/{}/pods/{}/portforward",
+        namespace, pod_name
+    ))
+    .header(CONNECTION, "Upgrade")
+    .header(UPGRADE, SPDY_PROTOCOL)
+    .header(STREAM_PROTOCOL_HEADER, PORT_FORWARD_PROTOCOL)
+    .body(Body::empty())?;
+
+    let response = client.send(request).await?;
+    if response.status() != StatusCode::SWITCHING_PROTOCOLS {
+        anyhow::bail!(
+            "SPDY upgrade for pod {} was rejected with {}",
+            pod_name,
+            response.status()
+        );
+    }
+
+    let upgraded = hyper::upgrade::on(response)
+        .await
+        .context("Failed to upgrade the connection to SPDY")?;
+    let (mut upstream_reader, mut upstream_writer) = tokio::io::split(TokioIo::new(upgraded));
+
+    let port = port.to_string();
+    let mut compressor = HeaderCompressor::new();
+    // the error stream is only ever read, so it's half-closed right away
+    let error_stream = syn_stream(
+        &mut compressor,
+        ERROR_STREAM_ID,
+        FLAG_FIN,
+        &[("streamtype", "error"), ("port", &port), ("requestid", "0")],
+    )?;
+    let data_stream = syn_stream(
+        &mut compressor,
+        DATA_STREAM_ID,
+        0,
+        &[("streamtype", "data"), ("port", &port), ("requestid", "0")],
+    )?;
+    upstream_writer.write_all(&error_stream).await?;
+    upstream_writer.write_all(&data_stream).await?;
+    upstream_writer.flush().await?;
+
+    let (local, remote) = tokio::io::duplex(DUPLEX_BUFFER);
+    let (mut remote_reader, mut remote_writer) = tokio::io::split(remote);
+    let (frames_tx, mut frames_rx) = mpsc::channel::<Vec<u8>>(PENDING_FRAMES);
+
+    tokio::spawn(async move {
+        while let Some(frame) = frames_rx.recv().await {
+            if let Err(e) = upstream_writer.write_all(&frame).await {
+                debug!("Failed to write SPDY frame: {}", e);
+                break;
+            }
+        }
+        let _ = upstream_writer.shutdown().await;
+    });
+
+    let outgoing = frames_tx.clone();
+    let client_to_pod = tokio::spawn(async move {
+        let mut buffer = vec![0u8; MAX_DATA_FRAME];
+        loop {
+            match remote_reader.read(&mut buffer).await {
+                Ok(0) | Err(_) => {
+                    let _ = outgoing
+                        .send(data_frame(DATA_STREAM_ID, FLAG_FIN, &[]))
+                        .await;
+                    break;
+                }
+                Ok(n) => {
+                    if outgoing
+                        .send(data_frame(DATA_STREAM_ID, 0, &buffer[..n]))
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    // spdystream, which serves port-forward on the api server and kubelet,
+    // doesn't enforce flow control, so no WINDOW_UPDATE frames are sent
+    tokio::spawn(async move {
+        let mut error_message = Vec::new();
+        loop {
+            match read_frame(&mut upstream_reader).await {
+                Ok(Frame::Data {
+                    stream_id: DATA_STREAM_ID,
+                    flags,
+                    payload,
+                }) => {
+                    if remote_writer.write_all(&payload).await.is_err() {
+                        break;
+                    }
+                    if flags & FLAG_FIN != 0 {
+                        break;
+                    }
+                }
+                Ok(Frame::Data {
+                    stream_id: ERROR_STREAM_ID,
+                    flags,
+                    payload,
+                }) => {
+                    error_message.extend(payload);
+                    if flags & FLAG_FIN != 0 && !error_message.is_empty() {
+                        error!(
+                            "Port forward to {} failed: {}",
+                            port,
+                            String::from_utf8_lossy(&error_message)
+                        );
+                        break;
+                    }
+                }
+                Ok(Frame::Control {
+                    frame_type: PING,
+                    flags,
+                    payload,
+                }) => {
+                    let _ = frames_tx.send(control_frame(PING, flags, &payload)).await;
+                }
+                Ok(Frame::Control {
+                    frame_type: RST_STREAM | GOAWAY,
+                    ..
+                }) => break,
+                Ok(frame) => trace!("Ignoring SPDY frame {:?}", frame),
+                Err(e) => {
+                    debug!("SPDY connection closed: {}", e);
+                    break;
+                }
+            }
+        }
+        let _ = remote_writer.shutdown().await;
+        client_to_pod.abort();
+    });
+
+    Ok(local)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_frame_round_trip() {
+        let data = data_frame(DATA_STREAM_ID, FLAG_FIN, b"hello");
+        assert_eq!(&data[..8], &[0, 0, 0, 3, 1, 0, 0, 5]);
+
+        let ping = control_frame(PING, 0, &7u32.to_be_bytes());
+        assert_eq!(&ping[..4], &[0x80, 3, 0, 6]);
+
+        let frames = [data, ping].concat();
+        let mut reader = frames.as_slice();
+        assert_eq!(
+            read_frame(&mut reader).await.unwrap(),
+            Frame::Data {
+                stream_id: DATA_STREAM_ID,
+                flags: FLAG_FIN,
+                payload: b"hello".to_vec(),
+            }
+        );
+        assert_eq!(
+            read_frame(&mut reader).await.unwrap(),
+            Frame::Control {
+                frame_type: PING,
+                flags: 0,
+                payload: 7u32.to_be_bytes().to_vec(),
+            }
+        );
+        assert!(read_frame(&mut reader).await.is_err());
+    }
+
+    #[test]
+    fn test_header_block() {
+        let block = header_block(&[("Port", "8080")]);
+        assert_eq!(
+            block,
+            [
+                &1u32.to_be_bytes()[..],
+                &4u32.to_be_bytes(),
+                b"port",
+                &4u32.to_be_bytes(),
+                b"8080",
+            ]
+            .concat()
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use kftray_commons::models::config_model::Config;
+use lazy_static::lazy_static;
+use log::{
+    debug,
+    info,
+};
+use serde::Serialize;
+use tokio::io::AsyncReadExt;
+use tokio::process::{
+    Child,
+    Command,
+};
+
+// ssh exits right away when the remote port can't be bound, a mirror that
+// lives this long is taken as up
+const SSH_STARTUP_GRACE: Duration = Duration::from_secs(3);
+const SSH_KEEPALIVE_SECS: u32 = 30;
+
+struct MirrorProcess {
+    destination: String,
+    remote_port: u16,
+    child: Child,
+}
+
+lazy_static! {
+    static ref SSH_MIRRORS: Mutex<HashMap<i64, MirrorProcess>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SshMirror {
+    pub config_id: i64,
+    pub destination: String,
+    pub remote_port: u16,
+    pub alive: bool,
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+fn ssh_destination(config: &Config) -> Option<String> {
+    let host = non_empty(&config.ssh_mirror_host)?;
+    Some(match non_empty(&config.ssh_mirror_user) {
+        Some(user) => format!("{}@{}", user, host),
+        None => host.to_string(),
+    })
+}
+
+// the remote end only listens on the loopback of the remote machine, like
+// any `ssh -R` without GatewayPorts
+pub fn ssh_mirror_args(config: &Config, local_port: u16) -> Option<Vec<String>> {
+    let destination = ssh_destination(config)?;
+    let local_address = match config.local_address.as_deref() {
+        None | Some("0.0.0.0") | Some("") => "127.0.0.1",
+        Some("::") => "::1",
+        Some(address) => address,
+    };
+    let local_address = match local_address.contains(':') {
+        true => format!("[{}]", local_address),
+        false => local_address.to_string(),
+    };
+
+    let mut args = vec![
+        "-N".to_string(),
+        "-o".to_string(),
+        "ExitOnForwardFailure=yes".to_string(),
+        "-o".to_string(),
+        "BatchMode=yes".to_string(),
+        "-o".to_string(),
+        format!("ServerAliveInterval={}", SSH_KEEPALIVE_SECS),
+    ];
+    if let Some(key) = non_empty(&config.ssh_mirror_key) {
+        args.extend(["-i".to_string(), key.to_string()]);
+    }
+    args.push("-R".to_string());
+    args.push(format!(
+        "{}:{}:{}",
+        config.ssh_mirror_port.unwrap_or(local_port),
+        local_address,
+        local_port
+    ));
+    args.push(destination);
+    Some(args)
+}
+
+async fn startup_error(mut child: Child) -> String {
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        let _ = pipe.read_to_string(&mut stderr).await;
+    }
+    match stderr.trim() {
+        "" => "ssh exited before the remote port was forwarded".to_string(),
+        stderr => stderr.to_string(),
+    }
+}
+
+// batch mode keeps ssh from prompting, so the key has to be usable without a
+// passphrase or through an agent
+pub async fn start_ssh_mirror(config: &Config, local_port: u16) -> Result<(), String> {
+    let Some(args) = ssh_mirror_args(config, local_port) else {
+        return Ok(());
+    };
+    let config_id = config.id.unwrap_or_default();
+    stop_ssh_mirror(Some(config_id));
+
+    let mut child = Command::new("ssh")
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to run ssh: {}", e))?;
+
+    if let Ok(status) = tokio::time::timeout(SSH_STARTUP_GRACE, child.wait()).await {
+        let status = status.map_err(|e| e.to_string())?;
+        debug!("ssh mirror of config {} exited with {}", config_id, status);
+        return Err(startup_error(child).await);
+    }
+
+    let destination = ssh_destination(config).unwrap_or_default();
+    let remote_port = config.ssh_mirror_port.unwrap_or(local_port);
+    info!(
+        "Mirroring config {} onto port {} of {}",
+        config_id, remote_port, destination
+    );
+    SSH_MIRRORS.lock().unwrap().insert(
+        config_id,
+        MirrorProcess {
+            destination,
+            remote_port,
+            child,
+        },
+    );
+    Ok(())
+}
+
+pub fn stop_ssh_mirror(config_id: Option<i64>) {
+    let stopped: Vec<MirrorProcess> = {
+        let mut mirrors = SSH_MIRRORS.lock().unwrap();
+        match config_id {
+            Some(config_id) => mirrors.remove(&config_id).into_iter().collect(),
+            None => mirrors.drain().map(|(_, mirror)| mirror).collect(),
+        }
+    };
+
+    for mut mirror in stopped {
+        if let Err(e) = mirror.child.start_kill() {
+            debug!(
+                "Failed to stop the ssh mirror to {}: {}",
+                mirror.destination, e
+            );
+        }
+    }
+}
+
+pub fn get_ssh_mirrors() -> Vec<SshMirror> {
+    let mut mirrors: Vec<SshMirror> = SSH_MIRRORS
+        .lock()
+        .unwrap()
+        .iter_mut()
+        .map(|(config_id, mirror)| SshMirror {
+            config_id: *config_id,
+            destination: mirror.destination.clone(),
+            remote_port: mirror.remote_port,
+            alive: matches!(mirror.child.try_wait(), Ok(None)),
+        })
+        .collect();
+    mirrors.sort_by_key(|mirror| mirror.config_id);
+    mirrors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_mirror_args() {
+        let mut config = Config {
+            ssh_mirror_host: Some("devbox.internal".to_string()),
+            ssh_mirror_user: Some("dev".to_string()),
+            ssh_mirror_key: Some("/home/dev/.ssh/id_ed25519".to_string()),
+            ..Default::default()
+        };
+        let args = ssh_mirror_args(&config, 5432).unwrap();
+        assert_eq!(
+            args[args.len() - 4..],
+            [
+                "/home/dev/.ssh/id_ed25519",
+                "-R",
+                "5432:127.0.0.1:5432",
+                "dev@devbox.internal"
+            ]
+        );
+
+        config.ssh_mirror_user = None;
+        config.ssh_mirror_key = Some(" ".to_string());
+        config.ssh_mirror_port = Some(15432);
+        config.local_address = Some("::1".to_string());
+        let args = ssh_mirror_args(&config, 5432).unwrap();
+        assert!(!args.contains(&"-i".to_string()));
+        assert_eq!(
+            args[args.len() - 2..],
+            ["15432:[::1]:5432", "devbox.internal"]
+        );
+
+        config.ssh_mirror_host = Some(String::new());
+        assert!(ssh_mirror_args(&config, 5432).is_none());
+    }
+}
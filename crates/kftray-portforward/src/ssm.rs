@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+
+use hyper::Uri;
+use kftray_commons::models::config_model::Config;
+use kube::config::Kubeconfig;
+use lazy_static::lazy_static;
+use log::{
+    info,
+    warn,
+};
+use tokio::io::AsyncReadExt;
+use tokio::net::{
+    TcpListener,
+    TcpStream,
+};
+use tokio::process::{
+    Child,
+    Command,
+};
+use tokio::sync::Mutex;
+
+const SSM_DOCUMENT: &str = "AWS-StartPortForwardingSessionToRemoteHost";
+const SSM_READY_TIMEOUT: Duration = Duration::from_secs(30);
+const SSM_READY_POLL: Duration = Duration::from_millis(250);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SsmTarget {
+    pub instance_id: String,
+    pub profile: Option<String>,
+    pub region: Option<String>,
+}
+
+struct SsmTunnel {
+    local_port: u16,
+    child: Child,
+}
+
+type TunnelKey = (SsmTarget, String, u16);
+
+lazy_static! {
+    static ref SSM_TUNNELS: Mutex<HashMap<TunnelKey, SsmTunnel>> = Mutex::new(HashMap::new());
+}
+
+pub fn ssm_target(config: &Config) -> Option<SsmTarget> {
+    let instance_id = config.ssm_instance_id.clone().filter(|id| !id.is_empty())?;
+    Some(SsmTarget {
+        instance_id,
+        profile: config.ssm_profile.clone().filter(|p| !p.is_empty()),
+        region: config.ssm_region.clone().filter(|r| !r.is_empty()),
+    })
+}
+
+pub fn api_server_address(server: &str) -> Result<(String, u16), String> {
+    let uri = server
+        .parse::<Uri>()
+        .map_err(|e| format!("Invalid API server address {}: {}", server, e))?;
+    let host = uri
+        .host()
+        .ok_or_else(|| format!("API server address {} has no host", server))?
+        .trim_matches(|c| c == '[' || c == ']')
+        .to_string();
+    let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+        Some("http") => 80,
+        _ => 443,
+    });
+    Ok((host, port))
+}
+
+fn start_session_args(target: &SsmTarget, host: &str, port: u16, local_port: u16) -> Vec<String> {
+    let mut args = vec![
+        "ssm".to_string(),
+        "start-session".to_string(),
+        "--target".to_string(),
+        target.instance_id.clone(),
+        "--document-name".to_string(),
+        SSM_DOCUMENT.to_string(),
+        "--parameters".to_string(),
+        format!(
+            "host={},portNumber={},localPortNumber={}",
+            host, port, local_port
+        ),
+    ];
+    if let Some(profile) = &target.profile {
+        args.extend(["--profile".to_string(), profile.clone()]);
+    }
+    if let Some(region) = &target.region {
+        args.extend(["--region".to_string(), region.clone()]);
+    }
+    args
+}
+
+async fn free_local_port() -> Result<u16, String> {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|e| e.to_string())?;
+    listener
+        .local_addr()
+        .map(|addr| addr.port())
+        .map_err(|e| e.to_string())
+}
+
+async fn wait_for_tunnel(child: &mut Child, local_port: u16) -> Result<(), String> {
+    let deadline = tokio::time::Instant::now() + SSM_READY_TIMEOUT;
+    loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            let mut stderr = String::new();
+            if let Some(mut pipe) = child.stderr.take() {
+                let _ = pipe.read_to_string(&mut stderr).await;
+            }
+            return Err(format!(
+                "aws ssm start-session exited with {}: {}",
+                status,
+                stderr.trim()
+            ));
+        }
+
+        if TcpStream::connect(("127.0.0.1", local_port)).await.is_ok() {
+            return Ok(());
+        }
+
+        if tokio::time::Instant::now() >= deadline {
+            return Err(format!(
+                "SSM tunnel did not open within {}s",
+                SSM_READY_TIMEOUT.as_secs()
+            ));
+        }
+        tokio::time::sleep(SSM_READY_POLL).await;
+    }
+}
+
+// one ssm session per bastion and api server, shared by every config behind
+// it and restarted when the session has died
+pub async fn ensure_ssm_tunnel(target: &SsmTarget, host: &str, port: u16) -> Result<u16, String> {
+    let mut tunnels = SSM_TUNNELS.lock().await;
+    let key = (target.clone(), host.to_string(), port);
+
+    if let Some(tunnel) = tunnels.get_mut(&key) {
+        if matches!(tunnel.child.try_wait(), Ok(None)) {
+            return Ok(tunnel.local_port);
+        }
+        warn!(
+            "SSM tunnel through {} to {}:{} has closed, reopening it",
+            target.instance_id, host, port
+        );
+        tunnels.remove(&key);
+    }
+
+    let local_port = free_local_port().await?;
+    let mut child = Command::new("aws")
+        .args(start_session_args(target, host, port, local_port))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to run the aws cli: {}", e))?;
+
+    wait_for_tunnel(&mut child, local_port).await?;
+
+    info!(
+        "Opened SSM tunnel through {} to {}:{} on local port {}",
+        target.instance_id, host, port, local_port
+    );
+    tunnels.insert(key, SsmTunnel { local_port, child });
+    Ok(local_port)
+}
+
+// points the context's cluster at a local ssm tunnel, keeping the original
+// host as the tls server name so the api server certificate still verifies
+pub async fn route_through_ssm(
+    kubeconfig: &Kubeconfig, context_name: &str, target: &SsmTarget,
+) -> Result<Kubeconfig, String> {
+    let mut kubeconfig = kubeconfig.clone();
+    let cluster_name = kubeconfig
+        .contexts
+        .iter()
+        .find(|context| context.name == context_name)
+        .and_then(|context| context.context.as_ref())
+        .map(|context| context.cluster.clone())
+        .ok_or_else(|| format!("Context {} not found", context_name))?;
+    let cluster = kubeconfig
+        .clusters
+        .iter_mut()
+        .find(|cluster| cluster.name == cluster_name)
+        .and_then(|cluster| cluster.cluster.as_mut())
+        .ok_or_else(|| format!("Cluster {} not found", cluster_name))?;
+    let server = cluster
+        .server
+        .clone()
+        .ok_or_else(|| format!("Cluster {} has no server", cluster_name))?;
+
+    let (host, port) = api_server_address(&server)?;
+    let local_port = ensure_ssm_tunnel(target, &host, port).await?;
+
+    cluster.server = Some(format!("https://127.0.0.1:{}", local_port));
+    if cluster.tls_server_name.is_none() {
+        cluster.tls_server_name = Some(host);
+    }
+    Ok(kubeconfig)
+}
+
+pub async fn close_ssm_tunnels() {
+    let tunnels: Vec<SsmTunnel> = SSM_TUNNELS.lock().await.drain().map(|(_, t)| t).collect();
+    for mut tunnel in tunnels {
+        if let Err(e) = tunnel.child.kill().await {
+            warn!("Failed to close SSM tunnel: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_start_session_args() {
+        assert_eq!(
+            api_server_address("https://abc.gr7.eu-west-1.eks.amazonaws.com").unwrap(),
+            ("abc.gr7.eu-west-1.eks.amazonaws.com".to_string(), 443)
+        );
+        assert_eq!(
+            api_server_address("https://10.0.12.4:6443").unwrap(),
+            ("10.0.12.4".to_string(), 6443)
+        );
+
+        let target = SsmTarget {
+            instance_id: "i-0abc".to_string(),
+            profile: Some("prod".to_string()),
+            region: None,
+        };
+        let args = start_session_args(&target, "10.0.12.4", 6443, 50123);
+        assert_eq!(
+            args[7],
+            "host=10.0.12.4,portNumber=6443,localPortNumber=50123"
+        );
+        assert_eq!(args[8..], ["--profile", "prod"]);
+    }
+}
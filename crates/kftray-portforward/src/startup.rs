@@ -0,0 +1,249 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+use kftray_commons::models::config_model::Config;
+use kftray_commons::utils::settings::current_settings;
+use log::{
+    info,
+    warn,
+};
+use serde::Serialize;
+
+use crate::audit::start_with_reason;
+use crate::cluster_probe::probe_context;
+use crate::headless::start_config;
+use crate::models::kube::HttpLogState;
+use crate::progress::{
+    Progress,
+    ProgressKind,
+};
+
+type ClusterKey = (Option<String>, String);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StartupFailure {
+    pub config_id: i64,
+    pub error: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct StartupReport {
+    pub started: Vec<i64>,
+    pub failed: Vec<StartupFailure>,
+    // started once their cluster became reachable on a later attempt
+    pub retried: Vec<i64>,
+}
+
+impl StartupReport {
+    fn fail(&mut self, config_id: i64, error: String) {
+        warn!("Auto-start of config {} failed: {}", config_id, error);
+        self.failed.push(StartupFailure { config_id, error });
+    }
+}
+
+// the dependencies of the config that are part of the same batch, the others
+// are expected to be running already
+fn batch_dependencies(config: &Config, ids: &HashSet<i64>) -> Vec<i64> {
+    config
+        .depends_on
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|id| ids.contains(id) && Some(*id) != config.id)
+        .collect()
+}
+
+// orders the configs so each comes after the ones it depends on, keeping the
+// given order otherwise. configs stuck in a dependency cycle are returned
+// separately
+pub fn startup_order(configs: Vec<Config>) -> (Vec<Config>, Vec<Config>) {
+    let ids: HashSet<i64> = configs.iter().filter_map(|config| config.id).collect();
+    let mut placed: HashSet<i64> = HashSet::new();
+    let mut ordered = Vec::with_capacity(configs.len());
+    let mut remaining = configs;
+
+    while !remaining.is_empty() {
+        let (ready, blocked): (Vec<Config>, Vec<Config>) =
+            remaining.into_iter().partition(|config| {
+                batch_dependencies(config, &ids)
+                    .iter()
+                    .all(|id| placed.contains(id))
+            });
+        if ready.is_empty() {
+            return (ordered, blocked);
+        }
+        placed.extend(ready.iter().filter_map(|config| config.id));
+        ordered.extend(ready);
+        remaining = blocked;
+    }
+    (ordered, Vec::new())
+}
+
+// brings up the forwards kftray starts on its own at launch. dependencies go
+// first, first connections to different clusters are staggered and configs
+// whose cluster isn't reachable yet, e.g. while the vpn still connects, are
+// retried for a while before they count as failed
+pub async fn auto_start_configs(
+    configs: Vec<Config>, reason: Option<&str>, http_log_state: Arc<HttpLogState>,
+) -> StartupReport {
+    let settings = current_settings();
+    let stagger = Duration::from_millis(settings.startup_stagger_ms);
+    let retry_delay = Duration::from_secs(settings.startup_retry_delay_secs);
+
+    let ids: HashSet<i64> = configs.iter().filter_map(|config| config.id).collect();
+    let progress = Progress::begin(
+        ProgressKind::StartAll,
+        configs.len(),
+        format!("Auto-starting {} config(s)", configs.len()),
+    );
+    let mut report = StartupReport::default();
+
+    let (mut pending, cyclic) = startup_order(configs);
+    for config in cyclic {
+        let config_id = config.id.unwrap_or_default();
+        report.fail(config_id, "Its dependencies form a cycle".to_string());
+        progress.advance(format!("Skipped config {}", config_id));
+    }
+
+    let mut started: HashSet<i64> = HashSet::new();
+    let mut failed: HashSet<i64> = report.failed.iter().map(|f| f.config_id).collect();
+    let mut last_errors: HashMap<i64, String> = HashMap::new();
+
+    for attempt in 0..=settings.startup_retry_attempts {
+        if attempt > 0 {
+            info!(
+                "Retrying {} config(s) in {}s, attempt {} of {}",
+                pending.len(),
+                retry_delay.as_secs(),
+                attempt,
+                settings.startup_retry_attempts
+            );
+            tokio::time::sleep(retry_delay).await;
+        }
+
+        let mut reachable: HashMap<ClusterKey, Result<(), String>> = HashMap::new();
+        let mut deferred = Vec::new();
+        for config in pending {
+            let config_id = config.id.unwrap_or_default();
+            let dependencies = batch_dependencies(&config, &ids);
+            if let Some(dependency) = dependencies.iter().find(|id| failed.contains(id)) {
+                report.fail(
+                    config_id,
+                    format!("Dependency {} failed to start", dependency),
+                );
+                failed.insert(config_id);
+                progress.advance(format!("Skipped config {}", config_id));
+                continue;
+            }
+            if let Some(dependency) = dependencies.iter().find(|id| !started.contains(id)) {
+                last_errors.insert(
+                    config_id,
+                    format!("Dependency {} never started", dependency),
+                );
+                deferred.push(config);
+                continue;
+            }
+
+            let cluster: ClusterKey = (config.kubeconfig.clone(), config.context.clone());
+            if !reachable.contains_key(&cluster) {
+                if !reachable.is_empty() {
+                    tokio::time::sleep(stagger).await;
+                }
+                let probe = probe_context(&config.context, config.kubeconfig.clone()).await;
+                let status = if probe.reachable {
+                    Ok(())
+                } else {
+                    Err(probe
+                        .error
+                        .unwrap_or_else(|| "Cluster is not reachable".to_string()))
+                };
+                reachable.insert(cluster.clone(), status);
+            }
+            if let Some(Err(e)) = reachable.get(&cluster) {
+                last_errors.insert(config_id, format!("Context {}: {}", config.context, e));
+                deferred.push(config);
+                continue;
+            }
+
+            let configs = [config.clone()];
+            let start = start_config(config, http_log_state.clone());
+            match start_with_reason(&configs, reason, start).await {
+                Ok(_) => {
+                    started.insert(config_id);
+                    report.started.push(config_id);
+                    if attempt > 0 {
+                        report.retried.push(config_id);
+                    }
+                    progress.advance(format!("Started config {}", config_id));
+                }
+                Err(e) => {
+                    report.fail(config_id, e);
+                    failed.insert(config_id);
+                    progress.advance(format!("Failed to start config {}", config_id));
+                }
+            }
+        }
+
+        pending = deferred;
+        if pending.is_empty() {
+            break;
+        }
+    }
+
+    for config in pending {
+        let config_id = config.id.unwrap_or_default();
+        let error = last_errors
+            .remove(&config_id)
+            .unwrap_or_else(|| "Gave up waiting for the cluster".to_string());
+        report.fail(config_id, error);
+        progress.advance(format!("Gave up on config {}", config_id));
+    }
+
+    info!(
+        "Auto-start finished: {} started ({} after retrying), {} failed",
+        report.started.len(),
+        report.retried.len(),
+        report.failed.len()
+    );
+    progress.finish(format!(
+        "Started {} of {} config(s)",
+        report.started.len(),
+        report.started.len() + report.failed.len()
+    ));
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(id: i64, depends_on: &[i64]) -> Config {
+        Config {
+            id: Some(id),
+            depends_on: Some(depends_on.to_vec()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_startup_order() {
+        let (ordered, cyclic) = startup_order(vec![
+            config(1, &[3]),
+            config(2, &[]),
+            config(3, &[2, 99]),
+            config(4, &[4]),
+        ]);
+        let ids: Vec<i64> = ordered.iter().filter_map(|config| config.id).collect();
+        assert_eq!(ids, vec![2, 4, 3, 1]);
+        assert!(cyclic.is_empty());
+
+        let (ordered, cyclic) =
+            startup_order(vec![config(1, &[2]), config(2, &[1]), config(3, &[])]);
+        assert_eq!(ordered.len(), 1);
+        assert_eq!(cyclic.len(), 2);
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use kftray_commons::config::get_config;
+use kftray_commons::utils::access_audit::{
+    record_access_audit,
+    require_access_reason,
+};
+use kftray_commons::utils::template::resolve_config_templates;
+use lazy_static::lazy_static;
+use log::{
+    error,
+    info,
+};
+use serde::Serialize;
+
+use crate::circuit_breaker::reset_circuit;
+use crate::client::KubeTuning;
+use crate::models::kube::PortForward;
+use crate::pending_start::{
+    missing_target_message,
+    target_exists,
+};
+use crate::pod_finder::{
+    config_target,
+    is_proxy_config,
+};
+use crate::reload::running_config;
+
+struct Switch {
+    target_config_id: i64,
+    forward: PortForward,
+}
+
+lazy_static! {
+    // keyed by the config that owns the listener
+    static ref SWITCHES: Mutex<HashMap<i64, Switch>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ForwardSwitch {
+    pub config_id: i64,
+    pub target_config_id: i64,
+}
+
+// the upstream new connections of a listener go to, open connections keep
+// the target they were made with
+pub(crate) fn switched_forward(forward: &PortForward) -> Option<PortForward> {
+    SWITCHES
+        .lock()
+        .unwrap()
+        .get(&forward.config_id)
+        .map(|switch| forward.retargeted(&switch.forward))
+}
+
+// repoints the local port of a running config at the target of another
+// config, switching to the config itself points it back. a target in a
+// production context needs a reason, audited like a start
+pub async fn switch_forward_target(
+    config_id: i64, target_config_id: i64, reason: Option<&str>,
+) -> Result<ForwardSwitch, String> {
+    let listener =
+        running_config(config_id).ok_or_else(|| format!("Config {} isn't running", config_id))?;
+    if is_proxy_config(&listener) || !listener.protocol.eq_ignore_ascii_case("tcp") {
+        return Err(format!(
+            "Config {} isn't a TCP forward to a service or pod",
+            config_id
+        ));
+    }
+
+    if target_config_id == config_id {
+        clear_forward_switch(Some(config_id));
+        reset_circuit(Some(config_id));
+        info!("Config {} forwards to its own target again", config_id);
+        return Ok(ForwardSwitch {
+            config_id,
+            target_config_id,
+        });
+    }
+
+    let target = resolve_config_templates(get_config(target_config_id).await?).await?;
+    if is_proxy_config(&target) {
+        return Err(format!(
+            "Config {} forwards through a proxy pod and can't be switched to",
+            target_config_id
+        ));
+    }
+    let audit = require_access_reason(std::slice::from_ref(&target), reason).await?;
+
+    let forward = PortForward::new(
+        config_target(&target),
+        None,
+        None,
+        Some(target.context.clone()),
+        target.kubeconfig.clone(),
+        config_id,
+        target.workload_type.clone().unwrap_or_default(),
+        KubeTuning::for_config(&target),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    if target_exists(&forward.pod_api, &forward.svc_api, &target).await == Some(false) {
+        return Err(missing_target_message(&target));
+    }
+
+    SWITCHES.lock().unwrap().insert(
+        config_id,
+        Switch {
+            target_config_id,
+            forward,
+        },
+    );
+    // failures of the old target say nothing about the new one
+    reset_circuit(Some(config_id));
+    if let Err(e) = record_access_audit(&audit).await {
+        error!("Failed to record access audit: {}", e);
+    }
+    info!(
+        "Config {} forwards to the target of config {}",
+        config_id, target_config_id
+    );
+    Ok(ForwardSwitch {
+        config_id,
+        target_config_id,
+    })
+}
+
+pub(crate) fn clear_forward_switch(config_id: Option<i64>) {
+    let mut switches = SWITCHES.lock().unwrap();
+    match config_id {
+        Some(config_id) => {
+            switches.remove(&config_id);
+        }
+        None => switches.clear(),
+    }
+}
+
+pub fn get_forward_switches() -> Vec<ForwardSwitch> {
+    let mut switches: Vec<ForwardSwitch> = SWITCHES
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(config_id, switch)| ForwardSwitch {
+            config_id: *config_id,
+            target_config_id: switch.target_config_id,
+        })
+        .collect();
+    switches.sort_by_key(|switch| switch.config_id);
+    switches
+}
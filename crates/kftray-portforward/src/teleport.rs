@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use kube::config::Kubeconfig;
+use lazy_static::lazy_static;
+use log::{
+    info,
+    warn,
+};
+use serde::Serialize;
+use tokio::process::Command;
+
+const TSH_REFRESH_TIMEOUT: Duration = Duration::from_secs(30);
+// sso logins wait on the browser
+const TSH_LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+// gui apps don't get the shell's PATH on macOS
+const TSH_LOCATIONS: [&str; 4] = [
+    "/usr/local/bin/tsh",
+    "/opt/homebrew/bin/tsh",
+    "/Applications/tsh.app/Contents/MacOS/tsh",
+    "C:\\Program Files\\Teleport\\tsh.exe",
+];
+
+const AUTH_ERROR_MARKERS: [&str; 8] = [
+    "unauthorized",
+    "401",
+    "certificate has expired",
+    "expired certificate",
+    "not logged in",
+    "tsh login",
+    "exec auth",
+    "auth exec",
+];
+
+lazy_static! {
+    static ref LOGIN_REQUIRED: Mutex<HashMap<String, TeleportLogin>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TeleportCluster {
+    pub kube_cluster: Option<String>,
+    pub teleport_cluster: Option<String>,
+    pub proxy: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TeleportLogin {
+    pub context: String,
+    pub proxy: Option<String>,
+    pub command: String,
+}
+
+fn is_tsh(command: &str) -> bool {
+    Path::new(command)
+        .file_stem()
+        .is_some_and(|stem| stem.eq_ignore_ascii_case("tsh"))
+}
+
+fn flag_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter().enumerate().find_map(|(index, arg)| {
+        if let Some(value) = arg.strip_prefix(&format!("{}=", flag)) {
+            Some(value.to_string())
+        } else if arg == flag {
+            args.get(index + 1).cloned()
+        } else {
+            None
+        }
+    })
+}
+
+// tsh writes contexts whose user runs `tsh kube credentials` as an exec plugin
+pub fn teleport_cluster(kubeconfig: &Kubeconfig, context_name: &str) -> Option<TeleportCluster> {
+    let user = kubeconfig
+        .contexts
+        .iter()
+        .find(|context| context.name == context_name)?
+        .context
+        .as_ref()?
+        .user
+        .clone();
+    let exec = kubeconfig
+        .auth_infos
+        .iter()
+        .find(|auth_info| auth_info.name == user)?
+        .auth_info
+        .as_ref()?
+        .exec
+        .as_ref()?;
+
+    if !is_tsh(exec.command.as_deref()?) {
+        return None;
+    }
+
+    let args = exec.args.clone().unwrap_or_default();
+    Some(TeleportCluster {
+        kube_cluster: flag_value(&args, "--kube-cluster"),
+        teleport_cluster: flag_value(&args, "--teleport-cluster"),
+        proxy: flag_value(&args, "--proxy"),
+    })
+}
+
+fn find_tsh() -> Option<PathBuf> {
+    let on_path = std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths)
+            .flat_map(|dir| [dir.join("tsh"), dir.join("tsh.exe")])
+            .find(|path| path.is_file())
+    });
+    on_path.or_else(|| {
+        TSH_LOCATIONS
+            .iter()
+            .map(PathBuf::from)
+            .find(|path| path.is_file())
+    })
+}
+
+// points tsh exec plugins at an absolute path when tsh isn't on PATH, so the
+// plugin still runs when kftray is started outside a login shell
+pub fn resolve_tsh_commands(kubeconfig: &mut Kubeconfig) {
+    let mut tsh = None;
+    for auth_info in &mut kubeconfig.auth_infos {
+        let Some(exec) = auth_info
+            .auth_info
+            .as_mut()
+            .and_then(|auth_info| auth_info.exec.as_mut())
+        else {
+            continue;
+        };
+        let Some(command) = exec.command.as_deref() else {
+            continue;
+        };
+        if !is_tsh(command) || Path::new(command).is_absolute() {
+            continue;
+        }
+
+        match tsh.get_or_insert_with(find_tsh) {
+            Some(path) => exec.command = Some(path.to_string_lossy().into_owned()),
+            None => warn!("tsh is not installed, Teleport contexts will fail to authenticate"),
+        }
+    }
+}
+
+pub fn is_auth_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    AUTH_ERROR_MARKERS
+        .iter()
+        .any(|marker| message.contains(marker))
+}
+
+pub fn login_command(cluster: &TeleportCluster) -> String {
+    match &cluster.proxy {
+        Some(proxy) => format!("tsh login --proxy={}", proxy),
+        None => "tsh login".to_string(),
+    }
+}
+
+async fn run_tsh(args: &[String], timeout: Duration) -> Result<(), String> {
+    let tsh = find_tsh().ok_or_else(|| "tsh is not installed".to_string())?;
+    let child = Command::new(tsh)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| format!("tsh {} timed out", args.join(" ")))?
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+// asks tsh for fresh kube certs, which only works while the teleport session
+// itself is still valid
+pub async fn refresh_teleport_credentials(cluster: &TeleportCluster) -> Result<(), String> {
+    let mut args = vec!["kube".to_string(), "login".to_string()];
+    args.extend(cluster.kube_cluster.clone());
+    args.extend(
+        cluster
+            .proxy
+            .iter()
+            .map(|proxy| format!("--proxy={}", proxy)),
+    );
+    args.extend(
+        cluster
+            .teleport_cluster
+            .iter()
+            .map(|teleport_cluster| format!("--cluster={}", teleport_cluster)),
+    );
+    run_tsh(&args, TSH_REFRESH_TIMEOUT).await
+}
+
+pub fn require_login(context: &str, cluster: &TeleportCluster) -> String {
+    let login = TeleportLogin {
+        context: context.to_string(),
+        proxy: cluster.proxy.clone(),
+        command: login_command(cluster),
+    };
+    let message = format!(
+        "Teleport session for context {} has expired, please run `{}`",
+        context, login.command
+    );
+    LOGIN_REQUIRED
+        .lock()
+        .unwrap()
+        .insert(context.to_string(), login);
+    message
+}
+
+pub fn clear_login_required(context: &str) {
+    LOGIN_REQUIRED.lock().unwrap().remove(context);
+}
+
+pub fn get_teleport_logins() -> Vec<TeleportLogin> {
+    let mut logins: Vec<_> = LOGIN_REQUIRED.lock().unwrap().values().cloned().collect();
+    logins.sort_by(|a, b| a.context.cmp(&b.context));
+    logins
+}
+
+// runs `tsh login` for a context waiting on one, sso opens the browser
+pub async fn teleport_login(context: &str) -> Result<(), String> {
+    let login = LOGIN_REQUIRED
+        .lock()
+        .unwrap()
+        .get(context)
+        .cloned()
+        .ok_or_else(|| format!("Context {} is not waiting on a Teleport login", context))?;
+
+    let mut args = vec!["login".to_string()];
+    args.extend(login.proxy.iter().map(|proxy| format!("--proxy={}", proxy)));
+    run_tsh(&args, TSH_LOGIN_TIMEOUT).await?;
+
+    info!("Logged in to Teleport for context {}", context);
+    clear_login_required(context);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use kube::config::{
+        AuthInfo,
+        Context,
+        ExecConfig,
+        NamedAuthInfo,
+        NamedContext,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_teleport_cluster() {
+        let kubeconfig = Kubeconfig {
+            contexts: vec![NamedContext {
+                name: "teleport.example.com-prod".to_string(),
+                context: Some(Context {
+                    cluster: "teleport.example.com".to_string(),
+                    user: "teleport.example.com-prod".to_string(),
+                    ..Default::default()
+                }),
+            }],
+            auth_infos: vec![NamedAuthInfo {
+                name: "teleport.example.com-prod".to_string(),
+                auth_info: Some(AuthInfo {
+                    exec: Some(ExecConfig {
+                        api_version: None,
+                        command: Some("/usr/local/bin/tsh".to_string()),
+                        args: Some(
+                            [
+                                "kube",
+                                "credentials",
+                                "--kube-cluster=prod",
+                                "--teleport-cluster",
+                                "teleport.example.com",
+                                "--proxy=teleport.example.com:443",
+                            ]
+                            .map(String::from)
+                            .to_vec(),
+                        ),
+                        env: None,
+                        drop_env: None,
+                        interactive_mode: None,
+                        provide_cluster_info: false,
+                        cluster: None,
+                    }),
+                    ..Default::default()
+                }),
+            }],
+            ..Default::default()
+        };
+
+        let cluster = teleport_cluster(&kubeconfig, "teleport.example.com-prod").unwrap();
+        assert_eq!(cluster.kube_cluster.as_deref(), Some("prod"));
+        assert_eq!(
+            cluster.teleport_cluster.as_deref(),
+            Some("teleport.example.com")
+        );
+        assert_eq!(
+            login_command(&cluster),
+            "tsh login --proxy=teleport.example.com:443"
+        );
+        assert!(teleport_cluster(&kubeconfig, "other").is_none());
+
+        assert!(is_auth_error(
+            "ApiError: Unauthorized (ErrorResponse { code: 401 })"
+        ));
+        assert!(is_auth_error("ERROR: not logged in, please run tsh login"));
+        assert!(!is_auth_error("connection refused"));
+    }
+}
@@ -0,0 +1,106 @@
+use tokio::io::{
+    AsyncReadExt,
+    AsyncWriteExt,
+};
+use tokio::net::{
+    TcpListener,
+    TcpStream,
+};
+
+use crate::port_forward::CHILD_PROCESSES;
+
+pub(crate) const MOCK_CONTEXT: &str = "mock";
+
+const VERSION: &str = r#"{"major":"1","minor":"31","gitVersion":"v1.31.0","gitCommit":"","gitTreeState":"clean","buildDate":"","goVersion":"","compiler":"gc","platform":"linux/amd64"}"#;
+const NOT_FOUND: &str =
+    r#"{"kind":"Status","apiVersion":"v1","status":"Failure","reason":"NotFound","code":404}"#;
+
+// a stand-in api server for start tests. it answers the version probe the
+// client makes on connect and 404s the rest, which the start treats as a
+// target it can't check yet and goes ahead with. returns the kubeconfig
+// that points at it
+pub(crate) async fn mock_cluster() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(serve(stream));
+        }
+    });
+
+    let kubeconfig = std::env::temp_dir().join(format!(
+        "kftray-mock-cluster-{}-{}.yaml",
+        std::process::id(),
+        addr.port()
+    ));
+    std::fs::write(
+        &kubeconfig,
+        format!(
+            "apiVersion: v1
+kind: Config
+clusters:
+- name: {context}
+  cluster:
+    server: http://{addr}
+users:
+- name: {context}
+  user: {{}}
+contexts:
+- name: {context}
+  context:
+    cluster: {context}
+    user: {context}
+current-context: {context}
+",
+            context = MOCK_CONTEXT,
+            addr = addr
+        ),
+    )
+    .unwrap();
+    kubeconfig.to_string_lossy().to_string()
+}
+
+// drops the listener a start test left behind. the stop commands look the
+// config up in the database, which tests don't have
+pub(crate) fn drop_forward(config_id: i64) {
+    let prefix = format!("{}_", config_id);
+    CHILD_PROCESSES.lock().unwrap().retain(|key, handle| {
+        let keep = !key.starts_with(&prefix);
+        if !keep {
+            handle.abort();
+        }
+        keep
+    });
+}
+
+async fn serve(mut stream: TcpStream) {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") else {
+            match stream.read(&mut chunk).await {
+                Ok(0) | Err(_) => return,
+                Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+            }
+            continue;
+        };
+        let head = String::from_utf8_lossy(&buffer[..end]).to_string();
+        buffer.drain(..end + 4);
+
+        let path = head.split_whitespace().nth(1).unwrap_or_default();
+        let (status, body) = if path == "/version" {
+            ("200 OK", VERSION)
+        } else {
+            ("404 Not Found", NOT_FOUND)
+        };
+        let response = format!(
+            "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        if stream.write_all(response.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,631 @@
+use std::collections::{
+    BTreeMap,
+    HashSet,
+};
+
+use k8s_openapi::api::core::v1::{
+    Pod,
+    Service,
+    ServicePort,
+};
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::tool_import_model::ToolImport;
+use kftray_commons::utils::settings::current_settings;
+use kftray_commons::utils::tool_import::{
+    k9s_forwards,
+    kubefwd_address,
+    kubefwd_alias,
+    lens_forwards,
+    parse_kubefwd_command,
+    read_lens_store,
+    KubectlForward,
+    K9S_PORT_FORWARD_ANNOTATION,
+    K9S_TOOL,
+    KUBECTL_TOOL,
+    KUBEFWD_TOOL,
+    LENS_TOOL,
+};
+use kube::api::ListParams;
+use kube::{
+    Api,
+    Client,
+};
+use log::info;
+
+use crate::client::{
+    create_client_with_specific_context,
+    list_all_namespaces,
+};
+
+// labels that tell the pods of one workload apart, a selector needs to leave
+// them out to keep matching after a restart
+const POD_INSTANCE_LABELS: [&str; 4] = [
+    "pod-template-hash",
+    "controller-revision-hash",
+    "statefulset.kubernetes.io/pod-name",
+    "apps.kubernetes.io/pod-index",
+];
+
+async fn context_client(context: &str, kubeconfig: Option<String>) -> Result<Client, String> {
+    let (client, _, _) = create_client_with_specific_context(kubeconfig, Some(context))
+        .await
+        .map_err(|e| e.to_string())?;
+    client.ok_or_else(|| format!("Failed to create a client for context {}", context))
+}
+
+fn label_selector(labels: &BTreeMap<String, String>) -> Option<String> {
+    let selector: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
+    (!selector.is_empty()).then(|| selector.join(","))
+}
+
+// kftray forwards to pods by label, so a single pod becomes the labels it
+// shares with its siblings
+fn pod_selector(pod: &Pod) -> Option<String> {
+    let mut labels = pod.metadata.labels.clone()?;
+    labels.retain(|key, _| !POD_INSTANCE_LABELS.contains(&key.as_str()));
+    label_selector(&labels)
+}
+
+fn pod_alias(pod: &Pod) -> String {
+    let labels = pod.metadata.labels.as_ref();
+    ["app.kubernetes.io/name", "app"]
+        .iter()
+        .find_map(|key| labels.and_then(|labels| labels.get(*key)).cloned())
+        .or_else(|| pod.metadata.name.clone())
+        .unwrap_or_default()
+}
+
+fn named_container_port(pod: &Pod, container: Option<&str>, name: &str) -> Option<u16> {
+    pod.spec
+        .as_ref()?
+        .containers
+        .iter()
+        .filter(|candidate| container.is_none() || container == Some(candidate.name.as_str()))
+        .flat_map(|candidate| candidate.ports.iter().flatten())
+        .find(|port| port.name.as_deref() == Some(name))
+        .and_then(|port| u16::try_from(port.container_port).ok())
+}
+
+// the container port a service port lands on, named ones are looked up on a
+// pod behind the service
+async fn service_target_port(
+    client: &Client, namespace: &str, service: &Service, port: &ServicePort,
+) -> Option<u16> {
+    let name = match &port.target_port {
+        Some(IntOrString::String(name)) => name,
+        Some(IntOrString::Int(target)) => return u16::try_from(*target).ok(),
+        None => return u16::try_from(port.port).ok(),
+    };
+    let selector = label_selector(service.spec.as_ref()?.selector.as_ref()?)?;
+    let pods: Api<Pod> = Api::namespaced(client.clone(), namespace);
+    let pods = pods
+        .list(&ListParams::default().labels(&selector))
+        .await
+        .ok()?;
+    pods.items
+        .iter()
+        .find_map(|pod| named_container_port(pod, None, name))
+}
+
+fn port_protocol(port: &ServicePort) -> String {
+    match port.protocol.as_deref() {
+        Some("UDP") => "udp".to_string(),
+        _ => current_settings().default_protocol,
+    }
+}
+
+fn service_config(
+    context: &str, kubeconfig: Option<String>, namespace: &str, service: &str, remote_port: u16,
+    local_port: u16,
+) -> Config {
+    Config {
+        id: None,
+        context: context.to_string(),
+        kubeconfig,
+        namespace: namespace.to_string(),
+        service: Some(service.to_string()),
+        alias: Some(service.to_string()),
+        local_port: Some(local_port),
+        remote_port: Some(remote_port),
+        protocol: current_settings().default_protocol,
+        workload_type: Some("service".to_string()),
+        ..Default::default()
+    }
+}
+
+fn pod_config(
+    context: &str, kubeconfig: Option<String>, namespace: &str, pod: &Pod, selector: String,
+    remote_port: u16, local_port: u16,
+) -> Config {
+    Config {
+        id: None,
+        context: context.to_string(),
+        kubeconfig,
+        namespace: namespace.to_string(),
+        service: None,
+        target: Some(selector),
+        alias: Some(pod_alias(pod)),
+        local_port: Some(local_port),
+        remote_port: Some(remote_port),
+        protocol: current_settings().default_protocol,
+        workload_type: Some("pod".to_string()),
+        ..Default::default()
+    }
+}
+
+// the lens store doesn't say which cluster a forward was for, so all of them
+// are looked up in the given context. lens keeps service ports, kftray wants
+// the port on the pod
+pub async fn import_lens(
+    store_path: Option<&str>, context: &str, kubeconfig: Option<String>,
+) -> Result<ToolImport, String> {
+    let forwards = lens_forwards(&read_lens_store(store_path)?)?;
+    let client = context_client(context, kubeconfig.clone()).await?;
+    let mut import = ToolImport {
+        tool: LENS_TOOL.to_string(),
+        ..Default::default()
+    };
+
+    for forward in forwards {
+        let local_port = forward.forward_port.unwrap_or(forward.port);
+        let name = format!("{} {}/{}", forward.kind, forward.namespace, forward.name);
+        match forward.kind.as_str() {
+            "service" => {
+                let services: Api<Service> = Api::namespaced(client.clone(), &forward.namespace);
+                let Ok(Some(service)) = services.get_opt(&forward.name).await else {
+                    import
+                        .skipped
+                        .push(format!("{}: not found in {}", name, context));
+                    continue;
+                };
+                let port = service
+                    .spec
+                    .as_ref()
+                    .and_then(|spec| spec.ports.as_ref())
+                    .and_then(|ports| ports.iter().find(|port| port.port == forward.port as i32));
+                let remote_port = match port {
+                    Some(port) => {
+                        service_target_port(&client, &forward.namespace, &service, port).await
+                    }
+                    None => None,
+                };
+                let Some(remote_port) = remote_port else {
+                    import
+                        .skipped
+                        .push(format!("{}: port {} not found", name, forward.port));
+                    continue;
+                };
+                import.configs.push(service_config(
+                    context,
+                    kubeconfig.clone(),
+                    &forward.namespace,
+                    &forward.name,
+                    remote_port,
+                    local_port,
+                ));
+            }
+            "pod" => {
+                let pods: Api<Pod> = Api::namespaced(client.clone(), &forward.namespace);
+                let Ok(Some(pod)) = pods.get_opt(&forward.name).await else {
+                    import
+                        .skipped
+                        .push(format!("{}: not found in {}", name, context));
+                    continue;
+                };
+                let Some(selector) = pod_selector(&pod) else {
+                    import
+                        .skipped
+                        .push(format!("{}: the pod has no labels to select it by", name));
+                    continue;
+                };
+                import.configs.push(pod_config(
+                    context,
+                    kubeconfig.clone(),
+                    &forward.namespace,
+                    &pod,
+                    selector,
+                    forward.port,
+                    local_port,
+                ));
+            }
+            _ => import.skipped.push(format!("{}: unsupported kind", name)),
+        }
+    }
+
+    info!(
+        "Translated {} Lens forward(s), skipped {}",
+        import.configs.len(),
+        import.skipped.len()
+    );
+    Ok(import)
+}
+
+// forwards what `kubefwd svc` would: every service of the namespaces that
+// matches the selectors, each on its own loopback address under its service
+// name. contexts and kubeconfig fall back to the given ones when the command
+// has none
+pub async fn import_kubefwd(
+    command: &str, context: &str, kubeconfig: Option<String>,
+) -> Result<ToolImport, String> {
+    let command = parse_kubefwd_command(command)?;
+    let kubeconfig = command.kubeconfig.clone().or(kubeconfig);
+    let contexts = match command.contexts.is_empty() {
+        true => vec![context.to_string()],
+        false => command.contexts.clone(),
+    };
+    let mut params = ListParams::default();
+    if let Some(selector) = &command.selector {
+        params = params.labels(selector);
+    }
+    if let Some(field_selector) = &command.field_selector {
+        params = params.fields(field_selector);
+    }
+
+    let mut import = ToolImport {
+        tool: KUBEFWD_TOOL.to_string(),
+        ..Default::default()
+    };
+    let mut forwarded = 0;
+    for (context_index, context) in contexts.iter().enumerate() {
+        let client = context_client(context, kubeconfig.clone()).await?;
+        let namespaces = if command.all_namespaces {
+            list_all_namespaces(client.clone())
+                .await
+                .map_err(|e| e.to_string())?
+        } else if command.namespaces.is_empty() {
+            vec!["default".to_string()]
+        } else {
+            command.namespaces.clone()
+        };
+
+        for (namespace_index, namespace) in namespaces.iter().enumerate() {
+            let services: Api<Service> = Api::namespaced(client.clone(), namespace);
+            let services = services
+                .list(&params)
+                .await
+                .map_err(|e| format!("Failed to list services in {}: {}", namespace, e))?;
+
+            for service in &services.items {
+                let name = service.metadata.name.clone().unwrap_or_default();
+                let has_selector = service
+                    .spec
+                    .as_ref()
+                    .and_then(|spec| spec.selector.as_ref())
+                    .is_some_and(|selector| !selector.is_empty());
+                if !has_selector {
+                    import.skipped.push(format!(
+                        "service {}/{}: no selector to find its pods by",
+                        namespace, name
+                    ));
+                    continue;
+                }
+                let Some(address) = kubefwd_address(forwarded) else {
+                    import.skipped.push(format!(
+                        "service {}/{}: out of loopback addresses",
+                        namespace, name
+                    ));
+                    continue;
+                };
+                forwarded += 1;
+
+                let mut alias = kubefwd_alias(&name, namespace, namespace_index == 0);
+                if context_index > 0 {
+                    alias = format!("{}.{}", alias, context);
+                }
+                let ports = service
+                    .spec
+                    .as_ref()
+                    .and_then(|spec| spec.ports.clone())
+                    .unwrap_or_default();
+                for port in &ports {
+                    let Some(remote_port) =
+                        service_target_port(&client, namespace, service, port).await
+                    else {
+                        import.skipped.push(format!(
+                            "service {}/{}: no pod port behind port {}",
+                            namespace, name, port.port
+                        ));
+                        continue;
+                    };
+                    let service_port = port.port as u16;
+                    let local_port = *command.mappings.get(&service_port).unwrap_or(&service_port);
+
+                    let mut config = service_config(
+                        context,
+                        kubeconfig.clone(),
+                        namespace,
+                        &name,
+                        remote_port,
+                        local_port,
+                    );
+                    config.alias = Some(alias.clone());
+                    config.local_address = Some(address.clone());
+                    config.domain_enabled = Some(true);
+                    config.protocol = port_protocol(port);
+                    import.configs.push(config);
+                }
+            }
+        }
+    }
+
+    info!(
+        "Translated kubefwd into {} config(s), skipped {}",
+        import.configs.len(),
+        import.skipped.len()
+    );
+    Ok(import)
+}
+
+// k9s keeps its automatic forwards in a pod annotation, the pods of one
+// workload carry the same one and end up as a single config
+pub async fn import_k9s(context: &str, kubeconfig: Option<String>) -> Result<ToolImport, String> {
+    let client = context_client(context, kubeconfig.clone()).await?;
+    let pods: Api<Pod> = Api::all(client);
+    let pods = pods
+        .list(&ListParams::default())
+        .await
+        .map_err(|e| format!("Failed to list pods: {}", e))?;
+
+    let mut import = ToolImport {
+        tool: K9S_TOOL.to_string(),
+        ..Default::default()
+    };
+    let mut seen = HashSet::new();
+    for pod in &pods.items {
+        let Some(annotation) = pod
+            .metadata
+            .annotations
+            .as_ref()
+            .and_then(|annotations| annotations.get(K9S_PORT_FORWARD_ANNOTATION))
+        else {
+            continue;
+        };
+        let namespace = pod.metadata.namespace.clone().unwrap_or_default();
+        let name = format!(
+            "pod {}/{}",
+            namespace,
+            pod.metadata.name.clone().unwrap_or_default()
+        );
+        let forwards = match k9s_forwards(annotation) {
+            Ok(forwards) => forwards,
+            Err(e) => {
+                import.skipped.push(format!("{}: {}", name, e));
+                continue;
+            }
+        };
+        let Some(selector) = pod_selector(pod) else {
+            import
+                .skipped
+                .push(format!("{}: the pod has no labels to select it by", name));
+            continue;
+        };
+
+        for forward in forwards {
+            let remote_port = match forward.port.parse::<u16>() {
+                Ok(port) => Some(port),
+                Err(_) => named_container_port(pod, Some(&forward.container), &forward.port),
+            };
+            let Some(remote_port) = remote_port else {
+                import.skipped.push(format!(
+                    "{}: container {} has no port {}",
+                    name, forward.container, forward.port
+                ));
+                continue;
+            };
+            if !seen.insert((namespace.clone(), selector.clone(), remote_port)) {
+                continue;
+            }
+            import.configs.push(pod_config(
+                context,
+                kubeconfig.clone(),
+                &namespace,
+                pod,
+                selector.clone(),
+                remote_port,
+                forward.local_port.unwrap_or(remote_port),
+            ));
+        }
+    }
+
+    info!(
+        "Translated {} k9s forward(s) from {}, skipped {}",
+        import.configs.len(),
+        context,
+        import.skipped.len()
+    );
+    Ok(import)
+}
+
+// the configs a running `kubectl port-forward` stands for. the context is the
+// given one unless the command names its own, ports kubectl picked at random
+// fall back to the remote port
+pub async fn import_kubectl_forward(
+    forward: &KubectlForward, context: &str, kubeconfig: Option<String>,
+) -> Result<ToolImport, String> {
+    let context = forward.context.as_deref().unwrap_or(context);
+    let kubeconfig = forward.kubeconfig.clone().or(kubeconfig);
+    let namespace = forward.namespace.as_deref().unwrap_or("default");
+    let local_address = forward
+        .addresses
+        .iter()
+        .find(|address| address.as_str() != "localhost")
+        .cloned();
+    let client = context_client(context, kubeconfig.clone()).await?;
+    let name = format!("{} {}/{}", forward.kind, namespace, forward.name);
+
+    let mut import = ToolImport {
+        tool: KUBECTL_TOOL.to_string(),
+        ..Default::default()
+    };
+    match forward.kind.as_str() {
+        "service" => {
+            let services: Api<Service> = Api::namespaced(client.clone(), namespace);
+            let Ok(Some(service)) = services.get_opt(&forward.name).await else {
+                return Err(format!("{}: not found in {}", name, context));
+            };
+            let ports = service
+                .spec
+                .as_ref()
+                .and_then(|spec| spec.ports.clone())
+                .unwrap_or_default();
+            for (local_port, remote) in &forward.ports {
+                let port = ports.iter().find(|port| {
+                    port.port.to_string() == *remote || port.name.as_deref() == Some(remote)
+                });
+                let remote_port = match port {
+                    Some(port) => service_target_port(&client, namespace, &service, port).await,
+                    None => None,
+                };
+                let (Some(port), Some(remote_port)) = (port, remote_port) else {
+                    import
+                        .skipped
+                        .push(format!("{}: port {} not found", name, remote));
+                    continue;
+                };
+                let mut config = service_config(
+                    context,
+                    kubeconfig.clone(),
+                    namespace,
+                    &forward.name,
+                    remote_port,
+                    local_port.unwrap_or(port.port as u16),
+                );
+                config.local_address = local_address.clone();
+                config.protocol = "tcp".to_string();
+                import.configs.push(config);
+            }
+        }
+        "pod" => {
+            let pods: Api<Pod> = Api::namespaced(client.clone(), namespace);
+            let Ok(Some(pod)) = pods.get_opt(&forward.name).await else {
+                return Err(format!("{}: not found in {}", name, context));
+            };
+            let Some(selector) = pod_selector(&pod) else {
+                return Err(format!("{}: the pod has no labels to select it by", name));
+            };
+            for (local_port, remote) in &forward.ports {
+                let remote_port = match remote.parse::<u16>() {
+                    Ok(port) => Some(port),
+                    Err(_) => named_container_port(&pod, None, remote),
+                };
+                let Some(remote_port) = remote_port else {
+                    import
+                        .skipped
+                        .push(format!("{}: port {} not found", name, remote));
+                    continue;
+                };
+                let mut config = pod_config(
+                    context,
+                    kubeconfig.clone(),
+                    namespace,
+                    &pod,
+                    selector.clone(),
+                    remote_port,
+                    local_port.unwrap_or(remote_port),
+                );
+                config.local_address = local_address.clone();
+                config.protocol = "tcp".to_string();
+                import.configs.push(config);
+            }
+        }
+        _ => import.skipped.push(format!(
+            "{}: unsupported kind, forward to its service or pods instead",
+            name
+        )),
+    }
+
+    info!(
+        "Translated kubectl port-forward to {} into {} config(s), skipped {}",
+        name,
+        import.configs.len(),
+        import.skipped.len()
+    );
+    Ok(import)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+
+    use super::*;
+    use crate::headless::start_config;
+    use crate::models::kube::HttpLogState;
+    use crate::test_cluster::{
+        drop_forward,
+        mock_cluster,
+        MOCK_CONTEXT,
+    };
+
+    #[test]
+    fn test_pod_selector() {
+        let labels: BTreeMap<String, String> = [
+            ("app", "api"),
+            ("pod-template-hash", "6d4cf56db6"),
+            ("tier", "backend"),
+        ]
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        let pod = Pod {
+            metadata: ObjectMeta {
+                name: Some("api-6d4cf56db6-x2x7q".to_string()),
+                labels: Some(labels),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(pod_selector(&pod).as_deref(), Some("app=api,tier=backend"));
+        assert_eq!(pod_alias(&pod), "api");
+
+        let unlabelled = Pod {
+            metadata: ObjectMeta {
+                name: Some("debug".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(pod_selector(&unlabelled), None);
+        assert_eq!(pod_alias(&unlabelled), "debug");
+    }
+
+    #[tokio::test]
+    async fn test_start_imported_pod_forward() {
+        let pod = Pod {
+            metadata: ObjectMeta {
+                name: Some("api-6d4cf56db6-x2x7q".to_string()),
+                labels: Some(BTreeMap::from([("app".to_string(), "api".to_string())])),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let selector = pod_selector(&pod).unwrap();
+        let config = Config {
+            id: Some(9_183),
+            ..pod_config(
+                MOCK_CONTEXT,
+                Some(mock_cluster().await),
+                "default",
+                &pod,
+                selector,
+                8080,
+                0,
+            )
+        };
+        assert_eq!(config.service, None);
+
+        let responses = start_config(config, Arc::new(HttpLogState::new()))
+            .await
+            .unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].status, 0);
+        assert_eq!(responses[0].service, "app=api");
+
+        drop_forward(9_183);
+    }
+}
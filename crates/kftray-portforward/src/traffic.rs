@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::sync::atomic::{
+    AtomicU64,
+    Ordering,
+};
+use std::sync::{
+    Arc,
+    Mutex,
+};
+use std::time::Duration;
+
+use kftray_commons::models::access_log_model::AccessLogEntry;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+const CONNECTION_LOG_CAPACITY: usize = 256;
+
+lazy_static! {
+    static ref TRAFFIC: Mutex<HashMap<i64, Arc<TrafficCounters>>> = Mutex::new(HashMap::new());
+    static ref CONNECTION_LOGS: broadcast::Sender<AccessLogEntry> =
+        broadcast::channel(CONNECTION_LOG_CAPACITY).0;
+}
+
+// what the tcp connections of one forward moved since kftray started
+#[derive(Debug, Default)]
+pub struct TrafficCounters {
+    active: AtomicU64,
+    total: AtomicU64,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+}
+
+impl TrafficCounters {
+    pub fn record_in(&self, len: usize) {
+        self.bytes_in.fetch_add(len as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_out(&self, len: usize) {
+        self.bytes_out.fetch_add(len as u64, Ordering::Relaxed);
+    }
+
+    // counts the connection as active until the returned guard is dropped
+    pub fn open(self: &Arc<Self>) -> OpenConnection {
+        self.active.fetch_add(1, Ordering::Relaxed);
+        self.total.fetch_add(1, Ordering::Relaxed);
+        OpenConnection(self.clone())
+    }
+}
+
+pub struct OpenConnection(Arc<TrafficCounters>);
+
+impl Drop for OpenConnection {
+    fn drop(&mut self) {
+        self.0.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct TrafficSnapshot {
+    pub config_id: i64,
+    pub active_connections: u64,
+    pub total_connections: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+}
+
+pub fn traffic_counters(config_id: i64) -> Arc<TrafficCounters> {
+    TRAFFIC
+        .lock()
+        .unwrap()
+        .entry(config_id)
+        .or_default()
+        .clone()
+}
+
+pub fn get_traffic(config_id: i64) -> TrafficSnapshot {
+    let counters = TRAFFIC.lock().unwrap().get(&config_id).cloned();
+    counters
+        .map(|counters| TrafficSnapshot {
+            config_id,
+            active_connections: counters.active.load(Ordering::Relaxed),
+            total_connections: counters.total.load(Ordering::Relaxed),
+            bytes_in: counters.bytes_in.load(Ordering::Relaxed),
+            bytes_out: counters.bytes_out.load(Ordering::Relaxed),
+        })
+        .unwrap_or(TrafficSnapshot {
+            config_id,
+            ..Default::default()
+        })
+}
+
+// bytes per second in both directions between two snapshots of a forward
+pub fn throughput(previous: &TrafficSnapshot, current: &TrafficSnapshot, elapsed: Duration) -> u64 {
+    let moved = (current.bytes_in + current.bytes_out)
+        .saturating_sub(previous.bytes_in + previous.bytes_out);
+    match elapsed.as_millis() as u64 {
+        0 => 0,
+        millis => moved * 1000 / millis,
+    }
+}
+
+// every finished tcp connection, whether or not the access log is written
+pub fn publish_connection_log(entry: AccessLogEntry) {
+    let _ = CONNECTION_LOGS.send(entry);
+}
+
+pub fn subscribe_connection_logs() -> broadcast::Receiver<AccessLogEntry> {
+    CONNECTION_LOGS.subscribe()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traffic_counters() {
+        let config_id = 9_188;
+        let counters = traffic_counters(config_id);
+        let first = counters.open();
+        let second = counters.open();
+        counters.record_in(300);
+        counters.record_out(700);
+        drop(first);
+
+        let snapshot = get_traffic(config_id);
+        assert_eq!(snapshot.active_connections, 1);
+        assert_eq!(snapshot.total_connections, 2);
+        assert_eq!((snapshot.bytes_in, snapshot.bytes_out), (300, 700));
+        drop(second);
+        assert_eq!(get_traffic(config_id).active_connections, 0);
+
+        let previous = TrafficSnapshot::default();
+        assert_eq!(
+            throughput(&previous, &snapshot, Duration::from_millis(500)),
+            2000
+        );
+        assert_eq!(throughput(&snapshot, &previous, Duration::ZERO), 0);
+    }
+}
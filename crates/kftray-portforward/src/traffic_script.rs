@@ -0,0 +1,711 @@
+use std::collections::{
+    BTreeMap,
+    HashMap,
+    VecDeque,
+};
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::sync::{
+    Arc,
+    Mutex,
+};
+use std::time::{
+    Duration,
+    SystemTime,
+};
+
+use kftray_commons::models::config_model::Config;
+use lazy_static::lazy_static;
+use log::{
+    debug,
+    info,
+    warn,
+};
+use rhai::{
+    Array,
+    Dynamic,
+    Engine,
+    Map,
+    Scope,
+    AST,
+};
+use tokio::task::JoinHandle;
+
+use crate::mock_rules::{
+    content_length,
+    find_header,
+    reason_phrase,
+    RequestHead,
+    MAX_HEADERS,
+    MAX_HEAD_SIZE,
+};
+
+// keeps a script stuck in a loop from stalling the connection it runs for
+const MAX_SCRIPT_OPERATIONS: u64 = 1_000_000;
+// larger bodies stream past the script
+pub const MAX_SCRIPT_BODY: usize = 1024 * 1024;
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+pub type SentRequests = Arc<Mutex<VecDeque<ScriptRequest>>>;
+// repeated headers such as set-cookie keep one value each
+pub type Headers = BTreeMap<String, Vec<String>>;
+
+struct LoadedScript {
+    script: Option<Arc<TrafficScript>>,
+    watcher: JoinHandle<()>,
+}
+
+lazy_static! {
+    static ref TRAFFIC_SCRIPTS: Mutex<HashMap<i64, LoadedScript>> = Mutex::new(HashMap::new());
+}
+
+// header names are lowercased. the content length is left out, it follows
+// the body
+fn header_map(headers: &[(String, Vec<u8>)]) -> Headers {
+    let mut map = Headers::new();
+    for (name, value) in headers {
+        let name = name.to_ascii_lowercase();
+        if name == "content-length" {
+            continue;
+        }
+        map.entry(name)
+            .or_default()
+            .push(String::from_utf8_lossy(value).to_string());
+    }
+    map
+}
+
+fn write_headers(message: &mut String, headers: &Headers) {
+    for (name, values) in headers {
+        if name.eq_ignore_ascii_case("content-length") {
+            continue;
+        }
+        for value in values {
+            message.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+}
+
+// a header the message repeats shows up as an array in the script
+fn headers_to_dynamic(headers: &Headers) -> Dynamic {
+    Dynamic::from_map(
+        headers
+            .iter()
+            .map(|(name, values)| {
+                let value = match values.as_slice() {
+                    [value] => value.clone().into(),
+                    _ => Dynamic::from_array(
+                        values.iter().map(|value| value.clone().into()).collect(),
+                    ),
+                };
+                (name.as_str().into(), value)
+            })
+            .collect(),
+    )
+}
+
+fn map_string(map: &Map, key: &str) -> Option<String> {
+    map.get(key).map(|value| value.to_string())
+}
+
+fn map_headers(map: &Map) -> Option<Headers> {
+    let headers = map.get("headers")?.clone().try_cast::<Map>()?;
+    Some(
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let values = match value.clone().try_cast::<Array>() {
+                    Some(values) => values.iter().map(|value| value.to_string()).collect(),
+                    None => vec![value.to_string()],
+                };
+                (name.to_ascii_lowercase(), values)
+            })
+            .collect(),
+    )
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Headers,
+    pub body: String,
+    version: u8,
+    has_length: bool,
+}
+
+impl ScriptRequest {
+    pub fn new(head: &RequestHead, body: &[u8]) -> Self {
+        ScriptRequest {
+            method: head.method.clone(),
+            path: head.path.clone(),
+            headers: header_map(&head.headers),
+            body: String::from_utf8_lossy(body).to_string(),
+            version: head.version,
+            has_length: content_length(&head.headers).is_some(),
+        }
+    }
+
+    fn to_map(&self) -> Map {
+        let mut map = Map::new();
+        map.insert("method".into(), self.method.clone().into());
+        map.insert("path".into(), self.path.clone().into());
+        map.insert("headers".into(), headers_to_dynamic(&self.headers));
+        map.insert("body".into(), self.body.clone().into());
+        map
+    }
+
+    // keys the script left out keep their value
+    fn updated(&self, map: &Map) -> Self {
+        ScriptRequest {
+            method: map_string(map, "method").unwrap_or_else(|| self.method.clone()),
+            path: map_string(map, "path").unwrap_or_else(|| self.path.clone()),
+            headers: map_headers(map).unwrap_or_else(|| self.headers.clone()),
+            body: map_string(map, "body").unwrap_or_else(|| self.body.clone()),
+            ..self.clone()
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut request = format!("{} {} HTTP/1.{}\r\n", self.method, self.path, self.version);
+        write_headers(&mut request, &self.headers);
+        if self.has_length || !self.body.is_empty() {
+            request.push_str(&format!("content-length: {}\r\n", self.body.len()));
+        }
+        request.push_str("\r\n");
+        request.push_str(&self.body);
+        request.into_bytes()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScriptResponse {
+    pub status: u16,
+    pub headers: Headers,
+    pub body: String,
+}
+
+impl ScriptResponse {
+    fn to_map(&self) -> Map {
+        let mut map = Map::new();
+        map.insert("status".into(), Dynamic::from_int(self.status as i64));
+        map.insert("headers".into(), headers_to_dynamic(&self.headers));
+        map.insert("body".into(), self.body.clone().into());
+        map
+    }
+
+    fn updated(&self, map: &Map) -> Self {
+        ScriptResponse {
+            status: map
+                .get("status")
+                .and_then(|status| status.as_int().ok())
+                .and_then(|status| u16::try_from(status).ok())
+                .unwrap_or(self.status),
+            headers: map_headers(map).unwrap_or_else(|| self.headers.clone()),
+            body: map_string(map, "body").unwrap_or_else(|| self.body.clone()),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut response = format!(
+            "HTTP/1.1 {} {}\r\n",
+            self.status,
+            reason_phrase(self.status)
+        );
+        write_headers(&mut response, &self.headers);
+        response.push_str(&format!("content-length: {}\r\n\r\n", self.body.len()));
+        response.push_str(&self.body);
+        response.into_bytes()
+    }
+}
+
+pub enum RequestVerdict {
+    Forward(ScriptRequest),
+    Respond(ScriptResponse),
+}
+
+pub struct TrafficScript {
+    engine: Engine,
+    ast: AST,
+    on_request: bool,
+    on_response: bool,
+}
+
+impl TrafficScript {
+    pub fn compile(source: &str) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_SCRIPT_OPERATIONS);
+        engine.on_print(|text| info!("Traffic script: {}", text));
+        let ast = engine.compile(source).map_err(|e| e.to_string())?;
+
+        let defines = |name: &str, params: usize| {
+            ast.iter_functions()
+                .any(|function| function.name == name && function.params.len() == params)
+        };
+        let on_request = defines("on_request", 1);
+        let on_response = defines("on_response", 2);
+        if !on_request && !on_response {
+            return Err(
+                "The script defines neither on_request(request) nor on_response(request, response)"
+                    .to_string(),
+            );
+        }
+
+        Ok(TrafficScript {
+            engine,
+            ast,
+            on_request,
+            on_response,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::compile(&source).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn handles_requests(&self) -> bool {
+        self.on_request
+    }
+
+    pub fn handles_responses(&self) -> bool {
+        self.on_response
+    }
+
+    // none sends the request on untouched. a map with a `status` answers it,
+    // any other map replaces it
+    pub fn on_request(&self, request: &ScriptRequest) -> Result<Option<RequestVerdict>, String> {
+        if !self.on_request {
+            return Ok(None);
+        }
+
+        let result: Dynamic = self
+            .engine
+            .call_fn(
+                &mut Scope::new(),
+                &self.ast,
+                "on_request",
+                (request.to_map(),),
+            )
+            .map_err(|e| e.to_string())?;
+        if result.is_unit() {
+            return Ok(None);
+        }
+        let map = result
+            .try_cast::<Map>()
+            .ok_or("on_request has to return a map or nothing")?;
+        if map.contains_key("status") {
+            let response = ScriptResponse {
+                status: 200,
+                ..Default::default()
+            };
+            return Ok(Some(RequestVerdict::Respond(response.updated(&map))));
+        }
+        Ok(Some(RequestVerdict::Forward(request.updated(&map))))
+    }
+
+    // none leaves the response as the pod sent it
+    pub fn on_response(
+        &self, request: &ScriptRequest, response: &ScriptResponse,
+    ) -> Result<Option<ScriptResponse>, String> {
+        if !self.on_response {
+            return Ok(None);
+        }
+
+        let result: Dynamic = self
+            .engine
+            .call_fn(
+                &mut Scope::new(),
+                &self.ast,
+                "on_response",
+                (request.to_map(), response.to_map()),
+            )
+            .map_err(|e| e.to_string())?;
+        if result.is_unit() {
+            return Ok(None);
+        }
+        let map = result
+            .try_cast::<Map>()
+            .ok_or("on_response has to return a map or nothing")?;
+        Ok(Some(response.updated(&map)))
+    }
+}
+
+// none when the body runs until the connection closes, or is chunked
+pub fn response_body_length(
+    status: u16, headers: &[(String, Vec<u8>)], request_method: Option<&str>,
+) -> Option<usize> {
+    let bodiless = matches!(status, 100..=199 | 204 | 304) && status != 101;
+    if bodiless || request_method.is_some_and(|method| method.eq_ignore_ascii_case("HEAD")) {
+        return Some(0);
+    }
+    if status == 101 || find_header(headers, "transfer-encoding").is_some() {
+        return None;
+    }
+    content_length(headers)
+}
+
+struct HeldResponse {
+    request: ScriptRequest,
+    status: u16,
+    headers: Vec<(String, Vec<u8>)>,
+    head_bytes: Vec<u8>,
+    body: Vec<u8>,
+}
+
+// follows the responses of one connection and hands each one, with the
+// request it answers, to on_response
+pub struct ResponseRelay {
+    script: Arc<TrafficScript>,
+    sent: SentRequests,
+    pending: Vec<u8>,
+    body_left: usize,
+    held: Option<HeldResponse>,
+    passthrough: bool,
+}
+
+impl ResponseRelay {
+    pub fn new(script: Arc<TrafficScript>, sent: SentRequests) -> Self {
+        ResponseRelay {
+            script,
+            sent,
+            pending: Vec::new(),
+            body_left: 0,
+            held: None,
+            passthrough: false,
+        }
+    }
+
+    fn release_held(&mut self) -> Vec<u8> {
+        let Some(held) = self.held.take() else {
+            return Vec::new();
+        };
+        let Ok(body) = String::from_utf8(held.body.clone()) else {
+            return [held.head_bytes, held.body].concat();
+        };
+
+        let response = ScriptResponse {
+            status: held.status,
+            headers: header_map(&held.headers),
+            body,
+        };
+        match self.script.on_response(&held.request, &response) {
+            Ok(Some(changed)) => changed.to_bytes(),
+            Ok(None) => [held.head_bytes, held.body].concat(),
+            Err(e) => {
+                warn!(
+                    "Traffic script failed on the response to {} {}: {}",
+                    held.request.method, held.request.path, e
+                );
+                [held.head_bytes, held.body].concat()
+            }
+        }
+    }
+
+    // the bytes to send on to the client, held back while a response is
+    // incomplete
+    pub fn feed(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut data = data.to_vec();
+        while !data.is_empty() {
+            if self.passthrough {
+                out.append(&mut data);
+                break;
+            }
+
+            if self.body_left > 0 {
+                let body = data.len().min(self.body_left);
+                let rest = data.split_off(body);
+                self.body_left -= body;
+                match &mut self.held {
+                    Some(held) => held.body.append(&mut data),
+                    None => out.append(&mut data),
+                }
+                if self.body_left == 0 {
+                    out.extend(self.release_held());
+                }
+                data = rest;
+                continue;
+            }
+
+            self.pending.append(&mut data);
+            let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+            let mut response = httparse::Response::new(&mut headers);
+            let head_len = match response.parse(&self.pending) {
+                Ok(httparse::Status::Complete(head_len)) => head_len,
+                Ok(httparse::Status::Partial) if self.pending.len() <= MAX_HEAD_SIZE => break,
+                _ => {
+                    debug!("Not an HTTP/1 response, relaying the connection untouched");
+                    self.passthrough = true;
+                    data = std::mem::take(&mut self.pending);
+                    continue;
+                }
+            };
+            let status = response.code.unwrap_or_default();
+            let headers: Vec<(String, Vec<u8>)> = response
+                .headers
+                .iter()
+                .map(|header| (header.name.to_string(), header.value.to_vec()))
+                .collect();
+
+            let mut head_bytes = std::mem::take(&mut self.pending);
+            data = head_bytes.split_off(head_len);
+            // interim responses come ahead of the final one to the same request
+            if (100..200).contains(&status) && status != 101 {
+                out.append(&mut head_bytes);
+                continue;
+            }
+
+            let request = self.sent.lock().unwrap().pop_front();
+            let method = request.as_ref().map(|request| request.method.as_str());
+            let Some(body_len) = response_body_length(status, &headers, method) else {
+                self.passthrough = true;
+                out.append(&mut head_bytes);
+                continue;
+            };
+
+            self.body_left = body_len;
+            match request {
+                Some(request) if body_len <= MAX_SCRIPT_BODY => {
+                    self.held = Some(HeldResponse {
+                        request,
+                        status,
+                        headers,
+                        head_bytes,
+                        body: Vec::new(),
+                    });
+                    if body_len == 0 {
+                        out.extend(self.release_held());
+                    }
+                }
+                _ => out.append(&mut head_bytes),
+            }
+        }
+        out
+    }
+}
+
+async fn modified_at(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path)
+        .await
+        .and_then(|m| m.modified())
+        .ok()
+}
+
+// reloads the script when its file changes, a script that stops compiling
+// keeps the last one that did
+fn watch_script(config_id: i64, path: PathBuf, mut modified: Option<SystemTime>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(RELOAD_CHECK_INTERVAL).await;
+            let current = modified_at(&path).await;
+            if current == modified {
+                continue;
+            }
+            modified = current;
+
+            let reloaded = match tokio::fs::read_to_string(&path).await {
+                Ok(source) => TrafficScript::compile(&source),
+                Err(e) => Err(e.to_string()),
+            };
+            match reloaded {
+                Ok(script) => {
+                    info!("Reloaded traffic script {}", path.display());
+                    if let Some(loaded) = TRAFFIC_SCRIPTS.lock().unwrap().get_mut(&config_id) {
+                        loaded.script = Some(Arc::new(script));
+                    }
+                }
+                Err(e) => warn!(
+                    "Keeping the previous traffic script, {}: {}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+    })
+}
+
+// a script that doesn't load still registers, so fixing the file brings it
+// in. a config without a script drops the one of an earlier start
+pub(crate) fn register_traffic_script(config: &Config) -> Result<(), String> {
+    let Some(config_id) = config.id else {
+        return Ok(());
+    };
+    let Some(path) = config
+        .traffic_script
+        .as_deref()
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+    else {
+        clear_traffic_script(Some(config_id));
+        return Ok(());
+    };
+
+    let path = PathBuf::from(path);
+    let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+    let (script, result) = match TrafficScript::load(&path) {
+        Ok(script) => {
+            info!(
+                "Config {} runs traffic script {}",
+                config_id,
+                path.display()
+            );
+            (Some(Arc::new(script)), Ok(()))
+        }
+        Err(e) => (None, Err(e)),
+    };
+    let watcher = watch_script(config_id, path, modified);
+    if let Some(previous) = TRAFFIC_SCRIPTS
+        .lock()
+        .unwrap()
+        .insert(config_id, LoadedScript { script, watcher })
+    {
+        previous.watcher.abort();
+    }
+    result
+}
+
+pub(crate) fn traffic_script(config_id: i64) -> Option<Arc<TrafficScript>> {
+    TRAFFIC_SCRIPTS
+        .lock()
+        .unwrap()
+        .get(&config_id)
+        .and_then(|loaded| loaded.script.clone())
+}
+
+pub(crate) fn clear_traffic_script(config_id: Option<i64>) {
+    let stopped: Vec<LoadedScript> = {
+        let mut scripts = TRAFFIC_SCRIPTS.lock().unwrap();
+        match config_id {
+            Some(config_id) => scripts.remove(&config_id).into_iter().collect(),
+            None => scripts.drain().map(|(_, loaded)| loaded).collect(),
+        }
+    };
+
+    for loaded in stopped {
+        loaded.watcher.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rules::parse_request_head;
+
+    fn request() -> ScriptRequest {
+        let head = parse_request_head(
+            b"POST /orders HTTP/1.1\r\nHost: shop\r\nAccept: a\r\naccept: b\r\nContent-Length: 2\r\n\r\n",
+        )
+        .unwrap()
+        .unwrap();
+        ScriptRequest::new(&head, b"{}")
+    }
+
+    #[test]
+    fn test_script_messages() {
+        let request = request();
+        assert_eq!(request.headers.get("accept").unwrap(), &["a", "b"]);
+        assert!(!request.headers.contains_key("content-length"));
+
+        let changed = ScriptRequest {
+            body: "{\"id\":7}".to_string(),
+            ..request
+        };
+        assert_eq!(
+            changed.to_bytes(),
+            b"POST /orders HTTP/1.1\r\naccept: a\r\naccept: b\r\nhost: shop\r\ncontent-length: 8\r\n\r\n{\"id\":7}"
+        );
+
+        let response = ScriptResponse {
+            status: 404,
+            headers: Headers::from([("content-length".to_string(), vec!["3".to_string()])]),
+            body: String::new(),
+        };
+        assert_eq!(
+            response.to_bytes(),
+            b"HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_script_rewrites_traffic() {
+        let script = TrafficScript::compile(
+            r#"
+            fn on_request(request) {
+                if request.path == "/flags" {
+                    return #{ status: 503, body: "down" };
+                }
+                request.headers["x-debug"] = "me";
+                request
+            }
+
+            fn on_response(request, response) {
+                if request.path == "/orders" {
+                    response.headers["x-cookies"] = response.headers["set-cookie"].len().to_string();
+                    response.body = "changed";
+                    return response;
+                }
+            }
+            "#,
+        )
+        .unwrap();
+        assert!(script.handles_requests());
+        assert!(script.handles_responses());
+
+        let request = request();
+        let Some(RequestVerdict::Forward(changed)) = script.on_request(&request).unwrap() else {
+            panic!("the request should be forwarded");
+        };
+        assert_eq!(changed.headers.get("x-debug").unwrap(), &["me"]);
+        assert_eq!(changed.headers.get("accept").unwrap(), &["a", "b"]);
+
+        let flags = ScriptRequest {
+            path: "/flags".to_string(),
+            ..request.clone()
+        };
+        let Some(RequestVerdict::Respond(answer)) = script.on_request(&flags).unwrap() else {
+            panic!("the request should be answered");
+        };
+        assert_eq!((answer.status, answer.body.as_str()), (503, "down"));
+
+        let response = ScriptResponse {
+            status: 200,
+            headers: Headers::from([(
+                "set-cookie".to_string(),
+                vec!["a=1".to_string(), "b=2".to_string()],
+            )]),
+            body: "{}".to_string(),
+        };
+        let changed = script.on_response(&request, &response).unwrap().unwrap();
+        assert_eq!(changed.status, 200);
+        assert_eq!(changed.body, "changed");
+        assert_eq!(changed.headers.get("x-cookies").unwrap(), &["2"]);
+        assert_eq!(changed.headers.get("set-cookie").unwrap(), &["a=1", "b=2"]);
+        assert!(script.on_response(&flags, &response).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_script_failures() {
+        assert!(TrafficScript::compile("fn on_request(request) { request").is_err());
+        assert!(TrafficScript::compile("fn helper(request) { request }").is_err());
+
+        let script = TrafficScript::compile("fn on_request(request) { loop { } }").unwrap();
+        assert!(!script.handles_responses());
+        assert!(script.on_request(&request()).is_err());
+    }
+
+    #[test]
+    fn test_response_body_length() {
+        let length = vec![("Content-Length".to_string(), b"12".to_vec())];
+        let chunked = vec![("Transfer-Encoding".to_string(), b"chunked".to_vec())];
+        assert_eq!(response_body_length(200, &length, Some("GET")), Some(12));
+        assert_eq!(response_body_length(200, &length, Some("head")), Some(0));
+        assert_eq!(response_body_length(204, &[], None), Some(0));
+        assert_eq!(response_body_length(304, &length, None), Some(0));
+        assert_eq!(response_body_length(101, &[], Some("GET")), None);
+        assert_eq!(response_body_length(200, &chunked, Some("GET")), None);
+        assert_eq!(response_body_length(200, &[], Some("GET")), None);
+    }
+}
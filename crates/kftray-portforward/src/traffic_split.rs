@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::sync::atomic::{
+    AtomicU64,
+    Ordering,
+};
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
+use kftray_commons::models::config_model::Config;
+use lazy_static::lazy_static;
+use log::info;
+use rand::Rng;
+use serde::Serialize;
+
+use crate::models::kube::{
+    NameSpace,
+    Port,
+    PortForward,
+    Target,
+    TargetSelector,
+};
+
+struct Split {
+    split_target: String,
+    percent: u8,
+    forward: PortForward,
+    primary_connections: Arc<AtomicU64>,
+    split_connections: Arc<AtomicU64>,
+}
+
+lazy_static! {
+    static ref TRAFFIC_SPLITS: Mutex<HashMap<i64, Split>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TrafficSplit {
+    pub config_id: i64,
+    pub split_target: String,
+    pub percent: u8,
+    pub primary_connections: u64,
+    pub split_connections: u64,
+}
+
+// `roll` is uniform in 0..100
+pub fn goes_to_split(percent: u8, roll: u8) -> bool {
+    roll < percent.min(100)
+}
+
+pub fn split_target(config: &Config) -> Option<Target> {
+    let split_target = config
+        .split_target
+        .as_deref()
+        .map(str::trim)
+        .filter(|target| !target.is_empty())?;
+    if config.split_percent.unwrap_or_default() == 0 {
+        return None;
+    }
+
+    let selector = match config.workload_type.as_deref() {
+        Some("pod") => TargetSelector::PodLabel(split_target.to_string()),
+        _ => TargetSelector::ServiceName(split_target.to_string()),
+    };
+    let remote_port = config.split_remote_port.or(config.remote_port);
+    Some(Target {
+        selector,
+        port: Port::from(remote_port.unwrap_or_default() as i32),
+        namespace: NameSpace(Some(config.namespace.clone())),
+    })
+}
+
+// the split target lives next to the primary one, so it shares its clients.
+// a config without a split drops the one of an earlier start
+pub(crate) fn register_traffic_split(config: &Config, primary: &PortForward) {
+    let Some(target) = split_target(config) else {
+        clear_traffic_split(Some(primary.config_id));
+        return;
+    };
+    let split_target = config.split_target.clone().unwrap_or_default();
+    let percent = config.split_percent.unwrap_or_default().min(100);
+    info!(
+        "Config {} sends {}% of its connections to {}",
+        primary.config_id, percent, split_target
+    );
+
+    let forward = PortForward {
+        target,
+        negotiated_transport: Default::default(),
+        ..primary.clone()
+    };
+    TRAFFIC_SPLITS.lock().unwrap().insert(
+        primary.config_id,
+        Split {
+            split_target,
+            percent,
+            forward,
+            primary_connections: Default::default(),
+            split_connections: Default::default(),
+        },
+    );
+}
+
+// picks the upstream of a new connection
+pub(crate) fn split_forward(forward: &PortForward) -> PortForward {
+    let splits = TRAFFIC_SPLITS.lock().unwrap();
+    let Some(split) = splits.get(&forward.config_id) else {
+        return forward.clone();
+    };
+
+    let roll = rand::thread_rng().gen_range(0..100);
+    if goes_to_split(split.percent, roll) {
+        split.split_connections.fetch_add(1, Ordering::Relaxed);
+        split.forward.clone()
+    } else {
+        split.primary_connections.fetch_add(1, Ordering::Relaxed);
+        forward.clone()
+    }
+}
+
+pub(crate) fn clear_traffic_split(config_id: Option<i64>) {
+    let mut splits = TRAFFIC_SPLITS.lock().unwrap();
+    match config_id {
+        Some(config_id) => {
+            splits.remove(&config_id);
+        }
+        None => splits.clear(),
+    }
+}
+
+pub fn get_traffic_splits() -> Vec<TrafficSplit> {
+    let mut splits: Vec<TrafficSplit> = TRAFFIC_SPLITS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(config_id, split)| TrafficSplit {
+            config_id: *config_id,
+            split_target: split.split_target.clone(),
+            percent: split.percent,
+            primary_connections: split.primary_connections.load(Ordering::Relaxed),
+            split_connections: split.split_connections.load(Ordering::Relaxed),
+        })
+        .collect();
+    splits.sort_by_key(|split| split.config_id);
+    splits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_target() {
+        assert!(goes_to_split(10, 9));
+        assert!(!goes_to_split(10, 10));
+        assert!(!goes_to_split(0, 0));
+        assert!(goes_to_split(200, 99));
+
+        let mut config = Config {
+            namespace: "shop".to_string(),
+            workload_type: Some("service".to_string()),
+            remote_port: Some(8080),
+            split_target: Some("api-canary".to_string()),
+            split_percent: Some(20),
+            ..Default::default()
+        };
+        let target = split_target(&config).unwrap();
+        assert!(
+            matches!(target.selector, TargetSelector::ServiceName(ref name) if name == "api-canary")
+        );
+        assert!(matches!(target.port, Port::Number(8080)));
+
+        config.workload_type = Some("pod".to_string());
+        config.split_remote_port = Some(9090);
+        let target = split_target(&config).unwrap();
+        assert!(matches!(target.selector, TargetSelector::PodLabel(_)));
+        assert!(matches!(target.port, Port::Number(9090)));
+
+        config.split_percent = Some(0);
+        assert!(split_target(&config).is_none());
+    }
+}
@@ -0,0 +1,35 @@
+use tokio::io::{
+    AsyncRead,
+    AsyncWrite,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Transport {
+    #[default]
+    Auto,
+    WebSocket,
+    Spdy,
+}
+
+impl Transport {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Transport::Auto => "auto",
+            Transport::WebSocket => "websocket",
+            Transport::Spdy => "spdy",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(Transport::Auto),
+            "websocket" => Some(Transport::WebSocket),
+            "spdy" => Some(Transport::Spdy),
+            _ => None,
+        }
+    }
+}
+
+pub trait UpstreamStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> UpstreamStream for T {}
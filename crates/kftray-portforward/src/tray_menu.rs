@@ -0,0 +1,248 @@
+use std::collections::{
+    BTreeMap,
+    HashSet,
+};
+
+use kftray_commons::config_state::get_configs_state;
+use kftray_commons::models::config_model::Config;
+use kftray_commons::utils::config_display::{
+    config_color_hex,
+    config_display_name,
+};
+use kftray_commons::utils::profile::read_active_profile_configs;
+use kftray_commons::utils::search::filter_configs;
+use kftray_commons::utils::usage::pinned_config_ids;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::port_manifest::{
+    forward_health,
+    PortHealth,
+};
+
+const UNTAGGED: &str = "untagged";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrayGrouping {
+    #[default]
+    Context,
+    Namespace,
+    Tag,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TrayEntry {
+    pub config_id: i64,
+    pub label: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    // `#rrggbb`, whatever name the config uses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    pub context: String,
+    pub namespace: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_port: Option<u16>,
+    pub pinned: bool,
+    // none while the config is stopped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<PortHealth>,
+    pub glyph: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TrayGroup {
+    pub name: String,
+    pub running: usize,
+    pub entries: Vec<TrayEntry>,
+}
+
+// pinned configs come first and are still listed in their group
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct TrayMenu {
+    pub pinned: Vec<TrayEntry>,
+    pub groups: Vec<TrayGroup>,
+}
+
+pub fn tray_glyph(health: Option<PortHealth>) -> &'static str {
+    match health {
+        None => "○",
+        Some(PortHealth::Healthy) => "●",
+        Some(PortHealth::Degraded) => "◐",
+        Some(PortHealth::Starting) | Some(PortHealth::Stopping) => "◌",
+        Some(PortHealth::Failed) => "✕",
+    }
+}
+
+fn group_names(config: &Config, grouping: TrayGrouping) -> Vec<String> {
+    match grouping {
+        TrayGrouping::Context => vec![config.context.clone()],
+        TrayGrouping::Namespace => vec![config.namespace.clone()],
+        // a config with several tags shows up under each of them
+        TrayGrouping::Tag => match config.tags.as_ref().filter(|tags| !tags.is_empty()) {
+            Some(tags) => tags.clone(),
+            None => vec![UNTAGGED.to_string()],
+        },
+    }
+}
+
+fn tray_entry(config: &Config, pinned: bool, health: Option<PortHealth>) -> Option<TrayEntry> {
+    let config_id = config.id?;
+    Some(TrayEntry {
+        config_id,
+        label: config_display_name(config),
+        icon: config.icon.clone().filter(|icon| !icon.trim().is_empty()),
+        color: config_color_hex(config),
+        context: config.context.clone(),
+        namespace: config.namespace.clone(),
+        local_port: config.local_port,
+        pinned,
+        health,
+        glyph: tray_glyph(health).to_string(),
+    })
+}
+
+pub fn build_tray_menu(
+    configs: Vec<Config>, running: &HashSet<i64>, pinned: &HashSet<i64>,
+    health: impl Fn(i64) -> PortHealth, grouping: TrayGrouping, query: Option<&str>,
+) -> TrayMenu {
+    let configs = match query {
+        Some(query) => filter_configs(configs, query),
+        None => configs,
+    };
+
+    let mut menu = TrayMenu::default();
+    let mut groups: BTreeMap<String, TrayGroup> = BTreeMap::new();
+    for config in &configs {
+        let health = config
+            .id
+            .filter(|config_id| running.contains(config_id))
+            .map(&health);
+        let is_pinned = config
+            .id
+            .is_some_and(|config_id| pinned.contains(&config_id));
+        let Some(entry) = tray_entry(config, is_pinned, health) else {
+            continue;
+        };
+
+        if entry.pinned {
+            menu.pinned.push(entry.clone());
+        }
+        for name in group_names(config, grouping) {
+            let group = groups.entry(name.clone()).or_insert_with(|| TrayGroup {
+                name,
+                running: 0,
+                entries: Vec::new(),
+            });
+            if entry.health.is_some() {
+                group.running += 1;
+            }
+            group.entries.push(entry.clone());
+        }
+    }
+
+    // a search keeps the best matches on top, otherwise it's alphabetical
+    if query.is_none() {
+        menu.pinned.sort_by(|a, b| a.label.cmp(&b.label));
+        for group in groups.values_mut() {
+            group.entries.sort_by(|a, b| a.label.cmp(&b.label));
+        }
+    }
+    menu.groups = groups.into_values().collect();
+    menu
+}
+
+// the configs of the active profile, filtered by the search query when there
+// is one
+pub async fn get_tray_menu(
+    grouping: TrayGrouping, query: Option<String>,
+) -> Result<TrayMenu, String> {
+    let configs = read_active_profile_configs().await?;
+    let running: HashSet<i64> = get_configs_state()
+        .await?
+        .into_iter()
+        .filter(|state| state.is_running)
+        .map(|state| state.config_id)
+        .collect();
+    let pinned = pinned_config_ids().await?;
+    let query = query.filter(|query| !query.trim().is_empty());
+
+    Ok(build_tray_menu(
+        configs,
+        &running,
+        &pinned,
+        forward_health,
+        grouping,
+        query.as_deref(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(id: i64, alias: &str, context: &str, tags: &[&str]) -> Config {
+        Config {
+            id: Some(id),
+            alias: Some(alias.to_string()),
+            context: context.to_string(),
+            tags: (!tags.is_empty()).then(|| tags.iter().map(|tag| tag.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build_tray_menu() {
+        let configs = vec![
+            config(1, "web", "prod", &["frontend"]),
+            config(2, "api", "prod", &["frontend", "backend"]),
+            config(3, "db", "dev", &[]),
+        ];
+        let running = HashSet::from([2]);
+        let pinned = HashSet::from([2]);
+        let health = |_| PortHealth::Degraded;
+
+        let menu = build_tray_menu(
+            configs.clone(),
+            &running,
+            &pinned,
+            health,
+            TrayGrouping::Context,
+            None,
+        );
+        assert_eq!(menu.pinned.len(), 1);
+        assert_eq!(menu.pinned[0].glyph, "◐");
+        let names: Vec<&str> = menu.groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["dev", "prod"]);
+        let prod = &menu.groups[1];
+        assert_eq!(prod.running, 1);
+        assert_eq!(prod.entries[0].label, "api");
+        assert_eq!(prod.entries[1].glyph, "○");
+
+        let menu = build_tray_menu(
+            configs.clone(),
+            &running,
+            &pinned,
+            health,
+            TrayGrouping::Tag,
+            None,
+        );
+        let names: Vec<&str> = menu.groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["backend", "frontend", UNTAGGED]);
+        assert_eq!(menu.groups[1].entries.len(), 2);
+
+        let menu = build_tray_menu(
+            configs,
+            &running,
+            &pinned,
+            health,
+            TrayGrouping::Tag,
+            Some("db"),
+        );
+        assert_eq!(menu.groups.len(), 1);
+        assert_eq!(menu.groups[0].entries[0].config_id, 3);
+    }
+}
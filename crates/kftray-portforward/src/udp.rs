@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{
+    AtomicU32,
+    AtomicU64,
+    Ordering,
+};
+use std::sync::{
+    Arc,
+    Mutex,
+};
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+use tokio::io::{
+    AsyncRead,
+    AsyncReadExt,
+    AsyncWrite,
+    AsyncWriteExt,
+};
+
+// the largest payload a udp datagram can carry, every frame of the udp over
+// tcp protocol holds exactly one datagram behind a u32 big endian length. the
+// protocol has no fragments of its own, the kernel puts ip fragments back
+// together before a socket hands the datagram over and the stream carries a
+// frame in as many pieces as it needs
+pub const MAX_UDP_DATAGRAM: usize = 65535;
+pub const UDP_RECONNECT_ATTEMPTS: u32 = 5;
+const UDP_RECONNECT_BASE_DELAY: Duration = Duration::from_millis(250);
+const UDP_RECONNECT_MAX_DELAY: Duration = Duration::from_secs(8);
+
+lazy_static! {
+    static ref UDP_ASSOCIATIONS: Mutex<HashMap<i64, HashMap<SocketAddr, Arc<UdpAssociation>>>> =
+        Mutex::new(HashMap::new());
+}
+
+// one local peer of a udp forward, relayed over its own upstream stream
+#[derive(Debug)]
+pub struct UdpAssociation {
+    peer: SocketAddr,
+    packets_in: AtomicU64,
+    packets_out: AtomicU64,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    dropped: AtomicU64,
+    reconnects: AtomicU32,
+    last_active: Mutex<Instant>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct UdpAssociationStats {
+    pub peer: String,
+    pub packets_in: u64,
+    pub packets_out: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub dropped: u64,
+    pub reconnects: u32,
+    pub idle_secs: u64,
+}
+
+impl UdpAssociation {
+    fn new(peer: SocketAddr) -> Self {
+        UdpAssociation {
+            peer,
+            packets_in: AtomicU64::new(0),
+            packets_out: AtomicU64::new(0),
+            bytes_in: AtomicU64::new(0),
+            bytes_out: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            reconnects: AtomicU32::new(0),
+            last_active: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn record_in(&self, len: usize) {
+        self.packets_in.fetch_add(1, Ordering::Relaxed);
+        self.bytes_in.fetch_add(len as u64, Ordering::Relaxed);
+        self.touch();
+    }
+
+    pub fn record_out(&self, len: usize) {
+        self.packets_out.fetch_add(1, Ordering::Relaxed);
+        self.bytes_out.fetch_add(len as u64, Ordering::Relaxed);
+        self.touch();
+    }
+
+    pub fn record_dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_reconnect(&self) {
+        self.reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn peer(&self) -> SocketAddr {
+        self.peer
+    }
+
+    pub fn last_active(&self) -> Instant {
+        *self.last_active.lock().unwrap()
+    }
+
+    fn touch(&self) {
+        *self.last_active.lock().unwrap() = Instant::now();
+    }
+
+    fn stats(&self) -> UdpAssociationStats {
+        UdpAssociationStats {
+            peer: self.peer.to_string(),
+            packets_in: self.packets_in.load(Ordering::Relaxed),
+            packets_out: self.packets_out.load(Ordering::Relaxed),
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.bytes_out.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+            idle_secs: self.last_active().elapsed().as_secs(),
+        }
+    }
+}
+
+pub fn register_udp_association(config_id: i64, peer: SocketAddr) -> Arc<UdpAssociation> {
+    let association = Arc::new(UdpAssociation::new(peer));
+    UDP_ASSOCIATIONS
+        .lock()
+        .unwrap()
+        .entry(config_id)
+        .or_default()
+        .insert(peer, association.clone());
+    association
+}
+
+// only removes the entry if it still belongs to that association, a new one
+// for the same peer may already have taken its place
+pub fn remove_udp_association(config_id: i64, association: &Arc<UdpAssociation>) {
+    let mut associations = UDP_ASSOCIATIONS.lock().unwrap();
+    if let Some(peers) = associations.get_mut(&config_id) {
+        if peers
+            .get(&association.peer)
+            .is_some_and(|current| Arc::ptr_eq(current, association))
+        {
+            peers.remove(&association.peer);
+        }
+        if peers.is_empty() {
+            associations.remove(&config_id);
+        }
+    }
+}
+
+pub fn get_udp_associations(config_id: i64) -> Vec<UdpAssociationStats> {
+    let mut stats: Vec<UdpAssociationStats> = UDP_ASSOCIATIONS
+        .lock()
+        .unwrap()
+        .get(&config_id)
+        .map(|peers| {
+            peers
+                .values()
+                .map(|association| association.stats())
+                .collect()
+        })
+        .unwrap_or_default();
+    stats.sort_by(|a, b| a.peer.cmp(&b.peer));
+    stats
+}
+
+pub fn udp_reconnect_delay(attempt: u32) -> Duration {
+    UDP_RECONNECT_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(UDP_RECONNECT_MAX_DELAY)
+}
+
+// frames may arrive split over any number of upstream reads, a length past
+// MAX_UDP_DATAGRAM means the stream is out of sync rather than a big datagram
+pub async fn read_udp_frame(
+    reader: &mut (impl AsyncRead + Unpin),
+) -> std::io::Result<Option<Vec<u8>>> {
+    let mut len_bytes = [0u8; 4];
+    match reader.read_exact(&mut len_bytes).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let len = u32::from_be_bytes(len_bytes) as usize;
+    if len > MAX_UDP_DATAGRAM {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("UDP frame of {} bytes exceeds {}", len, MAX_UDP_DATAGRAM),
+        ));
+    }
+
+    let mut datagram = vec![0u8; len];
+    reader.read_exact(&mut datagram).await?;
+    Ok(Some(datagram))
+}
+
+// length and payload go out in one write so a frame isn't spread over more
+// upstream messages than needed
+pub async fn write_udp_frame(
+    writer: &mut (impl AsyncWrite + Unpin), datagram: &[u8],
+) -> std::io::Result<()> {
+    if datagram.len() > MAX_UDP_DATAGRAM {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "UDP datagram of {} bytes exceeds {}",
+                datagram.len(),
+                MAX_UDP_DATAGRAM
+            ),
+        ));
+    }
+
+    let mut frame = Vec::with_capacity(4 + datagram.len());
+    frame.extend_from_slice(&(datagram.len() as u32).to_be_bytes());
+    frame.extend_from_slice(datagram);
+    writer.write_all(&frame).await?;
+    writer.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_udp_association_registry() {
+        let peer: SocketAddr = "127.0.0.1:5353".parse().unwrap();
+        let stale = register_udp_association(-161, peer);
+        let current = register_udp_association(-161, peer);
+        current.record_in(100);
+        current.record_out(40);
+        current.record_reconnect();
+
+        remove_udp_association(-161, &stale);
+        let stats = get_udp_associations(-161);
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].bytes_in, stats[0].bytes_out), (100, 40));
+        assert_eq!(stats[0].reconnects, 1);
+
+        remove_udp_association(-161, &current);
+        assert!(get_udp_associations(-161).is_empty());
+
+        assert_eq!(udp_reconnect_delay(0), Duration::from_millis(250));
+        assert_eq!(udp_reconnect_delay(10), Duration::from_secs(8));
+    }
+
+    // a client initial of quic v1, padded to the 1200 bytes the spec requires
+    fn quic_initial() -> Vec<u8> {
+        let mut packet = vec![0xc3, 0x00, 0x00, 0x00, 0x01, 0x08];
+        packet.extend_from_slice(&[0x83, 0x94, 0xc8, 0xf0, 0x3e, 0x51, 0x57, 0x08]);
+        packet.extend_from_slice(&[0x00, 0x00, 0x44, 0x9e]);
+        packet.resize(1200, 0);
+        packet
+    }
+
+    #[tokio::test]
+    async fn test_udp_frames_keep_datagram_boundaries() {
+        let datagrams = vec![
+            quic_initial(),
+            Vec::new(),
+            (0..MAX_UDP_DATAGRAM).map(|i| i as u8).collect::<Vec<u8>>(),
+            quic_initial()[..64].to_vec(),
+        ];
+
+        // a tiny pipe splits every frame over many reads
+        let (mut client, mut server) = tokio::io::duplex(61);
+        let sent = datagrams.clone();
+        let writer = tokio::spawn(async move {
+            for datagram in &sent {
+                write_udp_frame(&mut client, datagram).await.unwrap();
+            }
+        });
+
+        for datagram in &datagrams {
+            assert_eq!(
+                read_udp_frame(&mut server).await.unwrap().as_ref(),
+                Some(datagram)
+            );
+        }
+        writer.await.unwrap();
+        assert_eq!(read_udp_frame(&mut server).await.unwrap(), None);
+
+        let oversized = (MAX_UDP_DATAGRAM as u32 + 1).to_be_bytes();
+        assert!(read_udp_frame(&mut &oversized[..]).await.is_err());
+        let truncated = [0, 0, 0, 8, 1, 2];
+        assert!(read_udp_frame(&mut &truncated[..]).await.is_err());
+    }
+}
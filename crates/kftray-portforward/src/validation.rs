@@ -0,0 +1,206 @@
+use k8s_openapi::api::core::v1::{
+    Namespace,
+    Pod,
+    Service,
+};
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use kftray_commons::config::read_configs;
+use kftray_commons::models::{
+    config_model::Config,
+    diagnostic::Diagnostic,
+};
+use kftray_commons::utils::config_state::get_configs_state;
+use kftray_commons::utils::config_validation::validate_config_fields;
+use kftray_commons::utils::template::resolve_config_templates;
+use kube::api::ListParams;
+use kube::{
+    Api,
+    Client,
+};
+use log::warn;
+
+use crate::client::{
+    create_client_with_tuning,
+    KubeTuning,
+};
+use crate::pending_start::{
+    missing_target_message,
+    no_endpoints_message,
+    ready_endpoint_count,
+};
+
+pub async fn validate_config(config: &Config) -> Vec<Diagnostic> {
+    let existing = read_configs().await.unwrap_or_else(|e| {
+        warn!("Failed to read configs for validation: {}", e);
+        Vec::new()
+    });
+
+    validate_config_with_existing(config, &existing).await
+}
+
+pub async fn validate_config_with_existing(
+    config: &Config, existing: &[Config],
+) -> Vec<Diagnostic> {
+    let config = &match resolve_config_templates(config.clone()).await {
+        Ok(config) => config,
+        Err(e) => return vec![Diagnostic::error("template", e)],
+    };
+
+    let states = get_configs_state().await.unwrap_or_default();
+    let mut diagnostics = validate_config_fields(config, existing, &states);
+
+    if diagnostics
+        .iter()
+        .any(|d| d.is_error() && matches!(d.field.as_str(), "context" | "kubeconfig"))
+    {
+        return diagnostics;
+    }
+
+    let client = match create_client_with_tuning(
+        config.kubeconfig.clone(),
+        Some(&config.context),
+        KubeTuning::for_config(config),
+    )
+    .await
+    {
+        Ok((Some(client), _, contexts)) => {
+            if !contexts.is_empty() && !contexts.contains(&config.context) {
+                diagnostics.push(Diagnostic::error(
+                    "context",
+                    format!("Context '{}' not found in kubeconfig", config.context),
+                ));
+                return diagnostics;
+            }
+            client
+        }
+        Ok((None, _, _)) => {
+            diagnostics.push(Diagnostic::error(
+                "context",
+                format!("Failed to create client for context '{}'", config.context),
+            ));
+            return diagnostics;
+        }
+        Err(e) => {
+            diagnostics.push(Diagnostic::error(
+                "context",
+                format!(
+                    "Failed to create client for context '{}': {}",
+                    config.context, e
+                ),
+            ));
+            return diagnostics;
+        }
+    };
+
+    if !check_namespace(client.clone(), config, &mut diagnostics).await {
+        return diagnostics;
+    }
+
+    match config.workload_type.as_deref() {
+        Some("service") => check_service(client, config, &mut diagnostics).await,
+        Some("pod") => check_pods(client, config, &mut diagnostics).await,
+        _ => {}
+    }
+
+    diagnostics
+}
+
+async fn check_namespace(
+    client: Client, config: &Config, diagnostics: &mut Vec<Diagnostic>,
+) -> bool {
+    let namespaces: Api<Namespace> = Api::all(client);
+
+    match namespaces.get_opt(&config.namespace).await {
+        Ok(Some(_)) => true,
+        Ok(None) => {
+            diagnostics.push(Diagnostic::error(
+                "namespace",
+                format!("Namespace '{}' not found", config.namespace),
+            ));
+            false
+        }
+        Err(e) => {
+            diagnostics.push(Diagnostic::warning(
+                "namespace",
+                format!("Unable to verify namespace '{}': {}", config.namespace, e),
+            ));
+            true
+        }
+    }
+}
+
+// a config that waits for its target may be saved before the target exists
+fn missing_target(config: &Config, field: &str) -> Diagnostic {
+    match config.wait_for_target.unwrap_or_default() {
+        true => Diagnostic::warning(field, missing_target_message(config)),
+        false => Diagnostic::error(field, missing_target_message(config)),
+    }
+}
+
+async fn check_service(client: Client, config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    let service_name = config.service.clone().unwrap_or_default();
+    let services: Api<Service> = Api::namespaced(client, &config.namespace);
+
+    let service = match services.get_opt(&service_name).await {
+        Ok(Some(service)) => service,
+        Ok(None) => {
+            diagnostics.push(missing_target(config, "service"));
+            return;
+        }
+        Err(e) => {
+            diagnostics.push(Diagnostic::warning(
+                "service",
+                format!("Unable to verify service '{}': {}", service_name, e),
+            ));
+            return;
+        }
+    };
+
+    if ready_endpoint_count(&services, &service_name).await == Some(0) {
+        diagnostics.push(Diagnostic::warning(
+            "service",
+            no_endpoints_message(&service_name),
+        ));
+    }
+
+    let remote_port = i32::from(config.remote_port.unwrap_or_default());
+    let ports = service.spec.and_then(|spec| spec.ports).unwrap_or_default();
+
+    let port_exists = ports.iter().any(|port| {
+        port.port == remote_port
+            || matches!(port.target_port, Some(IntOrString::Int(target)) if target == remote_port)
+    });
+
+    if !port_exists {
+        let available = ports
+            .iter()
+            .map(|port| port.port.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        diagnostics.push(Diagnostic::error(
+            "remote_port",
+            format!(
+                "Port {} is not exposed by service '{}' (available: {})",
+                remote_port, service_name, available
+            ),
+        ));
+    }
+}
+
+async fn check_pods(client: Client, config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    let selector = config.target.clone().unwrap_or_default();
+    let pods: Api<Pod> = Api::namespaced(client, &config.namespace);
+
+    match pods.list(&ListParams::default().labels(&selector)).await {
+        Ok(list) if list.items.is_empty() => {
+            diagnostics.push(missing_target(config, "target"));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            diagnostics.push(Diagnostic::warning(
+                "target",
+                format!("Unable to list pods for selector '{}': {}", selector, e),
+            ));
+        }
+    }
+}
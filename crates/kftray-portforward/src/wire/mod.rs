@@ -0,0 +1,174 @@
+pub mod mysql;
+pub mod postgres;
+pub mod redis;
+
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use kftray_commons::models::protocol_model::StreamProtocol;
+use kftray_commons::models::query_log_model::QueryLogEntry;
+use kftray_commons::utils::access_log::now_millis;
+use kftray_commons::utils::query_log::{
+    record_query,
+    redact_sql,
+};
+use log::debug;
+pub use mysql::MysqlDecoder;
+pub use postgres::PostgresDecoder;
+pub use redis::RedisDecoder;
+
+const MAX_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
+const MAX_PENDING_QUERIES: usize = 64;
+const REDACTED: &str = "?";
+
+pub trait WireDecoder: Send + std::fmt::Debug {
+    fn client_data(&mut self, data: &[u8]);
+    fn server_data(&mut self, data: &[u8]);
+}
+
+pub fn wire_decoder(
+    protocol: StreamProtocol, config_id: i64, redact: bool,
+) -> Option<Box<dyn WireDecoder>> {
+    match protocol {
+        StreamProtocol::Postgres => Some(Box::new(PostgresDecoder::new(QueryTracker::new(
+            config_id, protocol, redact,
+        )))),
+        StreamProtocol::Mysql => Some(Box::new(MysqlDecoder::new(QueryTracker::new(
+            config_id, protocol, redact,
+        )))),
+        // commands are redacted per argument by the decoder, not as SQL
+        StreamProtocol::Redis => Some(Box::new(RedisDecoder::new(
+            QueryTracker::new(config_id, protocol, false),
+            redact,
+        ))),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+struct PendingQuery {
+    statement: String,
+    params: Vec<String>,
+    timestamp_ms: u64,
+    started: Instant,
+}
+
+#[derive(Debug)]
+pub struct QueryTracker {
+    config_id: i64,
+    protocol: StreamProtocol,
+    redact: bool,
+    pending: VecDeque<PendingQuery>,
+}
+
+impl QueryTracker {
+    pub fn new(config_id: i64, protocol: StreamProtocol, redact: bool) -> Self {
+        Self {
+            config_id,
+            protocol,
+            redact,
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn start(&mut self, statement: &str, params: Vec<String>) {
+        if self.pending.len() == MAX_PENDING_QUERIES {
+            self.pending.pop_front();
+        }
+
+        let (statement, params) = if self.redact {
+            (
+                redact_sql(statement),
+                params.iter().map(|_| REDACTED.to_string()).collect(),
+            )
+        } else {
+            (statement.to_string(), params)
+        };
+
+        self.pending.push_back(PendingQuery {
+            statement,
+            params,
+            timestamp_ms: now_millis(),
+            started: Instant::now(),
+        });
+    }
+
+    pub fn complete(&mut self, rows: Option<u64>, error: Option<String>) {
+        let Some(query) = self.pending.pop_front() else {
+            return;
+        };
+
+        let entry = QueryLogEntry {
+            config_id: self.config_id,
+            timestamp_ms: query.timestamp_ms,
+            protocol: self.protocol,
+            statement: query.statement,
+            params: query.params,
+            duration_ms: query.started.elapsed().as_millis() as u64,
+            rows,
+            error,
+        };
+
+        debug!(
+            "{} query for config {} took {}ms: {}",
+            self.protocol.as_str(),
+            self.config_id,
+            entry.duration_ms,
+            entry.statement
+        );
+        record_query(entry);
+    }
+
+    pub fn skip(&mut self) {
+        self.pending.pop_front();
+    }
+
+    pub fn discard_pending(&mut self) {
+        self.pending.clear();
+    }
+}
+
+fn read_cstr(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let end = data.iter().position(|b| *b == 0)?;
+    Some((&data[..end], &data[end + 1..]))
+}
+
+fn lossy(data: &[u8]) -> String {
+    String::from_utf8_lossy(data).into_owned()
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Reassembler {
+    buffer: Vec<u8>,
+}
+
+impl Reassembler {
+    pub(crate) fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    // `frame_len` returns the full length of the message at the start of the
+    // buffer once its header is available
+    pub(crate) fn next_frame(
+        &mut self, frame_len: impl Fn(&[u8]) -> Option<usize>,
+    ) -> Result<Option<Vec<u8>>, String> {
+        let Some(len) = frame_len(&self.buffer) else {
+            if self.buffer.len() > MAX_MESSAGE_BYTES {
+                self.buffer.clear();
+                return Err("incomplete message exceeds the decode buffer".to_string());
+            }
+            return Ok(None);
+        };
+
+        if len == 0 || len > MAX_MESSAGE_BYTES {
+            self.buffer.clear();
+            return Err(format!("invalid message length {}", len));
+        }
+
+        if self.buffer.len() < len {
+            return Ok(None);
+        }
+
+        Ok(Some(self.buffer.drain(..len).collect()))
+    }
+}
@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+
+use log::warn;
+
+use crate::wire::{
+    lossy,
+    QueryTracker,
+    Reassembler,
+    WireDecoder,
+};
+
+const CLIENT_SSL: u32 = 0x0000_0800;
+const CLIENT_DEPRECATE_EOF: u32 = 0x0100_0000;
+const SSL_REQUEST_LEN: usize = 32;
+
+const COM_QUERY: u8 = 0x03;
+const COM_STMT_PREPARE: u8 = 0x16;
+const COM_STMT_EXECUTE: u8 = 0x17;
+const COM_STMT_CLOSE: u8 = 0x19;
+
+const OK_PACKET: u8 = 0x00;
+const EOF_PACKET: u8 = 0xFE;
+const ERR_PACKET: u8 = 0xFF;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Greeting,
+    HandshakeResponse,
+    Authenticating,
+    Command,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Response {
+    Idle,
+    Result,
+    Columns { remaining: u64 },
+    ColumnsEof,
+    Rows { count: u64 },
+    Prepare { statement: String },
+    Skip { remaining: u32 },
+}
+
+#[derive(Debug)]
+pub struct MysqlDecoder {
+    tracker: QueryTracker,
+    client: Reassembler,
+    server: Reassembler,
+    phase: Phase,
+    response: Response,
+    deprecate_eof: bool,
+    disabled: bool,
+    statements: HashMap<u32, String>,
+}
+
+fn frame_len(buffer: &[u8]) -> Option<usize> {
+    let header = buffer.get(..4)?;
+    Some(4 + u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize)
+}
+
+fn read_u32(data: &[u8]) -> Option<u32> {
+    let bytes = data.get(..4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_u16(data: &[u8]) -> Option<u16> {
+    let bytes = data.get(..2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_lenenc(data: &[u8]) -> Option<u64> {
+    let (&first, rest) = data.split_first()?;
+    let width = match first {
+        0..=0xFA => return Some(first as u64),
+        0xFC => 2,
+        0xFD => 3,
+        0xFE => 8,
+        _ => return None,
+    };
+
+    let bytes = rest.get(..width)?;
+    let mut value = [0u8; 8];
+    value[..width].copy_from_slice(bytes);
+    Some(u64::from_le_bytes(value))
+}
+
+fn parse_error(payload: &[u8]) -> String {
+    let code = read_u16(&payload[1..]).unwrap_or_default();
+    let message = match payload.get(3..) {
+        Some([b'#', rest @ ..]) if rest.len() >= 5 => &rest[5..],
+        Some(rest) => rest,
+        None => &[],
+    };
+
+    format!("{} (error {})", lossy(message), code)
+}
+
+fn is_eof(payload: &[u8]) -> bool {
+    payload.first() == Some(&EOF_PACKET) && payload.len() < 9
+}
+
+impl MysqlDecoder {
+    pub fn new(tracker: QueryTracker) -> Self {
+        Self {
+            tracker,
+            client: Reassembler::default(),
+            server: Reassembler::default(),
+            phase: Phase::Greeting,
+            response: Response::Idle,
+            deprecate_eof: false,
+            disabled: false,
+            statements: HashMap::new(),
+        }
+    }
+
+    fn disable(&mut self, reason: &str) {
+        warn!("Disabling mysql query log for this connection: {}", reason);
+        self.disabled = true;
+        self.tracker.discard_pending();
+    }
+
+    fn on_client_packet(&mut self, payload: &[u8]) {
+        match self.phase {
+            Phase::HandshakeResponse => {
+                let capabilities = read_u32(payload).unwrap_or_default();
+                if capabilities & CLIENT_SSL != 0 && payload.len() == SSL_REQUEST_LEN {
+                    return self.disable("connection is encrypted");
+                }
+                self.deprecate_eof = capabilities & CLIENT_DEPRECATE_EOF != 0;
+                self.phase = Phase::Authenticating;
+            }
+            Phase::Command => {
+                let Some((&command, body)) = payload.split_first() else {
+                    return;
+                };
+
+                self.response = match command {
+                    COM_QUERY => {
+                        self.tracker.start(&lossy(body), Vec::new());
+                        Response::Result
+                    }
+                    COM_STMT_PREPARE => Response::Prepare {
+                        statement: lossy(body),
+                    },
+                    COM_STMT_EXECUTE => {
+                        let id = read_u32(body).unwrap_or_default();
+                        let statement = self
+                            .statements
+                            .get(&id)
+                            .cloned()
+                            .unwrap_or_else(|| format!("<prepared statement {}>", id));
+                        self.tracker.start(&statement, Vec::new());
+                        Response::Result
+                    }
+                    COM_STMT_CLOSE => {
+                        if let Some(id) = read_u32(body) {
+                            self.statements.remove(&id);
+                        }
+                        Response::Idle
+                    }
+                    _ => Response::Idle,
+                };
+            }
+            Phase::Greeting | Phase::Authenticating => {}
+        }
+    }
+
+    fn on_server_packet(&mut self, payload: &[u8]) {
+        match self.phase {
+            Phase::Greeting => {
+                self.phase = Phase::HandshakeResponse;
+                return;
+            }
+            Phase::Authenticating => {
+                match payload.first() {
+                    Some(&OK_PACKET) => self.phase = Phase::Command,
+                    Some(&ERR_PACKET) => self.disable("authentication failed"),
+                    _ => {}
+                }
+                return;
+            }
+            Phase::HandshakeResponse => return,
+            Phase::Command => {}
+        }
+
+        let response = std::mem::replace(&mut self.response, Response::Idle);
+        self.response = match response {
+            Response::Idle => Response::Idle,
+            Response::Result => match payload.first() {
+                Some(&OK_PACKET) => {
+                    self.tracker
+                        .complete(payload.get(1..).and_then(read_lenenc), None);
+                    Response::Idle
+                }
+                Some(&ERR_PACKET) => {
+                    self.tracker.complete(None, Some(parse_error(payload)));
+                    Response::Idle
+                }
+                _ => match read_lenenc(payload) {
+                    Some(columns) if columns > 0 => Response::Columns { remaining: columns },
+                    _ => {
+                        self.tracker.complete(None, None);
+                        Response::Idle
+                    }
+                },
+            },
+            Response::Columns { remaining } if remaining > 1 => Response::Columns {
+                remaining: remaining - 1,
+            },
+            Response::Columns { .. } if self.deprecate_eof => Response::Rows { count: 0 },
+            Response::Columns { .. } => Response::ColumnsEof,
+            Response::ColumnsEof => Response::Rows { count: 0 },
+            Response::Rows { count } if is_eof(payload) => {
+                self.tracker.complete(Some(count), None);
+                Response::Idle
+            }
+            Response::Rows { .. } if payload.first() == Some(&ERR_PACKET) => {
+                self.tracker.complete(None, Some(parse_error(payload)));
+                Response::Idle
+            }
+            Response::Rows { count } => Response::Rows { count: count + 1 },
+            Response::Prepare { statement } => match payload.first() {
+                Some(&OK_PACKET) => {
+                    if let Some(id) = read_u32(&payload[1..]) {
+                        self.statements.insert(id, statement);
+                    }
+
+                    let columns = payload.get(5..).and_then(read_u16).unwrap_or_default();
+                    let params = payload.get(7..).and_then(read_u16).unwrap_or_default();
+                    let eof_packets = if self.deprecate_eof {
+                        0
+                    } else {
+                        u32::from(columns > 0) + u32::from(params > 0)
+                    };
+
+                    match columns as u32 + params as u32 + eof_packets {
+                        0 => Response::Idle,
+                        remaining => Response::Skip { remaining },
+                    }
+                }
+                Some(&ERR_PACKET) => {
+                    self.tracker.start(&statement, Vec::new());
+                    self.tracker.complete(None, Some(parse_error(payload)));
+                    Response::Idle
+                }
+                _ => Response::Idle,
+            },
+            Response::Skip { remaining } if remaining > 1 => Response::Skip {
+                remaining: remaining - 1,
+            },
+            Response::Skip { .. } => Response::Idle,
+        };
+    }
+}
+
+impl WireDecoder for MysqlDecoder {
+    fn client_data(&mut self, data: &[u8]) {
+        if self.disabled {
+            return;
+        }
+
+        self.client.push(data);
+        loop {
+            match self.client.next_frame(frame_len) {
+                Ok(Some(frame)) => self.on_client_packet(&frame[4..]),
+                Ok(None) => break,
+                Err(e) => return self.disable(&e),
+            }
+            if self.disabled {
+                return;
+            }
+        }
+    }
+
+    fn server_data(&mut self, data: &[u8]) {
+        if self.disabled {
+            return;
+        }
+
+        self.server.push(data);
+        loop {
+            match self.server.next_frame(frame_len) {
+                Ok(Some(frame)) => self.on_server_packet(&frame[4..]),
+                Ok(None) => break,
+                Err(e) => return self.disable(&e),
+            }
+            if self.disabled {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kftray_commons::models::protocol_model::StreamProtocol;
+    use kftray_commons::utils::query_log::get_query_log;
+
+    use super::*;
+
+    fn packet(seq: u8, payload: &[u8]) -> Vec<u8> {
+        let mut packet = (payload.len() as u32).to_le_bytes()[..3].to_vec();
+        packet.push(seq);
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    fn connected(config_id: i64, redact: bool) -> MysqlDecoder {
+        let mut decoder =
+            MysqlDecoder::new(QueryTracker::new(config_id, StreamProtocol::Mysql, redact));
+
+        decoder.server_data(&packet(0, b"\x0a8.0.36\0"));
+        let mut handshake = CLIENT_DEPRECATE_EOF.to_le_bytes().to_vec();
+        handshake.extend_from_slice(&[0; 60]);
+        decoder.client_data(&packet(1, &handshake));
+        decoder.server_data(&packet(2, &[OK_PACKET, 0, 0, 2, 0, 0, 0]));
+        decoder
+    }
+
+    #[test]
+    fn test_query_result_set_rows() {
+        let config_id = 6_101;
+        let mut decoder = connected(config_id, true);
+
+        decoder.client_data(&packet(0, b"\x03SELECT id FROM orders WHERE total > 100"));
+
+        let mut server = packet(1, &[1]);
+        server.extend(packet(2, b"\x03def\0\0\0\x02id\0"));
+        server.extend(packet(3, b"\x011"));
+        server.extend(packet(4, b"\x012"));
+        server.extend(packet(5, &[EOF_PACKET, 0, 0, 2, 0, 0, 0]));
+        decoder.server_data(&server);
+
+        decoder.client_data(&packet(0, b"\x03DELETE FROM orders"));
+        decoder.server_data(&packet(1, &[OK_PACKET, 7, 0, 2, 0, 0, 0]));
+
+        decoder.client_data(&packet(0, b"\x03SELECT * FROM nope"));
+        decoder.server_data(&packet(1, b"\xff\x7a\x04#42S02Table 'nope' doesn't exist"));
+
+        let log = get_query_log(config_id, None);
+        assert_eq!(log.len(), 3);
+        assert_eq!(log[0].statement, "SELECT id FROM orders WHERE total > ?");
+        assert_eq!(log[0].rows, Some(2));
+        assert_eq!(log[1].rows, Some(7));
+        assert_eq!(
+            log[2].error.as_deref(),
+            Some("Table 'nope' doesn't exist (error 1146)")
+        );
+    }
+
+    #[test]
+    fn test_prepared_statement_execute() {
+        let config_id = 6_102;
+        let mut decoder = connected(config_id, false);
+
+        decoder.client_data(&packet(0, b"\x16UPDATE users SET name = ? WHERE id = ?"));
+        let mut server = packet(1, &[OK_PACKET, 9, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0]);
+        server.extend(packet(2, b"\x03def\0\0\0\x01?\0"));
+        server.extend(packet(3, b"\x03def\0\0\0\x01?\0"));
+        decoder.server_data(&server);
+
+        decoder.client_data(&packet(0, &[COM_STMT_EXECUTE, 9, 0, 0, 0, 0, 1, 0, 0, 0]));
+        decoder.server_data(&packet(1, &[OK_PACKET, 1, 0, 2, 0, 0, 0]));
+
+        let log = get_query_log(config_id, None);
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].statement, "UPDATE users SET name = ? WHERE id = ?");
+        assert_eq!(log[0].rows, Some(1));
+    }
+}
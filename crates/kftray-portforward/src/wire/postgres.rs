@@ -0,0 +1,384 @@
+use std::collections::{
+    HashMap,
+    VecDeque,
+};
+
+use log::warn;
+
+use crate::wire::{
+    lossy,
+    read_cstr,
+    QueryTracker,
+    Reassembler,
+    WireDecoder,
+};
+
+const PROTOCOL_V3: u32 = 196608;
+const SSL_REQUEST: u32 = 80877103;
+const GSSENC_REQUEST: u32 = 80877104;
+
+#[derive(Debug)]
+pub struct PostgresDecoder {
+    tracker: QueryTracker,
+    client: Reassembler,
+    server: Reassembler,
+    startup_done: bool,
+    awaiting_encryption_reply: bool,
+    disabled: bool,
+    statements: HashMap<String, String>,
+    portals: HashMap<String, (String, Vec<String>)>,
+    batches: VecDeque<usize>,
+    open_batch: usize,
+    completed_in_batch: usize,
+}
+
+fn read_u16(data: &[u8]) -> Option<(u16, &[u8])> {
+    let bytes = data.get(..2)?;
+    Some((u16::from_be_bytes([bytes[0], bytes[1]]), &data[2..]))
+}
+
+fn read_i32(data: &[u8]) -> Option<(i32, &[u8])> {
+    let bytes = data.get(..4)?;
+    Some((
+        i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        &data[4..],
+    ))
+}
+
+fn frame_len(buffer: &[u8], typed: bool) -> Option<usize> {
+    let offset = usize::from(typed);
+    let (len, _) = read_i32(buffer.get(offset..)?)?;
+
+    match usize::try_from(len) {
+        Ok(len) if len >= 4 => Some(offset + len),
+        _ => Some(0),
+    }
+}
+
+fn parse_bind(body: &[u8]) -> Option<(String, String, Vec<String>)> {
+    let (portal, rest) = read_cstr(body)?;
+    let (statement, rest) = read_cstr(rest)?;
+
+    let (format_count, mut rest) = read_u16(rest)?;
+    let mut formats = Vec::with_capacity(format_count as usize);
+    for _ in 0..format_count {
+        let (format, next) = read_u16(rest)?;
+        formats.push(format);
+        rest = next;
+    }
+
+    let (param_count, mut rest) = read_u16(rest)?;
+    let mut params = Vec::with_capacity(param_count as usize);
+    for index in 0..param_count as usize {
+        let (len, next) = read_i32(rest)?;
+        let binary = match formats.len() {
+            0 => false,
+            1 => formats[0] == 1,
+            _ => formats.get(index) == Some(&1),
+        };
+
+        let Ok(len) = usize::try_from(len) else {
+            params.push("NULL".to_string());
+            rest = next;
+            continue;
+        };
+
+        let value = next.get(..len)?;
+        params.push(if binary {
+            format!("<{} bytes>", len)
+        } else {
+            lossy(value)
+        });
+        rest = &next[len..];
+    }
+
+    Some((lossy(portal), lossy(statement), params))
+}
+
+fn parse_error(body: &[u8]) -> String {
+    let mut fields = HashMap::new();
+    let mut rest = body;
+
+    while let Some((&field, remaining)) = rest.split_first() {
+        if field == 0 {
+            break;
+        }
+        let Some((value, remaining)) = read_cstr(remaining) else {
+            break;
+        };
+        fields.insert(field, lossy(value));
+        rest = remaining;
+    }
+
+    let message = fields
+        .remove(&b'M')
+        .unwrap_or_else(|| "unknown error".to_string());
+    match fields.remove(&b'C') {
+        Some(code) => format!("{} (SQLSTATE {})", message, code),
+        None => message,
+    }
+}
+
+impl PostgresDecoder {
+    pub fn new(tracker: QueryTracker) -> Self {
+        Self {
+            tracker,
+            client: Reassembler::default(),
+            server: Reassembler::default(),
+            startup_done: false,
+            awaiting_encryption_reply: false,
+            disabled: false,
+            statements: HashMap::new(),
+            portals: HashMap::new(),
+            batches: VecDeque::new(),
+            open_batch: 0,
+            completed_in_batch: 0,
+        }
+    }
+
+    fn disable(&mut self, reason: &str) {
+        warn!(
+            "Disabling postgres query log for this connection: {}",
+            reason
+        );
+        self.disabled = true;
+        self.tracker.discard_pending();
+    }
+
+    fn start_query(&mut self, statement: &str, params: Vec<String>) {
+        self.tracker.start(statement, params);
+        self.open_batch += 1;
+    }
+
+    fn close_batch(&mut self) {
+        self.batches.push_back(self.open_batch);
+        self.open_batch = 0;
+    }
+
+    fn complete_query(&mut self, rows: Option<u64>, error: Option<String>) {
+        let batch_size = self.batches.front().copied().unwrap_or(self.open_batch);
+        if self.completed_in_batch < batch_size {
+            self.completed_in_batch += 1;
+            self.tracker.complete(rows, error);
+        }
+    }
+
+    fn on_startup_message(&mut self, frame: &[u8]) {
+        let Some((code, _)) = frame.get(4..).and_then(read_i32) else {
+            return;
+        };
+
+        match code as u32 {
+            PROTOCOL_V3 => self.startup_done = true,
+            SSL_REQUEST | GSSENC_REQUEST => self.awaiting_encryption_reply = true,
+            _ => {}
+        }
+    }
+
+    fn on_client_message(&mut self, frame: &[u8]) {
+        let body = &frame[5..];
+
+        match frame[0] {
+            b'Q' => {
+                if let Some((statement, _)) = read_cstr(body) {
+                    self.start_query(&lossy(statement), Vec::new());
+                    self.close_batch();
+                }
+            }
+            b'P' => {
+                if let Some((name, rest)) = read_cstr(body) {
+                    if let Some((query, _)) = read_cstr(rest) {
+                        self.statements.insert(lossy(name), lossy(query));
+                    }
+                }
+            }
+            b'B' => {
+                if let Some((portal, statement, params)) = parse_bind(body) {
+                    let query = self.statements.get(&statement).cloned().unwrap_or_default();
+                    self.portals.insert(portal, (query, params));
+                }
+            }
+            b'E' => {
+                if let Some((portal, _)) = read_cstr(body) {
+                    if let Some((query, params)) = self.portals.get(&lossy(portal)).cloned() {
+                        self.start_query(&query, params);
+                    }
+                }
+            }
+            b'S' => self.close_batch(),
+            b'C' => {
+                if let Some((&kind, rest)) = body.split_first() {
+                    if let Some((name, _)) = read_cstr(rest) {
+                        if kind == b'S' {
+                            self.statements.remove(&lossy(name));
+                        } else {
+                            self.portals.remove(&lossy(name));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_server_message(&mut self, frame: &[u8]) {
+        let body = &frame[5..];
+
+        match frame[0] {
+            b'C' => {
+                let rows = read_cstr(body).and_then(|(tag, _)| {
+                    lossy(tag)
+                        .rsplit(' ')
+                        .next()
+                        .and_then(|count| count.parse().ok())
+                });
+                self.complete_query(rows, None);
+            }
+            b'E' => self.complete_query(None, Some(parse_error(body))),
+            b'I' => self.complete_query(None, None),
+            b'Z' => {
+                let Some(batch_size) = self.batches.pop_front() else {
+                    return;
+                };
+                for _ in self.completed_in_batch..batch_size {
+                    self.tracker.skip();
+                }
+                self.completed_in_batch = 0;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl WireDecoder for PostgresDecoder {
+    fn client_data(&mut self, data: &[u8]) {
+        if self.disabled {
+            return;
+        }
+
+        self.client.push(data);
+        loop {
+            match self
+                .client
+                .next_frame(|buffer| frame_len(buffer, self.startup_done))
+            {
+                Ok(Some(frame)) if self.startup_done => self.on_client_message(&frame),
+                Ok(Some(frame)) => self.on_startup_message(&frame),
+                Ok(None) => break,
+                Err(e) => return self.disable(&e),
+            }
+        }
+    }
+
+    fn server_data(&mut self, mut data: &[u8]) {
+        if self.disabled {
+            return;
+        }
+
+        if self.awaiting_encryption_reply {
+            let Some((&reply, rest)) = data.split_first() else {
+                return;
+            };
+            self.awaiting_encryption_reply = false;
+            if reply != b'N' {
+                return self.disable("connection is encrypted");
+            }
+            data = rest;
+        }
+
+        self.server.push(data);
+        loop {
+            match self.server.next_frame(|buffer| frame_len(buffer, true)) {
+                Ok(Some(frame)) => self.on_server_message(&frame),
+                Ok(None) => break,
+                Err(e) => return self.disable(&e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kftray_commons::models::protocol_model::StreamProtocol;
+    use kftray_commons::utils::query_log::get_query_log;
+
+    use super::*;
+
+    fn message(tag: u8, body: &[u8]) -> Vec<u8> {
+        let mut message = vec![tag];
+        message.extend_from_slice(&(body.len() as u32 + 4).to_be_bytes());
+        message.extend_from_slice(body);
+        message
+    }
+
+    fn startup() -> Vec<u8> {
+        let mut body = PROTOCOL_V3.to_be_bytes().to_vec();
+        body.extend_from_slice(b"user\0app\0\0");
+        let mut message = (body.len() as u32 + 4).to_be_bytes().to_vec();
+        message.extend_from_slice(&body);
+        message
+    }
+
+    fn decoder(config_id: i64, redact: bool) -> PostgresDecoder {
+        let mut decoder = PostgresDecoder::new(QueryTracker::new(
+            config_id,
+            StreamProtocol::Postgres,
+            redact,
+        ));
+        decoder.client_data(&startup());
+        decoder.server_data(&message(b'Z', b"I"));
+        decoder
+    }
+
+    #[test]
+    fn test_extended_query_with_params() {
+        let config_id = 6_001;
+        let mut decoder = decoder(config_id, false);
+
+        let mut client = message(b'P', b"\0SELECT * FROM orders WHERE id = $1\0\0\0");
+        client.extend(message(b'B', b"\0\0\0\0\0\x01\0\0\0\x0242\0\0"));
+        client.extend(message(b'E', b"\0\0\0\0\0"));
+        client.extend(message(b'S', b""));
+        // split mid-message to exercise reassembly
+        decoder.client_data(&client[..7]);
+        decoder.client_data(&client[7..]);
+
+        let mut server = message(b'1', b"");
+        server.extend(message(b'2', b""));
+        server.extend(message(b'D', b"\0\x01\0\0\0\x0242"));
+        server.extend(message(b'C', b"SELECT 1\0"));
+        server.extend(message(b'Z', b"I"));
+        decoder.server_data(&server);
+
+        let log = get_query_log(config_id, None);
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].statement, "SELECT * FROM orders WHERE id = $1");
+        assert_eq!(log[0].params, vec!["42"]);
+        assert_eq!(log[0].rows, Some(1));
+    }
+
+    #[test]
+    fn test_simple_query_error_is_redacted() {
+        let config_id = 6_002;
+        let mut decoder = decoder(config_id, true);
+
+        decoder.client_data(&message(
+            b'Q',
+            b"SELECT * FROM users WHERE email = 'a@b.c'\0",
+        ));
+        let mut server = message(
+            b'E',
+            b"SERROR\0C42P01\0Mrelation \"users\" does not exist\0\0",
+        );
+        server.extend(message(b'Z', b"I"));
+        decoder.server_data(&server);
+
+        let log = get_query_log(config_id, None);
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].statement, "SELECT * FROM users WHERE email = ?");
+        assert_eq!(
+            log[0].error.as_deref(),
+            Some("relation \"users\" does not exist (SQLSTATE 42P01)")
+        );
+    }
+}
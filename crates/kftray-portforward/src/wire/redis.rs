@@ -0,0 +1,262 @@
+use log::warn;
+
+use crate::wire::{
+    lossy,
+    QueryTracker,
+    Reassembler,
+    WireDecoder,
+};
+
+const MAX_LOGGED_ARGS: usize = 32;
+const MAX_ARG_CHARS: usize = 128;
+const SENSITIVE_COMMANDS: &[&str] = &["AUTH", "HELLO", "MIGRATE"];
+
+#[derive(Debug)]
+pub struct RedisDecoder {
+    tracker: QueryTracker,
+    redact: bool,
+    client: Reassembler,
+    server: Reassembler,
+    disabled: bool,
+}
+
+fn line_end(buffer: &[u8]) -> Option<usize> {
+    buffer.windows(2).position(|window| window == b"\r\n")
+}
+
+fn parse_len(header: &[u8]) -> Option<i64> {
+    std::str::from_utf8(header).ok()?.parse().ok()
+}
+
+// returns `Some(0)` for input that is not valid RESP so the caller can bail
+fn value_len(buffer: &[u8]) -> Option<usize> {
+    let end = line_end(buffer)?;
+    let after = end + 2;
+    let header = &buffer[1..end];
+
+    match buffer[0] {
+        b'+' | b'-' | b':' | b'_' | b',' | b'#' | b'(' => Some(after),
+        b'$' | b'!' | b'=' => match parse_len(header) {
+            Some(len) if len < 0 => Some(after),
+            Some(len) => Some(after + len as usize + 2),
+            None => Some(0),
+        },
+        kind @ (b'*' | b'~' | b'>' | b'%' | b'|') => {
+            let Some(count) = parse_len(header) else {
+                return Some(0);
+            };
+            let count = match kind {
+                b'%' | b'|' => count.max(0) * 2,
+                _ => count.max(0),
+            };
+
+            let mut offset = after;
+            for _ in 0..count {
+                match value_len(buffer.get(offset..)?)? {
+                    0 => return Some(0),
+                    len => offset += len,
+                }
+            }
+            Some(offset)
+        }
+        _ => Some(0),
+    }
+}
+
+fn command_len(buffer: &[u8]) -> Option<usize> {
+    match buffer.first()? {
+        b'*' => value_len(buffer),
+        _ => buffer.iter().position(|b| *b == b'\n').map(|end| end + 1),
+    }
+}
+
+fn parse_command(frame: &[u8]) -> Vec<Vec<u8>> {
+    if frame.first() != Some(&b'*') {
+        return frame
+            .split(|b| b.is_ascii_whitespace())
+            .filter(|arg| !arg.is_empty())
+            .map(<[u8]>::to_vec)
+            .collect();
+    }
+
+    let mut args = Vec::new();
+    let Some(end) = line_end(frame) else {
+        return args;
+    };
+    let mut rest = &frame[end + 2..];
+
+    while let Some(end) = line_end(rest).filter(|_| rest.first() == Some(&b'$')) {
+        let Some(len) = parse_len(&rest[1..end]).and_then(|len| usize::try_from(len).ok()) else {
+            break;
+        };
+        let Some(arg) = rest.get(end + 2..end + 2 + len) else {
+            break;
+        };
+        args.push(arg.to_vec());
+        rest = rest.get(end + 4 + len..).unwrap_or_default();
+    }
+
+    args
+}
+
+fn format_arg(arg: &[u8]) -> String {
+    let arg = lossy(arg);
+    if arg.chars().count() <= MAX_ARG_CHARS {
+        return arg;
+    }
+
+    let truncated: String = arg.chars().take(MAX_ARG_CHARS).collect();
+    format!("{}...", truncated)
+}
+
+pub fn format_command(args: &[Vec<u8>], redact: bool) -> String {
+    let Some((name, args)) = args.split_first() else {
+        return String::new();
+    };
+
+    let name = lossy(name).to_uppercase();
+    let sensitive = SENSITIVE_COMMANDS.contains(&name.as_str());
+
+    let mut parts = vec![name];
+    for (index, arg) in args.iter().take(MAX_LOGGED_ARGS).enumerate() {
+        if sensitive || (redact && index > 0) {
+            parts.push("?".to_string());
+        } else {
+            parts.push(format_arg(arg));
+        }
+    }
+
+    if args.len() > MAX_LOGGED_ARGS {
+        parts.push(format!("... ({} more)", args.len() - MAX_LOGGED_ARGS));
+    }
+
+    parts.join(" ")
+}
+
+fn reply_summary(frame: &[u8]) -> (Option<u64>, Option<String>) {
+    let Some(end) = line_end(frame) else {
+        return (None, None);
+    };
+    let header = &frame[1..end];
+
+    match frame[0] {
+        b'-' => (None, Some(lossy(header))),
+        b'!' => (None, Some(lossy(&frame[end + 2..frame.len() - 2]))),
+        b'*' | b'~' | b'%' => (
+            parse_len(header).and_then(|count| u64::try_from(count).ok()),
+            None,
+        ),
+        _ => (None, None),
+    }
+}
+
+impl RedisDecoder {
+    pub fn new(tracker: QueryTracker, redact: bool) -> Self {
+        Self {
+            tracker,
+            redact,
+            client: Reassembler::default(),
+            server: Reassembler::default(),
+            disabled: false,
+        }
+    }
+
+    fn disable(&mut self, reason: &str) {
+        warn!(
+            "Disabling redis command log for this connection: {}",
+            reason
+        );
+        self.disabled = true;
+        self.tracker.discard_pending();
+    }
+}
+
+impl WireDecoder for RedisDecoder {
+    fn client_data(&mut self, data: &[u8]) {
+        if self.disabled {
+            return;
+        }
+
+        self.client.push(data);
+        loop {
+            match self.client.next_frame(command_len) {
+                Ok(Some(frame)) => {
+                    let args = parse_command(&frame);
+                    if !args.is_empty() {
+                        self.tracker
+                            .start(&format_command(&args, self.redact), Vec::new());
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => return self.disable(&e),
+            }
+        }
+    }
+
+    fn server_data(&mut self, data: &[u8]) {
+        if self.disabled {
+            return;
+        }
+
+        self.server.push(data);
+        loop {
+            match self.server.next_frame(value_len) {
+                // out-of-band pushes and attributes are not replies to a command
+                Ok(Some(frame)) if matches!(frame[0], b'>' | b'|') => {}
+                Ok(Some(frame)) => {
+                    let (rows, error) = reply_summary(&frame);
+                    self.tracker.complete(rows, error);
+                }
+                Ok(None) => break,
+                Err(e) => return self.disable(&e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kftray_commons::models::protocol_model::StreamProtocol;
+    use kftray_commons::utils::query_log::get_query_log;
+
+    use super::*;
+
+    #[test]
+    fn test_value_len() {
+        assert_eq!(value_len(b"+OK\r\n"), Some(5));
+        assert_eq!(value_len(b"$5\r\nhello\r\n"), Some(11));
+        assert_eq!(value_len(b"*2\r\n$1\r\na\r\n:1\r\n"), Some(15));
+        assert_eq!(value_len(b"*2\r\n$1\r\na\r\n"), None);
+        assert_eq!(value_len(b"$-1\r\n"), Some(5));
+        assert_eq!(value_len(b"?bogus\r\n"), Some(0));
+    }
+
+    #[test]
+    fn test_format_command_redaction() {
+        let args: Vec<Vec<u8>> = vec![b"set".to_vec(), b"user:1".to_vec(), b"secret".to_vec()];
+        assert_eq!(format_command(&args, true), "SET user:1 ?");
+        assert_eq!(format_command(&args, false), "SET user:1 secret");
+
+        let auth: Vec<Vec<u8>> = vec![b"AUTH".to_vec(), b"hunter2".to_vec()];
+        assert_eq!(format_command(&auth, false), "AUTH ?");
+    }
+
+    #[test]
+    fn test_pipelined_commands() {
+        let config_id = 6_201;
+        let mut decoder = RedisDecoder::new(
+            QueryTracker::new(config_id, StreamProtocol::Redis, false),
+            true,
+        );
+
+        decoder.client_data(b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n*2\r\n$6\r\nLRANGE");
+        decoder.client_data(b"\r\n$4\r\nlist\r\nPING\r\n");
+        decoder.server_data(b"+OK\r\n*2\r\n$1\r\na\r\n$1\r\nb\r\n-ERR unknown\r\n");
+
+        let log = get_query_log(config_id, None);
+        let statements: Vec<&str> = log.iter().map(|e| e.statement.as_str()).collect();
+        assert_eq!(statements, vec!["SET k ?", "LRANGE list", "PING"]);
+        assert_eq!(log[1].rows, Some(2));
+        assert_eq!(log[2].error.as_deref(), Some("ERR unknown"));
+    }
+}
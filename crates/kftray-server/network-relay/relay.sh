@@ -0,0 +1,13 @@
+#!/bin/sh
+# the wireguard end of the cluster network, kftray passes the peer, the
+# addresses and the key through the environment
+set -e
+printf '%s' "$WG_PRIVATE_KEY" > /tmp/wg.key
+ip link add wg0 type wireguard
+wg set wg0 listen-port "$WG_PORT" private-key /tmp/wg.key peer "$WG_PEER" allowed-ips "$WG_PEER_ADDRESS/32"
+ip address add "$WG_ADDRESS/30" dev wg0
+ip link set wg0 up
+sysctl -w net.ipv4.ip_forward=1 >/dev/null
+iptables -t nat -A POSTROUTING -s "$WG_PEER_ADDRESS/32" -j MASQUERADE
+touch /tmp/ready
+exec sleep infinity
@@ -31,8 +31,12 @@ use log::{
     info,
 };
 
+// the largest payload a udp datagram can carry, each frame holds exactly one
+// datagram behind a u32 big endian length
+const MAX_DATAGRAM_SIZE: usize = 65535;
+
 fn handle_tcp_to_udp(
-    mut tcp_stream: TcpStream, udp_socket: Arc<UdpSocket>, is_running: Arc<AtomicBool>,
+    mut tcp_stream: impl Read, udp_socket: Arc<UdpSocket>, is_running: Arc<AtomicBool>,
 ) -> io::Result<()> {
     while is_running.load(Ordering::SeqCst) {
         let size = match tcp_stream.read_u32::<BigEndian>() {
@@ -53,6 +57,15 @@ fn handle_tcp_to_udp(
             }
         };
 
+        if size > MAX_DATAGRAM_SIZE {
+            error!("TCP to UDP: Frame of {} bytes is out of sync", size);
+
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("frame of {} bytes exceeds {}", size, MAX_DATAGRAM_SIZE),
+            ));
+        }
+
         let mut buffer = vec![0u8; size];
 
         match tcp_stream.read_exact(&mut buffer) {
@@ -80,29 +93,34 @@ fn handle_tcp_to_udp(
 fn handle_udp_to_tcp(
     udp_socket: Arc<UdpSocket>, tcp_stream: Arc<Mutex<TcpStream>>, is_running: Arc<AtomicBool>,
 ) -> io::Result<()> {
-    let mut buffer = [0u8; 65535];
+    let mut buffer = vec![0u8; MAX_DATAGRAM_SIZE];
 
     while is_running.load(Ordering::SeqCst) {
         match udp_socket.recv(&mut buffer) {
             Ok(size) => {
                 debug!("UDP to TCP: Received {} bytes from UDP", size);
 
-                let mut length_buffer = vec![];
+                // length and payload in one write, so the frame leaves as a
+                // whole instead of in two pieces
+                let mut frame = Vec::with_capacity(4 + size);
 
-                length_buffer.write_u32::<BigEndian>(size as u32)?;
+                frame.write_u32::<BigEndian>(size as u32)?;
 
-                if let Ok(mut stream) = tcp_stream.lock() {
-                    debug!("UDP to TCP: Sending size to TCP client");
-
-                    stream.write_all(&length_buffer)?;
+                frame.extend_from_slice(&buffer[..size]);
 
+                if let Ok(mut stream) = tcp_stream.lock() {
                     debug!("UDP to TCP: Sending {} bytes to TCP client", size);
 
-                    stream.write_all(&buffer[..size])?;
+                    stream.write_all(&frame)?;
 
                     stream.flush()?;
                 }
             }
+            // the target wasn't listening when an earlier datagram arrived,
+            // that's no reason to drop the association
+            Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {
+                debug!("UDP to TCP: Target refused a datagram: {}", e);
+            }
             Err(e) => {
                 if e.kind() != io::ErrorKind::WouldBlock {
                     error!("UDP to TCP: Error receiving from UDP socket: {}", e);
@@ -174,3 +192,57 @@ pub fn start_udp_over_tcp_proxy(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(datagram: &[u8]) -> Vec<u8> {
+        let mut frame = vec![];
+        frame.write_u32::<BigEndian>(datagram.len() as u32).unwrap();
+        frame.extend_from_slice(datagram);
+        frame
+    }
+
+    #[test]
+    fn test_tcp_to_udp_keeps_datagram_boundaries() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        sender.connect(receiver.local_addr().unwrap()).unwrap();
+
+        // a quic initial padded to 1200 bytes and a jumbo datagram, kept under
+        // the 9216 bytes macos allows by default
+        let mut quic_initial = vec![0xc3, 0x00, 0x00, 0x00, 0x01];
+        quic_initial.resize(1200, 0);
+        let large: Vec<u8> = (0..9000).map(|i| i as u8).collect();
+
+        let mut frames = frame(&quic_initial);
+        frames.extend(frame(&large));
+        handle_tcp_to_udp(
+            io::Cursor::new(frames),
+            Arc::new(sender),
+            Arc::new(AtomicBool::new(true)),
+        )
+        .unwrap();
+
+        let mut buffer = vec![0u8; MAX_DATAGRAM_SIZE];
+        let size = receiver.recv(&mut buffer).unwrap();
+        assert_eq!(&buffer[..size], &quic_initial[..]);
+        let size = receiver.recv(&mut buffer).unwrap();
+        assert_eq!(&buffer[..size], &large[..]);
+    }
+
+    #[test]
+    fn test_tcp_to_udp_rejects_oversized_frames() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.connect(socket.local_addr().unwrap()).unwrap();
+
+        let frames = ((MAX_DATAGRAM_SIZE + 1) as u32).to_be_bytes().to_vec();
+        let result = handle_tcp_to_udp(
+            io::Cursor::new(frames),
+            Arc::new(socket),
+            Arc::new(AtomicBool::new(true)),
+        );
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,19 @@
+use kftray_commons::models::access_log_model::AccessLogEntry;
+use kftray_commons::utils::access_log::{
+    clear_access_log,
+    get_access_log,
+};
+use log::info;
+
+#[tauri::command]
+pub async fn get_access_log_cmd(
+    config_id: i64, since: Option<u64>,
+) -> Result<Vec<AccessLogEntry>, String> {
+    get_access_log(config_id, since).await
+}
+
+#[tauri::command]
+pub async fn clear_access_log_cmd(config_id: i64) -> Result<(), String> {
+    info!("Clearing access log for config {}", config_id);
+    clear_access_log(config_id).await
+}
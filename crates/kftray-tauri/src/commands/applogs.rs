@@ -0,0 +1,66 @@
+use std::sync::Mutex;
+
+use kftray_commons::utils::app_log::{
+    list_app_logs,
+    purge_app_logs,
+    read_app_log_tail,
+    tail_app_logs,
+    AppLogFile,
+};
+use lazy_static::lazy_static;
+use log::info;
+use tauri::async_runtime::JoinHandle;
+use tauri::{
+    AppHandle,
+    Manager,
+};
+
+lazy_static! {
+    static ref APP_LOG_FOLLOWER: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+}
+
+#[tauri::command]
+pub async fn list_app_logs_cmd() -> Result<Vec<AppLogFile>, String> {
+    list_app_logs()
+}
+
+#[tauri::command]
+pub async fn tail_app_logs_cmd(lines: usize) -> Result<Vec<String>, String> {
+    read_app_log_tail(lines)
+}
+
+#[tauri::command]
+pub async fn purge_app_logs_cmd() -> Result<u64, String> {
+    let freed = purge_app_logs()?;
+    info!("Purged {} bytes of app logs", freed);
+    Ok(freed)
+}
+
+// sends the last lines and then every new one as an app_log_line event
+#[tauri::command]
+pub async fn follow_app_logs_cmd(app_handle: AppHandle, lines: usize) -> Result<(), String> {
+    let mut tail = tail_app_logs(lines, true)?;
+
+    // emitting goes through the logger too, so failures here are not logged
+    // to avoid feeding the follower its own errors
+    let handle = tauri::async_runtime::spawn(async move {
+        while let Some(line) = tail.recv().await {
+            if app_handle.emit_all("app_log_line", &line).is_err() {
+                break;
+            }
+        }
+    });
+
+    if let Some(previous) = APP_LOG_FOLLOWER.lock().unwrap().replace(handle) {
+        previous.abort();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unfollow_app_logs_cmd() -> Result<(), String> {
+    if let Some(handle) = APP_LOG_FOLLOWER.lock().unwrap().take() {
+        handle.abort();
+    }
+    Ok(())
+}
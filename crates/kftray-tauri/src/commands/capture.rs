@@ -0,0 +1,23 @@
+use kftray_portforward::capture::{
+    is_capture_enabled,
+    start_capture,
+    stop_capture,
+};
+use log::info;
+
+#[tauri::command]
+pub fn start_capture_cmd(config_id: i64) -> Result<String, String> {
+    info!("Starting traffic capture for config {}", config_id);
+    start_capture(config_id).map(|path| path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn stop_capture_cmd(config_id: i64) -> Option<String> {
+    info!("Stopping traffic capture for config {}", config_id);
+    stop_capture(config_id).map(|path| path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn is_capture_enabled_cmd(config_id: i64) -> bool {
+    is_capture_enabled(config_id)
+}
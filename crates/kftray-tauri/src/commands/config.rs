@@ -1,19 +1,73 @@
+use std::sync::Arc;
+
+use kftray_commons::bulk_update::{
+    tag_configs,
+    untag_configs,
+    update_configs_bulk,
+};
 use kftray_commons::config::{
     delete_all_configs,
     delete_config,
     delete_configs,
     export_configs,
     get_config,
-    get_configs,
     import_configs,
     insert_config,
     update_config,
 };
+use kftray_commons::config_history::get_config_history;
+use kftray_commons::config_snapshot::{
+    list_snapshots,
+    rollback_to,
+};
+use kftray_commons::config_trash::{
+    list_trash,
+    purge_trash,
+    restore_config,
+};
+use kftray_commons::encryption::{
+    disable_encryption,
+    enable_encryption,
+    is_encryption_enabled,
+};
+use kftray_commons::models::bulk_update_model::{
+    ConfigChange,
+    ConfigFilter,
+};
+use kftray_commons::models::config_history_model::ConfigHistoryEntry;
 use kftray_commons::models::config_model::Config;
+use kftray_commons::models::config_snapshot_model::ConfigSnapshot;
+use kftray_commons::models::config_trash_model::TrashedConfig;
+use kftray_commons::models::diagnostic::Diagnostic;
+use kftray_commons::models::reload_model::PendingReload;
+use kftray_commons::models::response::CustomResponse;
+use kftray_commons::utils::profile::read_active_profile_configs;
+use kftray_commons::utils::search::search_configs;
+use kftray_commons::utils::settings::current_settings;
+use kftray_portforward::models::kube::HttpLogState;
+use kftray_portforward::quick_connect::{
+    quick_connect,
+    QuickConnectItem,
+};
+use kftray_portforward::reload::{
+    pending_reload,
+    pending_reloads,
+    reload_config,
+};
+use kftray_portforward::tray_menu::{
+    get_tray_menu,
+    TrayGrouping,
+    TrayMenu,
+};
+use kftray_portforward::validation::validate_config;
 use log::{
     error,
     info,
 };
+use tauri::{
+    AppHandle,
+    Manager,
+};
 
 #[tauri::command]
 pub async fn delete_config_cmd(id: i64) -> Result<(), String> {
@@ -33,6 +87,62 @@ pub async fn delete_all_configs_cmd() -> Result<(), String> {
     delete_all_configs().await
 }
 
+#[tauri::command]
+pub async fn update_configs_bulk_cmd(
+    filter: ConfigFilter, patch: serde_json::Value, dry_run: bool,
+) -> Result<Vec<ConfigChange>, String> {
+    info!(
+        "Bulk updating configs matching {:?} (dry run: {})",
+        filter, dry_run
+    );
+    update_configs_bulk(filter, patch, dry_run).await
+}
+
+#[tauri::command]
+pub async fn tag_configs_cmd(ids: Vec<i64>, tag: String) -> Result<Vec<ConfigChange>, String> {
+    info!("Tagging configs {:?} with {}", ids, tag);
+    tag_configs(ids, tag).await
+}
+
+#[tauri::command]
+pub async fn untag_configs_cmd(ids: Vec<i64>, tag: String) -> Result<Vec<ConfigChange>, String> {
+    info!("Removing tag {} from configs {:?}", tag, ids);
+    untag_configs(ids, tag).await
+}
+
+#[tauri::command]
+pub async fn get_config_history_cmd(id: i64) -> Result<Vec<ConfigHistoryEntry>, String> {
+    get_config_history(id).await
+}
+
+#[tauri::command]
+pub async fn list_trash_cmd() -> Result<Vec<TrashedConfig>, String> {
+    list_trash().await
+}
+
+#[tauri::command]
+pub async fn restore_config_cmd(id: i64) -> Result<i64, String> {
+    info!("Restoring deleted config {}", id);
+    restore_config(id).await
+}
+
+#[tauri::command]
+pub async fn purge_trash_cmd() -> Result<u64, String> {
+    info!("Emptying the trash");
+    purge_trash().await
+}
+
+#[tauri::command]
+pub async fn list_snapshots_cmd() -> Result<Vec<ConfigSnapshot>, String> {
+    list_snapshots().await
+}
+
+#[tauri::command]
+pub async fn rollback_to_snapshot_cmd(snapshot_id: i64) -> Result<usize, String> {
+    info!("Rolling configs back to snapshot {}", snapshot_id);
+    rollback_to(snapshot_id).await
+}
+
 #[tauri::command]
 pub async fn insert_config_cmd(config: Config) -> Result<(), String> {
     insert_config(config).await
@@ -41,11 +151,30 @@ pub async fn insert_config_cmd(config: Config) -> Result<(), String> {
 #[tauri::command]
 pub async fn get_configs_cmd() -> Result<Vec<Config>, String> {
     info!("get_configs called");
-    let configs = get_configs().await?;
+    let configs = read_active_profile_configs().await?;
     info!("{:?}", configs);
     Ok(configs)
 }
 
+#[tauri::command]
+pub async fn search_configs_cmd(query: String) -> Result<Vec<Config>, String> {
+    search_configs(&query).await
+}
+
+// ad-hoc targets, recent and matching configs for the command palette
+#[tauri::command]
+pub async fn quick_connect_cmd(query: String) -> Result<Vec<QuickConnectItem>, String> {
+    quick_connect(&query).await
+}
+
+// pinned configs, then one section per group, each entry with its status glyph
+#[tauri::command]
+pub async fn get_tray_menu_cmd(
+    grouping: Option<TrayGrouping>, query: Option<String>,
+) -> Result<TrayMenu, String> {
+    get_tray_menu(grouping.unwrap_or_default(), query).await
+}
+
 #[tauri::command]
 pub async fn get_config_cmd(id: i64) -> Result<Config, String> {
     info!("get_config called with id: {}", id);
@@ -53,8 +182,52 @@ pub async fn get_config_cmd(id: i64) -> Result<Config, String> {
 }
 
 #[tauri::command]
-pub async fn update_config_cmd(config: Config) -> Result<(), String> {
-    update_config(config).await
+pub async fn validate_config_cmd(config: Config) -> Result<Vec<Diagnostic>, String> {
+    info!("validate_config called for: {:?}", config.alias);
+    Ok(validate_config(&config).await)
+}
+
+#[tauri::command]
+pub async fn update_config_cmd(
+    config: Config, http_log_state: tauri::State<'_, HttpLogState>, app_handle: AppHandle,
+) -> Result<(), String> {
+    let config_id = config.id;
+    update_config(config).await?;
+
+    let Some(config_id) = config_id else {
+        return Ok(());
+    };
+
+    // a running forward keeps its old values until it is restarted
+    match pending_reload(config_id).await {
+        Ok(Some(_)) if current_settings().auto_reload_configs => {
+            if let Err(e) = reload_config(config_id, Arc::new(http_log_state.inner().clone())).await
+            {
+                error!("Failed to reload config {}: {}", config_id, e);
+            }
+        }
+        Ok(Some(pending)) => {
+            if let Err(e) = app_handle.emit_all("config_reload_pending", &pending) {
+                error!("Failed to emit reload pending event: {}", e);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => error!("Failed to check config {} for changes: {}", config_id, e),
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn reload_config_cmd(
+    config_id: i64, http_log_state: tauri::State<'_, HttpLogState>,
+) -> Result<Vec<CustomResponse>, String> {
+    reload_config(config_id, Arc::new(http_log_state.inner().clone())).await
+}
+
+#[tauri::command]
+pub async fn get_pending_reloads_cmd() -> Result<Vec<PendingReload>, String> {
+    pending_reloads().await
 }
 
 #[tauri::command]
@@ -70,3 +243,18 @@ pub async fn import_configs_cmd(json: String) -> Result<(), String> {
     }
     Ok(())
 }
+
+#[tauri::command]
+pub async fn set_config_encryption_cmd(enabled: bool) -> Result<(), String> {
+    info!("Setting config encryption: {}", enabled);
+    if enabled {
+        enable_encryption().await
+    } else {
+        disable_encryption().await
+    }
+}
+
+#[tauri::command]
+pub fn is_config_encryption_enabled_cmd() -> Result<bool, String> {
+    is_encryption_enabled()
+}
@@ -1,5 +1,22 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use kftray_commons::config_state::get_configs_state;
-use kftray_commons::models::config_state_model::ConfigState;
+use kftray_commons::models::config_state_model::{
+    ConfigState,
+    ForwardPhase,
+};
+use kftray_commons::models::reconcile_model::ReconcileReport;
+use kftray_commons::models::response::CustomResponse;
+use kftray_commons::models::session_model::SessionInfo;
+use kftray_portforward::lifecycle::get_forward_phases;
+use kftray_portforward::models::kube::HttpLogState;
+use kftray_portforward::reconcile::reconcile_config_states;
+use kftray_portforward::session::{
+    adopt_session,
+    clean_session,
+    get_sessions,
+};
 
 #[tauri::command]
 pub async fn get_config_states() -> Result<Vec<ConfigState>, String> {
@@ -8,3 +25,35 @@ pub async fn get_config_states() -> Result<Vec<ConfigState>, String> {
     log::info!("{:?}", configs);
     Ok(configs)
 }
+
+#[tauri::command]
+pub async fn reconcile_config_states_cmd() -> Result<ReconcileReport, String> {
+    reconcile_config_states().await
+}
+
+#[tauri::command]
+pub async fn get_forward_phases_cmd() -> Result<HashMap<i64, ForwardPhase>, String> {
+    Ok(get_forward_phases())
+}
+
+#[tauri::command]
+pub async fn list_sessions_cmd() -> Result<Vec<SessionInfo>, String> {
+    get_sessions().await
+}
+
+#[tauri::command]
+pub async fn clean_session_cmd(session_id: String) -> Result<ReconcileReport, String> {
+    clean_session(&session_id).await
+}
+
+#[tauri::command]
+pub async fn adopt_session_cmd(
+    session_id: String, reason: Option<String>, http_log_state: tauri::State<'_, HttpLogState>,
+) -> Result<Vec<CustomResponse>, String> {
+    adopt_session(
+        &session_id,
+        reason.as_deref(),
+        Arc::new(http_log_state.inner().clone()),
+    )
+    .await
+}
@@ -139,12 +139,10 @@ pub async fn import_configs_from_github(
         clear_existing_configs().await.map_err(|e| e.to_string())?;
     }
 
-    for config in configs {
-        let config_json = serde_json::to_string(&config)
-            .map_err(|e| format!("Failed to serialize config: {}", e))?;
-
-        import_configs(config_json).await?;
-    }
+    // imported in one go so the sync leaves a single snapshot behind
+    let configs_json = serde_json::to_string(&configs)
+        .map_err(|e| format!("Failed to serialize configs: {}", e))?;
+    import_configs(configs_json).await?;
 
     if let Err(e) = migrate_configs().await {
         error!("Error migrating configs: {}. Please check if the configurations are valid and compatible with the current system/version.", e);
@@ -0,0 +1,7 @@
+use kftray_commons::models::hook_model::HookResult;
+use kftray_portforward::hooks::get_hook_results;
+
+#[tauri::command]
+pub fn get_hook_results_cmd(config_id: i64) -> Vec<HookResult> {
+    get_hook_results(config_id)
+}
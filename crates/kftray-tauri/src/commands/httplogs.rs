@@ -1,7 +1,30 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use kftray_commons::models::http_transaction_model::{
+    HttpTransaction,
+    HttpTransactionQuery,
+};
 use kftray_commons::utils::config_dir::get_log_folder_path;
+use kftray_commons::utils::http_log_stream::subscribe_http_logs;
+use kftray_commons::utils::http_store::{
+    clear_http_transactions,
+    query_http_transactions,
+};
 use kftray_portforward::models::kube::HttpLogState;
+use lazy_static::lazy_static;
 use log::error;
 use log::info;
+use tauri::async_runtime::JoinHandle;
+use tauri::{
+    AppHandle,
+    Manager,
+};
+
+lazy_static! {
+    static ref HTTP_LOG_SUBSCRIPTIONS: Mutex<HashMap<i64, JoinHandle<()>>> =
+        Mutex::new(HashMap::new());
+}
 
 #[tauri::command]
 pub async fn set_http_logs_cmd(
@@ -194,3 +217,51 @@ fn fallback_methods(log_file_path: &str) -> Result<(), String> {
             .or_else(|_| try_open_with_editor(log_file_path, "vim"))
     }
 }
+
+#[tauri::command]
+pub async fn subscribe_http_logs_cmd(app_handle: AppHandle, config_id: i64) -> Result<(), String> {
+    let mut subscription = subscribe_http_logs(config_id);
+    let event = format!("http_log_{}", config_id);
+
+    let handle = tauri::async_runtime::spawn(async move {
+        while let Some(record) = subscription.recv().await {
+            if let Err(e) = app_handle.emit_all(&event, &record) {
+                error!("Failed to emit HTTP log record: {}", e);
+                break;
+            }
+        }
+    });
+
+    if let Some(previous) = HTTP_LOG_SUBSCRIPTIONS
+        .lock()
+        .unwrap()
+        .insert(config_id, handle)
+    {
+        previous.abort();
+    }
+
+    info!("Subscribed to HTTP logs for config {}", config_id);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unsubscribe_http_logs_cmd(config_id: i64) -> Result<(), String> {
+    if let Some(handle) = HTTP_LOG_SUBSCRIPTIONS.lock().unwrap().remove(&config_id) {
+        handle.abort();
+        info!("Unsubscribed from HTTP logs for config {}", config_id);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn query_http_transactions_cmd(
+    query: HttpTransactionQuery,
+) -> Result<Vec<HttpTransaction>, String> {
+    query_http_transactions(&query).await
+}
+
+#[tauri::command]
+pub async fn clear_http_transactions_cmd(config_id: Option<i64>) -> Result<u64, String> {
+    clear_http_transactions(config_id).await
+}
@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::sync::Mutex;
 
 use anyhow::Result;
 use k8s_openapi::api::core::v1::{
@@ -7,9 +8,47 @@ use k8s_openapi::api::core::v1::{
     Service,
 };
 use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use kftray_commons::access_audit::{
+    export_access_audit,
+    list_access_audit,
+};
 use kftray_commons::config_model::Config;
+use kftray_commons::context_settings::{
+    delete_context_settings,
+    list_context_settings,
+    save_context_settings,
+};
+use kftray_commons::models::access_audit_model::AccessAuditEntry;
+use kftray_commons::models::bulk_update_model::ConfigChange;
+use kftray_commons::models::cluster_probe_model::ContextProbe;
+use kftray_commons::models::context_settings_model::ContextSettings;
+use kftray_commons::models::tool_import_model::ToolImport;
+use kftray_portforward::cancel::CancelToken;
 use kftray_portforward::client::create_client_with_specific_context;
+use kftray_portforward::cloud::{
+    add_cloud_cluster,
+    list_cloud_clusters,
+    refresh_cloud_clusters,
+    CloudCluster,
+    ManagedContext,
+};
+use kftray_portforward::cluster_probe::{
+    get_context_probes,
+    probe_context,
+};
+use kftray_portforward::context_migration::{
+    find_broken_contexts,
+    remap_context,
+    BrokenContext,
+};
 use kftray_portforward::core::retrieve_service_configs;
+use kftray_portforward::discovery::{
+    discover_config_diff,
+    import_discovery_diff,
+    retrieve_service_configs_multi,
+    ContextDiscovery,
+    DiscoveryDiffEntry,
+};
 use kftray_portforward::models::kube::{
     KubeContextInfo,
     KubeNamespaceInfo,
@@ -17,6 +56,16 @@ use kftray_portforward::models::kube::{
     KubeServicePortInfo,
     PodInfo,
 };
+use kftray_portforward::teleport::{
+    get_teleport_logins,
+    teleport_login,
+    TeleportLogin,
+};
+use kftray_portforward::tool_import::{
+    import_k9s,
+    import_kubefwd,
+    import_lens,
+};
 use kube::Resource;
 use kube::{
     api::{
@@ -25,36 +74,25 @@ use kube::{
     },
     ResourceExt,
 };
-use log::info;
+use lazy_static::lazy_static;
+use log::{
+    error,
+    info,
+};
+use tauri::{
+    AppHandle,
+    Manager,
+};
+
+lazy_static! {
+    static ref DISCOVERY_CANCEL: Mutex<Option<CancelToken>> = Mutex::new(None);
+}
 
 #[tauri::command]
 pub async fn list_kube_contexts(
     kubeconfig: Option<String>,
 ) -> Result<Vec<KubeContextInfo>, String> {
-    info!("list_kube_contexts {}", kubeconfig.as_deref().unwrap_or(""));
-
-    let (_, kubeconfig, contexts) = create_client_with_specific_context(kubeconfig, None)
-        .await
-        .map_err(|err| format!("Failed to create client: {}", err))?;
-
-    if let Some(kubeconfig) = kubeconfig {
-        let contexts: Vec<KubeContextInfo> = kubeconfig
-            .contexts
-            .into_iter()
-            .map(|c| KubeContextInfo { name: c.name })
-            .collect();
-
-        Ok(contexts)
-    } else if !contexts.is_empty() {
-        let context_infos: Vec<KubeContextInfo> = contexts
-            .into_iter()
-            .map(|name| KubeContextInfo { name })
-            .collect();
-
-        Ok(context_infos)
-    } else {
-        Err("Failed to retrieve kubeconfig".to_string())
-    }
+    kftray_portforward::client::list_kube_contexts(kubeconfig).await
 }
 
 #[tauri::command]
@@ -289,3 +327,148 @@ pub async fn get_services_with_annotations(
 
     retrieve_service_configs(&context_name, kubeconfig_path).await
 }
+
+// progress goes out as "discovery_progress" events while the contexts are
+// scanned
+#[tauri::command]
+pub async fn get_services_with_annotations_multi(
+    app_handle: AppHandle, contexts: Vec<String>, kubeconfig_path: Option<String>,
+) -> Result<Vec<ContextDiscovery>, String> {
+    let cancel = CancelToken::new();
+    if let Some(previous) = DISCOVERY_CANCEL.lock().unwrap().replace(cancel.clone()) {
+        previous.cancel();
+    }
+
+    Ok(
+        retrieve_service_configs_multi(contexts, kubeconfig_path, cancel, |progress| {
+            if let Err(e) = app_handle.emit_all("discovery_progress", &progress) {
+                error!("Failed to emit discovery progress: {}", e);
+            }
+        })
+        .await,
+    )
+}
+
+#[tauri::command]
+pub async fn cancel_discovery_cmd() -> Result<bool, String> {
+    Ok(match DISCOVERY_CANCEL.lock().unwrap().take() {
+        Some(cancel) => {
+            cancel.cancel();
+            true
+        }
+        None => false,
+    })
+}
+
+#[tauri::command]
+pub async fn get_discovery_diff_cmd(
+    context_name: String, kubeconfig_path: Option<String>,
+) -> Result<Vec<DiscoveryDiffEntry>, String> {
+    discover_config_diff(&context_name, kubeconfig_path).await
+}
+
+#[tauri::command]
+pub async fn import_discovery_diff_cmd(entries: Vec<DiscoveryDiffEntry>) -> Result<usize, String> {
+    import_discovery_diff(entries).await
+}
+
+// the importers only translate, the configs are saved like discovered ones
+#[tauri::command]
+pub async fn import_lens_cmd(
+    context_name: String, kubeconfig_path: Option<String>, store_path: Option<String>,
+) -> Result<ToolImport, String> {
+    info!("Importing Lens port forwards into context {}", context_name);
+    import_lens(store_path.as_deref(), &context_name, kubeconfig_path).await
+}
+
+#[tauri::command]
+pub async fn import_kubefwd_cmd(
+    command: String, context_name: String, kubeconfig_path: Option<String>,
+) -> Result<ToolImport, String> {
+    info!("Importing kubefwd command: {}", command);
+    import_kubefwd(&command, &context_name, kubeconfig_path).await
+}
+
+#[tauri::command]
+pub async fn import_k9s_cmd(
+    context_name: String, kubeconfig_path: Option<String>,
+) -> Result<ToolImport, String> {
+    info!("Importing k9s port forwards from context {}", context_name);
+    import_k9s(&context_name, kubeconfig_path).await
+}
+
+#[tauri::command]
+pub async fn probe_context_cmd(
+    context_name: String, kubeconfig: Option<String>,
+) -> Result<ContextProbe, String> {
+    Ok(probe_context(&context_name, kubeconfig).await)
+}
+
+#[tauri::command]
+pub async fn get_context_probes_cmd() -> Result<Vec<ContextProbe>, String> {
+    Ok(get_context_probes())
+}
+
+#[tauri::command]
+pub async fn get_teleport_logins_cmd() -> Result<Vec<TeleportLogin>, String> {
+    Ok(get_teleport_logins())
+}
+
+#[tauri::command]
+pub async fn teleport_login_cmd(context_name: String) -> Result<(), String> {
+    teleport_login(&context_name).await
+}
+
+#[tauri::command]
+pub async fn add_cloud_cluster_cmd(cluster: CloudCluster) -> Result<ManagedContext, String> {
+    add_cloud_cluster(cluster).await
+}
+
+#[tauri::command]
+pub async fn list_cloud_clusters_cmd() -> Result<Vec<ManagedContext>, String> {
+    list_cloud_clusters().await
+}
+
+#[tauri::command]
+pub async fn refresh_cloud_clusters_cmd() -> Result<Vec<ManagedContext>, String> {
+    refresh_cloud_clusters().await
+}
+
+#[tauri::command]
+pub async fn list_context_settings_cmd() -> Result<Vec<ContextSettings>, String> {
+    list_context_settings().await
+}
+
+#[tauri::command]
+pub async fn save_context_settings_cmd(settings: ContextSettings) -> Result<(), String> {
+    save_context_settings(settings).await
+}
+
+#[tauri::command]
+pub async fn delete_context_settings_cmd(context_name: String) -> Result<(), String> {
+    delete_context_settings(&context_name).await
+}
+
+#[tauri::command]
+pub async fn list_access_audit_cmd(
+    context_name: Option<String>,
+) -> Result<Vec<AccessAuditEntry>, String> {
+    list_access_audit(context_name.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn export_access_audit_cmd(format: String) -> Result<String, String> {
+    export_access_audit(&format).await
+}
+
+#[tauri::command]
+pub async fn find_broken_contexts_cmd() -> Result<Vec<BrokenContext>, String> {
+    find_broken_contexts().await
+}
+
+#[tauri::command]
+pub async fn remap_context_cmd(
+    kubeconfig: Option<String>, from_context: String, to_context: String, dry_run: bool,
+) -> Result<Vec<ConfigChange>, String> {
+    remap_context(kubeconfig, &from_context, &to_context, dry_run).await
+}
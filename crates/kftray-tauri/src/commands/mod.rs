@@ -1,7 +1,18 @@
+pub mod access_log;
+pub mod applogs;
+pub mod capture;
 pub mod config;
 pub mod config_state;
 pub mod github;
+pub mod hooks;
 pub mod httplogs;
 pub mod kubecontext;
+pub mod podlogs;
 pub mod portforward;
+pub mod profile;
+pub mod query_log;
+pub mod settings;
+pub mod stats;
+pub mod usage;
+pub mod variables;
 pub mod window_state;
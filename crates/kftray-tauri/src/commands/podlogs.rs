@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use futures::StreamExt;
+use kftray_commons::models::pod_status_model::PodLogLine;
+use kftray_portforward::pod_logs::stream_pod_logs;
+use lazy_static::lazy_static;
+use log::{
+    error,
+    info,
+};
+use tauri::async_runtime::JoinHandle;
+use tauri::{
+    AppHandle,
+    Manager,
+};
+
+lazy_static! {
+    static ref POD_LOG_STREAMS: Mutex<HashMap<i64, JoinHandle<()>>> = Mutex::new(HashMap::new());
+}
+
+#[tauri::command]
+pub async fn stream_pod_logs_cmd(
+    app_handle: AppHandle, config_id: i64, follow: bool, tail_lines: Option<i64>,
+) -> Result<String, String> {
+    let mut stream = stream_pod_logs(config_id, follow, tail_lines).await?;
+    let pod_name = stream.pod_name.clone();
+    let event = format!("pod_logs_{}", config_id);
+
+    let handle = tauri::async_runtime::spawn(async move {
+        while let Some(line) = stream.lines.next().await {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    error!("Pod log stream for config {} failed: {}", config_id, e);
+                    break;
+                }
+            };
+
+            let record = PodLogLine {
+                config_id,
+                pod_name: stream.pod_name.clone(),
+                line,
+            };
+            if let Err(e) = app_handle.emit_all(&event, &record) {
+                error!("Failed to emit pod log line: {}", e);
+                break;
+            }
+        }
+    });
+
+    if let Some(previous) = POD_LOG_STREAMS.lock().unwrap().insert(config_id, handle) {
+        previous.abort();
+    }
+
+    info!(
+        "Streaming logs of pod {} for config {}",
+        pod_name, config_id
+    );
+    Ok(pod_name)
+}
+
+#[tauri::command]
+pub async fn stop_pod_logs_cmd(config_id: i64) -> Result<(), String> {
+    if let Some(handle) = POD_LOG_STREAMS.lock().unwrap().remove(&config_id) {
+        handle.abort();
+        info!("Stopped pod log stream for config {}", config_id);
+    }
+
+    Ok(())
+}
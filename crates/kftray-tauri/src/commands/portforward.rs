@@ -1,21 +1,145 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use kftray_commons::config::get_configs;
 use kftray_commons::models::config_model::Config;
-use kftray_commons::models::response::CustomResponse;
+use kftray_commons::models::response::{
+    CustomResponse,
+    ForwardOutcome,
+};
 use kftray_commons::utils::config_state::get_configs_state;
+use kftray_commons::utils::profile::read_active_profile_configs;
+use kftray_commons::utils::usage::pinned_config_ids;
+use kftray_portforward::adhoc::{
+    list_adhoc_forwards,
+    start_adhoc_forward,
+    stop_adhoc_forward,
+    AdhocForwardSpec,
+};
+use kftray_portforward::audit::start_with_reason;
+use kftray_portforward::benchmark::{
+    benchmark_forward,
+    BenchmarkReport,
+};
+use kftray_portforward::cancel::{
+    cancel_start,
+    CancelToken,
+};
+use kftray_portforward::circuit_breaker::{
+    get_open_circuits,
+    subscribe_circuit_events,
+    CircuitStatus,
+};
+use kftray_portforward::cluster_network::{
+    get_cluster_network,
+    plan_cluster_network,
+    start_cluster_network,
+    stop_cluster_network,
+    ClusterNetwork,
+    ClusterNetworkPlan,
+    ClusterNetworkRequest,
+};
+use kftray_portforward::compose_export::export_compose_override;
+use kftray_portforward::context_group::{
+    get_offline_contexts,
+    mark_context_offline,
+    mark_context_online,
+    start_all_for_context,
+    stop_all_for_context,
+    OfflineContext,
+};
 use kftray_portforward::core::{
-    deploy_and_forward_pod,
-    start_port_forward,
+    deploy_and_forward_pod_with_cancel,
+    prewarm_proxy_pods,
+    start_port_forward_with_cancel,
     stop_all_port_forward,
     stop_port_forward,
     stop_proxy_forward,
+    StartOptions,
 };
+use kftray_portforward::expiry::{
+    get_forward_expiries,
+    set_forward_ttl,
+    subscribe_expiry_events,
+    ForwardExpiry,
+};
+use kftray_portforward::external_forward::{
+    adopt_external_forward,
+    list_external_forwards,
+    Adoption,
+    ExternalForward,
+};
+use kftray_portforward::fanout::{
+    get_fan_out_forwards,
+    FanOutForward,
+};
+use kftray_portforward::kubectl_export::export_kubectl;
+use kftray_portforward::mappings::{
+    get_forward_mappings,
+    subscribe_forward_mappings,
+    ForwardMappings,
+};
+use kftray_portforward::mdns::get_advertised_forwards;
 use kftray_portforward::models::kube::HttpLogState;
+use kftray_portforward::outcome::{
+    forward_outcomes,
+    summarize_outcomes,
+};
+use kftray_portforward::overrides::{
+    get_forward_overrides,
+    start_with_overrides,
+    ForwardOverrides,
+};
+use kftray_portforward::pac::{
+    pac_proxy_address,
+    pac_routes,
+    pac_url,
+    start_pac_proxy,
+    stop_pac_proxy,
+    PacRoute,
+};
+use kftray_portforward::pending_start::{
+    cancel_pending_start,
+    get_pending_starts,
+    PendingStart,
+};
+use kftray_portforward::port_manifest::{
+    get_port_manifest,
+    subscribe_port_manifest,
+    PortManifest,
+};
+use kftray_portforward::progress::subscribe_progress;
+use kftray_portforward::shutdown::{
+    shutdown,
+    SHUTDOWN_GRACE,
+};
+use kftray_portforward::smoke_test::{
+    test_forward,
+    ForwardTestReport,
+};
+use kftray_portforward::ssh_mirror::{
+    get_ssh_mirrors,
+    SshMirror,
+};
+use kftray_portforward::target_switch::{
+    get_forward_switches,
+    switch_forward_target,
+    ForwardSwitch,
+};
+use kftray_portforward::teleport::get_teleport_logins;
+use kftray_portforward::traffic_split::{
+    get_traffic_splits,
+    TrafficSplit,
+};
+use kftray_portforward::udp::{
+    get_udp_associations,
+    UdpAssociationStats,
+};
 use log::error;
 use log::info;
+use tauri::api::notification::Notification;
 use tauri::AppHandle;
 use tauri::Manager;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::Mutex;
 use tokio::time::{
     interval,
@@ -38,7 +162,7 @@ pub async fn check_and_emit_changes(app_handle: AppHandle) {
             }
         };
 
-        let current_configs = match get_configs().await {
+        let current_configs = match read_active_profile_configs().await {
             Ok(configs) => configs,
             Err(e) => {
                 error!("Failed to get configs: {}", e);
@@ -66,6 +190,123 @@ pub async fn check_and_emit_changes(app_handle: AppHandle) {
     }
 }
 
+// lets the ui show "upstream failing" as soon as a circuit opens, and clear
+// it again once the upstream is back
+pub async fn emit_circuit_events(app_handle: AppHandle) {
+    let mut events = subscribe_circuit_events();
+    loop {
+        match events.recv().await {
+            Ok(status) => {
+                if let Err(e) = app_handle.emit_all("upstream_circuit_changed", &status) {
+                    error!("Failed to emit circuit event: {}", e);
+                }
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                error!("Circuit event listener lagged, skipped {} events", skipped);
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+// a time limit on the start request applies to every config it started
+fn apply_ttl(
+    configs: &[Config], ttl_secs: Option<u64>, result: &Result<Vec<CustomResponse>, String>,
+) {
+    let (Ok(responses), Some(ttl_secs)) = (result, ttl_secs.filter(|secs| *secs > 0)) else {
+        return;
+    };
+    // a start without fail fast answers the configs that failed too
+    let failed: Vec<i64> = responses
+        .iter()
+        .filter(|response| response.status != 0)
+        .filter_map(|response| response.id)
+        .collect();
+    for config_id in configs.iter().filter_map(|config| config.id) {
+        if !failed.contains(&config_id) {
+            set_forward_ttl(config_id, Duration::from_secs(ttl_secs));
+        }
+    }
+}
+
+pub async fn emit_expiry_events(app_handle: AppHandle) {
+    let mut events = subscribe_expiry_events();
+    loop {
+        match events.recv().await {
+            Ok(expiry) => {
+                if let Err(e) = app_handle.emit_all("forward_expired", &expiry) {
+                    error!("Failed to emit expiry event: {}", e);
+                }
+                if let Err(e) = Notification::new(&app_handle.config().tauri.bundle.identifier)
+                    .title("Port forward expired")
+                    .body(format!(
+                        "Config {} reached its time limit of {}s and was stopped",
+                        expiry.config_id, expiry.ttl_secs
+                    ))
+                    .show()
+                {
+                    error!("Failed to show expiry notification: {}", e);
+                }
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                error!("Expiry event listener lagged, skipped {} events", skipped);
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+// start-all, stop-all, discovery and proxy deployments report each step so
+// the ui can show a progress bar for them
+pub async fn emit_progress_events(app_handle: AppHandle) {
+    let mut events = subscribe_progress();
+    loop {
+        match events.recv().await {
+            Ok(progress) => {
+                if let Err(e) = app_handle.emit_all("operation_progress", &progress) {
+                    error!("Failed to emit progress event: {}", e);
+                }
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                error!("Progress event listener lagged, skipped {} events", skipped);
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+// every message carries the full set, so a lagged listener only has to wait
+// for the next one
+pub async fn emit_mapping_events(app_handle: AppHandle) {
+    let mut events = subscribe_forward_mappings();
+    loop {
+        match events.recv().await {
+            Ok(mappings) => {
+                if let Err(e) = app_handle.emit_all("forward_mappings_changed", &mappings) {
+                    error!("Failed to emit forward mappings: {}", e);
+                }
+            }
+            Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+pub async fn emit_port_manifest_events(app_handle: AppHandle) {
+    let mut events = subscribe_port_manifest();
+    loop {
+        match events.recv().await {
+            Ok(manifest) => {
+                if let Err(e) = app_handle.emit_all("port_manifest_changed", &manifest) {
+                    error!("Failed to emit port manifest: {}", e);
+                }
+            }
+            Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
 fn config_compare_changes<T: PartialEq>(prev: &[T], current: &[T]) -> bool {
     if prev.len() != current.len() {
         return false;
@@ -80,30 +321,112 @@ fn config_compare_changes<T: PartialEq>(prev: &[T], current: &[T]) -> bool {
     true
 }
 
+// lets the ui offer a login for contexts whose teleport session expired
+fn emit_teleport_logins(app_handle: &AppHandle) {
+    for login in get_teleport_logins() {
+        if let Err(e) = app_handle.emit_all("teleport_login_required", &login) {
+            error!("Failed to emit teleport login event: {}", e);
+        }
+    }
+}
+
 #[tauri::command]
 pub async fn start_port_forward_udp_cmd(
     configs: Vec<Config>, http_log_state: tauri::State<'_, HttpLogState>,
-    _app_handle: tauri::AppHandle,
+    app_handle: tauri::AppHandle, ttl_secs: Option<u64>, reason: Option<String>,
+    fail_fast: Option<bool>, force: Option<bool>,
 ) -> Result<Vec<CustomResponse>, String> {
-    start_port_forward(
+    let options = StartOptions {
+        fail_fast: fail_fast.unwrap_or(true),
+        force: force.unwrap_or_default(),
+    };
+    let start = start_port_forward_with_cancel(
         configs.clone(),
         "udp",
         Arc::new(http_log_state.inner().clone()),
-    )
-    .await
+        CancelToken::new(),
+        options,
+    );
+    let result = start_with_reason(&configs, reason.as_deref(), start).await;
+    if result.is_err() {
+        emit_teleport_logins(&app_handle);
+    }
+    apply_ttl(&configs, ttl_secs, &result);
+    result
 }
 
 #[tauri::command]
 pub async fn start_port_forward_tcp_cmd(
     configs: Vec<Config>, http_log_state: tauri::State<'_, HttpLogState>,
-    _app_handle: tauri::AppHandle,
+    app_handle: tauri::AppHandle, ttl_secs: Option<u64>, reason: Option<String>,
+    fail_fast: Option<bool>, force: Option<bool>,
 ) -> Result<Vec<CustomResponse>, String> {
-    start_port_forward(
+    let options = StartOptions {
+        fail_fast: fail_fast.unwrap_or(true),
+        force: force.unwrap_or_default(),
+    };
+    let start = start_port_forward_with_cancel(
         configs.clone(),
         "tcp",
         Arc::new(http_log_state.inner().clone()),
-    )
-    .await
+        CancelToken::new(),
+        options,
+    );
+    let result = start_with_reason(&configs, reason.as_deref(), start).await;
+    if result.is_err() {
+        emit_teleport_logins(&app_handle);
+    }
+    apply_ttl(&configs, ttl_secs, &result);
+    result
+}
+
+// the starts above, answered with typed outcomes instead of raw responses
+#[tauri::command]
+pub async fn start_port_forward_outcomes_cmd(
+    configs: Vec<Config>, protocol: String, http_log_state: tauri::State<'_, HttpLogState>,
+    app_handle: tauri::AppHandle, ttl_secs: Option<u64>, reason: Option<String>,
+    fail_fast: Option<bool>, force: Option<bool>,
+) -> Result<Vec<ForwardOutcome>, String> {
+    let config_ids: Vec<i64> = configs.iter().filter_map(|config| config.id).collect();
+    let result = match protocol.as_str() {
+        "udp" => {
+            start_port_forward_udp_cmd(
+                configs,
+                http_log_state,
+                app_handle,
+                ttl_secs,
+                reason,
+                fail_fast,
+                force,
+            )
+            .await
+        }
+        _ => {
+            start_port_forward_tcp_cmd(
+                configs,
+                http_log_state,
+                app_handle,
+                ttl_secs,
+                reason,
+                fail_fast,
+                force,
+            )
+            .await
+        }
+    };
+    Ok(summarize_outcomes(forward_outcomes(&result, &config_ids)))
+}
+
+#[tauri::command]
+pub async fn stop_port_forward_outcome_cmd(config_id: String) -> Result<ForwardOutcome, String> {
+    let parsed_id = config_id.parse::<i64>().ok();
+    Ok(match stop_port_forward(config_id).await {
+        Ok(response) => ForwardOutcome::from(&response),
+        Err(error) => ForwardOutcome::Failed {
+            config_id: parsed_id,
+            error,
+        },
+    })
 }
 
 #[tauri::command]
@@ -123,9 +446,75 @@ pub async fn stop_port_forward_cmd(
 #[tauri::command]
 pub async fn deploy_and_forward_pod_cmd(
     configs: Vec<Config>, http_log_state: tauri::State<'_, HttpLogState>,
-    _app_handle: tauri::AppHandle,
+    app_handle: tauri::AppHandle, ttl_secs: Option<u64>, reason: Option<String>,
+    fail_fast: Option<bool>, force: Option<bool>,
 ) -> Result<Vec<CustomResponse>, String> {
-    deploy_and_forward_pod(configs.clone(), Arc::new(http_log_state.inner().clone())).await
+    let options = StartOptions {
+        fail_fast: fail_fast.unwrap_or(true),
+        force: force.unwrap_or_default(),
+    };
+    let start = deploy_and_forward_pod_with_cancel(
+        configs.clone(),
+        Arc::new(http_log_state.inner().clone()),
+        CancelToken::new(),
+        options,
+    );
+    let result = start_with_reason(&configs, reason.as_deref(), start).await;
+    if result.is_err() {
+        emit_teleport_logins(&app_handle);
+    }
+    apply_ttl(&configs, ttl_secs, &result);
+    result
+}
+
+// rolls back the in-flight start the config is part of
+#[tauri::command]
+pub async fn cancel_start_cmd(config_id: i64) -> Result<bool, String> {
+    Ok(cancel_start(config_id))
+}
+
+#[tauri::command]
+pub async fn test_forward_cmd(
+    config_id: i64, reason: Option<String>, http_log_state: tauri::State<'_, HttpLogState>,
+) -> Result<ForwardTestReport, String> {
+    let http_log_state = Arc::new(http_log_state.inner().clone());
+    test_forward(config_id, reason.as_deref(), http_log_state).await
+}
+
+#[tauri::command]
+pub async fn benchmark_forward_cmd(
+    config_id: i64, duration_secs: Option<u64>,
+) -> Result<BenchmarkReport, String> {
+    let duration = Duration::from_secs(duration_secs.unwrap_or(10));
+    benchmark_forward(config_id, duration).await
+}
+
+// the running forwards when no configs are picked
+#[tauri::command]
+pub async fn export_kubectl_cmd(
+    config_ids: Option<Vec<i64>>, script: Option<bool>,
+) -> Result<String, String> {
+    export_kubectl(config_ids, script.unwrap_or(false)).await
+}
+
+#[tauri::command]
+pub async fn get_forward_mappings_cmd() -> Result<ForwardMappings, String> {
+    get_forward_mappings().await
+}
+
+#[tauri::command]
+pub async fn get_port_manifest_cmd() -> Result<PortManifest, String> {
+    get_port_manifest().await
+}
+
+#[tauri::command]
+pub async fn export_compose_override_cmd(services: Option<Vec<String>>) -> Result<String, String> {
+    export_compose_override(services.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn get_forward_expiries_cmd() -> Result<Vec<ForwardExpiry>, String> {
+    Ok(get_forward_expiries())
 }
 
 #[tauri::command]
@@ -139,6 +528,258 @@ pub async fn stop_proxy_forward_cmd(
     stop_proxy_forward(config_id, namespace, service_name).await
 }
 
+#[tauri::command]
+pub async fn start_all_for_context_cmd(
+    context_name: String, reason: Option<String>, http_log_state: tauri::State<'_, HttpLogState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<CustomResponse>, String> {
+    let result = start_all_for_context(
+        &context_name,
+        reason.as_deref(),
+        Arc::new(http_log_state.inner().clone()),
+    )
+    .await;
+    if result.is_err() {
+        emit_teleport_logins(&app_handle);
+    }
+    result
+}
+
+// parks a relay pod for each context and namespace of the proxy configs,
+// when the warm pool is on
+#[tauri::command]
+pub async fn prewarm_proxy_pods_cmd(config_ids: Vec<i64>) -> Result<usize, String> {
+    let configs = read_active_profile_configs()
+        .await?
+        .into_iter()
+        .filter(|config| config.id.is_some_and(|id| config_ids.contains(&id)))
+        .collect();
+    prewarm_proxy_pods(configs).await
+}
+
+// the pinned proxy configs are the ones toggled often enough to keep warm
+pub async fn prewarm_pinned_proxy_pods() {
+    let pinned = match pinned_config_ids().await {
+        Ok(pinned) => pinned,
+        Err(e) => {
+            error!("Failed to read pinned configs to prewarm: {}", e);
+            return;
+        }
+    };
+    let configs = match read_active_profile_configs().await {
+        Ok(configs) => configs
+            .into_iter()
+            .filter(|config| config.id.is_some_and(|id| pinned.contains(&id)))
+            .collect(),
+        Err(e) => {
+            error!("Failed to read configs to prewarm: {}", e);
+            return;
+        }
+    };
+    match prewarm_proxy_pods(configs).await {
+        Ok(warmed) => info!("Prewarmed {} proxy pod(s)", warmed),
+        Err(e) => error!("Failed to prewarm proxy pods: {}", e),
+    }
+}
+
+// kubectl port-forwards started by hand, next to the configs they clash with
+#[tauri::command]
+pub async fn list_external_forwards_cmd() -> Result<Vec<ExternalForward>, String> {
+    list_external_forwards().await
+}
+
+#[tauri::command]
+pub async fn adopt_external_forward_cmd(
+    pid: u32, context_name: Option<String>, kubeconfig_path: Option<String>,
+    http_log_state: tauri::State<'_, HttpLogState>,
+) -> Result<Adoption, String> {
+    info!("Adopting kubectl port-forward process {}", pid);
+    adopt_external_forward(
+        pid,
+        context_name,
+        kubeconfig_path,
+        Arc::new(http_log_state.inner().clone()),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn stop_all_for_context_cmd(context_name: String) -> Result<Vec<CustomResponse>, String> {
+    stop_all_for_context(&context_name).await
+}
+
+#[tauri::command]
+pub async fn mark_context_offline_cmd(context_name: String) -> Result<Vec<i64>, String> {
+    mark_context_offline(&context_name).await
+}
+
+#[tauri::command]
+pub async fn mark_context_online_cmd(
+    context_name: String, reason: Option<String>, http_log_state: tauri::State<'_, HttpLogState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<CustomResponse>, String> {
+    let result = mark_context_online(
+        &context_name,
+        reason.as_deref(),
+        Arc::new(http_log_state.inner().clone()),
+    )
+    .await;
+    if result.is_err() {
+        emit_teleport_logins(&app_handle);
+    }
+    result
+}
+
+#[tauri::command]
+pub async fn get_offline_contexts_cmd() -> Result<Vec<OfflineContext>, String> {
+    Ok(get_offline_contexts())
+}
+
+#[tauri::command]
+pub async fn start_with_overrides_cmd(
+    config_id: i64, overrides: ForwardOverrides, reason: Option<String>,
+    http_log_state: tauri::State<'_, HttpLogState>, app_handle: tauri::AppHandle,
+) -> Result<Vec<CustomResponse>, String> {
+    let result = start_with_overrides(
+        config_id,
+        overrides,
+        reason.as_deref(),
+        Arc::new(http_log_state.inner().clone()),
+    )
+    .await;
+    if result.is_err() {
+        emit_teleport_logins(&app_handle);
+    }
+    result
+}
+
+#[tauri::command]
+pub async fn get_forward_overrides_cmd(config_id: i64) -> Result<Option<ForwardOverrides>, String> {
+    Ok(get_forward_overrides(config_id))
+}
+
+#[tauri::command]
+pub async fn start_adhoc_forward_cmd(
+    spec: AdhocForwardSpec, http_log_state: tauri::State<'_, HttpLogState>,
+) -> Result<(i64, Vec<CustomResponse>), String> {
+    start_adhoc_forward(spec, Arc::new(http_log_state.inner().clone())).await
+}
+
+#[tauri::command]
+pub async fn stop_adhoc_forward_cmd(config_id: i64) -> Result<CustomResponse, String> {
+    stop_adhoc_forward(config_id).await
+}
+
+#[tauri::command]
+pub async fn list_adhoc_forwards_cmd() -> Result<Vec<Config>, String> {
+    Ok(list_adhoc_forwards())
+}
+
+#[tauri::command]
+pub async fn get_open_circuits_cmd() -> Result<Vec<CircuitStatus>, String> {
+    Ok(get_open_circuits())
+}
+
+#[tauri::command]
+pub async fn get_fan_out_forwards_cmd(config_id: i64) -> Result<Vec<FanOutForward>, String> {
+    Ok(get_fan_out_forwards(config_id))
+}
+
+#[tauri::command]
+pub async fn get_udp_associations_cmd(config_id: i64) -> Result<Vec<UdpAssociationStats>, String> {
+    Ok(get_udp_associations(config_id))
+}
+
+#[tauri::command]
+pub async fn get_mdns_advertisements_cmd() -> Result<HashMap<i64, String>, String> {
+    Ok(get_advertised_forwards())
+}
+
+// the ui shows the plan first, starting and stopping each ask the system for
+// administrator rights
+#[tauri::command]
+pub async fn plan_cluster_network_cmd(
+    request: ClusterNetworkRequest,
+) -> Result<ClusterNetworkPlan, String> {
+    plan_cluster_network(&request).await
+}
+
+#[tauri::command]
+pub async fn start_cluster_network_cmd(
+    request: ClusterNetworkRequest, http_log_state: tauri::State<'_, HttpLogState>,
+) -> Result<ClusterNetwork, String> {
+    info!("Starting the cluster network of {}", request.context);
+    start_cluster_network(request, Arc::new(http_log_state.inner().clone())).await
+}
+
+#[tauri::command]
+pub async fn stop_cluster_network_cmd() -> Result<(), String> {
+    stop_cluster_network().await
+}
+
+#[tauri::command]
+pub async fn get_cluster_network_cmd() -> Result<Option<ClusterNetwork>, String> {
+    Ok(get_cluster_network().await)
+}
+
+#[tauri::command]
+pub async fn get_ssh_mirrors_cmd() -> Result<Vec<SshMirror>, String> {
+    Ok(get_ssh_mirrors())
+}
+
+// new connections on the local port of the config go to the target of
+// another config, open ones stay where they are
+#[tauri::command]
+pub async fn switch_forward_target_cmd(
+    config_id: i64, target_config_id: i64, reason: Option<String>,
+) -> Result<ForwardSwitch, String> {
+    switch_forward_target(config_id, target_config_id, reason.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn get_forward_switches_cmd() -> Result<Vec<ForwardSwitch>, String> {
+    Ok(get_forward_switches())
+}
+
+#[tauri::command]
+pub async fn get_traffic_splits_cmd() -> Result<Vec<TrafficSplit>, String> {
+    Ok(get_traffic_splits())
+}
+
+#[tauri::command]
+pub async fn get_pending_starts_cmd() -> Result<Vec<PendingStart>, String> {
+    Ok(get_pending_starts())
+}
+
+#[tauri::command]
+pub async fn cancel_pending_start_cmd(config_id: i64) -> Result<(), String> {
+    cancel_pending_start(Some(config_id));
+    Ok(())
+}
+
+// returns the url browsers should use as their automatic proxy configuration
+#[tauri::command]
+pub async fn start_pac_proxy_cmd(port: Option<u16>) -> Result<String, String> {
+    let address = start_pac_proxy(port.unwrap_or_default()).await?;
+    Ok(pac_url(address))
+}
+
+#[tauri::command]
+pub async fn stop_pac_proxy_cmd() -> Result<(), String> {
+    stop_pac_proxy();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_pac_url_cmd() -> Result<Option<String>, String> {
+    Ok(pac_proxy_address().map(pac_url))
+}
+
+#[tauri::command]
+pub async fn get_pac_routes_cmd() -> Result<Vec<PacRoute>, String> {
+    pac_routes().await
+}
+
 #[tauri::command]
 pub async fn handle_exit_app(app_handle: tauri::AppHandle) {
     let windows_map = app_handle.windows();
@@ -168,7 +809,7 @@ pub async fn handle_exit_app(app_handle: tauri::AppHandle) {
                 tauri::async_runtime::spawn(async move {
                     if ask_result {
                         info!("Attempting to stop all port forwards...");
-                        match stop_all_port_forward().await {
+                        match shutdown(SHUTDOWN_GRACE).await {
                             Ok(responses) => {
                                 info!("Successfully stopped all port forwards: {:?}", responses);
                                 app_handle.exit(0);
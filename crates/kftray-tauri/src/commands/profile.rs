@@ -0,0 +1,38 @@
+use kftray_commons::models::profile_model::Profile;
+use kftray_commons::models::response::CustomResponse;
+use kftray_commons::utils::profile::{
+    create_profile,
+    delete_profile,
+    get_active_profile,
+    list_profiles,
+};
+use kftray_portforward::profile::switch_profile;
+use log::info;
+
+#[tauri::command]
+pub async fn list_profiles_cmd() -> Result<Vec<Profile>, String> {
+    list_profiles().await
+}
+
+#[tauri::command]
+pub async fn get_active_profile_cmd() -> Result<String, String> {
+    get_active_profile().await
+}
+
+#[tauri::command]
+pub async fn create_profile_cmd(name: String) -> Result<(), String> {
+    info!("Creating profile: {}", name);
+    create_profile(&name).await
+}
+
+#[tauri::command]
+pub async fn delete_profile_cmd(name: String) -> Result<(), String> {
+    info!("Deleting profile: {}", name);
+    delete_profile(&name).await
+}
+
+#[tauri::command]
+pub async fn switch_profile_cmd(name: String) -> Result<Vec<CustomResponse>, String> {
+    info!("Switching to profile: {}", name);
+    switch_profile(&name).await
+}
@@ -0,0 +1,18 @@
+use kftray_commons::models::query_log_model::QueryLogEntry;
+use kftray_commons::utils::query_log::{
+    clear_query_log,
+    get_query_log,
+};
+
+#[tauri::command]
+pub async fn get_query_log_cmd(
+    config_id: i64, since: Option<u64>,
+) -> Result<Vec<QueryLogEntry>, String> {
+    Ok(get_query_log(config_id, since))
+}
+
+#[tauri::command]
+pub async fn clear_query_log_cmd(config_id: i64) -> Result<(), String> {
+    clear_query_log(config_id);
+    Ok(())
+}
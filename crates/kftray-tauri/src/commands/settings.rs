@@ -0,0 +1,54 @@
+use kftray_commons::models::settings_model::Settings;
+use kftray_commons::utils::db_mode::{
+    read_database_settings,
+    save_database_settings,
+    DatabaseSettings,
+};
+use kftray_commons::utils::settings::{
+    get_settings,
+    set_setting,
+    update_settings,
+};
+use log::info;
+use tauri::AppHandle;
+
+use crate::hotkeys::register_hotkeys;
+
+#[tauri::command]
+pub async fn get_settings_cmd() -> Result<Settings, String> {
+    get_settings().await
+}
+
+#[tauri::command]
+pub async fn update_settings_cmd(app_handle: AppHandle, settings: Settings) -> Result<(), String> {
+    update_settings(&settings).await?;
+    register_hotkeys(&app_handle, &settings.hotkeys);
+    info!("Settings updated");
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_setting_cmd(
+    app_handle: AppHandle, key: String, value: serde_json::Value,
+) -> Result<Settings, String> {
+    let settings = set_setting(&key, value).await?;
+    if key == "hotkeys" {
+        register_hotkeys(&app_handle, &settings.hotkeys);
+    }
+    info!("Setting {} updated", key);
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn get_database_settings_cmd() -> Result<DatabaseSettings, String> {
+    Ok(read_database_settings())
+}
+
+// takes effect on the next launch, KFTRAY_DB_PATH and KFTRAY_DB_MODE still
+// win over it
+#[tauri::command]
+pub async fn save_database_settings_cmd(settings: DatabaseSettings) -> Result<(), String> {
+    save_database_settings(&settings)?;
+    info!("Database settings updated, they apply after a restart");
+    Ok(())
+}
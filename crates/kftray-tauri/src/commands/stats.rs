@@ -0,0 +1,51 @@
+use kftray_commons::models::grpc_health_model::GrpcHealthStatus;
+use kftray_commons::models::latency_model::LatencyStats;
+use kftray_commons::models::pod_status_model::TargetPodStatus;
+use kftray_commons::models::protocol_model::ProtocolStats;
+use kftray_commons::models::proxy_diagnosis_model::ProxyDiagnosis;
+use kftray_commons::models::target_description_model::TargetDescription;
+use kftray_commons::utils::latency::{
+    clear_latency_stats,
+    get_latency_stats,
+};
+use kftray_portforward::describe::describe_target;
+use kftray_portforward::grpc_health::get_grpc_health;
+use kftray_portforward::pod_watch::get_target_pod_status;
+use kftray_portforward::protocol::get_protocol_stats;
+use kftray_portforward::proxy_diagnosis::diagnose_proxy;
+
+#[tauri::command]
+pub async fn get_latency_stats_cmd(config_id: i64) -> Result<LatencyStats, String> {
+    Ok(get_latency_stats(config_id))
+}
+
+#[tauri::command]
+pub async fn clear_latency_stats_cmd(config_id: i64) -> Result<(), String> {
+    clear_latency_stats(config_id);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_protocol_stats_cmd(config_id: i64) -> Result<ProtocolStats, String> {
+    Ok(get_protocol_stats(config_id))
+}
+
+#[tauri::command]
+pub async fn get_grpc_health_cmd(config_id: i64) -> Result<Vec<GrpcHealthStatus>, String> {
+    Ok(get_grpc_health(config_id))
+}
+
+#[tauri::command]
+pub async fn get_target_pod_status_cmd(config_id: i64) -> Result<Option<TargetPodStatus>, String> {
+    Ok(get_target_pod_status(config_id))
+}
+
+#[tauri::command]
+pub async fn describe_target_cmd(config_id: i64) -> Result<TargetDescription, String> {
+    describe_target(config_id).await
+}
+
+#[tauri::command]
+pub async fn diagnose_proxy_cmd(config_id: i64) -> Result<ProxyDiagnosis, String> {
+    diagnose_proxy(config_id).await
+}
@@ -0,0 +1,30 @@
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::config_usage_model::ConfigUsage;
+use kftray_commons::utils::usage::{
+    get_frequent_configs,
+    get_recent_configs,
+    read_config_usage,
+    set_config_pinned,
+};
+use log::info;
+
+#[tauri::command]
+pub async fn get_config_usage_cmd() -> Result<Vec<ConfigUsage>, String> {
+    read_config_usage().await
+}
+
+#[tauri::command]
+pub async fn set_config_pinned_cmd(id: i64, pinned: bool) -> Result<(), String> {
+    info!("Setting pinned={} for config {}", pinned, id);
+    set_config_pinned(id, pinned).await
+}
+
+#[tauri::command]
+pub async fn get_recent_configs_cmd(limit: Option<usize>) -> Result<Vec<Config>, String> {
+    get_recent_configs(limit).await
+}
+
+#[tauri::command]
+pub async fn get_frequent_configs_cmd(limit: Option<usize>) -> Result<Vec<Config>, String> {
+    get_frequent_configs(limit).await
+}
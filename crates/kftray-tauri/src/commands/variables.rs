@@ -0,0 +1,28 @@
+use std::collections::HashMap;
+
+use kftray_commons::utils::profile::DEFAULT_PROFILE;
+use kftray_commons::utils::variables::{
+    delete_variable,
+    get_variables,
+    set_variable,
+};
+use log::info;
+
+#[tauri::command]
+pub async fn get_variables_cmd(profile: Option<String>) -> Result<HashMap<String, String>, String> {
+    get_variables(profile.as_deref().unwrap_or(DEFAULT_PROFILE)).await
+}
+
+#[tauri::command]
+pub async fn set_variable_cmd(
+    profile: Option<String>, name: String, value: String,
+) -> Result<(), String> {
+    info!("Setting variable: {}", name);
+    set_variable(profile.as_deref().unwrap_or(DEFAULT_PROFILE), &name, &value).await
+}
+
+#[tauri::command]
+pub async fn delete_variable_cmd(profile: Option<String>, name: String) -> Result<(), String> {
+    info!("Deleting variable: {}", name);
+    delete_variable(profile.as_deref().unwrap_or(DEFAULT_PROFILE), &name).await
+}
@@ -0,0 +1,68 @@
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
+use kftray_commons::models::settings_model::HotkeyBinding;
+use kftray_portforward::hotkeys::run_hotkey;
+use kftray_portforward::models::kube::HttpLogState;
+use lazy_static::lazy_static;
+use log::{
+    error,
+    info,
+};
+use serde_json::json;
+use tauri::{
+    AppHandle,
+    GlobalShortcutManager,
+    Manager,
+};
+
+lazy_static! {
+    static ref REGISTERED_HOTKEYS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+}
+
+fn trigger_hotkey(app_handle: AppHandle, hotkey: HotkeyBinding) {
+    tauri::async_runtime::spawn(async move {
+        let http_log_state = Arc::new(app_handle.state::<HttpLogState>().inner().clone());
+        match run_hotkey(&hotkey, http_log_state).await {
+            Ok(report) => {
+                let payload = json!({
+                    "accelerator": hotkey.accelerator,
+                    "report": report,
+                });
+                if let Err(e) = app_handle.emit_all("hotkey_triggered", payload) {
+                    error!("Failed to emit hotkey event: {}", e);
+                }
+            }
+            Err(e) => error!("Hotkey {} failed: {}", hotkey.accelerator, e),
+        }
+    });
+}
+
+// replaces the bindings of the previous settings. a combination another app
+// already holds is logged and skipped
+pub fn register_hotkeys(app_handle: &AppHandle, hotkeys: &[HotkeyBinding]) {
+    let mut manager = app_handle.global_shortcut_manager();
+    let mut registered = REGISTERED_HOTKEYS.lock().unwrap();
+
+    for accelerator in registered.drain(..) {
+        if let Err(e) = manager.unregister(&accelerator) {
+            error!("Failed to unregister hotkey {}: {}", accelerator, e);
+        }
+    }
+
+    for hotkey in hotkeys {
+        let app_handle = app_handle.clone();
+        let binding = hotkey.clone();
+        let result = manager.register(&hotkey.accelerator, move || {
+            trigger_hotkey(app_handle.clone(), binding.clone());
+        });
+        match result {
+            Ok(()) => registered.push(hotkey.accelerator.clone()),
+            Err(e) => error!("Failed to register hotkey {}: {}", hotkey.accelerator, e),
+        }
+    }
+
+    info!("Registered {} hotkey(s)", registered.len());
+}
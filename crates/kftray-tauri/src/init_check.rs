@@ -7,9 +7,11 @@ use kftray_commons::config_state::{
 };
 use kftray_commons::config_state_model::ConfigState;
 use kftray_commons::models::config_model::Config;
-use kftray_portforward::deploy_and_forward_pod;
 use kftray_portforward::models::kube::HttpLogState;
-use kftray_portforward::start_port_forward;
+use kftray_portforward::startup::{
+    auto_start_configs,
+    StartupReport,
+};
 use log::{
     error,
     info,
@@ -25,7 +27,9 @@ use sysinfo::{
     System,
 };
 
-pub async fn check_and_manage_ports() -> Result<(), String> {
+// forwards that were running when kftray quit come back up in one batch, see
+// auto_start_configs for the ordering and retries
+pub async fn check_and_manage_ports() -> Result<StartupReport, String> {
     let config_states = match get_configs_state().await {
         Ok(states) => states,
         Err(e) => {
@@ -34,17 +38,20 @@ pub async fn check_and_manage_ports() -> Result<(), String> {
         }
     };
 
+    let mut to_start = Vec::new();
     for config_state in config_states {
         if config_state.is_running {
             match get_config(config_state.config_id).await {
-                Ok(config) => {
-                    if let Err(err) = check_and_manage_port(config).await {
+                Ok(config) => match check_and_manage_port(&config).await {
+                    Ok(true) => to_start.push(config),
+                    Ok(false) => {}
+                    Err(err) => {
                         error!(
                             "Error check state for config {}: {}",
                             config_state.config_id, err
                         );
                     }
-                }
+                },
                 Err(_) => {
                     error!(
                         "Could not retrieve config with ID {}",
@@ -55,19 +62,36 @@ pub async fn check_and_manage_ports() -> Result<(), String> {
         }
     }
 
-    Ok(())
+    if to_start.is_empty() {
+        return Ok(StartupReport::default());
+    }
+
+    info!("Restoring {} port forward(s)", to_start.len());
+    let report = auto_start_configs(to_start, None, Arc::new(HttpLogState::new())).await;
+    for failure in &report.failed {
+        let config_state = ConfigState {
+            id: None,
+            config_id: failure.config_id,
+            is_running: false,
+            local_port: None,
+        };
+        update_config_state(&config_state).await?;
+    }
+
+    Ok(report)
 }
 
-async fn check_and_manage_port(config: Config) -> Result<(), Box<dyn std::error::Error>> {
+// true when nothing holds the port and the forward should be started again
+async fn check_and_manage_port(config: &Config) -> Result<bool, Box<dyn std::error::Error>> {
     let port = config.local_port.unwrap_or(0);
 
     if let Some((pid, process_name)) = find_process_by_port(port).await {
-        handle_existing_process(config, port, pid, process_name).await?;
+        handle_existing_process(config.clone(), port, pid, process_name).await?;
+        Ok(false)
     } else {
-        start_port_forwarding(config).await?;
+        info!("No process is occupying the port. Starting port forwarding...");
+        Ok(true)
     }
-
-    Ok(())
 }
 
 async fn handle_existing_process(
@@ -89,6 +113,7 @@ async fn handle_existing_process(
             id: None,
             config_id: config.id.unwrap(),
             is_running: false,
+            local_port: None,
         };
         update_config_state(&config_state).await?;
     }
@@ -96,49 +121,6 @@ async fn handle_existing_process(
     Ok(())
 }
 
-async fn start_port_forwarding(config: Config) -> Result<(), String> {
-    info!("No process is occupying the port. Starting port forwarding...");
-
-    let protocol = config.protocol.as_str();
-    let http_log_state = Arc::new(HttpLogState::new());
-
-    info!(
-        "Starting workload type '{:?}' for config: {:?}",
-        config.workload_type, config.alias
-    );
-
-    let configs = vec![config.clone()];
-    let forward_result = match config.workload_type.as_deref() {
-        Some("proxy") => deploy_and_forward_pod(configs, http_log_state).await,
-        _ => start_port_forward(configs, protocol, http_log_state).await,
-    };
-
-    match forward_result {
-        Ok(responses) => {
-            for response in responses {
-                info!("Port forwarding response: {:?}", response);
-            }
-            let config_state = ConfigState {
-                id: None,
-                config_id: config.id.unwrap(),
-                is_running: true,
-            };
-            update_config_state(&config_state).await?;
-        }
-        Err(e) => {
-            error!("Failed to start port forwarding: {}", e);
-            let config_state = ConfigState {
-                id: None,
-                config_id: config.id.unwrap(),
-                is_running: false,
-            };
-            update_config_state(&config_state).await?;
-        }
-    }
-
-    Ok(())
-}
-
 async fn find_process_by_port(port: u16) -> Option<(i32, String)> {
     let af_flags = AddressFamilyFlags::IPV4 | AddressFamilyFlags::IPV6;
     let proto_flags = ProtocolFlags::TCP | ProtocolFlags::UDP;
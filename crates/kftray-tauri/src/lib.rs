@@ -1,5 +1,6 @@
 pub mod commands;
 pub use commands::*;
+pub mod hotkeys;
 pub mod logging;
 pub mod tray;
 pub mod window;
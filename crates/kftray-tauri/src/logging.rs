@@ -1,44 +1,33 @@
-use std::{
-    env,
-    fs::OpenOptions,
-    io,
-};
+use std::env;
 
-use kftray_commons::utils::config_dir::get_app_log_path;
+use kftray_commons::utils::app_log::RotatingLogWriter;
+use kftray_commons::utils::config_log::install_config_log_router;
+use kftray_commons::utils::settings::get_settings;
 
 pub fn setup_logging() -> Result<(), Box<dyn std::error::Error>> {
+    // RUST_LOG wins over the configured level, the database may not exist yet
+    // on the first start so the settings fall back to their defaults
     let log_filter = match env::var("RUST_LOG") {
         Ok(filter) => filter.parse().unwrap_or(log::LevelFilter::Info),
-        Err(_) => log::LevelFilter::Off,
+        Err(_) => tauri::async_runtime::block_on(get_settings())
+            .unwrap_or_default()
+            .log_level
+            .parse()
+            .unwrap_or(log::LevelFilter::Off),
     };
 
-    if env::var("KFTRAY_DEBUG").is_ok() {
-        let log_path = get_app_log_path().map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-
-        let log_dir = log_path.parent().ok_or_else(|| {
-            io::Error::new(io::ErrorKind::Other, "Could not find the log directory")
-        })?;
-
-        std::fs::create_dir_all(log_dir)
-            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Could not create log directory"))?;
-
-        let log_file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(log_path)
-            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Could not open log file"))?;
-
-        env_logger::Builder::from_default_env()
-            .filter_level(log_filter)
-            .format_timestamp_secs()
-            .target(env_logger::Target::Pipe(Box::new(log_file)))
-            .init();
-    } else {
-        env_logger::Builder::new()
-            .filter_level(log_filter)
-            .format_timestamp_secs()
-            .init();
-    }
+    // the app log always goes to the rotating files under the config dir,
+    // stderr keeps getting a copy unless running with KFTRAY_DEBUG
+    let writer = RotatingLogWriter::app()?.with_stderr_echo(env::var("KFTRAY_DEBUG").is_err());
+
+    // configs with their own log level or file are routed in front of it, so
+    // the app logger itself lets everything through
+    let logger = env_logger::Builder::new()
+        .target(env_logger::Target::Pipe(Box::new(writer)))
+        .filter_level(log::LevelFilter::Trace)
+        .format_timestamp_secs()
+        .build();
+    install_config_log_router(Box::new(logger), log_filter)?;
 
     Ok(())
 }
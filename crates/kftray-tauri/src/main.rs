@@ -14,6 +14,7 @@ use log::{
     info,
 };
 mod commands;
+mod hotkeys;
 mod init_check;
 mod logging;
 mod tray;
@@ -32,6 +33,11 @@ use tauri::{
 use tokio::runtime::Runtime;
 
 use crate::commands::portforward::check_and_emit_changes;
+use crate::commands::portforward::emit_circuit_events;
+use crate::commands::portforward::emit_expiry_events;
+use crate::commands::portforward::emit_mapping_events;
+use crate::commands::portforward::emit_port_manifest_events;
+use crate::commands::portforward::emit_progress_events;
 use crate::tray::{
     create_tray_menu,
     handle_run_event,
@@ -64,6 +70,7 @@ fn main() {
         .setup(move |app| {
             let app_handle = app.app_handle();
             let app_handle_clone = app_handle.clone();
+            let hotkeys_handle = app_handle.clone();
             tauri::async_runtime::spawn(async move {
                 if let Err(e) =
                     kftray_commons::utils::config::clean_all_custom_hosts_entries().await
@@ -78,16 +85,53 @@ fn main() {
                 if let Err(e) = kftray_commons::utils::migration::migrate_configs().await {
                     error!("Failed to migrate configs: {}", e);
                 }
+
+                match kftray_commons::utils::settings::get_settings().await {
+                    Ok(settings) => {
+                        if let Some(port) = settings.pac_proxy_port {
+                            if let Err(e) = kftray_portforward::pac::start_pac_proxy(port).await {
+                                error!("Failed to start the PAC proxy: {}", e);
+                            }
+                        }
+                        hotkeys::register_hotkeys(&hotkeys_handle, &settings.hotkeys);
+                        if settings.proxy_warm_pool_ttl_secs > 0 {
+                            tauri::async_runtime::spawn(
+                                commands::portforward::prewarm_pinned_proxy_pods(),
+                            );
+                        }
+                    }
+                    Err(e) => error!("Failed to load settings: {}", e),
+                }
+
+                if let Err(e) = kftray_commons::utils::config_trash::purge_expired_trash().await {
+                    error!("Failed to purge expired trash: {}", e);
+                }
+
+                // what the last run published is stale by now
+                if let Err(e) = kftray_portforward::mappings::publish_forward_mappings().await {
+                    error!("Failed to publish forward mappings: {}", e);
+                }
             });
 
             tauri::async_runtime::spawn(async move {
                 alert_multiple_configs(app_handle_clone).await;
             });
 
+            let app_handle_clone = app_handle.clone();
             tauri::async_runtime::spawn(async move {
+                // stale states would keep their configs from being auto-started
+                if let Err(e) = kftray_portforward::reconcile::reconcile_config_states().await {
+                    error!("Failed to reconcile config states: {}", e);
+                }
+
                 info!("Starting port management checks");
-                if let Err(e) = init_check::check_and_manage_ports().await {
-                    error!("Error in port management: {}", e);
+                match init_check::check_and_manage_ports().await {
+                    Ok(report) => {
+                        if let Err(e) = app_handle_clone.emit_all("auto_start_finished", &report) {
+                            error!("Failed to emit auto-start report: {}", e);
+                        }
+                    }
+                    Err(e) => error!("Error in port management: {}", e),
                 }
             });
 
@@ -96,6 +140,23 @@ fn main() {
                 check_and_emit_changes(app_handle_clone).await;
             });
 
+            tauri::async_runtime::spawn(emit_circuit_events(app_handle.clone()));
+            tauri::async_runtime::spawn(emit_expiry_events(app_handle.clone()));
+            tauri::async_runtime::spawn(emit_progress_events(app_handle.clone()));
+            tauri::async_runtime::spawn(emit_mapping_events(app_handle.clone()));
+            tauri::async_runtime::spawn(emit_port_manifest_events(app_handle.clone()));
+            tauri::async_runtime::spawn(kftray_portforward::port_manifest::watch_port_manifest());
+
+            #[cfg(target_os = "linux")]
+            tauri::async_runtime::spawn(kftray_portforward::dbus::serve_dbus(Arc::new(
+                http_log_state.clone(),
+            )));
+
+            let app_handle_clone = app_handle.clone();
+            tauri::async_runtime::spawn(kftray_portforward::shutdown::shutdown_on_signal(
+                move || app_handle_clone.exit(0),
+            ));
+
             #[cfg(target_os = "macos")]
             {
                 app.set_activation_policy(tauri::ActivationPolicy::Accessory);
@@ -113,9 +174,12 @@ fn main() {
             let mut shortcut = app.global_shortcut_manager();
 
             shortcut
-                .register("CmdOrCtrl+Shift+F1", move || {
-                    toggle_window_visibility(&window);
-                })
+                .register(
+                    kftray_commons::utils::settings::TOGGLE_WINDOW_HOTKEY,
+                    move || {
+                        toggle_window_visibility(&window);
+                    },
+                )
                 .unwrap_or_else(|err| error!("{:?}", err));
 
             Ok(())
@@ -128,6 +192,8 @@ fn main() {
             commands::portforward::start_port_forward_tcp_cmd,
             commands::portforward::start_port_forward_udp_cmd,
             commands::portforward::stop_port_forward_cmd,
+            commands::portforward::start_port_forward_outcomes_cmd,
+            commands::portforward::stop_port_forward_outcome_cmd,
             commands::portforward::stop_all_port_forward_cmd,
             commands::portforward::handle_exit_app,
             commands::kubecontext::list_kube_contexts,
@@ -136,30 +202,155 @@ fn main() {
             commands::kubecontext::list_pods,
             commands::kubecontext::list_ports,
             commands::kubecontext::get_services_with_annotations,
+            commands::kubecontext::get_services_with_annotations_multi,
+            commands::kubecontext::cancel_discovery_cmd,
+            commands::kubecontext::get_discovery_diff_cmd,
+            commands::kubecontext::import_discovery_diff_cmd,
+            commands::kubecontext::import_lens_cmd,
+            commands::kubecontext::import_kubefwd_cmd,
+            commands::kubecontext::import_k9s_cmd,
+            commands::kubecontext::probe_context_cmd,
+            commands::kubecontext::get_context_probes_cmd,
+            commands::kubecontext::get_teleport_logins_cmd,
+            commands::kubecontext::teleport_login_cmd,
+            commands::kubecontext::add_cloud_cluster_cmd,
+            commands::kubecontext::list_cloud_clusters_cmd,
+            commands::kubecontext::refresh_cloud_clusters_cmd,
+            commands::kubecontext::list_context_settings_cmd,
+            commands::kubecontext::save_context_settings_cmd,
+            commands::kubecontext::delete_context_settings_cmd,
+            commands::kubecontext::list_access_audit_cmd,
+            commands::kubecontext::export_access_audit_cmd,
+            commands::kubecontext::find_broken_contexts_cmd,
+            commands::kubecontext::remap_context_cmd,
             commands::portforward::deploy_and_forward_pod_cmd,
             commands::portforward::stop_proxy_forward_cmd,
+            commands::portforward::start_all_for_context_cmd,
+            commands::portforward::stop_all_for_context_cmd,
+            commands::portforward::prewarm_proxy_pods_cmd,
+            commands::portforward::list_external_forwards_cmd,
+            commands::portforward::adopt_external_forward_cmd,
+            commands::portforward::mark_context_offline_cmd,
+            commands::portforward::mark_context_online_cmd,
+            commands::portforward::get_offline_contexts_cmd,
+            commands::portforward::start_with_overrides_cmd,
+            commands::portforward::get_forward_overrides_cmd,
+            commands::portforward::start_adhoc_forward_cmd,
+            commands::portforward::stop_adhoc_forward_cmd,
+            commands::portforward::list_adhoc_forwards_cmd,
+            commands::portforward::get_forward_expiries_cmd,
+            commands::portforward::cancel_start_cmd,
+            commands::portforward::test_forward_cmd,
+            commands::portforward::benchmark_forward_cmd,
+            commands::portforward::export_kubectl_cmd,
+            commands::portforward::export_compose_override_cmd,
+            commands::portforward::get_forward_mappings_cmd,
+            commands::portforward::get_port_manifest_cmd,
+            commands::portforward::start_pac_proxy_cmd,
+            commands::portforward::stop_pac_proxy_cmd,
+            commands::portforward::get_pac_url_cmd,
+            commands::portforward::get_pac_routes_cmd,
+            commands::portforward::get_open_circuits_cmd,
+            commands::portforward::get_fan_out_forwards_cmd,
+            commands::portforward::get_udp_associations_cmd,
+            commands::portforward::get_mdns_advertisements_cmd,
+            commands::portforward::get_ssh_mirrors_cmd,
+            commands::portforward::switch_forward_target_cmd,
+            commands::portforward::get_forward_switches_cmd,
+            commands::portforward::get_traffic_splits_cmd,
+            commands::portforward::get_pending_starts_cmd,
+            commands::portforward::cancel_pending_start_cmd,
+            commands::portforward::plan_cluster_network_cmd,
+            commands::portforward::start_cluster_network_cmd,
+            commands::portforward::stop_cluster_network_cmd,
+            commands::portforward::get_cluster_network_cmd,
             commands::httplogs::set_http_logs_cmd,
             commands::httplogs::get_http_logs_cmd,
             commands::config::get_configs_cmd,
             commands::config::insert_config_cmd,
             commands::config::delete_config_cmd,
             commands::config::get_config_cmd,
+            commands::config::search_configs_cmd,
+            commands::config::quick_connect_cmd,
+            commands::config::get_tray_menu_cmd,
             commands::config::update_config_cmd,
+            commands::config::update_configs_bulk_cmd,
+            commands::config::tag_configs_cmd,
+            commands::config::untag_configs_cmd,
+            commands::config::list_snapshots_cmd,
+            commands::config::rollback_to_snapshot_cmd,
+            commands::config::reload_config_cmd,
+            commands::config::get_pending_reloads_cmd,
+            commands::config::validate_config_cmd,
             commands::config::export_configs_cmd,
             commands::config::import_configs_cmd,
             commands::config::delete_configs_cmd,
             commands::config::delete_all_configs_cmd,
+            commands::config::get_config_history_cmd,
+            commands::config::list_trash_cmd,
+            commands::config::restore_config_cmd,
+            commands::config::purge_trash_cmd,
+            commands::config::set_config_encryption_cmd,
+            commands::config::is_config_encryption_enabled_cmd,
             commands::window_state::open_save_dialog,
             commands::window_state::close_save_dialog,
             commands::github::import_configs_from_github,
             commands::httplogs::open_log_file,
             commands::httplogs::clear_http_logs,
             commands::httplogs::get_http_log_size,
+            commands::httplogs::subscribe_http_logs_cmd,
+            commands::httplogs::unsubscribe_http_logs_cmd,
+            commands::httplogs::query_http_transactions_cmd,
+            commands::httplogs::clear_http_transactions_cmd,
+            commands::applogs::list_app_logs_cmd,
+            commands::applogs::tail_app_logs_cmd,
+            commands::applogs::purge_app_logs_cmd,
+            commands::applogs::follow_app_logs_cmd,
+            commands::applogs::unfollow_app_logs_cmd,
+            commands::stats::get_latency_stats_cmd,
+            commands::stats::clear_latency_stats_cmd,
+            commands::stats::get_protocol_stats_cmd,
+            commands::stats::get_grpc_health_cmd,
+            commands::stats::get_target_pod_status_cmd,
+            commands::stats::describe_target_cmd,
+            commands::stats::diagnose_proxy_cmd,
+            commands::podlogs::stream_pod_logs_cmd,
+            commands::podlogs::stop_pod_logs_cmd,
+            commands::query_log::get_query_log_cmd,
+            commands::query_log::clear_query_log_cmd,
             commands::github::store_key,
             commands::github::get_key,
             commands::github::delete_key,
             commands::window_state::toggle_pin_state,
             commands::config_state::get_config_states,
+            commands::config_state::reconcile_config_states_cmd,
+            commands::config_state::get_forward_phases_cmd,
+            commands::config_state::list_sessions_cmd,
+            commands::config_state::clean_session_cmd,
+            commands::config_state::adopt_session_cmd,
+            commands::settings::get_settings_cmd,
+            commands::settings::update_settings_cmd,
+            commands::settings::get_database_settings_cmd,
+            commands::settings::save_database_settings_cmd,
+            commands::settings::set_setting_cmd,
+            commands::variables::get_variables_cmd,
+            commands::variables::set_variable_cmd,
+            commands::variables::delete_variable_cmd,
+            commands::profile::list_profiles_cmd,
+            commands::profile::get_active_profile_cmd,
+            commands::profile::create_profile_cmd,
+            commands::profile::delete_profile_cmd,
+            commands::profile::switch_profile_cmd,
+            commands::usage::get_config_usage_cmd,
+            commands::usage::set_config_pinned_cmd,
+            commands::usage::get_recent_configs_cmd,
+            commands::usage::get_frequent_configs_cmd,
+            commands::hooks::get_hook_results_cmd,
+            commands::access_log::get_access_log_cmd,
+            commands::access_log::clear_access_log_cmd,
+            commands::capture::start_capture_cmd,
+            commands::capture::stop_capture_cmd,
+            commands::capture::is_capture_enabled_cmd,
         ])
         .build(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,433 @@
+use std::time::Duration;
+
+use kftray_commons::config::read_configs;
+use kftray_commons::models::diagnostic::Severity;
+use kftray_commons::utils::db::init;
+use kftray_commons::utils::migration::migrate_configs;
+use kftray_commons::utils::profile::{
+    create_profile,
+    delete_profile,
+    list_profiles,
+};
+use kftray_commons::utils::search::search_configs;
+use kftray_commons::utils::service::{
+    install_engine_service,
+    is_engine_service_installed,
+    uninstall_engine_service,
+};
+use kftray_commons::utils::settings::get_settings;
+use kftray_portforward::adhoc::{
+    run_adhoc_forward,
+    AdhocForwardSpec,
+};
+use kftray_portforward::describe::describe_target;
+use kftray_portforward::expiry::parse_ttl;
+use kftray_portforward::headless::run_engine;
+use kftray_portforward::profile::switch_profile;
+use kftray_portforward::quick_connect::quick_connect;
+use kftray_portforward::validation::validate_config_with_existing;
+
+const USAGE: &str = "Usage:
+  kftui                          start the terminal UI
+  kftui validate [ids]           validate configs against the cluster
+  kftui search <query>           fuzzy search configs (use tag:<name> to filter by tag)
+  kftui describe <id>            describe the service, endpoints and pod behind a config
+  kftui profile                  list profiles
+  kftui profile use <name>       switch the active profile
+  kftui profile create <name>    create a profile
+  kftui profile delete <name>    delete a profile
+  kftui daemon [ids] [options]   run forwards headless (default: active profile)
+  kftui forward <context> <namespace> <service> <remote port> [local port] [options]
+                                 forward once without saving a config
+  kftui connect <query> [options]
+                                 forward the best match for a search, or a
+                                 context/namespace/service:port[:local port] target
+    --for <duration>             stop the forwards after a duration like 30m or 2h
+    --reason <text>              why access is needed, required for production contexts
+  kftui service install [ids]    run the daemon at login (launchd / logon task)
+  kftui service uninstall        remove the login service
+  kftui service status           show whether the login service is installed";
+
+pub enum ProfileAction {
+    List,
+    Use(String),
+    Create(String),
+    Delete(String),
+}
+
+pub enum ServiceAction {
+    Install(Vec<i64>),
+    Uninstall,
+    Status,
+}
+
+pub enum Command {
+    Validate {
+        ids: Vec<i64>,
+    },
+    Search {
+        query: String,
+    },
+    Describe {
+        id: i64,
+    },
+    Profile(ProfileAction),
+    Daemon {
+        ids: Vec<i64>,
+        ttl: Option<Duration>,
+        reason: Option<String>,
+    },
+    Forward {
+        spec: AdhocForwardSpec,
+        ttl: Option<Duration>,
+    },
+    Connect {
+        query: String,
+        ttl: Option<Duration>,
+        reason: Option<String>,
+    },
+    Service(ServiceAction),
+}
+
+fn parse_port(port: &str) -> Result<u16, String> {
+    port.parse::<u16>()
+        .map_err(|_| format!("Invalid port: {}", port))
+}
+
+// pulls `<flag> <value>` out of the arguments wherever it appears
+fn take_flag(args: &[String], flag: &str) -> Result<(Vec<String>, Option<String>), String> {
+    let Some(index) = args.iter().position(|arg| arg == flag) else {
+        return Ok((args.to_vec(), None));
+    };
+    let value = args
+        .get(index + 1)
+        .ok_or_else(|| USAGE.to_string())?
+        .clone();
+
+    let mut rest = args.to_vec();
+    rest.drain(index..=index + 1);
+    Ok((rest, Some(value)))
+}
+
+struct RunFlags {
+    ttl: Option<Duration>,
+    reason: Option<String>,
+}
+
+fn take_run_flags(args: &[String]) -> Result<(Vec<String>, RunFlags), String> {
+    let (args, ttl) = take_flag(args, "--for")?;
+    let (args, reason) = take_flag(&args, "--reason")?;
+    let ttl = ttl.as_deref().map(parse_ttl).transpose()?;
+    Ok((args, RunFlags { ttl, reason }))
+}
+
+fn parse_ids(args: &[String]) -> Result<Vec<i64>, String> {
+    args.iter()
+        .map(|id| {
+            id.parse::<i64>()
+                .map_err(|_| format!("Invalid config id: {}", id))
+        })
+        .collect()
+}
+
+pub fn parse_args(args: &[String]) -> Result<Option<Command>, String> {
+    let Some(command) = args.first() else {
+        return Ok(None);
+    };
+
+    match command.as_str() {
+        "validate" => Ok(Some(Command::Validate {
+            ids: parse_ids(&args[1..])?,
+        })),
+        "search" => Ok(Some(Command::Search {
+            query: args[1..].join(" "),
+        })),
+        "describe" => {
+            let id = args.get(1).ok_or_else(|| USAGE.to_string())?;
+            let id = id
+                .parse::<i64>()
+                .map_err(|_| format!("Invalid config id: {}", id))?;
+            Ok(Some(Command::Describe { id }))
+        }
+        "profile" => {
+            let action = match (args.get(1).map(String::as_str), args.get(2)) {
+                (None, _) | (Some("list"), _) => ProfileAction::List,
+                (Some("use"), Some(name)) => ProfileAction::Use(name.clone()),
+                (Some("create"), Some(name)) => ProfileAction::Create(name.clone()),
+                (Some("delete"), Some(name)) => ProfileAction::Delete(name.clone()),
+                _ => return Err(USAGE.to_string()),
+            };
+            Ok(Some(Command::Profile(action)))
+        }
+        "daemon" => {
+            let (ids, flags) = take_run_flags(&args[1..])?;
+            Ok(Some(Command::Daemon {
+                ids: parse_ids(&ids)?,
+                ttl: flags.ttl,
+                reason: flags.reason,
+            }))
+        }
+        "forward" => {
+            let (args, flags) = take_run_flags(&args[1..])?;
+            let [context, namespace, service, remote_port, rest @ ..] = args.as_slice() else {
+                return Err(USAGE.to_string());
+            };
+            let spec = AdhocForwardSpec {
+                context: context.clone(),
+                namespace: namespace.clone(),
+                service: Some(service.clone()),
+                remote_port: parse_port(remote_port)?,
+                local_port: rest.first().map(|port| parse_port(port)).transpose()?,
+                reason: flags.reason,
+                ..AdhocForwardSpec::default()
+            };
+            Ok(Some(Command::Forward {
+                spec,
+                ttl: flags.ttl,
+            }))
+        }
+        "connect" => {
+            let (args, flags) = take_run_flags(&args[1..])?;
+            if args.is_empty() {
+                return Err(USAGE.to_string());
+            }
+            Ok(Some(Command::Connect {
+                query: args.join(" "),
+                ttl: flags.ttl,
+                reason: flags.reason,
+            }))
+        }
+        "service" => {
+            let action = match args.get(1).map(String::as_str) {
+                Some("install") => ServiceAction::Install(parse_ids(&args[2..])?),
+                Some("uninstall") => ServiceAction::Uninstall,
+                Some("status") | None => ServiceAction::Status,
+                _ => return Err(USAGE.to_string()),
+            };
+            Ok(Some(Command::Service(action)))
+        }
+        other => Err(format!("Unknown command: {}\n\n{}", other, USAGE)),
+    }
+}
+
+pub async fn run_command(command: Command) -> Result<(), Box<dyn std::error::Error>> {
+    init().await?;
+
+    if let Err(e) = migrate_configs().await {
+        eprintln!("Failed to migrate configs: {}", e);
+    }
+
+    if let Err(e) = get_settings().await {
+        eprintln!("Failed to load settings: {}", e);
+    }
+
+    match command {
+        Command::Validate { ids } => validate(ids).await,
+        Command::Search { query } => search(&query).await,
+        Command::Describe { id } => describe(id).await,
+        Command::Profile(action) => profile(action).await,
+        Command::Daemon { ids, ttl, reason } => Ok(run_engine(ids, ttl, reason).await?),
+        Command::Forward { spec, ttl } => Ok(run_adhoc_forward(spec, ttl).await?),
+        Command::Connect { query, ttl, reason } => connect(&query, ttl, reason).await,
+        Command::Service(action) => service(action),
+    }
+}
+
+fn service(action: ServiceAction) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        ServiceAction::Install(ids) => {
+            let program = std::env::current_exe()?;
+            let args: Vec<String> = std::iter::once("daemon".to_string())
+                .chain(ids.iter().map(i64::to_string))
+                .collect();
+            let location = install_engine_service(&program, &args)?;
+            println!("Installed the kftray engine service ({})", location);
+        }
+        ServiceAction::Uninstall => {
+            uninstall_engine_service()?;
+            println!("Removed the kftray engine service");
+        }
+        ServiceAction::Status => {
+            if is_engine_service_installed()? {
+                println!("The kftray engine service is installed");
+            } else {
+                println!("The kftray engine service is not installed");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn search(query: &str) -> Result<(), Box<dyn std::error::Error>> {
+    for config in search_configs(query).await? {
+        println!(
+            "[{}] {} ({}/{}/{}) {}",
+            config.id.unwrap_or_default(),
+            config.alias.clone().unwrap_or_default(),
+            config.context,
+            config.namespace,
+            config.service.clone().unwrap_or_default(),
+            config.tags.clone().unwrap_or_default().join(",")
+        );
+    }
+
+    Ok(())
+}
+
+async fn connect(
+    query: &str, ttl: Option<Duration>, reason: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let item = quick_connect(query)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("Nothing matches '{}'", query))?;
+    println!("Connecting {} ({})", item.label, item.detail);
+
+    match (item.config_id, item.spec) {
+        (Some(id), _) => Ok(run_engine(vec![id], ttl, reason).await?),
+        (None, Some(spec)) => {
+            Ok(run_adhoc_forward(AdhocForwardSpec { reason, ..spec }, ttl).await?)
+        }
+        (None, None) => Err(format!("Nothing to forward for '{}'", query).into()),
+    }
+}
+
+async fn describe(id: i64) -> Result<(), Box<dyn std::error::Error>> {
+    let description = describe_target(id).await?;
+
+    if let Some(service) = &description.service {
+        println!(
+            "Service:   {}/{} ({})",
+            description.namespace, service.name, service.service_type
+        );
+        println!(
+            "  cluster ip  {}",
+            service.cluster_ip.as_deref().unwrap_or("-")
+        );
+        println!("  ports       {}", service.ports.join(", "));
+        let selector: Vec<String> = service
+            .selector
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        println!("  selector    {}", selector.join(","));
+    }
+
+    if let Some(endpoints) = &description.endpoints {
+        println!("Endpoints:");
+        println!("  ready       {}", endpoints.ready_addresses.join(", "));
+        println!("  not ready   {}", endpoints.not_ready_addresses.join(", "));
+    }
+
+    match &description.pod {
+        Some(pod) => {
+            println!(
+                "Pod:       {}/{} ({})",
+                description.namespace, pod.name, pod.phase
+            );
+            println!("  node        {}", pod.node.as_deref().unwrap_or("-"));
+            println!("  ip          {}", pod.pod_ip.as_deref().unwrap_or("-"));
+            println!("  images      {}", pod.images.join(", "));
+            for (key, value) in &pod.labels {
+                println!("  label       {}={}", key, value);
+            }
+            for condition in &pod.conditions {
+                println!(
+                    "  condition   {}={}{}",
+                    condition.condition_type,
+                    condition.status,
+                    condition
+                        .reason
+                        .as_ref()
+                        .map(|reason| format!(" ({})", reason))
+                        .unwrap_or_default()
+                );
+            }
+        }
+        None => println!("Pod:       none found"),
+    }
+
+    Ok(())
+}
+
+async fn profile(action: ProfileAction) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        ProfileAction::List => {
+            for profile in list_profiles().await? {
+                let marker = if profile.is_active { "*" } else { " " };
+                println!("{} {}", marker, profile.name);
+            }
+        }
+        ProfileAction::Use(name) => {
+            let stopped = switch_profile(&name).await?;
+            println!(
+                "Switched to profile '{}' ({} forward(s) stopped)",
+                name,
+                stopped.len()
+            );
+        }
+        ProfileAction::Create(name) => {
+            create_profile(&name).await?;
+            println!("Created profile '{}'", name);
+        }
+        ProfileAction::Delete(name) => {
+            delete_profile(&name).await?;
+            println!("Deleted profile '{}'", name);
+        }
+    }
+
+    Ok(())
+}
+
+async fn validate(ids: Vec<i64>) -> Result<(), Box<dyn std::error::Error>> {
+    let configs = read_configs().await?;
+    let selected: Vec<_> = configs
+        .iter()
+        .filter(|config| ids.is_empty() || config.id.is_some_and(|id| ids.contains(&id)))
+        .collect();
+
+    let mut error_count = 0;
+
+    for config in &selected {
+        println!(
+            "[{}] {} ({}/{}/{})",
+            config.id.unwrap_or_default(),
+            config.alias.clone().unwrap_or_default(),
+            config.context,
+            config.namespace,
+            config.service.clone().unwrap_or_default()
+        );
+
+        let diagnostics = validate_config_with_existing(config, &configs).await;
+
+        if diagnostics.is_empty() {
+            println!("  ok");
+        }
+
+        for diagnostic in diagnostics {
+            let label = match diagnostic.severity {
+                Severity::Error => {
+                    error_count += 1;
+                    "error"
+                }
+                Severity::Warning => "warning",
+                Severity::Info => "info",
+            };
+            println!("  {:<8}{}: {}", label, diagnostic.field, diagnostic.message);
+        }
+    }
+
+    println!(
+        "\n{} config(s) checked, {} error(s)",
+        selected.len(),
+        error_count
+    );
+
+    if error_count > 0 {
+        return Err(format!("{} validation error(s)", error_count).into());
+    }
+
+    Ok(())
+}
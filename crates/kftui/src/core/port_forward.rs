@@ -10,14 +10,17 @@ use crossterm::{
     },
 };
 use kftray_commons::models::config_model::Config;
-use kftray_portforward::core::stop_all_port_forward;
-use kftray_portforward::core::{
-    deploy_and_forward_pod,
-    start_port_forward,
-    stop_port_forward,
-    stop_proxy_forward,
+use kftray_commons::models::response::CustomResponse;
+use kftray_portforward::batch::{
+    start_configs_batch,
+    stop_configs_batch,
+    BatchReport,
 };
 use kftray_portforward::models::kube::HttpLogState;
+use kftray_portforward::shutdown::{
+    shutdown,
+    SHUTDOWN_GRACE,
+};
 use log::error;
 
 use crate::tui::input::{
@@ -25,72 +28,59 @@ use crate::tui::input::{
     AppState,
 };
 
-pub async fn start_port_forwarding(app: &mut App, config: Config) {
-    match config.workload_type.as_deref() {
-        Some("proxy") => {
-            if let Err(e) =
-                deploy_and_forward_pod(vec![config.clone()], Arc::new(HttpLogState::new())).await
-            {
-                error!("Failed to start proxy forward: {:?}", e);
-                app.error_message = Some(format!("Failed to start proxy forward: {:?}", e));
-                app.state = AppState::ShowErrorPopup;
-            }
-        }
-        Some("service") | Some("pod") => match config.protocol.as_str() {
-            "tcp" => {
-                let log_state = Arc::new(HttpLogState::new());
-                let result = start_port_forward(vec![config.clone()], "tcp", log_state).await;
-                if let Err(e) = result {
-                    error!("Failed to start TCP port forward: {:?}", e);
-                    app.error_message = Some(format!("Failed to start TCP port forward: {:?}", e));
-                    app.state = AppState::ShowErrorPopup;
-                }
-            }
-            "udp" => {
-                let result =
-                    deploy_and_forward_pod(vec![config.clone()], Arc::new(HttpLogState::new()))
-                        .await;
-                if let Err(e) = result {
-                    error!("Failed to start UDP port forward: {:?}", e);
-                    app.error_message = Some(format!("Failed to start UDP port forward: {:?}", e));
-                    app.state = AppState::ShowErrorPopup;
-                }
-            }
-            _ => {}
-        },
-        _ => {}
+// a lan listener the os firewall gets in the way of would otherwise just look
+// like connection refused from other machines
+fn show_firewall_warnings(app: &mut App, responses: &[CustomResponse]) {
+    let warnings: Vec<String> = responses
+        .iter()
+        .filter_map(|response| response.firewall_warning.as_ref())
+        .map(|warning| format!("{}. {}", warning.message, warning.action))
+        .collect();
+    if !warnings.is_empty() {
+        app.error_message = Some(warnings.join("\n"));
+        app.state = AppState::ShowErrorPopup;
     }
 }
 
-pub async fn stop_port_forwarding(app: &mut App, config: Config) {
-    match config.workload_type.as_deref() {
-        Some("proxy") => {
-            if let Err(e) = stop_proxy_forward(
-                config.id.unwrap_or_default(),
-                &config.namespace,
-                config.service.clone().unwrap_or_default(),
-            )
-            .await
-            {
-                error!("Failed to stop proxy forward: {:?}", e);
-                app.error_message = Some(format!("Failed to stop proxy forward: {:?}", e));
-                app.state = AppState::ShowErrorPopup;
-            }
-        }
-        Some("service") | Some("pod") => {
-            if let Err(e) = stop_port_forward(config.id.unwrap_or_default().to_string()).await {
-                error!("Failed to stop port forward: {:?}", e);
-                app.error_message = Some(format!("Failed to stop port forward: {:?}", e));
-                app.state = AppState::ShowErrorPopup;
-            }
-        }
-        _ => {}
+// the failures of a batch end up in one popup instead of one per config
+fn show_batch_errors(app: &mut App, report: &BatchReport, action: &str) {
+    if report.failed.is_empty() {
+        return;
     }
+    let errors = report.errors();
+    error!(
+        "Failed to {} {} config(s): {:?}",
+        action,
+        errors.len(),
+        errors
+    );
+    app.error_message = Some(format!(
+        "Failed to {} {} config(s):\n{}",
+        action,
+        errors.len(),
+        errors.join("\n")
+    ));
+    app.state = AppState::ShowErrorPopup;
+}
+
+pub async fn start_port_forwarding(
+    app: &mut App, configs: Vec<Config>, reason: Option<&str>,
+) -> Vec<i64> {
+    let report = start_configs_batch(configs, reason, Arc::new(HttpLogState::new())).await;
+    show_firewall_warnings(app, &report.responses);
+    show_batch_errors(app, &report, "start");
+    report.succeeded
+}
+
+pub async fn stop_port_forwarding(app: &mut App, configs: &[Config]) -> Vec<i64> {
+    let report = stop_configs_batch(configs).await;
+    show_batch_errors(app, &report, "stop");
+    report.succeeded
 }
 
 pub async fn stop_all_port_forward_and_exit(app: &mut App) {
     log::debug!("Stopping all port forwards...");
-    match stop_all_port_forward().await {
+    match shutdown(SHUTDOWN_GRACE).await {
         Ok(responses) => {
             for response in responses {
                 if response.status != 0 {
@@ -106,6 +96,10 @@ pub async fn stop_all_port_forward_and_exit(app: &mut App) {
     }
     log::debug!("Exiting application...");
 
+    restore_terminal_and_exit();
+}
+
+pub fn restore_terminal_and_exit() {
     disable_raw_mode().expect("Failed to disable raw mode");
     execute!(std::io::stdout(), LeaveAlternateScreen, Show)
         .expect("Failed to leave alternate screen and show cursor");
@@ -1,4 +1,5 @@
 #![allow(clippy::needless_return)]
+mod cli;
 mod core;
 mod tui;
 mod utils;
@@ -9,5 +10,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tui_logger::init_logger(log::LevelFilter::Debug).unwrap();
     tui_logger::set_default_level(log::LevelFilter::Debug);
 
-    run_tui().await
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match cli::parse_args(&args) {
+        Ok(Some(command)) => cli::run_command(command).await,
+        Ok(None) => run_tui().await,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    }
 }
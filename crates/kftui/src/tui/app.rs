@@ -9,10 +9,13 @@ use crossterm::{
         LeaveAlternateScreen,
     },
 };
-use kftray_commons::config::read_configs;
 use kftray_commons::utils::config_state::read_config_states;
 use kftray_commons::utils::db::init;
 use kftray_commons::utils::migration::migrate_configs;
+use kftray_commons::utils::profile::read_active_profile_configs;
+use kftray_commons::utils::settings::get_settings;
+use kftray_portforward::reconcile::reconcile_config_states;
+use kftray_portforward::shutdown::install_shutdown_handler;
 use log::error;
 use ratatui::{
     backend::CrosstermBackend,
@@ -23,6 +26,7 @@ use tokio::time::{
     Duration,
 };
 
+use crate::core::port_forward::restore_terminal_and_exit;
 use crate::tui::input::{
     handle_input,
     App,
@@ -36,6 +40,16 @@ pub async fn run_tui() -> Result<(), Box<dyn std::error::Error>> {
         error!("Failed to migrate configs: {}", e);
     }
 
+    if let Err(e) = get_settings().await {
+        error!("Failed to load settings: {}", e);
+    }
+
+    if let Err(e) = reconcile_config_states().await {
+        error!("Failed to reconcile config states: {}", e);
+    }
+
+    install_shutdown_handler(restore_terminal_and_exit);
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
@@ -63,10 +77,14 @@ async fn run_app<B: ratatui::backend::Backend>(
     let mut interval = time::interval(Duration::from_millis(100));
 
     loop {
-        let configs = read_configs().await.unwrap_or_default();
+        let configs = read_active_profile_configs().await.unwrap_or_default();
         let mut config_states = read_config_states().await.unwrap_or_default();
 
         app.update_configs(&configs, &config_states);
+        app.refresh_traffic();
+        if let Some(form) = &mut app.edit_form {
+            form.poll();
+        }
 
         terminal.draw(|f| {
             draw_ui(f, app, &config_states);
@@ -0,0 +1,91 @@
+use std::collections::HashSet;
+
+use kftray_portforward::discovery::{
+    DiscoveryDiffEntry,
+    DiscoveryStatus,
+};
+use ratatui::widgets::ListState;
+
+// what discovery found in one context, and which of it to import. new and
+// changed configs start out picked, unchanged ones have nothing to import
+pub struct DiscoveryWizard {
+    pub context: String,
+    pub entries: Vec<DiscoveryDiffEntry>,
+    pub picked: HashSet<usize>,
+    pub list_state: ListState,
+}
+
+impl DiscoveryWizard {
+    pub fn new(context: String, entries: Vec<DiscoveryDiffEntry>) -> Self {
+        let picked = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.status != DiscoveryStatus::Unchanged)
+            .map(|(index, _)| index)
+            .collect();
+        let mut list_state = ListState::default();
+        list_state.select((!entries.is_empty()).then_some(0));
+        DiscoveryWizard {
+            context,
+            entries,
+            picked,
+            list_state,
+        }
+    }
+
+    pub fn count(&self, status: DiscoveryStatus) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.status == status)
+            .count()
+    }
+
+    pub fn next(&mut self) {
+        if let Some(selected) = self.list_state.selected() {
+            self.list_state
+                .select(Some((selected + 1).min(self.entries.len() - 1)));
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if let Some(selected) = self.list_state.selected() {
+            self.list_state.select(Some(selected.saturating_sub(1)));
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        let Some(selected) = self.list_state.selected() else {
+            return;
+        };
+        if self.entries[selected].status == DiscoveryStatus::Unchanged {
+            return;
+        }
+        if !self.picked.remove(&selected) {
+            self.picked.insert(selected);
+        }
+    }
+
+    pub fn toggle_all(&mut self) {
+        let importable: HashSet<usize> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.status != DiscoveryStatus::Unchanged)
+            .map(|(index, _)| index)
+            .collect();
+        if self.picked == importable {
+            self.picked.clear();
+        } else {
+            self.picked = importable;
+        }
+    }
+
+    pub fn picked_entries(&self) -> Vec<DiscoveryDiffEntry> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.picked.contains(index))
+            .map(|(_, entry)| entry.clone())
+            .collect()
+    }
+}
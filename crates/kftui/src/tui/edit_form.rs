@@ -0,0 +1,295 @@
+use futures::FutureExt;
+use kftray_commons::models::config_model::Config;
+use kftray_commons::models::config_state_model::ConfigState;
+use kftray_commons::models::diagnostic::Diagnostic;
+use kftray_commons::utils::config_validation::validate_config_fields;
+use kftray_portforward::validation::validate_config_with_existing;
+use tokio::task::JoinHandle;
+use tokio::time::{
+    Duration,
+    Instant,
+};
+
+// the cluster is only asked once typing paused for this long
+const CLUSTER_CHECK_DELAY: Duration = Duration::from_millis(700);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FieldKind {
+    Text,
+    Port,
+    Toggle,
+}
+
+pub struct FormField {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub kind: FieldKind,
+    pub value: String,
+}
+
+const FIELDS: [(&str, &str, FieldKind); 16] = [
+    ("alias", "Alias", FieldKind::Text),
+    ("display_name", "Display name", FieldKind::Text),
+    ("icon", "Icon", FieldKind::Text),
+    ("color", "Color", FieldKind::Text),
+    ("context", "Context", FieldKind::Text),
+    ("kubeconfig", "Kubeconfig", FieldKind::Text),
+    ("namespace", "Namespace", FieldKind::Text),
+    ("workload_type", "Workload type", FieldKind::Text),
+    ("service", "Service", FieldKind::Text),
+    ("target", "Pod selector", FieldKind::Text),
+    ("remote_address", "Remote address", FieldKind::Text),
+    ("protocol", "Protocol", FieldKind::Text),
+    ("local_address", "Local address", FieldKind::Text),
+    ("local_port", "Local port", FieldKind::Port),
+    ("remote_port", "Remote port", FieldKind::Port),
+    ("domain_enabled", "Domain", FieldKind::Toggle),
+];
+
+fn field_value(config: &Config, name: &str) -> String {
+    let value = match name {
+        "alias" => config.alias.clone(),
+        "display_name" => config.display_name.clone(),
+        "icon" => config.icon.clone(),
+        "color" => config.color.clone(),
+        "context" => Some(config.context.clone()),
+        "kubeconfig" => config.kubeconfig.clone(),
+        "namespace" => Some(config.namespace.clone()),
+        "workload_type" => config.workload_type.clone(),
+        "service" => config.service.clone(),
+        "target" => config.target.clone(),
+        "remote_address" => config.remote_address.clone(),
+        "protocol" => Some(config.protocol.clone()),
+        "local_address" => config.local_address.clone(),
+        "local_port" => config.local_port.map(|port| port.to_string()),
+        "remote_port" => config.remote_port.map(|port| port.to_string()),
+        "domain_enabled" => Some(if config.domain_enabled.unwrap_or_default() {
+            "on".to_string()
+        } else {
+            "off".to_string()
+        }),
+        _ => None,
+    };
+    value.unwrap_or_default()
+}
+
+// Config::default() fills in placeholders, a new config starts out blank
+pub fn new_config_template() -> Config {
+    Config {
+        service: None,
+        namespace: "default".to_string(),
+        local_port: None,
+        remote_port: None,
+        context: String::new(),
+        workload_type: Some("service".to_string()),
+        protocol: "tcp".to_string(),
+        remote_address: None,
+        alias: None,
+        target: None,
+        ..Default::default()
+    }
+}
+
+fn optional(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+// the config the form describes. values that don't parse, like a port that
+// isn't a number, come back as errors on their field
+fn form_config(base: &Config, fields: &[FormField]) -> (Config, Vec<Diagnostic>) {
+    let mut config = base.clone();
+    let mut diagnostics = Vec::new();
+
+    for field in fields {
+        let value = field.value.trim();
+        match field.name {
+            "alias" => config.alias = optional(value),
+            "display_name" => config.display_name = optional(value),
+            "icon" => config.icon = optional(value),
+            "color" => config.color = optional(value),
+            "context" => config.context = value.to_string(),
+            "kubeconfig" => config.kubeconfig = optional(value),
+            "namespace" => config.namespace = value.to_string(),
+            "workload_type" => config.workload_type = optional(value),
+            "service" => config.service = optional(value),
+            "target" => config.target = optional(value),
+            "remote_address" => config.remote_address = optional(value),
+            "protocol" => config.protocol = value.to_string(),
+            "local_address" => config.local_address = optional(value),
+            "local_port" | "remote_port" => {
+                let port = match value {
+                    "" => None,
+                    value => match value.parse::<u16>() {
+                        Ok(port) => Some(port),
+                        Err(_) => {
+                            diagnostics.push(Diagnostic::error(
+                                field.name,
+                                format!("'{}' is not a port between 0 and 65535", value),
+                            ));
+                            continue;
+                        }
+                    },
+                };
+                if field.name == "local_port" {
+                    config.local_port = port;
+                } else {
+                    config.remote_port = port;
+                }
+            }
+            "domain_enabled" => config.domain_enabled = Some(value == "on"),
+            _ => {}
+        }
+    }
+    (config, diagnostics)
+}
+
+// edits one config, or a new one when the base has no id. the field checks run
+// on every keystroke, the cluster checks once typing pauses
+pub struct EditForm {
+    pub base: Config,
+    pub fields: Vec<FormField>,
+    pub selected: usize,
+    pub diagnostics: Vec<Diagnostic>,
+    pub checking_cluster: bool,
+    // why the last save didn't go through
+    pub status: Option<String>,
+    existing: Vec<Config>,
+    states: Vec<ConfigState>,
+    edited_at: Option<Instant>,
+    cluster_check: Option<JoinHandle<Vec<Diagnostic>>>,
+}
+
+impl EditForm {
+    pub fn new(base: Config, existing: Vec<Config>, states: Vec<ConfigState>) -> Self {
+        let fields = FIELDS
+            .iter()
+            .map(|&(name, label, kind)| FormField {
+                name,
+                label,
+                kind,
+                value: field_value(&base, name),
+            })
+            .collect();
+        let mut form = EditForm {
+            base,
+            fields,
+            selected: 0,
+            diagnostics: Vec::new(),
+            checking_cluster: false,
+            status: None,
+            existing,
+            states,
+            edited_at: None,
+            cluster_check: None,
+        };
+        form.changed();
+        form
+    }
+
+    pub fn is_new(&self) -> bool {
+        self.base.id.is_none()
+    }
+
+    pub fn config(&self) -> (Config, Vec<Diagnostic>) {
+        form_config(&self.base, &self.fields)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.is_error())
+    }
+
+    pub fn field_diagnostics(&self, name: &str) -> impl Iterator<Item = &Diagnostic> {
+        let name = name.to_string();
+        self.diagnostics
+            .iter()
+            .filter(move |diagnostic| diagnostic.field == name)
+    }
+
+    // the diagnostics about things the form has no field for
+    pub fn other_diagnostics(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| !FIELDS.iter().any(|(name, ..)| *name == diagnostic.field))
+    }
+
+    pub fn next_field(&mut self) {
+        self.selected = (self.selected + 1) % self.fields.len();
+    }
+
+    pub fn previous_field(&mut self) {
+        self.selected = (self.selected + self.fields.len() - 1) % self.fields.len();
+    }
+
+    pub fn input(&mut self, c: char) {
+        let field = &mut self.fields[self.selected];
+        match field.kind {
+            FieldKind::Toggle => {
+                field.value = if field.value == "on" { "off" } else { "on" }.to_string();
+            }
+            FieldKind::Port if !c.is_ascii_digit() => return,
+            _ => field.value.push(c),
+        }
+        self.changed();
+    }
+
+    pub fn backspace(&mut self) {
+        let field = &mut self.fields[self.selected];
+        if field.kind == FieldKind::Toggle || field.value.pop().is_none() {
+            return;
+        }
+        self.changed();
+    }
+
+    fn changed(&mut self) {
+        let (config, mut diagnostics) = self.config();
+        diagnostics.extend(validate_config_fields(
+            &config,
+            &self.existing,
+            &self.states,
+        ));
+        self.diagnostics = diagnostics;
+
+        if let Some(check) = self.cluster_check.take() {
+            check.abort();
+        }
+        self.edited_at = Some(Instant::now());
+        self.checking_cluster = false;
+        self.status = None;
+    }
+
+    // called on every tick of the ui loop
+    pub fn poll(&mut self) {
+        if let Some(check) = &mut self.cluster_check {
+            if let Some(result) = check.now_or_never() {
+                self.cluster_check = None;
+                self.checking_cluster = false;
+                if let Ok(diagnostics) = result {
+                    self.diagnostics = diagnostics;
+                }
+            }
+            return;
+        }
+
+        let Some(edited_at) = self.edited_at else {
+            return;
+        };
+        if edited_at.elapsed() < CLUSTER_CHECK_DELAY {
+            return;
+        }
+        self.edited_at = None;
+
+        // no point asking the cluster about a config that can't be read
+        let (config, parse_errors) = self.config();
+        if !parse_errors.is_empty() {
+            return;
+        }
+        let existing = self.existing.clone();
+        self.checking_cluster = true;
+        self.cluster_check = Some(tokio::spawn(async move {
+            validate_config_with_existing(&config, &existing).await
+        }));
+    }
+}
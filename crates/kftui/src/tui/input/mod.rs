@@ -17,6 +17,17 @@ use kftray_commons::models::{
     config_model::Config,
     config_state_model::ConfigState,
 };
+use kftray_commons::utils::access_audit::needs_access_reason;
+use kftray_commons::utils::bulk_update::{
+    tag_configs,
+    untag_configs,
+};
+use kftray_commons::utils::config::{
+    insert_config,
+    read_configs,
+    update_config,
+};
+use kftray_commons::utils::config_state::get_configs_state;
 use log::LevelFilter;
 pub use popup::*;
 use ratatui::widgets::ListState;
@@ -29,9 +40,16 @@ use tui_logger::TuiWidgetEvent;
 use tui_logger::TuiWidgetState;
 
 use crate::core::port_forward::stop_all_port_forward_and_exit;
+use crate::tui::discovery::DiscoveryWizard;
+use crate::tui::edit_form::{
+    new_config_template,
+    EditForm,
+};
 use crate::tui::input::navigation::handle_auto_add_configs;
 use crate::tui::input::navigation::handle_context_selection;
 use crate::tui::input::navigation::handle_port_forward;
+use crate::tui::input::navigation::import_discovered_configs;
+use crate::tui::traffic::TrafficView;
 #[derive(PartialEq, Clone, Copy)]
 pub enum DeleteButton {
     Confirm,
@@ -65,6 +83,10 @@ pub enum AppState {
     ShowAbout,
     ShowDeleteConfirmation,
     ShowContextSelection,
+    ShowDiscoveryDiff,
+    ShowReasonPrompt,
+    ShowTagPrompt,
+    EditConfig,
 }
 
 pub struct App {
@@ -80,6 +102,10 @@ pub struct App {
     pub active_table: ActiveTable,
     pub import_export_message: Option<String>,
     pub input_buffer: String,
+    // configs waiting on a reason before they can start in a production context
+    pub pending_reason_configs: Vec<Config>,
+    // the marked configs the tag prompt applies to
+    pub pending_tag_configs: Vec<Config>,
     pub selected_file_path: Option<std::path::PathBuf>,
     pub file_content: Option<String>,
     pub stopped_configs: Vec<Config>,
@@ -96,6 +122,11 @@ pub struct App {
     pub selected_context_index: usize,
     pub context_list_state: ListState,
     pub logger_state: TuiWidgetState,
+    // the logs pane shows the traffic of the selected running forward instead
+    pub show_traffic: bool,
+    pub traffic: Option<TrafficView>,
+    pub edit_form: Option<EditForm>,
+    pub discovery: Option<DiscoveryWizard>,
 }
 
 impl App {
@@ -118,6 +149,8 @@ impl App {
             selected_rows_running: HashSet::new(),
             import_export_message: None,
             input_buffer: String::new(),
+            pending_reason_configs: Vec::new(),
+            pending_tag_configs: Vec::new(),
             selected_file_path: None,
             file_content: None,
             stopped_configs: Vec::new(),
@@ -134,6 +167,10 @@ impl App {
             selected_context_index: 0,
             context_list_state: ListState::default(),
             logger_state,
+            show_traffic: false,
+            traffic: None,
+            edit_form: None,
+            discovery: None,
         };
 
         if let Ok((_, height)) = size() {
@@ -173,6 +210,26 @@ impl App {
             .collect();
     }
 
+    pub fn refresh_traffic(&mut self) {
+        let config_id = if self.show_traffic {
+            let row = self
+                .table_state_running
+                .selected()
+                .unwrap_or(self.selected_row_running);
+            self.running_configs.get(row).and_then(|config| config.id)
+        } else {
+            None
+        };
+
+        match config_id {
+            Some(config_id) => match &mut self.traffic {
+                Some(view) if view.config_id == config_id => view.poll(),
+                _ => self.traffic = Some(TrafficView::new(config_id)),
+            },
+            None => self.traffic = None,
+        }
+    }
+
     pub fn scroll_up(&mut self) {
         match self.active_table {
             ActiveTable::Stopped => {
@@ -292,6 +349,22 @@ pub async fn handle_input(app: &mut App, _config_states: &mut [ConfigState]) ->
                     log::debug!("Handling ShowContextSelection state");
                     handle_context_selection_input(app, key.code).await?;
                 }
+                AppState::ShowDiscoveryDiff => {
+                    log::debug!("Handling ShowDiscoveryDiff state");
+                    handle_discovery_diff_input(app, key.code).await?;
+                }
+                AppState::ShowReasonPrompt => {
+                    log::debug!("Handling ShowReasonPrompt state");
+                    handle_reason_prompt_input(app, key.code).await?;
+                }
+                AppState::ShowTagPrompt => {
+                    log::debug!("Handling ShowTagPrompt state");
+                    handle_tag_prompt_input(app, key.code).await?;
+                }
+                AppState::EditConfig => {
+                    log::debug!("Handling EditConfig state");
+                    handle_edit_form_input(app, key.code).await?;
+                }
                 AppState::Normal => {
                     log::debug!("Handling Normal state");
                     handle_normal_input(app, key.code).await?;
@@ -509,6 +582,9 @@ async fn handle_stopped_table_input(app: &mut App, key: KeyCode) -> io::Result<(
         KeyCode::Char('f') => handle_port_forwarding(app).await?,
         KeyCode::Char('d') => show_delete_confirmation(app),
         KeyCode::Char('a') => toggle_select_all(app),
+        KeyCode::Char('g') => show_tag_prompt(app),
+        KeyCode::Enter => open_edit_form(app, false).await,
+        KeyCode::Char('n') => open_edit_form(app, true).await,
         _ => {}
     }
     Ok(())
@@ -552,6 +628,10 @@ async fn handle_running_table_input(app: &mut App, key: KeyCode) -> io::Result<(
         KeyCode::Char('f') => handle_port_forwarding(app).await?,
         KeyCode::Char('d') => show_delete_confirmation(app),
         KeyCode::Char('a') => toggle_select_all(app),
+        KeyCode::Char('g') => show_tag_prompt(app),
+        KeyCode::Enter => open_edit_form(app, false).await,
+        KeyCode::Char('n') => open_edit_form(app, true).await,
+        KeyCode::Char('t') => app.show_traffic = !app.show_traffic,
         _ => {}
     }
     Ok(())
@@ -600,6 +680,7 @@ async fn handle_logs_input(app: &mut App, key: KeyCode) -> io::Result<()> {
         KeyCode::Esc => app.logger_state.transition(TuiWidgetEvent::EscapeKey),
         KeyCode::Char('h') => app.logger_state.transition(TuiWidgetEvent::HideKey),
         KeyCode::Char('f') => app.logger_state.transition(TuiWidgetEvent::FocusKey),
+        KeyCode::Char('t') => app.show_traffic = !app.show_traffic,
         _ => {}
     }
     Ok(())
@@ -646,7 +727,8 @@ fn toggle_row_selection(app: &mut App) {
     }
 }
 
-async fn handle_port_forwarding(app: &mut App) -> io::Result<()> {
+// the rows marked with space, or the row under the cursor when none are
+fn marked_configs(app: &mut App) -> Vec<Config> {
     let (selected_rows, configs, selected_row) = match app.active_table {
         ActiveTable::Stopped => (
             &mut app.selected_rows_stopped,
@@ -661,37 +743,229 @@ async fn handle_port_forwarding(app: &mut App) -> io::Result<()> {
     };
 
     if configs.is_empty() {
-        return Ok(());
+        return Vec::new();
     }
 
     if selected_rows.is_empty() {
         selected_rows.insert(selected_row);
     }
 
-    let selected_configs: Vec<Config> = selected_rows
-        .iter()
-        .filter_map(|&row| configs.get(row).cloned())
-        .collect();
+    let mut rows: Vec<usize> = selected_rows.iter().copied().collect();
+    rows.sort_unstable();
+    rows.into_iter()
+        .filter_map(|row| configs.get(row).cloned())
+        .collect()
+}
+
+fn clear_marked_rows(app: &mut App) {
+    match app.active_table {
+        ActiveTable::Stopped => app.selected_rows_stopped.clear(),
+        ActiveTable::Running => app.selected_rows_running.clear(),
+    }
+}
 
-    for config in selected_configs.clone() {
-        handle_port_forward(app, config).await;
+async fn handle_port_forwarding(app: &mut App) -> io::Result<()> {
+    let selected_configs = marked_configs(app);
+    if selected_configs.is_empty() {
+        return Ok(());
     }
 
     if app.active_table == ActiveTable::Stopped {
-        app.running_configs.extend(selected_configs.clone());
-        app.stopped_configs
-            .retain(|config| !selected_configs.contains(config));
+        match needs_access_reason(&selected_configs).await {
+            Ok(true) => {
+                app.pending_reason_configs = selected_configs;
+                app.input_buffer.clear();
+                app.state = AppState::ShowReasonPrompt;
+                return Ok(());
+            }
+            Ok(false) => {}
+            Err(e) => log::error!("Failed to check for production contexts: {}", e),
+        }
+    }
+
+    toggle_port_forwarding(app, selected_configs, None).await;
+    Ok(())
+}
+
+async fn toggle_port_forwarding(
+    app: &mut App, selected_configs: Vec<Config>, reason: Option<&str>,
+) {
+    let done = handle_port_forward(app, selected_configs.clone(), reason).await;
+    let moved: Vec<Config> = selected_configs
+        .into_iter()
+        .filter(|config| config.id.is_some_and(|id| done.contains(&id)))
+        .collect();
+
+    if app.active_table == ActiveTable::Stopped {
+        app.running_configs.extend(moved.clone());
+        app.stopped_configs.retain(|config| !moved.contains(config));
     } else {
-        app.stopped_configs.extend(selected_configs.clone());
-        app.running_configs
-            .retain(|config| !selected_configs.contains(config));
+        app.stopped_configs.extend(moved.clone());
+        app.running_configs.retain(|config| !moved.contains(config));
     }
 
-    match app.active_table {
-        ActiveTable::Stopped => app.selected_rows_stopped.clear(),
-        ActiveTable::Running => app.selected_rows_running.clear(),
+    clear_marked_rows(app);
+}
+
+fn show_tag_prompt(app: &mut App) {
+    app.pending_tag_configs = marked_configs(app);
+    if !app.pending_tag_configs.is_empty() {
+        app.input_buffer.clear();
+        app.state = AppState::ShowTagPrompt;
+    }
+}
+
+// `name` adds the tag to the marked configs, `-name` takes it off them
+async fn handle_tag_prompt_input(app: &mut App, key: KeyCode) -> io::Result<()> {
+    match key {
+        KeyCode::Enter => {
+            let input = app.input_buffer.trim().to_string();
+            let (tag, add) = match input.strip_prefix('-') {
+                Some(tag) => (tag.trim().to_string(), false),
+                None => (input, true),
+            };
+            if tag.is_empty() {
+                return Ok(());
+            }
+
+            let ids: Vec<i64> = std::mem::take(&mut app.pending_tag_configs)
+                .iter()
+                .filter_map(|config| config.id)
+                .collect();
+            app.input_buffer.clear();
+            app.state = AppState::Normal;
+
+            let result = if add {
+                tag_configs(ids, tag.clone()).await
+            } else {
+                untag_configs(ids, tag.clone()).await
+            };
+            match result {
+                Ok(changes) => {
+                    log::info!(
+                        "{} tag {} on {} config(s)",
+                        if add { "Set" } else { "Removed" },
+                        tag,
+                        changes.len()
+                    );
+                    clear_marked_rows(app);
+                }
+                Err(e) => {
+                    app.error_message = Some(format!("Failed to update tags: {}", e));
+                    app.state = AppState::ShowErrorPopup;
+                }
+            }
+        }
+        KeyCode::Char(c) => app.input_buffer.push(c),
+        KeyCode::Backspace => {
+            app.input_buffer.pop();
+        }
+        KeyCode::Esc => {
+            app.pending_tag_configs.clear();
+            app.input_buffer.clear();
+            app.state = AppState::Normal;
+        }
+        _ => {}
     }
+    Ok(())
+}
 
+async fn handle_reason_prompt_input(app: &mut App, key: KeyCode) -> io::Result<()> {
+    match key {
+        KeyCode::Enter => {
+            let reason = app.input_buffer.trim().to_string();
+            if reason.is_empty() {
+                return Ok(());
+            }
+            let configs = std::mem::take(&mut app.pending_reason_configs);
+            app.input_buffer.clear();
+            app.state = AppState::Normal;
+            toggle_port_forwarding(app, configs, Some(&reason)).await;
+        }
+        KeyCode::Char(c) => app.input_buffer.push(c),
+        KeyCode::Backspace => {
+            app.input_buffer.pop();
+        }
+        KeyCode::Esc => {
+            app.pending_reason_configs.clear();
+            app.input_buffer.clear();
+            app.state = AppState::Normal;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+async fn open_edit_form(app: &mut App, new: bool) {
+    let base = if new {
+        new_config_template()
+    } else {
+        let config = match app.active_table {
+            ActiveTable::Stopped => app.stopped_configs.get(app.selected_row_stopped),
+            ActiveTable::Running => app.running_configs.get(app.selected_row_running),
+        };
+        match config {
+            Some(config) => config.clone(),
+            None => return,
+        }
+    };
+
+    let existing = read_configs().await.unwrap_or_else(|e| {
+        log::error!("Failed to read configs for validation: {}", e);
+        Vec::new()
+    });
+    let states = get_configs_state().await.unwrap_or_default();
+    app.edit_form = Some(EditForm::new(base, existing, states));
+    app.state = AppState::EditConfig;
+}
+
+async fn save_edit_form(app: &mut App) {
+    let Some(form) = &mut app.edit_form else {
+        return;
+    };
+    if form.has_errors() {
+        form.status = Some("Fix the errors before saving".to_string());
+        return;
+    }
+
+    let (config, _) = form.config();
+    let result = if form.is_new() {
+        insert_config(config).await
+    } else {
+        update_config(config).await
+    };
+    match result {
+        Ok(()) => {
+            log::info!(
+                "{} config {}",
+                if form.is_new() { "Added" } else { "Saved" },
+                form.fields[0].value
+            );
+            app.edit_form = None;
+            app.state = AppState::Normal;
+        }
+        Err(e) => form.status = Some(format!("Failed to save: {}", e)),
+    }
+}
+
+async fn handle_edit_form_input(app: &mut App, key: KeyCode) -> io::Result<()> {
+    let Some(form) = &mut app.edit_form else {
+        app.state = AppState::Normal;
+        return Ok(());
+    };
+
+    match key {
+        KeyCode::Enter => save_edit_form(app).await,
+        KeyCode::Esc => {
+            app.edit_form = None;
+            app.state = AppState::Normal;
+        }
+        KeyCode::Down | KeyCode::Tab => form.next_field(),
+        KeyCode::Up | KeyCode::BackTab => form.previous_field(),
+        KeyCode::Backspace => form.backspace(),
+        KeyCode::Char(c) => form.input(c),
+        _ => {}
+    }
     Ok(())
 }
 
@@ -769,6 +1043,29 @@ pub async fn handle_context_selection_input(app: &mut App, key: KeyCode) -> io::
             app.context_list_state
                 .select(Some(app.selected_context_index));
         }
+    } else if let KeyCode::Esc = key {
+        app.state = AppState::Normal;
+    }
+    Ok(())
+}
+
+pub async fn handle_discovery_diff_input(app: &mut App, key: KeyCode) -> io::Result<()> {
+    let Some(discovery) = &mut app.discovery else {
+        app.state = AppState::Normal;
+        return Ok(());
+    };
+
+    match key {
+        KeyCode::Enter => import_discovered_configs(app).await,
+        KeyCode::Esc => {
+            app.discovery = None;
+            app.state = AppState::Normal;
+        }
+        KeyCode::Up => discovery.previous(),
+        KeyCode::Down => discovery.next(),
+        KeyCode::Char(' ') => discovery.toggle(),
+        KeyCode::Char('a') => discovery.toggle_all(),
+        _ => {}
     }
     Ok(())
 }
@@ -1,23 +1,29 @@
 use kftray_commons::models::config_model::Config;
-use kftray_commons::utils::config::insert_config;
 use kftray_portforward::client::list_kube_contexts;
-use kftray_portforward::core::retrieve_service_configs;
+use kftray_portforward::discovery::{
+    discover_config_diff,
+    import_discovery_diff,
+};
 
 use crate::core::port_forward::{
     start_port_forwarding,
     stop_port_forwarding,
 };
+use crate::tui::discovery::DiscoveryWizard;
 use crate::tui::input::ActiveTable;
 use crate::tui::input::{
     App,
     AppState,
 };
 
-pub async fn handle_port_forward(app: &mut App, config: Config) {
+// returns the ids of the configs that were started or stopped
+pub async fn handle_port_forward(
+    app: &mut App, configs: Vec<Config>, reason: Option<&str>,
+) -> Vec<i64> {
     if app.active_table == ActiveTable::Stopped {
-        start_port_forwarding(app, config).await;
+        start_port_forwarding(app, configs, reason).await
     } else {
-        stop_port_forwarding(app, config).await;
+        stop_port_forwarding(app, &configs).await
     }
 }
 
@@ -37,9 +43,10 @@ pub async fn handle_auto_add_configs(app: &mut App) {
     app.context_list_state.select(Some(0));
 }
 
+// nothing is saved yet, the wizard shows what discovery would add or change
 pub async fn handle_context_selection(app: &mut App, context: &str) {
-    let configs = match retrieve_service_configs(context, None).await {
-        Ok(configs) => configs,
+    let entries = match discover_config_diff(context, None).await {
+        Ok(entries) => entries,
         Err(e) => {
             app.error_message = Some(format!("Failed to retrieve service configs: {}", e));
             app.state = AppState::ShowErrorPopup;
@@ -47,13 +54,27 @@ pub async fn handle_context_selection(app: &mut App, context: &str) {
         }
     };
 
-    for config in configs {
-        if let Err(e) = insert_config(config).await {
-            app.error_message = Some(format!("Failed to insert config: {}", e));
+    app.discovery = Some(DiscoveryWizard::new(context.to_string(), entries));
+    app.state = AppState::ShowDiscoveryDiff;
+}
+
+pub async fn import_discovered_configs(app: &mut App) {
+    let Some(discovery) = app.discovery.take() else {
+        return;
+    };
+
+    match import_discovery_diff(discovery.picked_entries()).await {
+        Ok(imported) => {
+            log::info!(
+                "Imported {} config(s) from context {}",
+                imported,
+                discovery.context
+            );
+            app.state = AppState::Normal;
+        }
+        Err(e) => {
+            app.error_message = Some(format!("Failed to import configs: {}", e));
             app.state = AppState::ShowErrorPopup;
-            return;
         }
     }
-
-    app.state = AppState::Normal;
 }
@@ -1,5 +1,8 @@
 mod app;
+mod discovery;
+mod edit_form;
 pub mod input;
+mod traffic;
 mod ui;
 
 pub use app::run_tui;
@@ -0,0 +1,143 @@
+use std::collections::VecDeque;
+
+use kftray_commons::models::access_log_model::AccessLogEntry;
+use kftray_commons::models::http_log_model::{
+    HttpLogKind,
+    HttpLogRecord,
+};
+use kftray_commons::utils::http_log_stream::{
+    subscribe_http_logs,
+    HttpLogSubscription,
+};
+use kftray_portforward::traffic::{
+    get_traffic,
+    subscribe_connection_logs,
+    throughput,
+    TrafficSnapshot,
+};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::TryRecvError;
+use tokio::time::{
+    Duration,
+    Instant,
+};
+
+const SAMPLE_EVERY: Duration = Duration::from_secs(1);
+// a minute of throughput, one bar per second
+const THROUGHPUT_HISTORY: usize = 60;
+const ENTRY_HISTORY: usize = 200;
+
+// the live traffic of the forward selected in the running table
+pub struct TrafficView {
+    pub config_id: i64,
+    pub snapshot: TrafficSnapshot,
+    pub throughput: VecDeque<u64>,
+    pub entries: VecDeque<String>,
+    sampled_at: Instant,
+    http_logs: HttpLogSubscription,
+    connection_logs: broadcast::Receiver<AccessLogEntry>,
+}
+
+impl TrafficView {
+    pub fn new(config_id: i64) -> Self {
+        let mut view = TrafficView {
+            config_id,
+            snapshot: get_traffic(config_id),
+            throughput: VecDeque::with_capacity(THROUGHPUT_HISTORY),
+            entries: VecDeque::with_capacity(ENTRY_HISTORY),
+            sampled_at: Instant::now(),
+            http_logs: subscribe_http_logs(config_id),
+            connection_logs: subscribe_connection_logs(),
+        };
+        view.poll();
+        view
+    }
+
+    // called on every tick of the ui loop, so nothing in here may wait
+    pub fn poll(&mut self) {
+        let elapsed = self.sampled_at.elapsed();
+        if elapsed >= SAMPLE_EVERY {
+            let snapshot = get_traffic(self.config_id);
+            self.push_throughput(throughput(&self.snapshot, &snapshot, elapsed));
+            self.snapshot = snapshot;
+            self.sampled_at = Instant::now();
+        }
+
+        while let Some(record) = self.http_logs.try_recv() {
+            self.push_entry(http_log_line(&record));
+        }
+
+        loop {
+            match self.connection_logs.try_recv() {
+                Ok(entry) if entry.config_id == self.config_id => {
+                    self.push_entry(connection_line(&entry))
+                }
+                Ok(_) | Err(TryRecvError::Lagged(_)) => {}
+                Err(_) => break,
+            }
+        }
+    }
+
+    fn push_throughput(&mut self, bytes_per_sec: u64) {
+        if self.throughput.len() == THROUGHPUT_HISTORY {
+            self.throughput.pop_front();
+        }
+        self.throughput.push_back(bytes_per_sec);
+    }
+
+    fn push_entry(&mut self, line: String) {
+        if self.entries.len() == ENTRY_HISTORY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(line);
+    }
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+fn http_log_line(record: &HttpLogRecord) -> String {
+    let method = record.method.as_deref().unwrap_or("-");
+    let path = record.path.as_deref().unwrap_or("");
+    match record.kind {
+        HttpLogKind::Request => format!("→ {} {}", method, path),
+        HttpLogKind::Response => format!(
+            "← {} {}{}",
+            record
+                .status
+                .map(|status| status.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            path,
+            record
+                .took_ms
+                .map(|took_ms| format!(" ({} ms)", took_ms))
+                .unwrap_or_default()
+        ),
+    }
+}
+
+fn connection_line(entry: &AccessLogEntry) -> String {
+    let mut line = format!(
+        "conn {} {} ms, in {} out {}",
+        entry.client_addr,
+        entry.duration_ms,
+        format_bytes(entry.bytes_in),
+        format_bytes(entry.bytes_out)
+    );
+    if let Some(error) = &entry.error {
+        line.push_str(&format!(", {}", error));
+    }
+    line
+}
@@ -0,0 +1,139 @@
+use kftray_portforward::discovery::{
+    DiscoveryDiffEntry,
+    DiscoveryStatus,
+};
+use ratatui::{
+    layout::{
+        Constraint,
+        Direction,
+        Layout,
+        Rect,
+    },
+    style::{
+        Modifier,
+        Style,
+    },
+    text::{
+        Line,
+        Span,
+    },
+    widgets::{
+        Block,
+        Borders,
+        Clear,
+        List,
+        ListItem,
+        Paragraph,
+    },
+    Frame,
+};
+
+use crate::tui::discovery::DiscoveryWizard;
+use crate::tui::ui::{
+    BASE,
+    GREEN,
+    MAUVE,
+    SUBTEXT0,
+    TEXT,
+    YELLOW,
+};
+
+fn entry_item(entry: &DiscoveryDiffEntry, picked: bool) -> ListItem<'static> {
+    let (label, color) = match entry.status {
+        DiscoveryStatus::New => ("new", GREEN),
+        DiscoveryStatus::Changed => ("changed", YELLOW),
+        DiscoveryStatus::Unchanged => ("same", SUBTEXT0),
+    };
+    let checkbox = match (entry.status, picked) {
+        (DiscoveryStatus::Unchanged, _) => "   ",
+        (_, true) => "[x]",
+        (_, false) => "[ ]",
+    };
+    let config = &entry.config;
+
+    let mut lines = vec![Line::from(vec![
+        Span::raw(format!("{} ", checkbox)),
+        Span::styled(format!("{:<8}", label), Style::default().fg(color)),
+        Span::styled(
+            config.alias.clone().unwrap_or_default(),
+            Style::default().fg(TEXT).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!(
+                "  {}/{}:{} → {}",
+                config.namespace,
+                config.service.clone().unwrap_or_default(),
+                config.remote_port.unwrap_or_default(),
+                config.local_port.unwrap_or_default()
+            ),
+            Style::default().fg(SUBTEXT0),
+        ),
+    ])];
+    for change in &entry.changes {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "             {}: {} → {}",
+                change.field, change.before, change.after
+            ),
+            Style::default().fg(YELLOW),
+        )));
+    }
+    ListItem::new(lines)
+}
+
+pub fn render_discovery_diff(f: &mut Frame, discovery: &mut DiscoveryWizard, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(4)].as_ref())
+        .split(area);
+
+    let items: Vec<ListItem> = if discovery.entries.is_empty() {
+        vec![ListItem::new(Span::styled(
+            "no services found in this context",
+            Style::default().fg(SUBTEXT0),
+        ))]
+    } else {
+        discovery
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| entry_item(entry, discovery.picked.contains(&index)))
+            .collect()
+    };
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Span::styled(
+                    format!("Discovered in {}", discovery.context),
+                    Style::default().fg(MAUVE),
+                ))
+                .style(Style::default().bg(BASE).fg(TEXT)),
+        )
+        .highlight_style(Style::default().fg(YELLOW).add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+
+    let summary = vec![
+        Line::from(format!(
+            "{} new, {} changed, {} already imported. {} picked to import",
+            discovery.count(DiscoveryStatus::New),
+            discovery.count(DiscoveryStatus::Changed),
+            discovery.count(DiscoveryStatus::Unchanged),
+            discovery.picked.len()
+        )),
+        Line::from(Span::styled(
+            "space: pick | a: pick all | enter: import | esc: cancel",
+            Style::default().fg(SUBTEXT0),
+        )),
+    ];
+    let summary = Paragraph::new(summary).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().bg(BASE).fg(TEXT)),
+    );
+
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, chunks[0], &mut discovery.list_state);
+    f.render_widget(summary, chunks[1]);
+}
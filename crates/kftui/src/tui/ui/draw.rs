@@ -43,10 +43,13 @@ use crate::tui::ui::{
     render_about_popup,
     render_background_overlay,
     render_confirmation_popup,
+    render_discovery_diff,
+    render_edit_form,
     render_error_popup,
     render_help_popup,
     render_input_prompt,
     render_legend,
+    render_traffic,
     BASE,
     TEXT,
     YELLOW,
@@ -122,12 +125,21 @@ pub fn draw_ui(f: &mut Frame, app: &mut App, config_states: &[ConfigState]) {
         f.render_widget(empty_block, bottom_chunks[0]);
     }
 
-    render_logs(
-        f,
-        app,
-        bottom_chunks[1],
-        app.active_component == ActiveComponent::Logs,
-    );
+    if let Some(traffic) = &app.traffic {
+        render_traffic(
+            f,
+            traffic,
+            bottom_chunks[1],
+            app.active_component == ActiveComponent::Logs,
+        );
+    } else {
+        render_logs(
+            f,
+            app,
+            bottom_chunks[1],
+            app.active_component == ActiveComponent::Logs,
+        );
+    }
 
     render_legend(f, chunks[2], app.active_component);
 
@@ -155,7 +167,37 @@ pub fn draw_ui(f: &mut Frame, app: &mut App, config_states: &[ConfigState]) {
         AppState::ShowInputPrompt => {
             let input_area = centered_rect(40, 20, size);
             render_background_overlay(f, size);
-            render_input_prompt(f, &app.input_buffer, input_area);
+            render_input_prompt(f, "Enter file name", &app.input_buffer, input_area);
+        }
+        AppState::ShowReasonPrompt => {
+            let input_area = centered_rect(50, 20, size);
+            render_background_overlay(f, size);
+            render_input_prompt(
+                f,
+                "Reason for production access",
+                &app.input_buffer,
+                input_area,
+            );
+        }
+        AppState::ShowTagPrompt => {
+            let input_area = centered_rect(50, 20, size);
+            render_background_overlay(f, size);
+            render_input_prompt(
+                f,
+                &format!(
+                    "Tag {} config(s), -tag removes it",
+                    app.pending_tag_configs.len()
+                ),
+                &app.input_buffer,
+                input_area,
+            );
+        }
+        AppState::EditConfig => {
+            if let Some(form) = &app.edit_form {
+                let form_area = centered_rect(70, 80, size);
+                render_background_overlay(f, size);
+                render_edit_form(f, form, form_area);
+            }
         }
         AppState::ShowConfirmationPopup => {
             let confirmation_area = centered_rect(50, 30, size);
@@ -184,6 +226,13 @@ pub fn draw_ui(f: &mut Frame, app: &mut App, config_states: &[ConfigState]) {
             render_background_overlay(f, size);
             render_context_selection_popup(f, app, context_selection_area);
         }
+        AppState::ShowDiscoveryDiff => {
+            if let Some(discovery) = &mut app.discovery {
+                let discovery_area = centered_rect(70, 70, size);
+                render_background_overlay(f, size);
+                render_discovery_diff(f, discovery, discovery_area);
+            }
+        }
         _ => {}
     }
 }
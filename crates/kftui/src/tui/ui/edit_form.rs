@@ -0,0 +1,124 @@
+use kftray_commons::models::diagnostic::{
+    Diagnostic,
+    Severity,
+};
+use ratatui::{
+    layout::Rect,
+    style::{
+        Modifier,
+        Style,
+    },
+    text::{
+        Line,
+        Span,
+    },
+    widgets::{
+        Block,
+        Borders,
+        Clear,
+        Paragraph,
+    },
+    Frame,
+};
+
+use crate::tui::edit_form::EditForm;
+use crate::tui::ui::{
+    BASE,
+    GREEN,
+    MAUVE,
+    RED,
+    SUBTEXT0,
+    SURFACE1,
+    TEXT,
+    YELLOW,
+};
+
+fn diagnostic_line(diagnostic: &Diagnostic, indent: &str) -> Line<'static> {
+    let (color, prefix) = match diagnostic.severity {
+        Severity::Error => (RED, "error"),
+        Severity::Warning => (YELLOW, "warning"),
+        Severity::Info => (SUBTEXT0, "info"),
+    };
+    Line::from(Span::styled(
+        format!("{}{}: {}", indent, prefix, diagnostic.message),
+        Style::default().fg(color),
+    ))
+}
+
+pub fn render_edit_form(f: &mut Frame, form: &EditForm, area: Rect) {
+    let title = if form.is_new() {
+        "New config".to_string()
+    } else {
+        format!("Edit config {}", form.base.id.unwrap_or_default())
+    };
+    let label_width = form
+        .fields
+        .iter()
+        .map(|field| field.label.len())
+        .max()
+        .unwrap_or_default();
+
+    let mut lines = Vec::new();
+    for (index, field) in form.fields.iter().enumerate() {
+        let selected = index == form.selected;
+        let value_style = if selected {
+            Style::default()
+                .fg(TEXT)
+                .bg(SURFACE1)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(TEXT)
+        };
+        let cursor = if selected { "_" } else { "" };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{:>width$}: ", field.label, width = label_width),
+                Style::default().fg(if selected { YELLOW } else { SUBTEXT0 }),
+            ),
+            Span::styled(format!("{}{}", field.value, cursor), value_style),
+        ]));
+        for diagnostic in form.field_diagnostics(field.name) {
+            lines.push(diagnostic_line(diagnostic, &" ".repeat(label_width + 2)));
+        }
+    }
+
+    let others: Vec<&Diagnostic> = form.other_diagnostics().collect();
+    if !others.is_empty() {
+        lines.push(Line::from(""));
+        for diagnostic in others {
+            lines.push(diagnostic_line(diagnostic, ""));
+        }
+    }
+
+    lines.push(Line::from(""));
+    let errors = form
+        .diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.is_error())
+        .count();
+    let status = if let Some(status) = &form.status {
+        Span::styled(status.clone(), Style::default().fg(RED))
+    } else if form.checking_cluster {
+        Span::styled("checking the cluster…", Style::default().fg(SUBTEXT0))
+    } else if errors > 0 {
+        Span::styled(format!("{} error(s)", errors), Style::default().fg(RED))
+    } else {
+        Span::styled("no problems found", Style::default().fg(GREEN))
+    };
+    lines.push(Line::from(status));
+    lines.push(Line::from(Span::styled(
+        "tab/↑/↓: field | type to edit | enter: save | esc: cancel",
+        Style::default().fg(SUBTEXT0),
+    )));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(Span::styled(title, Style::default().fg(MAUVE)))
+        .style(Style::default().bg(BASE).fg(MAUVE));
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .style(Style::default().fg(TEXT).bg(BASE));
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
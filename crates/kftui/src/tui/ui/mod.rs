@@ -1,16 +1,22 @@
 use ratatui::prelude::Color;
 
+mod discovery;
 mod draw;
+mod edit_form;
 mod header;
 mod logo;
 mod popup;
 mod render;
 mod table;
+mod traffic;
+pub use discovery::*;
 pub use draw::*;
+pub use edit_form::*;
 pub use logo::*;
 pub use popup::*;
 pub use render::*;
 pub use table::*;
+pub use traffic::*;
 
 pub const PINK: Color = Color::Rgb(245, 194, 231);
 pub const MAUVE: Color = Color::Rgb(203, 166, 247);
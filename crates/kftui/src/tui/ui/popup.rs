@@ -96,16 +96,9 @@ fn render_popup(
     render_shadow_layers(f, popup_shadow_layers);
 }
 
-pub fn render_input_prompt(f: &mut Frame, input_buffer: &str, area: Rect) {
+pub fn render_input_prompt(f: &mut Frame, title: &str, input_buffer: &str, area: Rect) {
     let input_paragraph = Text::raw(input_buffer);
-    render_popup(
-        f,
-        area,
-        "Enter file name",
-        PINK,
-        input_paragraph,
-        Alignment::Left,
-    );
+    render_popup(f, area, title, PINK, input_paragraph, Alignment::Left);
 }
 
 pub fn render_confirmation_popup(f: &mut Frame, message: &Option<String>, area: Rect) {
@@ -142,6 +135,10 @@ pub fn render_help_popup(f: &mut Frame, area: Rect) {
             "f: Start/Stop Port Forward",
             Style::default().fg(YELLOW),
         )),
+        Line::from(Span::styled(
+            "t: Show Traffic/Logs of Running Forward",
+            Style::default().fg(YELLOW),
+        )),
         Line::from(Span::styled(
             "Space: Select/Deselect",
             Style::default().fg(YELLOW),
@@ -150,6 +147,15 @@ pub fn render_help_popup(f: &mut Frame, area: Rect) {
             "Ctrl+A: Select/Deselect All",
             Style::default().fg(YELLOW),
         )),
+        Line::from(Span::styled(
+            "g: Tag Selected (-tag removes)",
+            Style::default().fg(YELLOW),
+        )),
+        Line::from(Span::styled(
+            "Enter: Edit Config",
+            Style::default().fg(YELLOW),
+        )),
+        Line::from(Span::styled("n: New Config", Style::default().fg(YELLOW))),
         Line::from(Span::styled("h: Show Help", Style::default().fg(YELLOW))),
         Line::from(Span::styled("i: Import", Style::default().fg(YELLOW))),
         Line::from(Span::styled("e: Export", Style::default().fg(YELLOW))),
@@ -369,7 +375,7 @@ pub fn render_context_selection_popup(f: &mut Frame, app: &mut App, area: Rect)
     let explanation_text = vec![
         Line::from(""),
         Line::from(vec![
-            Span::raw("- Select a context to automatically import services with the annotation "),
+            Span::raw("- Select a context to look for services with the annotation "),
             Span::styled("kftray.app/enabled: true", Style::default().fg(YELLOW)),
             Span::raw("."),
         ]),
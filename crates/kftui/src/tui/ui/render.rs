@@ -36,11 +36,12 @@ pub fn render_legend(f: &mut Frame, area: Rect, active_component: ActiveComponen
 
     let menu_legend = "←/→: navigate | enter: open | tab: switch to configs tab";
 
-    let table_legend = "pageup/down: scroll | ↑/↓: navigate | ←/→: switch table | space: select | f: start/stop | d: delete | ctrla: select all | tab: switch to details";
+    let table_legend = "pageup/down: scroll | ↑/↓: navigate | ←/→: switch table | space: select | f: start/stop | enter: edit | n: new | d: delete | ctrla: select all | g: tag | t: traffic | tab: switch to details";
 
     let details_legend = "pageup/pagedown: scroll | ←/→: switch tabs | tab: switch to menu";
 
-    let logs_legend = "pageup/pagedown: scroll | ←/→: switch focus | c: clear output";
+    let logs_legend =
+        "pageup/pagedown: scroll | ←/→: switch focus | c: clear output | t: logs/traffic";
 
     let legend_message = match active_component {
         ActiveComponent::Menu => format!("{} | {}", common_legend, menu_legend),
@@ -157,6 +158,14 @@ pub fn draw_file_explorer_popup(f: &mut Frame, app: &mut App, area: Rect, is_imp
     f.render_widget(help_widget, chunks[1]);
 }
 
+fn table_title(title: &str, marked: usize) -> String {
+    if marked == 0 {
+        title.to_string()
+    } else {
+        format!("{} ({} marked)", title, marked)
+    }
+}
+
 pub fn draw_configs_tab(
     f: &mut Frame, app: &mut App, config_states: &[ConfigState], area: Rect, has_focus: bool,
 ) {
@@ -176,7 +185,7 @@ pub fn draw_configs_tab(
         &app.stopped_configs,
         config_states,
         &mut app.table_state_stopped,
-        "Stopped Configs",
+        &table_title("Stopped Configs", app.selected_rows_stopped.len()),
         has_focus && app.active_component == ActiveComponent::StoppedTable,
         &app.selected_rows_stopped,
     );
@@ -187,7 +196,7 @@ pub fn draw_configs_tab(
         &app.running_configs,
         config_states,
         &mut app.table_state_running,
-        "Running Configs",
+        &table_title("Running Configs", app.selected_rows_running.len()),
         has_focus && app.active_component == ActiveComponent::RunningTable,
         &app.selected_rows_running,
     );
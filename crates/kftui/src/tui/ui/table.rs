@@ -1,7 +1,12 @@
 use std::collections::HashSet;
 
+use kftray_commons::config_state::bound_local_port;
 use kftray_commons::models::config_model::Config;
 use kftray_commons::models::config_state_model::ConfigState;
+use kftray_commons::utils::config_display::{
+    config_label,
+    parse_config_color,
+};
 use ratatui::prelude::Alignment;
 use ratatui::widgets::BorderType;
 use ratatui::widgets::TableState;
@@ -11,6 +16,7 @@ use ratatui::{
         Rect,
     },
     style::{
+        Color,
         Modifier,
         Style,
     },
@@ -47,6 +53,17 @@ use crate::tui::ui::{
     YELLOW,
 };
 
+// the name in the config's own color, when it has one
+fn name_span(config: &Config) -> Span<'static> {
+    match config.color.as_deref().and_then(parse_config_color) {
+        Some((r, g, b)) => Span::styled(
+            config_label(config),
+            Style::default().fg(Color::Rgb(r, g, b)),
+        ),
+        None => Span::raw(config_label(config)),
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn draw_configs_table(
     frame: &mut Frame, area: Rect, configs: &[Config], config_states: &[ConfigState],
@@ -75,11 +92,10 @@ pub fn draw_configs_table(
             };
 
             Row::new(vec![
-                Cell::from(config.alias.clone().unwrap_or_default()),
+                Cell::from(name_span(config)),
                 Cell::from(config.workload_type.clone().unwrap_or_default()),
                 Cell::from(
-                    config
-                        .local_port
+                    bound_local_port(config, config_states)
                         .map_or_else(|| "".to_string(), |port| port.to_string()),
                 ),
                 Cell::from(config.context.clone()),
@@ -106,7 +122,7 @@ pub fn draw_configs_table(
     )
     .header(
         Row::new(vec![
-            Cell::from("Alias"),
+            Cell::from("Name"),
             Cell::from("Workload"),
             Cell::from("Local Port"),
             Cell::from("Context"),
@@ -171,6 +187,10 @@ pub fn render_details(
             Span::styled("Context: ", Style::default().add_modifier(Modifier::BOLD)),
             Span::raw(&config.context),
         ]),
+        Line::from(vec![
+            Span::styled("Name: ", Style::default().add_modifier(Modifier::BOLD)),
+            name_span(config),
+        ]),
         Line::from(vec![
             Span::styled("Alias: ", Style::default().add_modifier(Modifier::BOLD)),
             Span::raw(config.alias.clone().unwrap_or_default()),
@@ -196,8 +216,7 @@ pub fn render_details(
                 Style::default().add_modifier(Modifier::BOLD),
             ),
             Span::raw(
-                config
-                    .local_port
+                bound_local_port(config, config_states)
                     .map_or_else(|| "".to_string(), |port| port.to_string()),
             ),
         ]),
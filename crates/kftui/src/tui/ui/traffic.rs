@@ -0,0 +1,121 @@
+use ratatui::{
+    layout::{
+        Constraint,
+        Direction,
+        Layout,
+        Rect,
+    },
+    style::{
+        Modifier,
+        Style,
+    },
+    text::{
+        Line,
+        Span,
+    },
+    widgets::{
+        Block,
+        Borders,
+        List,
+        ListItem,
+        Paragraph,
+        Sparkline,
+    },
+    Frame,
+};
+
+use crate::tui::traffic::{
+    format_bytes,
+    TrafficView,
+};
+use crate::tui::ui::{
+    BASE,
+    GREEN,
+    MAUVE,
+    SUBTEXT0,
+    TEAL,
+    TEXT,
+    YELLOW,
+};
+
+pub fn render_traffic(f: &mut Frame, traffic: &TrafficView, area: Rect, has_focus: bool) {
+    let focus_color = if has_focus { YELLOW } else { TEXT };
+    let border_modifier = if has_focus {
+        Modifier::BOLD
+    } else {
+        Modifier::empty()
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(Span::styled("Traffic", Style::default().fg(MAUVE)))
+        .border_style(
+            Style::default()
+                .fg(focus_color)
+                .add_modifier(border_modifier),
+        )
+        .style(Style::default().fg(TEXT).bg(BASE));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(1),
+                Constraint::Length(4),
+                Constraint::Min(0),
+            ]
+            .as_ref(),
+        )
+        .split(inner);
+
+    let snapshot = &traffic.snapshot;
+    let current = traffic.throughput.back().copied().unwrap_or_default();
+    let summary = Line::from(vec![
+        Span::styled("conns ", Style::default().fg(SUBTEXT0)),
+        Span::styled(
+            format!(
+                "{} open / {} total",
+                snapshot.active_connections, snapshot.total_connections
+            ),
+            Style::default().fg(GREEN),
+        ),
+        Span::styled("  in ", Style::default().fg(SUBTEXT0)),
+        Span::raw(format_bytes(snapshot.bytes_in)),
+        Span::styled("  out ", Style::default().fg(SUBTEXT0)),
+        Span::raw(format_bytes(snapshot.bytes_out)),
+        Span::styled("  now ", Style::default().fg(SUBTEXT0)),
+        Span::styled(
+            format!("{}/s", format_bytes(current)),
+            Style::default().fg(TEAL),
+        ),
+    ]);
+    f.render_widget(Paragraph::new(summary), chunks[0]);
+
+    // newest bars on the right, so only what fits is shown
+    let width = chunks[1].width as usize;
+    let history: Vec<u64> = traffic.throughput.iter().copied().collect();
+    let visible = &history[history.len().saturating_sub(width)..];
+    let sparkline = Sparkline::default()
+        .data(visible)
+        .style(Style::default().fg(TEAL));
+    f.render_widget(sparkline, chunks[1]);
+
+    let height = chunks[2].height as usize;
+    let entries: Vec<ListItem> = traffic
+        .entries
+        .iter()
+        .skip(traffic.entries.len().saturating_sub(height))
+        .map(|entry| ListItem::new(entry.as_str()))
+        .collect();
+    let entries = if entries.is_empty() {
+        vec![ListItem::new(Span::styled(
+            "waiting for connections",
+            Style::default().fg(SUBTEXT0),
+        ))]
+    } else {
+        entries
+    };
+    f.render_widget(List::new(entries), chunks[2]);
+}